@@ -0,0 +1,42 @@
+/* C bindings for embedding the MLTT type checker.
+ *
+ * Strings are null-terminated UTF-8. Strings and modules returned by these
+ * functions are owned by the caller, and must be released with
+ * `mltt_string_free` and `mltt_module_free` respectively.
+ */
+
+#ifndef MLTT_H
+#define MLTT_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A module that has been checked. */
+typedef struct mltt_module mltt_module;
+
+/* Check the source code of a module. Returns null on failure, writing a
+ * description of the errors to `error` if it is not null. */
+mltt_module *mltt_check(const char *source, char **error);
+
+/* Lookup the type of an item in a module. Returns null if the item is not
+ * found. */
+char *mltt_item_type(const mltt_module *module, const char *name);
+
+/* Normalize an expression in the scope of a module's items. Returns the
+ * normal form, writing its type to `ty` if it is not null. Returns null on
+ * failure, writing a description of the errors to `error` if it is not
+ * null. */
+char *mltt_normalize(const mltt_module *module, const char *expr, char **ty, char **error);
+
+/* Free a module returned by `mltt_check`. */
+void mltt_module_free(mltt_module *module);
+
+/* Free a string returned by one of the other functions. */
+void mltt_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MLTT_H */
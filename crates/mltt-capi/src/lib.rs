@@ -0,0 +1,267 @@
+//! C bindings for embedding the MLTT type checker.
+//!
+//! This exposes the [`mltt`] crate over the C ABI, so that the type checker
+//! can be used from C, or from any language that can call C functions, such
+//! as Python or Swift. The corresponding declarations can be found in
+//! `include/mltt.h`.
+//!
+//! Strings are passed as null-terminated UTF-8. Any strings or modules that
+//! are returned by these functions are owned by the caller, and must be
+//! released with `mltt_string_free` or `mltt_module_free` respectively.
+//!
+//! [`mltt`]: ../mltt/index.html
+
+#![warn(rust_2018_idioms)]
+
+use mltt::{CheckedModule, Diagnostics};
+use mltt_core::Label;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// A module that has been checked, opaque to C.
+pub struct Module(CheckedModule);
+
+/// Check the source code of a module.
+///
+/// Returns a new module on success. On failure, returns null, and if `error`
+/// is not null, writes a description of the errors to it.
+///
+/// # Safety
+///
+/// `source` must be a valid, null-terminated string, and `error` must either
+/// be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn mltt_check(source: *const c_char, error: *mut *mut c_char) -> *mut Module {
+    set_error(error, None);
+
+    let result = read_str(source).and_then(|source| {
+        catch_panic(|| mltt::check_str(source).map_err(|diagnostics| render(&diagnostics)))
+    });
+
+    match result {
+        Ok(module) => Box::into_raw(Box::new(Module(module))),
+        Err(message) => {
+            set_error(error, Some(message));
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Lookup the type of an item in a module.
+///
+/// Returns null if the item is not found, or if its type could not be
+/// displayed.
+///
+/// # Safety
+///
+/// `module` must have been returned by `mltt_check`, and not yet freed.
+/// `name` must be a valid, null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mltt_item_type(module: *const Module, name: *const c_char) -> *mut c_char {
+    let module = match module.as_ref() {
+        Some(Module(module)) => module,
+        None => return ptr::null_mut(),
+    };
+    let name = match read_str(name) {
+        Ok(name) => Label(name.to_owned()),
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let ty = catch_panic(|| {
+        let ty = module.item_type(&name);
+        Ok(ty.map(|ty| module.display_term(&ty)))
+    });
+
+    match ty {
+        Ok(Some(ty)) => into_c_string(ty),
+        Ok(None) | Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Normalize an expression in the scope of a module's items.
+///
+/// Returns the normal form of the expression on success, and writes its type
+/// to `ty` if it is not null. On failure, returns null, and if `error` is not
+/// null, writes a description of the errors to it.
+///
+/// # Safety
+///
+/// `module` must have been returned by `mltt_check`, and not yet freed.
+/// `expr` must be a valid, null-terminated string, and `ty` and `error` must
+/// either be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn mltt_normalize(
+    module: *const Module,
+    expr: *const c_char,
+    ty: *mut *mut c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    set_error(ty, None);
+    set_error(error, None);
+
+    let module = match module.as_ref() {
+        Some(Module(module)) => module,
+        None => {
+            set_error(error, Some("module is null".to_owned()));
+            return ptr::null_mut();
+        },
+    };
+
+    let result = read_str(expr).and_then(|expr| {
+        catch_panic(|| match mltt::normalize(module, expr) {
+            Ok((term, term_ty)) => Ok((module.display_term(&term), module.display_term(&term_ty))),
+            Err(diagnostics) => Err(render(&diagnostics)),
+        })
+    });
+
+    match result {
+        Ok((term, term_ty)) => {
+            set_error(ty, Some(term_ty));
+            into_c_string(term)
+        },
+        Err(message) => {
+            set_error(error, Some(message));
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Free a module that was returned by `mltt_check`. Does nothing if `module`
+/// is null.
+///
+/// # Safety
+///
+/// `module` must be null, or have been returned by `mltt_check` and not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn mltt_module_free(module: *mut Module) {
+    if !module.is_null() {
+        drop(Box::from_raw(module));
+    }
+}
+
+/// Free a string that was returned by one of the other functions. Does
+/// nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null, or have been returned from this library and not
+/// yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn mltt_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Read a string that was passed in from C.
+unsafe fn read_str<'a>(string: *const c_char) -> Result<&'a str, String> {
+    if string.is_null() {
+        return Err("string is null".to_owned());
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| "string is not valid UTF-8".to_owned())
+}
+
+/// Write a string to an output parameter, if it is not null.
+unsafe fn set_error(out: *mut *mut c_char, string: Option<String>) {
+    if let Some(out) = out.as_mut() {
+        *out = string.map_or(ptr::null_mut(), into_c_string);
+    }
+}
+
+/// Convert a string into one that is owned by C. Interior null bytes are
+/// escaped, so that the string is not truncated.
+fn into_c_string(string: String) -> *mut c_char {
+    let string = CString::new(string.replace('\0', "\\0")).unwrap();
+    string.into_raw()
+}
+
+/// Render diagnostics as plain text, one per line.
+fn render(diagnostics: &Diagnostics) -> String {
+    (diagnostics.iter())
+        .map(|diagnostic| format!("{}: {}", diagnostic.severity, diagnostic.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Catch any panics, so that they don't unwind across the C boundary.
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => Err("error: internal compiler error: the type checker panicked".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Take ownership of a string that was returned to C.
+    unsafe fn take_string(string: *mut c_char) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let result = CStr::from_ptr(string).to_str().unwrap().to_owned();
+        mltt_string_free(string);
+        Some(result)
+    }
+
+    fn c_string(string: &str) -> CString {
+        CString::new(string).unwrap()
+    }
+
+    #[test]
+    fn check_and_query() {
+        let source = c_string("id : Fun (A : Type) -> A -> A; id A a = a;");
+        let mut error = ptr::null_mut();
+
+        unsafe {
+            let module = mltt_check(source.as_ptr(), &mut error);
+            assert!(!module.is_null());
+            assert_eq!(take_string(error), None);
+
+            let ty = mltt_item_type(module, c_string("id").as_ptr());
+            assert_eq!(take_string(ty).unwrap(), "Fun (A : Type) -> A -> A");
+            assert!(mltt_item_type(module, c_string("missing").as_ptr()).is_null());
+
+            let mut ty = ptr::null_mut();
+            let expr = c_string("id String \"hello\"");
+            let term = mltt_normalize(module, expr.as_ptr(), &mut ty, &mut error);
+            assert_eq!(take_string(term).unwrap(), "\"hello\"");
+            assert_eq!(take_string(ty).unwrap(), "String");
+            assert_eq!(take_string(error), None);
+
+            mltt_module_free(module);
+        }
+    }
+
+    #[test]
+    fn check_errors() {
+        let source = c_string("id : Type; id = Type;");
+        let mut error = ptr::null_mut();
+
+        unsafe {
+            assert!(mltt_check(source.as_ptr(), &mut error).is_null());
+            assert!(take_string(error).unwrap().starts_with("error: "));
+        }
+    }
+
+    #[test]
+    fn normalize_errors() {
+        let mut error = ptr::null_mut();
+
+        unsafe {
+            let module = mltt_check(c_string("").as_ptr(), ptr::null_mut());
+            let expr = c_string("missing");
+            let term = mltt_normalize(module, expr.as_ptr(), ptr::null_mut(), &mut error);
+            assert!(term.is_null());
+            assert!(take_string(error).is_some());
+
+            mltt_module_free(module);
+        }
+    }
+}
@@ -0,0 +1,489 @@
+use language_reporting::termcolor::StandardStream;
+use language_reporting::Diagnostic;
+use mltt_core::metrics::Metrics;
+use mltt_core::nbe::Strategy;
+use mltt_core::signature::ModuleSignature;
+use mltt_core::{agda, domain, meta, pretty, prim, syntax, validate, var};
+use mltt_core::{DocString, Label, Rc, UniverseLevel};
+use mltt_elaborate::{Context, LanguageOptions};
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_span::{File, FileSpan, Files};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::color::ColorArg;
+
+/// Type check some files.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// When to use colored output.
+    #[structopt(
+        long = "color",
+        default_value = "auto",
+        raw(possible_values = "ColorArg::VARIANTS")
+    )]
+    pub color: ColorArg,
+    /// Allow `Type : Type`, at the expense of logical consistency.
+    #[structopt(long = "type-in-type")]
+    pub type_in_type: bool,
+    /// The greatest universe level that can be represented.
+    #[structopt(long = "max-universe", name = "LEVEL")]
+    pub max_universe: Option<u16>,
+    /// Don't bring the builtin types and values into scope.
+    #[structopt(long = "no-prelude")]
+    pub no_prelude: bool,
+    /// Evaluate definitions and arguments lazily, the first time that they
+    /// are needed.
+    #[structopt(long = "call-by-need")]
+    pub call_by_need: bool,
+    /// Report numeric literals with unknown types as ambiguous, rather than
+    /// defaulting them to `S32` or `F64`.
+    #[structopt(long = "no-literal-defaulting")]
+    pub no_literal_defaulting: bool,
+    /// Explain the steps that were taken when two types fail to unify.
+    #[structopt(long = "explain-conversion")]
+    pub explain_conversion: bool,
+    /// The greatest number of steps that a conversion check can take before
+    /// giving up.
+    #[structopt(long = "conversion-budget", name = "STEPS")]
+    pub conversion_budget: Option<u32>,
+    /// Refuse to run primitives that have effects when normalizing
+    /// definitions, leaving them unevaluated.
+    #[structopt(long = "pure")]
+    pub pure: bool,
+    /// Report the sizes of each definition after elaboration and after
+    /// normalization.
+    #[structopt(long = "stats")]
+    pub stats: bool,
+    /// Report the primitive applications that were constant folded when
+    /// normalizing each definition, and the primitives that were left stuck.
+    #[structopt(long = "fold-report")]
+    pub fold_report: bool,
+    /// Print the elaborated core syntax of each file, noting the universe
+    /// level that the type of each item lives in.
+    #[structopt(long = "dump-elaborated")]
+    pub dump_elaborated: bool,
+    /// Export the checked files to Agda, writing them to the given directory.
+    #[structopt(long = "export-agda", name = "DIR", parse(from_os_str))]
+    pub export_agda: Option<PathBuf>,
+    /// The files to type check.
+    #[structopt(name = "FILE", parse(from_os_str))]
+    pub files: Vec<PathBuf>,
+}
+
+/// Run the type checker with the given options.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let writer = StandardStream::stderr(options.color.color_choice(atty::Stream::Stderr));
+    let mut files = Files::new();
+    let mut language_options = LanguageOptions {
+        type_in_type: options.type_in_type,
+        max_universe: (options.max_universe).map_or(UniverseLevel::MAX, UniverseLevel),
+        no_prelude: options.no_prelude,
+        explain_conversion: options.explain_conversion,
+        conversion_budget: options.conversion_budget,
+        ..LanguageOptions::default()
+    };
+    if options.call_by_need {
+        language_options.strategy = Strategy::CallByNeed;
+    }
+    if options.no_literal_defaulting {
+        language_options.default_int_ty = None;
+        language_options.default_float_ty = None;
+    }
+    let mut error_count = 0;
+
+    for path in &options.files {
+        let src = fs::read_to_string(path)?;
+        let file_id = files.add(path.display().to_string(), src);
+        let mut metas = meta::Env::new();
+        let mut warnings = Vec::new();
+        let result = check_file(language_options, &mut metas, &files[file_id], &mut warnings)
+            .and_then(|(mut context, module, signature)| {
+                context.set_run_capability(match options.pure {
+                    true => prim::Capability::Pure,
+                    false => prim::Capability::Unsafe,
+                });
+                if let Some(dir) = &options.export_agda {
+                    export_agda(dir, path, &context, &metas, &module)?;
+                }
+                if options.dump_elaborated {
+                    dump_elaborated(path, &context, &metas, &module, &signature)?;
+                }
+                if options.fold_report {
+                    let reports = fold_reports(&context, &metas, &module, &signature)?;
+                    print_fold_reports(path, &reports);
+                }
+                match options.stats {
+                    true => item_stats(&context, &metas, &module, &signature).map(Some),
+                    false => Ok(None),
+                }
+            });
+        let config = language_reporting::DefaultConfig;
+
+        for warning in &warnings {
+            language_reporting::emit(&mut writer.lock(), &files, warning, &config)?;
+        }
+        match result {
+            Ok(Some(file_stats)) => print_stats(files[file_id].name(), &file_stats),
+            Ok(None) => {},
+            Err(diagnostic) => {
+                language_reporting::emit(&mut writer.lock(), &files, &diagnostic, &config)?;
+                error_count += 1;
+            },
+        }
+    }
+
+    match error_count {
+        0 => Ok(()),
+        1 => Err("aborting due to a previous error".into()),
+        _ => Err(format!("aborting due to {} previous errors", error_count).into()),
+    }
+}
+
+/// Parse and elaborate the given file, collecting any warnings about unused or
+/// shadowed bindings, and about defaulted literals.
+///
+/// The language options can be overridden by the file itself. Returns the
+/// context that the file was elaborated in, along with the elaborated module
+/// and its signature.
+pub(crate) fn check_file(
+    language_options: LanguageOptions,
+    metas: &mut meta::Env,
+    file: &File,
+    warnings: &mut Vec<Diagnostic<FileSpan>>,
+) -> Result<(Context, syntax::Module, ModuleSignature), Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
+    let concrete_module = parser::parse_module(lexer)?;
+    let language_options = mltt_elaborate::module_options(language_options, &concrete_module)?;
+    let context = Context::new(language_options);
+    let resolution = mltt_elaborate::resolve_items(&context, &concrete_module.items);
+    warnings.extend(resolution.warnings);
+    let start_meta = metas.next_index();
+    let result = mltt_elaborate::check_module(&context, metas, &concrete_module.items);
+    warnings.extend(mltt_elaborate::defaulting_warnings(metas, start_meta));
+    let (module, signature) = result?;
+
+    Ok((context, module, signature))
+}
+
+/// The sizes of a definition after elaboration and after normalization.
+struct ItemStats {
+    label: Label,
+    elaborated: Metrics,
+    normalized: Metrics,
+}
+
+/// Compute the sizes of the definitions in an elaborated module.
+///
+/// The normalized definitions are read back from the values in the signature
+/// of the module, so earlier definitions will have been unfolded into them.
+fn item_stats(
+    context: &Context,
+    metas: &meta::Env,
+    module: &syntax::Module,
+    signature: &ModuleSignature,
+) -> Result<Vec<ItemStats>, Diagnostic<FileSpan>> {
+    let context = &context.run_context();
+    let mut stats = Vec::new();
+
+    for item in &module.items {
+        if let syntax::Item::Definition(_, label, term) = item {
+            let value = (signature.lookup_item(label)).and_then(|item| item.value.as_ref());
+            if let Some(value) = value {
+                let normalized = context.read_back_value(metas, None, value)?;
+                stats.push(ItemStats {
+                    label: label.clone(),
+                    elaborated: term.metrics(),
+                    normalized: normalized.metrics(),
+                });
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Print the items of an elaborated module, each followed by a comment giving
+/// the universe level of its type, as inferred by the elaborator.
+fn dump_elaborated(
+    path: &Path,
+    context: &Context,
+    metas: &meta::Env,
+    module: &syntax::Module,
+    signature: &ModuleSignature,
+) -> Result<(), Diagnostic<FileSpan>> {
+    let mut context = context.clone();
+    let mut env = context.pretty_env();
+
+    println!("-- {}", path.display());
+    for item in &module.items {
+        let (doc, ty) = match item {
+            syntax::Item::Declaration(_, label, term_ty) => {
+                let doc = pretty::declaration(pretty::binder(label), term_ty.to_display_doc(&env));
+                (doc, Some(context.eval_term(metas, None, term_ty)?))
+            },
+            syntax::Item::Definition(_, label, term) => {
+                let doc = pretty::definition(pretty::binder(label), term.to_display_doc(&env));
+                env.fresh_name(Some(&label.0));
+                (
+                    doc,
+                    signature.lookup_item(label).map(|item| item.ty.clone()),
+                )
+            },
+        };
+
+        let doc = match &ty {
+            Some(ty) => {
+                let level = universe_level(&context, metas, ty)?;
+                let universe = syntax::Term::universe(level).to_display_doc(&env);
+                doc.append(" -- in ").append(universe)
+            },
+            None => doc,
+        };
+        println!("{}", doc.pretty(100));
+
+        if let (syntax::Item::Definition(_, label, _), Some(ty)) = (item, ty) {
+            let value = (signature.lookup_item(label)).and_then(|item| item.value.clone());
+            if let Some(value) = value {
+                context.add_defn(label.to_string(), value, ty);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The universe level of a type.
+fn universe_level(
+    context: &Context,
+    metas: &meta::Env,
+    ty: &Rc<domain::Type>,
+) -> Result<UniverseLevel, Diagnostic<FileSpan>> {
+    let ty = context.read_back_value(metas, None, ty)?;
+    let mut validation_context = context.validation_context();
+    validate::synth_universe(&mut validation_context, metas, &ty)
+        .map_err(|error| error.to_diagnostic(None))
+}
+
+/// The primitives that were constant folded when normalizing a definition,
+/// and the primitives that were left stuck in its normal form, along with the
+/// number of times that each one occurred.
+struct FoldReport {
+    label: Label,
+    folded: BTreeMap<prim::Name, usize>,
+    stuck: BTreeMap<prim::Name, usize>,
+}
+
+/// Normalize the definitions in an elaborated module again, recording the
+/// primitives that are interpreted along the way.
+///
+/// Definitions are normalized in the context of the earlier definitions, so
+/// primitives are only reported for the definition whose normalization
+/// caused them to be folded.
+fn fold_reports(
+    context: &Context,
+    metas: &meta::Env,
+    module: &syntax::Module,
+    signature: &ModuleSignature,
+) -> Result<Vec<FoldReport>, Diagnostic<FileSpan>> {
+    let mut context = context.run_context();
+    let mut reports = Vec::new();
+
+    for item in &module.items {
+        if let syntax::Item::Definition(_, label, term) = item {
+            let fold_log = Rc::new(prim::FoldLog::new());
+            context.set_fold_log(Some(fold_log.clone()));
+            let value = context.eval_term(metas, None, term)?;
+            let normalized = context.read_back_value(metas, None, &value)?;
+
+            let mut stuck = BTreeMap::new();
+            count_prims(&normalized, &mut stuck);
+            reports.push(FoldReport {
+                label: label.clone(),
+                folded: fold_log.folds(),
+                stuck,
+            });
+
+            if let Some(item) = signature.lookup_item(label) {
+                context.add_defn(label.to_string(), value, item.ty.clone());
+            }
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Count the primitives in a term. In a normal form, these are the
+/// primitives that could not be interpreted.
+fn count_prims(term: &syntax::Term, counts: &mut BTreeMap<prim::Name, usize>) {
+    use mltt_core::syntax::Term;
+
+    match term {
+        Term::Prim(prim_name) => *counts.entry(prim_name.clone()).or_insert(0) += 1,
+        Term::Var(_) | Term::Meta(_) => {},
+
+        Term::Ann(term, term_ty) => {
+            count_prims(term, counts);
+            count_prims(term_ty, counts);
+        },
+        Term::Let(items, body) => {
+            for item in items {
+                match item {
+                    syntax::Item::Declaration(_, _, term)
+                    | syntax::Item::Definition(_, _, term) => count_prims(term, counts),
+                }
+            }
+            count_prims(body, counts);
+        },
+
+        Term::LiteralType(_) | Term::LiteralIntro(_) => {},
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            count_prims(scrutinee, counts);
+            for (_, body) in clauses.iter() {
+                count_prims(body, counts);
+            }
+            count_prims(default_body, counts);
+        },
+
+        Term::FunType(_, _, _, param_ty, body_ty) => {
+            count_prims(param_ty, counts);
+            count_prims(body_ty, counts);
+        },
+        Term::FunIntro(_, _, body) => count_prims(body, counts),
+        Term::FunElim(fun, _, arg) => {
+            count_prims(fun, counts);
+            count_prims(arg, counts);
+        },
+
+        Term::RecordType(ty_fields) => {
+            for (_, _, _, ty) in ty_fields {
+                count_prims(ty, counts);
+            }
+        },
+        Term::RecordIntro(intro_fields) => {
+            for (_, term) in intro_fields {
+                count_prims(term, counts);
+            }
+        },
+        Term::RecordElim(record, _) => count_prims(record, counts),
+
+        Term::ArrayType(elem_ty) => count_prims(elem_ty, counts),
+        Term::ArrayIntro(elems) => {
+            for elem in elems {
+                count_prims(elem, counts);
+            }
+        },
+
+        Term::Universe(_) => {},
+    }
+}
+
+fn print_fold_reports(path: &Path, reports: &[FoldReport]) {
+    fn format_counts(counts: &BTreeMap<prim::Name, usize>) -> String {
+        match counts.is_empty() {
+            true => "none".to_owned(),
+            false => (counts.iter())
+                .map(|(prim_name, count)| format!("{} x{}", prim_name, count))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    println!("{}:", path.display());
+    for report in reports {
+        println!(
+            "  {}: folded {}; stuck {}",
+            report.label,
+            format_counts(&report.folded),
+            format_counts(&report.stuck),
+        );
+    }
+}
+
+/// Export an elaborated module to an Agda file in the given directory, named
+/// after the file that it was checked from.
+///
+/// The definitions in the context are exported before the module, so that
+/// the module can refer to them.
+fn export_agda(
+    dir: &Path,
+    path: &Path,
+    context: &Context,
+    metas: &meta::Env,
+    module: &syntax::Module,
+) -> Result<(), Diagnostic<FileSpan>> {
+    let mut items = Vec::new();
+    for var_level in (0..context.values().size().0).map(var::Level) {
+        let var_index = context.values().size().index(var_level);
+        let label = Label(
+            context
+                .lookup_level_name(var_level)
+                .unwrap_or("_")
+                .to_owned(),
+        );
+        let value = context.values().lookup_entry(var_index);
+        let ty = context.lookup_level(var_level).map(|(_, ty)| ty);
+        if let (Some(value), Some(ty)) = (value, ty) {
+            let doc = DocString::from("");
+            let ty = context.read_back_value(metas, None, ty)?;
+            let term = context.read_back_value(metas, None, value)?;
+            items.push(syntax::Item::Declaration(doc.clone(), label.clone(), ty));
+            items.push(syntax::Item::Definition(doc, label, term));
+        }
+    }
+    items.extend(module.items.iter().cloned());
+
+    let module_name = path
+        .file_stem()
+        .map_or("Main".into(), |stem| stem.to_string_lossy());
+    let doc = agda::export_module(&module_name, &syntax::Module { items });
+    let export_path = dir.join(format!("{}.agda", module_name));
+
+    fs::write(&export_path, doc.pretty(100).to_string()).map_err(|error| {
+        Diagnostic::new_error(format!(
+            "could not write `{}`: {}",
+            export_path.display(),
+            error,
+        ))
+    })
+}
+
+fn print_stats(file_name: &str, stats: &[ItemStats]) {
+    println!("{}:", file_name);
+    for item_stats in stats {
+        println!(
+            "  {}: elaborated {}; normalized {}",
+            item_stats.label, item_stats.elaborated, item_stats.normalized,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pure_fold_reports() {
+        let mut files = Files::new();
+        let src = "greeting : String;\ngreeting = (primitive \"debug-print\") \"hello\";\n";
+        let file_id = files.add("test", src);
+        let mut metas = meta::Env::new();
+        let mut warnings = Vec::new();
+        let language_options = LanguageOptions::default();
+        let (mut context, module, signature) =
+            check_file(language_options, &mut metas, &files[file_id], &mut warnings).unwrap();
+        let debug_print = prim::Name::from("debug-print");
+
+        let reports = fold_reports(&context, &metas, &module, &signature).unwrap();
+        assert_eq!(reports[0].folded.get(&debug_print), Some(&1));
+        assert_eq!(reports[0].stuck.get(&debug_print), None);
+
+        context.set_run_capability(prim::Capability::Pure);
+        let reports = fold_reports(&context, &metas, &module, &signature).unwrap();
+        assert_eq!(reports[0].folded.get(&debug_print), None);
+        assert_eq!(reports[0].stuck.get(&debug_print), Some(&1));
+    }
+}
@@ -0,0 +1,174 @@
+//! Colored terminal output.
+
+use language_reporting::termcolor::{Buffer, Color, ColorChoice, ColorSpec, WriteColor};
+use mltt_core::pretty::Style;
+use pretty::{BoxDoc, Doc, Render, RenderAnnotated};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// When to use colored output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorArg {
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    Never,
+    /// Use colors if we are writing to a terminal.
+    Auto,
+}
+
+impl ColorArg {
+    /// The possible values that can be passed on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["always", "never", "auto"];
+
+    /// Get the color choice to use when writing to the given stream.
+    pub fn color_choice(self, stream: atty::Stream) -> ColorChoice {
+        match self {
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+            ColorArg::Auto if atty::is(stream) => ColorChoice::Auto,
+            ColorArg::Auto => ColorChoice::Never,
+        }
+    }
+}
+
+impl fmt::Display for ColorArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorArg::Always => write!(f, "always"),
+            ColorArg::Never => write!(f, "never"),
+            ColorArg::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl FromStr for ColorArg {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<ColorArg, String> {
+        match src {
+            "always" => Ok(ColorArg::Always),
+            "never" => Ok(ColorArg::Never),
+            "auto" => Ok(ColorArg::Auto),
+            _ => Err(format!("unexpected color option `{}`", src)),
+        }
+    }
+}
+
+/// The color specification to use when rendering a style.
+fn style_spec(style: Style) -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    match style {
+        Style::Keyword => spec.set_fg(Some(Color::Magenta)).set_bold(true),
+        Style::Binder => spec.set_fg(Some(Color::Blue)),
+        Style::Literal => spec.set_fg(Some(Color::Green)),
+        Style::Universe => spec.set_fg(Some(Color::Yellow)),
+        Style::Redex => spec.set_fg(Some(Color::Red)).set_bold(true),
+    };
+    spec
+}
+
+/// Render a document to the writer, highlighting the styled parts of the
+/// document if the writer supports color.
+pub fn render<'doc>(
+    doc: &'doc Doc<'doc, BoxDoc<'doc, Style>, Style>,
+    width: usize,
+    writer: impl WriteColor,
+) -> io::Result<()> {
+    doc.render_raw(width, &mut StyledWriter::new(writer))
+}
+
+/// Render a document to a string, without any colors.
+pub fn render_plain<'doc>(
+    doc: &'doc Doc<'doc, BoxDoc<'doc, Style>, Style>,
+    width: usize,
+) -> String {
+    let mut buffer = Buffer::no_color();
+    match render(doc, width, &mut buffer) {
+        Ok(()) => String::from_utf8_lossy(buffer.as_slice()).into_owned(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Adapts a colored writer to render style annotations.
+struct StyledWriter<W> {
+    /// The styles that are currently in effect.
+    specs: Vec<ColorSpec>,
+    writer: W,
+}
+
+impl<W> StyledWriter<W> {
+    fn new(writer: W) -> StyledWriter<W> {
+        StyledWriter {
+            specs: Vec::new(),
+            writer,
+        }
+    }
+}
+
+impl<W: WriteColor> Render for StyledWriter<W> {
+    type Error = io::Error;
+
+    fn write_str(&mut self, s: &str) -> io::Result<usize> {
+        self.writer.write(s.as_bytes())
+    }
+
+    fn write_str_all(&mut self, s: &str) -> io::Result<()> {
+        self.writer.write_all(s.as_bytes())
+    }
+}
+
+impl<W: WriteColor> RenderAnnotated<Style> for StyledWriter<W> {
+    fn push_annotation(&mut self, style: &Style) -> io::Result<()> {
+        let spec = style_spec(*style);
+        self.writer.set_color(&spec)?;
+        self.specs.push(spec);
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> io::Result<()> {
+        self.specs.pop();
+        match self.specs.last() {
+            Some(spec) => self.writer.set_color(spec),
+            None => self.writer.reset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language_reporting::termcolor::Buffer;
+    use mltt_core::pretty;
+
+    fn render_to_string(doc: &Doc<'_, BoxDoc<'_, Style>, Style>, mut buffer: Buffer) -> String {
+        render(doc, 80, &mut buffer).unwrap();
+        String::from_utf8(buffer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn render_no_color() {
+        let doc = pretty::ann(pretty::binder("x"), pretty::universe0());
+
+        assert_eq!(render_to_string(&doc, Buffer::no_color()), "x : Type");
+    }
+
+    #[test]
+    fn render_ansi() {
+        let doc = pretty::ann(pretty::binder("x"), pretty::universe0());
+
+        assert_eq!(
+            render_to_string(&doc, Buffer::ansi()),
+            "\u{1b}[0m\u{1b}[34mx\u{1b}[0m : \u{1b}[0m\u{1b}[33mType\u{1b}[0m",
+        );
+    }
+
+    #[test]
+    fn color_arg_from_str() {
+        assert_eq!("always".parse(), Ok(ColorArg::Always));
+        assert_eq!("never".parse(), Ok(ColorArg::Never));
+        assert_eq!("auto".parse(), Ok(ColorArg::Auto));
+        assert!("sometimes".parse::<ColorArg>().is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use language_reporting::termcolor::StandardStream;
+use language_reporting::Diagnostic;
+use mltt_compile::{closure, dead_code, erased, lifted};
+use mltt_core::{meta, syntax, Label};
+use mltt_elaborate::{Context, LanguageOptions};
+use mltt_span::{FileSpan, Files};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::check;
+use crate::color::ColorArg;
+
+/// Compile some files.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// When to use colored output.
+    #[structopt(
+        long = "color",
+        default_value = "auto",
+        raw(possible_values = "ColorArg::VARIANTS")
+    )]
+    pub color: ColorArg,
+    /// Don't bring the builtin types and values into scope.
+    #[structopt(long = "no-prelude")]
+    pub no_prelude: bool,
+    /// Report the definitions that were removed because they were unreachable
+    /// from `main`.
+    #[structopt(long = "verbose")]
+    pub verbose: bool,
+    /// The files to compile.
+    #[structopt(name = "FILE", parse(from_os_str))]
+    pub files: Vec<PathBuf>,
+}
+
+/// Run the compiler with the given options.
+///
+/// There are no backends yet, so this only checks that the files can be
+/// lowered through the compilation passes.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let writer = StandardStream::stderr(options.color.color_choice(atty::Stream::Stderr));
+    let mut files = Files::new();
+    let language_options = LanguageOptions {
+        no_prelude: options.no_prelude,
+        ..LanguageOptions::default()
+    };
+    let mut error_count = 0;
+
+    for path in &options.files {
+        let src = fs::read_to_string(path)?;
+        let file_id = files.add(path.display().to_string(), src);
+        let mut metas = meta::Env::new();
+        let mut warnings = Vec::new();
+        let result =
+            check::check_file(language_options, &mut metas, &files[file_id], &mut warnings)
+                .and_then(|(context, module, _)| compile_module(&context, &metas, &module));
+        let config = language_reporting::DefaultConfig;
+
+        for warning in &warnings {
+            language_reporting::emit(&mut writer.lock(), &files, warning, &config)?;
+        }
+        match result {
+            Ok(dropped) => {
+                if options.verbose && !dropped.is_empty() {
+                    print_dropped(files[file_id].name(), &dropped);
+                }
+            },
+            Err(diagnostic) => {
+                language_reporting::emit(&mut writer.lock(), &files, &diagnostic, &config)?;
+                error_count += 1;
+            },
+        }
+    }
+
+    match error_count {
+        0 => Ok(()),
+        1 => Err("aborting due to a previous error".into()),
+        _ => Err(format!("aborting due to {} previous errors", error_count).into()),
+    }
+}
+
+/// Lower an elaborated module through the compilation passes, returning the
+/// labels of the definitions that were removed by dead code elimination.
+fn compile_module(
+    context: &Context,
+    metas: &meta::Env,
+    module: &syntax::Module,
+) -> Result<Vec<Label>, Diagnostic<FileSpan>> {
+    let size = context.values().size();
+    let erased_module =
+        erased::erase_module(context.prims(), metas, size, module).map_err(Diagnostic::new_bug)?;
+    let roots = dead_code::default_roots(&erased_module);
+    let (erased_module, dropped) = dead_code::eliminate_dead_code(&erased_module, &roots);
+    let closure_module =
+        closure::convert_module(size, &erased_module).map_err(Diagnostic::new_bug)?;
+    let _program = lifted::lift_module(&closure_module);
+
+    Ok(dropped)
+}
+
+fn print_dropped(file_name: &str, dropped: &[Label]) {
+    println!("{}:", file_name);
+    for label in dropped {
+        println!("  dropped unused definition `{}`", label);
+    }
+}
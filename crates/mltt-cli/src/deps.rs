@@ -0,0 +1,112 @@
+use language_reporting::termcolor::StandardStream;
+use language_reporting::Diagnostic;
+use mltt_core::deps::DependencyGraph;
+use mltt_core::meta;
+use mltt_elaborate::LanguageOptions;
+use mltt_span::{File, FileSpan, Files};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::check;
+use crate::color::ColorArg;
+
+/// Print the dependencies between the definitions of some files.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// When to use colored output.
+    #[structopt(
+        long = "color",
+        default_value = "auto",
+        raw(possible_values = "ColorArg::VARIANTS")
+    )]
+    pub color: ColorArg,
+    /// Don't bring the builtin types and values into scope.
+    #[structopt(long = "no-prelude")]
+    pub no_prelude: bool,
+    /// Print the definitions that use each definition, rather than the
+    /// definitions that it uses.
+    #[structopt(long = "reverse")]
+    pub reverse: bool,
+    /// Print the dependencies as a graph in the Graphviz DOT format.
+    #[structopt(long = "dot")]
+    pub dot: bool,
+    /// The files to print the dependencies of.
+    #[structopt(name = "FILE", parse(from_os_str))]
+    pub files: Vec<PathBuf>,
+}
+
+/// Print the dependencies with the given options.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let writer = StandardStream::stderr(options.color.color_choice(atty::Stream::Stderr));
+    let mut files = Files::new();
+    let language_options = LanguageOptions {
+        no_prelude: options.no_prelude,
+        ..LanguageOptions::default()
+    };
+    let mut error_count = 0;
+
+    for path in &options.files {
+        let src = fs::read_to_string(path)?;
+        let file_id = files.add(path.display().to_string(), src);
+        let mut warnings = Vec::new();
+        let result = dependency_graph(language_options, &files[file_id], &mut warnings);
+        let config = language_reporting::DefaultConfig;
+
+        for warning in &warnings {
+            language_reporting::emit(&mut writer.lock(), &files, warning, &config)?;
+        }
+        match result {
+            Ok(graph) if options.dot => print!("{}", graph.to_dot()),
+            Ok(graph) => {
+                println!("{}:", files[file_id].name());
+                for (position, label) in graph.labels().iter().enumerate() {
+                    match describe(&graph, position, options.reverse) {
+                        None => println!("  {}", label),
+                        Some(labels) => println!("  {}: {}", label, labels),
+                    }
+                }
+            },
+            Err(diagnostic) => {
+                language_reporting::emit(&mut writer.lock(), &files, &diagnostic, &config)?;
+                error_count += 1;
+            },
+        }
+    }
+
+    match error_count {
+        0 => Ok(()),
+        1 => Err("aborting due to a previous error".into()),
+        _ => Err(format!("aborting due to {} previous errors", error_count).into()),
+    }
+}
+
+/// Check a file, and build the dependency graph of its definitions.
+pub(crate) fn dependency_graph(
+    language_options: LanguageOptions,
+    file: &File,
+    warnings: &mut Vec<Diagnostic<FileSpan>>,
+) -> Result<DependencyGraph, Diagnostic<FileSpan>> {
+    let mut metas = meta::Env::new();
+    let (_, module, _) = check::check_file(language_options, &mut metas, file, warnings)?;
+
+    Ok(DependencyGraph::from_module(&module))
+}
+
+/// List the labels of the definitions that a definition depends on, or that
+/// depend on it if `reverse` is set. Returns `None` if there are none.
+pub(crate) fn describe(graph: &DependencyGraph, position: usize, reverse: bool) -> Option<String> {
+    let positions = match reverse {
+        true => graph.dependents(position),
+        false => graph.dependencies(position).iter().cloned().collect(),
+    };
+    if positions.is_empty() {
+        return None;
+    }
+
+    let labels = (positions.iter())
+        .map(|position| graph.labels()[*position].to_string())
+        .collect::<Vec<_>>();
+
+    Some(labels.join(", "))
+}
@@ -0,0 +1,447 @@
+//! A kernel for running MLTT code in Jupyter notebooks.
+//!
+//! This implements the message layer of the [Jupyter messaging protocol].
+//! Requests are read from standard input as JSON objects, one per line, and
+//! the replies are written to standard output in the same way, with a
+//! `channel` field saying which socket they should be sent on (`shell` or
+//! `iopub`). Connecting these to the ZeroMQ sockets described in the kernel's
+//! connection file, and signing the messages, is left to a bridge process.
+//!
+//! The following requests are supported:
+//!
+//! - `kernel_info_request`: describe the kernel and the language
+//! - `execute_request`: elaborate and normalize an expression, like the REPL
+//! - `inspect_request`: show the type of the name under the cursor
+//! - `shutdown_request`: stop the kernel
+//!
+//! [Jupyter messaging protocol]: https://jupyter-client.readthedocs.io/en/stable/messaging.html
+
+use language_reporting::termcolor::Buffer;
+use language_reporting::Diagnostic;
+use mltt_core::{meta, pretty};
+use mltt_elaborate::{Context, MetaInsertion};
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_parse::token::TokenKind;
+use mltt_span::{FileId, FileSpan, Files};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use crate::{color, repl};
+
+/// The version of the messaging protocol that the kernel implements.
+const PROTOCOL_VERSION: &str = "5.3";
+
+/// Run a Jupyter kernel.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// The width to wrap pretty printed output at.
+    #[structopt(long = "width", default_value = "80")]
+    pub width: usize,
+}
+
+/// Run the kernel with the given options, until a shutdown is requested or
+/// standard input is closed.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut kernel = Kernel::new(options.width);
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Skip malformed requests, rather than stopping the kernel. They can't
+        // be replied to, because the reply needs the header of the request.
+        let request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(error) => {
+                log::error!("skipping malformed request: {}", error);
+                continue;
+            },
+        };
+        for reply in kernel.handle(&request) {
+            serde_json::to_writer(&mut stdout, &reply)?;
+            writeln!(stdout)?;
+        }
+        stdout.flush()?;
+
+        if kernel.is_shutdown() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// The state of a kernel, which persists between the cells that are executed.
+pub struct Kernel {
+    files: Files,
+    /// The file that the code of each request is loaded into, until the code
+    /// creates some metavariables.
+    file_id: FileId,
+    context: Context,
+    metas: meta::Env,
+    /// The width to wrap pretty printed output at.
+    width: usize,
+    /// The identifier of this session, used in the headers of the replies.
+    session: String,
+    /// The number of messages that the kernel has sent.
+    message_count: u64,
+    /// The number of cells that have been executed.
+    execution_count: u64,
+    /// Whether a shutdown has been requested.
+    is_shutdown: bool,
+}
+
+impl Kernel {
+    /// Create a new kernel.
+    pub fn new(width: usize) -> Kernel {
+        let mut files = Files::new();
+        // Reuse the same file for each request where possible, to avoid
+        // leaking memory
+        let file_id = files.add_virtual("cell", "");
+
+        Kernel {
+            files,
+            file_id,
+            context: Context::default(),
+            metas: meta::Env::new(),
+            width,
+            session: format!("mltt-{}", std::process::id()),
+            message_count: 0,
+            execution_count: 0,
+            is_shutdown: false,
+        }
+    }
+
+    /// Returns `true` if a shutdown has been requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+
+    /// Handle a request, returning the messages to send in response.
+    pub fn handle(&mut self, request: &Value) -> Vec<Value> {
+        let msg_type = request["header"]["msg_type"].as_str().unwrap_or("");
+
+        let mut replies = vec![self.status(request, "busy")];
+        match msg_type {
+            "kernel_info_request" => {
+                let content = json!({
+                    "status": "ok",
+                    "protocol_version": PROTOCOL_VERSION,
+                    "implementation": "mltt",
+                    "implementation_version": env!("CARGO_PKG_VERSION"),
+                    "language_info": {
+                        "name": "mltt",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "mimetype": "text/x-mltt",
+                        "file_extension": ".mltt",
+                    },
+                    "banner": "MLTT",
+                });
+                replies.push(self.message("shell", request, "kernel_info_reply", content));
+            },
+            "execute_request" => self.execute(request, &mut replies),
+            "inspect_request" => {
+                let content = &request["content"];
+                let code = content["code"].as_str().unwrap_or("");
+                let cursor_pos = content["cursor_pos"].as_u64().unwrap_or(0) as usize;
+                let content = match self.inspect(code, cursor_pos) {
+                    Some(text) => json!({
+                        "status": "ok",
+                        "found": true,
+                        "data": { "text/plain": text },
+                        "metadata": {},
+                    }),
+                    None => json!({ "status": "ok", "found": false, "data": {}, "metadata": {} }),
+                };
+                replies.push(self.message("shell", request, "inspect_reply", content));
+            },
+            "shutdown_request" => {
+                self.is_shutdown = true;
+                let restart = request["content"]["restart"].as_bool().unwrap_or(false);
+                let content = json!({ "status": "ok", "restart": restart });
+                replies.push(self.message("shell", request, "shutdown_reply", content));
+            },
+            // Other requests are optional, so we only report our status
+            _ => {},
+        }
+        replies.push(self.status(request, "idle"));
+
+        replies
+    }
+
+    /// Execute the code of an execute request, publishing the result.
+    fn execute(&mut self, request: &Value, replies: &mut Vec<Value>) {
+        let content = &request["content"];
+        let code = content["code"].as_str().unwrap_or("");
+        if !content["silent"].as_bool().unwrap_or(false) {
+            self.execution_count += 1;
+        }
+        let execution_count = self.execution_count;
+
+        let content = json!({ "code": code, "execution_count": execution_count });
+        replies.push(self.message("iopub", request, "execute_input", content));
+
+        if code.trim().is_empty() {
+            let content = json!({ "status": "ok", "execution_count": execution_count });
+            replies.push(self.message("shell", request, "execute_reply", content));
+            return;
+        }
+
+        let (warnings, result) = self.eval(code);
+        if !warnings.is_empty() {
+            let content = json!({ "name": "stderr", "text": warnings });
+            replies.push(self.message("iopub", request, "stream", content));
+        }
+
+        let content = match result {
+            Ok(text) => {
+                let content = json!({
+                    "execution_count": execution_count,
+                    "data": { "text/plain": text },
+                    "metadata": {},
+                });
+                replies.push(self.message("iopub", request, "execute_result", content));
+                json!({
+                    "status": "ok",
+                    "execution_count": execution_count,
+                    "user_expressions": {},
+                })
+            },
+            Err(text) => {
+                let error = json!({
+                    "ename": "error",
+                    "evalue": text.lines().next().unwrap_or(""),
+                    "traceback": text.lines().collect::<Vec<_>>(),
+                });
+                replies.push(self.message("iopub", request, "error", error.clone()));
+                let mut content = json!({ "status": "error", "execution_count": execution_count });
+                content["ename"] = error["ename"].clone();
+                content["evalue"] = error["evalue"].clone();
+                content["traceback"] = error["traceback"].clone();
+                content
+            },
+        };
+        replies.push(self.message("shell", request, "execute_reply", content));
+    }
+
+    /// Elaborate and normalize some code, returning the rendered warnings,
+    /// along with either the rendered result or the rendered error.
+    fn eval(&mut self, code: &str) -> (String, Result<String, String>) {
+        self.files.update(self.file_id, code.to_owned());
+        let file = &self.files[self.file_id];
+
+        let start_meta = self.metas.next_index();
+        let result = repl::read_eval(&self.context, &mut self.metas, file);
+        let warnings = mltt_elaborate::defaulting_warnings(&self.metas, start_meta)
+            .map(|warning| self.render_diagnostic(&warning))
+            .collect::<String>();
+
+        // Keep the code if any metavariables were created from it, as their
+        // spans point into it
+        if self.metas.next_index() != start_meta {
+            self.file_id = self.files.add_virtual("cell", "");
+        }
+
+        let result = match result {
+            Ok(evaluated) => {
+                let doc = pretty::ann(
+                    evaluated.term_to_doc(&self.context),
+                    self.context
+                        .value_to_doc(&self.metas, &evaluated.synthesized.ty),
+                );
+                Ok(color::render_plain(&doc, self.width))
+            },
+            Err(diagnostic) => Err(self.render_diagnostic(&diagnostic)),
+        };
+
+        (warnings, result)
+    }
+
+    /// Describe the name at the given cursor position, which is measured in
+    /// unicode code points.
+    fn inspect(&mut self, code: &str, cursor_pos: usize) -> Option<String> {
+        let cursor = (code.char_indices().nth(cursor_pos)).map_or(code.len(), |(i, _)| i);
+
+        // Lex the code, so that names are found using the same rules as the
+        // parser, and include any field projections leading up to the name.
+        self.files.update(self.file_id, code.to_owned());
+        let tokens = Lexer::new(&self.files[self.file_id])
+            .filter(|token| !token.is_whitespace())
+            .map(|token| {
+                let span = token.span();
+                (token.kind, span.start().to_usize(), span.end().to_usize())
+            })
+            .collect::<Vec<_>>();
+        let is_name = |kind| matches!(kind, TokenKind::Identifier | TokenKind::Keyword(_));
+        let mut index = tokens
+            .iter()
+            .position(|&(kind, start, end)| is_name(kind) && start <= cursor && cursor <= end)?;
+        let end = tokens[index].2;
+        while index >= 2
+            && tokens[index - 1].0 == TokenKind::Dot
+            && tokens[index - 2].0 == TokenKind::Identifier
+            && tokens[index - 2].2 == tokens[index - 1].1
+            && tokens[index - 1].2 == tokens[index].1
+        {
+            index -= 2;
+        }
+        let name = code[tokens[index].1..end].to_owned();
+
+        self.files.update(self.file_id, name.clone());
+        let file = &self.files[self.file_id];
+        let concrete_term = parser::parse_term(Lexer::new(file)).ok()?;
+        let mut metas = self.metas.clone();
+        let synthesized = mltt_elaborate::synth_term(
+            MetaInsertion::No,
+            &self.context,
+            &mut metas,
+            &concrete_term,
+        )
+        .ok()?;
+
+        let doc = pretty::ann(name, self.context.value_to_doc(&metas, &synthesized.ty));
+        Some(color::render_plain(&doc, self.width))
+    }
+
+    /// Render a diagnostic to a string, without any colors.
+    fn render_diagnostic(&self, diagnostic: &Diagnostic<FileSpan>) -> String {
+        let mut buffer = Buffer::no_color();
+        let config = language_reporting::DefaultConfig;
+        match language_reporting::emit(&mut buffer, &self.files, diagnostic, &config) {
+            Ok(()) => String::from_utf8_lossy(buffer.as_slice()).into_owned(),
+            Err(_) => diagnostic.message.clone(),
+        }
+    }
+
+    /// A status message, published when the kernel starts and finishes
+    /// handling a request.
+    fn status(&mut self, parent: &Value, execution_state: &str) -> Value {
+        let content = json!({ "execution_state": execution_state });
+        self.message("iopub", parent, "status", content)
+    }
+
+    /// Create a message to send on the given channel, in reply to a request.
+    fn message(&mut self, channel: &str, parent: &Value, msg_type: &str, content: Value) -> Value {
+        self.message_count += 1;
+
+        json!({
+            "channel": channel,
+            "header": {
+                "msg_id": format!("{}-{}", self.session, self.message_count),
+                "session": self.session,
+                "username": "mltt",
+                "msg_type": msg_type,
+                "version": PROTOCOL_VERSION,
+            },
+            "parent_header": parent["header"].clone(),
+            "metadata": {},
+            "content": content,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(msg_type: &str, content: Value) -> Value {
+        json!({
+            "header": { "msg_id": "1", "msg_type": msg_type },
+            "content": content,
+        })
+    }
+
+    fn find<'a>(replies: &'a [Value], msg_type: &str) -> &'a Value {
+        (replies.iter())
+            .find(|reply| reply["header"]["msg_type"] == msg_type)
+            .unwrap_or_else(|| panic!("no `{}` message in {:?}", msg_type, replies))
+    }
+
+    #[test]
+    fn kernel_info() {
+        let mut kernel = Kernel::new(80);
+        let replies = kernel.handle(&request("kernel_info_request", json!({})));
+
+        let reply = find(&replies, "kernel_info_reply");
+        assert_eq!(reply["channel"], "shell");
+        assert_eq!(reply["parent_header"]["msg_id"], "1");
+        assert_eq!(reply["content"]["language_info"]["name"], "mltt");
+        assert_eq!(
+            replies.first().unwrap()["content"]["execution_state"],
+            "busy"
+        );
+        assert_eq!(
+            replies.last().unwrap()["content"]["execution_state"],
+            "idle"
+        );
+    }
+
+    #[test]
+    fn execute() {
+        let mut kernel = Kernel::new(80);
+
+        let content = json!({ "code": "((fun x => x) : Bool -> Bool) true" });
+        let replies = kernel.handle(&request("execute_request", content));
+        assert_eq!(find(&replies, "execute_reply")["content"]["status"], "ok");
+        assert_eq!(
+            find(&replies, "execute_reply")["content"]["execution_count"],
+            1
+        );
+        let result = find(&replies, "execute_result");
+        assert_eq!(result["content"]["data"]["text/plain"], "true : Bool");
+
+        let content = json!({ "code": "undefined-name" });
+        let replies = kernel.handle(&request("execute_request", content));
+        assert_eq!(
+            find(&replies, "execute_reply")["content"]["status"],
+            "error"
+        );
+        assert_eq!(
+            find(&replies, "execute_reply")["content"]["execution_count"],
+            2
+        );
+        find(&replies, "error");
+    }
+
+    #[test]
+    fn inspect() {
+        let mut kernel = Kernel::new(80);
+
+        let content = json!({ "code": "Type", "cursor_pos": 2 });
+        let replies = kernel.handle(&request("inspect_request", content));
+        let reply = find(&replies, "inspect_reply");
+        assert_eq!(reply["content"]["found"], true);
+        assert_eq!(reply["content"]["data"]["text/plain"], "Type : Type^1");
+
+        let content = json!({ "code": "fun x => x", "cursor_pos": 5 });
+        let replies = kernel.handle(&request("inspect_request", content));
+        assert_eq!(find(&replies, "inspect_reply")["content"]["found"], false);
+
+        let content = json!({ "code": "Bool->Bool", "cursor_pos": 2 });
+        let replies = kernel.handle(&request("inspect_request", content));
+        let reply = find(&replies, "inspect_reply");
+        assert_eq!(reply["content"]["found"], true);
+        assert_eq!(reply["content"]["data"]["text/plain"], "Bool : Type");
+
+        let content = json!({ "code": "  ", "cursor_pos": 1 });
+        let replies = kernel.handle(&request("inspect_request", content));
+        assert_eq!(find(&replies, "inspect_reply")["content"]["found"], false);
+    }
+
+    #[test]
+    fn shutdown() {
+        let mut kernel = Kernel::new(80);
+        let content = json!({ "restart": false });
+        let replies = kernel.handle(&request("shutdown_request", content));
+
+        find(&replies, "shutdown_reply");
+        assert!(kernel.is_shutdown());
+    }
+}
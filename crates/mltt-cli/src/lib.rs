@@ -4,7 +4,14 @@
 
 use std::error::Error;
 
+pub mod check;
+pub mod color;
+pub mod compile;
+pub mod deps;
+pub mod kernel;
 pub mod repl;
+pub mod trace;
+pub mod validate;
 
 /// The MLTT command line interface.
 #[derive(structopt::StructOpt)]
@@ -12,20 +19,37 @@ pub mod repl;
 pub enum Options {
     /// Type check some files.
     #[structopt(name = "check")]
-    Check,
+    Check(check::Options),
+    /// Compile some files.
+    #[structopt(name = "compile")]
+    Compile(compile::Options),
+    /// Print the dependencies between the definitions of some files.
+    #[structopt(name = "deps")]
+    Deps(deps::Options),
     /// Runs the language server/IDE support.
     #[structopt(name = "ide")]
     Ide,
+    /// Runs a kernel for Jupyter notebooks.
+    #[structopt(name = "kernel")]
+    Kernel(kernel::Options),
     /// Runs the REPL/interactive mode.
     #[structopt(name = "repl")]
     Repl(repl::Options),
+    /// Check some files in the JSON interchange format using the core type
+    /// checker.
+    #[structopt(name = "validate")]
+    Validate(validate::Options),
 }
 
 /// Run the CLI with the given options
 pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
     match options {
-        Options::Check => Err("not yet implemented".into()),
-        Options::Ide => Err("not yet implemented".into()),
+        Options::Check(options) => check::run(options),
+        Options::Compile(options) => compile::run(options),
+        Options::Deps(options) => deps::run(options),
+        Options::Ide => mltt_language_server::run(),
+        Options::Kernel(options) => kernel::run(options),
         Options::Repl(options) => repl::run(options),
+        Options::Validate(options) => validate::run(options),
     }
 }
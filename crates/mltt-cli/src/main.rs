@@ -5,7 +5,7 @@ use std::error::Error;
 use structopt::StructOpt;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    pretty_env_logger::init();
+    mltt_cli::trace::init()?;
 
     mltt_cli::run(Options::from_args())
 }
@@ -1,33 +1,306 @@
-use language_reporting::termcolor::{ColorChoice, StandardStream};
-use language_reporting::Diagnostic;
-use mltt_core::{domain, meta, pretty, syntax};
-use mltt_elaborate::{Context, MetaInsertion};
+use ::pretty::{BoxDoc, Doc};
+use language_reporting::termcolor::{ColorSpec, StandardStream, WriteColor};
+use language_reporting::{Diagnostic, LabelStyle, Severity};
+use mltt_concrete::Term;
+use mltt_core::pretty::{self, Style};
+use mltt_core::{diff, domain, meta, nbe, prim, steps, syntax, Label, Rc};
+use mltt_elaborate::{Context, LanguageOptions, MetaInsertion, Synthesized};
 use mltt_parse::lexer::Lexer;
 use mltt_parse::parser;
-use mltt_span::{File, FileSpan, Files};
+use mltt_span::{ByteIndex, File, FileId, FileSpan, Files};
 use rustyline::error::ReadlineError;
-use rustyline::{Config, Editor};
+use rustyline::history::History;
+use rustyline::{Config, EditMode, Editor};
+use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::error::Error;
-use std::io::Write;
-use std::path::PathBuf;
-use std::rc::Rc;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::color::{self, ColorArg};
+use crate::{deps, trace};
 
 /// The MLTT REPL/interactive mode.
 #[derive(structopt::StructOpt)]
 pub struct Options {
-    /// The file to save the command history to.
-    #[structopt(long = "history-file", default_value = "repl-history")]
-    pub history_file: PathBuf,
+    /// The file to save the command history to. Defaults to `mltt/repl-history`
+    /// in the user's data directory, eg. `~/.local/share/mltt/repl-history`.
+    #[structopt(long = "history-file", parse(from_os_str))]
+    pub history_file: Option<PathBuf>,
+    /// The greatest number of lines to keep in the command history.
+    #[structopt(long = "history-size", default_value = "1000")]
+    pub history_size: usize,
+    /// Record the inputs and outputs of the session to a Markdown transcript.
+    #[structopt(long = "record", name = "TRANSCRIPT", parse(from_os_str))]
+    pub record: Option<PathBuf>,
     /// The prompt to display before expressions.
-    #[structopt(long = "prompt", default_value = "> ")]
+    #[structopt(long = "prompt", raw(default_value = "DEFAULT_PROMPT"))]
     pub prompt: String,
+    /// The width to wrap pretty printed output at. Defaults to the width of
+    /// the terminal.
+    #[structopt(long = "width")]
+    pub width: Option<usize>,
+    /// When to use colored output.
+    #[structopt(
+        long = "color",
+        default_value = "auto",
+        raw(possible_values = "ColorArg::VARIANTS")
+    )]
+    pub color: ColorArg,
+    /// The greatest number of steps that a conversion check can take before
+    /// giving up.
+    #[structopt(long = "conversion-budget", name = "STEPS")]
+    pub conversion_budget: Option<u32>,
+    /// Refuse to run primitives that have effects, leaving them unevaluated.
+    #[structopt(long = "pure")]
+    pub pure: bool,
+    /// How input is read and results are written.
+    #[structopt(
+        long = "protocol",
+        default_value = "text",
+        raw(possible_values = "ProtocolArg::VARIANTS")
+    )]
+    pub protocol: ProtocolArg,
+}
+
+/// How the REPL reads input and writes results.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProtocolArg {
+    /// Read lines interactively, and print results as text.
+    Text,
+    /// Read lines from standard input, and reply to each one with a single
+    /// JSON object, for driving the REPL from other programs.
+    Json,
+}
+
+impl ProtocolArg {
+    /// The possible values that can be passed on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["text", "json"];
+}
+
+impl fmt::Display for ProtocolArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolArg::Text => write!(f, "text"),
+            ProtocolArg::Json => write!(f, "json"),
+        }
+    }
 }
 
+impl FromStr for ProtocolArg {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<ProtocolArg, String> {
+        match src {
+            "text" => Ok(ProtocolArg::Text),
+            "json" => Ok(ProtocolArg::Json),
+            _ => Err(format!("unexpected protocol option `{}`", src)),
+        }
+    }
+}
+
+/// Settings that can be changed while the REPL is running, using the `:set`
+/// and `:unset` commands. These start out with the values given on the
+/// command line.
+#[derive(Debug, Clone, PartialEq)]
+struct Settings {
+    /// Whether implicit and instance arguments are displayed.
+    show_implicits: bool,
+    /// Whether results are normalized in full, rather than being read back
+    /// from their weak head normal forms with the large function bodies and
+    /// other unevaluated parts elided.
+    normalize: bool,
+    /// When to use colored output.
+    color: ColorArg,
+    /// The width to wrap pretty printed output at, or `None` to use the width
+    /// of the terminal.
+    width: Option<usize>,
+    /// The greatest number of steps that a conversion check can take.
+    conversion_budget: Option<u32>,
+    /// Whether the steps taken by failed conversion checks are reported.
+    explain_conversion: bool,
+    /// Whether primitives that have effects are left unevaluated.
+    pure: bool,
+    /// The greatest number of redexes that `:steps` contracts.
+    step_fuel: usize,
+    /// The filter for the messages logged while evaluating input, in the same
+    /// format as the `RUST_LOG` environment variable.
+    trace: Option<String>,
+    /// The prompt to display before expressions.
+    prompt: String,
+}
+
+impl Settings {
+    /// The names of the settings, in the order that they are listed.
+    const NAMES: &'static [&'static str] = &[
+        "show-implicits",
+        "normalize",
+        "color",
+        "width",
+        "conversion-budget",
+        "explain-conversion",
+        "pure",
+        "step-fuel",
+        "trace",
+        "prompt",
+    ];
+
+    /// The initial settings, taken from the command line options.
+    fn new(options: &Options) -> Settings {
+        Settings {
+            show_implicits: false,
+            normalize: false,
+            color: options.color,
+            width: options.width,
+            conversion_budget: options.conversion_budget,
+            explain_conversion: false,
+            pure: options.pure,
+            step_fuel: STEP_FUEL,
+            trace: None,
+            prompt: options.prompt.clone(),
+        }
+    }
+
+    /// Set the setting with the given name. Boolean settings can be enabled
+    /// without supplying a value.
+    fn set(&mut self, name: &str, value: Option<&str>) -> Result<(), String> {
+        fn parse<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+            let value = value.ok_or_else(|| format!("expected a value for `{}`", name))?;
+            (value.parse()).map_err(|_| format!("invalid value for `{}`: `{}`", name, value))
+        }
+
+        match name {
+            "show-implicits" => {
+                self.show_implicits = value.map_or(Ok(true), |_| parse(name, value))?
+            },
+            "normalize" => self.normalize = value.map_or(Ok(true), |_| parse(name, value))?,
+            "color" => self.color = parse(name, value)?,
+            "width" => self.width = Some(parse(name, value)?),
+            "conversion-budget" => self.conversion_budget = Some(parse(name, value)?),
+            "explain-conversion" => {
+                self.explain_conversion = value.map_or(Ok(true), |_| parse(name, value))?
+            },
+            "pure" => self.pure = value.map_or(Ok(true), |_| parse(name, value))?,
+            "step-fuel" => self.step_fuel = parse(name, value)?,
+            "trace" => {
+                self.trace = Some(parse(name, value)?);
+                trace::set_filter(self.trace.as_deref());
+            },
+            "prompt" => {
+                let value: String = parse(name, value)?;
+                // Allow the prompt to be quoted, so that it can end with spaces
+                self.prompt = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(prompt) => prompt.to_owned(),
+                    None => value,
+                };
+            },
+            _ => return Err(format!("unknown option `{}`", name)),
+        }
+
+        Ok(())
+    }
+
+    /// Reset the setting with the given name to its default value. Boolean
+    /// settings are disabled.
+    fn unset(&mut self, name: &str) -> Result<(), String> {
+        match name {
+            "show-implicits" => self.show_implicits = false,
+            "normalize" => self.normalize = false,
+            "color" => self.color = ColorArg::Auto,
+            "width" => self.width = None,
+            "conversion-budget" => self.conversion_budget = None,
+            "explain-conversion" => self.explain_conversion = false,
+            "pure" => self.pure = false,
+            "step-fuel" => self.step_fuel = STEP_FUEL,
+            "trace" => {
+                self.trace = None;
+                trace::set_filter(None);
+            },
+            "prompt" => self.prompt = DEFAULT_PROMPT.to_owned(),
+            _ => return Err(format!("unknown option `{}`", name)),
+        }
+
+        Ok(())
+    }
+
+    /// The current value of the setting with the given name, formatted for
+    /// display.
+    fn value(&self, name: &str) -> Option<String> {
+        fn optional(value: Option<impl ToString>) -> String {
+            value.map_or_else(|| "unset".to_owned(), |value| value.to_string())
+        }
+
+        match name {
+            "show-implicits" => Some(self.show_implicits.to_string()),
+            "normalize" => Some(self.normalize.to_string()),
+            "color" => Some(self.color.to_string()),
+            "width" => Some(optional(self.width)),
+            "conversion-budget" => Some(optional(self.conversion_budget)),
+            "explain-conversion" => Some(self.explain_conversion.to_string()),
+            "pure" => Some(self.pure.to_string()),
+            "step-fuel" => Some(self.step_fuel.to_string()),
+            "trace" => Some(optional(self.trace.as_ref())),
+            "prompt" => Some(format!("{:?}", self.prompt)),
+            _ => None,
+        }
+    }
+
+    /// Update the elaboration context to use these settings.
+    fn apply(&self, context: &mut Context) {
+        context.set_show_implicits(self.show_implicits);
+        context.set_elide_size(if self.normalize {
+            None
+        } else {
+            Some(ELIDE_SIZE)
+        });
+        context.set_conversion_budget(self.conversion_budget);
+        context.set_explain_conversion(self.explain_conversion);
+        context.set_run_capability(if self.pure {
+            prim::Capability::Pure
+        } else {
+            prim::Capability::Unsafe
+        });
+    }
+
+    /// Create a writer for standard output that uses the color setting.
+    fn stdout(&self) -> StandardStream {
+        StandardStream::stdout(self.color.color_choice(atty::Stream::Stdout))
+    }
+}
+
+/// The prompt to display before expressions, if none was specified.
+const DEFAULT_PROMPT: &str = "> ";
+
+/// The size, in nodes, above which the bodies of functions are elided when
+/// results are not being normalized in full.
+const ELIDE_SIZE: usize = 32;
+
+/// The greatest number of redexes that `:steps` contracts, if the `step-fuel`
+/// setting has not been changed.
+const STEP_FUEL: usize = 100;
+
 /// Run the REPL with the given options.
 pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
-    let mut writer = StandardStream::stdout(ColorChoice::Always);
+    if options.protocol == ProtocolArg::Json {
+        return run_json(&options);
+    }
+
+    let mut settings = Settings::new(&options);
+    let transcript = match &options.record {
+        Some(path) => Some(fs::File::create(path)?),
+        None => None,
+    };
+    let mut writer = RecordingWriter::new(settings.stdout(), transcript);
+    let history_file = options.history_file.clone().or_else(default_history_file);
     let mut editor = {
+        // Previous lines can be searched with `Ctrl-R` in Emacs mode
         let config = Config::builder()
+            .edit_mode(EditMode::Emacs)
+            .max_history_size(options.history_size)
             .history_ignore_space(true)
             .history_ignore_dups(true)
             .build();
@@ -35,36 +308,53 @@ pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
         Editor::<()>::with_config(config)
     };
 
-    if editor.load_history(&options.history_file).is_err() {
-        // No previous REPL history!
+    if let Some(history_file) = &history_file {
+        if editor.load_history(history_file).is_err() {
+            // No previous REPL history!
+        }
     }
+    // The lines entered during this session, to be merged with the history
+    // saved by other sessions when we exit
+    let mut session_lines = Vec::new();
 
     let mut files = Files::new();
-    let context = Context::default();
+    // Reuse the same file for each line of input where possible, to avoid
+    // leaking memory
+    let mut file_id = files.add_virtual("repl", "");
+    let mut context = Context::new(LanguageOptions {
+        conversion_budget: settings.conversion_budget,
+        ..LanguageOptions::default()
+    });
+    settings.apply(&mut context);
     let mut metas = meta::Env::new();
+    let mut last_result = None;
 
     loop {
-        match editor.readline(&options.prompt) {
+        match editor.readline(&settings.prompt) {
             Ok(line) => {
-                let file_id = files.add("repl", line);
-                let file = &files[file_id];
-                editor.add_history_entry(file.contents());
-
-                match read_eval(&context, &mut metas, file) {
-                    Ok((term, ty)) => {
-                        let output = pretty::ann(
-                            context.term_to_doc(&term),
-                            context.value_to_doc(&metas, &ty),
-                        );
-
-                        let width = pretty_width(&mut editor);
-                        writeln!(writer, "{}", output.pretty(width))?;
-                    },
-                    Err(diagnostic) => {
-                        let config = language_reporting::DefaultConfig;
-                        language_reporting::emit(&mut writer.lock(), &files, &diagnostic, &config)?;
+                if editor.add_history_entry(line.as_str()) {
+                    session_lines.push(line.clone());
+                }
+
+                let width = pretty_width(&settings, &mut editor);
+                let mut input = Input {
+                    files: &mut files,
+                    file_id: &mut file_id,
+                    context: &context,
+                    metas: &mut metas,
+                    last_result: &mut last_result,
+                };
+
+                match Command::from_line(&line) {
+                    Some(command) => {
+                        run_command(&mut writer, width, &mut input, &mut settings, command)?;
+                        settings.apply(&mut context);
+                        writer.writer = settings.stdout();
                     },
+                    None => input.eval(&mut writer, width, &line, read_eval)?,
                 }
+
+                writer.record(&settings.prompt, &line)?;
             },
             Err(ReadlineError::Interrupted) => println!("Interrupted!"),
             Err(ReadlineError::Eof) => break,
@@ -72,35 +362,1452 @@ pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    editor.save_history(&options.history_file)?;
+    if let Some(history_file) = &history_file {
+        save_history(history_file, &session_lines, options.history_size)?;
+    }
 
     println!("Bye bye");
 
     Ok(())
 }
 
+/// Run the REPL using the JSON protocol, until standard input is closed.
+///
+/// Each line of input is answered with a single JSON object on its own line,
+/// as described by [`respond`]. Results are printed without wrapping, unless a
+/// width was given on the command line.
+fn run_json(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut settings = Settings::new(options);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut files = Files::new();
+    // Reuse the same file for each line of input where possible, to avoid
+    // leaking memory
+    let mut file_id = files.add_virtual("repl", "");
+    let mut context = Context::new(LanguageOptions {
+        conversion_budget: settings.conversion_budget,
+        ..LanguageOptions::default()
+    });
+    settings.apply(&mut context);
+    let mut metas = meta::Env::new();
+    let mut last_result = None;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let response = respond(&mut input, &mut settings, &line);
+        settings.apply(&mut context);
+
+        serde_json::to_writer(&mut stdout, &response)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Evaluate a line of input, returning a JSON object with the following
+/// fields:
+///
+/// - `input`: the line of input
+/// - `status`: `"ok"` if the input was evaluated, or `"error"` otherwise
+/// - `term`: the evaluated term, printed in full, or `null` if there was an
+///   error
+/// - `type`: the type of the term, or `null` if there was an error
+/// - `diagnostics`: the errors and warnings that were found
+/// - `time_ms`: the time taken to evaluate the input, in milliseconds
+///
+/// The `:check`, `:set` and `:unset` commands are also supported, with the
+/// last two replying with no term or type.
+fn respond(input: &mut Input<'_>, settings: &mut Settings, line: &str) -> Value {
+    let start_time = Instant::now();
+    let width = settings.width.unwrap_or(1_000_000_000);
+    let mut diagnostics = Vec::new();
+
+    let error = |message: String| json!({ "severity": "error", "message": message, "labels": [] });
+
+    let result = match Command::from_line(line) {
+        None => input.eval_json(width, line, read_eval, &mut diagnostics),
+        Some(Command::Check(src)) => input.eval_json(width, src, read_check, &mut diagnostics),
+        Some(Command::Level(src)) => input.eval_json(width, src, read_level, &mut diagnostics),
+        Some(Command::Set(name, value)) => settings.set(name, value).map(|()| None).map_err(error),
+        Some(Command::Unset(name)) => settings.unset(name).map(|()| None).map_err(error),
+        Some(Command::Unknown(command)) => Err(error(format!("unknown command `{}`", command))),
+        Some(_) => Err(error(
+            "command is not supported by the JSON protocol".to_owned(),
+        )),
+    };
+    let (status, term, ty) = match result {
+        Ok(Some((term, ty))) => ("ok", Value::from(term), Value::from(ty)),
+        Ok(None) => ("ok", Value::Null, Value::Null),
+        Err(diagnostic) => {
+            diagnostics.push(diagnostic);
+            ("error", Value::Null, Value::Null)
+        },
+    };
+
+    json!({
+        "input": line,
+        "status": status,
+        "term": term,
+        "type": ty,
+        "diagnostics": diagnostics,
+        "time_ms": start_time.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// Convert a diagnostic to JSON, with the locations of its labels given as
+/// byte offsets along with one-based lines and columns.
+fn diagnostic_to_json(files: &Files, diagnostic: &Diagnostic<FileSpan>) -> Value {
+    let position = |span: FileSpan, byte: ByteIndex| {
+        let location = files.location(span.source(), byte);
+        json!({
+            "byte": byte.to_usize(),
+            "line": location.map(|location| location.line.to_usize() + 1),
+            "column": location.map(|location| location.column.to_usize() + 1),
+        })
+    };
+    let labels = (diagnostic.labels.iter())
+        .map(|label| {
+            json!({
+                "primary": label.style == LabelStyle::Primary,
+                "message": label.message,
+                "start": position(label.span, label.span.start()),
+                "end": position(label.span, label.span.end()),
+            })
+        })
+        .collect::<Vec<_>>();
+    let severity = match diagnostic.severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    };
+
+    json!({
+        "severity": severity,
+        "message": diagnostic.message,
+        "labels": labels,
+    })
+}
+
+/// A writer that records the text that is written to it in a Markdown
+/// transcript, leaving out any colors.
+struct RecordingWriter<W> {
+    /// The writer to forward the output to.
+    writer: W,
+    /// The transcript to record to, along with the output written since the
+    /// last input was recorded, or `None` if we are not recording.
+    transcript: Option<(fs::File, Vec<u8>)>,
+}
+
+impl<W> RecordingWriter<W> {
+    fn new(writer: W, transcript: Option<fs::File>) -> RecordingWriter<W> {
+        RecordingWriter {
+            writer,
+            transcript: transcript.map(|file| (file, Vec::new())),
+        }
+    }
+
+    /// Record a line of input to the transcript, followed by the output that
+    /// was written in response to it.
+    fn record(&mut self, prompt: &str, input: &str) -> io::Result<()> {
+        if let Some((file, output)) = &mut self.transcript {
+            writeln!(file, "```text")?;
+            writeln!(file, "{}{}", prompt, input)?;
+            file.write_all(output)?;
+            if !output.is_empty() && !output.ends_with(b"\n") {
+                writeln!(file)?;
+            }
+            writeln!(file, "```")?;
+            writeln!(file)?;
+            output.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.writer.write(buf)?;
+        if let Some((_, output)) = &mut self.transcript {
+            output.extend_from_slice(&buf[..len]);
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: WriteColor> WriteColor for RecordingWriter<W> {
+    fn supports_color(&self) -> bool {
+        self.writer.supports_color()
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.writer.set_color(spec)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.writer.reset()
+    }
+}
+
+/// The default location of the history file, in the user's data directory.
+fn default_history_file() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("mltt").join("repl-history"))
+}
+
+/// Save the lines entered during this session to the history file, appending
+/// them to the lines saved by other sessions since this one started.
+fn save_history(
+    path: &Path,
+    session_lines: &[String],
+    max_size: usize,
+) -> Result<(), Box<dyn Error>> {
+    let mut saved_history = History::new();
+    if saved_history.load(path).is_err() {
+        // No previous REPL history!
+    }
+    let saved_lines = (0..saved_history.len()).filter_map(|index| saved_history.get(index));
+
+    let mut history = History::new();
+    history.set_max_len(max_size);
+    for line in merge_history(saved_lines, session_lines, max_size) {
+        history.add(line);
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    history.save(path)?;
+
+    Ok(())
+}
+
+/// Merge the lines of the current session into the saved history, keeping
+/// only the most recent occurrence of each line, and at most `max_size` lines.
+fn merge_history<'a>(
+    saved_lines: impl IntoIterator<Item = &'a String>,
+    session_lines: &'a [String],
+    max_size: usize,
+) -> Vec<&'a String> {
+    let lines = saved_lines
+        .into_iter()
+        .chain(session_lines)
+        .collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    let mut merged = (lines.into_iter().rev())
+        .filter(|line| seen.insert(*line))
+        .take(max_size)
+        .collect::<Vec<_>>();
+    merged.reverse();
+    merged
+}
+
+/// Commands that can be entered at the REPL prompt.
+enum Command<'line> {
+    /// Check a term against a type, and evaluate it.
+    Check(&'line str),
+    /// Evaluate a type, displaying the universe level that it lives in.
+    Level(&'line str),
+    /// Evaluate two terms, displaying the differences between their normal
+    /// forms.
+    Diff(&'line str),
+    /// Reduce a term one redex at a time, displaying each step.
+    Steps(&'line str),
+    /// Print the definitions that a definition in a file depends on, and the
+    /// definitions that depend on it.
+    Deps(&'line str, &'line str),
+    /// Print the last result in full, without eliding anything.
+    Force,
+    /// Print the metavariables that have been created so far.
+    Metas,
+    /// Print the types of the primitives whose names start with a prefix.
+    Prims(Option<&'line str>),
+    /// List the current settings.
+    Settings,
+    /// Change a setting, with an optional value.
+    Set(&'line str, Option<&'line str>),
+    /// Reset a setting to its default value.
+    Unset(&'line str),
+    /// An unrecognised command.
+    Unknown(&'line str),
+}
+
+impl<'line> Command<'line> {
+    /// Parse a command from a line of input, returning `None` if the line
+    /// does not start with a `:`.
+    fn from_line(line: &'line str) -> Option<Command<'line>> {
+        let line = line.trim();
+        if !line.starts_with(':') {
+            return None;
+        }
+
+        // The value of a setting is the rest of the line, so that it can
+        // contain spaces
+        let (command, args) = split_word(line);
+        match (command, args) {
+            (":check", Some(args)) => return Some(Command::Check(args)),
+            (":level", Some(args)) => return Some(Command::Level(args)),
+            (":diff", Some(args)) => return Some(Command::Diff(args)),
+            (":steps", Some(args)) => return Some(Command::Steps(args)),
+            (_, _) => {},
+        }
+
+        let (name, value) = match args.map(split_word) {
+            Some((name, value)) => (Some(name), value),
+            None => (None, None),
+        };
+        match (command, name, value) {
+            (":force", None, _) => Some(Command::Force),
+            (":metas", None, _) => Some(Command::Metas),
+            (":deps", Some(path), Some(name)) => Some(Command::Deps(path, name)),
+            (":prims", prefix, None) => Some(Command::Prims(prefix)),
+            (":set", None, _) => Some(Command::Settings),
+            (":set", Some(name), value) => Some(Command::Set(name, value)),
+            (":unset", Some(name), None) => Some(Command::Unset(name)),
+            (_, _, _) => Some(Command::Unknown(line)),
+        }
+    }
+}
+
+/// Split the first word from the start of a string, returning the rest of the
+/// string if it is not empty.
+fn split_word(src: &str) -> (&str, Option<&str>) {
+    match src.split_once(char::is_whitespace) {
+        Some((word, rest)) if !rest.trim().is_empty() => (word, Some(rest.trim())),
+        Some((word, _)) => (word, None),
+        None => (src, None),
+    }
+}
+
+/// The state needed to evaluate input from the REPL.
+struct Input<'a> {
+    /// The files that input is read from.
+    files: &'a mut Files,
+    /// The file that is reused for each line of input, until the input
+    /// creates some metavariables.
+    file_id: &'a mut FileId,
+    /// The context to evaluate the input in.
+    context: &'a Context,
+    /// The metavariables that have been created so far.
+    metas: &'a mut meta::Env,
+    /// The result of the last input that was evaluated successfully.
+    last_result: &'a mut Option<Evaluated>,
+}
+
+impl<'a> Input<'a> {
+    /// Put some input in the input file, then read it with the given function.
+    ///
+    /// The spans of the metavariables that are created while reading the
+    /// input point into the input file, so if there are any, the file is kept
+    /// as it is and later input is put in a new file.
+    fn read<T>(&mut self, src: &str, read: impl FnOnce(&mut meta::Env, &File) -> T) -> T {
+        self.files.update(*self.file_id, src.to_owned());
+
+        let start_meta = self.metas.next_index();
+        let result = read(self.metas, &self.files[*self.file_id]);
+
+        if self.metas.next_index() != start_meta {
+            *self.file_id = self.files.add_virtual("repl", "");
+        }
+
+        result
+    }
+
+    /// Read and evaluate some input with the given function, printing the
+    /// result along with any errors or warnings that were found.
+    fn eval(
+        &mut self,
+        writer: &mut impl WriteColor,
+        width: usize,
+        src: &str,
+        read: ReadEval,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = self.context;
+        let start_meta = self.metas.next_index();
+        let result = self.read(src, |metas, file| read(context, metas, file));
+        let metas = &*self.metas;
+        let config = language_reporting::DefaultConfig;
+
+        for warning in mltt_elaborate::defaulting_warnings(metas, start_meta) {
+            language_reporting::emit(&mut *writer, &*self.files, &warning, &config)?;
+        }
+
+        match result {
+            Ok(evaluated) => {
+                print_result(writer, width, context, metas, &evaluated)?;
+                *self.last_result = Some(evaluated);
+            },
+            Err(diagnostic) => {
+                language_reporting::emit(&mut *writer, &*self.files, &diagnostic, &config)?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Input<'a> {
+    /// Read two terms, printing the differences between their normal forms
+    /// along with any errors that were found.
+    fn diff(
+        &mut self,
+        writer: &mut impl WriteColor,
+        width: usize,
+        src: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = self.context;
+        let config = language_reporting::DefaultConfig;
+
+        match self.read(src, |metas, file| read_diff(context, metas, file)) {
+            Ok(differences) if differences.is_empty() => writeln!(writer, "the terms are equal")?,
+            Ok(differences) => {
+                let env = context.pretty_env();
+                for difference in &differences {
+                    color::render(&difference.to_display_doc(&env), width, &mut *writer)?;
+                    writeln!(writer)?;
+                }
+            },
+            Err(diagnostic) => {
+                language_reporting::emit(&mut *writer, &*self.files, &diagnostic, &config)?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Input<'a> {
+    /// Read a term, printing each step of its reduction with the redex that
+    /// is contracted next highlighted, along with any errors that were found.
+    fn steps(
+        &mut self,
+        writer: &mut impl WriteColor,
+        width: usize,
+        fuel: usize,
+        src: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = self.context;
+        let config = language_reporting::DefaultConfig;
+
+        let steps = match self.read(src, |metas, file| read_steps(context, metas, file, fuel)) {
+            Ok(steps) => steps,
+            Err(diagnostic) => {
+                language_reporting::emit(&mut *writer, &*self.files, &diagnostic, &config)?;
+                return Ok(());
+            },
+        };
+
+        // Function bodies are displayed in full, as that is where many of the
+        // redexes are found
+        let mut env = context.pretty_env();
+        env.set_elide_size(None);
+        for (index, step) in steps.iter().enumerate() {
+            env.set_highlight(step.redex.as_ref().map(|redex| redex.term.clone()));
+            write!(writer, "{}: ", index)?;
+            color::render(&step.term.to_display_doc(&env).group(), width, &mut *writer)?;
+            writeln!(writer)?;
+        }
+
+        let step_count = steps.len() - 1;
+        let plural = if step_count == 1 { "" } else { "s" };
+        match steps.last().and_then(|step| step.redex.as_ref()) {
+            None => writeln!(
+                writer,
+                "normal form reached after {} step{}",
+                step_count, plural
+            )?,
+            Some(_) => writeln!(
+                writer,
+                "stopped after {} step{} - the fuel can be raised with `:set step-fuel`",
+                step_count, plural,
+            )?,
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Input<'a> {
+    /// Read and evaluate some input with the given function for the JSON
+    /// protocol, returning the rendered term and type, or the error that was
+    /// found. Any warnings are added to the diagnostics.
+    fn eval_json(
+        &mut self,
+        width: usize,
+        src: &str,
+        read: ReadEval,
+        diagnostics: &mut Vec<Value>,
+    ) -> Result<Option<(String, String)>, Value> {
+        // Results are normalized in full, so that clients can parse them back
+        let mut context = self.context.clone();
+        context.set_elide_size(None);
+        let context = &context;
+        let start_meta = self.metas.next_index();
+        let result = self.read(src, |metas, file| read(context, metas, file));
+        let metas = &*self.metas;
+
+        for warning in mltt_elaborate::defaulting_warnings(metas, start_meta) {
+            diagnostics.push(diagnostic_to_json(self.files, &warning));
+        }
+
+        match result {
+            Ok(evaluated) => {
+                let term = color::render_plain(&evaluated.term_to_doc(context), width);
+                let ty = context.value_to_doc(metas, &evaluated.synthesized.ty);
+                let ty = color::render_plain(&ty, width);
+                *self.last_result = Some(evaluated);
+                Ok(Some((term, ty)))
+            },
+            Err(diagnostic) => Err(diagnostic_to_json(self.files, &diagnostic)),
+        }
+    }
+}
+
+/// Print the result of evaluating some input, along with its type.
+fn print_result(
+    writer: &mut impl WriteColor,
+    width: usize,
+    context: &Context,
+    metas: &meta::Env,
+    evaluated: &Evaluated,
+) -> Result<(), Box<dyn Error>> {
+    let output = pretty::ann(
+        evaluated.term_to_doc(context),
+        context.value_to_doc(metas, &evaluated.synthesized.ty),
+    );
+
+    color::render(&output, width, &mut *writer)?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Run a REPL command.
+fn run_command(
+    writer: &mut impl WriteColor,
+    width: usize,
+    input: &mut Input<'_>,
+    settings: &mut Settings,
+    command: Command<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let (files, context, metas) = (&*input.files, input.context, &*input.metas);
+
+    match command {
+        Command::Check(src) => input.eval(writer, width, src, read_check)?,
+        Command::Level(src) => input.eval(writer, width, src, read_level)?,
+        Command::Diff(src) => input.diff(writer, width, src)?,
+        Command::Steps(src) => input.steps(writer, width, settings.step_fuel, src)?,
+        Command::Deps(path, name) => print_deps(writer, path, name)?,
+        Command::Force => match &mut *input.last_result {
+            Some(evaluated) => {
+                let mut context = context.clone();
+                context.set_elide_size(None);
+                let run_context = context.run_context();
+                match run_context.read_back_value_shared(metas, None, &evaluated.value) {
+                    Ok(normal_term) => {
+                        // Keep the normal form, so that it is not computed again
+                        evaluated.normal_term = normal_term;
+                        evaluated.elided = Rc::from(Vec::new());
+                        print_result(writer, width, &context, metas, evaluated)?;
+                    },
+                    Err(diagnostic) => {
+                        let config = language_reporting::DefaultConfig;
+                        language_reporting::emit(&mut *writer, files, &diagnostic, &config)?;
+                    },
+                }
+            },
+            None => writeln!(writer, "nothing has been evaluated yet")?,
+        },
+        Command::Metas => {
+            for (index, (span, solution, ty)) in metas.iter() {
+                let location = match files.location(span.source(), span.start()) {
+                    Some(location) => format!(
+                        "{}:{}:{}",
+                        files[span.source()].name(),
+                        location.line.to_usize() + 1,
+                        location.column.to_usize() + 1,
+                    ),
+                    None => span.source().to_string(),
+                };
+                let ty = context.value_to_doc(metas, ty);
+                let (output, status) = match solution {
+                    meta::Solution::Unsolved => {
+                        (pretty::declaration(index.to_string(), ty), "unsolved")
+                    },
+                    meta::Solution::Solved(value) => {
+                        let label = pretty::ann(index.to_string(), ty);
+                        let value = context.value_to_doc(metas, value);
+                        (pretty::definition(label, value), "solved")
+                    },
+                };
+
+                color::render(&output, width, &mut *writer)?;
+                writeln!(writer, " -- {} at {}", status, location)?;
+            }
+        },
+        Command::Prims(prefix) => {
+            let mut entries = (context.prims().entries())
+                .filter(|(name, _)| name.0.starts_with(prefix.unwrap_or("")))
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|(name, _)| *name);
+
+            for (name, entry) in entries {
+                match &entry.ty {
+                    Some(ty) => {
+                        let output = pretty::declaration(name.to_string(), context.term_to_doc(ty));
+                        color::render(&output, width, &mut *writer)?;
+                        writeln!(writer)?;
+                    },
+                    None => writeln!(writer, "{} -- type given at each use", name)?,
+                }
+            }
+        },
+        Command::Settings => {
+            for name in Settings::NAMES {
+                let value = settings.value(name).unwrap_or_default();
+                writeln!(writer, "{} = {}", name, value)?;
+            }
+        },
+        Command::Set(name, value) => {
+            if let Err(message) = settings.set(name, value) {
+                writeln!(writer, "{}", message)?;
+            }
+        },
+        Command::Unset(name) => {
+            if let Err(message) = settings.unset(name) {
+                writeln!(writer, "{}", message)?;
+            }
+        },
+        Command::Unknown(command) => writeln!(writer, "unknown command `{}`", command)?,
+    }
+
+    Ok(())
+}
+
+/// Check a file, then print the definitions that the definition with the
+/// given name depends on, and the definitions that depend on it.
+fn print_deps(writer: &mut impl WriteColor, path: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    let src = match fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(error) => {
+            writeln!(writer, "could not read `{}`: {}", path, error)?;
+            return Ok(());
+        },
+    };
+    let mut files = Files::new();
+    let file_id = files.add(path, src);
+    let mut warnings = Vec::new();
+    let graph =
+        match deps::dependency_graph(LanguageOptions::default(), &files[file_id], &mut warnings) {
+            Ok(graph) => graph,
+            Err(diagnostic) => {
+                let config = language_reporting::DefaultConfig;
+                language_reporting::emit(&mut *writer, &files, &diagnostic, &config)?;
+                return Ok(());
+            },
+        };
+
+    match graph.position(&Label(name.to_owned())) {
+        None => writeln!(writer, "no definition named `{}` in `{}`", name, path)?,
+        Some(position) => {
+            let nothing = || "nothing".to_owned();
+            let dependencies = deps::describe(&graph, position, false).unwrap_or_else(nothing);
+            let dependents = deps::describe(&graph, position, true).unwrap_or_else(nothing);
+            writeln!(writer, "depends on: {}", dependencies)?;
+            writeln!(writer, "used by: {}", dependents)?;
+        },
+    }
+
+    Ok(())
+}
+
 /// Get the pretty width of the editor.
-fn pretty_width(editor: &mut Editor<()>) -> usize {
-    match editor.dimensions() {
-        Some((width, _)) => width,
-        None => 1000_000_000,
+fn pretty_width(settings: &Settings, editor: &mut Editor<()>) -> usize {
+    match (settings.width, editor.dimensions()) {
+        (Some(width), _) | (None, Some((width, _))) => width,
+        (None, None) => 1_000_000_000,
+    }
+}
+
+/// The result of reading and evaluating a term.
+pub(crate) struct Evaluated {
+    /// The elaborated term, along with its type.
+    pub synthesized: Synthesized,
+    /// The value of the term, in weak head normal form.
+    pub value: Rc<domain::Value>,
+    /// The value read back into the core syntax. This is the normal form of
+    /// the term, unless the context elides function bodies, in which case it
+    /// is only read back as far as it has been evaluated.
+    pub normal_term: Rc<syntax::Term>,
+    /// The placeholders for the parts of `normal_term` that were left
+    /// unevaluated.
+    pub elided: Rc<[Rc<syntax::Term>]>,
+}
+
+impl Evaluated {
+    /// Convert the term to a pretty printable document, with the parts of it
+    /// that were left unevaluated displayed as `...`.
+    pub fn term_to_doc(&self, context: &Context) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        let mut env = context.pretty_env();
+        env.set_elided(self.elided.clone());
+        self.normal_term.to_display_doc(&env)
     }
 }
 
+/// A function that reads and evaluates a file.
+type ReadEval = fn(&Context, &mut meta::Env, &File) -> Result<Evaluated, Diagnostic<FileSpan>>;
+
 /// Read and evaluate the given file.
-fn read_eval(
+///
+/// A type annotation on the whole term checks the term against it, as in
+/// `(fun x => x) : Bool -> Bool`. An annotation at the end of a function
+/// applies to its body, so functions without parameter annotations need to be
+/// wrapped in parentheses, or checked with `:check`.
+pub(crate) fn read_eval(
     context: &Context,
     metas: &mut meta::Env,
     file: &File,
-) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
-    let lexer = Lexer::new(&file);
+) -> Result<Evaluated, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
     let concrete_term = parser::parse_term(lexer)?;
 
-    let (core_term, ty) =
-        mltt_elaborate::synth_term(MetaInsertion::Yes, &context, metas, &concrete_term)?;
+    eval(context, metas, &concrete_term).map_err(|diagnostic| match &concrete_term {
+        Term::FunIntro(_, _, body) => match body.as_ref() {
+            Term::Ann(_, ann) => diagnostic.with_label(
+                language_reporting::Label::new_secondary(ann.span()).with_message(
+                    "this annotates the body of the function - wrap the function in \
+                     parentheses or use `:check` to annotate the function instead",
+                ),
+            ),
+            _ => diagnostic,
+        },
+        _ => diagnostic,
+    })
+}
 
+/// Read a term and a type from the given file, then check the term against
+/// the type and evaluate it.
+fn read_check(
+    context: &Context,
+    metas: &mut meta::Env,
+    file: &File,
+) -> Result<Evaluated, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
+    let (concrete_term, concrete_ty) = parser::parse_term_and_type(lexer)?;
+    let concrete_term = Term::Ann(Box::new(concrete_term), Box::new(concrete_ty));
+
+    eval(context, metas, &concrete_term)
+}
+
+/// Read a type from the given file, then evaluate it. The universe level of
+/// the type is displayed as the type of the result.
+fn read_level(
+    context: &Context,
+    metas: &mut meta::Env,
+    file: &File,
+) -> Result<Evaluated, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
+    let concrete_term = parser::parse_term(lexer)?;
+
+    // Report terms that are not types, and infer the levels of types whose
+    // universes are not yet known. The term is elaborated again by `eval`,
+    // so the metavariables created here are thrown away.
+    let (_, level) = mltt_elaborate::synth_universe(context, &mut metas.clone(), &concrete_term)?;
+    let mut evaluated = eval(context, metas, &concrete_term)?;
+    evaluated.synthesized.ty = Rc::from(domain::Value::universe(level));
+
+    Ok(evaluated)
+}
+
+/// Read two terms from the given file, then find the differences between
+/// their normal forms. The second term is checked against the type of the
+/// first, so that the terms can be compared.
+fn read_diff(
+    context: &Context,
+    metas: &mut meta::Env,
+    file: &File,
+) -> Result<Vec<diff::Difference>, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
+    // The first term is parsed as an argument, as with the term of `:check`
+    let (concrete_term1, concrete_term2) = parser::parse_term_and_type(lexer)?;
+
+    let start_meta = metas.next_index();
+    let synthesized =
+        mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, &concrete_term1)?;
+    let term2 = mltt_elaborate::check_term(context, metas, &concrete_term2, &synthesized.ty)?;
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    mltt_elaborate::check_placeholders(metas, start_meta)?;
+
+    let context = &context.run_context();
+    let (span1, span2) = (concrete_term1.span(), concrete_term2.span());
+    let value1 = context.eval_term(metas, span1, &synthesized.term)?;
+    let value2 = context.eval_term(metas, span2, &term2)?;
+    let normal_term1 = context.read_back_value(metas, span1, &value1)?;
+    let normal_term2 = context.read_back_value(metas, span2, &value2)?;
+
+    Ok(diff::diff_terms(&normal_term1, &normal_term2))
+}
+
+/// Read a term from the given file, then reduce it one redex at a time,
+/// contracting at most `fuel` redexes.
+fn read_steps(
+    context: &Context,
+    metas: &mut meta::Env,
+    file: &File,
+    fuel: usize,
+) -> Result<Vec<steps::ReductionStep>, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(file);
+    let concrete_term = parser::parse_term(lexer)?;
+
+    let start_meta = metas.next_index();
+    let synthesized =
+        mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, &concrete_term)?;
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    mltt_elaborate::check_placeholders(metas, start_meta)?;
+
+    // Fill in the solved metavariables up front, rather than taking steps to
+    // unfold them that would otherwise be hidden with the implicit arguments
+    let size = context.values().size();
+    let term = nbe::zonk_term(context.prims(), metas, size, &synthesized.term)
+        .map_err(|error| Diagnostic::new_bug(format!("failed to zonk term: {}", error)))?;
+
+    let context = &context.run_context();
+    context.reduction_steps(metas, concrete_term.span(), &term, fuel)
+}
+
+/// Elaborate and evaluate a term.
+fn eval(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_term: &Term<'_>,
+) -> Result<Evaluated, Diagnostic<FileSpan>> {
+    let start_meta = metas.next_index();
+    let synthesized =
+        mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, concrete_term)?;
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    mltt_elaborate::check_placeholders(metas, start_meta)?;
+
+    let context = &context.run_context();
     let term_span = concrete_term.span();
-    let term = context.normalize_term(metas, term_span, &core_term)?;
+    let value = context.whnf(metas, term_span, &synthesized.term)?;
+    let value = context.expand_record_value(metas, term_span, &value, &synthesized.ty)?;
+    let (normal_term, elided) = match context.elide_size() {
+        // Shared sub-values are bound with a `let`, to avoid blowing up the
+        // size of the printed term
+        None => (
+            context.read_back_value_shared(metas, term_span, &value)?,
+            Vec::new(),
+        ),
+        // Avoid normalizing the parts of the value that are still unevaluated
+        Some(elide_size) => context.read_back_whnf(metas, term_span, &value, elide_size)?,
+    };
+
+    Ok(Evaluated {
+        synthesized,
+        value,
+        normal_term,
+        elided: Rc::from(elided),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> Settings {
+        Settings::new(&Options {
+            history_file: None,
+            history_size: 1000,
+            record: None,
+            prompt: DEFAULT_PROMPT.to_owned(),
+            width: None,
+            color: ColorArg::Auto,
+            conversion_budget: None,
+            pure: false,
+            protocol: ProtocolArg::Text,
+        })
+    }
 
-    Ok((term, ty))
+    #[test]
+    fn parse_set_commands() {
+        match Command::from_line(":set") {
+            Some(Command::Settings) => {},
+            _ => panic!("expected `:set` to list the settings"),
+        }
+        match Command::from_line(":set  width   80") {
+            Some(Command::Set("width", Some("80"))) => {},
+            _ => panic!("expected `:set width 80` to set the width"),
+        }
+        match Command::from_line(":set prompt \"mltt> \"") {
+            Some(Command::Set("prompt", Some("\"mltt> \""))) => {},
+            _ => panic!("expected `:set prompt` to take the rest of the line"),
+        }
+        match Command::from_line(":unset width 80") {
+            Some(Command::Unknown(_)) => {},
+            _ => panic!("expected `:unset` to take no value"),
+        }
+    }
+
+    #[test]
+    fn parse_check_command() {
+        match Command::from_line(":check (fun x => x) Bool -> Bool") {
+            Some(Command::Check("(fun x => x) Bool -> Bool")) => {},
+            _ => panic!("expected `:check` to take the rest of the line"),
+        }
+        match Command::from_line(":prims u8-") {
+            Some(Command::Prims(Some("u8-"))) => {},
+            _ => panic!("expected `:prims` to take a prefix"),
+        }
+        match Command::from_line(":level Type -> Type") {
+            Some(Command::Level("Type -> Type")) => {},
+            _ => panic!("expected `:level` to take the rest of the line"),
+        }
+        match Command::from_line(":diff (fun x => x) fun y => y") {
+            Some(Command::Diff("(fun x => x) fun y => y")) => {},
+            _ => panic!("expected `:diff` to take the rest of the line"),
+        }
+        match Command::from_line(":steps (fun x => x) true") {
+            Some(Command::Steps("(fun x => x) true")) => {},
+            _ => panic!("expected `:steps` to take the rest of the line"),
+        }
+        match Command::from_line(":deps tests/samples/sigma.mltt eta") {
+            Some(Command::Deps("tests/samples/sigma.mltt", "eta")) => {},
+            _ => panic!("expected `:deps` to take a path and a name"),
+        }
+        match Command::from_line(":force") {
+            Some(Command::Force) => {},
+            _ => panic!("expected `:force` to print the last result in full"),
+        }
+        match Command::from_line(":check") {
+            Some(Command::Unknown(_)) => {},
+            _ => panic!("expected `:check` to take a term and a type"),
+        }
+    }
+
+    fn eval_str(read: ReadEval, src: &str) -> String {
+        eval_str_in(&Context::new(LanguageOptions::default()), read, src)
+    }
+
+    fn eval_str_in(context: &Context, read: ReadEval, src: &str) -> String {
+        let mut files = Files::new();
+        let file_id = files.add("test", src);
+        let mut metas = meta::Env::new();
+
+        let evaluated = read(context, &mut metas, &files[file_id]).unwrap();
+        let doc = pretty::ann(
+            evaluated.term_to_doc(context),
+            context.value_to_doc(&metas, &evaluated.synthesized.ty),
+        );
+        doc.group().pretty(1_000_000_000).to_string()
+    }
+
+    #[test]
+    fn eval_annotated_function() {
+        assert_eq!(
+            eval_str(read_eval, "(fun x => x) : Bool -> Bool"),
+            "fun x => x : Bool -> Bool",
+        );
+        assert_eq!(
+            eval_str(read_eval, "fun (x : Bool) => x : Bool"),
+            "fun x => x : Bool -> Bool",
+        );
+        assert_eq!(
+            eval_str(read_check, "(fun x y => x) (Bool -> String -> Bool)"),
+            "fun x y => x : Bool -> String -> Bool",
+        );
+    }
+
+    #[test]
+    fn eval_unannotated_function_body() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "fun x => x : Bool");
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+
+        let diagnostic = match read_eval(&context, &mut metas, &files[file_id]) {
+            Ok(_) => panic!("expected an error"),
+            Err(diagnostic) => diagnostic,
+        };
+        let hint = diagnostic.labels.last().unwrap();
+        assert_eq!(hint.style, LabelStyle::Secondary);
+        assert_eq!(hint.span, FileSpan::new(file_id, 13, 17));
+    }
+
+    #[test]
+    fn elide_large_function_bodies() {
+        let mut context = Context::new(LanguageOptions::default());
+        let mut settings = settings();
+        settings.apply(&mut context);
+
+        let small = "fun f x => f (f x) : (Bool -> Bool) -> Bool -> Bool";
+        let small_src = "(fun f x => f (f x)) : (Bool -> Bool) -> Bool -> Bool";
+        assert_eq!(eval_str_in(&context, read_eval, small_src), small);
+        let large_body = format!("{}f x{}", "f (".repeat(20), ")".repeat(20));
+        let large = format!("fun f x => {} : (Bool -> Bool) -> Bool -> Bool", large_body);
+        let large_src = format!(
+            "(fun f x => {}) : (Bool -> Bool) -> Bool -> Bool",
+            large_body
+        );
+        assert_eq!(
+            eval_str_in(&context, read_eval, &large_src),
+            "fun f x => ... : (Bool -> Bool) -> Bool -> Bool",
+        );
+
+        settings.set("normalize", None).unwrap();
+        settings.apply(&mut context);
+        assert_eq!(eval_str_in(&context, read_eval, &large_src), large);
+    }
+
+    #[test]
+    fn eval_prims() {
+        assert_eq!(eval_str(read_eval, "(primitive \"u8-add\") 1 2"), "3 : U8",);
+        assert_eq!(
+            eval_str(read_eval, "primitive \"array-length\""),
+            "primitive \"array-length\" : Fun (A : Type) -> Array A -> U64",
+        );
+    }
+
+    #[test]
+    fn eval_effectful_prims() {
+        let src = "(primitive \"debug-print\") \"hello\"";
+        assert_eq!(eval_str(read_eval, src), "\"hello\" : String");
+
+        let mut context = Context::default();
+        context.set_run_capability(prim::Capability::Pure);
+        assert_eq!(
+            eval_str_in(&context, read_eval, src),
+            "(primitive \"debug-print\") \"hello\" : String",
+        );
+    }
+
+    #[test]
+    fn eval_acc_elim() {
+        let src = r#"
+            let
+                Acc = primitive "Acc";
+                acc = primitive "acc";
+                acc-elim = primitive "acc-elim";
+                Below : Bool -> Bool -> Type;
+                Below _ _ = Record {};
+            in
+                fun (below : Fun (y : Bool) -> Below y true -> Acc Bool Below y) =>
+                    acc-elim Bool Below (fun _ => Bool) (fun _ _ rec => rec false (record {}))
+                        true (acc Bool Below true below)
+        "#;
+        assert_eq!(
+            eval_str(read_eval, src),
+            concat!(
+                "fun below => (primitive \"acc-elim\") Bool (fun x x1 => Record {}) (fun x => Bool) ",
+                "(fun x x1 rec => rec false (record {})) false (below false (record {})) : ",
+                "(Fun (y : Bool) -> Record {} -> (primitive \"Acc\") Bool (fun x1 x2 => Record {}) y) -> Bool",
+            ),
+        );
+    }
+
+    #[test]
+    fn print_prims() {
+        use language_reporting::termcolor::Buffer;
+
+        let mut settings = settings();
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let mut writer = Buffer::no_color();
+        let command = Command::Prims(Some("s8-neg"));
+        run_command(&mut writer, 80, &mut input, &mut settings, command).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "\"s8-neg\" : S8 -> S8;\n\"s8-neg-checked\" : S8 -> S8;\n\"s8-neg-wrap\" : S8 -> S8;\n",
+        );
+    }
+
+    #[test]
+    fn eval_levels() {
+        assert_eq!(
+            eval_str(read_level, "Bool -> String"),
+            "Bool -> String : Type"
+        );
+        assert_eq!(
+            eval_str(read_level, "Type -> Type^1"),
+            "Type -> Type^1 : Type^2"
+        );
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "true");
+        let context = Context::new(LanguageOptions::default());
+        assert!(read_level(&context, &mut meta::Env::new(), &files[file_id]).is_err());
+    }
+
+    #[test]
+    fn eval_stuck_record() {
+        assert_eq!(
+            eval_str(read_eval, "? : Record { x : Bool; y : String }"),
+            "record { x = ?0.x; y = ?0.y; } : Record { x : Bool; y : String; }",
+        );
+    }
+
+    #[test]
+    fn merge_history_deduplicates() {
+        let lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        let saved_lines = lines(&["a", "b", "c", "b"]);
+        let session_lines = lines(&["d", "a"]);
+
+        let merged = merge_history(&saved_lines, &session_lines, 10);
+        assert_eq!(
+            merged,
+            lines(&["c", "b", "d", "a"]).iter().collect::<Vec<_>>()
+        );
+
+        let merged = merge_history(&saved_lines, &session_lines, 2);
+        assert_eq!(merged, lines(&["d", "a"]).iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn record_transcript() {
+        use language_reporting::termcolor::Buffer;
+
+        let path = std::env::temp_dir().join("mltt-record-transcript-test.md");
+        let transcript = fs::File::create(&path).unwrap();
+        let mut writer = RecordingWriter::new(Buffer::ansi(), Some(transcript));
+
+        let doc = pretty::ann(pretty::binder("x"), pretty::universe0());
+        color::render(&doc, 80, &mut writer).unwrap();
+        writeln!(writer).unwrap();
+        writer.record("> ", "x").unwrap();
+        writer.record("> ", ":unset show-implicits").unwrap();
+
+        assert!(writer.writer.as_slice().starts_with(b"\x1b["));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "```text\n> x\nx : Type\n```\n\n```text\n> :unset show-implicits\n```\n\n",
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn json_responses() {
+        let mut settings = settings();
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let response = respond(&mut input, &mut settings, "true");
+        assert_eq!(response["status"], "ok");
+        assert_eq!(response["term"], "true");
+        assert_eq!(response["type"], "Bool");
+        assert_eq!(response["diagnostics"], json!([]));
+        assert!(response["time_ms"].is_f64());
+
+        let response = respond(&mut input, &mut settings, ":check true String");
+        assert_eq!(response["status"], "error");
+        assert_eq!(response["term"], Value::Null);
+        let diagnostic = &response["diagnostics"][0];
+        assert_eq!(diagnostic["severity"], "error");
+        assert_eq!(diagnostic["labels"][0]["start"]["column"], 1);
+
+        let response = respond(&mut input, &mut settings, ":set width 40");
+        assert_eq!(response["status"], "ok");
+        assert_eq!(settings.width, Some(40));
+
+        let response = respond(&mut input, &mut settings, ":metas");
+        assert_eq!(response["status"], "error");
+    }
+
+    #[test]
+    fn json_responses_are_not_elided() {
+        let mut settings = settings();
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let mut context = Context::new(LanguageOptions::default());
+        settings.apply(&mut context);
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let large = format!(
+            "(fun f x => {}f x{}) : (Bool -> Bool) -> Bool -> Bool",
+            "f (".repeat(20),
+            ")".repeat(20),
+        );
+        let response = respond(&mut input, &mut settings, &large);
+        assert_eq!(response["status"], "ok");
+        assert_eq!(
+            response["term"],
+            format!("fun f x => {}f x{}", "f (".repeat(20), ")".repeat(20)),
+        );
+    }
+
+    #[test]
+    fn diff_output() {
+        use language_reporting::termcolor::NoColor;
+
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let mut writer = NoColor::new(Vec::new());
+        input
+            .diff(&mut writer, 80, "(fun (x : Bool) => x) fun x => true")
+            .unwrap();
+        input
+            .diff(&mut writer, 80, "(fun (x : Bool) => x) fun y => y")
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "/fun-intro.body: x ≠ true\nthe terms are equal\n",
+        );
+    }
+
+    #[test]
+    fn metas_locations() {
+        use language_reporting::termcolor::NoColor;
+
+        let mut settings = settings();
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let first_file_id = file_id;
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        // Later input must not overwrite the input that the metavariable was
+        // created from
+        let mut writer = NoColor::new(Vec::new());
+        input
+            .eval(&mut writer, 80, "(fun (x : _) => x) true", read_eval)
+            .unwrap();
+        input
+            .eval(&mut writer, 80, "\"a longer line of input\"", read_eval)
+            .unwrap();
+        run_command(&mut writer, 80, &mut input, &mut settings, Command::Metas).unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(output.ends_with("?0 : Type = Bool; -- solved at repl:1:11\n"));
+        assert_ne!(file_id, first_file_id);
+        assert_eq!(files[first_file_id].contents(), "(fun (x : _) => x) true");
+    }
+
+    #[test]
+    fn force_last_result() {
+        use language_reporting::termcolor::NoColor;
+
+        let mut settings = settings();
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let mut context = Context::new(LanguageOptions::default());
+        settings.apply(&mut context);
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let body = format!("{}f x{}", "f (".repeat(20), ")".repeat(20));
+        let large = format!("(fun f x => {}) : (Bool -> Bool) -> Bool -> Bool", body);
+        let mut writer = NoColor::new(Vec::new());
+        input
+            .eval(&mut writer, 1_000_000_000, &large, read_eval)
+            .unwrap();
+        run_command(
+            &mut writer,
+            1_000_000_000,
+            &mut input,
+            &mut settings,
+            Command::Force,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            format!(
+                "fun f x => ... : (Bool -> Bool) -> Bool -> Bool\n\
+                 fun f x => {} : (Bool -> Bool) -> Bool -> Bool\n",
+                body,
+            ),
+        );
+    }
+
+    #[test]
+    fn steps_output() {
+        use language_reporting::termcolor::{Buffer, NoColor};
+
+        let mut files = Files::new();
+        let mut file_id = files.add_virtual("repl", "");
+        let context = Context::new(LanguageOptions::default());
+        let mut metas = meta::Env::new();
+        let mut last_result = None;
+        let mut input = Input {
+            files: &mut files,
+            file_id: &mut file_id,
+            context: &context,
+            metas: &mut metas,
+            last_result: &mut last_result,
+        };
+
+        let mut writer = NoColor::new(Vec::new());
+        input
+            .steps(&mut writer, 80, 100, "(fun (x : Bool) => x) true")
+            .unwrap();
+        input
+            .steps(&mut writer, 80, 1, "(fun (x : Bool) => x) true")
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "0: ((fun x => x) : Bool -> Bool) true\n\
+             1: (fun x => x) true\n\
+             2: true\n\
+             normal form reached after 2 steps\n\
+             0: ((fun x => x) : Bool -> Bool) true\n\
+             1: (fun x => x) true\n\
+             stopped after 1 step - the fuel can be raised with `:set step-fuel`\n",
+        );
+
+        // The redex is highlighted, but not the argument that it is applied to
+        let mut buffer = Buffer::ansi();
+        input
+            .steps(&mut buffer, 80, 0, "(fun (x : Bool) => x) true")
+            .unwrap();
+        let output = String::from_utf8(buffer.into_inner()).unwrap();
+        assert!(output.starts_with("0: \u{1b}[0m\u{1b}[1m\u{1b}[31m(("));
+        assert!(output.contains("Bool -> Bool)\u{1b}[0m \u{1b}[0m\u{1b}[32mtrue"));
+    }
+
+    #[test]
+    fn deps_output() {
+        use language_reporting::termcolor::NoColor;
+
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../tests/samples/sigma.mltt"
+        );
+        let mut writer = NoColor::new(Vec::new());
+        print_deps(&mut writer, path, "Exists").unwrap();
+        print_deps(&mut writer, path, "eta").unwrap();
+        print_deps(&mut writer, path, "missing").unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            format!(
+                "depends on: nothing\n\
+                 used by: eta\n\
+                 depends on: Exists\n\
+                 used by: nothing\n\
+                 no definition named `missing` in `{}`\n",
+                path,
+            ),
+        );
+    }
+
+    #[test]
+    fn set_and_unset() {
+        let mut settings = settings();
+
+        settings.set("show-implicits", None).unwrap();
+        settings.set("normalize", Some("true")).unwrap();
+        settings.set("color", Some("never")).unwrap();
+        settings.set("width", Some("80")).unwrap();
+        settings.set("conversion-budget", Some("1000")).unwrap();
+        settings.set("explain-conversion", Some("true")).unwrap();
+        settings.set("pure", None).unwrap();
+        settings.set("step-fuel", Some("10")).unwrap();
+        settings.set("trace", Some("mltt_elaborate=trace")).unwrap();
+        settings.set("prompt", Some("\"mltt> \"")).unwrap();
+
+        let values = (Settings::NAMES.iter())
+            .map(|name| settings.value(name).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                "true",
+                "true",
+                "never",
+                "80",
+                "1000",
+                "true",
+                "true",
+                "10",
+                "mltt_elaborate=trace",
+                "\"mltt> \""
+            ],
+        );
+
+        for name in Settings::NAMES {
+            settings.unset(name).unwrap();
+        }
+        assert_eq!(settings, self::settings());
+
+        assert!(settings.set("width", Some("wide")).is_err());
+        assert!(settings.set("width", None).is_err());
+        assert!(settings.set("trace", None).is_err());
+        assert!(settings.set("fuel", Some("10")).is_err());
+        assert!(settings.unset("fuel").is_err());
+    }
 }
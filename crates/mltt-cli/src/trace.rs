@@ -0,0 +1,88 @@
+//! Logging, with a trace filter that can be changed while the REPL is
+//! running.
+//!
+//! Messages are logged if they are enabled by the `RUST_LOG` environment
+//! variable, or by the trace filter. Both filters use the same syntax, for
+//! example `mltt_elaborate=trace,mltt_core::validate=debug`.
+
+use env_logger::filter::{self, Filter};
+use log::{Log, Metadata, Record};
+use std::env;
+use std::sync::{OnceLock, RwLock};
+
+/// The global logger, if it has been initialized.
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+struct Logger {
+    /// The filter given by the `RUST_LOG` environment variable.
+    env_filter: Filter,
+    /// The filter given by the trace setting of the REPL.
+    trace_filter: RwLock<Option<Filter>>,
+    /// The logger that formats the messages, which accepts every message.
+    logger: env_logger::Logger,
+}
+
+impl Logger {
+    /// Update the maximum level of the `log` crate, so that messages that
+    /// neither filter enables are skipped as early as possible.
+    fn update_max_level(&self) {
+        let trace_filter = self.trace_filter.read().unwrap();
+        let trace_level = trace_filter.as_ref().map(Filter::filter);
+        log::set_max_level(Ord::max(
+            self.env_filter.filter(),
+            trace_level.unwrap_or(log::LevelFilter::Off),
+        ));
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let trace_filter = self.trace_filter.read().unwrap();
+        self.env_filter.enabled(metadata)
+            || (trace_filter.as_ref()).is_some_and(|filter| filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let trace_filter = self.trace_filter.read().unwrap();
+        if self.env_filter.matches(record)
+            || (trace_filter.as_ref()).is_some_and(|filter| filter.matches(record))
+        {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// Initialize the global logger, using the filter given by the `RUST_LOG`
+/// environment variable.
+pub fn init() -> Result<(), log::SetLoggerError> {
+    let mut env_filter = filter::Builder::new();
+    if let Ok(spec) = env::var("RUST_LOG") {
+        env_filter.parse(&spec);
+    }
+
+    let logger = LOGGER.get_or_init(|| Logger {
+        env_filter: env_filter.build(),
+        trace_filter: RwLock::new(None),
+        logger: pretty_env_logger::formatted_builder()
+            .filter_level(log::LevelFilter::Trace)
+            .build(),
+    });
+    log::set_logger(logger)?;
+    logger.update_max_level();
+
+    Ok(())
+}
+
+/// Set the trace filter, or remove it if `spec` is `None`. This has no effect
+/// if the global logger has not been initialized with [`init`].
+pub fn set_filter(spec: Option<&str>) {
+    if let Some(logger) = LOGGER.get() {
+        let filter = spec.map(|spec| filter::Builder::new().parse(spec).build());
+        *logger.trace_filter.write().unwrap() = filter;
+        logger.update_max_level();
+    }
+}
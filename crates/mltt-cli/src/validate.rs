@@ -0,0 +1,59 @@
+use mltt_core::{interchange, lint, meta, prim, validate, var};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Check some files in the JSON interchange format using the core type
+/// checker.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Allow `Type : Type`, at the expense of logical consistency.
+    #[structopt(long = "type-in-type")]
+    pub type_in_type: bool,
+    /// The files to check.
+    #[structopt(name = "FILE", parse(from_os_str))]
+    pub files: Vec<PathBuf>,
+}
+
+/// Run the core type checker with the given options.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut context =
+        validate::Context::new(prim::Env::default(), var::Env::new(), var::Env::new());
+    context.set_type_in_type(options.type_in_type);
+    let mut error_count = 0;
+
+    for path in &options.files {
+        let src = fs::read_to_string(path)?;
+        let metas = meta::Env::new();
+        let module = match interchange::read_module(&src) {
+            Ok(module) => module,
+            Err(error) => {
+                eprintln!("error: {}: {}", path.display(), error);
+                error_count += 1;
+                continue;
+            },
+        };
+
+        // Report all of the malformed terms before type checking, which
+        // stops at the first error
+        let lint_errors = lint::lint_module(&context, &metas, &module);
+        if !lint_errors.is_empty() {
+            for error in &lint_errors {
+                eprintln!("error: {}: {}", path.display(), error);
+            }
+            error_count += lint_errors.len();
+            continue;
+        }
+
+        if let Err(error) = validate::check_module(&context, &metas, &module) {
+            eprintln!("error: {}: {}", path.display(), error);
+            error_count += 1;
+        }
+    }
+
+    match error_count {
+        0 => Ok(()),
+        1 => Err("aborting due to a previous error".into()),
+        _ => Err(format!("aborting due to {} previous errors", error_count).into()),
+    }
+}
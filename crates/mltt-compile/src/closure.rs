@@ -0,0 +1,375 @@
+//! Closure conversion.
+//!
+//! Functions in the erased core can refer to any of the variables that are in
+//! scope where they are introduced. Closure conversion makes this explicit:
+//! each function records the local variables that it captures, and its body
+//! only refers to those captures and to its own parameter.
+//!
+//! Top-level definitions are referred to as globals, so they never need to be
+//! captured.
+
+use mltt_core::literal::{LiteralIntro, LiteralPattern};
+use mltt_core::{prim, var, Label, Rc};
+use std::collections::BTreeSet;
+
+use crate::erased;
+
+/// A closure converted module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// The definitions in the module. The definition at position `n` is
+    /// referred to using `Term::Global`, at a level `n` greater than the
+    /// number of globals that were in scope before the module.
+    pub definitions: Vec<Definition>,
+}
+
+/// A closure converted definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub label: Label,
+    pub term: Rc<Term>,
+}
+
+/// Closure converted terms.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// Local variables of the enclosing function
+    Var(var::Index),
+    /// Top-level definitions
+    Global(var::Level),
+    /// Primitives
+    Prim(prim::Name),
+    /// Terms that were only needed for type checking
+    Erased,
+
+    /// Let bindings
+    Let(Vec<Definition>, Rc<Term>),
+
+    /// Literal introductions
+    LiteralIntro(LiteralIntro),
+    /// Eliminate a literal (case split on literals)
+    LiteralElim(Rc<Term>, Rc<[(LiteralPattern, Rc<Term>)]>, Rc<Term>),
+
+    /// Introduce a closure, capturing the given local variables
+    ///
+    /// The body is in the scope of the captured variables, in the order that
+    /// they are given, followed by the parameter of the function.
+    Closure(Vec<var::Index>, Rc<Term>),
+    /// Eliminate a function (application)
+    FunElim(Rc<Term>, Rc<Term>),
+
+    /// Introduce a record
+    ///
+    /// Later fields are in the scope of the earlier fields.
+    RecordIntro(Vec<(Label, Rc<Term>)>),
+    /// Eliminate a record (projection)
+    RecordElim(Rc<Term>, Label),
+
+    /// Introduce an array
+    ArrayIntro(Vec<Rc<Term>>),
+}
+
+/// Closure convert a module whose terms are in the scope of the given number
+/// of globals.
+pub fn convert_module(globals: var::Size, module: &erased::Module) -> Result<Module, String> {
+    let mut scope = Scope::new(globals);
+    let mut definitions = Vec::with_capacity(module.definitions.len());
+
+    for definition in &module.definitions {
+        let term = convert_term(&mut scope, &definition.term)?;
+        scope.add_global();
+        definitions.push(Definition {
+            label: definition.label.clone(),
+            term,
+        });
+    }
+
+    Ok(Module { definitions })
+}
+
+/// What a variable in the erased core refers to after closure conversion.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Binding {
+    /// A top-level definition.
+    Global(var::Level),
+    /// A local variable of the enclosing function.
+    Local(var::Level),
+    /// A local variable of an outer function that was not captured.
+    Uncaptured,
+}
+
+/// The variables in scope during closure conversion.
+#[derive(Debug, Clone)]
+struct Scope {
+    /// The bindings of each variable in the erased core, by level.
+    bindings: Vec<Binding>,
+    /// The number of globals that are in scope.
+    globals: var::Size,
+    /// The number of local variables of the enclosing function.
+    locals: var::Size,
+}
+
+impl Scope {
+    /// A scope containing the given number of globals.
+    fn new(globals: var::Size) -> Scope {
+        Scope {
+            bindings: (0..globals.0)
+                .map(|l| Binding::Global(var::Level(l)))
+                .collect(),
+            globals,
+            locals: var::Size(0),
+        }
+    }
+
+    fn add_global(&mut self) {
+        self.bindings
+            .push(Binding::Global(self.globals.next_level()));
+        self.globals += 1;
+    }
+
+    fn add_local(&mut self) {
+        self.bindings.push(Binding::Local(self.locals.next_level()));
+        self.locals += 1;
+    }
+
+    /// Run `f` in a new scope, removing any local variables that it added once
+    /// it returns.
+    fn scope<T>(&mut self, f: impl FnOnce(&mut Scope) -> T) -> T {
+        let (bindings_len, locals) = (self.bindings.len(), self.locals);
+        let result = f(self);
+        self.bindings.truncate(bindings_len);
+        self.locals = locals;
+        result
+    }
+
+    fn lookup(&self, var_index: var::Index) -> Option<Binding> {
+        let len = self.bindings.len();
+        let position = len.checked_sub(var_index.0 as usize + 1)?;
+        Some(self.bindings[position])
+    }
+}
+
+/// Closure convert a term.
+fn convert_term(scope: &mut Scope, term: &Rc<erased::Term>) -> Result<Rc<Term>, String> {
+    match term.as_ref() {
+        erased::Term::Var(var_index) => match scope.lookup(*var_index) {
+            Some(Binding::Global(var_level)) => Ok(Rc::from(Term::Global(var_level))),
+            Some(Binding::Local(var_level)) => {
+                Ok(Rc::from(Term::Var(scope.locals.index(var_level))))
+            },
+            Some(Binding::Uncaptured) => {
+                Err("closure conversion: variable was not captured".to_owned())
+            },
+            None => Err("closure conversion: variable not found".to_owned()),
+        },
+        erased::Term::Prim(prim_name) => Ok(Rc::from(Term::Prim(prim_name.clone()))),
+        erased::Term::Erased => Ok(Rc::from(Term::Erased)),
+
+        erased::Term::Let(definitions, body) => scope.scope(|scope| {
+            let definitions = (definitions.iter())
+                .map(|definition| {
+                    let term = convert_term(scope, &definition.term)?;
+                    scope.add_local();
+                    Ok(Definition {
+                        label: definition.label.clone(),
+                        term,
+                    })
+                })
+                .collect::<Result<_, String>>()?;
+            let body = convert_term(scope, body)?;
+
+            Ok(Rc::from(Term::Let(definitions, body)))
+        }),
+
+        erased::Term::LiteralIntro(literal_intro) => {
+            Ok(Rc::from(Term::LiteralIntro(literal_intro.clone())))
+        },
+        erased::Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = convert_term(scope, scrutinee)?;
+            let clauses = (clauses.iter())
+                .map(|(literal_intro, body)| {
+                    Ok((literal_intro.clone(), convert_term(scope, body)?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let default_body = convert_term(scope, default_body)?;
+
+            Ok(Rc::from(Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_body,
+            )))
+        },
+
+        erased::Term::FunIntro(body) => {
+            // Find the local variables that are used in the body of the
+            // function, in the order that they were bound
+            let mut free_vars = BTreeSet::new();
+            erased::collect_free_vars(body, 1, &mut free_vars);
+            let captured_levels = (free_vars.into_iter().rev())
+                .filter_map(|var_index| match scope.lookup(var::Index(var_index)) {
+                    Some(Binding::Local(var_level)) => Some(var_level),
+                    Some(Binding::Global(_)) | Some(Binding::Uncaptured) | None => None,
+                })
+                .collect::<Vec<_>>();
+
+            // The body of the closure can only see the variables it captures
+            let mut body_scope = Scope {
+                bindings: (scope.bindings.iter())
+                    .map(|binding| match binding {
+                        Binding::Local(var_level) => match captured_levels
+                            .iter()
+                            .position(|captured_level| captured_level == var_level)
+                        {
+                            Some(position) => Binding::Local(var::Level(position as u32)),
+                            None => Binding::Uncaptured,
+                        },
+                        binding => *binding,
+                    })
+                    .collect(),
+                globals: scope.globals,
+                locals: var::Size(captured_levels.len() as u32),
+            };
+            body_scope.add_local();
+            let body = convert_term(&mut body_scope, body)?;
+
+            let captures = (captured_levels.into_iter())
+                .map(|var_level| scope.locals.index(var_level))
+                .collect();
+
+            Ok(Rc::from(Term::Closure(captures, body)))
+        },
+        erased::Term::FunElim(fun, arg) => {
+            let fun = convert_term(scope, fun)?;
+            let arg = convert_term(scope, arg)?;
+            Ok(Rc::from(Term::FunElim(fun, arg)))
+        },
+
+        erased::Term::RecordIntro(fields) => scope.scope(|scope| {
+            let fields = (fields.iter())
+                .map(|(label, term)| {
+                    let term = convert_term(scope, term)?;
+                    scope.add_local();
+                    Ok((label.clone(), term))
+                })
+                .collect::<Result<_, String>>()?;
+
+            Ok(Rc::from(Term::RecordIntro(fields)))
+        }),
+        erased::Term::RecordElim(record, label) => {
+            let record = convert_term(scope, record)?;
+            Ok(Rc::from(Term::RecordElim(record, label.clone())))
+        },
+
+        erased::Term::ArrayIntro(elems) => {
+            let elems = (elems.iter())
+                .map(|elem| convert_term(scope, elem))
+                .collect::<Result<_, String>>()?;
+
+            Ok(Rc::from(Term::ArrayIntro(elems)))
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn var(index: u32) -> Rc<erased::Term> {
+        Rc::from(erased::Term::Var(var::Index(index)))
+    }
+
+    fn definition(name: &str, term: Rc<erased::Term>) -> erased::Definition {
+        erased::Definition {
+            label: Label(name.to_owned()),
+            term,
+        }
+    }
+
+    #[test]
+    fn capture_outer_param() {
+        // const = fun a => fun b => a
+        let module = erased::Module {
+            definitions: vec![definition(
+                "const",
+                Rc::from(erased::Term::FunIntro(Rc::from(erased::Term::FunIntro(
+                    var(1),
+                )))),
+            )],
+        };
+
+        let module = convert_module(var::Size(0), &module).unwrap();
+
+        let expected = Term::Closure(
+            Vec::new(),
+            Rc::from(Term::Closure(
+                vec![var::Index(0)],
+                // The capture comes before the parameter
+                Rc::from(Term::Var(var::Index(1))),
+            )),
+        );
+        assert_eq!(module.definitions[0].term, Rc::from(expected));
+    }
+
+    #[test]
+    fn globals_not_captured() {
+        // id = fun a => a
+        // apply = fun a => id a
+        let module = erased::Module {
+            definitions: vec![
+                definition("id", Rc::from(erased::Term::FunIntro(var(0)))),
+                definition(
+                    "apply",
+                    Rc::from(erased::Term::FunIntro(Rc::from(erased::Term::FunElim(
+                        var(1),
+                        var(0),
+                    )))),
+                ),
+            ],
+        };
+
+        // Pretend that there is a global from the prelude in scope
+        let module = convert_module(var::Size(1), &module).unwrap();
+
+        let expected = Term::Closure(
+            Vec::new(),
+            Rc::from(Term::FunElim(
+                Rc::from(Term::Global(var::Level(1))),
+                Rc::from(Term::Var(var::Index(0))),
+            )),
+        );
+        assert_eq!(module.definitions[1].term, Rc::from(expected));
+    }
+
+    #[test]
+    fn capture_let_bindings() {
+        // fun a => let x = a; y = x; in fun b => y
+        let term = Rc::from(erased::Term::FunIntro(Rc::from(erased::Term::Let(
+            vec![definition("x", var(0)), definition("y", var(0))],
+            Rc::from(erased::Term::FunIntro(var(1))),
+        ))));
+
+        let term = convert_term(&mut Scope::new(var::Size(0)), &term).unwrap();
+
+        let expected = Term::Closure(
+            Vec::new(),
+            Rc::from(Term::Let(
+                vec![
+                    Definition {
+                        label: Label("x".to_owned()),
+                        term: Rc::from(Term::Var(var::Index(0))),
+                    },
+                    Definition {
+                        label: Label("y".to_owned()),
+                        term: Rc::from(Term::Var(var::Index(0))),
+                    },
+                ],
+                // Only `y` is captured, which is at index 0 outside the closure
+                Rc::from(Term::Closure(
+                    vec![var::Index(0)],
+                    Rc::from(Term::Var(var::Index(1))),
+                )),
+            )),
+        );
+        assert_eq!(term, Rc::from(expected));
+    }
+}
@@ -0,0 +1,237 @@
+//! Dead code elimination.
+//!
+//! Definitions that can't be reached from the roots of a module - its `main`
+//! definition, or otherwise every definition that it exports - are removed
+//! from the erased core, so that backends don't need to emit them.
+
+use mltt_core::deps::DependencyGraph;
+use mltt_core::{var, Label, Rc};
+use std::collections::BTreeSet;
+
+use crate::erased::{self, Definition, Module, Term};
+
+/// The roots of a module: `main` if it is defined, otherwise every definition,
+/// as they are all exported.
+pub fn default_roots(module: &Module) -> Vec<Label> {
+    let main = Label("main".to_owned());
+    match module.definitions.iter().any(|def| def.label == main) {
+        true => vec![main],
+        false => (module.definitions.iter())
+            .map(|def| def.label.clone())
+            .collect(),
+    }
+}
+
+/// Remove the definitions that can't be reached from the given roots.
+///
+/// Returns the remaining module, along with the labels of the definitions that
+/// were removed, in the order that they were defined.
+pub fn eliminate_dead_code(module: &Module, roots: &[Label]) -> (Module, Vec<Label>) {
+    let definitions = &module.definitions;
+
+    // Roots refer to the last definition with a given label, as any earlier
+    // definitions are shadowed
+    let graph = dependency_graph(module);
+    let roots = (roots.iter())
+        .filter_map(|root| graph.position(root))
+        .collect::<Vec<_>>();
+    let reachable = graph.reachable(&roots);
+
+    // The new positions of the reachable definitions
+    let mut new_positions = Vec::with_capacity(definitions.len());
+    let mut kept = 0;
+    for is_reachable in &reachable {
+        new_positions.push(kept);
+        if *is_reachable {
+            kept += 1;
+        }
+    }
+
+    let mut kept_definitions = Vec::with_capacity(kept as usize);
+    let mut dropped = Vec::new();
+
+    for (position, def) in definitions.iter().enumerate() {
+        if !reachable[position] {
+            dropped.push(def.label.clone());
+            continue;
+        }
+
+        let (position, new_position) = (position as u32, new_positions[position]);
+        let term = rename_free_vars(&def.term, 0, &|var_index| match position
+            .checked_sub(var_index + 1)
+        {
+            Some(dependency) => new_position - new_positions[dependency as usize] - 1,
+            None => var_index - position + new_position,
+        });
+        kept_definitions.push(Definition {
+            label: def.label.clone(),
+            term,
+        });
+    }
+
+    let module = Module {
+        definitions: kept_definitions,
+    };
+
+    (module, dropped)
+}
+
+/// Build the graph of the dependencies between the definitions of a module.
+fn dependency_graph(module: &Module) -> DependencyGraph {
+    let labels = (module.definitions.iter())
+        .map(|def| def.label.clone())
+        .collect();
+    let dependencies = (module.definitions.iter().enumerate())
+        .map(|(position, def)| {
+            let mut free_vars = BTreeSet::new();
+            erased::collect_free_vars(&def.term, 0, &mut free_vars);
+            // Indices past the start of the module refer to globals that were
+            // defined outside of it
+            (free_vars.into_iter())
+                .filter_map(|var_index| (position as u32).checked_sub(var_index + 1))
+                .map(|dependency| dependency as usize)
+                .collect()
+        })
+        .collect();
+
+    DependencyGraph::new(labels, dependencies)
+}
+
+/// Rename the variables that are free in a term, under the given number of
+/// binders. The renaming is given indices relative to the scope outside of
+/// these binders.
+fn rename_free_vars(term: &Rc<Term>, binders: u32, rename: &dyn Fn(u32) -> u32) -> Rc<Term> {
+    match term.as_ref() {
+        Term::Var(var_index) if var_index.0 >= binders => {
+            let var_index = var::Index(rename(var_index.0 - binders) + binders);
+            Rc::from(Term::Var(var_index))
+        },
+        Term::Var(_) | Term::Prim(_) | Term::Erased | Term::LiteralIntro(_) => term.clone(),
+        Term::Let(definitions, body) => {
+            let body_binders = binders + definitions.len() as u32;
+            let definitions = (definitions.iter().enumerate())
+                .map(|(i, def)| Definition {
+                    label: def.label.clone(),
+                    term: rename_free_vars(&def.term, binders + i as u32, rename),
+                })
+                .collect();
+            let body = rename_free_vars(body, body_binders, rename);
+
+            Rc::from(Term::Let(definitions, body))
+        },
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = rename_free_vars(scrutinee, binders, rename);
+            let clauses = (clauses.iter())
+                .map(|(literal_intro, body)| {
+                    let body = rename_free_vars(body, binders, rename);
+                    (literal_intro.clone(), body)
+                })
+                .collect::<Vec<_>>();
+            let default_body = rename_free_vars(default_body, binders, rename);
+
+            Rc::from(Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_body,
+            ))
+        },
+        Term::FunIntro(body) => {
+            Rc::from(Term::FunIntro(rename_free_vars(body, binders + 1, rename)))
+        },
+        Term::FunElim(fun, arg) => Rc::from(Term::FunElim(
+            rename_free_vars(fun, binders, rename),
+            rename_free_vars(arg, binders, rename),
+        )),
+        Term::RecordIntro(fields) => {
+            let fields = (fields.iter().enumerate())
+                .map(|(i, (label, term))| {
+                    let term = rename_free_vars(term, binders + i as u32, rename);
+                    (label.clone(), term)
+                })
+                .collect();
+
+            Rc::from(Term::RecordIntro(fields))
+        },
+        Term::RecordElim(record, label) => Rc::from(Term::RecordElim(
+            rename_free_vars(record, binders, rename),
+            label.clone(),
+        )),
+        Term::ArrayIntro(elems) => {
+            let elems = (elems.iter())
+                .map(|elem| rename_free_vars(elem, binders, rename))
+                .collect();
+
+            Rc::from(Term::ArrayIntro(elems))
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn var(index: u32) -> Rc<Term> {
+        Rc::from(Term::Var(var::Index(index)))
+    }
+
+    fn definition(name: &str, term: Rc<Term>) -> Definition {
+        Definition {
+            label: Label(name.to_owned()),
+            term,
+        }
+    }
+
+    #[test]
+    fn keep_everything_without_main() {
+        let module = Module {
+            definitions: vec![
+                definition("id", Rc::from(Term::FunIntro(var(0)))),
+                definition("unit", Rc::from(Term::RecordIntro(Vec::new()))),
+            ],
+        };
+
+        let roots = default_roots(&module);
+        let (result, dropped) = eliminate_dead_code(&module, &roots);
+
+        assert_eq!(result, module);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn drop_unreachable_definitions() {
+        // id = fun a => a
+        // unused = fun a => a
+        // unit = record {}
+        // main = let outer = <global>; in id unit
+        let module = Module {
+            definitions: vec![
+                definition("id", Rc::from(Term::FunIntro(var(0)))),
+                definition("unused", Rc::from(Term::FunIntro(var(0)))),
+                definition("unit", Rc::from(Term::RecordIntro(Vec::new()))),
+                // There is a global defined outside of the module at index 3
+                definition(
+                    "main",
+                    Rc::from(Term::Let(
+                        vec![definition("outer", var(3))],
+                        Rc::from(Term::FunElim(var(3), var(1))),
+                    )),
+                ),
+            ],
+        };
+
+        let roots = default_roots(&module);
+        let (result, dropped) = eliminate_dead_code(&module, &roots);
+
+        assert_eq!(dropped, vec![Label("unused".to_owned())]);
+        assert_eq!(
+            result.definitions[2],
+            definition(
+                "main",
+                Rc::from(Term::Let(
+                    vec![definition("outer", var(2))],
+                    Rc::from(Term::FunElim(var(2), var(1))),
+                )),
+            ),
+        );
+    }
+}
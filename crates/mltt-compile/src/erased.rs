@@ -0,0 +1,303 @@
+//! The erased core syntax.
+//!
+//! This is the core syntax with the parts that are only needed for type
+//! checking removed. Types are replaced with `Term::Erased`, annotations and
+//! declarations are dropped, solved metavariables are replaced with their
+//! solutions, and application modes are forgotten.
+//!
+//! Functions that take types as parameters, like implicit type parameters,
+//! are kept as functions for now, and are passed `Term::Erased` as their
+//! argument.
+
+use mltt_core::literal::{LiteralIntro, LiteralPattern};
+use mltt_core::{meta, nbe, prim, syntax, var, Label, Rc};
+use std::collections::BTreeSet;
+
+/// An erased module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// The definitions in the module. Each definition is in the scope of the
+    /// definitions that come before it.
+    pub definitions: Vec<Definition>,
+}
+
+/// An erased definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub label: Label,
+    pub term: Rc<Term>,
+}
+
+/// Erased terms.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// Variables
+    Var(var::Index),
+    /// Primitives
+    Prim(prim::Name),
+    /// Terms that were only needed for type checking
+    Erased,
+
+    /// Let bindings
+    Let(Vec<Definition>, Rc<Term>),
+
+    /// Literal introductions
+    LiteralIntro(LiteralIntro),
+    /// Eliminate a literal (case split on literals)
+    LiteralElim(Rc<Term>, Rc<[(LiteralPattern, Rc<Term>)]>, Rc<Term>),
+
+    /// Introduce a function
+    FunIntro(Rc<Term>),
+    /// Eliminate a function (application)
+    FunElim(Rc<Term>, Rc<Term>),
+
+    /// Introduce a record
+    ///
+    /// Later fields are in the scope of the earlier fields.
+    RecordIntro(Vec<(Label, Rc<Term>)>),
+    /// Eliminate a record (projection)
+    RecordElim(Rc<Term>, Label),
+
+    /// Introduce an array
+    ArrayIntro(Vec<Rc<Term>>),
+}
+
+/// Erase the items of a module that was checked in an environment of the given
+/// size.
+pub fn erase_module(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    module: &syntax::Module,
+) -> Result<Module, String> {
+    let definitions = erase_items(prims, metas, size, &module.items)?;
+    Ok(Module { definitions })
+}
+
+/// Erase a term that was checked in an environment of the given size.
+pub fn erase_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    term: &Rc<syntax::Term>,
+) -> Result<Rc<Term>, String> {
+    use mltt_core::syntax::Term as CoreTerm;
+
+    match term.as_ref() {
+        CoreTerm::Var(var_index) => Ok(Rc::from(Term::Var(*var_index))),
+        CoreTerm::Meta(meta_index) => match metas.lookup_solution(*meta_index) {
+            Some((_, meta::Solution::Solved(value), _)) => {
+                let term = nbe::read_back_value(prims, metas, size, value)?;
+                erase_term(prims, metas, size, &term)
+            },
+            Some((_, meta::Solution::Unsolved, _)) => {
+                Err(format!("erase: unsolved metavariable: {:?}", meta_index))
+            },
+            None => Err("erase: metavariable not found".to_owned()),
+        },
+        CoreTerm::Prim(prim_name) => Ok(Rc::from(Term::Prim(prim_name.clone()))),
+
+        CoreTerm::Ann(term, _) => erase_term(prims, metas, size, term),
+        CoreTerm::Let(items, body) => {
+            let definitions = erase_items(prims, metas, size, items)?;
+            let size = size + definitions.len() as u32;
+            let body = erase_term(prims, metas, size, body)?;
+
+            Ok(Rc::from(Term::Let(definitions, body)))
+        },
+
+        CoreTerm::LiteralIntro(literal_intro) => {
+            Ok(Rc::from(Term::LiteralIntro(literal_intro.clone())))
+        },
+        CoreTerm::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = erase_term(prims, metas, size, scrutinee)?;
+            let clauses = (clauses.iter())
+                .map(|(literal_intro, body)| {
+                    Ok((literal_intro.clone(), erase_term(prims, metas, size, body)?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let default_body = erase_term(prims, metas, size, default_body)?;
+
+            Ok(Rc::from(Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_body,
+            )))
+        },
+
+        CoreTerm::FunIntro(_, _, body) => {
+            let body = erase_term(prims, metas, size + 1, body)?;
+            Ok(Rc::from(Term::FunIntro(body)))
+        },
+        CoreTerm::FunElim(fun, _, arg) => {
+            let fun = erase_term(prims, metas, size, fun)?;
+            let arg = erase_term(prims, metas, size, arg)?;
+            Ok(Rc::from(Term::FunElim(fun, arg)))
+        },
+
+        CoreTerm::RecordIntro(fields) => {
+            let fields = (fields.iter().enumerate())
+                .map(|(i, (label, term))| {
+                    let term = erase_term(prims, metas, size + i as u32, term)?;
+                    Ok((label.clone(), term))
+                })
+                .collect::<Result<_, String>>()?;
+
+            Ok(Rc::from(Term::RecordIntro(fields)))
+        },
+        CoreTerm::RecordElim(record, label) => {
+            let record = erase_term(prims, metas, size, record)?;
+            Ok(Rc::from(Term::RecordElim(record, label.clone())))
+        },
+
+        CoreTerm::ArrayIntro(elems) => {
+            let elems = (elems.iter())
+                .map(|elem| erase_term(prims, metas, size, elem))
+                .collect::<Result<_, String>>()?;
+
+            Ok(Rc::from(Term::ArrayIntro(elems)))
+        },
+
+        CoreTerm::LiteralType(_)
+        | CoreTerm::FunType(..)
+        | CoreTerm::RecordType(_)
+        | CoreTerm::ArrayType(_)
+        | CoreTerm::Universe(_) => Ok(Rc::from(Term::Erased)),
+    }
+}
+
+/// Erase a list of items, keeping only the definitions.
+fn erase_items(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    items: &[syntax::Item],
+) -> Result<Vec<Definition>, String> {
+    let mut definitions = Vec::new();
+
+    for item in items {
+        if let syntax::Item::Definition(_, label, term) = item {
+            let size = size + definitions.len() as u32;
+            let term = erase_term(prims, metas, size, term)?;
+            definitions.push(Definition {
+                label: label.clone(),
+                term,
+            });
+        }
+    }
+
+    Ok(definitions)
+}
+
+/// Collect the variables that are free in a term, under the given number of
+/// binders. The indices are relative to the scope outside of these binders.
+pub fn collect_free_vars(term: &Term, binders: u32, free_vars: &mut BTreeSet<u32>) {
+    match term {
+        Term::Var(var_index) => {
+            if var_index.0 >= binders {
+                free_vars.insert(var_index.0 - binders);
+            }
+        },
+        Term::Prim(_) | Term::Erased | Term::LiteralIntro(_) => {},
+        Term::Let(definitions, body) => {
+            for (i, definition) in definitions.iter().enumerate() {
+                collect_free_vars(&definition.term, binders + i as u32, free_vars);
+            }
+            collect_free_vars(body, binders + definitions.len() as u32, free_vars);
+        },
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            collect_free_vars(scrutinee, binders, free_vars);
+            for (_, body) in clauses.iter() {
+                collect_free_vars(body, binders, free_vars);
+            }
+            collect_free_vars(default_body, binders, free_vars);
+        },
+        Term::FunIntro(body) => collect_free_vars(body, binders + 1, free_vars),
+        Term::FunElim(fun, arg) => {
+            collect_free_vars(fun, binders, free_vars);
+            collect_free_vars(arg, binders, free_vars);
+        },
+        Term::RecordIntro(fields) => {
+            for (i, (_, term)) in fields.iter().enumerate() {
+                collect_free_vars(term, binders + i as u32, free_vars);
+            }
+        },
+        Term::RecordElim(record, _) => collect_free_vars(record, binders, free_vars),
+        Term::ArrayIntro(elems) => {
+            for elem in elems {
+                collect_free_vars(elem, binders, free_vars);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mltt_core::{AppMode, DocString, UniverseLevel};
+
+    use super::*;
+
+    #[test]
+    fn erase_types() {
+        // (fun A a => a) : Fun (A : Type) -> A -> A
+        let term = Rc::from(syntax::Term::ann(
+            syntax::Term::FunIntro(
+                AppMode::Explicit,
+                Some("A".to_owned()),
+                Rc::from(syntax::Term::FunIntro(
+                    AppMode::Explicit,
+                    Some("a".to_owned()),
+                    Rc::from(syntax::Term::var(0)),
+                )),
+            ),
+            syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("A".to_owned()),
+                Rc::from(syntax::Term::universe(0)),
+                Rc::from(syntax::Term::FunType(
+                    DocString::from(""),
+                    AppMode::Explicit,
+                    None,
+                    Rc::from(syntax::Term::var(0)),
+                    Rc::from(syntax::Term::var(1)),
+                )),
+            ),
+        ));
+
+        let erased = erase_term(&prim::Env::new(), &meta::Env::new(), var::Size(0), &term).unwrap();
+
+        let expected = Term::FunIntro(Rc::from(Term::FunIntro(Rc::from(Term::Var(var::Index(0))))));
+        assert_eq!(erased, Rc::from(expected));
+    }
+
+    #[test]
+    fn erase_declarations() {
+        let label = Label("Unit".to_owned());
+        let module = syntax::Module {
+            items: vec![
+                syntax::Item::Declaration(
+                    DocString::from(""),
+                    label.clone(),
+                    Rc::from(syntax::Term::Universe(UniverseLevel(1))),
+                ),
+                syntax::Item::Definition(
+                    DocString::from(""),
+                    label.clone(),
+                    Rc::from(syntax::Term::RecordType(Vec::new())),
+                ),
+            ],
+        };
+
+        let erased =
+            erase_module(&prim::Env::new(), &meta::Env::new(), var::Size(0), &module).unwrap();
+
+        assert_eq!(
+            erased.definitions,
+            vec![Definition {
+                label,
+                term: Rc::from(Term::Erased),
+            }],
+        );
+    }
+}
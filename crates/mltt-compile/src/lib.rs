@@ -0,0 +1,19 @@
+//! Compilation passes for the MLTT language's core syntax.
+//!
+//! This is groundwork for the code generation backends. Once a module has been
+//! elaborated and validated, it is lowered through the following passes:
+//!
+//! - type erasure, into the erased core (see the [`erased`] module)
+//! - dead code elimination (see the [`dead_code`] module)
+//! - closure conversion (see the [`closure`] module)
+//! - lambda lifting (see the [`lifted`] module)
+//!
+//! Each pass produces a new intermediate representation, so that backends can
+//! pick up from whichever one suits them best.
+
+#![warn(rust_2018_idioms)]
+
+pub mod closure;
+pub mod dead_code;
+pub mod erased;
+pub mod lifted;
@@ -0,0 +1,230 @@
+//! Lambda lifting.
+//!
+//! After closure conversion, the body of each closure only refers to the
+//! variables that it captures, its own parameter, and globals. This means
+//! that it can be lifted out into a top-level function, which takes the
+//! captured variables as extra parameters before its own. What remains in
+//! place of the closure is the construction of the environment that is passed
+//! to the function when it is applied.
+
+use mltt_core::literal::{LiteralIntro, LiteralPattern};
+use mltt_core::{prim, var, Label, Rc};
+use std::fmt;
+
+use crate::closure;
+
+/// The position of a function in a program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FunctionId(pub u32);
+
+impl fmt::Display for FunctionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fun{}", self.0)
+    }
+}
+
+/// A lambda lifted program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// The functions that were lifted out of the definitions. Functions come
+    /// after any functions that they refer to.
+    pub functions: Vec<Function>,
+    /// The definitions in the program, referred to using `Term::Global`.
+    pub definitions: Vec<Definition>,
+}
+
+impl Program {
+    /// Lookup a function in the program.
+    pub fn lookup_function(&self, id: FunctionId) -> Option<&Function> {
+        self.functions.get(id.0 as usize)
+    }
+}
+
+/// A top-level function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// The number of captured variables that the function expects, in
+    /// addition to its parameter.
+    pub captures: u32,
+    /// The body of the function, in the scope of the captured variables,
+    /// followed by the parameter.
+    pub body: Rc<Term>,
+}
+
+/// A lambda lifted definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub label: Label,
+    pub term: Rc<Term>,
+}
+
+/// Lambda lifted terms.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// Local variables of the enclosing function
+    Var(var::Index),
+    /// Top-level definitions
+    Global(var::Level),
+    /// Primitives
+    Prim(prim::Name),
+    /// Terms that were only needed for type checking
+    Erased,
+
+    /// Let bindings
+    Let(Vec<Definition>, Rc<Term>),
+
+    /// Literal introductions
+    LiteralIntro(LiteralIntro),
+    /// Eliminate a literal (case split on literals)
+    LiteralElim(Rc<Term>, Rc<[(LiteralPattern, Rc<Term>)]>, Rc<Term>),
+
+    /// Construct a closure from a top-level function and the local variables
+    /// that it captures
+    MakeClosure(FunctionId, Vec<var::Index>),
+    /// Eliminate a function (application)
+    FunElim(Rc<Term>, Rc<Term>),
+
+    /// Introduce a record
+    ///
+    /// Later fields are in the scope of the earlier fields.
+    RecordIntro(Vec<(Label, Rc<Term>)>),
+    /// Eliminate a record (projection)
+    RecordElim(Rc<Term>, Label),
+
+    /// Introduce an array
+    ArrayIntro(Vec<Rc<Term>>),
+}
+
+/// Lift the closures in a closure converted module out into top-level
+/// functions.
+pub fn lift_module(module: &closure::Module) -> Program {
+    let mut functions = Vec::new();
+    let definitions = (module.definitions.iter())
+        .map(|definition| Definition {
+            label: definition.label.clone(),
+            term: lift_term(&mut functions, &definition.term),
+        })
+        .collect();
+
+    Program {
+        functions,
+        definitions,
+    }
+}
+
+/// Lift the closures in a term, adding them to the list of functions.
+fn lift_term(functions: &mut Vec<Function>, term: &Rc<closure::Term>) -> Rc<Term> {
+    let lift_definitions = |functions: &mut Vec<Function>, definitions: &[closure::Definition]| {
+        (definitions.iter())
+            .map(|definition| Definition {
+                label: definition.label.clone(),
+                term: lift_term(functions, &definition.term),
+            })
+            .collect()
+    };
+
+    match term.as_ref() {
+        closure::Term::Var(var_index) => Rc::from(Term::Var(*var_index)),
+        closure::Term::Global(var_level) => Rc::from(Term::Global(*var_level)),
+        closure::Term::Prim(prim_name) => Rc::from(Term::Prim(prim_name.clone())),
+        closure::Term::Erased => Rc::from(Term::Erased),
+
+        closure::Term::Let(definitions, body) => {
+            let definitions = lift_definitions(functions, definitions);
+            let body = lift_term(functions, body);
+            Rc::from(Term::Let(definitions, body))
+        },
+
+        closure::Term::LiteralIntro(literal_intro) => {
+            Rc::from(Term::LiteralIntro(literal_intro.clone()))
+        },
+        closure::Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee = lift_term(functions, scrutinee);
+            let clauses = (clauses.iter())
+                .map(|(literal_intro, body)| (literal_intro.clone(), lift_term(functions, body)))
+                .collect::<Vec<_>>();
+            let default_body = lift_term(functions, default_body);
+            Rc::from(Term::LiteralElim(
+                scrutinee,
+                Rc::from(clauses),
+                default_body,
+            ))
+        },
+
+        closure::Term::Closure(captures, body) => {
+            let body = lift_term(functions, body);
+            let id = FunctionId(functions.len() as u32);
+            functions.push(Function {
+                captures: captures.len() as u32,
+                body,
+            });
+            Rc::from(Term::MakeClosure(id, captures.clone()))
+        },
+        closure::Term::FunElim(fun, arg) => {
+            let fun = lift_term(functions, fun);
+            let arg = lift_term(functions, arg);
+            Rc::from(Term::FunElim(fun, arg))
+        },
+
+        closure::Term::RecordIntro(fields) => {
+            let fields = (fields.iter())
+                .map(|(label, term)| (label.clone(), lift_term(functions, term)))
+                .collect();
+            Rc::from(Term::RecordIntro(fields))
+        },
+        closure::Term::RecordElim(record, label) => {
+            let record = lift_term(functions, record);
+            Rc::from(Term::RecordElim(record, label.clone()))
+        },
+
+        closure::Term::ArrayIntro(elems) => {
+            let elems = elems
+                .iter()
+                .map(|elem| lift_term(functions, elem))
+                .collect();
+            Rc::from(Term::ArrayIntro(elems))
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lift_nested_closures() {
+        // const = fun a => fun b => a
+        let module = closure::Module {
+            definitions: vec![closure::Definition {
+                label: Label("const".to_owned()),
+                term: Rc::from(closure::Term::Closure(
+                    Vec::new(),
+                    Rc::from(closure::Term::Closure(
+                        vec![var::Index(0)],
+                        Rc::from(closure::Term::Var(var::Index(1))),
+                    )),
+                )),
+            }],
+        };
+
+        let program = lift_module(&module);
+
+        assert_eq!(
+            program.functions,
+            vec![
+                Function {
+                    captures: 1,
+                    body: Rc::from(Term::Var(var::Index(1))),
+                },
+                Function {
+                    captures: 0,
+                    body: Rc::from(Term::MakeClosure(FunctionId(0), vec![var::Index(0)])),
+                },
+            ],
+        );
+        assert_eq!(
+            program.definitions[0].term,
+            Rc::from(Term::MakeClosure(FunctionId(1), Vec::new())),
+        );
+    }
+}
@@ -17,8 +17,18 @@ use mltt_span::{ByteIndex, ByteSize, FileId, FileSpan};
 use std::borrow::Cow;
 use std::fmt;
 
+pub mod literal;
 pub mod pretty;
 
+/// A module, corresponding to a single source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module<'file> {
+    /// Attributes that apply to the whole module, eg. `#![options(no_prelude)]`.
+    pub attributes: Vec<Attribute<'file>>,
+    /// The items in the module.
+    pub items: Vec<Item<'file>>,
+}
+
 /// Top-level items in a module.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Item<'file> {
@@ -26,13 +36,25 @@ pub enum Item<'file> {
     Declaration(Declaration<'file>),
     /// Term definitions.
     Definition(Definition<'file>),
+    /// Sections of items that share a list of parameters.
+    Section(Section<'file>),
+    /// Namespaces of items that are accessed using qualified names.
+    Namespace(Namespace<'file>),
+    /// Bring the items of a namespace into scope.
+    Open(Open<'file>),
+    /// Names that stand for a pattern.
+    PatternSynonym(PatternSynonym<'file>),
 }
 
 impl<'file> Item<'file> {
     /// Returns `true` if the item is a definition.
     pub fn is_definition(&self) -> bool {
         match self {
-            Item::Declaration(_) => false,
+            Item::Declaration(_)
+            | Item::Section(_)
+            | Item::Namespace(_)
+            | Item::Open(_)
+            | Item::PatternSynonym(_) => false,
             Item::Definition(_) => true,
         }
     }
@@ -41,6 +63,108 @@ impl<'file> Item<'file> {
         match self {
             Item::Declaration(declaration) => declaration.span(),
             Item::Definition(definition) => definition.span(),
+            Item::Section(section) => section.span,
+            Item::Namespace(namespace) => namespace.span,
+            Item::Open(open) => open.span,
+            Item::PatternSynonym(synonym) => synonym.span,
+        }
+    }
+
+    /// The span of the whole item, including its doc comments.
+    pub fn full_span(&self) -> FileSpan {
+        match self {
+            Item::Declaration(declaration) => declaration.full_span(),
+            Item::Definition(definition) => definition.full_span(),
+            Item::Section(section) => section.span,
+            Item::Namespace(namespace) => namespace.span,
+            Item::Open(open) => open.span,
+            Item::PatternSynonym(synonym) => synonym.full_span(),
+        }
+    }
+}
+
+/// Sections of items that share a list of parameters.
+///
+/// The items are abstracted over the parameters when the section is closed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section<'file> {
+    pub span: FileSpan,
+    pub params: Vec<TypeParam<'file>>,
+    pub items: Vec<Item<'file>>,
+}
+
+/// Namespaces of items.
+///
+/// The items can be referred to from outside of the namespace using qualified
+/// names, for example `Foo.item`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Namespace<'file> {
+    pub span: FileSpan,
+    pub name: SpannedString<'file>,
+    pub items: Vec<Item<'file>>,
+}
+
+/// Bring the items of a namespace into scope, allowing them to be referred to
+/// without qualification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Open<'file> {
+    pub span: FileSpan,
+    /// Whether the items are re-exported from the enclosing namespace, eg.
+    /// `public open Foo;`.
+    pub is_public: bool,
+    pub name: SpannedString<'file>,
+    pub imports: Imports<'file>,
+}
+
+/// Names that stand for a pattern, eg. `pattern newline = '\n';`.
+///
+/// These can be used in both patterns and expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternSynonym<'file> {
+    pub span: FileSpan,
+    pub docs: Vec<SpannedString<'file>>,
+    pub label: SpannedString<'file>,
+    pub pattern: Pattern<'file>,
+}
+
+impl<'file> PatternSynonym<'file> {
+    /// The span of the whole pattern synonym, including its doc comments.
+    pub fn full_span(&self) -> FileSpan {
+        docs_span(&self.docs, self.span)
+    }
+}
+
+/// The items that are brought into scope when opening a namespace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Imports<'file> {
+    /// All of the items in the namespace, eg. `open Foo;`.
+    All,
+    /// Only the listed items, eg. `open Foo (bar, baz);`.
+    Only(Vec<SpannedString<'file>>),
+    /// All of the items except for the listed ones, eg. `open Foo hiding (bar);`.
+    Hiding(Vec<SpannedString<'file>>),
+    /// None of the items, but the namespace can be referred to using a new
+    /// name, eg. `open Foo as F;`.
+    Alias(SpannedString<'file>),
+}
+
+impl<'file> Imports<'file> {
+    /// The names of the items that are listed explicitly.
+    pub fn item_names(&self) -> &[SpannedString<'file>] {
+        match self {
+            Imports::All | Imports::Alias(_) => &[],
+            Imports::Only(names) | Imports::Hiding(names) => names,
+        }
+    }
+
+    /// Returns `true` if the item with the given name should be brought into
+    /// scope.
+    pub fn includes(&self, name: &str) -> bool {
+        match self {
+            Imports::All => true,
+            Imports::Only(names) => names.iter().any(|n| n.slice == name),
+            Imports::Hiding(names) => names.iter().all(|n| n.slice != name),
+            Imports::Alias(_) => false,
         }
     }
 }
@@ -49,6 +173,7 @@ impl<'file> Item<'file> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration<'file> {
     pub docs: Vec<SpannedString<'file>>,
+    pub attributes: Vec<Attribute<'file>>,
     pub label: SpannedString<'file>,
     pub body_ty: Term<'file>,
 }
@@ -57,21 +182,68 @@ impl<'file> Declaration<'file> {
     pub fn span(&self) -> FileSpan {
         FileSpan::merge(self.label.span(), self.body_ty.span())
     }
+
+    /// The span of the whole declaration, including its doc comments and
+    /// attributes.
+    pub fn full_span(&self) -> FileSpan {
+        docs_span(&self.docs, attributes_span(&self.attributes, self.span()))
+    }
 }
 
 /// Term definitions.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Definition<'file> {
     pub docs: Vec<SpannedString<'file>>,
+    pub attributes: Vec<Attribute<'file>>,
     pub label: SpannedString<'file>,
     pub params: Vec<IntroParam<'file>>,
     pub body_ty: Option<Term<'file>>,
     pub body: Term<'file>,
+    /// A proof that the parameter of the definition is accessible, allowing
+    /// the body to call the definition on the elements below it.
+    pub termination_by: Option<Term<'file>>,
 }
 
 impl<'file> Definition<'file> {
     pub fn span(&self) -> FileSpan {
-        FileSpan::merge(self.label.span(), self.body.span())
+        let end = self.termination_by.as_ref().unwrap_or(&self.body);
+        FileSpan::merge(self.label.span(), end.span())
+    }
+
+    /// The span of the whole definition, including its doc comments and
+    /// attributes.
+    pub fn full_span(&self) -> FileSpan {
+        docs_span(&self.docs, attributes_span(&self.attributes, self.span()))
+    }
+}
+
+/// Extend a span to cover the doc comments that precede it.
+fn docs_span(docs: &[SpannedString<'_>], span: FileSpan) -> FileSpan {
+    let doc_spans = docs.iter().map(SpannedString::span);
+    FileSpan::cover(doc_spans.chain(Some(span))).unwrap_or(span)
+}
+
+/// Extend a span to cover the attributes that precede it.
+fn attributes_span(attributes: &[Attribute<'_>], span: FileSpan) -> FileSpan {
+    let attribute_spans = attributes.iter().map(|attribute| attribute.span);
+    FileSpan::cover(attribute_spans.chain(Some(span))).unwrap_or(span)
+}
+
+/// Attributes that can be attached to items, eg. `#[inline]` or
+/// `#[options(type_in_type)]`.
+///
+/// The parser accepts any attribute name - it is up to the elaborator to decide
+/// which attributes are understood.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'file> {
+    pub span: FileSpan,
+    pub name: SpannedString<'file>,
+    pub args: Vec<SpannedString<'file>>,
+}
+
+impl<'file> fmt::Display for Attribute<'file> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_doc().group().pretty(1_000_000_000).fmt(f)
     }
 }
 
@@ -99,7 +271,7 @@ impl<'file> SpannedString<'file> {
         FileSpan::new(
             self.source,
             self.start,
-            self.start + ByteSize::from_str_len_utf8(&self.slice),
+            self.start + ByteSize::from_str_len_utf8(self.slice),
         )
     }
 }
@@ -121,15 +293,15 @@ impl<'file> fmt::Display for SpannedString<'file> {
     }
 }
 
-impl<'file> Into<String> for SpannedString<'file> {
-    fn into(self) -> String {
-        self.to_string()
+impl<'file> From<SpannedString<'file>> for String {
+    fn from(val: SpannedString<'file>) -> Self {
+        val.to_string()
     }
 }
 
-impl<'a, 'file> Into<String> for &'a SpannedString<'file> {
-    fn into(self) -> String {
-        self.to_string()
+impl<'a, 'file> From<&'a SpannedString<'file>> for String {
+    fn from(val: &'a SpannedString<'file>) -> Self {
+        val.to_string()
     }
 }
 
@@ -140,9 +312,12 @@ pub enum Pattern<'file> {
     Var(SpannedString<'file>),
     /// Literal introductions.
     LiteralIntro(LiteralKind, SpannedString<'file>),
-    // TODO:
-    // /// Patterns with an explicit type annotation.
-    // Ann(Box<Pattern<'file>>, Box<Term<'file>>),
+    /// Inclusive ranges of literals, eg. `'a'..'z'`.
+    Range(Box<Pattern<'file>>, Box<Pattern<'file>>),
+    /// String prefixes, eg. `"http://" ++ _`.
+    Prefix(FileSpan, Box<Pattern<'file>>),
+    /// Patterns with an explicit type annotation.
+    Ann(FileSpan, Box<Pattern<'file>>, Box<Term<'file>>),
 }
 
 impl<'file> Pattern<'file> {
@@ -150,6 +325,9 @@ impl<'file> Pattern<'file> {
         match self {
             Pattern::Var(name) => name.span(),
             Pattern::LiteralIntro(_, literal) => literal.span(),
+            Pattern::Range(start, end) => FileSpan::merge(start.span(), end.span()),
+            Pattern::Prefix(span, _) => *span,
+            Pattern::Ann(span, _, _) => *span,
         }
     }
 }
@@ -185,22 +363,52 @@ impl LiteralKind {
     }
 }
 
-/// A group of parameters to be used in a function type.
+/// A group of parameters to be used in a function type, along with the doc
+/// comments that precede it.
 #[derive(Debug, Clone, PartialEq)]
 pub enum TypeParam<'file> {
-    Explicit(FileSpan, Vec<SpannedString<'file>>, Term<'file>),
-    Implicit(FileSpan, Vec<SpannedString<'file>>, Option<Term<'file>>),
-    Instance(FileSpan, SpannedString<'file>, Term<'file>),
+    Explicit(
+        FileSpan,
+        Vec<SpannedString<'file>>,
+        Vec<SpannedString<'file>>,
+        Term<'file>,
+    ),
+    Implicit(
+        FileSpan,
+        Vec<SpannedString<'file>>,
+        Vec<SpannedString<'file>>,
+        Option<Term<'file>>,
+    ),
+    Instance(
+        FileSpan,
+        Vec<SpannedString<'file>>,
+        SpannedString<'file>,
+        Term<'file>,
+    ),
 }
 
 impl<'file> TypeParam<'file> {
     pub fn span(&self) -> FileSpan {
         match self {
-            TypeParam::Explicit(span, _, _)
-            | TypeParam::Implicit(span, _, _)
-            | TypeParam::Instance(span, _, _) => *span,
+            TypeParam::Explicit(span, _, _, _)
+            | TypeParam::Implicit(span, _, _, _)
+            | TypeParam::Instance(span, _, _, _) => *span,
+        }
+    }
+
+    /// The doc comments that precede the parameter group.
+    pub fn docs(&self) -> &[SpannedString<'file>] {
+        match self {
+            TypeParam::Explicit(_, docs, _, _)
+            | TypeParam::Implicit(_, docs, _, _)
+            | TypeParam::Instance(_, docs, _, _) => docs,
         }
     }
+
+    /// The span of the whole parameter group, including its doc comments.
+    pub fn full_span(&self) -> FileSpan {
+        docs_span(self.docs(), self.span())
+    }
 }
 
 impl<'file> fmt::Display for TypeParam<'file> {
@@ -252,7 +460,7 @@ impl<'file> Arg<'file> {
         match self {
             Arg::Explicit(term) => Cow::Borrowed(term),
             Arg::Implicit(_, label, term) | Arg::Instance(_, label, term) => match term {
-                None => Cow::Owned(Term::Var(label.clone())),
+                None => Cow::Owned(Term::Var(*label)),
                 Some(term) => Cow::Borrowed(term),
             },
         }
@@ -303,7 +511,7 @@ impl<'file> RecordIntroField<'file> {
     ) {
         match self {
             RecordIntroField::Punned { label } => {
-                (label, &[][..], None, Cow::Owned(Term::Var(label.clone())))
+                (label, &[][..], None, Cow::Owned(Term::Var(*label)))
             },
             RecordIntroField::Explicit {
                 label,
@@ -339,6 +547,8 @@ pub enum Term<'file> {
     Prim(FileSpan, SpannedString<'file>),
     /// Holes
     Hole(FileSpan),
+    /// Placeholders, to be filled in by unification
+    Placeholder(FileSpan),
 
     /// A parenthesized term
     Parens(FileSpan, Box<Term<'file>>),
@@ -354,10 +564,21 @@ pub enum Term<'file> {
         Box<Term<'file>>,
     ),
     /// Case expressions
+    ///
+    /// Each clause has a pattern, an optional boolean guard, and a body.
     Case(
         FileSpan,
         Box<Term<'file>>,
-        Vec<(Pattern<'file>, Term<'file>)>,
+        Vec<(Pattern<'file>, Option<Term<'file>>, Term<'file>)>,
+    ),
+    /// With expressions
+    ///
+    /// These are like case expressions, but the scrutinee is also abstracted
+    /// out of the expected type of the clauses.
+    With(
+        FileSpan,
+        Box<Term<'file>>,
+        Vec<(Pattern<'file>, Option<Term<'file>>, Term<'file>)>,
     ),
 
     /// Literal introductions.
@@ -383,6 +604,24 @@ pub enum Term<'file> {
     /// Eliminate a record by projecting on it
     RecordElim(Box<Term<'file>>, SpannedString<'file>),
 
+    /// Dependent pair type
+    ///
+    /// Also known as a _sigma type_ or _dependent sum type_. This is sugar
+    /// for a dependent record type with `fst` and `snd` fields.
+    SigmaType(
+        FileSpan,
+        SpannedString<'file>,
+        Box<Term<'file>>,
+        Box<Term<'file>>,
+    ),
+    /// Introduce a pair
+    ///
+    /// This is sugar for a record introduction with `fst` and `snd` fields.
+    PairIntro(FileSpan, Box<Term<'file>>, Box<Term<'file>>),
+
+    /// Array introduction
+    ArrayIntro(FileSpan, Vec<Term<'file>>),
+
     /// Universe of types
     Universe(FileSpan, Option<SpannedString<'file>>),
 }
@@ -393,11 +632,13 @@ impl<'file> Term<'file> {
             Term::Var(name) => name.span(),
             Term::Prim(span, _) => *span,
             Term::Hole(span) => *span,
+            Term::Placeholder(span) => *span,
             Term::Parens(span, _) => *span,
             Term::Ann(term, term_ty) => FileSpan::merge(term.span(), term_ty.span()),
             Term::Let(span, _, _) => *span,
             Term::If(span, _, _, _) => *span,
             Term::Case(span, _, _) => *span,
+            Term::With(span, _, _) => *span,
             Term::LiteralIntro(_, literal) => literal.span(),
             Term::FunType(span, _, _) => *span,
             Term::FunArrowType(param_ty, body_ty) => {
@@ -414,6 +655,9 @@ impl<'file> Term<'file> {
             Term::RecordType(span, _) => *span,
             Term::RecordIntro(span, _) => *span,
             Term::RecordElim(record, label) => FileSpan::merge(record.span(), label.span()),
+            Term::SigmaType(span, _, _, _) => *span,
+            Term::PairIntro(span, _, _) => *span,
+            Term::ArrayIntro(span, _) => *span,
             Term::Universe(span, _) => *span,
         }
     }
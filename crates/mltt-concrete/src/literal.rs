@@ -0,0 +1,286 @@
+//! Decoding of string and character literals.
+//!
+//! The concrete syntax stores literals as they were written in the source
+//! code, including their quotes and escape sequences. These functions process
+//! the escape sequences, returning the value of the literal along with the
+//! locations of the escapes, so that errors can point at the offending escape.
+
+use language_reporting::{Diagnostic, Label};
+use mltt_span::{ByteSize, FileSpan};
+use std::str::CharIndices;
+
+use crate::SpannedString;
+
+/// An escape sequence in a string or character literal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Escape {
+    /// The span of the escape sequence, including the leading backslash.
+    pub span: FileSpan,
+    /// The character that the escape sequence stands for.
+    pub value: char,
+}
+
+/// The decoded value of a string literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedString {
+    /// The value of the literal, with all escapes processed.
+    pub value: String,
+    /// The escape sequences that were found in the literal.
+    pub escapes: Vec<Escape>,
+}
+
+/// The decoded value of a character literal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecodedChar {
+    /// The value of the literal, with any escape processed.
+    pub value: char,
+    /// The escape sequence used to write the character, if any.
+    pub escape: Option<Escape>,
+}
+
+/// Decode the source of a string literal, eg. `"hello\n"`.
+pub fn decode_string(src: &SpannedString<'_>) -> Result<DecodedString, Diagnostic<FileSpan>> {
+    let mut decoder = Decoder::new(src);
+    let mut value = String::new();
+    let mut escapes = Vec::new();
+
+    decoder.expect_quote('"')?;
+    loop {
+        match decoder.expect_char()? {
+            (_, '"') => break,
+            (start, '\\') => {
+                let escape = decoder.decode_escape(start)?;
+                value.push(escape.value);
+                escapes.push(escape);
+            },
+            (_, ch) => value.push(ch),
+        }
+    }
+    decoder.expect_end()?;
+
+    Ok(DecodedString { value, escapes })
+}
+
+/// Decode the source of a character literal, eg. `'a'` or `'\u{3bb}'`.
+pub fn decode_char(src: &SpannedString<'_>) -> Result<DecodedChar, Diagnostic<FileSpan>> {
+    let mut decoder = Decoder::new(src);
+
+    decoder.expect_quote('\'')?;
+    let decoded = match decoder.expect_char()? {
+        (_, '\'') => return Err(decoder.error(src.span(), "empty character literal")),
+        (start, '\\') => {
+            let escape = decoder.decode_escape(start)?;
+            DecodedChar {
+                value: escape.value,
+                escape: Some(escape),
+            }
+        },
+        (_, ch) => DecodedChar {
+            value: ch,
+            escape: None,
+        },
+    };
+    decoder.expect_quote('\'')?;
+    decoder.expect_end()?;
+
+    Ok(decoded)
+}
+
+/// A helper for decoding literals, keeping track of byte offsets.
+struct Decoder<'a, 'file> {
+    src: &'a SpannedString<'file>,
+    chars: CharIndices<'file>,
+}
+
+impl<'a, 'file> Decoder<'a, 'file> {
+    fn new(src: &'a SpannedString<'file>) -> Decoder<'a, 'file> {
+        Decoder {
+            src,
+            chars: src.slice.char_indices(),
+        }
+    }
+
+    /// The position of the next character in the source file.
+    fn offset(&self) -> usize {
+        self.src.slice.len() - self.chars.as_str().len()
+    }
+
+    /// The span from the given offset to the current offset.
+    fn span_from(&self, start: usize) -> FileSpan {
+        let start_index = self.src.start + ByteSize::from(start);
+        let end_index = self.src.start + ByteSize::from(self.offset());
+        FileSpan::new(self.src.source, start_index, end_index)
+    }
+
+    fn end_span(&self) -> FileSpan {
+        let end = self.src.start + ByteSize::from(self.src.slice.len());
+        FileSpan::new(self.src.source, end, end)
+    }
+
+    fn error(&self, span: FileSpan, message: &str) -> Diagnostic<FileSpan> {
+        Diagnostic::new_error(message.to_owned()).with_label(Label::new_primary(span))
+    }
+
+    fn expect_char(&mut self) -> Result<(usize, char), Diagnostic<FileSpan>> {
+        match self.chars.next() {
+            Some((offset, ch)) => Ok((offset, ch)),
+            None => Err(self.error(self.end_span(), "unterminated literal")),
+        }
+    }
+
+    fn expect_quote(&mut self, quote: char) -> Result<(), Diagnostic<FileSpan>> {
+        match self.expect_char()? {
+            (_, ch) if ch == quote => Ok(()),
+            (start, _) => {
+                let message = format!("expected `{}`", quote);
+                Err(self.error(self.span_from(start), &message))
+            },
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), Diagnostic<FileSpan>> {
+        match self.chars.next() {
+            None => Ok(()),
+            Some((start, _)) => {
+                let span = FileSpan::new(
+                    self.src.source,
+                    self.src.start + ByteSize::from(start),
+                    self.end_span().end(),
+                );
+                Err(self.error(span, "unexpected characters after the end of the literal"))
+            },
+        }
+    }
+
+    fn expect_hex_digit(&mut self, start: usize) -> Result<u32, Diagnostic<FileSpan>> {
+        match self.expect_char()? {
+            (_, ch) if ch.is_ascii_hexdigit() => Ok(ch.to_digit(16).unwrap()),
+            (_, _) => Err(self.error(self.span_from(start), "expected a hexadecimal digit")),
+        }
+    }
+
+    /// Decode an escape sequence, after the leading backslash at `start` has
+    /// been consumed.
+    fn decode_escape(&mut self, start: usize) -> Result<Escape, Diagnostic<FileSpan>> {
+        let value = match self.expect_char()? {
+            (_, '\'') => '\'',
+            (_, '\"') => '\"',
+            (_, '\\') => '\\',
+            (_, 'n') => '\n',
+            (_, 'r') => '\r',
+            (_, 't') => '\t',
+            (_, '0') => '\0',
+            (_, 'x') => {
+                let code = self.expect_hex_digit(start)? * 16 + self.expect_hex_digit(start)?;
+                if code > 0x7F {
+                    let span = self.span_from(start);
+                    return Err(self.error(span, "ASCII escapes must be at most `\\x7F`"));
+                }
+                std::char::from_u32(code).unwrap()
+            },
+            (_, 'u') => {
+                if self.expect_char()?.1 != '{' {
+                    return Err(self.error(self.span_from(start), "expected `{`"));
+                }
+
+                let mut code = 0_u32;
+                loop {
+                    match self.expect_char()? {
+                        (_, '}') => break,
+                        (_, '_') => continue,
+                        (_, ch) if ch.is_ascii_hexdigit() => {
+                            code = code
+                                .saturating_mul(16)
+                                .saturating_add(ch.to_digit(16).unwrap());
+                        },
+                        (_, _) => {
+                            let span = self.span_from(start);
+                            return Err(self.error(span, "invalid unicode escape"));
+                        },
+                    }
+                }
+
+                match std::char::from_u32(code) {
+                    Some(ch) => ch,
+                    None => {
+                        let span = self.span_from(start);
+                        return Err(self.error(span, "escape is not a valid unicode scalar value"));
+                    },
+                }
+            },
+            (_, ch) => {
+                let message = format!("unknown escape code `\\{}`", ch);
+                return Err(self.error(self.span_from(start), &message));
+            },
+        };
+
+        Ok(Escape {
+            span: self.span_from(start),
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mltt_span::{FileId, Files};
+
+    use super::*;
+
+    fn file_id() -> FileId {
+        Files::new().add("test", "")
+    }
+
+    #[test]
+    fn decode_string_escapes() {
+        let file_id = file_id();
+        let src = SpannedString::new(file_id, 10, r#""a\n\u{3bb}\x41λ""#);
+        let decoded = decode_string(&src).unwrap();
+
+        assert_eq!(decoded.value, "a\nλAλ");
+        assert_eq!(
+            decoded.escapes,
+            [
+                Escape {
+                    span: FileSpan::new(file_id, 12, 14),
+                    value: '\n',
+                },
+                Escape {
+                    span: FileSpan::new(file_id, 14, 21),
+                    value: 'λ',
+                },
+                Escape {
+                    span: FileSpan::new(file_id, 21, 25),
+                    value: 'A',
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn decode_char_escape() {
+        let file_id = file_id();
+        let decoded = decode_char(&SpannedString::new(file_id, 10, r"'\t'")).unwrap();
+
+        assert_eq!(decoded.value, '\t');
+        assert_eq!(
+            decoded.escape,
+            Some(Escape {
+                span: FileSpan::new(file_id, 11, 13),
+                value: '\t',
+            }),
+        );
+
+        let decoded = decode_char(&SpannedString::new(file_id, 10, "'λ'")).unwrap();
+        assert_eq!(decoded.escape, None);
+    }
+
+    #[test]
+    fn decode_invalid_escape() {
+        let file_id = file_id();
+        let src = SpannedString::new(file_id, 10, r#""ab\u{d800}""#);
+        let diagnostic = decode_string(&src).unwrap_err();
+
+        assert_eq!(diagnostic.labels[0].span, FileSpan::new(file_id, 13, 21));
+    }
+}
@@ -1,8 +1,8 @@
 use pretty::{BoxDoc, Doc};
 
 use crate::{
-    Arg, Declaration, Definition, IntroParam, Item, Pattern, RecordIntroField, RecordTypeField,
-    SpannedString, Term, TypeParam,
+    Arg, Attribute, Declaration, Definition, Imports, IntroParam, Item, Namespace, Open, Pattern,
+    PatternSynonym, RecordIntroField, RecordTypeField, Section, SpannedString, Term, TypeParam,
 };
 
 impl<'file> Item<'file> {
@@ -11,6 +11,111 @@ impl<'file> Item<'file> {
         match self {
             Item::Declaration(declaration) => declaration.to_doc(),
             Item::Definition(definition) => definition.to_doc(),
+            Item::Section(section) => section.to_doc(),
+            Item::Namespace(namespace) => namespace.to_doc(),
+            Item::Open(open) => open.to_doc(),
+            Item::PatternSynonym(synonym) => synonym.to_doc(),
+        }
+    }
+}
+
+impl<'file> Section<'file> {
+    /// Convert the section into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let params = self
+            .params
+            .iter()
+            .map(|param| Doc::space().append(param.to_doc()));
+        let items = self
+            .items
+            .iter()
+            .map(|item| Doc::newline().append(item.to_doc()));
+
+        Doc::nil()
+            .append("section")
+            .append(Doc::concat(params))
+            .append(Doc::concat(items).nest(4))
+            .append(Doc::newline())
+            .append("end")
+    }
+}
+
+impl<'file> Namespace<'file> {
+    /// Convert the namespace into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| Doc::newline().append(item.to_doc()));
+
+        Doc::nil()
+            .append("namespace")
+            .append(Doc::space())
+            .append(self.name.to_doc())
+            .append(Doc::concat(items).nest(4))
+            .append(Doc::newline())
+            .append("end")
+    }
+}
+
+impl<'file> Open<'file> {
+    /// Convert the open item into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        Doc::nil()
+            .append(if self.is_public { "public " } else { "" })
+            .append("open")
+            .append(Doc::space())
+            .append(self.name.to_doc())
+            .append(self.imports.to_doc())
+            .append(";")
+    }
+}
+
+impl<'file> PatternSynonym<'file> {
+    /// Convert the pattern synonym into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let docs = Doc::concat(
+            self.docs
+                .iter()
+                .map(|doc| doc.to_doc().append(Doc::newline())),
+        );
+
+        Doc::nil()
+            .append(docs)
+            .append("pattern")
+            .append(Doc::space())
+            .append(self.label.to_doc())
+            .append(Doc::space())
+            .append("=")
+            .append(Doc::space())
+            .append(self.pattern.to_doc())
+            .append(";")
+    }
+}
+
+impl<'file> Imports<'file> {
+    /// Convert the imports into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        fn names_to_doc<'a>(names: &'a [SpannedString<'_>]) -> Doc<'a, BoxDoc<'a, ()>> {
+            Doc::text("(")
+                .append(Doc::intersperse(
+                    names.iter().map(SpannedString::to_doc),
+                    Doc::text(",").append(Doc::space()),
+                ))
+                .append(")")
+        }
+
+        match self {
+            Imports::All => Doc::nil(),
+            Imports::Only(names) => Doc::space().append(names_to_doc(names)),
+            Imports::Hiding(names) => Doc::space()
+                .append("hiding")
+                .append(Doc::space())
+                .append(names_to_doc(names)),
+            Imports::Alias(alias) => Doc::space()
+                .append("as")
+                .append(Doc::space())
+                .append(alias.to_doc()),
         }
     }
 }
@@ -23,9 +128,15 @@ impl<'file> Declaration<'file> {
                 .iter()
                 .map(|doc| doc.to_doc().append(Doc::newline())),
         );
+        let attributes = Doc::concat(
+            self.attributes
+                .iter()
+                .map(|attribute| attribute.to_doc().append(Doc::newline())),
+        );
 
         Doc::nil()
             .append(docs)
+            .append(attributes)
             .append(self.label.to_doc())
             .append(Doc::space())
             .append(":")
@@ -43,6 +154,11 @@ impl<'file> Definition<'file> {
                 .iter()
                 .map(|doc| doc.to_doc().append(Doc::newline())),
         );
+        let attributes = Doc::concat(
+            self.attributes
+                .iter()
+                .map(|attribute| attribute.to_doc().append(Doc::newline())),
+        );
         let params = Doc::intersperse(self.params.iter().map(IntroParam::to_doc), Doc::space());
         let body_ty = self.body_ty.as_ref().map_or(Doc::nil(), |body_ty| {
             Doc::nil()
@@ -54,6 +170,7 @@ impl<'file> Definition<'file> {
 
         Doc::nil()
             .append(docs)
+            .append(attributes)
             .append(self.label.to_doc())
             .append(Doc::space())
             .append(params)
@@ -62,10 +179,39 @@ impl<'file> Definition<'file> {
             .append("=")
             .append(Doc::space())
             .append(self.body.to_doc())
+            .append(self.termination_by.as_ref().map_or(Doc::nil(), |proof| {
+                Doc::space()
+                    .append("termination-by")
+                    .append(Doc::space())
+                    .append(proof.to_doc())
+            }))
             .append(";")
     }
 }
 
+impl<'file> Attribute<'file> {
+    /// Convert the attribute into a pretty-printable document.
+    pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
+        let args = if self.args.is_empty() {
+            Doc::nil()
+        } else {
+            Doc::nil()
+                .append("(")
+                .append(Doc::intersperse(
+                    self.args.iter().map(SpannedString::to_doc),
+                    Doc::text(",").append(Doc::space()),
+                ))
+                .append(")")
+        };
+
+        Doc::nil()
+            .append("#[")
+            .append(self.name.to_doc())
+            .append(args)
+            .append("]")
+    }
+}
+
 impl<'file> SpannedString<'file> {
     /// Convert the string into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
@@ -79,6 +225,16 @@ impl<'file> Pattern<'file> {
         match self {
             Pattern::Var(name) => name.to_doc(),
             Pattern::LiteralIntro(_, literal) => literal.to_doc(),
+            Pattern::Range(start, end) => start.to_doc().append("..").append(end.to_doc()),
+            Pattern::Prefix(_, prefix) => prefix.to_doc().append(" ++ _"),
+            Pattern::Ann(_, pattern, ann) => Doc::nil()
+                .append("(")
+                .append(pattern.to_doc())
+                .append(Doc::space())
+                .append(":")
+                .group()
+                .append(Doc::space().append(ann.to_doc()).group().nest(4))
+                .append(")"),
         }
     }
 }
@@ -87,7 +243,7 @@ impl<'file> TypeParam<'file> {
     /// Convert the parameter into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
         match self {
-            TypeParam::Explicit(_, param_names, param_ty) => Doc::nil()
+            TypeParam::Explicit(_, _, param_names, param_ty) => Doc::nil()
                 .append("(")
                 .append(Doc::intersperse(
                     param_names.iter().map(SpannedString::to_doc),
@@ -98,14 +254,14 @@ impl<'file> TypeParam<'file> {
                 .append(Doc::space())
                 .append(param_ty.to_doc())
                 .append(")"),
-            TypeParam::Implicit(_, param_labels, None) => Doc::nil()
+            TypeParam::Implicit(_, _, param_labels, None) => Doc::nil()
                 .append("{")
                 .append(Doc::intersperse(
                     param_labels.iter().map(SpannedString::to_doc),
                     Doc::space(),
                 ))
                 .append("}"),
-            TypeParam::Implicit(_, param_labels, Some(term)) => Doc::nil()
+            TypeParam::Implicit(_, _, param_labels, Some(term)) => Doc::nil()
                 .append("{")
                 .append(Doc::intersperse(
                     param_labels.iter().map(SpannedString::to_doc),
@@ -116,7 +272,7 @@ impl<'file> TypeParam<'file> {
                 .append(Doc::space())
                 .append(term.to_doc())
                 .append("}"),
-            TypeParam::Instance(_, param_label, term) => Doc::nil()
+            TypeParam::Instance(_, _, param_label, term) => Doc::nil()
                 .append("{{")
                 .append(param_label.to_doc())
                 .append(Doc::space())
@@ -165,7 +321,7 @@ impl<'file> Arg<'file> {
     /// Convert the argument into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
         match self {
-            Arg::Explicit(term) => term.to_doc(),
+            Arg::Explicit(term) => term.to_prec_doc(Prec::Arg),
             Arg::Implicit(_, param_label, None) => {
                 Doc::text("{").append(param_label.to_doc()).append("}")
             },
@@ -240,36 +396,84 @@ impl<'file> RecordIntroField<'file> {
     }
 }
 
+/// The precedence of a term, mirroring the operator precedences used by the
+/// parser. Terms are wrapped in parentheses when they appear in a position
+/// that requires a higher precedence than their own.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Prec {
+    /// Terms that extend as far to the right as possible, eg. `fun x => x`,
+    /// `let .. in x`, `if .. then .. else x`, or `x : A`.
+    Term,
+    /// Function types, eg. `Fun (x : A) -> B` or `A -> B`.
+    Arrow,
+    /// Function applications and other terms that can not be used as an
+    /// argument, eg. `f x` or `case x { .. }`.
+    App,
+    /// Atomic terms that can be used as arguments, eg. `x`, `1`, or `r.l`.
+    Arg,
+}
+
 impl<'file> Term<'file> {
+    /// The precedence of the term when it is printed.
+    pub fn prec(&self) -> Prec {
+        match self {
+            Term::Var(_)
+            | Term::Hole(_)
+            | Term::Placeholder(_)
+            | Term::Parens(_, _)
+            | Term::LiteralIntro(_, _)
+            | Term::RecordElim(_, _)
+            | Term::PairIntro(_, _, _)
+            | Term::ArrayIntro(_, _)
+            | Term::Universe(_, _) => Prec::Arg,
+            Term::Prim(_, _)
+            | Term::Case(_, _, _)
+            | Term::With(_, _, _)
+            | Term::FunElim(_, _)
+            | Term::RecordType(_, _)
+            | Term::RecordIntro(_, _) => Prec::App,
+            Term::FunType(_, _, _) | Term::FunArrowType(_, _) | Term::SigmaType(_, _, _, _) => {
+                Prec::Arrow
+            },
+            Term::Ann(_, _)
+            | Term::Let(_, _, _)
+            | Term::If(_, _, _, _)
+            | Term::FunIntro(_, _, _) => Prec::Term,
+        }
+    }
+
     /// Convert the term into a pretty-printable document.
     pub fn to_doc(&self) -> Doc<'_, BoxDoc<'_, ()>> {
-        match self {
+        self.to_prec_doc(Prec::Term)
+    }
+
+    /// Convert the term into a pretty-printable document, wrapping it in
+    /// parentheses if its precedence is lower than `prec`.
+    pub fn to_prec_doc(&self, prec: Prec) -> Doc<'_, BoxDoc<'_, ()>> {
+        let doc = match self {
             Term::Var(name) => name.to_doc(),
             Term::Prim(_, name) => Doc::nil()
                 .append("primitive")
                 .append(Doc::space())
                 .append(name.to_doc()),
             Term::Hole(_) => Doc::text("?"),
+            Term::Placeholder(_) => Doc::text("_"),
             Term::Parens(_, term) => Doc::text("(").append(term.to_doc()).append(")"),
             Term::Ann(term, ann) => Doc::nil()
-                .append(term.to_doc())
+                .append(term.to_prec_doc(Prec::Arrow))
                 .append(Doc::space())
                 .append(":")
-                .append(Doc::space())
-                .append(ann.to_doc()),
+                .group()
+                .append(Doc::space().append(ann.to_doc()).group().nest(4)),
             Term::Let(_, items, body) => {
                 let items = Doc::intersperse(items.iter().map(Item::to_doc), Doc::newline());
 
                 Doc::nil()
                     .append("let")
-                    .append(Doc::space())
+                    .append(Doc::newline().append(items).nest(4))
                     .append(Doc::newline())
-                    .append(items.nest(4))
-                    .append(Doc::newline())
-                    .append(Doc::space())
                     .append("in")
-                    .append(Doc::space())
-                    .append(body.to_doc())
+                    .append(Doc::space().append(body.to_doc()).group().nest(4))
             },
             Term::If(_, condition, consequent, alternative) => Doc::nil()
                 .append("if")
@@ -277,35 +481,48 @@ impl<'file> Term<'file> {
                 .append(condition.to_doc())
                 .append(Doc::space())
                 .append("then")
-                .append(Doc::space())
-                .append(consequent.to_doc())
+                .group()
+                .append(Doc::space().append(consequent.to_doc()).nest(4))
                 .append(Doc::space())
                 .append("else")
-                .append(Doc::space())
-                .append(alternative.to_doc()),
-            Term::Case(_, scrutinee, clauses) => {
+                .append(Doc::space().append(alternative.to_doc()).nest(4))
+                .group(),
+            Term::Case(_, scrutinee, clauses) | Term::With(_, scrutinee, clauses) => {
+                let keyword = match self {
+                    Term::With(_, _, _) => "with",
+                    _ => "case",
+                };
                 let clauses = Doc::intersperse(
-                    clauses.iter().map(|(param, body)| {
+                    clauses.iter().map(|(param, guard, body)| {
+                        let guard = match guard {
+                            None => Doc::nil(),
+                            Some(guard) => Doc::space()
+                                .append("|")
+                                .append(Doc::space())
+                                .append(guard.to_doc()),
+                        };
+
                         Doc::nil()
                             .append(param.to_doc())
+                            .append(guard)
                             .append(Doc::space())
                             .append("=>")
-                            .append(Doc::space())
-                            .append(body.to_doc())
+                            .group()
+                            .append(Doc::space().append(body.to_doc()).group().nest(4))
                     }),
                     Doc::text(";").append(Doc::space()),
                 );
 
                 Doc::nil()
-                    .append("case")
+                    .append(keyword)
                     .append(Doc::space())
-                    .append(scrutinee.to_doc())
+                    .append(scrutinee.to_prec_doc(Prec::Arg))
                     .append(Doc::space())
                     .append("{")
-                    .append(Doc::space())
-                    .append(clauses)
+                    .append(Doc::space().append(clauses).nest(4))
                     .append(Doc::space())
                     .append("}")
+                    .group()
             },
             Term::LiteralIntro(_, literal) => literal.to_doc(),
             Term::FunType(_, params, body_ty) => Doc::nil()
@@ -317,14 +534,24 @@ impl<'file> Term<'file> {
                 ))
                 .append(Doc::space())
                 .append("->")
-                .append(Doc::space())
-                .append(body_ty.to_doc()),
+                .group()
+                .append(
+                    Doc::space()
+                        .append(body_ty.to_prec_doc(Prec::Arrow))
+                        .group()
+                        .nest(4),
+                ),
             Term::FunArrowType(param_ty, body_ty) => Doc::nil()
-                .append(param_ty.to_doc())
+                .append(param_ty.to_prec_doc(Prec::App))
                 .append(Doc::space())
                 .append("->")
-                .append(Doc::space())
-                .append(body_ty.to_doc()),
+                .group()
+                .append(
+                    Doc::space()
+                        .append(body_ty.to_prec_doc(Prec::Arrow))
+                        .group()
+                        .nest(4),
+                ),
             Term::FunIntro(_, param_names, body) => Doc::nil()
                 .append("fun")
                 .append(Doc::space())
@@ -334,51 +561,97 @@ impl<'file> Term<'file> {
                 ))
                 .append(Doc::space())
                 .append("=>")
-                .append(Doc::space())
-                .append(body.to_doc()),
+                .group()
+                .append(Doc::space().append(body.to_doc()).group().nest(4)),
             Term::FunElim(fun, args) => {
+                let fun = match fun.as_ref() {
+                    Term::FunElim(_, _) => fun.to_prec_doc(Prec::App),
+                    _ => fun.to_prec_doc(Prec::Arg),
+                };
                 let args = Doc::intersperse(args.iter().map(Arg::to_doc), Doc::space());
 
                 Doc::nil()
-                    .append(fun.to_doc())
-                    .append(Doc::space())
-                    .append(args)
+                    .append(fun)
+                    .append(Doc::space().append(args).nest(4))
+                    .group()
             },
             Term::RecordType(_, ty_fields) if ty_fields.is_empty() => Doc::text("Record {}"),
             Term::RecordType(_, ty_fields) => {
-                let ty_fields = Doc::intersperse(
-                    ty_fields.iter().map(RecordTypeField::to_doc),
-                    Doc::newline(),
-                );
+                let ty_fields =
+                    Doc::intersperse(ty_fields.iter().map(RecordTypeField::to_doc), Doc::space());
 
                 Doc::nil()
                     .append("Record")
                     .append(Doc::space())
                     .append("{")
-                    .append(Doc::newline())
-                    .append(ty_fields.nest(4))
-                    .append(Doc::newline())
+                    .append(Doc::space().append(ty_fields).nest(4))
+                    .append(Doc::space())
                     .append("}")
+                    .group()
             },
             Term::RecordIntro(_, intro_fields) if intro_fields.is_empty() => Doc::text("record {}"),
             Term::RecordIntro(_, intro_fields) => {
                 let intro_fields = Doc::intersperse(
                     intro_fields.iter().map(RecordIntroField::to_doc),
-                    Doc::newline(),
+                    Doc::space(),
                 );
 
                 Doc::nil()
                     .append("record")
                     .append(Doc::space())
                     .append("{")
-                    .append(Doc::newline())
-                    .append(intro_fields.nest(4))
-                    .append(Doc::newline())
+                    .append(Doc::space().append(intro_fields).nest(4))
+                    .append(Doc::space())
                     .append("}")
+                    .group()
             },
-            Term::RecordElim(record, label) => record.to_doc().append(".").append(label.to_doc()),
+            Term::RecordElim(record, label) => record
+                .to_prec_doc(Prec::Arg)
+                .append(".")
+                .append(label.to_doc()),
+            Term::SigmaType(_, name, param_ty, body_ty) => Doc::nil()
+                .append("Σ")
+                .append(Doc::space())
+                .append("(")
+                .append(name.to_doc())
+                .append(Doc::space())
+                .append(":")
+                .append(Doc::space())
+                .append(param_ty.to_doc())
+                .append(")")
+                .append(Doc::space())
+                .append("->")
+                .group()
+                .append(
+                    Doc::space()
+                        .append(body_ty.to_prec_doc(Prec::Arrow))
+                        .group()
+                        .nest(4),
+                ),
+            Term::PairIntro(_, fst, snd) => Doc::nil()
+                .append("(")
+                .append(fst.to_doc())
+                .append(",")
+                .append(Doc::space())
+                .append(snd.to_doc())
+                .append(")")
+                .group(),
+            Term::ArrayIntro(_, elems) => Doc::nil()
+                .append("[")
+                .append(Doc::intersperse(
+                    elems.iter().map(Term::to_doc),
+                    Doc::text(",").append(Doc::space()),
+                ))
+                .append("]")
+                .group(),
             Term::Universe(_, None) => Doc::text("Type"),
             Term::Universe(_, Some(level)) => Doc::text("Type^").append(level.to_doc()),
+        };
+
+        if self.prec() < prec {
+            Doc::text("(").append(doc).append(")")
+        } else {
+            doc
         }
     }
 }
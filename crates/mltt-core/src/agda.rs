@@ -0,0 +1,759 @@
+//! Exporting core modules to Agda syntax.
+//!
+//! This allows developments to be cross-validated in a more mature proof
+//! assistant. The translation is mostly direct, with the following caveats:
+//!
+//! - Integer literal types lose their widths: unsigned integers become `Nat`,
+//!   signed integers become `Int`, and floats become `Float`.
+//! - Literal eliminations become pattern matching lambdas.
+//! - Arrays become lists.
+//! - Agda records are nominal, so each distinct list of field labels is given
+//!   a universe polymorphic record declaration, parameterised by the types of
+//!   its fields. The empty record becomes `⊤`.
+//! - Metavariables become underscores, to be solved by Agda.
+//! - Primitives, range and prefix patterns, and projections of labels that
+//!   are shared by more than one record declaration, become holes that need
+//!   to be filled in by hand.
+
+use pretty::{BoxDoc, Doc};
+
+use crate::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use crate::pretty::{parens, with_prec, Env, Prec};
+use crate::{syntax, var, AppMode, Label};
+
+/// Pretty printed Agda source.
+pub type AgdaDoc = Doc<'static, BoxDoc<'static, ()>, ()>;
+
+/// Builtin modules that are imported qualified, to avoid clashing with the
+/// names defined in the exported module.
+const IMPORTS: &[(&str, &str)] = &[
+    ("Agda.Primitive", "Primitive"),
+    ("Agda.Builtin.Bool", "Builtin-Bool"),
+    ("Agda.Builtin.Char", "Builtin-Char"),
+    ("Agda.Builtin.Float", "Builtin-Float"),
+    ("Agda.Builtin.Int", "Builtin-Int"),
+    ("Agda.Builtin.List", "Builtin-List"),
+    ("Agda.Builtin.Nat", "Builtin-Nat"),
+    ("Agda.Builtin.String", "Builtin-String"),
+    ("Agda.Builtin.Unit", "Builtin-Unit"),
+];
+
+/// Names that can't be used as identifiers in Agda.
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "codata",
+    "coinductive",
+    "constructor",
+    "data",
+    "do",
+    "eta-equality",
+    "field",
+    "forall",
+    "hiding",
+    "import",
+    "in",
+    "inductive",
+    "infix",
+    "infixl",
+    "infixr",
+    "instance",
+    "let",
+    "macro",
+    "module",
+    "mutual",
+    "no-eta-equality",
+    "open",
+    "overlap",
+    "pattern",
+    "postulate",
+    "primitive",
+    "private",
+    "Prop",
+    "public",
+    "quote",
+    "quoteTerm",
+    "record",
+    "renaming",
+    "rewrite",
+    "Set",
+    "syntax",
+    "tactic",
+    "to",
+    "unquote",
+    "unquoteDecl",
+    "unquoteDef",
+    "using",
+    "variable",
+    "where",
+    "with",
+];
+
+/// Export a module to Agda, giving it the given module name. This should
+/// match the name of the file that it is written to.
+///
+/// The terms in the module should not refer to any variables that are
+/// defined outside of it.
+pub fn export_module(module_name: &str, module: &syntax::Module) -> AgdaDoc {
+    let mut exporter = Exporter {
+        records: Vec::new(),
+    };
+    let mut env = Env::empty();
+    let items = exporter.items_doc(&module.items, &mut env);
+
+    let imports = Doc::concat(IMPORTS.iter().map(|(module_name, alias)| {
+        Doc::text(format!("import {} as {}", module_name, alias)).append(Doc::newline())
+    }));
+    let records = Doc::concat(
+        (exporter.records.iter().enumerate())
+            .map(|(index, labels)| record_doc(index, labels).append(Doc::newline())),
+    );
+
+    Doc::nil()
+        .append(Doc::text(format!("module {} where", module_name)))
+        .append(Doc::newline())
+        .append(Doc::newline())
+        .append(imports)
+        .append(Doc::newline())
+        .append(records)
+        .append(items)
+}
+
+/// State that is collected while exporting a module.
+struct Exporter {
+    /// The field labels of the record declarations that are needed by the
+    /// module. Each record is named after its position in this list.
+    records: Vec<Vec<Label>>,
+}
+
+impl Exporter {
+    /// Get the position of the record declaration with the given field labels,
+    /// adding one if it does not exist yet.
+    fn record_index(&mut self, labels: Vec<Label>) -> usize {
+        match self.records.iter().position(|other| *other == labels) {
+            Some(index) => index,
+            None => {
+                self.records.push(labels);
+                self.records.len() - 1
+            },
+        }
+    }
+
+    fn items_doc(&mut self, items: &[syntax::Item], env: &mut Env) -> AgdaDoc {
+        let mut declared = None;
+
+        Doc::concat(items.iter().map(|item| {
+            let (doc, item_doc) = match item {
+                syntax::Item::Declaration(doc, label, term_ty) => {
+                    // Use the name that the definition will be given
+                    let name = env.clone().fresh_name(Some(&identifier(label)));
+                    let term_ty = self.term_doc(env, term_ty, Prec::Term);
+                    declared = Some(label);
+                    (&doc[..], declaration(name, term_ty))
+                },
+                syntax::Item::Definition(doc, label, term) => {
+                    let term = self.term_doc(env, term, Prec::Term);
+                    let name = env.fresh_name(Some(&identifier(label)));
+                    let item_doc = definition(name, term).append(Doc::newline());
+                    // The documentation was already added to the declaration
+                    match declared.take() {
+                        Some(declared) if declared == label => ("", item_doc),
+                        _ => (&doc[..], item_doc),
+                    }
+                },
+            };
+
+            Doc::concat(doc.lines().map(|line| {
+                Doc::text(format!("-- {}", line).trim_end().to_owned()).append(Doc::newline())
+            }))
+            .append(item_doc)
+            .append(Doc::newline())
+        }))
+    }
+
+    fn let_items_doc(&mut self, items: &[syntax::Item], env: &mut Env) -> AgdaDoc {
+        let mut docs = Vec::with_capacity(items.len());
+        for item in items {
+            docs.push(match item {
+                syntax::Item::Declaration(_, label, term_ty) => {
+                    let name = env.clone().fresh_name(Some(&identifier(label)));
+                    declaration(name, self.term_doc(env, term_ty, Prec::Term))
+                },
+                syntax::Item::Definition(_, label, term) => {
+                    let term = self.term_doc(env, term, Prec::Term);
+                    definition(env.fresh_name(Some(&identifier(label))), term)
+                },
+            });
+        }
+
+        let_block(docs)
+    }
+
+    fn term_doc(&mut self, env: &Env, term: &syntax::Term, prec: Prec) -> AgdaDoc {
+        let (term_prec, doc) = match term {
+            syntax::Term::Var(var_index) => {
+                (Prec::Arg, Doc::as_string(env.lookup_name(*var_index)))
+            },
+            syntax::Term::Meta(_) => (Prec::Arg, Doc::text("_")),
+            syntax::Term::Prim(prim_name) => (Prec::Arg, hole(format!("primitive {}", prim_name))),
+
+            syntax::Term::Ann(term, term_ty) => {
+                let term_ty = self.term_doc(env, term_ty, Prec::Term);
+                let term = self.term_doc(env, term, Prec::Term);
+                let name = env.clone().fresh_name(None);
+                let items = let_block(vec![
+                    declaration(name.clone(), term_ty),
+                    definition(name.clone(), term),
+                ]);
+                (Prec::Term, items.append(Doc::text(name)))
+            },
+            syntax::Term::Let(items, body) => {
+                let mut env = env.clone();
+                let items = self.let_items_doc(items, &mut env);
+                let body = self.term_doc(&env, body, Prec::Term);
+                (Prec::Term, items.append(body))
+            },
+
+            syntax::Term::LiteralType(literal_ty) => (Prec::Arg, literal_ty_doc(literal_ty)),
+            syntax::Term::LiteralIntro(literal_intro) => literal_intro_doc(literal_intro),
+            syntax::Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let mut clause_docs = (clauses.iter())
+                    .map(|(literal_pattern, body)| {
+                        let pattern = match literal_pattern {
+                            LiteralPattern::Intro(literal_intro) => {
+                                literal_intro_doc(literal_intro).1
+                            },
+                            literal_pattern => hole(format!("pattern {}", literal_pattern)),
+                        };
+                        clause(pattern, self.term_doc(env, body, Prec::Term))
+                    })
+                    .collect::<Vec<_>>();
+                // Agda reports the default clause as unreachable if the
+                // other clauses are exhaustive
+                let is_exhaustive = clauses.len() == 2
+                    && (clauses.iter()).all(|(literal_pattern, _)| {
+                        matches!(
+                            literal_pattern,
+                            LiteralPattern::Intro(LiteralIntro::Bool(_))
+                        )
+                    });
+                if !is_exhaustive {
+                    let default_body = self.term_doc(env, default_body, Prec::Term);
+                    clause_docs.push(clause(Doc::text("_"), default_body));
+                }
+
+                let fun = Doc::text("\\ {")
+                    .append(Doc::space())
+                    .append(Doc::intersperse(
+                        clause_docs,
+                        Doc::text(";").append(Doc::space()),
+                    ))
+                    .append(Doc::space())
+                    .append("}")
+                    .group();
+                let scrutinee = self.term_doc(env, scrutinee, Prec::Arg);
+                (
+                    Prec::App,
+                    parens(fun).append(Doc::space()).append(scrutinee),
+                )
+            },
+
+            syntax::Term::FunType(_, AppMode::Explicit, _, param_ty, body_ty)
+                if !body_ty.has_free_var(var::Index(0)) =>
+            {
+                let param_ty = self.term_doc(env, param_ty, Prec::App);
+                let mut env = env.clone();
+                env.fresh_name(None);
+                let body_ty = self.term_doc(&env, body_ty, Prec::Arrow);
+                (Prec::Arrow, arrow(param_ty, body_ty))
+            },
+            syntax::Term::FunType(_, app_mode, name_hint, param_ty, body_ty) => {
+                let param_ty = self.term_doc(env, param_ty, Prec::Term);
+                let mut env = env.clone();
+                let name = env.fresh_name(param_name_hint(app_mode, name_hint).as_deref());
+                let param = Doc::text(name)
+                    .append(Doc::space())
+                    .append(":")
+                    .append(Doc::space())
+                    .append(param_ty);
+                let body_ty = self.term_doc(&env, body_ty, Prec::Arrow);
+                (Prec::Arrow, arrow(with_app_mode(app_mode, param), body_ty))
+            },
+            syntax::Term::FunIntro(..) => {
+                let mut env = env.clone();
+                let mut params = Vec::new();
+                let mut body = term;
+                while let syntax::Term::FunIntro(app_mode, name_hint, next_body) = body {
+                    let name_hint = param_name_hint(app_mode, name_hint);
+                    let name = env.fresh_name(name_hint.as_deref());
+                    params.push(match app_mode {
+                        AppMode::Explicit => Doc::text(name),
+                        _ => with_app_mode(app_mode, Doc::text(name)),
+                    });
+                    body = next_body;
+                }
+
+                let doc = Doc::text("\\")
+                    .append(Doc::space())
+                    .append(Doc::intersperse(params, Doc::space()))
+                    .append(Doc::space())
+                    .append("->")
+                    .group()
+                    .append(
+                        Doc::space()
+                            .append(self.term_doc(&env, body, Prec::Term))
+                            .nest(4),
+                    );
+                (Prec::Term, doc)
+            },
+            syntax::Term::FunElim(..) => {
+                let mut args = Vec::new();
+                let mut head = term;
+                while let syntax::Term::FunElim(fun, app_mode, arg) = head {
+                    args.push((app_mode, arg));
+                    head = fun;
+                }
+
+                let mut doc = self.term_doc(env, head, Prec::Arg);
+                for (app_mode, arg) in args.into_iter().rev() {
+                    let arg = match app_mode {
+                        AppMode::Explicit => self.term_doc(env, arg, Prec::Arg),
+                        _ => with_app_mode(app_mode, self.term_doc(env, arg, Prec::Term)),
+                    };
+                    doc = doc.append(Doc::space().append(arg).nest(4));
+                }
+                (Prec::App, doc.group())
+            },
+
+            syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => {
+                (Prec::Arg, Doc::text("Builtin-Unit.⊤"))
+            },
+            syntax::Term::RecordType(ty_fields) => {
+                let labels = ty_fields.iter().map(|(_, label, _, _)| label.clone());
+                let index = self.record_index(labels.collect());
+
+                let mut env = env.clone();
+                let mut names = Vec::with_capacity(ty_fields.len());
+                let mut doc = Doc::text(record_name(index));
+                for (_, label, name_hint, ty) in ty_fields {
+                    let field_ty = match names.is_empty() {
+                        true => self.term_doc(&env, ty, Prec::Arg),
+                        false => parens(
+                            Doc::text("\\ ")
+                                .append(Doc::intersperse(names.clone(), Doc::space()))
+                                .append(" ->")
+                                .append(
+                                    Doc::space()
+                                        .append(self.term_doc(&env, ty, Prec::Term))
+                                        .nest(4),
+                                )
+                                .group(),
+                        ),
+                    };
+                    doc = doc.append(Doc::space().append(field_ty).nest(4));
+
+                    let name_hint = name_hint.clone().unwrap_or_else(|| identifier(label));
+                    names.push(Doc::text(env.fresh_name(Some(&name_hint))));
+                }
+                (Prec::App, doc.group())
+            },
+            syntax::Term::RecordIntro(intro_fields) if intro_fields.is_empty() => {
+                (Prec::Arg, Doc::text("Builtin-Unit.tt"))
+            },
+            syntax::Term::RecordIntro(intro_fields) => {
+                let labels = intro_fields.iter().map(|(label, _)| label.clone());
+                let index = self.record_index(labels.collect());
+                let constructor = Doc::text(format!("mk-{}", record_name(index)));
+
+                // Later fields can refer to earlier fields, which needs to be
+                // made explicit with a let binding
+                let is_dependent = (intro_fields.iter().enumerate()).any(|(i, (_, term))| {
+                    (0..i as u32).any(|index| term.has_free_var(var::Index(index)))
+                });
+
+                if is_dependent {
+                    let mut env = env.clone();
+                    let mut definitions = Vec::with_capacity(intro_fields.len());
+                    let mut names = Vec::with_capacity(intro_fields.len());
+                    for (label, term) in intro_fields {
+                        let term = self.term_doc(&env, term, Prec::Term);
+                        let name = env.fresh_name(Some(&identifier(label)));
+                        definitions.push(definition(name.clone(), term));
+                        names.push(Doc::text(name));
+                    }
+                    let body = constructor
+                        .append(Doc::space())
+                        .append(Doc::intersperse(names, Doc::space()));
+                    (Prec::Term, let_block(definitions).append(body))
+                } else {
+                    // None of the fields refer to earlier fields, so we can
+                    // print them in the current environment
+                    let mut doc = constructor;
+                    let mut env = env.clone();
+                    for (label, term) in intro_fields {
+                        let term = self.term_doc(&env, term, Prec::Arg);
+                        doc = doc.append(Doc::space().append(term).nest(4));
+                        env.fresh_name(Some(&identifier(label)));
+                    }
+                    (Prec::App, doc.group())
+                }
+            },
+            syntax::Term::RecordElim(record, label) => {
+                // Export the record first, in case it introduces the record
+                // declaration that the label belongs to
+                let record = self.term_doc(env, record, Prec::Arg);
+                let mut records =
+                    (self.records.iter().enumerate()).filter(|(_, labels)| labels.contains(label));
+                match (records.next(), records.next()) {
+                    (Some((index, _)), None) => {
+                        let projection = format!("{}.{}", record_name(index), identifier(label));
+                        (
+                            Prec::App,
+                            Doc::text(projection).append(Doc::space()).append(record),
+                        )
+                    },
+                    (_, _) => (Prec::Arg, hole(format!("projection {}", label))),
+                }
+            },
+
+            syntax::Term::ArrayType(elem_ty) => {
+                let elem_ty = self.term_doc(env, elem_ty, Prec::Arg);
+                let doc =
+                    Doc::text("Builtin-List.List").append(Doc::space().append(elem_ty).nest(4));
+                (Prec::App, doc.group())
+            },
+            syntax::Term::ArrayIntro(elems) if elems.is_empty() => {
+                (Prec::Arg, Doc::text("Builtin-List.[]"))
+            },
+            syntax::Term::ArrayIntro(elems) => {
+                let mut doc = Doc::nil();
+                for elem in elems {
+                    let elem = self.term_doc(env, elem, Prec::App);
+                    doc = doc
+                        .append(elem)
+                        .append(" Builtin-List.∷")
+                        .append(Doc::space());
+                }
+                (Prec::Arrow, doc.append("Builtin-List.[]").group())
+            },
+
+            syntax::Term::Universe(level) => match level.0 {
+                0 => (Prec::Arg, Doc::text("Set")),
+                level => (Prec::Arg, Doc::text(format!("Set{}", level))),
+            },
+        };
+
+        with_prec(term_prec, prec, doc)
+    }
+}
+
+/// Convert a label into a valid Agda identifier.
+///
+/// Underscores are used for mixfix operators and dots are used for qualified
+/// names in Agda, so they are replaced, and keywords are suffixed with a prime.
+fn identifier(label: &Label) -> String {
+    let name = label.0.replace('_', "‿").replace('.', "-");
+    match KEYWORDS.contains(&name.as_str()) {
+        true => format!("{}'", name),
+        false => name,
+    }
+}
+
+/// The name hint to use for a parameter, falling back to the label of
+/// implicit and instance parameters.
+fn param_name_hint(app_mode: &AppMode, name_hint: &Option<String>) -> Option<String> {
+    match (name_hint, app_mode) {
+        (Some(name_hint), _) if name_hint == "_" => None,
+        (Some(name_hint), _) => Some(identifier(&Label(name_hint.clone()))),
+        (None, AppMode::Explicit) => None,
+        (None, AppMode::Implicit(label)) | (None, AppMode::Instance(label)) => {
+            Some(identifier(label))
+        },
+    }
+}
+
+fn record_name(index: usize) -> String {
+    format!("Record{}", index)
+}
+
+/// The declaration of a record with the given field labels.
+///
+/// The type of each field is a parameter of the record, and is a function of
+/// the fields that come before it.
+fn record_doc(index: usize, labels: &[Label]) -> AgdaDoc {
+    let field_names = labels.iter().map(identifier).collect::<Vec<_>>();
+    let levels = (0..labels.len())
+        .map(|i| format!("level{}", i))
+        .collect::<Vec<_>>();
+
+    let params = (0..labels.len()).map(|i| {
+        let earlier_fields =
+            (0..i).map(|j| format!("({} : {}) -> ", field_names[j], field_ty(&field_names, j)));
+        format!(
+            "(Field{} : {}Set {})",
+            i,
+            earlier_fields.collect::<String>(),
+            levels[i],
+        )
+    });
+    let fields = (0..labels.len()).map(|i| {
+        Doc::text(format!(
+            "    {} : {}",
+            field_names[i],
+            field_ty(&field_names, i)
+        ))
+        .append(Doc::newline())
+    });
+
+    // Agda is sensitive to layout, so the head of the declaration is kept on a
+    // single line
+    Doc::text(format!(
+        "record {} {{{} : Primitive.Level}} {} : Set ({}) where",
+        record_name(index),
+        levels.join(" "),
+        params.collect::<Vec<_>>().join(" "),
+        levels.join(" Primitive.⊔ "),
+    ))
+    .append(Doc::newline())
+    .append(Doc::text(format!(
+        "  constructor mk-{}",
+        record_name(index)
+    )))
+    .append(Doc::newline())
+    .append("  field")
+    .append(Doc::newline())
+    .append(Doc::concat(fields))
+}
+
+/// The type of the field at the given position in a record declaration.
+fn field_ty(field_names: &[String], index: usize) -> String {
+    let mut ty = format!("Field{}", index);
+    for name in &field_names[..index] {
+        ty.push(' ');
+        ty.push_str(name);
+    }
+    ty
+}
+
+fn literal_ty_doc(literal_ty: &LiteralType) -> AgdaDoc {
+    Doc::text(match literal_ty {
+        LiteralType::String => "Builtin-String.String",
+        LiteralType::Char => "Builtin-Char.Char",
+        LiteralType::Bool => "Builtin-Bool.Bool",
+        LiteralType::U8 | LiteralType::U16 | LiteralType::U32 | LiteralType::U64 => {
+            "Builtin-Nat.Nat"
+        },
+        LiteralType::S8 | LiteralType::S16 | LiteralType::S32 | LiteralType::S64 => {
+            "Builtin-Int.Int"
+        },
+        LiteralType::F32 | LiteralType::F64 => "Builtin-Float.Float",
+    })
+}
+
+/// Convert a literal into an Agda expression, which can also be used as a
+/// pattern for everything except negative floats.
+fn literal_intro_doc(literal_intro: &LiteralIntro) -> (Prec, AgdaDoc) {
+    fn int(value: i64) -> (Prec, AgdaDoc) {
+        let doc = match value {
+            value if value >= 0 => format!("Builtin-Int.pos {}", value),
+            value => format!("Builtin-Int.negsuc {}", -(value + 1)),
+        };
+        (Prec::App, Doc::text(doc))
+    }
+
+    fn float(value: f64) -> (Prec, AgdaDoc) {
+        match value {
+            value if !value.is_finite() => (Prec::Arg, hole(format!("float {}", value))),
+            value if value.is_sign_negative() => {
+                let doc = format!("Builtin-Float.primFloatNegate {:?}", -value);
+                (Prec::App, Doc::text(doc))
+            },
+            value => (Prec::Arg, Doc::text(format!("{:?}", value))),
+        }
+    }
+
+    match literal_intro {
+        LiteralIntro::String(value) => (Prec::Arg, Doc::text(quote(value, '"'))),
+        LiteralIntro::Char(value) => (Prec::Arg, Doc::text(quote(&value.to_string(), '\''))),
+        LiteralIntro::Bool(true) => (Prec::Arg, Doc::text("Builtin-Bool.true")),
+        LiteralIntro::Bool(false) => (Prec::Arg, Doc::text("Builtin-Bool.false")),
+        LiteralIntro::U8(value) => (Prec::Arg, Doc::as_string(value)),
+        LiteralIntro::U16(value) => (Prec::Arg, Doc::as_string(value)),
+        LiteralIntro::U32(value) => (Prec::Arg, Doc::as_string(value)),
+        LiteralIntro::U64(value) => (Prec::Arg, Doc::as_string(value)),
+        LiteralIntro::S8(value) => int(i64::from(*value)),
+        LiteralIntro::S16(value) => int(i64::from(*value)),
+        LiteralIntro::S32(value) => int(i64::from(*value)),
+        LiteralIntro::S64(value) => int(*value),
+        LiteralIntro::F32(value) => float(f64::from(*value)),
+        LiteralIntro::F64(value) => float(*value),
+    }
+}
+
+/// Quote a string or character literal, using Agda's escape sequences.
+fn quote(value: &str, delimiter: char) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push(delimiter);
+    for ch in value.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch == delimiter => {
+                quoted.push('\\');
+                quoted.push(ch);
+            },
+            ch if ch.is_control() => quoted.push_str(&format!("\\{}\\&", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push(delimiter);
+    quoted
+}
+
+fn hole(contents: String) -> AgdaDoc {
+    Doc::text(format!("{{! {} !}}", contents))
+}
+
+fn with_app_mode(app_mode: &AppMode, doc: AgdaDoc) -> AgdaDoc {
+    match app_mode {
+        AppMode::Explicit => parens(doc),
+        AppMode::Implicit(_) => Doc::text("{").append(doc).append("}"),
+        AppMode::Instance(_) => Doc::text("{{").append(doc).append("}}"),
+    }
+}
+
+fn arrow(param_ty: AgdaDoc, body_ty: AgdaDoc) -> AgdaDoc {
+    param_ty
+        .append(Doc::space())
+        .append("->")
+        .group()
+        .append(Doc::space().append(body_ty).group().nest(4))
+}
+
+fn declaration(name: String, term_ty: AgdaDoc) -> AgdaDoc {
+    Doc::text(name)
+        .append(Doc::space())
+        .append(":")
+        .group()
+        .append(Doc::space().append(term_ty).group().nest(4))
+}
+
+fn definition(name: String, term: AgdaDoc) -> AgdaDoc {
+    Doc::text(name)
+        .append(Doc::space())
+        .append("=")
+        .group()
+        .append(Doc::space().append(term).group().nest(4))
+}
+
+fn clause(pattern: AgdaDoc, body: AgdaDoc) -> AgdaDoc {
+    pattern
+        .append(Doc::space())
+        .append("->")
+        .group()
+        .append(Doc::space().append(body).group().nest(4))
+}
+
+/// The start of a let expression, using explicit braces so that the layout
+/// of the items does not matter.
+fn let_block(items: Vec<AgdaDoc>) -> AgdaDoc {
+    Doc::text("let {")
+        .append(
+            Doc::space()
+                .append(Doc::intersperse(items, Doc::text(";").append(Doc::space())))
+                .nest(4),
+        )
+        .append(Doc::space())
+        .append("}")
+        .append(Doc::space())
+        .append("in")
+        .group()
+        .append(Doc::space())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DocString, Rc};
+
+    fn export_term(term: &syntax::Term) -> String {
+        let mut exporter = Exporter {
+            records: Vec::new(),
+        };
+        let doc = exporter.term_doc(&Env::empty(), term, Prec::Term);
+        format!("{}", doc.group().pretty(1_000_000_000))
+    }
+
+    #[test]
+    fn export_fun_type() {
+        // Fun {A : Type} -> A -> A
+        let term = syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Implicit(Label("A".to_owned())),
+            None,
+            Rc::from(syntax::Term::universe(0)),
+            Rc::from(syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                None,
+                Rc::from(syntax::Term::var(0)),
+                Rc::from(syntax::Term::var(1)),
+            )),
+        );
+
+        assert_eq!(export_term(&term), "{A : Set} -> A -> A");
+    }
+
+    #[test]
+    fn export_literals() {
+        let term = syntax::Term::FunElim(
+            Rc::from(syntax::Term::literal_intro(-3i32)),
+            AppMode::Explicit,
+            Rc::from(syntax::Term::literal_intro("a\"b\n")),
+        );
+
+        assert_eq!(export_term(&term), "(Builtin-Int.negsuc 2) \"a\\\"b\\n\"",);
+    }
+
+    #[test]
+    fn export_arrays() {
+        // [1, 2] : Array U32
+        let term = syntax::Term::ann(
+            syntax::Term::ArrayIntro(vec![
+                Rc::from(syntax::Term::literal_intro(1u32)),
+                Rc::from(syntax::Term::literal_intro(2u32)),
+            ]),
+            syntax::Term::ArrayType(Rc::from(syntax::Term::literal_ty(LiteralType::U32))),
+        );
+
+        assert!(export_term(&term).contains("1 Builtin-List.∷ 2 Builtin-List.∷ Builtin-List.[]",));
+        assert!(export_term(&term).contains("Builtin-List.List Builtin-Nat.Nat"));
+    }
+
+    #[test]
+    fn export_records() {
+        // (record { x = 1; y = x }).y
+        let term = syntax::Term::RecordElim(
+            Rc::from(syntax::Term::RecordIntro(vec![
+                (
+                    Label("x".to_owned()),
+                    Rc::from(syntax::Term::literal_intro(1u32)),
+                ),
+                (Label("y".to_owned()), Rc::from(syntax::Term::var(0))),
+            ])),
+            Label("y".to_owned()),
+        );
+
+        assert_eq!(
+            export_term(&term),
+            "Record0.y (let { x = 1; y = x } in mk-Record0 x y)",
+        );
+    }
+
+    #[test]
+    fn export_keywords() {
+        assert_eq!(identifier(&Label("record".to_owned())), "record'");
+        assert_eq!(identifier(&Label("a_b".to_owned())), "a‿b");
+    }
+}
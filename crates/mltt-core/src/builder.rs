@@ -0,0 +1,272 @@
+//! A builder for core terms that keeps variables in scope.
+//!
+//! Core terms refer to variables using debruijn indices, which are easy to get
+//! wrong when synthesizing terms by hand. Instead, binders are built with
+//! closures that are passed a [`Var`] for the bound variable:
+//!
+//! ```rust
+//! use mltt_core::builder::TermBuilder;
+//! use mltt_core::syntax::Term;
+//! use mltt_core::{AppMode, Rc};
+//!
+//! // fun A a => a : Fun (A : Type) -> A -> A
+//! let builder = TermBuilder::new();
+//! let id = builder.fun_intro(AppMode::Explicit, Some("A"), |builder, _| {
+//!     builder.fun_intro(AppMode::Explicit, Some("a"), |builder, a| builder.var(a))
+//! });
+//! let universe = Rc::from(Term::universe(0));
+//! let id_ty = builder.fun_type(AppMode::Explicit, Some("A"), universe, |builder, a| {
+//!     builder.fun_type(AppMode::Explicit, None, builder.var(a), |builder, _| {
+//!         builder.var(a)
+//!     })
+//! });
+//! ```
+//!
+//! The lifetimes of the builders and variables make sure that variables can
+//! only be used inside the body of their binder, so the following fails to
+//! compile:
+//!
+//! ```compile_fail
+//! use mltt_core::builder::TermBuilder;
+//! use mltt_core::AppMode;
+//!
+//! let builder = TermBuilder::new();
+//! let mut escaped = None;
+//! builder.fun_intro(AppMode::Explicit, Some("x"), |builder, x| {
+//!     escaped = Some(x);
+//!     builder.var(x)
+//! });
+//! ```
+//!
+//! Terms that are constructed directly with [`Term`] are not checked.
+
+use std::marker::PhantomData;
+
+use crate::syntax::{Item, Term};
+use crate::{var, AppMode, DocString, Label, Rc};
+
+/// The fields of a record type, in the order that they bind their variables.
+pub type RecordTypeFields = Vec<(DocString, Label, Option<String>, Rc<Term>)>;
+
+/// The fields of a record introduction, in the order that they bind their
+/// variables.
+pub type RecordIntroFields = Vec<(Label, Rc<Term>)>;
+
+/// Builds terms in a scope.
+///
+/// The `'scope` lifetime identifies the scope of the builder, and `'parent`
+/// the scope that encloses it. Variables of enclosing scopes can be used in
+/// nested scopes, but not the other way around.
+#[derive(Debug)]
+pub struct TermBuilder<'scope, 'parent: 'scope> {
+    /// The number of variables that are bound in the scope.
+    size: var::Size,
+    /// The scope lifetime is invariant, so that builders can't be converted
+    /// into the builders of nested scopes.
+    scope: PhantomData<fn(&'scope ()) -> &'scope ()>,
+    parent: PhantomData<&'parent ()>,
+}
+
+/// A variable that is bound in a scope.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Var<'scope> {
+    level: var::Level,
+    scope: PhantomData<&'scope ()>,
+}
+
+impl TermBuilder<'static, 'static> {
+    /// Create a builder for closed terms.
+    pub fn new() -> TermBuilder<'static, 'static> {
+        TermBuilder::with_size(var::Size(0))
+    }
+}
+
+impl Default for TermBuilder<'static, 'static> {
+    fn default() -> TermBuilder<'static, 'static> {
+        TermBuilder::new()
+    }
+}
+
+impl<'scope, 'parent> TermBuilder<'scope, 'parent> {
+    fn with_size(size: var::Size) -> TermBuilder<'scope, 'parent> {
+        TermBuilder {
+            size,
+            scope: PhantomData,
+            parent: PhantomData,
+        }
+    }
+
+    /// The number of variables that are bound in the scope.
+    pub fn size(&self) -> var::Size {
+        self.size
+    }
+
+    /// Build a term in the scope of a new variable.
+    fn bind<T>(&self, body: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> T) -> T {
+        let var = Var {
+            level: self.size.next_level(),
+            scope: PhantomData,
+        };
+        body(&TermBuilder::with_size(var::Size(self.size.0 + 1)), var)
+    }
+
+    /// Refer to a variable that is in scope.
+    pub fn var(&self, var: Var<'scope>) -> Rc<Term> {
+        Rc::from(Term::Var(self.size.index(var.level)))
+    }
+
+    /// Build a dependent function type.
+    pub fn fun_type(
+        &self,
+        app_mode: AppMode,
+        name: Option<&str>,
+        param_ty: Rc<Term>,
+        body_ty: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> Rc<Term>,
+    ) -> Rc<Term> {
+        let name = name.map(str::to_owned);
+        let body_ty = self.bind(body_ty);
+        Rc::from(Term::FunType(
+            DocString::from(""),
+            app_mode,
+            name,
+            param_ty,
+            body_ty,
+        ))
+    }
+
+    /// Build a function.
+    pub fn fun_intro(
+        &self,
+        app_mode: AppMode,
+        name: Option<&str>,
+        body: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> Rc<Term>,
+    ) -> Rc<Term> {
+        let name = name.map(str::to_owned);
+        Rc::from(Term::FunIntro(app_mode, name, self.bind(body)))
+    }
+
+    /// Build a let binding of a single definition.
+    pub fn let_defn(
+        &self,
+        label: &str,
+        term: Rc<Term>,
+        body: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> Rc<Term>,
+    ) -> Rc<Term> {
+        let item = Item::Definition(DocString::from(""), Label(label.to_owned()), term);
+        Rc::from(Term::Let(vec![item], self.bind(body)))
+    }
+
+    /// Build a record type from its fields.
+    pub fn record_type(&self, fields: RecordTypeFields) -> Rc<Term> {
+        Rc::from(Term::RecordType(fields))
+    }
+
+    /// Add a field to the front of the fields of a record type. The remaining
+    /// fields are in the scope of the field's variable.
+    pub fn record_type_field(
+        &self,
+        label: &str,
+        ty: Rc<Term>,
+        rest: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> RecordTypeFields,
+    ) -> RecordTypeFields {
+        let mut fields = self.bind(rest);
+        let field = (DocString::from(""), Label(label.to_owned()), None, ty);
+        fields.insert(0, field);
+        fields
+    }
+
+    /// Build a record from its fields.
+    pub fn record_intro(&self, fields: RecordIntroFields) -> Rc<Term> {
+        Rc::from(Term::RecordIntro(fields))
+    }
+
+    /// Add a field to the front of the fields of a record. The remaining
+    /// fields are in the scope of the field's variable.
+    pub fn record_intro_field(
+        &self,
+        label: &str,
+        term: Rc<Term>,
+        rest: impl for<'a> FnOnce(&TermBuilder<'a, 'scope>, Var<'a>) -> RecordIntroFields,
+    ) -> RecordIntroFields {
+        let mut fields = self.bind(rest);
+        fields.insert(0, (Label(label.to_owned()), term));
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn universe() -> Rc<Term> {
+        Rc::from(Term::universe(0))
+    }
+
+    #[test]
+    fn nested_functions() {
+        let builder = TermBuilder::new();
+        let term = builder.fun_intro(AppMode::Explicit, Some("x"), |builder, x| {
+            builder.fun_intro(AppMode::Explicit, Some("y"), |builder, y| {
+                Rc::from(Term::FunElim(
+                    builder.var(y),
+                    AppMode::Explicit,
+                    builder.var(x),
+                ))
+            })
+        });
+
+        let expected = Term::FunIntro(
+            AppMode::Explicit,
+            Some("x".to_owned()),
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                Some("y".to_owned()),
+                Rc::from(Term::FunElim(
+                    Rc::from(Term::var(0)),
+                    AppMode::Explicit,
+                    Rc::from(Term::var(1)),
+                )),
+            )),
+        );
+        assert_eq!(*term, expected);
+    }
+
+    #[test]
+    fn dependent_record_type() {
+        let builder = TermBuilder::new();
+        let fields = builder.record_type_field("A", universe(), |builder, a| {
+            builder.record_type_field("a", builder.var(a), |builder, _| {
+                builder.record_type_field("b", builder.var(a), |_, _| Vec::new())
+            })
+        });
+        let term = builder.record_type(fields);
+
+        match &*term {
+            Term::RecordType(fields) => {
+                let tys = fields.iter().map(|(_, _, _, ty)| ty.clone());
+                let expected = vec![universe(), Rc::from(Term::var(0)), Rc::from(Term::var(1))];
+                assert_eq!(tys.collect::<Vec<_>>(), expected);
+            },
+            _ => panic!("expected a record type"),
+        }
+    }
+
+    #[test]
+    fn let_defn() {
+        let builder = TermBuilder::new();
+        let term = builder.fun_intro(AppMode::Explicit, Some("x"), |builder, x| {
+            builder.let_defn("y", builder.var(x), |builder, _| builder.var(x))
+        });
+
+        match &*term {
+            Term::FunIntro(_, _, body) => match &**body {
+                Term::Let(items, body) => {
+                    assert_eq!(items.len(), 1);
+                    assert_eq!(**body, Term::var(1));
+                },
+                _ => panic!("expected a let binding"),
+            },
+            _ => panic!("expected a function"),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Dependency graphs between the definitions of a module.
+//!
+//! A definition depends on the earlier definitions that it refers to, either
+//! in its term or in its declared type. The graph can answer which definitions
+//! a definition uses, and which definitions use it, and is also used to find
+//! the definitions that are reachable from a set of roots when eliminating
+//! dead code.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::syntax::{Item, Module, Term};
+use crate::Label;
+
+/// A graph of the dependencies between the definitions of a module.
+///
+/// Definitions are identified by their position in the module, as labels can
+/// be shadowed by later definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyGraph {
+    /// The labels of the definitions, in the order that they were defined.
+    labels: Vec<Label>,
+    /// The positions of the definitions that each definition refers to
+    /// directly. These always come before the definition itself.
+    dependencies: Vec<BTreeSet<usize>>,
+}
+
+impl DependencyGraph {
+    /// Create a graph from the labels of some definitions, along with the
+    /// positions of the earlier definitions that each of them refer to.
+    pub fn new(labels: Vec<Label>, dependencies: Vec<BTreeSet<usize>>) -> DependencyGraph {
+        assert_eq!(labels.len(), dependencies.len());
+        DependencyGraph {
+            labels,
+            dependencies,
+        }
+    }
+
+    /// Build the dependency graph of an elaborated module.
+    ///
+    /// The dependencies of a forward declaration are added to the definition
+    /// that it declares.
+    pub fn from_module(module: &Module) -> DependencyGraph {
+        let mut labels = Vec::new();
+        let mut dependencies = Vec::new();
+        let mut declared = HashMap::<&Label, BTreeSet<usize>>::new();
+
+        for item in &module.items {
+            let position = labels.len();
+            let mut free_vars = BTreeSet::new();
+            match item {
+                Item::Declaration(_, label, term_ty) => {
+                    collect_free_vars(term_ty, 0, &mut free_vars);
+                    (declared.entry(label).or_default()).extend(positions(position, &free_vars));
+                },
+                Item::Definition(_, label, term) => {
+                    collect_free_vars(term, 0, &mut free_vars);
+                    let mut item_dependencies = declared.remove(label).unwrap_or_default();
+                    item_dependencies.extend(positions(position, &free_vars));
+                    labels.push(label.clone());
+                    dependencies.push(item_dependencies);
+                },
+            }
+        }
+
+        DependencyGraph::new(labels, dependencies)
+    }
+
+    /// The number of definitions in the graph.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Whether the graph has no definitions.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// The labels of the definitions, in the order that they were defined.
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    /// The position of the definition with the given label. This is the last
+    /// definition with the label, as any earlier ones are shadowed.
+    pub fn position(&self, label: &Label) -> Option<usize> {
+        self.labels.iter().rposition(|l| l == label)
+    }
+
+    /// The positions of the definitions that a definition refers to directly.
+    pub fn dependencies(&self, position: usize) -> &BTreeSet<usize> {
+        &self.dependencies[position]
+    }
+
+    /// The positions of the definitions that refer to a definition directly.
+    pub fn dependents(&self, position: usize) -> Vec<usize> {
+        (position + 1..self.len())
+            .filter(|other| self.dependencies[*other].contains(&position))
+            .collect()
+    }
+
+    /// Find the definitions that can be reached from the given roots,
+    /// returning whether each definition is reachable.
+    pub fn reachable(&self, roots: &[usize]) -> Vec<bool> {
+        let mut pending = roots.to_vec();
+        let mut reachable = vec![false; self.len()];
+
+        while let Some(position) = pending.pop() {
+            if !reachable[position] {
+                reachable[position] = true;
+                pending.extend(self.dependencies[position].iter().cloned());
+            }
+        }
+
+        reachable
+    }
+
+    /// Group the definitions into layers, where each definition only depends
+    /// on definitions in earlier layers. The definitions within a layer don't
+    /// depend on each other, so they could be checked in parallel.
+    pub fn layers(&self) -> Vec<Vec<usize>> {
+        let mut depths = Vec::<usize>::with_capacity(self.len());
+        let mut layers = Vec::<Vec<usize>>::new();
+
+        for (position, dependencies) in self.dependencies.iter().enumerate() {
+            let depth = (dependencies.iter())
+                .map(|dependency| depths[*dependency] + 1)
+                .max()
+                .unwrap_or(0);
+            depths.push(depth);
+            match layers.get_mut(depth) {
+                Some(layer) => layer.push(position),
+                None => layers.push(vec![position]),
+            }
+        }
+
+        layers
+    }
+
+    /// Render the graph in the Graphviz DOT format, with an edge from each
+    /// definition to the definitions that it depends on.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph dependencies {{").unwrap();
+        for (position, label) in self.labels.iter().enumerate() {
+            writeln!(dot, "    n{} [label={:?}];", position, label.0).unwrap();
+        }
+        for (position, dependencies) in self.dependencies.iter().enumerate() {
+            for dependency in dependencies {
+                writeln!(dot, "    n{} -> n{};", position, dependency).unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// Convert the free variables of an item at the given position into the
+/// positions of the definitions that they refer to. Variables that refer past
+/// the start of the module are defined outside of it, and are skipped.
+fn positions(position: usize, free_vars: &BTreeSet<u32>) -> impl Iterator<Item = usize> + '_ {
+    (free_vars.iter()).filter_map(move |var_index| {
+        (position as u32)
+            .checked_sub(var_index + 1)
+            .map(|dependency| dependency as usize)
+    })
+}
+
+/// Collect the variables that are free in a term, under the given number of
+/// binders. The indices are relative to the scope outside of these binders.
+fn collect_free_vars(term: &Term, binders: u32, free_vars: &mut BTreeSet<u32>) {
+    match term {
+        Term::Var(var_index) => {
+            if var_index.0 >= binders {
+                free_vars.insert(var_index.0 - binders);
+            }
+        },
+        Term::Meta(_) | Term::Prim(_) => {},
+
+        Term::Ann(term, term_ty) => {
+            collect_free_vars(term, binders, free_vars);
+            collect_free_vars(term_ty, binders, free_vars);
+        },
+        Term::Let(items, body) => {
+            let mut binders = binders;
+            for item in items {
+                match item {
+                    Item::Declaration(_, _, term_ty) => {
+                        collect_free_vars(term_ty, binders, free_vars);
+                    },
+                    Item::Definition(_, _, term) => {
+                        collect_free_vars(term, binders, free_vars);
+                        binders += 1;
+                    },
+                }
+            }
+            collect_free_vars(body, binders, free_vars);
+        },
+
+        Term::LiteralType(_) | Term::LiteralIntro(_) => {},
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            collect_free_vars(scrutinee, binders, free_vars);
+            for (_, body) in clauses.iter() {
+                collect_free_vars(body, binders, free_vars);
+            }
+            collect_free_vars(default_body, binders, free_vars);
+        },
+
+        Term::FunType(_, _, _, param_ty, body_ty) => {
+            collect_free_vars(param_ty, binders, free_vars);
+            collect_free_vars(body_ty, binders + 1, free_vars);
+        },
+        Term::FunIntro(_, _, body) => collect_free_vars(body, binders + 1, free_vars),
+        Term::FunElim(fun, _, arg) => {
+            collect_free_vars(fun, binders, free_vars);
+            collect_free_vars(arg, binders, free_vars);
+        },
+
+        Term::RecordType(ty_fields) => {
+            for (i, (_, _, _, ty)) in ty_fields.iter().enumerate() {
+                collect_free_vars(ty, binders + i as u32, free_vars);
+            }
+        },
+        Term::RecordIntro(intro_fields) => {
+            for (i, (_, term)) in intro_fields.iter().enumerate() {
+                collect_free_vars(term, binders + i as u32, free_vars);
+            }
+        },
+        Term::RecordElim(record, _) => collect_free_vars(record, binders, free_vars),
+
+        Term::ArrayType(elem_ty) => collect_free_vars(elem_ty, binders, free_vars),
+        Term::ArrayIntro(elems) => {
+            for elem in elems {
+                collect_free_vars(elem, binders, free_vars);
+            }
+        },
+
+        Term::Universe(_) => {},
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::LiteralIntro;
+    use crate::{AppMode, DocString, Rc};
+
+    fn declaration(name: &str, term_ty: Term) -> Item {
+        Item::Declaration(
+            DocString::from(""),
+            Label(name.to_owned()),
+            Rc::from(term_ty),
+        )
+    }
+
+    fn definition(name: &str, term: Term) -> Item {
+        Item::Definition(DocString::from(""), Label(name.to_owned()), Rc::from(term))
+    }
+
+    /// ```text
+    /// A = Type;
+    /// x : A;
+    /// x = true;
+    /// f = fun _ => x;
+    /// g = <global>;
+    /// ```
+    fn module() -> Module {
+        Module {
+            items: vec![
+                definition("A", Term::universe(0)),
+                declaration("x", Term::var(0)),
+                definition("x", Term::literal_intro(LiteralIntro::Bool(true))),
+                definition(
+                    "f",
+                    Term::FunIntro(AppMode::Explicit, None, Rc::from(Term::var(1))),
+                ),
+                // There is a global defined outside of the module at index 3
+                definition("g", Term::var(3)),
+            ],
+        }
+    }
+
+    #[test]
+    fn from_module() {
+        let graph = DependencyGraph::from_module(&module());
+
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.position(&Label("f".to_owned())), Some(2));
+        assert_eq!(graph.dependencies(0), &BTreeSet::new());
+        assert_eq!(graph.dependencies(1), &[0].iter().cloned().collect());
+        assert_eq!(graph.dependencies(2), &[1].iter().cloned().collect());
+        assert_eq!(graph.dependencies(3), &BTreeSet::new());
+        assert_eq!(graph.dependents(0), [1]);
+        assert_eq!(graph.dependents(3), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn reachable() {
+        let graph = DependencyGraph::from_module(&module());
+
+        assert_eq!(graph.reachable(&[1]), [true, true, false, false]);
+        assert_eq!(graph.reachable(&[2, 3]), [true, true, true, true]);
+    }
+
+    #[test]
+    fn layers() {
+        let graph = DependencyGraph::from_module(&module());
+
+        assert_eq!(graph.layers(), [vec![0, 3], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn to_dot() {
+        let graph = DependencyGraph::from_module(&module());
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dependencies {\n    \
+             n0 [label=\"A\"];\n    \
+             n1 [label=\"x\"];\n    \
+             n2 [label=\"f\"];\n    \
+             n3 [label=\"g\"];\n    \
+             n1 -> n0;\n    \
+             n2 -> n1;\n\
+             }\n",
+        );
+    }
+}
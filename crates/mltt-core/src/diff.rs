@@ -0,0 +1,440 @@
+//! Structural differences between terms.
+//!
+//! When two terms are not alpha equivalent it can be hard to see why, as they
+//! might only differ in a small part of a much larger term. The functions in
+//! this module walk both terms in parallel, and report the smallest subterms
+//! where they differ, along with the path from the roots of the terms to each
+//! of these subterms.
+
+use pretty::{BoxDoc, Doc};
+use std::fmt;
+
+use crate::domain::Value;
+use crate::pretty::{Env, Style};
+use crate::syntax::{Item, Term};
+use crate::{meta, nbe, prim, var, Label, Rc};
+
+/// A step from a term to one of its immediate subterms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// The term of an annotation.
+    AnnTerm,
+    /// The type of an annotation.
+    AnnType,
+    /// The item at the given position in a let binding.
+    LetItem(usize),
+    /// The body of a let binding.
+    LetBody,
+    /// The scrutinee of a literal elimination.
+    Scrutinee,
+    /// The body of the clause at the given position in a literal elimination.
+    Clause(usize),
+    /// The default body of a literal elimination.
+    DefaultClause,
+    /// The parameter type of a function type.
+    ParamType,
+    /// The body type of a function type.
+    BodyType,
+    /// The body of a function introduction.
+    FunBody,
+    /// The function of a function elimination.
+    Fun,
+    /// The argument of a function elimination.
+    Arg,
+    /// The type of a field in a record type.
+    FieldType(Label),
+    /// The term of a field in a record introduction.
+    Field(Label),
+    /// The record of a record elimination.
+    Record,
+    /// The element type of an array type.
+    ElemType,
+    /// The element at the given position in an array introduction.
+    Elem(usize),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::AnnTerm => write!(f, "ann.term"),
+            Step::AnnType => write!(f, "ann.type"),
+            Step::LetItem(index) => write!(f, "let.items[{}]", index),
+            Step::LetBody => write!(f, "let.body"),
+            Step::Scrutinee => write!(f, "case.scrutinee"),
+            Step::Clause(index) => write!(f, "case.clauses[{}]", index),
+            Step::DefaultClause => write!(f, "case.default"),
+            Step::ParamType => write!(f, "fun-type.param"),
+            Step::BodyType => write!(f, "fun-type.body"),
+            Step::FunBody => write!(f, "fun-intro.body"),
+            Step::Fun => write!(f, "fun-elim.fun"),
+            Step::Arg => write!(f, "fun-elim.arg"),
+            Step::FieldType(label) => write!(f, "record-type.{}", label),
+            Step::Field(label) => write!(f, "record-intro.{}", label),
+            Step::Record => write!(f, "record-elim.record"),
+            Step::ElemType => write!(f, "array-type.elem"),
+            Step::Elem(index) => write!(f, "array-intro[{}]", index),
+        }
+    }
+}
+
+/// The path from the root of a term to one of its subterms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Path(pub Vec<Step>);
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for step in &self.0 {
+            write!(f, "/{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+/// A pair of subterms where two terms differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// The path from the roots of the terms to the subterms.
+    pub path: Path,
+    /// The name hints of the variables that are bound between the roots of the
+    /// terms and the subterms, from the outermost to the innermost.
+    pub binders: Vec<Option<String>>,
+    /// The subterm of the first term.
+    pub term1: Rc<Term>,
+    /// The subterm of the second term.
+    pub term2: Rc<Term>,
+}
+
+impl Difference {
+    /// Convert the difference into a pretty-printable document, printing the
+    /// subterms in the environment of the roots of the terms.
+    pub fn to_display_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        let mut env = env.clone();
+        for name_hint in &self.binders {
+            env.fresh_name(name_hint.as_ref().map(String::as_str));
+        }
+
+        Doc::nil()
+            .append(Doc::as_string(&self.path))
+            .append(":")
+            .append(Doc::space().append(self.term1.to_display_doc(&env)).nest(4))
+            .append(
+                Doc::space()
+                    .append("≠")
+                    .append(Doc::space())
+                    .append(self.term2.to_display_doc(&env))
+                    .nest(4),
+            )
+            .group()
+    }
+}
+
+/// Find the smallest subterms where two terms differ. The result is empty if
+/// the terms are alpha equivalent.
+pub fn diff_terms(term1: &Rc<Term>, term2: &Rc<Term>) -> Vec<Difference> {
+    let mut diff = Diff::default();
+    diff.terms(term1, term2);
+    diff.differences
+}
+
+/// Find the smallest subterms where the normal forms of two values differ.
+pub fn diff_values(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    value1: &Rc<Value>,
+    value2: &Rc<Value>,
+) -> Result<Vec<Difference>, String> {
+    let term1 = nbe::read_back_value(prims, metas, size, value1)?;
+    let term2 = nbe::read_back_value(prims, metas, size, value2)?;
+
+    Ok(diff_terms(&term1, &term2))
+}
+
+/// The state of a diff between two terms.
+#[derive(Debug, Default)]
+struct Diff {
+    /// The path to the subterms that are being compared.
+    path: Vec<Step>,
+    /// The name hints of the variables bound along the path.
+    binders: Vec<Option<String>>,
+    /// The differences that have been found so far.
+    differences: Vec<Difference>,
+}
+
+impl Diff {
+    /// Compare the subterms at the end of the current path, descending into
+    /// them if they have the same shape.
+    fn terms(&mut self, term1: &Rc<Term>, term2: &Rc<Term>) {
+        if term1.alpha_eq(term2) {
+            return;
+        }
+
+        let start_len = self.differences.len();
+        if self.children(term1, term2) && self.differences.len() > start_len {
+            return;
+        }
+
+        // The terms have different shapes, or differ in something other than
+        // their subterms, like the index of a variable
+        self.differences.truncate(start_len);
+        self.differences.push(Difference {
+            path: Path(self.path.clone()),
+            binders: self.binders.clone(),
+            term1: term1.clone(),
+            term2: term2.clone(),
+        });
+    }
+
+    /// Compare a pair of subterms, one step further along the path, and
+    /// under the given binders.
+    fn step(&mut self, step: Step, binders: &[Option<String>], term1: &Rc<Term>, term2: &Rc<Term>) {
+        let binders_len = self.binders.len();
+        self.path.push(step);
+        self.binders.extend(binders.iter().cloned());
+        self.terms(term1, term2);
+        self.binders.truncate(binders_len);
+        self.path.pop();
+    }
+
+    /// Compare the immediate subterms of two terms, returning `false` if the
+    /// terms don't have the same shape.
+    fn children(&mut self, term1: &Term, term2: &Term) -> bool {
+        match (term1, term2) {
+            (Term::Ann(term1, ty1), Term::Ann(term2, ty2)) => {
+                self.step(Step::AnnTerm, &[], term1, term2);
+                self.step(Step::AnnType, &[], ty1, ty2);
+                true
+            },
+            (Term::Let(items1, body1), Term::Let(items2, body2)) => {
+                let same_items = items1.len() == items2.len()
+                    && Iterator::zip(items1.iter(), items2.iter()).all(|items| match items {
+                        (Item::Declaration(_, label1, _), Item::Declaration(_, label2, _))
+                        | (Item::Definition(_, label1, _), Item::Definition(_, label2, _)) => {
+                            label1 == label2
+                        },
+                        (_, _) => false,
+                    });
+                if !same_items {
+                    return false;
+                }
+
+                let mut binders = Vec::new();
+                for (index, items) in Iterator::zip(items1.iter(), items2.iter()).enumerate() {
+                    match items {
+                        (Item::Declaration(_, _, ty1), Item::Declaration(_, _, ty2)) => {
+                            self.step(Step::LetItem(index), &binders, ty1, ty2);
+                        },
+                        (Item::Definition(_, label, term1), Item::Definition(_, _, term2)) => {
+                            self.step(Step::LetItem(index), &binders, term1, term2);
+                            binders.push(Some(label.0.clone()));
+                        },
+                        (_, _) => unreachable!(),
+                    }
+                }
+                self.step(Step::LetBody, &binders, body1, body2);
+                true
+            },
+            (
+                Term::LiteralElim(scrutinee1, clauses1, default1),
+                Term::LiteralElim(scrutinee2, clauses2, default2),
+            ) => {
+                let same_patterns = clauses1.len() == clauses2.len()
+                    && Iterator::zip(clauses1.iter(), clauses2.iter())
+                        .all(|((pattern1, _), (pattern2, _))| pattern1.alpha_eq(pattern2));
+                if !same_patterns {
+                    return false;
+                }
+
+                self.step(Step::Scrutinee, &[], scrutinee1, scrutinee2);
+                let clauses = Iterator::zip(clauses1.iter(), clauses2.iter()).enumerate();
+                for (index, ((_, body1), (_, body2))) in clauses {
+                    self.step(Step::Clause(index), &[], body1, body2);
+                }
+                self.step(Step::DefaultClause, &[], default1, default2);
+                true
+            },
+
+            (
+                Term::FunType(_, app_mode1, name_hint, param_ty1, body_ty1),
+                Term::FunType(_, app_mode2, _, param_ty2, body_ty2),
+            ) if app_mode1 == app_mode2 => {
+                self.step(Step::ParamType, &[], param_ty1, param_ty2);
+                self.step(
+                    Step::BodyType,
+                    std::slice::from_ref(name_hint),
+                    body_ty1,
+                    body_ty2,
+                );
+                true
+            },
+            (Term::FunIntro(app_mode1, name_hint, body1), Term::FunIntro(app_mode2, _, body2))
+                if app_mode1 == app_mode2 =>
+            {
+                self.step(Step::FunBody, std::slice::from_ref(name_hint), body1, body2);
+                true
+            },
+            (Term::FunElim(fun1, app_mode1, arg1), Term::FunElim(fun2, app_mode2, arg2))
+                if app_mode1 == app_mode2 =>
+            {
+                self.step(Step::Fun, &[], fun1, fun2);
+                self.step(Step::Arg, &[], arg1, arg2);
+                true
+            },
+
+            (Term::RecordType(ty_fields1), Term::RecordType(ty_fields2)) => {
+                let same_labels = ty_fields1.len() == ty_fields2.len()
+                    && Iterator::zip(ty_fields1.iter(), ty_fields2.iter())
+                        .all(|((_, label1, _, _), (_, label2, _, _))| label1 == label2);
+                if !same_labels {
+                    return false;
+                }
+
+                // Later fields are in the scope of the earlier fields
+                let mut binders = Vec::new();
+                for (field1, field2) in Iterator::zip(ty_fields1.iter(), ty_fields2.iter()) {
+                    let (_, label, name_hint, ty1) = field1;
+                    let (_, _, _, ty2) = field2;
+                    self.step(Step::FieldType(label.clone()), &binders, ty1, ty2);
+                    binders.push(Some(name_hint.as_ref().unwrap_or(&label.0).clone()));
+                }
+                true
+            },
+            (Term::RecordIntro(intro_fields1), Term::RecordIntro(intro_fields2)) => {
+                let same_labels = intro_fields1.len() == intro_fields2.len()
+                    && Iterator::zip(intro_fields1.iter(), intro_fields2.iter())
+                        .all(|((label1, _), (label2, _))| label1 == label2);
+                if !same_labels {
+                    return false;
+                }
+
+                // Later fields are in the scope of the earlier fields
+                let mut binders = Vec::new();
+                for ((label, term1), (_, term2)) in
+                    Iterator::zip(intro_fields1.iter(), intro_fields2.iter())
+                {
+                    self.step(Step::Field(label.clone()), &binders, term1, term2);
+                    binders.push(Some(label.0.clone()));
+                }
+                true
+            },
+            (Term::RecordElim(record1, label1), Term::RecordElim(record2, label2))
+                if label1 == label2 =>
+            {
+                self.step(Step::Record, &[], record1, record2);
+                true
+            },
+
+            (Term::ArrayType(elem_ty1), Term::ArrayType(elem_ty2)) => {
+                self.step(Step::ElemType, &[], elem_ty1, elem_ty2);
+                true
+            },
+            (Term::ArrayIntro(elems1), Term::ArrayIntro(elems2))
+                if elems1.len() == elems2.len() =>
+            {
+                let elems = Iterator::zip(elems1.iter(), elems2.iter()).enumerate();
+                for (index, (elem1, elem2)) in elems {
+                    self.step(Step::Elem(index), &[], elem1, elem2);
+                }
+                true
+            },
+
+            (_, _) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::LiteralIntro;
+    use crate::AppMode;
+
+    fn fun_elim(fun: Term, arg: Term) -> Rc<Term> {
+        Rc::from(Term::FunElim(
+            Rc::from(fun),
+            AppMode::Explicit,
+            Rc::from(arg),
+        ))
+    }
+
+    fn bool_intro(value: bool) -> Term {
+        Term::literal_intro(LiteralIntro::Bool(value))
+    }
+
+    #[test]
+    fn diff_equal() {
+        let term = fun_elim(Term::var(0), bool_intro(true));
+
+        assert_eq!(diff_terms(&term, &term), Vec::new());
+    }
+
+    #[test]
+    fn diff_arg() {
+        let term1 = fun_elim(Term::var(0), bool_intro(true));
+        let term2 = fun_elim(Term::var(0), bool_intro(false));
+
+        let differences = diff_terms(&term1, &term2);
+
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path.to_string(), "/fun-elim.arg");
+        assert_eq!(differences[0].term1, Rc::from(bool_intro(true)));
+        assert_eq!(differences[0].term2, Rc::from(bool_intro(false)));
+    }
+
+    #[test]
+    fn diff_different_shapes() {
+        let term1 = fun_elim(Term::var(0), bool_intro(true));
+        let term2 = Rc::from(Term::var(0));
+
+        let differences = diff_terms(&term1, &term2);
+
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path, Path::default());
+        assert_eq!(differences[0].path.to_string(), "/");
+    }
+
+    #[test]
+    fn diff_under_binders() {
+        let fun_intro = |body| {
+            Rc::from(Term::FunIntro(
+                AppMode::Explicit,
+                Some("x".to_owned()),
+                body,
+            ))
+        };
+        let term1 = fun_intro(Rc::from(Term::RecordIntro(vec![
+            (Label("a".to_owned()), Rc::from(Term::var(0))),
+            (Label("b".to_owned()), Rc::from(Term::var(0))),
+        ])));
+        let term2 = fun_intro(Rc::from(Term::RecordIntro(vec![
+            (Label("a".to_owned()), Rc::from(Term::var(1))),
+            (Label("b".to_owned()), Rc::from(Term::var(1))),
+        ])));
+
+        let differences = diff_terms(&term1, &term2);
+        let paths = (differences.iter())
+            .map(|difference| difference.path.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            [
+                "/fun-intro.body/record-intro.a",
+                "/fun-intro.body/record-intro.b"
+            ],
+        );
+        assert_eq!(
+            differences[1].binders,
+            [Some("x".to_owned()), Some("a".to_owned())]
+        );
+
+        let doc = differences[1].to_display_doc(&Env::empty());
+        assert_eq!(
+            doc.group().pretty(80).to_string(),
+            "/fun-intro.body/record-intro.b: a ≠ x"
+        );
+    }
+}
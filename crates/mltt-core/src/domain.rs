@@ -1,10 +1,14 @@
 //! The semantic domain.
 
-use std::rc::Rc;
+#[cfg(not(feature = "sync"))]
+use std::cell::OnceCell;
+#[cfg(feature = "sync")]
+use std::sync::OnceLock as OnceCell;
 
-use super::literal::{LiteralIntro, LiteralType};
+use super::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use crate::nbe::Strategy;
 use crate::syntax::Term;
-use crate::{meta, prim, var, AppMode, DocString, Label, UniverseLevel};
+use crate::{meta, prim, var, AppMode, DocString, Label, Rc, UniverseLevel};
 
 /// Terms that are in _weak head normal form_.
 ///
@@ -26,7 +30,7 @@ pub enum Value {
     LiteralIntro(LiteralIntro),
 
     /// Dependent function types
-    FunType(AppMode, Option<String>, Rc<Type>, AppClosure),
+    FunType(DocString, AppMode, Option<String>, Rc<Type>, AppClosure),
     /// Introduce a function
     FunIntro(AppMode, Option<String>, AppClosure),
 
@@ -37,6 +41,14 @@ pub enum Value {
     /// Introduce a record
     RecordIntro(Vec<(Label, Rc<Value>)>),
 
+    /// Array types
+    ArrayType(Rc<Type>),
+    /// Introduce an array
+    ///
+    /// This is a persistent vector, so that primitives can update arrays
+    /// without copying every element.
+    ArrayIntro(im::Vector<Rc<Value>>),
+
     /// Universe of types
     Universe(UniverseLevel),
 }
@@ -57,6 +69,11 @@ impl Value {
         Value::Neutral(Head::Prim(name.into()), Vec::new())
     }
 
+    /// Construct a suspended computation.
+    pub fn thunk(thunk: Thunk) -> Value {
+        Value::Neutral(Head::Thunk(Rc::from(thunk)), Vec::new())
+    }
+
     /// Construct a literal type.
     pub fn literal_ty(ty: LiteralType) -> Value {
         Value::LiteralType(ty)
@@ -86,6 +103,43 @@ pub enum Head {
     Meta(meta::Index),
     /// Primitives
     Prim(prim::Name),
+    /// Suspended computations, created during call-by-need evaluation
+    Thunk(Rc<Thunk>),
+}
+
+/// A term whose evaluation has been suspended until its value is needed.
+///
+/// The result is memoized the first time the thunk is forced, so that the
+/// term is evaluated at most once, no matter how many times it is referred to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thunk {
+    /// The suspended term.
+    pub term: Rc<Term>,
+    /// The environment in which we'll run the term in.
+    pub values: var::Env<Rc<Value>>,
+    /// The value of the term, once it has been forced.
+    value: OnceCell<Rc<Value>>,
+}
+
+impl Thunk {
+    pub fn new(term: Rc<Term>, values: var::Env<Rc<Value>>) -> Thunk {
+        Thunk {
+            term,
+            values,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// The value of the thunk, if it has already been forced.
+    pub fn value(&self) -> Option<Rc<Value>> {
+        self.value.get().cloned()
+    }
+
+    /// Record the value of the thunk after it has been forced. Forcing is
+    /// deterministic, so if the value was already recorded it is kept.
+    pub fn set_value(&self, value: Rc<Value>) {
+        let _ = self.value.set(value);
+    }
 }
 
 /// A spine of eliminators.
@@ -118,11 +172,17 @@ pub struct AppClosure {
     /// At the moment this captures the _entire_ environment - would it be
     /// better to only capture what the `term` needs?
     pub values: var::Env<Rc<Value>>,
+    /// The strategy that will be used to evaluate the term.
+    pub strategy: Strategy,
 }
 
 impl AppClosure {
-    pub fn new(term: Rc<Term>, values: var::Env<Rc<Value>>) -> AppClosure {
-        AppClosure { term, values }
+    pub fn new(term: Rc<Term>, values: var::Env<Rc<Value>>, strategy: Strategy) -> AppClosure {
+        AppClosure {
+            term,
+            values,
+            strategy,
+        }
     }
 }
 
@@ -130,7 +190,7 @@ impl AppClosure {
 #[derive(Debug, Clone, PartialEq)]
 pub struct LiteralClosure {
     /// The clauses.
-    pub clauses: Rc<[(LiteralIntro, Rc<Term>)]>,
+    pub clauses: Rc<[(LiteralPattern, Rc<Term>)]>,
     /// The default term.
     pub default: Rc<Term>,
     /// The environment in which we'll run the clauses in.
@@ -138,18 +198,22 @@ pub struct LiteralClosure {
     /// At the moment this captures the _entire_ environment - would it be
     /// better to only capture what the `term` needs?
     pub values: var::Env<Rc<Value>>,
+    /// The strategy that will be used to evaluate the clauses.
+    pub strategy: Strategy,
 }
 
 impl LiteralClosure {
     pub fn new(
-        clauses: Rc<[(LiteralIntro, Rc<Term>)]>,
+        clauses: Rc<[(LiteralPattern, Rc<Term>)]>,
         default: Rc<Term>,
         values: var::Env<Rc<Value>>,
+        strategy: Strategy,
     ) -> LiteralClosure {
         LiteralClosure {
             clauses,
             default,
             values,
+            strategy,
         }
     }
 }
@@ -0,0 +1,386 @@
+//! Equality checks that take advantage of structural sharing.
+//!
+//! The derived `PartialEq` implementations for terms and values compare them
+//! node by node, as if they were trees. Evaluation often produces values that
+//! share subterms, however, and comparing these as trees repeats the same
+//! work for every path to a shared node - exponentially many in the worst
+//! case. The checks in this module skip pairs of nodes that are physically
+//! identical, and remember the pairs of nodes that have already been found to
+//! be equal, so that each pair is only compared once.
+//!
+//! The results are the same as those of `PartialEq`.
+
+use std::collections::HashSet;
+use std::ptr;
+
+use crate::domain::{AppClosure, Elim, Head, LiteralClosure, Thunk, Value};
+use crate::literal::LiteralPattern;
+use crate::syntax::{Item, Term};
+use crate::{var, Rc};
+
+impl Term {
+    /// Compare two terms for equality, taking advantage of any sharing
+    /// between their subterms.
+    pub fn shared_eq(&self, other: &Term) -> bool {
+        SharedEq::new().terms(self, other)
+    }
+}
+
+impl Value {
+    /// Compare two values for equality, taking advantage of any sharing
+    /// between their sub-values.
+    pub fn shared_eq(&self, other: &Value) -> bool {
+        SharedEq::new().values(self, other)
+    }
+}
+
+/// The state of a sharing-aware equality check.
+///
+/// This can be reused between checks to remember the pairs of nodes that were
+/// found to be equal, so long as the nodes are not mutated or dropped in the
+/// meantime.
+#[derive(Debug, Default)]
+pub struct SharedEq {
+    /// Pairs of terms that are known to be equal.
+    terms: HashSet<(*const Term, *const Term)>,
+    /// Pairs of values that are known to be equal.
+    values: HashSet<(*const Value, *const Value)>,
+}
+
+impl SharedEq {
+    /// Create a new equality check, with no known pairs.
+    pub fn new() -> SharedEq {
+        SharedEq::default()
+    }
+
+    /// Compare two terms for equality.
+    pub fn terms(&mut self, term1: &Term, term2: &Term) -> bool {
+        let key = (term1 as *const Term, term2 as *const Term);
+        if ptr::eq(term1, term2) || self.terms.contains(&key) {
+            return true;
+        }
+
+        let is_equal = match (term1, term2) {
+            (Term::Var(index1), Term::Var(index2)) => index1 == index2,
+            (Term::Meta(index1), Term::Meta(index2)) => index1 == index2,
+            (Term::Prim(name1), Term::Prim(name2)) => name1 == name2,
+
+            (Term::Ann(term1, ty1), Term::Ann(term2, ty2)) => {
+                self.terms(term1, term2) && self.terms(ty1, ty2)
+            },
+            (Term::Let(items1, body1), Term::Let(items2, body2)) => {
+                items1.len() == items2.len()
+                    && Iterator::zip(items1.iter(), items2.iter())
+                        .all(|(item1, item2)| self.items(item1, item2))
+                    && self.terms(body1, body2)
+            },
+
+            (Term::LiteralType(ty1), Term::LiteralType(ty2)) => ty1 == ty2,
+            (Term::LiteralIntro(intro1), Term::LiteralIntro(intro2)) => intro1 == intro2,
+            (
+                Term::LiteralElim(scrutinee1, clauses1, default1),
+                Term::LiteralElim(scrutinee2, clauses2, default2),
+            ) => {
+                self.terms(scrutinee1, scrutinee2)
+                    && self.clauses(clauses1, clauses2)
+                    && self.terms(default1, default2)
+            },
+
+            (
+                Term::FunType(doc1, app_mode1, name1, param_ty1, body_ty1),
+                Term::FunType(doc2, app_mode2, name2, param_ty2, body_ty2),
+            ) => {
+                doc1 == doc2
+                    && app_mode1 == app_mode2
+                    && name1 == name2
+                    && self.terms(param_ty1, param_ty2)
+                    && self.terms(body_ty1, body_ty2)
+            },
+            (Term::FunIntro(app_mode1, name1, body1), Term::FunIntro(app_mode2, name2, body2)) => {
+                app_mode1 == app_mode2 && name1 == name2 && self.terms(body1, body2)
+            },
+            (Term::FunElim(fun1, app_mode1, arg1), Term::FunElim(fun2, app_mode2, arg2)) => {
+                app_mode1 == app_mode2 && self.terms(fun1, fun2) && self.terms(arg1, arg2)
+            },
+
+            (Term::RecordType(ty_fields1), Term::RecordType(ty_fields2)) => {
+                ty_fields1.len() == ty_fields2.len()
+                    && Iterator::zip(ty_fields1.iter(), ty_fields2.iter()).all(
+                        |((doc1, label1, name1, ty1), (doc2, label2, name2, ty2))| {
+                            doc1 == doc2
+                                && label1 == label2
+                                && name1 == name2
+                                && self.terms(ty1, ty2)
+                        },
+                    )
+            },
+            (Term::RecordIntro(fields1), Term::RecordIntro(fields2)) => {
+                fields1.len() == fields2.len()
+                    && Iterator::zip(fields1.iter(), fields2.iter()).all(
+                        |((label1, term1), (label2, term2))| {
+                            label1 == label2 && self.terms(term1, term2)
+                        },
+                    )
+            },
+            (Term::RecordElim(record1, label1), Term::RecordElim(record2, label2)) => {
+                label1 == label2 && self.terms(record1, record2)
+            },
+
+            (Term::ArrayType(elem_ty1), Term::ArrayType(elem_ty2)) => {
+                self.terms(elem_ty1, elem_ty2)
+            },
+            (Term::ArrayIntro(elems1), Term::ArrayIntro(elems2)) => {
+                elems1.len() == elems2.len()
+                    && Iterator::zip(elems1.iter(), elems2.iter())
+                        .all(|(elem1, elem2)| self.terms(elem1, elem2))
+            },
+
+            (Term::Universe(level1), Term::Universe(level2)) => level1 == level2,
+
+            (_, _) => false,
+        };
+
+        if is_equal {
+            self.terms.insert(key);
+        }
+        is_equal
+    }
+
+    /// Compare two values for equality.
+    pub fn values(&mut self, value1: &Value, value2: &Value) -> bool {
+        let key = (value1 as *const Value, value2 as *const Value);
+        if ptr::eq(value1, value2) || self.values.contains(&key) {
+            return true;
+        }
+
+        let is_equal = match (value1, value2) {
+            (Value::Neutral(head1, spine1), Value::Neutral(head2, spine2)) => {
+                self.heads(head1, head2)
+                    && spine1.len() == spine2.len()
+                    && Iterator::zip(spine1.iter(), spine2.iter())
+                        .all(|(elim1, elim2)| self.elims(elim1, elim2))
+            },
+
+            (Value::LiteralType(ty1), Value::LiteralType(ty2)) => ty1 == ty2,
+            (Value::LiteralIntro(intro1), Value::LiteralIntro(intro2)) => intro1 == intro2,
+
+            (
+                Value::FunType(doc1, app_mode1, name1, param_ty1, body_ty1),
+                Value::FunType(doc2, app_mode2, name2, param_ty2, body_ty2),
+            ) => {
+                doc1 == doc2
+                    && app_mode1 == app_mode2
+                    && name1 == name2
+                    && self.values(param_ty1, param_ty2)
+                    && self.app_closures(body_ty1, body_ty2)
+            },
+            (
+                Value::FunIntro(app_mode1, name1, body1),
+                Value::FunIntro(app_mode2, name2, body2),
+            ) => app_mode1 == app_mode2 && name1 == name2 && self.app_closures(body1, body2),
+
+            (
+                Value::RecordTypeExtend(doc1, label1, name1, ty1, rest1),
+                Value::RecordTypeExtend(doc2, label2, name2, ty2, rest2),
+            ) => {
+                doc1 == doc2
+                    && label1 == label2
+                    && name1 == name2
+                    && self.values(ty1, ty2)
+                    && self.app_closures(rest1, rest2)
+            },
+            (Value::RecordTypeEmpty, Value::RecordTypeEmpty) => true,
+            (Value::RecordIntro(fields1), Value::RecordIntro(fields2)) => {
+                fields1.len() == fields2.len()
+                    && Iterator::zip(fields1.iter(), fields2.iter()).all(
+                        |((label1, value1), (label2, value2))| {
+                            label1 == label2 && self.values(value1, value2)
+                        },
+                    )
+            },
+
+            (Value::ArrayType(elem_ty1), Value::ArrayType(elem_ty2)) => {
+                self.values(elem_ty1, elem_ty2)
+            },
+            (Value::ArrayIntro(elems1), Value::ArrayIntro(elems2)) => {
+                elems1.len() == elems2.len()
+                    && Iterator::zip(elems1.iter(), elems2.iter())
+                        .all(|(elem1, elem2)| self.values(elem1, elem2))
+            },
+
+            (Value::Universe(level1), Value::Universe(level2)) => level1 == level2,
+
+            (_, _) => false,
+        };
+
+        if is_equal {
+            self.values.insert(key);
+        }
+        is_equal
+    }
+
+    fn items(&mut self, item1: &Item, item2: &Item) -> bool {
+        match (item1, item2) {
+            (Item::Declaration(doc1, label1, ty1), Item::Declaration(doc2, label2, ty2))
+            | (Item::Definition(doc1, label1, ty1), Item::Definition(doc2, label2, ty2)) => {
+                doc1 == doc2 && label1 == label2 && self.terms(ty1, ty2)
+            },
+            (_, _) => false,
+        }
+    }
+
+    fn clauses(
+        &mut self,
+        clauses1: &Rc<[(LiteralPattern, Rc<Term>)]>,
+        clauses2: &Rc<[(LiteralPattern, Rc<Term>)]>,
+    ) -> bool {
+        Rc::ptr_eq(clauses1, clauses2)
+            || (clauses1.len() == clauses2.len()
+                && Iterator::zip(clauses1.iter(), clauses2.iter()).all(
+                    |((intro1, body1), (intro2, body2))| {
+                        intro1 == intro2 && self.terms(body1, body2)
+                    },
+                ))
+    }
+
+    fn envs(&mut self, values1: &var::Env<Rc<Value>>, values2: &var::Env<Rc<Value>>) -> bool {
+        let (entries1, entries2) = (values1.entries(), values2.entries());
+        entries1.ptr_eq(entries2)
+            || (entries1.len() == entries2.len()
+                && Iterator::zip(entries1.iter(), entries2.iter())
+                    .all(|(value1, value2)| self.values(value1, value2)))
+    }
+
+    fn heads(&mut self, head1: &Head, head2: &Head) -> bool {
+        match (head1, head2) {
+            (Head::Var(level1), Head::Var(level2)) => level1 == level2,
+            (Head::Meta(index1), Head::Meta(index2)) => index1 == index2,
+            (Head::Prim(name1), Head::Prim(name2)) => name1 == name2,
+            (Head::Thunk(thunk1), Head::Thunk(thunk2)) => self.thunks(thunk1, thunk2),
+            (_, _) => false,
+        }
+    }
+
+    fn thunks(&mut self, thunk1: &Rc<Thunk>, thunk2: &Rc<Thunk>) -> bool {
+        Rc::ptr_eq(thunk1, thunk2)
+            || (self.terms(&thunk1.term, &thunk2.term)
+                && self.envs(&thunk1.values, &thunk2.values)
+                && match (thunk1.value(), thunk2.value()) {
+                    (Some(value1), Some(value2)) => self.values(&value1, &value2),
+                    (None, None) => true,
+                    (_, _) => false,
+                })
+    }
+
+    fn elims(&mut self, elim1: &Elim, elim2: &Elim) -> bool {
+        match (elim1, elim2) {
+            (Elim::Literal(closure1), Elim::Literal(closure2)) => {
+                self.literal_closures(closure1, closure2)
+            },
+            (Elim::Fun(app_mode1, arg1), Elim::Fun(app_mode2, arg2)) => {
+                app_mode1 == app_mode2 && self.values(arg1, arg2)
+            },
+            (Elim::Record(label1), Elim::Record(label2)) => label1 == label2,
+            (_, _) => false,
+        }
+    }
+
+    fn app_closures(&mut self, closure1: &AppClosure, closure2: &AppClosure) -> bool {
+        closure1.strategy == closure2.strategy
+            && self.terms(&closure1.term, &closure2.term)
+            && self.envs(&closure1.values, &closure2.values)
+    }
+
+    fn literal_closures(&mut self, closure1: &LiteralClosure, closure2: &LiteralClosure) -> bool {
+        closure1.strategy == closure2.strategy
+            && self.clauses(&closure1.clauses, &closure2.clauses)
+            && self.terms(&closure1.default, &closure2.default)
+            && self.envs(&closure1.values, &closure2.values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AppMode, DocString, Label};
+
+    /// A term of the given depth where both sides of each application share
+    /// the same subterm, so that it has exponentially many paths.
+    fn shared_term(depth: usize, leaf: Term) -> Rc<Term> {
+        (0..depth).fold(Rc::from(leaf), |term, _| {
+            Rc::from(Term::FunElim(term.clone(), AppMode::Explicit, term))
+        })
+    }
+
+    #[test]
+    fn shared_terms() {
+        // Comparing these node by node would take 2^64 steps
+        let term1 = shared_term(64, Term::var(0));
+        let term2 = shared_term(64, Term::var(0));
+
+        assert!(term1.shared_eq(&term2));
+    }
+
+    #[test]
+    fn shared_terms_differ() {
+        let term1 = shared_term(64, Term::var(0));
+        let term2 = shared_term(64, Term::var(1));
+
+        assert!(!term1.shared_eq(&term2));
+    }
+
+    #[test]
+    fn terms_agree_with_partial_eq() {
+        let doc = DocString::from("");
+        let terms = vec![
+            Term::var(0),
+            Term::universe(0),
+            Term::Let(
+                vec![Item::Definition(
+                    doc.clone(),
+                    Label("x".to_owned()),
+                    Rc::from(Term::var(0)),
+                )],
+                Rc::from(Term::var(0)),
+            ),
+            Term::Let(
+                vec![Item::Declaration(
+                    doc.clone(),
+                    Label("x".to_owned()),
+                    Rc::from(Term::var(0)),
+                )],
+                Rc::from(Term::var(0)),
+            ),
+            Term::RecordType(vec![(
+                doc.clone(),
+                Label("x".to_owned()),
+                None,
+                Rc::from(Term::var(0)),
+            )]),
+            Term::RecordIntro(vec![(Label("x".to_owned()), Rc::from(Term::var(0)))]),
+            Term::RecordIntro(vec![(Label("y".to_owned()), Rc::from(Term::var(0)))]),
+        ];
+
+        for term1 in &terms {
+            for term2 in &terms {
+                assert_eq!(term1.shared_eq(term2), term1 == term2);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_values() {
+        // Comparing these node by node would take 2^64 steps
+        let shared_value = |head| {
+            (0..64).fold(Rc::from(Value::var(head)), |value, _| {
+                Rc::from(Value::RecordIntro(vec![
+                    (Label("x".to_owned()), value.clone()),
+                    (Label("y".to_owned()), value),
+                ]))
+            })
+        };
+
+        assert!(shared_value(0).shared_eq(&shared_value(0)));
+        assert!(!shared_value(0).shared_eq(&shared_value(1)));
+    }
+}
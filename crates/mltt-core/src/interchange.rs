@@ -0,0 +1,279 @@
+//! A small JSON interchange format for terms.
+//!
+//! This allows external tools, and other experimental type checkers, to use
+//! `validate::check_module` as an independent checker. The format only covers
+//! a small fragment of the core syntax, using names rather than de Bruijn
+//! indices for variables:
+//!
+//! ```json
+//! {
+//!     "items": [
+//!         { "declaration": { "name": "id", "type": <term> } },
+//!         { "definition": { "name": "id", "term": <term> } }
+//!     ]
+//! }
+//! ```
+//!
+//! Where terms are one of:
+//!
+//! - `{ "var": "x" }`: a local variable, or an earlier definition
+//! - `{ "universe": 0 }`: a universe at the given level
+//! - `{ "pi": { "name": "x", "domain": <term>, "codomain": <term> } }`: a
+//!   dependent function type, where `name` is optional
+//! - `{ "lambda": { "name": "x", "body": <term> } }`: a function
+//! - `{ "app": { "fun": <term>, "arg": <term> } }`: a function application
+//! - `{ "ann": { "term": <term>, "type": <term> } }`: a term annotated with
+//!   its type
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+use crate::{syntax, var, AppMode, DocString, Label, Rc, UniverseLevel};
+
+/// A module in the interchange format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Module {
+    pub items: Vec<Item>,
+}
+
+/// An item in the interchange format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Item {
+    Declaration {
+        name: String,
+        #[serde(rename = "type")]
+        ty: Term,
+    },
+    Definition {
+        name: String,
+        term: Term,
+    },
+}
+
+/// A term in the interchange format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Term {
+    Var(String),
+    Universe(u16),
+    Pi {
+        #[serde(default)]
+        name: Option<String>,
+        domain: Box<Term>,
+        codomain: Box<Term>,
+    },
+    Lambda {
+        name: String,
+        body: Box<Term>,
+    },
+    App {
+        fun: Box<Term>,
+        arg: Box<Term>,
+    },
+    Ann {
+        term: Box<Term>,
+        #[serde(rename = "type")]
+        ty: Box<Term>,
+    },
+}
+
+/// Errors encountered when reading the interchange format.
+#[derive(Debug)]
+pub enum ReadError {
+    Json(serde_json::Error),
+    UnboundVariable(String),
+}
+
+impl Error for ReadError {}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Json(error) => write!(f, "invalid interchange format: {}", error),
+            ReadError::UnboundVariable(name) => write!(f, "unbound variable: {}", name),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ReadError {
+    fn from(src: serde_json::Error) -> ReadError {
+        ReadError::Json(src)
+    }
+}
+
+/// Read a module from the JSON interchange format, converting it into the
+/// core syntax.
+pub fn read_module(src: &str) -> Result<syntax::Module, ReadError> {
+    let module = serde_json::from_str::<Module>(src)?;
+    module.to_core()
+}
+
+impl Module {
+    /// Convert the module into the core syntax.
+    pub fn to_core(&self) -> Result<syntax::Module, ReadError> {
+        let mut names = Vec::new();
+        let items = (self.items.iter())
+            .map(|item| match item {
+                Item::Declaration { name, ty } => Ok(syntax::Item::Declaration(
+                    DocString::from(""),
+                    Label(name.clone()),
+                    ty.to_core(&mut names)?,
+                )),
+                Item::Definition { name, term } => {
+                    let term = term.to_core(&mut names)?;
+                    // Only definitions are added to the environment
+                    names.push(Some(name.clone()));
+                    Ok(syntax::Item::Definition(
+                        DocString::from(""),
+                        Label(name.clone()),
+                        term,
+                    ))
+                },
+            })
+            .collect::<Result<_, ReadError>>()?;
+
+        Ok(syntax::Module { items })
+    }
+}
+
+impl Term {
+    /// Convert the term into the core syntax, in an environment of the given
+    /// names.
+    pub fn to_core(&self, names: &mut Vec<Option<String>>) -> Result<Rc<syntax::Term>, ReadError> {
+        match self {
+            Term::Var(name) => match names.iter().rev().position(|n| n.as_ref() == Some(name)) {
+                Some(index) => Ok(Rc::from(syntax::Term::var(var::Index(index as u32)))),
+                None => Err(ReadError::UnboundVariable(name.clone())),
+            },
+            Term::Universe(level) => Ok(Rc::from(syntax::Term::universe(UniverseLevel(*level)))),
+            Term::Pi {
+                name,
+                domain,
+                codomain,
+            } => {
+                let domain = domain.to_core(names)?;
+                let codomain = with_name(names, name.clone(), |names| codomain.to_core(names))?;
+
+                Ok(Rc::from(syntax::Term::FunType(
+                    DocString::from(""),
+                    AppMode::Explicit,
+                    name.clone(),
+                    domain,
+                    codomain,
+                )))
+            },
+            Term::Lambda { name, body } => {
+                let body = with_name(names, Some(name.clone()), |names| body.to_core(names))?;
+                Ok(Rc::from(syntax::Term::FunIntro(
+                    AppMode::Explicit,
+                    Some(name.clone()),
+                    body,
+                )))
+            },
+            Term::App { fun, arg } => Ok(Rc::from(syntax::Term::FunElim(
+                fun.to_core(names)?,
+                AppMode::Explicit,
+                arg.to_core(names)?,
+            ))),
+            Term::Ann { term, ty } => Ok(Rc::from(syntax::Term::Ann(
+                term.to_core(names)?,
+                ty.to_core(names)?,
+            ))),
+        }
+    }
+}
+
+/// Run `f` with a name pushed onto the environment. Anonymous binders can't
+/// be referred to.
+fn with_name<T>(
+    names: &mut Vec<Option<String>>,
+    name: Option<String>,
+    f: impl FnOnce(&mut Vec<Option<String>>) -> T,
+) -> T {
+    names.push(name);
+    let result = f(names);
+    names.pop();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{meta, prim, validate};
+
+    fn check_module(src: &str) -> Result<(), Box<dyn Error>> {
+        let module = read_module(src)?;
+        let context = validate::Context::new(prim::Env::new(), var::Env::new(), var::Env::new());
+        validate::check_module(&context, &meta::Env::new(), &module)?;
+        Ok(())
+    }
+
+    #[test]
+    fn check_identity() {
+        let src = r#"{
+            "items": [
+                { "declaration": {
+                    "name": "id",
+                    "type": { "pi": {
+                        "name": "A",
+                        "domain": { "universe": 0 },
+                        "codomain": { "pi": { "domain": { "var": "A" }, "codomain": { "var": "A" } } }
+                    } }
+                } },
+                { "definition": {
+                    "name": "id",
+                    "term": { "lambda": { "name": "A", "body": { "lambda": { "name": "a", "body": { "var": "a" } } } } }
+                } },
+                { "definition": {
+                    "name": "id-id",
+                    "term": { "ann": {
+                        "term": { "lambda": { "name": "A", "body": { "app": { "fun": { "var": "id" }, "arg": { "var": "A" } } } } },
+                        "type": { "pi": {
+                            "name": "A",
+                            "domain": { "universe": 0 },
+                            "codomain": { "pi": { "domain": { "var": "A" }, "codomain": { "var": "A" } } }
+                        } }
+                    } }
+                } }
+            ]
+        }"#;
+
+        check_module(src).unwrap();
+    }
+
+    #[test]
+    fn anonymous_binders_are_unbound() {
+        let src = r#"{
+            "items": [
+                { "definition": {
+                    "name": "bad",
+                    "term": { "pi": { "domain": { "universe": 0 }, "codomain": { "var": "" } } }
+                } }
+            ]
+        }"#;
+
+        match read_module(src) {
+            Err(ReadError::UnboundVariable(name)) => assert_eq!(name, ""),
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reject_ill_typed() {
+        // The definition is not a function, so it can't be applied
+        let src = r#"{
+            "items": [
+                { "definition": { "name": "A", "term": { "universe": 0 } } },
+                { "definition": {
+                    "name": "bad",
+                    "term": { "app": { "fun": { "var": "A" }, "arg": { "var": "A" } } }
+                } }
+            ]
+        }"#;
+
+        assert!(check_module(src).is_err());
+    }
+}
@@ -3,17 +3,38 @@
 #![warn(rust_2018_idioms)]
 
 use std::fmt;
-use std::rc::Rc;
-use std::u16;
+
+/// The reference counted pointer used throughout the core syntax and the
+/// domain.
+///
+/// Enabling the `sync` feature switches this to an atomically reference
+/// counted pointer, allowing checked modules and values to be shared between
+/// threads, at some cost in performance.
+#[cfg(not(feature = "sync"))]
+pub use std::rc::Rc;
+#[cfg(feature = "sync")]
+pub use std::sync::Arc as Rc;
 
 pub mod meta;
 pub mod var;
 
+pub mod agda;
+pub mod builder;
+pub mod deps;
+pub mod diff;
 pub mod domain;
+pub mod equality;
+#[cfg(feature = "interchange")]
+pub mod interchange;
+pub mod lint;
 pub mod literal;
+pub mod metrics;
 pub mod pretty;
 pub mod prim;
+pub mod signature;
+pub mod steps;
 pub mod syntax;
+pub mod telescope;
 
 pub mod nbe;
 pub mod validate;
@@ -23,7 +44,7 @@ pub type DocString = Rc<str>;
 
 /// A label. These are treated as significant when comparing terms for alpha
 /// equivalence.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Label(pub String);
 
 impl fmt::Display for Label {
@@ -69,6 +90,12 @@ impl UniverseLevel {
     pub fn shift(self, shift: u16) -> Option<UniverseLevel> {
         Some(UniverseLevel(self.0.checked_add(shift)?))
     }
+
+    /// Shift the level by the given amount, returning `None` if the result
+    /// would be greater than `max`.
+    pub fn shift_within(self, shift: u16, max: UniverseLevel) -> Option<UniverseLevel> {
+        self.shift(shift).filter(|level| *level <= max)
+    }
 }
 
 impl From<u16> for UniverseLevel {
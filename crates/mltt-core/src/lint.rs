@@ -0,0 +1,319 @@
+//! Quick checks that the core syntax is well formed.
+//!
+//! This looks for mistakes that don't need any type information to be found,
+//! like variables that are out of scope, unknown primitives, or unsorted
+//! literal patterns. It is much faster than type checking with
+//! `validate::check_module`, and reports all of the problems that it finds,
+//! along with the item that they were found in. Tools that generate the core
+//! syntax directly should run it first, as type checking a malformed module
+//! can produce confusing errors.
+
+use language_reporting::Diagnostic;
+use mltt_span::FileSpan;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::syntax::{Item, Module, Term};
+use crate::validate::{self, TypeError};
+use crate::{meta, prim, AppMode, Label};
+
+/// A problem found in an item of a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintError {
+    /// The label of the item.
+    pub label: Label,
+    /// The problem that was found.
+    pub error: TypeError,
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in `{}`: {}", self.label, self.error)
+    }
+}
+
+impl From<LintError> for Diagnostic<FileSpan> {
+    fn from(src: LintError) -> Diagnostic<FileSpan> {
+        Diagnostic::new_error(format!("malformed core term {}", src))
+    }
+}
+
+/// Check that the items of a module are well formed, in the given context.
+///
+/// Returns the problems that were found, in the order that they appear in the
+/// module.
+pub fn lint_module(
+    context: &validate::Context,
+    metas: &meta::Env,
+    module: &Module,
+) -> Vec<LintError> {
+    let mut linter = Linter {
+        prims: context.prims(),
+        metas,
+        size: context.size().0,
+        label: Label(String::new()),
+        errors: Vec::new(),
+    };
+    linter.lint_items(&module.items);
+    linter.errors
+}
+
+struct Linter<'a> {
+    prims: &'a prim::Env,
+    metas: &'a meta::Env,
+    /// The number of variables that are in scope.
+    size: u32,
+    /// The label of the top-level item that is being checked.
+    label: Label,
+    errors: Vec<LintError>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, error: TypeError) {
+        self.errors.push(LintError {
+            label: self.label.clone(),
+            error,
+        });
+    }
+
+    /// Check some items, adding the definitions to the scope.
+    fn lint_items(&mut self, items: &[Item]) {
+        let is_top_level = self.label.0.is_empty();
+        let mut declarations = HashSet::new();
+        let mut definitions = HashSet::new();
+
+        for item in items {
+            let (label, term) = match item {
+                Item::Declaration(_, label, term_ty) => (label, term_ty),
+                Item::Definition(_, label, term) => (label, term),
+            };
+            if is_top_level {
+                self.label = label.clone();
+            }
+
+            match item {
+                Item::Declaration(..) if !declarations.insert(label) => {
+                    self.report(TypeError::AlreadyDeclared(label.clone()))
+                },
+                Item::Definition(..) if !definitions.insert(label) => {
+                    self.report(TypeError::AlreadyDefined(label.clone()))
+                },
+                Item::Declaration(..) | Item::Definition(..) => {},
+            }
+
+            self.lint_term(term);
+            if let Item::Definition(..) = item {
+                self.size += 1;
+            }
+        }
+
+        if is_top_level {
+            self.label = Label(String::new());
+        }
+    }
+
+    /// Check a term in the scope of the given number of new variables.
+    fn lint_under(&mut self, binders: u32, term: &Term) {
+        self.size += binders;
+        self.lint_term(term);
+        self.size -= binders;
+    }
+
+    fn lint_term(&mut self, term: &Term) {
+        match term {
+            Term::Var(var_index) => {
+                if var_index.0 >= self.size {
+                    self.report(TypeError::UnboundVariable(*var_index));
+                }
+            },
+            Term::Meta(meta_index) => {
+                if self.metas.lookup_solution(*meta_index).is_none() {
+                    self.report(TypeError::UnboundMeta(*meta_index));
+                }
+            },
+            Term::Prim(prim_name) => {
+                if self.prims.lookup_entry(prim_name).is_none() {
+                    self.report(TypeError::UnknownPrim(prim_name.clone()));
+                }
+            },
+
+            Term::Ann(term, term_ty) => {
+                if let (Term::FunIntro(found, ..), Term::FunType(_, expected, ..)) =
+                    (term.as_ref(), term_ty.as_ref())
+                {
+                    self.lint_app_modes(found, expected);
+                }
+                self.lint_term(term);
+                self.lint_term(term_ty);
+            },
+            Term::Let(items, body) => {
+                let size = self.size;
+                self.lint_items(items);
+                self.lint_term(body);
+                self.size = size;
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => {},
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                self.lint_term(scrutinee);
+                if let Err(error) = validate::check_literal_patterns(clauses) {
+                    self.report(error);
+                }
+                for (_, body) in clauses.iter() {
+                    self.lint_term(body);
+                }
+                self.lint_term(default_body);
+            },
+
+            Term::FunType(_, _, _, param_ty, body_ty) => {
+                self.lint_term(param_ty);
+                self.lint_under(1, body_ty);
+            },
+            Term::FunIntro(_, _, body) => self.lint_under(1, body),
+            Term::FunElim(fun, found, arg) => {
+                if let Term::FunIntro(expected, ..) = fun.as_ref() {
+                    self.lint_app_modes(found, expected);
+                }
+                self.lint_term(fun);
+                self.lint_term(arg);
+            },
+
+            Term::RecordType(ty_fields) => {
+                for (binders, (_, _, _, ty)) in ty_fields.iter().enumerate() {
+                    self.lint_under(binders as u32, ty);
+                }
+            },
+            Term::RecordIntro(intro_fields) => {
+                for (binders, (_, term)) in intro_fields.iter().enumerate() {
+                    self.lint_under(binders as u32, term);
+                }
+            },
+            Term::RecordElim(record, _) => self.lint_term(record),
+
+            Term::ArrayType(elem_ty) => self.lint_term(elem_ty),
+            Term::ArrayIntro(elems) => {
+                for elem in elems {
+                    self.lint_term(elem);
+                }
+            },
+
+            Term::Universe(_) => {},
+        }
+    }
+
+    /// Check that a function is used with the application mode that it was
+    /// introduced with.
+    fn lint_app_modes(&mut self, found: &AppMode, expected: &AppMode) {
+        if found != expected {
+            self.report(TypeError::UnexpectedAppMode {
+                found: found.clone(),
+                expected: expected.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::literal::{LiteralIntro, LiteralPattern};
+    use crate::{var, DocString, Rc};
+
+    fn lint(items: Vec<Item>) -> Vec<String> {
+        let context = validate::Context::empty();
+        let module = Module { items };
+        (lint_module(&context, &meta::Env::new(), &module).into_iter())
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    fn definition(label: &str, term: Term) -> Item {
+        Item::Definition(DocString::from(""), Label(label.to_owned()), Rc::from(term))
+    }
+
+    #[test]
+    fn well_formed() {
+        let id = Term::FunIntro(AppMode::Explicit, None, Rc::from(Term::var(0)));
+        let uses_id = Term::FunElim(
+            Rc::from(Term::var(0)),
+            AppMode::Explicit,
+            Rc::from(Term::universe(0)),
+        );
+
+        assert!(lint(vec![definition("id", id), definition("x", uses_id)]).is_empty());
+    }
+
+    #[test]
+    fn unbound_variables() {
+        let record = Term::RecordIntro(vec![
+            (Label("x".to_owned()), Rc::from(Term::universe(0))),
+            (Label("y".to_owned()), Rc::from(Term::var(0))),
+            (Label("z".to_owned()), Rc::from(Term::var(3))),
+        ]);
+
+        assert_eq!(
+            lint(vec![definition("a", Term::var(0)), definition("b", record)]),
+            vec![
+                format!("in `a`: {}", TypeError::UnboundVariable(var::Index(0))),
+                format!("in `b`: {}", TypeError::UnboundVariable(var::Index(3))),
+            ],
+        );
+    }
+
+    #[test]
+    fn unknown_prims_and_duplicates() {
+        assert_eq!(
+            lint(vec![
+                definition("a", Term::prim("missing")),
+                definition("a", Term::universe(0)),
+            ]),
+            vec![
+                "in `a`: unbound primitive: \"missing\"".to_owned(),
+                "in `a`: already defined: a".to_owned(),
+            ],
+        );
+    }
+
+    #[test]
+    fn unsorted_literal_patterns() {
+        let clauses = vec![
+            (
+                LiteralPattern::Intro(LiteralIntro::U8(2)),
+                Rc::from(Term::universe(0)),
+            ),
+            (
+                LiteralPattern::Intro(LiteralIntro::U8(1)),
+                Rc::from(Term::universe(0)),
+            ),
+        ];
+        let term = Term::LiteralElim(
+            Rc::from(Term::literal_intro(LiteralIntro::U8(1))),
+            Rc::from(clauses),
+            Rc::from(Term::universe(0)),
+        );
+
+        assert_eq!(lint(vec![definition("a", term)]).len(), 1);
+    }
+
+    #[test]
+    fn inconsistent_app_modes() {
+        let implicit = AppMode::Implicit(Label("A".to_owned()));
+        let fun = Term::FunIntro(implicit.clone(), None, Rc::from(Term::var(0)));
+        let term = Term::FunElim(
+            Rc::from(fun),
+            AppMode::Explicit,
+            Rc::from(Term::universe(0)),
+        );
+
+        assert_eq!(
+            lint(vec![definition("a", term)]),
+            vec![format!(
+                "in `a`: {}",
+                TypeError::UnexpectedAppMode {
+                    found: AppMode::Explicit,
+                    expected: implicit,
+                },
+            )],
+        );
+    }
+}
@@ -1,10 +1,12 @@
 //! Core literals.
 
+use std::cmp::Ordering;
 use std::fmt;
-use std::rc::Rc;
+
+use crate::Rc;
 
 /// Literal types.
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd)]
 pub enum LiteralType {
     String,
     Char,
@@ -162,6 +164,112 @@ impl From<String> for LiteralIntro {
     }
 }
 
+/// Patterns that match on literals.
+///
+/// Each pattern matches a contiguous range of literals, so the patterns of a
+/// literal elimination can be kept sorted and disjoint, allowing them to be
+/// binary searched during evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralPattern {
+    /// Match a single literal
+    Intro(LiteralIntro),
+    /// Match the literals in an inclusive range
+    Range(LiteralIntro, LiteralIntro),
+    /// Match the strings that start with a prefix
+    Prefix(Rc<str>),
+}
+
+impl LiteralPattern {
+    pub fn alpha_eq(&self, other: &LiteralPattern) -> bool {
+        match (self, other) {
+            (LiteralPattern::Intro(l1), LiteralPattern::Intro(l2)) => l1.alpha_eq(l2),
+            (LiteralPattern::Range(start1, end1), LiteralPattern::Range(start2, end2)) => {
+                start1.alpha_eq(start2) && end1.alpha_eq(end2)
+            },
+            (LiteralPattern::Prefix(prefix1), LiteralPattern::Prefix(prefix2)) => {
+                prefix1 == prefix2
+            },
+            (_, _) => false,
+        }
+    }
+
+    /// The smallest literal that the pattern matches.
+    pub fn start(&self) -> LiteralIntro {
+        match self {
+            LiteralPattern::Intro(literal_intro) => literal_intro.clone(),
+            LiteralPattern::Range(start, _) => start.clone(),
+            LiteralPattern::Prefix(prefix) => LiteralIntro::String(prefix.clone()),
+        }
+    }
+
+    /// Compare the literals matched by the pattern with a literal, returning
+    /// `Ordering::Equal` if the literal is matched by the pattern.
+    pub fn cmp_literal(&self, literal_intro: &LiteralIntro) -> Option<Ordering> {
+        match self {
+            LiteralPattern::Intro(pattern_intro) => pattern_intro.partial_cmp(literal_intro),
+            LiteralPattern::Range(start, end) => {
+                match (
+                    start.partial_cmp(literal_intro)?,
+                    end.partial_cmp(literal_intro)?,
+                ) {
+                    (_, Ordering::Less) => Some(Ordering::Less),
+                    (Ordering::Greater, _) => Some(Ordering::Greater),
+                    (_, _) => Some(Ordering::Equal),
+                }
+            },
+            LiteralPattern::Prefix(prefix) => match literal_intro {
+                LiteralIntro::String(value) if value.starts_with(prefix.as_ref()) => {
+                    Some(Ordering::Equal)
+                },
+                // Strings that don't start with the prefix are either less
+                // than the prefix, or greater than all of the strings that
+                // start with it
+                _ => LiteralIntro::String(prefix.clone()).partial_cmp(literal_intro),
+            },
+        }
+    }
+
+    /// Returns `true` if the literal is matched by the pattern.
+    pub fn matches(&self, literal_intro: &LiteralIntro) -> bool {
+        self.cmp_literal(literal_intro) == Some(Ordering::Equal)
+    }
+
+    /// Returns `true` if there is a literal that is matched by both patterns.
+    pub fn overlaps(&self, other: &LiteralPattern) -> bool {
+        match (self, other) {
+            (LiteralPattern::Intro(literal_intro), pattern)
+            | (pattern, LiteralPattern::Intro(literal_intro)) => pattern.matches(literal_intro),
+            (LiteralPattern::Range(start1, end1), LiteralPattern::Range(start2, end2)) => {
+                start1 <= end2 && start2 <= end1
+            },
+            (LiteralPattern::Prefix(prefix1), LiteralPattern::Prefix(prefix2)) => {
+                prefix1.starts_with(prefix2.as_ref()) || prefix2.starts_with(prefix1.as_ref())
+            },
+            (LiteralPattern::Range(start, end), pattern @ LiteralPattern::Prefix(_))
+            | (pattern @ LiteralPattern::Prefix(_), LiteralPattern::Range(start, end)) => {
+                pattern.cmp_literal(start) != Some(Ordering::Less)
+                    && pattern.cmp_literal(end) != Some(Ordering::Greater)
+            },
+        }
+    }
+}
+
+impl fmt::Display for LiteralPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiteralPattern::Intro(literal_intro) => write!(f, "{}", literal_intro),
+            LiteralPattern::Range(start, end) => write!(f, "{}..{}", start, end),
+            LiteralPattern::Prefix(prefix) => write!(f, "{:?} ++ _", prefix),
+        }
+    }
+}
+
+impl From<LiteralIntro> for LiteralPattern {
+    fn from(src: LiteralIntro) -> LiteralPattern {
+        LiteralPattern::Intro(src)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{f32, f64};
@@ -249,4 +357,35 @@ mod tests {
     fn alpha_eq_f64_neg_zero_neg_zero() {
         assert!(LiteralIntro::alpha_eq(&F64(-0.0), &F64(-0.0)));
     }
+
+    #[test]
+    fn cmp_literal_range() {
+        let pattern = LiteralPattern::Range(LiteralIntro::Char('b'), LiteralIntro::Char('d'));
+
+        assert_eq!(pattern.cmp_literal(&'a'.into()), Some(Ordering::Greater));
+        assert_eq!(pattern.cmp_literal(&'b'.into()), Some(Ordering::Equal));
+        assert_eq!(pattern.cmp_literal(&'d'.into()), Some(Ordering::Equal));
+        assert_eq!(pattern.cmp_literal(&'e'.into()), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn cmp_literal_prefix() {
+        let pattern = LiteralPattern::Prefix(Rc::from("ab"));
+
+        assert_eq!(pattern.cmp_literal(&"a".into()), Some(Ordering::Greater));
+        assert_eq!(pattern.cmp_literal(&"ab".into()), Some(Ordering::Equal));
+        assert_eq!(pattern.cmp_literal(&"abz".into()), Some(Ordering::Equal));
+        assert_eq!(pattern.cmp_literal(&"ac".into()), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn overlaps_prefix_range() {
+        let pattern = LiteralPattern::Prefix(Rc::from("ab"));
+        let range = |start: &str, end: &str| LiteralPattern::Range(start.into(), end.into());
+
+        assert!(pattern.overlaps(&range("a", "ab")));
+        assert!(pattern.overlaps(&range("abc", "b")));
+        assert!(!pattern.overlaps(&range("a", "aa")));
+        assert!(!pattern.overlaps(&range("ac", "b")));
+    }
 }
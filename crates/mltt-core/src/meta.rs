@@ -1,8 +1,9 @@
 use mltt_span::FileSpan;
 use std::fmt;
-use std::rc::Rc;
 
 use crate::domain;
+use crate::literal::LiteralType;
+use crate::{var, Rc};
 
 /// Metavariable index.
 ///
@@ -31,11 +32,47 @@ pub enum Solution {
     Solved(Rc<domain::Value>),
 }
 
+/// A numeric literal whose type was not known when it was elaborated.
+///
+/// The value of the literal is represented using a metavariable, which is
+/// solved once the type of the literal is known, or once the type has been
+/// defaulted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingLiteral {
+    /// The metavariable that stands for the value of the literal.
+    pub index: Index,
+    /// The span of the literal.
+    pub span: FileSpan,
+    /// The source of the literal, to be parsed once its type is known.
+    pub src: Rc<str>,
+    /// Whether this is a float literal, as opposed to an integer literal.
+    pub is_float: bool,
+    /// The metavariable, applied to the variables that were in scope.
+    pub value: Rc<domain::Value>,
+    /// The type of the literal.
+    pub ty: Rc<domain::Type>,
+    /// The values that were in scope when the literal was elaborated.
+    pub env: var::Env<Rc<domain::Value>>,
+}
+
 /// An environment of solved and unsolved metavariables.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Env {
     /// The solutions.
     solutions: Vec<(FileSpan, Solution, Rc<domain::Type>)>,
+    /// Metavariables that stand for placeholders in the concrete syntax. Unlike
+    /// holes, these must be solved during elaboration.
+    placeholders: Vec<Index>,
+    /// Literals that are waiting for their types to be inferred.
+    pending_literals: Vec<PendingLiteral>,
+    /// Literals whose types were defaulted, because they could not be inferred.
+    defaulted_literals: Vec<(Index, FileSpan, LiteralType)>,
+}
+
+impl Default for Env {
+    fn default() -> Env {
+        Env::new()
+    }
 }
 
 impl Env {
@@ -43,6 +80,9 @@ impl Env {
     pub fn new() -> Env {
         Env {
             solutions: Vec::new(),
+            placeholders: Vec::new(),
+            pending_literals: Vec::new(),
+            defaulted_literals: Vec::new(),
         }
     }
 
@@ -51,6 +91,35 @@ impl Env {
         self.solutions.get(index.0 as usize)
     }
 
+    /// Iterate over the metavariables in the environment, in the order that
+    /// they were created.
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &(FileSpan, Solution, Rc<domain::Type>))> {
+        self.solutions
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (Index(index as u32), entry))
+    }
+
+    /// The index that will be given to the next metavariable.
+    pub fn next_index(&self) -> Index {
+        Index(self.solutions.len() as u32)
+    }
+
+    /// Iterate over the placeholders that were created from `start` onwards,
+    /// but have not yet been solved.
+    pub fn unsolved_placeholders(
+        &self,
+        start: Index,
+    ) -> impl Iterator<Item = (Index, FileSpan)> + '_ {
+        self.placeholders
+            .iter()
+            .filter(move |index| **index >= start)
+            .filter_map(move |index| match self.lookup_solution(*index)? {
+                (span, Solution::Unsolved, _) => Some((*index, *span)),
+                (_, Solution::Solved(_), _) => None,
+            })
+    }
+
     /// Add a solution to the given metavariable index.
     pub fn add_solved(&mut self, index: Index, solved: Rc<domain::Value>) {
         match self.solutions.get_mut(index.0 as usize) {
@@ -66,4 +135,42 @@ impl Env {
         self.solutions.push((span, Solution::Unsolved, ty));
         index
     }
+
+    /// Create a fresh metavariable index for a placeholder, which must be
+    /// solved during elaboration.
+    pub fn add_placeholder(&mut self, span: FileSpan, ty: Rc<domain::Type>) -> Index {
+        let index = self.add_unsolved(span, ty);
+        self.placeholders.push(index);
+        index
+    }
+
+    /// Add a literal that is waiting for its type to be inferred.
+    pub fn add_pending_literal(&mut self, literal: PendingLiteral) {
+        self.pending_literals.push(literal);
+    }
+
+    /// Remove the pending literals that were created from `start` onwards.
+    pub fn take_pending_literals(&mut self, start: Index) -> Vec<PendingLiteral> {
+        let (taken, remaining) =
+            (self.pending_literals.drain(..)).partition(|literal| literal.index >= start);
+        self.pending_literals = remaining;
+        taken
+    }
+
+    /// Record that the type of a literal was defaulted.
+    pub fn add_defaulted_literal(&mut self, index: Index, span: FileSpan, ty: LiteralType) {
+        self.defaulted_literals.push((index, span, ty));
+    }
+
+    /// Iterate over the literals created from `start` onwards whose types were
+    /// defaulted.
+    pub fn defaulted_literals(
+        &self,
+        start: Index,
+    ) -> impl Iterator<Item = (FileSpan, &LiteralType)> + '_ {
+        self.defaulted_literals
+            .iter()
+            .filter(move |(index, _, _)| *index >= start)
+            .map(|(_, span, ty)| (*span, ty))
+    }
 }
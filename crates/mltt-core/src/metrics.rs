@@ -0,0 +1,195 @@
+//! Size and depth metrics for core terms and domain values.
+//!
+//! These are useful for spotting terms that blow up during elaboration or
+//! normalization, for example due to definitions being eagerly unfolded.
+
+use std::cmp;
+use std::fmt;
+
+use crate::domain::{AppClosure, Elim, LiteralClosure, Value};
+use crate::syntax::{Item, Term};
+
+/// The size and depth of a tree of nodes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Metrics {
+    /// The number of nodes in the tree.
+    pub size: usize,
+    /// The number of nodes on the longest path from the root to a leaf.
+    pub depth: usize,
+}
+
+impl Metrics {
+    /// The metrics of a node with no children.
+    pub fn leaf() -> Metrics {
+        Metrics { size: 1, depth: 1 }
+    }
+
+    /// The metrics of a node with the given children.
+    pub fn node(children: impl IntoIterator<Item = Metrics>) -> Metrics {
+        children
+            .into_iter()
+            .fold(Metrics::leaf(), |acc, child| Metrics {
+                size: acc.size + child.size,
+                depth: cmp::max(acc.depth, child.depth + 1),
+            })
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "size {}, depth {}", self.size, self.depth)
+    }
+}
+
+impl Item {
+    /// The size and depth of the term in the item.
+    pub fn metrics(&self) -> Metrics {
+        match self {
+            Item::Declaration(_, _, term) | Item::Definition(_, _, term) => term.metrics(),
+        }
+    }
+}
+
+impl Term {
+    /// The size and depth of the term.
+    pub fn metrics(&self) -> Metrics {
+        match self {
+            Term::Var(_) | Term::Meta(_) | Term::Prim(_) => Metrics::leaf(),
+
+            Term::Ann(term, term_ty) => Metrics::node(vec![term.metrics(), term_ty.metrics()]),
+            Term::Let(items, body) => {
+                Metrics::node(items.iter().map(Item::metrics).chain(Some(body.metrics())))
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => Metrics::leaf(),
+            Term::LiteralElim(scrutinee, clauses, default_body) => Metrics::node(
+                Some(scrutinee.metrics())
+                    .into_iter()
+                    .chain(clauses.iter().map(|(_, body)| body.metrics()))
+                    .chain(Some(default_body.metrics())),
+            ),
+
+            Term::FunType(_, _, _, param_ty, body_ty) => {
+                Metrics::node(vec![param_ty.metrics(), body_ty.metrics()])
+            },
+            Term::FunIntro(_, _, body) => Metrics::node(vec![body.metrics()]),
+            Term::FunElim(fun, _, arg) => Metrics::node(vec![fun.metrics(), arg.metrics()]),
+
+            Term::RecordType(ty_fields) => {
+                Metrics::node(ty_fields.iter().map(|(_, _, _, ty)| ty.metrics()))
+            },
+            Term::RecordIntro(intro_fields) => {
+                Metrics::node(intro_fields.iter().map(|(_, term)| term.metrics()))
+            },
+            Term::RecordElim(record, _) => Metrics::node(vec![record.metrics()]),
+
+            Term::ArrayType(elem_ty) => Metrics::node(vec![elem_ty.metrics()]),
+            Term::ArrayIntro(elems) => Metrics::node(elems.iter().map(|elem| elem.metrics())),
+
+            Term::Universe(_) => Metrics::leaf(),
+        }
+    }
+}
+
+impl Value {
+    /// The size and depth of the value.
+    ///
+    /// The bodies of closures are counted, but not the environments that they
+    /// capture, because these are shared with the rest of the program.
+    pub fn metrics(&self) -> Metrics {
+        match self {
+            Value::Neutral(_, spine) => Metrics::node(spine.iter().map(Elim::metrics)),
+
+            Value::LiteralType(_) | Value::LiteralIntro(_) => Metrics::leaf(),
+
+            Value::FunType(_, _, _, param_ty, body_ty) => {
+                Metrics::node(vec![param_ty.metrics(), body_ty.metrics()])
+            },
+            Value::FunIntro(_, _, body) => Metrics::node(vec![body.metrics()]),
+
+            Value::RecordTypeExtend(_, _, _, ty, rest) => {
+                Metrics::node(vec![ty.metrics(), rest.metrics()])
+            },
+            Value::RecordTypeEmpty => Metrics::leaf(),
+            Value::RecordIntro(intro_fields) => {
+                Metrics::node(intro_fields.iter().map(|(_, value)| value.metrics()))
+            },
+
+            Value::ArrayType(elem_ty) => Metrics::node(vec![elem_ty.metrics()]),
+            Value::ArrayIntro(elems) => Metrics::node(elems.iter().map(|elem| elem.metrics())),
+
+            Value::Universe(_) => Metrics::leaf(),
+        }
+    }
+}
+
+impl Elim {
+    /// The size and depth of the eliminator.
+    pub fn metrics(&self) -> Metrics {
+        match self {
+            Elim::Literal(closure) => closure.metrics(),
+            Elim::Fun(_, arg) => Metrics::node(vec![arg.metrics()]),
+            Elim::Record(_) => Metrics::leaf(),
+        }
+    }
+}
+
+impl AppClosure {
+    /// The size and depth of the body of the closure.
+    pub fn metrics(&self) -> Metrics {
+        self.term.metrics()
+    }
+}
+
+impl LiteralClosure {
+    /// The size and depth of the clauses of the closure.
+    pub fn metrics(&self) -> Metrics {
+        Metrics::node(
+            (self.clauses.iter())
+                .map(|(_, body)| body.metrics())
+                .chain(Some(self.default.metrics())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::Head;
+    use crate::{var, AppMode, DocString, Rc};
+
+    #[test]
+    fn term_metrics() {
+        // Fun (A : Type) -> A -> A
+        let term = Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("A".to_owned()),
+            Rc::from(Term::universe(0)),
+            Rc::from(Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                None,
+                Rc::from(Term::var(0)),
+                Rc::from(Term::var(1)),
+            )),
+        );
+
+        assert_eq!(term.metrics(), Metrics { size: 5, depth: 3 });
+    }
+
+    #[test]
+    fn value_metrics() {
+        // f a b
+        let arg = Rc::from(Value::var(1));
+        let value = Value::Neutral(
+            Head::Var(var::Level(0)),
+            vec![
+                Elim::Fun(AppMode::Explicit, arg.clone()),
+                Elim::Fun(AppMode::Explicit, arg),
+            ],
+        );
+
+        assert_eq!(value.metrics(), Metrics { size: 5, depth: 3 });
+    }
+}
@@ -4,11 +4,65 @@
 //! evaluation to `Value`s in weak-head-normal-form, and then reading it back
 //! `Normal` terms.
 
-use std::rc::Rc;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
-use crate::domain::{AppClosure, Elim, Head, LiteralClosure, Spine, Type, Value};
+use crate::domain::{AppClosure, Elim, Head, LiteralClosure, Spine, Thunk, Type, Value};
+use crate::literal::{LiteralIntro, LiteralPattern};
 use crate::syntax::{Item, Term};
-use crate::{meta, prim, var, AppMode, Label};
+use crate::telescope::Telescope;
+use crate::{meta, prim, var, AppMode, DocString, Label, Rc};
+
+/// The order in which terms are evaluated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Evaluate definitions and arguments before they are used.
+    #[default]
+    CallByValue,
+    /// Suspend definitions and arguments in thunks, evaluating them the first
+    /// time that they are needed and reusing the result after that.
+    CallByNeed,
+}
+
+/// A limit on the number of steps that a conversion check can take, so that
+/// pathological comparisons fail quickly rather than appearing to hang.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConversionBudget {
+    /// The greatest number of steps that can be taken, or `None` if there is
+    /// no limit.
+    limit: Option<u32>,
+    /// The number of steps that have been taken so far.
+    steps: u32,
+}
+
+impl ConversionBudget {
+    /// Create a budget that allows the given number of steps.
+    pub fn new(limit: Option<u32>) -> ConversionBudget {
+        ConversionBudget { limit, steps: 0 }
+    }
+
+    /// Create a budget that allows any number of steps.
+    pub fn unlimited() -> ConversionBudget {
+        ConversionBudget::new(None)
+    }
+
+    /// The greatest number of steps that can be taken.
+    pub fn limit(&self) -> Option<u32> {
+        self.limit
+    }
+
+    /// Take a step, returning `false` if the budget has been exceeded.
+    pub fn spend(&mut self) -> bool {
+        self.steps = self.steps.saturating_add(1);
+        !self.is_exceeded()
+    }
+
+    /// Returns `true` if more steps were taken than the budget allows.
+    pub fn is_exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.steps > limit)
+    }
+}
 
 /// Evaluate a primitive.
 pub fn eval_prim<'spine>(
@@ -20,7 +74,7 @@ pub fn eval_prim<'spine>(
         .lookup_entry(prim_name)
         .ok_or_else(|| format!("eval: primitive not found: {:?}", prim_name))?;
 
-    match prim.interpret(spine) {
+    match prims.interpret(prim_name, prim, spine) {
         Some(result) => result,
         None => Ok((Rc::from(Value::prim(prim_name.clone())), spine)),
     }
@@ -49,15 +103,9 @@ pub fn eval_literal_elim(
 ) -> Result<Rc<Value>, String> {
     match scrutinee.as_ref() {
         Value::LiteralIntro(literal_intro) => {
-            let index = closure.clauses.binary_search_by(|(l, _)| {
-                l.partial_cmp(literal_intro).unwrap() // NaN?
-            });
-
-            let clause_body = match index {
-                Ok(index) => &closure.clauses.get(index).unwrap().1,
-                Err(_) => &closure.default,
-            };
-            eval_term(prims, metas, &closure.values, clause_body)
+            let clause_body =
+                find_literal_clause(&closure.clauses, literal_intro).unwrap_or(&closure.default);
+            eval_term_with_strategy(prims, metas, closure.strategy, &closure.values, clause_body)
         },
         Value::Neutral(head, spine) => {
             let mut spine = spine.clone();
@@ -68,6 +116,54 @@ pub fn eval_literal_elim(
     }
 }
 
+/// Find the body of the clause that matches a literal.
+///
+/// The clauses are sorted and disjoint, so we can binary search them. If the
+/// clauses match every integer between the first and last clause, we index
+/// the clause directly instead, like a jump table.
+pub(crate) fn find_literal_clause<'a>(
+    clauses: &'a [(LiteralPattern, Rc<Term>)],
+    literal_intro: &LiteralIntro,
+) -> Option<&'a Rc<Term>> {
+    if let (Some((LiteralPattern::Intro(first), _)), Some((LiteralPattern::Intro(last), _))) =
+        (clauses.first(), clauses.last())
+    {
+        if let (Some(first), Some(last), Some(value)) =
+            (int_value(first), int_value(last), int_value(literal_intro))
+        {
+            if last - first + 1 == clauses.len() as i128 {
+                let index = usize::try_from(value - first).ok()?;
+                return clauses.get(index).map(|(_, body)| body);
+            }
+        }
+    }
+
+    // Incomparable literals (ie. `NaN`) fall through to the default clause
+    let index = clauses.binary_search_by(|(pattern, _)| {
+        (pattern.cmp_literal(literal_intro)).unwrap_or(Ordering::Less)
+    });
+    index.ok().map(|index| &clauses[index].1)
+}
+
+/// The value of an integer literal.
+fn int_value(literal_intro: &LiteralIntro) -> Option<i128> {
+    match literal_intro {
+        LiteralIntro::U8(value) => Some(i128::from(*value)),
+        LiteralIntro::U16(value) => Some(i128::from(*value)),
+        LiteralIntro::U32(value) => Some(i128::from(*value)),
+        LiteralIntro::U64(value) => Some(i128::from(*value)),
+        LiteralIntro::S8(value) => Some(i128::from(*value)),
+        LiteralIntro::S16(value) => Some(i128::from(*value)),
+        LiteralIntro::S32(value) => Some(i128::from(*value)),
+        LiteralIntro::S64(value) => Some(i128::from(*value)),
+        LiteralIntro::String(_)
+        | LiteralIntro::Char(_)
+        | LiteralIntro::Bool(_)
+        | LiteralIntro::F32(_)
+        | LiteralIntro::F64(_) => None,
+    }
+}
+
 /// Return the field in from a record.
 pub fn eval_record_elim(record: Rc<Value>, label: &Label) -> Result<Rc<Value>, String> {
     match record.as_ref() {
@@ -126,7 +222,7 @@ pub fn app_closure(
 ) -> Result<Rc<Value>, String> {
     let mut values = closure.values.clone();
     values.add_entry(arg);
-    eval_term(prims, metas, &values, &closure.term)
+    eval_term_with_strategy(prims, metas, closure.strategy, &values, &closure.term)
 }
 
 /// Instantiate a closure in an environment of the given size.
@@ -148,9 +244,30 @@ pub fn eval_term(
     values: &var::Env<Rc<Value>>,
     term: &Rc<Term>,
 ) -> Result<Rc<Value>, String> {
+    eval_term_with_strategy(prims, metas, Strategy::CallByValue, values, term)
+}
+
+/// Evaluate a term using the given evaluation strategy.
+///
+/// The closures in the resulting value remember the strategy, so that it is
+/// also used when they are later instantiated.
+pub fn eval_term_with_strategy(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    strategy: Strategy,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Value>, String> {
+    let eval_term = |values: &var::Env<Rc<Value>>, term: &Rc<Term>| {
+        eval_term_with_strategy(prims, metas, strategy, values, term)
+    };
+
     match term.as_ref() {
         Term::Var(var_index) => match values.lookup_entry(*var_index) {
-            Some(value) => Ok(value.clone()),
+            Some(value) => match value.as_ref() {
+                Value::Neutral(Head::Thunk(_), _) => force_value(prims, metas, value),
+                _ => Ok(value.clone()),
+            },
             None => Err("eval: variable not found".to_owned()),
         },
         Term::Meta(meta_level) => match metas.lookup_solution(*meta_level) {
@@ -160,49 +277,54 @@ pub fn eval_term(
         },
         Term::Prim(prim_name) => Ok(eval_prim(prims, prim_name, &[])?.0),
 
-        Term::Ann(term, _) => eval_term(prims, metas, values, term),
+        Term::Ann(term, _) => eval_term(values, term),
         Term::Let(items, body) => {
             let mut values = values.clone();
             for item in items {
                 if let Item::Definition(_, _, term) = item {
-                    values.add_entry(eval_term(prims, metas, &values, term)?);
+                    values.add_entry(suspend_term(prims, metas, strategy, &values, term)?);
                 }
             }
-            eval_term(prims, metas, &values, body)
+            eval_term(&values, body)
         },
 
         // Literals
-        Term::LiteralType(ty) => Ok(Rc::from(Value::literal_ty(ty.clone()))),
+        Term::LiteralType(ty) => Ok(Rc::from(Value::literal_ty(*ty))),
         Term::LiteralIntro(intro) => Ok(Rc::from(Value::literal_intro(intro.clone()))),
         Term::LiteralElim(scrutinee, clauses, default_body) => {
-            let scrutinee = eval_term(prims, metas, values, scrutinee)?;
-            let closure =
-                LiteralClosure::new(clauses.clone(), default_body.clone(), values.clone());
+            let scrutinee = eval_term(values, scrutinee)?;
+            let closure = LiteralClosure::new(
+                clauses.clone(),
+                default_body.clone(),
+                values.clone(),
+                strategy,
+            );
 
             eval_literal_elim(prims, metas, scrutinee, closure)
         },
 
         // Functions
-        Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+        Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => {
+            let doc = doc.clone();
             let app_mode = app_mode.clone();
             let name_hint = name_hint.clone();
-            let param_ty = eval_term(prims, metas, values, param_ty)?;
-            let body_ty = AppClosure::new(body_ty.clone(), values.clone());
+            let param_ty = eval_term(values, param_ty)?;
+            let body_ty = AppClosure::new(body_ty.clone(), values.clone(), strategy);
 
             Ok(Rc::from(Value::FunType(
-                app_mode, name_hint, param_ty, body_ty,
+                doc, app_mode, name_hint, param_ty, body_ty,
             )))
         },
         Term::FunIntro(app_mode, name_hint, body) => {
             let app_mode = app_mode.clone();
             let name_hint = name_hint.clone();
-            let body = AppClosure::new(body.clone(), values.clone());
+            let body = AppClosure::new(body.clone(), values.clone(), strategy);
 
             Ok(Rc::from(Value::FunIntro(app_mode, name_hint, body)))
         },
         Term::FunElim(fun, app_mode, arg) => {
-            let fun = eval_term(prims, metas, values, fun)?;
-            let arg = eval_term(prims, metas, values, arg)?;
+            let fun = eval_term(values, fun)?;
+            let arg = suspend_term(prims, metas, strategy, values, arg)?;
 
             eval_fun_elim(prims, metas, fun, app_mode, arg)
         },
@@ -214,9 +336,13 @@ pub fn eval_term(
                 let doc = doc.clone();
                 let label = label.clone();
                 let name_hint = name_hint.clone();
-                let ty = eval_term(prims, metas, values, ty)?;
-                let rest_fields = rest.iter().cloned().collect(); // FIXME: Seems expensive?
-                let rest = AppClosure::new(Rc::from(Term::RecordType(rest_fields)), values.clone());
+                let ty = eval_term(values, ty)?;
+                let rest_fields = rest.to_vec(); // FIXME: Seems expensive?
+                let rest = AppClosure::new(
+                    Rc::from(Term::RecordType(rest_fields)),
+                    values.clone(),
+                    strategy,
+                );
 
                 Ok(Rc::from(Value::RecordTypeExtend(
                     doc, label, name_hint, ty, rest,
@@ -224,15 +350,29 @@ pub fn eval_term(
             },
         },
         Term::RecordIntro(fields) => {
+            // Later fields are evaluated in the scope of the earlier fields
+            let mut values = values.clone();
             let fields = fields
                 .iter()
-                .map(|(label, term)| Ok((label.clone(), eval_term(prims, metas, values, term)?)))
+                .map(|(label, term)| {
+                    let term = eval_term(&values, term)?;
+                    values.add_entry(term.clone());
+                    Ok((label.clone(), term))
+                })
                 .collect::<Result<_, String>>()?;
 
             Ok(Rc::from(Value::RecordIntro(fields)))
         },
-        Term::RecordElim(record, label) => {
-            eval_record_elim(eval_term(prims, metas, values, record)?, label)
+        Term::RecordElim(record, label) => eval_record_elim(eval_term(values, record)?, label),
+
+        // Arrays
+        Term::ArrayType(elem_ty) => Ok(Rc::from(Value::ArrayType(eval_term(values, elem_ty)?))),
+        Term::ArrayIntro(elems) => {
+            let elems = (elems.iter())
+                .map(|elem| eval_term(values, elem))
+                .collect::<Result<_, String>>()?;
+
+            Ok(Rc::from(Value::ArrayIntro(elems)))
         },
 
         // Universes
@@ -240,6 +380,50 @@ pub fn eval_term(
     }
 }
 
+/// Evaluate a definition or argument according to the evaluation strategy,
+/// suspending it in a thunk if it is not needed yet.
+fn suspend_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    strategy: Strategy,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Value>, String> {
+    match (strategy, term.as_ref()) {
+        (Strategy::CallByValue, _) => eval_term_with_strategy(prims, metas, strategy, values, term),
+        // Avoid building up chains of thunks when passing variables along
+        (Strategy::CallByNeed, Term::Var(var_index)) => match values.lookup_entry(*var_index) {
+            Some(value) => Ok(value.clone()),
+            None => Err("eval: variable not found".to_owned()),
+        },
+        (Strategy::CallByNeed, _) => {
+            let thunk = Thunk::new(term.clone(), values.clone());
+            Ok(Rc::from(Value::thunk(thunk)))
+        },
+    }
+}
+
+/// Force a thunk, evaluating its term if this has not been done already.
+pub fn force_thunk(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    thunk: &Thunk,
+) -> Result<Rc<Value>, String> {
+    if let Some(value) = thunk.value() {
+        return Ok(value);
+    }
+
+    let value = eval_term_with_strategy(
+        prims,
+        metas,
+        Strategy::CallByNeed,
+        &thunk.values,
+        &thunk.term,
+    )?;
+    thunk.set_value(value.clone());
+    Ok(value)
+}
+
 /// Read a value back into the core syntax, normalizing as required.
 pub fn read_back_value(
     prims: &prim::Env,
@@ -247,143 +431,757 @@ pub fn read_back_value(
     size: var::Size,
     term: &Rc<Value>,
 ) -> Result<Rc<Term>, String> {
-    match term.as_ref() {
-        Value::Neutral(head, spine) => read_back_neutral(prims, metas, size, head, spine),
+    ReadBack::new(prims, metas).read_back_value(size, term)
+}
 
-        // Literals
-        Value::LiteralType(literal_ty) => Ok(Rc::from(Term::literal_ty(literal_ty.clone()))),
-        Value::LiteralIntro(literal_intro) => {
-            Ok(Rc::from(Term::literal_intro(literal_intro.clone())))
-        },
+/// Read a neutral value back into the core syntax, normalizing as required.
+pub fn read_back_neutral(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    head: &Head,
+    spine: &Spine,
+) -> Result<Rc<Term>, String> {
+    ReadBack::new(prims, metas).read_back_neutral(size, head, spine)
+}
 
-        // Functions
-        Value::FunType(app_mode, name_hint, param_ty, body_ty) => {
-            let app_mode = app_mode.clone();
-            let name_hint = name_hint.clone();
-            let body_ty = inst_closure(prims, metas, size, body_ty)?;
-            let param_ty = read_back_value(prims, metas, size, param_ty)?;
-            let body_ty = read_back_value(prims, metas, size + 1, &body_ty)?;
+/// Read a value back into the core syntax, binding the sub-values that are
+/// shared between different parts of the value with a `let`, rather than
+/// duplicating their normal forms.
+///
+/// Sub-values are considered to be shared if they are the same `Rc`. Only
+/// sub-values that don't depend on the variables bound inside the value are
+/// bound, with the bindings placed at the root of the resulting term. The
+/// bindings are not annotated with types, so the result is intended for
+/// display, rather than for further type checking.
+pub fn read_back_value_shared(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    value: &Rc<Value>,
+) -> Result<Rc<Term>, String> {
+    let mut read_back = ReadBack::new(prims, metas);
+    read_back.sharing = Some(Sharing::default());
+    let term = read_back.read_back_value(size, value)?;
 
-            Ok(Rc::from(Term::FunType(
-                app_mode, name_hint, param_ty, body_ty,
-            )))
-        },
-        Value::FunIntro(app_mode, name_hint, body) => {
-            let app_mode = app_mode.clone();
-            let name_hint = name_hint.clone();
-            let body = inst_closure(prims, metas, size, body)?;
-            let body = read_back_value(prims, metas, size + 1, &body)?;
+    match read_back.sharing {
+        Some(sharing) => Ok(sharing.bind_shared(size, &term)),
+        None => Ok(term),
+    }
+}
 
-            Ok(Rc::from(Term::FunIntro(app_mode, name_hint, body)))
-        },
+/// A term that has been read back with some of its subterms elided, along
+/// with the placeholders that stand in for those subterms.
+pub type ElidedTerm = (Rc<Term>, Vec<Rc<Term>>);
 
-        // Records
-        Value::RecordTypeExtend(doc, label, name_hint, term_ty, rest_ty) => {
-            let mut size = size;
+/// Read a value in weak head normal form back into the core syntax, without
+/// evaluating the parts of it that are still unevaluated. Thunks that have not
+/// been forced yet, and the bodies of functions whose terms are larger than
+/// `elide_size`, are replaced with placeholder terms. These are returned along
+/// with the term, so that they can be displayed as `...`.
+pub fn read_back_whnf(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    value: &Rc<Value>,
+    elide_size: usize,
+) -> Result<ElidedTerm, String> {
+    let mut read_back = ReadBack::new(prims, metas);
+    read_back.elision = Some(Elision {
+        size: elide_size,
+        placeholders: Vec::new(),
+    });
+    let term = read_back.read_back_value(size, value)?;
+
+    match read_back.elision {
+        Some(elision) => Ok((term, elision.placeholders)),
+        None => Ok((term, Vec::new())),
+    }
+}
 
-            let term_ty = read_back_value(prims, metas, size, term_ty)?;
+/// The unevaluated parts of a value that are left out when reading it back.
+struct Elision {
+    /// The size, in nodes, above which the bodies of functions are elided.
+    size: usize,
+    /// The terms that stand in for the parts that have been elided so far.
+    placeholders: Vec<Rc<Term>>,
+}
 
-            let mut rest_ty = inst_closure(prims, metas, size, rest_ty)?;
-            let mut field_tys = vec![(doc.clone(), label.clone(), name_hint.clone(), term_ty)];
+impl Elision {
+    /// Create a new placeholder. Placeholders are told apart by their
+    /// addresses, so each one is a separate allocation.
+    fn placeholder(&mut self) -> Rc<Term> {
+        let placeholder = Rc::from(Term::RecordIntro(Vec::new()));
+        self.placeholders.push(placeholder.clone());
+        placeholder
+    }
+}
+
+/// State used when reading values back into the core syntax.
+struct ReadBack<'a> {
+    prims: &'a prim::Env,
+    metas: &'a meta::Env,
+    /// The sub-values that have been read back so far, if sharing is being
+    /// preserved.
+    sharing: Option<Sharing>,
+    /// Whether to contract record introductions that are eta-expansions of
+    /// neutral records.
+    ///
+    /// This is only sound when the result is compared against a term of the
+    /// same type, as is the case during conversion checking, because we
+    /// don't know the type of the neutral record when reading it back.
+    eta: bool,
+    /// The unevaluated parts of the value that have been elided, if they are
+    /// being elided.
+    elision: Option<Elision>,
+}
+
+impl<'a> ReadBack<'a> {
+    fn new(prims: &'a prim::Env, metas: &'a meta::Env) -> ReadBack<'a> {
+        ReadBack {
+            prims,
+            metas,
+            sharing: None,
+            eta: false,
+            elision: None,
+        }
+    }
 
-            while let Value::RecordTypeExtend(doc, label, name_hint, next_term_ty, next_rest_ty) =
-                rest_ty.as_ref()
-            {
-                size += 1;
-                let next_term_ty = read_back_value(prims, metas, size, next_term_ty)?;
-                field_tys.push((doc.clone(), label.clone(), name_hint.clone(), next_term_ty));
-                rest_ty = inst_closure(prims, metas, size, next_rest_ty)?;
+    fn read_back_value(&mut self, size: var::Size, value: &Rc<Value>) -> Result<Rc<Term>, String> {
+        if let Some(sharing) = &mut self.sharing {
+            if let Some(term) = sharing.lookup(size, value) {
+                return Ok(term);
             }
+        }
 
-            Ok(Rc::from(Term::RecordType(field_tys)))
-        },
-        Value::RecordTypeEmpty => Ok(Rc::from(Term::RecordType(Vec::new()))),
-        Value::RecordIntro(fields) => {
-            let fields = fields
-                .iter()
-                .map(|(label, term)| {
-                    Ok((label.clone(), read_back_value(prims, metas, size, term)?))
-                })
-                .collect::<Result<_, String>>()?;
+        let term = self.read_back_value_step(size, value)?;
 
-            Ok(Rc::from(Term::RecordIntro(fields)))
-        },
+        if let Some(sharing) = &mut self.sharing {
+            sharing.insert(size, value, &term);
+        }
 
-        // Universes
-        Value::Universe(level) => Ok(Rc::from(Term::universe(*level))),
+        Ok(term)
+    }
+
+    fn read_back_value_step(
+        &mut self,
+        size: var::Size,
+        term: &Rc<Value>,
+    ) -> Result<Rc<Term>, String> {
+        let (prims, metas) = (self.prims, self.metas);
+
+        match term.as_ref() {
+            // Read back the solution if the metavariable has been solved since
+            // the value was constructed
+            Value::Neutral(Head::Meta(_), _) => {
+                let forced = force_value(prims, metas, term)?;
+                match forced.as_ref() {
+                    Value::Neutral(head @ Head::Meta(_), spine) => {
+                        self.read_back_neutral(size, head, spine)
+                    },
+                    _ => self.read_back_value(size, &forced),
+                }
+            },
+            Value::Neutral(Head::Thunk(thunk), _) => match &mut self.elision {
+                Some(elision) if thunk.value().is_none() => Ok(elision.placeholder()),
+                Some(_) | None => {
+                    let forced = force_value(prims, metas, term)?;
+                    self.read_back_value(size, &forced)
+                },
+            },
+            Value::Neutral(head, spine) => self.read_back_neutral(size, head, spine),
+
+            // Literals
+            Value::LiteralType(literal_ty) => Ok(Rc::from(Term::literal_ty(*literal_ty))),
+            Value::LiteralIntro(literal_intro) => {
+                Ok(Rc::from(Term::literal_intro(literal_intro.clone())))
+            },
+
+            // Functions
+            Value::FunType(doc, app_mode, name_hint, param_ty, body_ty) => {
+                let doc = doc.clone();
+                let app_mode = app_mode.clone();
+                let name_hint = name_hint.clone();
+                let body_ty = inst_closure(prims, metas, size, body_ty)?;
+                let param_ty = self.read_back_value(size, param_ty)?;
+                let body_ty = self.read_back_value(size + 1, &body_ty)?;
+
+                Ok(Rc::from(Term::FunType(
+                    doc, app_mode, name_hint, param_ty, body_ty,
+                )))
+            },
+            Value::FunIntro(app_mode, name_hint, body) => {
+                let app_mode = app_mode.clone();
+                let name_hint = name_hint.clone();
+                // Bodies that are functions are read back, as their values
+                // can be found without evaluating anything
+                let body = match &mut self.elision {
+                    Some(elision)
+                        if body.term.metrics().size > elision.size
+                            && !matches!(body.term.as_ref(), Term::FunIntro(..)) =>
+                    {
+                        elision.placeholder()
+                    },
+                    Some(_) | None => {
+                        let body = inst_closure(prims, metas, size, body)?;
+                        self.read_back_value(size + 1, &body)?
+                    },
+                };
+
+                Ok(Rc::from(Term::FunIntro(app_mode, name_hint, body)))
+            },
+
+            // Records
+            Value::RecordTypeExtend(..) => {
+                let telescope = Telescope::record(term.clone());
+                let (entries, _) = telescope.weaken(prims, metas, size)?;
+                let field_tys = entries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let label = (entry.label().cloned())
+                            .ok_or_else(|| "read_back_value: expected a field".to_owned())?;
+                        let ty = self.read_back_value(size + i as u32, &entry.ty)?;
+                        Ok((entry.doc, label, entry.name_hint, ty))
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                Ok(Rc::from(Term::RecordType(field_tys)))
+            },
+            Value::RecordTypeEmpty => Ok(Rc::from(Term::RecordType(Vec::new()))),
+            Value::RecordIntro(fields) => {
+                if self.eta {
+                    if let Some((head, spine)) = self.eta_contract_record(fields)? {
+                        return self.read_back_neutral(size, &head, &spine);
+                    }
+                }
+
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (label, term))| {
+                        let term = self.read_back_value(size + i as u32, term)?;
+                        Ok((label.clone(), term))
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                Ok(Rc::from(Term::RecordIntro(fields)))
+            },
+
+            // Arrays
+            Value::ArrayType(elem_ty) => Ok(Rc::from(Term::ArrayType(
+                self.read_back_value(size, elem_ty)?,
+            ))),
+            Value::ArrayIntro(elems) => {
+                let elems = (elems.iter())
+                    .map(|elem| self.read_back_value(size, elem))
+                    .collect::<Result<_, String>>()?;
+
+                Ok(Rc::from(Term::ArrayIntro(elems)))
+            },
+
+            // Universes
+            Value::Universe(level) => Ok(Rc::from(Term::universe(*level))),
+        }
+    }
+
+    /// Check if a record introduction is the eta-expansion of a neutral
+    /// record, ie. `record { l1 = r.l1; ...; ln = r.ln }`, returning the
+    /// neutral record `r` if so.
+    ///
+    /// The fields may themselves be eta-expansions of the fields of `r`, as
+    /// in `record { l1 = record { k1 = r.l1.k1; ... }; ... }`.
+    fn eta_contract_record(
+        &self,
+        fields: &[(Label, Rc<Value>)],
+    ) -> Result<Option<(Head, Spine)>, String> {
+        let mut record = None::<(Head, Spine)>;
+
+        for (label, value) in fields {
+            let value = force_value(self.prims, self.metas, value)?;
+            let (head, mut spine) = match value.as_ref() {
+                Value::Neutral(head, spine) => (head.clone(), spine.clone()),
+                Value::RecordIntro(fields) => match self.eta_contract_record(fields)? {
+                    Some(field) => field,
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            };
+            match spine.pop() {
+                Some(Elim::Record(elim_label)) if elim_label == *label => {},
+                _ => return Ok(None),
+            }
+
+            match &record {
+                None => record = Some((head, spine)),
+                Some((record_head, record_spine))
+                    if *record_head == head && *record_spine == spine => {},
+                Some(_) => return Ok(None),
+            }
+        }
+
+        Ok(record)
+    }
+
+    fn read_back_neutral(
+        &mut self,
+        size: var::Size,
+        head: &Head,
+        spine: &Spine,
+    ) -> Result<Rc<Term>, String> {
+        let (prims, metas) = (self.prims, self.metas);
+
+        let forced_spine;
+        let (head, spine) = match head {
+            Head::Var(var_level) => (
+                Rc::from(Term::var(size.index(*var_level))),
+                spine.as_slice(),
+            ),
+            Head::Meta(meta_index) => (Rc::from(Term::meta(*meta_index)), spine.as_slice()),
+            Head::Prim(prim_name) => {
+                let prim = prims
+                    .lookup_entry(prim_name)
+                    .ok_or_else(|| format!("read back: primitive not found: {:?}", prim_name))?;
+                // Primitives can only be interpreted once their arguments
+                // have been evaluated
+                forced_spine = force_args(prims, metas, spine)?;
+                match prims.interpret(prim_name, prim, &forced_spine) {
+                    Some(result) => {
+                        // Interpretations may suspend the computation of
+                        // their result, as in the eliminator of `Acc`
+                        let (value, spine) = result?;
+                        let value = force_value(prims, metas, &value)?;
+                        (self.read_back_value(size, &value)?, spine)
+                    },
+                    None => (
+                        Rc::from(Term::prim(prim_name.clone())),
+                        forced_spine.as_slice(),
+                    ),
+                }
+            },
+            Head::Thunk(thunk) => {
+                let value = spine
+                    .iter()
+                    .try_fold(force_thunk(prims, metas, thunk)?, |head, elim| {
+                        eval_elim(prims, metas, head, elim)
+                    })?;
+                return self.read_back_value(size, &value);
+            },
+        };
+
+        spine.iter().try_fold(head, |acc, elim| match elim {
+            Elim::Literal(closure) => {
+                let clauses = Rc::from(
+                    closure
+                        .clauses
+                        .iter()
+                        .map(|(literal_intro, body)| {
+                            let body = eval_term_with_strategy(
+                                prims,
+                                metas,
+                                closure.strategy,
+                                &closure.values,
+                                body,
+                            )?;
+                            let body = self.read_back_value(size, &body)?;
+                            Ok((literal_intro.clone(), body))
+                        })
+                        .collect::<Result<Vec<_>, String>>()?,
+                );
+                let default_body = eval_term_with_strategy(
+                    prims,
+                    metas,
+                    closure.strategy,
+                    &closure.values,
+                    &closure.default,
+                )?;
+                let default_body = self.read_back_value(size, &default_body)?;
+
+                Ok(Rc::from(Term::LiteralElim(acc, clauses, default_body)))
+            },
+            Elim::Fun(app_mode, arg) => {
+                let arg = self.read_back_value(size, arg)?;
+
+                Ok(Rc::from(Term::FunElim(acc, app_mode.clone(), arg)))
+            },
+            Elim::Record(label) => Ok(Rc::from(Term::RecordElim(acc, label.clone()))),
+        })
     }
 }
 
-/// Read a neutral value back into the core syntax, normalizing as required.
-pub fn read_back_neutral(
+/// A sub-value that was encountered when reading back a value.
+struct SharedValue {
+    /// The value. This keeps the value alive, so that its address is not
+    /// reused by another value during the read back.
+    value: Rc<Value>,
+    /// The size of the environment the value was first read back in.
+    size: var::Size,
+    /// The term that the value was first read back as.
+    term: Rc<Term>,
+    /// The number of times the value was encountered.
+    count: usize,
+}
+
+/// The sub-values encountered when reading back a value, used for preserving
+/// sharing.
+#[derive(Default)]
+struct Sharing {
+    /// The sub-values, in the order that they were first read back. The
+    /// sub-values of a value always come before the value itself.
+    values: Vec<SharedValue>,
+    /// The position of each sub-value in `values`.
+    positions: HashMap<*const Value, usize>,
+    /// The terms that each sub-value has been read back as, by the size of the
+    /// environment they were read back in.
+    terms: HashMap<(*const Value, var::Size), Rc<Term>>,
+}
+
+impl Sharing {
+    /// Lookup the term that a value was read back as, recording that it has
+    /// been encountered again.
+    fn lookup(&mut self, size: var::Size, value: &Rc<Value>) -> Option<Rc<Term>> {
+        let ptr = Rc::as_ptr(value);
+        let position = *self.positions.get(&ptr)?;
+        self.values[position].count += 1;
+        self.terms.get(&(ptr, size)).cloned()
+    }
+
+    /// Record the term that a value was read back as.
+    fn insert(&mut self, size: var::Size, value: &Rc<Value>, term: &Rc<Term>) {
+        let ptr = Rc::as_ptr(value);
+        if !self.positions.contains_key(&ptr) {
+            self.positions.insert(ptr, self.values.len());
+            self.values.push(SharedValue {
+                value: value.clone(),
+                size,
+                term: term.clone(),
+                count: 1,
+            });
+        }
+        self.terms.insert((ptr, size), term.clone());
+    }
+
+    /// Bind the sub-values that were encountered more than once at the root of
+    /// a term that was read back in an environment of the given size.
+    fn bind_shared(&self, size: var::Size, term: &Rc<Term>) -> Rc<Term> {
+        let shared_values = (self.values.iter())
+            .filter(|shared| shared.count > 1 && shared.term.metrics().size > 1)
+            .filter(|shared| {
+                // Skip values that depend on the variables bound inside the
+                // value, as they can't be moved to the root
+                let depth = shared.size.0 - size.0;
+                (0..depth).all(|index| !shared.term.has_free_var(var::Index(index)))
+            })
+            .collect::<Vec<_>>();
+
+        if shared_values.is_empty() {
+            return term.clone();
+        }
+
+        // The bindings of the shared terms, by address
+        let mut bindings = HashMap::new();
+        for (binding, shared) in shared_values.iter().enumerate() {
+            let ptr = Rc::as_ptr(&shared.value);
+            for ((value_ptr, _), term) in &self.terms {
+                if *value_ptr == ptr {
+                    bindings.insert(Rc::as_ptr(term), binding);
+                }
+            }
+        }
+
+        let items = (shared_values.iter().enumerate())
+            .map(|(binding, shared)| {
+                let depth = shared.size.0 - size.0;
+                let shift = i64::from(binding as u32) - i64::from(depth);
+                let rebind = Rebind::new(&bindings, binding, shift);
+                let label = Label(format!("shared{}", binding));
+                let term = rebind.term_children(&shared.term, 0);
+                Item::Definition(DocString::from(""), label, term)
+            })
+            .collect::<Vec<_>>();
+
+        let binding_count = items.len();
+        let rebind = Rebind::new(&bindings, binding_count, binding_count as i64);
+        let body = rebind.term(term, 0);
+
+        Rc::from(Term::Let(items, body))
+    }
+}
+
+/// Replaces shared terms with references to their bindings, shifting the free
+/// variables of the remaining terms to account for the new bindings.
+struct Rebind<'a> {
+    /// The bindings of the shared terms, by address.
+    bindings: &'a HashMap<*const Term, usize>,
+    /// The number of bindings that are in scope.
+    binding_count: usize,
+    /// The amount to shift the free variables by.
+    shift: i64,
+}
+
+impl<'a> Rebind<'a> {
+    fn new(
+        bindings: &'a HashMap<*const Term, usize>,
+        binding_count: usize,
+        shift: i64,
+    ) -> Rebind<'a> {
+        Rebind {
+            bindings,
+            binding_count,
+            shift,
+        }
+    }
+
+    fn term(&self, term: &Rc<Term>, depth: u32) -> Rc<Term> {
+        match self.bindings.get(&Rc::as_ptr(term)) {
+            Some(binding) if *binding < self.binding_count => {
+                let index = depth + (self.binding_count - binding - 1) as u32;
+                Rc::from(Term::var(index))
+            },
+            Some(_) | None => self.term_children(term, depth),
+        }
+    }
+
+    fn term_children(&self, term: &Rc<Term>, depth: u32) -> Rc<Term> {
+        match term.as_ref() {
+            Term::Var(index) if index.0 >= depth => {
+                Rc::from(Term::var((i64::from(index.0) + self.shift) as u32))
+            },
+            Term::Var(_) | Term::Meta(_) | Term::Prim(_) => term.clone(),
+
+            Term::Ann(term, term_ty) => {
+                Rc::from(Term::Ann(self.term(term, depth), self.term(term_ty, depth)))
+            },
+            Term::Let(items, body) => {
+                let mut depth = depth;
+                let items = (items.iter())
+                    .map(|item| match item {
+                        Item::Declaration(doc, label, term_ty) => {
+                            let term_ty = self.term(term_ty, depth);
+                            Item::Declaration(doc.clone(), label.clone(), term_ty)
+                        },
+                        Item::Definition(doc, label, term) => {
+                            let term = self.term(term, depth);
+                            depth += 1;
+                            Item::Definition(doc.clone(), label.clone(), term)
+                        },
+                    })
+                    .collect();
+                Rc::from(Term::Let(items, self.term(body, depth)))
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => term.clone(),
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let clauses = (clauses.iter())
+                    .map(|(literal_intro, body)| (literal_intro.clone(), self.term(body, depth)))
+                    .collect::<Vec<_>>();
+                Rc::from(Term::LiteralElim(
+                    self.term(scrutinee, depth),
+                    Rc::from(clauses),
+                    self.term(default_body, depth),
+                ))
+            },
+
+            Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => Rc::from(Term::FunType(
+                doc.clone(),
+                app_mode.clone(),
+                name_hint.clone(),
+                self.term(param_ty, depth),
+                self.term(body_ty, depth + 1),
+            )),
+            Term::FunIntro(app_mode, name_hint, body) => Rc::from(Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                self.term(body, depth + 1),
+            )),
+            Term::FunElim(fun, app_mode, arg) => Rc::from(Term::FunElim(
+                self.term(fun, depth),
+                app_mode.clone(),
+                self.term(arg, depth),
+            )),
+
+            Term::RecordType(ty_fields) => {
+                let ty_fields = (ty_fields.iter().enumerate())
+                    .map(|(i, (doc, label, name_hint, ty))| {
+                        let ty = self.term(ty, depth + i as u32);
+                        (doc.clone(), label.clone(), name_hint.clone(), ty)
+                    })
+                    .collect();
+                Rc::from(Term::RecordType(ty_fields))
+            },
+            Term::RecordIntro(intro_fields) => {
+                let intro_fields = (intro_fields.iter().enumerate())
+                    .map(|(i, (label, term))| (label.clone(), self.term(term, depth + i as u32)))
+                    .collect();
+                Rc::from(Term::RecordIntro(intro_fields))
+            },
+            Term::RecordElim(record, label) => {
+                Rc::from(Term::RecordElim(self.term(record, depth), label.clone()))
+            },
+
+            Term::ArrayType(elem_ty) => Rc::from(Term::ArrayType(self.term(elem_ty, depth))),
+            Term::ArrayIntro(elems) => {
+                let elems = elems.iter().map(|elem| self.term(elem, depth)).collect();
+                Rc::from(Term::ArrayIntro(elems))
+            },
+
+            Term::Universe(_) => term.clone(),
+        }
+    }
+}
+
+/// Fully normalize a term by first evaluating it, then reading it back.
+pub fn normalize_term(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+) -> Result<Rc<Term>, String> {
+    let value = eval_term(prims, metas, values, term)?;
+    read_back_value(prims, metas, values.size(), &value)
+}
+
+/// Replace the solved metavariables in a term with their solutions, read back
+/// in the scope that they occur in. This is sometimes known as _zonking_.
+///
+/// Unsolved metavariables are left in place.
+pub fn zonk_term(
     prims: &prim::Env,
     metas: &meta::Env,
     size: var::Size,
-    head: &Head,
-    spine: &Spine,
+    term: &Rc<Term>,
 ) -> Result<Rc<Term>, String> {
-    let (head, spine) = match head {
-        Head::Var(var_level) => (
-            Rc::from(Term::var(size.index(*var_level))),
-            spine.as_slice(),
-        ),
-        Head::Meta(meta_index) => (Rc::from(Term::meta(*meta_index)), spine.as_slice()),
-        Head::Prim(prim_name) => {
-            let (value, spine) = eval_prim(prims, prim_name, &spine)?;
-            (read_back_value(prims, metas, size, &value)?, spine)
+    let zonk_term = |size, term| zonk_term(prims, metas, size, term);
+
+    Ok(match term.as_ref() {
+        Term::Meta(meta_index) => match metas.lookup_solution(*meta_index) {
+            Some((_, meta::Solution::Solved(value), _)) => {
+                read_back_value(prims, metas, size, value)?
+            },
+            Some((_, meta::Solution::Unsolved, _)) => term.clone(),
+            None => return Err("zonk: metavariable not found".to_owned()),
         },
-    };
+        Term::Var(_) | Term::Prim(_) => term.clone(),
 
-    spine.iter().fold(Ok(head), |acc, elim| match elim {
-        Elim::Literal(closure) => {
-            let clauses = Rc::from(
-                closure
-                    .clauses
-                    .iter()
-                    .map(|(literal_intro, body)| {
-                        let body = eval_term(prims, metas, &closure.values, body)?;
-                        let body = read_back_value(prims, metas, size, &body)?;
-                        Ok((literal_intro.clone(), body))
-                    })
-                    .collect::<Result<Vec<_>, String>>()?,
-            );
-            let default_body = eval_term(prims, metas, &closure.values, &closure.default)?;
-            let default_body = read_back_value(prims, metas, size, &default_body)?;
+        Term::Ann(term, term_ty) => {
+            Rc::from(Term::Ann(zonk_term(size, term)?, zonk_term(size, term_ty)?))
+        },
+        Term::Let(items, body) => {
+            let mut size = size;
+            let mut zonked_items = Vec::with_capacity(items.len());
+            for item in items {
+                zonked_items.push(match item {
+                    Item::Declaration(doc, label, term_ty) => {
+                        let term_ty = zonk_term(size, term_ty)?;
+                        Item::Declaration(doc.clone(), label.clone(), term_ty)
+                    },
+                    Item::Definition(doc, label, term) => {
+                        let term = zonk_term(size, term)?;
+                        size += 1;
+                        Item::Definition(doc.clone(), label.clone(), term)
+                    },
+                });
+            }
+            Rc::from(Term::Let(zonked_items, zonk_term(size, body)?))
+        },
 
-            Ok(Rc::from(Term::LiteralElim(acc?, clauses, default_body)))
+        Term::LiteralType(_) | Term::LiteralIntro(_) => term.clone(),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let clauses = (clauses.iter())
+                .map(|(pattern, body)| Ok((pattern.clone(), zonk_term(size, body)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            Rc::from(Term::LiteralElim(
+                zonk_term(size, scrutinee)?,
+                Rc::from(clauses),
+                zonk_term(size, default_body)?,
+            ))
         },
-        Elim::Fun(app_mode, arg) => {
-            let arg = read_back_value(prims, metas, size, &arg)?;
 
-            Ok(Rc::from(Term::FunElim(acc?, app_mode.clone(), arg)))
+        Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => Rc::from(Term::FunType(
+            doc.clone(),
+            app_mode.clone(),
+            name_hint.clone(),
+            zonk_term(size, param_ty)?,
+            zonk_term(size + 1, body_ty)?,
+        )),
+        Term::FunIntro(app_mode, name_hint, body) => Rc::from(Term::FunIntro(
+            app_mode.clone(),
+            name_hint.clone(),
+            zonk_term(size + 1, body)?,
+        )),
+        Term::FunElim(fun, app_mode, arg) => Rc::from(Term::FunElim(
+            zonk_term(size, fun)?,
+            app_mode.clone(),
+            zonk_term(size, arg)?,
+        )),
+
+        Term::RecordType(ty_fields) => {
+            let ty_fields = (ty_fields.iter().enumerate())
+                .map(|(i, (doc, label, name_hint, ty))| {
+                    let ty = zonk_term(size + i as u32, ty)?;
+                    Ok((doc.clone(), label.clone(), name_hint.clone(), ty))
+                })
+                .collect::<Result<_, String>>()?;
+            Rc::from(Term::RecordType(ty_fields))
         },
-        Elim::Record(label) => Ok(Rc::from(Term::RecordElim(acc?, label.clone()))),
+        Term::RecordIntro(intro_fields) => {
+            let intro_fields = (intro_fields.iter().enumerate())
+                .map(|(i, (label, term))| Ok((label.clone(), zonk_term(size + i as u32, term)?)))
+                .collect::<Result<_, String>>()?;
+            Rc::from(Term::RecordIntro(intro_fields))
+        },
+        Term::RecordElim(record, label) => {
+            Rc::from(Term::RecordElim(zonk_term(size, record)?, label.clone()))
+        },
+
+        Term::ArrayType(elem_ty) => Rc::from(Term::ArrayType(zonk_term(size, elem_ty)?)),
+        Term::ArrayIntro(elems) => {
+            let elems = (elems.iter())
+                .map(|elem| zonk_term(size, elem))
+                .collect::<Result<_, String>>()?;
+            Rc::from(Term::ArrayIntro(elems))
+        },
+
+        Term::Universe(_) => term.clone(),
     })
 }
 
-/// Fully normalize a term by first evaluating it, then reading it back.
-pub fn normalize_term(
+/// Reduce a term to weak head normal form, without reading it back.
+///
+/// Values are already in weak head normal form, apart from neutral values
+/// whose metavariable heads have since been solved, so this is much cheaper
+/// than `normalize_term` when only the head constructor is needed.
+pub fn whnf(
     prims: &prim::Env,
     metas: &meta::Env,
     values: &var::Env<Rc<Value>>,
     term: &Rc<Term>,
-) -> Result<Rc<Term>, String> {
+) -> Result<Rc<Value>, String> {
     let value = eval_term(prims, metas, values, term)?;
-    read_back_value(prims, metas, values.size(), &value)
+    force_value(prims, metas, &value)
 }
 
 /// Evaluate a value further, if it's now possible due to updates made to the
-/// metavariable solutions.
+/// metavariable solutions, or if it is a thunk. This leaves the value in weak
+/// head normal form.
 pub fn force_value(
     prims: &prim::Env,
     metas: &meta::Env,
     value: &Rc<Value>,
 ) -> Result<Rc<Value>, String> {
     match value.as_ref() {
+        Value::Neutral(Head::Thunk(thunk), spine) => {
+            let value = spine
+                .iter()
+                .try_fold(force_thunk(prims, metas, thunk)?, |head, elim| {
+                    eval_elim(prims, metas, head, elim)
+                })?;
+            force_value(prims, metas, &value)
+        },
         Value::Neutral(Head::Meta(meta_level), spine) => match metas.lookup_solution(*meta_level) {
             Some((_, meta::Solution::Solved(value), _)) => {
-                let value = spine.iter().fold(Ok(value.clone()), |head, elim| {
-                    eval_elim(prims, metas, head?, elim)
+                let value = spine.iter().try_fold(value.clone(), |head, elim| {
+                    eval_elim(prims, metas, head, elim)
                 })?;
                 force_value(prims, metas, &value)
             },
@@ -393,6 +1191,19 @@ pub fn force_value(
     }
 }
 
+/// Force the arguments of the function eliminators in a spine.
+fn force_args(prims: &prim::Env, metas: &meta::Env, spine: &[Elim]) -> Result<Spine, String> {
+    spine
+        .iter()
+        .map(|elim| match elim {
+            Elim::Fun(app_mode, arg) => {
+                Ok(Elim::Fun(app_mode.clone(), force_value(prims, metas, arg)?))
+            },
+            Elim::Literal(_) | Elim::Record(_) => Ok(elim.clone()),
+        })
+        .collect()
+}
+
 /// Check whether a type is a subtype of another type.
 pub fn check_ty(
     prims: &prim::Env,
@@ -402,13 +1213,37 @@ pub fn check_ty(
     ty1: &Rc<Type>,
     ty2: &Rc<Type>,
 ) -> Result<bool, String> {
+    let mut budget = ConversionBudget::unlimited();
+    check_ty_with_budget(prims, metas, size, subtype, &mut budget, ty1, ty2)
+}
+
+/// Check whether a type is a subtype of another type, spending a step of the
+/// budget for each pair of types that are compared. If the budget is exceeded
+/// the types are assumed to be different.
+pub fn check_ty_with_budget(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    size: var::Size,
+    subtype: bool,
+    budget: &mut ConversionBudget,
+    ty1: &Rc<Type>,
+    ty2: &Rc<Type>,
+) -> Result<bool, String> {
+    if !budget.spend() {
+        return Ok(false);
+    }
+
     match (
         force_value(prims, metas, ty1)?.as_ref(),
         force_value(prims, metas, ty2)?.as_ref(),
     ) {
         (Value::Neutral(head1, spine1), Value::Neutral(head2, spine2)) => {
-            let term1 = read_back_neutral(prims, metas, size, head1, spine1)?;
-            let term2 = read_back_neutral(prims, metas, size, head2, spine2)?;
+            // Records are compared up to eta, so that `r` is equal to
+            // `record { l1 = r.l1; ...; ln = r.ln }`
+            let mut read_back = ReadBack::new(prims, metas);
+            read_back.eta = true;
+            let term1 = read_back.read_back_neutral(size, head1, spine1)?;
+            let term2 = read_back.read_back_neutral(size, head2, spine2)?;
 
             Ok(Term::alpha_eq(&term1, &term2))
         },
@@ -416,27 +1251,412 @@ pub fn check_ty(
             Ok(literal_ty1 == literal_ty2)
         },
         (
-            Value::FunType(app_mode1, _, param_ty1, body_ty1),
-            Value::FunType(app_mode2, _, param_ty2, body_ty2),
-        ) if app_mode1 == app_mode2 => Ok(check_ty(
-            prims, metas, size, subtype, param_ty2, param_ty1,
+            Value::FunType(_, app_mode1, _, param_ty1, body_ty1),
+            Value::FunType(_, app_mode2, _, param_ty2, body_ty2),
+        ) if app_mode1 == app_mode2 => Ok(check_ty_with_budget(
+            prims, metas, size, subtype, budget, param_ty2, param_ty1,
         )? && {
             let body_ty1 = inst_closure(prims, metas, size, body_ty1)?;
             let body_ty2 = inst_closure(prims, metas, size, body_ty2)?;
-            check_ty(prims, metas, size + 1, subtype, &body_ty1, &body_ty2)?
+            check_ty_with_budget(
+                prims,
+                metas,
+                size + 1,
+                subtype,
+                budget,
+                &body_ty1,
+                &body_ty2,
+            )?
         }),
         (
             Value::RecordTypeExtend(_, label1, _, term_ty1, rest_ty1),
             Value::RecordTypeExtend(_, label2, _, term_ty2, rest_ty2),
-        ) if label1 == label2 => Ok(check_ty(prims, metas, size, subtype, term_ty1, term_ty2)?
-            && {
-                let rest_ty1 = inst_closure(prims, metas, size, rest_ty1)?;
-                let rest_ty2 = inst_closure(prims, metas, size, rest_ty2)?;
-                check_ty(prims, metas, size + 1, subtype, &rest_ty1, &rest_ty2)?
-            }),
+        ) if label1 == label2 => Ok(check_ty_with_budget(
+            prims, metas, size, subtype, budget, term_ty1, term_ty2,
+        )? && {
+            let rest_ty1 = inst_closure(prims, metas, size, rest_ty1)?;
+            let rest_ty2 = inst_closure(prims, metas, size, rest_ty2)?;
+            check_ty_with_budget(
+                prims,
+                metas,
+                size + 1,
+                subtype,
+                budget,
+                &rest_ty1,
+                &rest_ty2,
+            )?
+        }),
         (Value::RecordTypeEmpty, Value::RecordTypeEmpty) => Ok(true),
+        (Value::ArrayType(elem_ty1), Value::ArrayType(elem_ty2)) => {
+            check_ty_with_budget(prims, metas, size, subtype, budget, elem_ty1, elem_ty2)
+        },
         (Value::Universe(level1), Value::Universe(level2)) if subtype => Ok(level1 <= level2),
         (Value::Universe(level1), Value::Universe(level2)) => Ok(level1 == level2),
         _ => Ok(false),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::LiteralType;
+
+    fn label(name: &str) -> Label {
+        Label(name.to_owned())
+    }
+
+    fn literal_clauses(patterns: Vec<LiteralPattern>) -> Vec<(LiteralPattern, Rc<Term>)> {
+        (patterns.into_iter().enumerate())
+            .map(|(index, pattern)| (pattern, Rc::from(Term::var(index as u32))))
+            .collect()
+    }
+
+    #[test]
+    fn find_literal_clause_dense() {
+        let clauses = literal_clauses(
+            (3..8)
+                .map(|value| LiteralPattern::Intro(LiteralIntro::S32(value)))
+                .collect(),
+        );
+
+        let find = |value| find_literal_clause(&clauses, &LiteralIntro::S32(value));
+        assert_eq!(find(3), Some(&Rc::from(Term::var(0))));
+        assert_eq!(find(5), Some(&Rc::from(Term::var(2))));
+        assert_eq!(find(7), Some(&Rc::from(Term::var(4))));
+        assert_eq!(find(2), None);
+        assert_eq!(find(8), None);
+        assert_eq!(find(i32::MIN), None);
+    }
+
+    #[test]
+    fn find_literal_clause_sparse() {
+        let clauses = literal_clauses(vec![
+            LiteralPattern::Intro(LiteralIntro::Char('!')),
+            LiteralPattern::Range(LiteralIntro::Char('0'), LiteralIntro::Char('9')),
+            LiteralPattern::Range(LiteralIntro::Char('a'), LiteralIntro::Char('z')),
+            LiteralPattern::Intro(LiteralIntro::Char('~')),
+        ]);
+
+        let find = |value| find_literal_clause(&clauses, &LiteralIntro::Char(value));
+        assert_eq!(find('!'), Some(&Rc::from(Term::var(0))));
+        assert_eq!(find('5'), Some(&Rc::from(Term::var(1))));
+        assert_eq!(find('q'), Some(&Rc::from(Term::var(2))));
+        assert_eq!(find('~'), Some(&Rc::from(Term::var(3))));
+        assert_eq!(find(' '), None);
+        assert_eq!(find('A'), None);
+    }
+
+    #[test]
+    fn read_back_shared_record() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let shared = Rc::from(Value::RecordIntro(vec![
+            (
+                label("a"),
+                Rc::from(Value::literal_intro(LiteralIntro::S32(1))),
+            ),
+            (label("b"), Rc::from(Value::var(0))),
+        ]));
+        let value = Rc::from(Value::RecordIntro(vec![
+            (label("x"), shared.clone()),
+            (label("y"), shared),
+        ]));
+
+        let term = read_back_value_shared(&prims, &metas, var::Size(1), &value).unwrap();
+
+        let shared_term = Rc::from(Term::RecordIntro(vec![
+            (
+                label("a"),
+                Rc::from(Term::literal_intro(LiteralIntro::S32(1))),
+            ),
+            // Record fields are in the scope of the fields before them
+            (label("b"), Rc::from(Term::var(1))),
+        ]));
+        let expected = Term::Let(
+            vec![Item::Definition(
+                DocString::from(""),
+                label("shared0"),
+                shared_term,
+            )],
+            Rc::from(Term::RecordIntro(vec![
+                (label("x"), Rc::from(Term::var(0))),
+                (label("y"), Rc::from(Term::var(1))),
+            ])),
+        );
+        assert_eq!(term, Rc::from(expected));
+    }
+
+    #[test]
+    fn read_back_shared_local() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        // fun x => record { x = f x; y = f x }, where `f x` is shared
+        let body = Rc::from(Term::Let(
+            vec![Item::Definition(
+                DocString::from(""),
+                label("p"),
+                Rc::from(Term::FunElim(
+                    Rc::from(Term::var(1)),
+                    AppMode::Explicit,
+                    Rc::from(Term::var(0)),
+                )),
+            )],
+            Rc::from(Term::RecordIntro(vec![
+                (label("x"), Rc::from(Term::var(0))),
+                (label("y"), Rc::from(Term::var(1))),
+            ])),
+        ));
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::var(0)));
+        let value = eval_term(
+            &prims,
+            &metas,
+            &values,
+            &Rc::from(Term::FunIntro(AppMode::Explicit, None, body)),
+        )
+        .unwrap();
+
+        let term = read_back_value_shared(&prims, &metas, var::Size(1), &value).unwrap();
+        let unshared_term = read_back_value(&prims, &metas, var::Size(1), &value).unwrap();
+
+        assert!(term.shared_eq(&unshared_term));
+    }
+
+    #[test]
+    fn whnf_fun_elim() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        // (fun A => Fun (a : A) -> A) S32
+        let fun = Rc::from(Term::FunIntro(
+            AppMode::Explicit,
+            Some("A".to_owned()),
+            Rc::from(Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("a".to_owned()),
+                Rc::from(Term::var(0)),
+                Rc::from(Term::var(1)),
+            )),
+        ));
+        let term = Rc::from(Term::FunElim(
+            fun,
+            AppMode::Explicit,
+            Rc::from(Term::literal_ty(LiteralType::S32)),
+        ));
+
+        let value = whnf(&prims, &metas, &var::Env::new(), &term).unwrap();
+
+        match value.as_ref() {
+            Value::FunType(_, AppMode::Explicit, _, param_ty, _) => {
+                assert_eq!(*param_ty, Rc::from(Value::literal_ty(LiteralType::S32)));
+            },
+            _ => panic!("expected a function type, found: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn call_by_need_unused_definition() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        // let unused = Type Type; in Type
+        let term = Rc::from(Term::Let(
+            vec![Item::Definition(
+                DocString::from(""),
+                label("unused"),
+                Rc::from(Term::FunElim(
+                    Rc::from(Term::universe(0)),
+                    AppMode::Explicit,
+                    Rc::from(Term::universe(0)),
+                )),
+            )],
+            Rc::from(Term::universe(0)),
+        ));
+        let values = var::Env::new();
+
+        assert!(eval_term(&prims, &metas, &values, &term).is_err());
+        assert_eq!(
+            eval_term_with_strategy(&prims, &metas, Strategy::CallByNeed, &values, &term),
+            Ok(Rc::from(Value::universe(0))),
+        );
+    }
+
+    #[test]
+    fn force_thunk_memoized() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let term = Rc::from(Term::RecordIntro(vec![(
+            label("a"),
+            Rc::from(Term::universe(0)),
+        )]));
+        let thunk = Thunk::new(term, var::Env::new());
+
+        let value1 = force_thunk(&prims, &metas, &thunk).unwrap();
+        let value2 = force_thunk(&prims, &metas, &thunk).unwrap();
+
+        assert!(Rc::ptr_eq(&value1, &value2));
+    }
+
+    #[test]
+    fn read_back_stuck_prim() {
+        let prims = prim::Env::default();
+        let metas = meta::Env::new();
+        let value = Rc::from(Value::prim("abort"));
+
+        let term = read_back_value(&prims, &metas, var::Size(0), &value).unwrap();
+
+        assert_eq!(term, Rc::from(Term::prim("abort")));
+    }
+
+    #[test]
+    fn read_back_whnf_elided() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let values = var::Env::new();
+        let closure =
+            |term: Term| AppClosure::new(Rc::from(term), values.clone(), Strategy::default());
+        let thunk = Rc::from(Thunk::new(Rc::from(Term::universe(0)), values.clone()));
+        let self_app = Term::FunElim(
+            Rc::from(Term::var(0)),
+            AppMode::Explicit,
+            Rc::from(Term::var(0)),
+        );
+        let value = Rc::from(Value::RecordIntro(vec![
+            (
+                label("a"),
+                Rc::from(Value::Neutral(Head::Thunk(thunk.clone()), Vec::new())),
+            ),
+            (
+                label("b"),
+                Rc::from(Value::FunIntro(AppMode::Explicit, None, closure(self_app))),
+            ),
+            (
+                label("c"),
+                Rc::from(Value::FunIntro(
+                    AppMode::Explicit,
+                    None,
+                    closure(Term::var(0)),
+                )),
+            ),
+        ]));
+
+        let (term, elided) = read_back_whnf(&prims, &metas, var::Size(0), &value, 1).unwrap();
+
+        assert_eq!(thunk.value(), None);
+        assert_eq!(elided.len(), 2);
+        match term.as_ref() {
+            Term::RecordIntro(fields) => {
+                assert!(Rc::ptr_eq(&fields[0].1, &elided[0]));
+                match fields[1].1.as_ref() {
+                    Term::FunIntro(_, _, body) => assert!(Rc::ptr_eq(body, &elided[1])),
+                    term => panic!("expected a function, found {:?}", term),
+                }
+                assert_eq!(
+                    fields[2].1,
+                    Rc::from(Term::FunIntro(
+                        AppMode::Explicit,
+                        None,
+                        Rc::from(Term::var(0))
+                    )),
+                );
+            },
+            term => panic!("expected a record, found {:?}", term),
+        }
+    }
+
+    fn record_elim(record: &Rc<Value>, name: &str) -> Rc<Value> {
+        match record.as_ref() {
+            Value::Neutral(head, spine) => {
+                let mut spine = spine.clone();
+                spine.push(Elim::Record(label(name)));
+                Rc::from(Value::Neutral(head.clone(), spine))
+            },
+            _ => panic!("expected a neutral record"),
+        }
+    }
+
+    fn fun_elim(fun: u32, arg: Rc<Value>) -> Rc<Type> {
+        let spine = vec![Elim::Fun(AppMode::Explicit, arg)];
+        Rc::from(Value::Neutral(Head::Var(var::Level(fun)), spine))
+    }
+
+    #[test]
+    fn check_ty_record_eta() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let pair = Rc::from(Value::var(1));
+        let expanded = Rc::from(Value::RecordIntro(vec![
+            (label("fst"), record_elim(&pair, "fst")),
+            (label("snd"), record_elim(&pair, "snd")),
+        ]));
+
+        let ty1 = fun_elim(0, pair);
+        let ty2 = fun_elim(0, expanded);
+
+        assert!(check_ty(&prims, &metas, var::Size(2), false, &ty1, &ty2).unwrap());
+        assert!(check_ty(&prims, &metas, var::Size(2), false, &ty2, &ty1).unwrap());
+    }
+
+    #[test]
+    fn check_ty_record_eta_nested() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let pair = Rc::from(Value::var(1));
+        let snd = record_elim(&pair, "snd");
+        let expanded = Rc::from(Value::RecordIntro(vec![
+            (label("fst"), record_elim(&pair, "fst")),
+            (
+                label("snd"),
+                Rc::from(Value::RecordIntro(vec![
+                    (label("fst"), record_elim(&snd, "fst")),
+                    (label("snd"), record_elim(&snd, "snd")),
+                ])),
+            ),
+        ]));
+
+        let ty1 = fun_elim(0, pair);
+        let ty2 = fun_elim(0, expanded);
+
+        assert!(check_ty(&prims, &metas, var::Size(2), false, &ty1, &ty2).unwrap());
+        assert!(check_ty(&prims, &metas, var::Size(2), false, &ty2, &ty1).unwrap());
+    }
+
+    #[test]
+    fn check_ty_record_eta_nested_mismatched() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let pair = Rc::from(Value::var(1));
+        let fst = record_elim(&pair, "fst");
+        let expanded = Rc::from(Value::RecordIntro(vec![
+            (label("fst"), record_elim(&pair, "fst")),
+            (
+                label("snd"),
+                Rc::from(Value::RecordIntro(vec![
+                    (label("fst"), record_elim(&fst, "fst")),
+                    (label("snd"), record_elim(&fst, "snd")),
+                ])),
+            ),
+        ]));
+
+        let ty1 = fun_elim(0, pair);
+        let ty2 = fun_elim(0, expanded);
+
+        assert!(!check_ty(&prims, &metas, var::Size(2), false, &ty1, &ty2).unwrap());
+        assert!(!check_ty(&prims, &metas, var::Size(2), false, &ty2, &ty1).unwrap());
+    }
+
+    #[test]
+    fn check_ty_record_eta_mismatched_labels() {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let pair = Rc::from(Value::var(1));
+        let swapped = Rc::from(Value::RecordIntro(vec![
+            (label("fst"), record_elim(&pair, "snd")),
+            (label("snd"), record_elim(&pair, "fst")),
+        ]));
+
+        let ty1 = fun_elim(0, pair);
+        let ty2 = fun_elim(0, swapped);
+
+        assert!(!check_ty(&prims, &metas, var::Size(2), false, &ty1, &ty2).unwrap());
+    }
+}
@@ -3,7 +3,67 @@
 use pretty::{BoxDoc, Doc};
 use std::borrow::Cow;
 
-use super::{syntax, var, AppMode, UniverseLevel};
+use super::literal::{LiteralIntro, LiteralPattern};
+use super::{syntax, var, AppMode, DocString, Label, Rc, UniverseLevel};
+
+/// The precedence of a term, mirroring the operator precedences used by the
+/// Pratt parser. Terms are wrapped in parentheses when they appear in a
+/// position that requires a higher precedence than their own.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Prec {
+    /// Terms that extend as far to the right as possible, eg. `fun x => x`,
+    /// `let .. in x`, or `x : A`.
+    Term,
+    /// Function types, eg. `Fun (x : A) -> B` or `A -> B`.
+    Arrow,
+    /// Function applications and other terms that can not be used as an
+    /// argument, eg. `f x` or `case x { .. }`.
+    App,
+    /// Atomic terms that can be used as arguments, eg. `x`, `1`, or `r.l`.
+    Arg,
+}
+
+/// Styles used to annotate pretty printed documents, allowing them to be
+/// highlighted when they are rendered, for example to a terminal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Style {
+    /// Keywords, eg. `fun`, `let`, or `Record`.
+    Keyword,
+    /// Names introduced by binders, eg. the `x` in `fun x => x`.
+    Binder,
+    /// Literals, eg. `1`, `"hello"`, or `true`.
+    Literal,
+    /// Universes, eg. `Type` or `Type^1`.
+    Universe,
+    /// The redex that is about to be contracted in a reduction step.
+    Redex,
+}
+
+pub fn keyword<'doc>(keyword: &'static str) -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
+    Doc::text(keyword).annotate(Style::Keyword)
+}
+
+pub fn binder<'doc>(name: impl ToString) -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
+    Doc::as_string(name).annotate(Style::Binder)
+}
+
+pub fn literal<'doc>(literal: impl ToString) -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
+    Doc::as_string(literal).annotate(Style::Literal)
+}
+
+/// Wrap a document in parentheses if its precedence is lower than the
+/// precedence required by its surroundings.
+pub fn with_prec<'doc, A>(
+    term_prec: Prec,
+    prec: Prec,
+    doc: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
+) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+    if term_prec < prec {
+        parens(doc)
+    } else {
+        doc.into()
+    }
+}
 
 pub fn parens<'doc, A>(
     inner: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
@@ -62,13 +122,14 @@ pub fn clause<'doc, A>(
         .append(";")
 }
 
-pub fn prim<'doc, A>(
-    prim_name: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
-) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+pub fn prim<'doc>(
+    prim_name: impl Into<Doc<'doc, BoxDoc<'doc, Style>, Style>>,
+) -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
     Doc::nil()
-        .append("primitive")
+        .append(keyword("primitive"))
         .append(Doc::space())
         .append(prim_name.into())
+        .group()
 }
 
 pub fn ann<'doc, A>(
@@ -93,16 +154,64 @@ pub fn record_elim<'doc, A>(
         .append(label.into())
 }
 
-pub fn universe0<'doc, A>() -> Doc<'doc, BoxDoc<'doc, A>, A> {
-    Doc::text("Type")
+pub fn array_ty<'doc, A>(
+    elem_ty: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
+) -> Doc<'doc, BoxDoc<'doc, A>, A> {
+    Doc::nil()
+        .append("Array")
+        .append(Doc::space())
+        .append(elem_ty.into())
 }
 
-pub fn universe<'doc, A>(
-    level: impl Into<Doc<'doc, BoxDoc<'doc, A>, A>>,
+pub fn array_intro<'doc, A: Clone>(
+    elems: impl Iterator<Item = Doc<'doc, BoxDoc<'doc, A>, A>>,
 ) -> Doc<'doc, BoxDoc<'doc, A>, A> {
-    Doc::text("Type^").append(level.into())
+    let elems = Doc::intersperse(elems.map(Doc::group), Doc::text(",").append(Doc::space()));
+
+    Doc::nil()
+        .append("[")
+        .append(Doc::nil().append(elems).nest(1))
+        .append("]")
+        .group()
+}
+
+pub fn universe0<'doc>() -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
+    Doc::text("Type").annotate(Style::Universe)
 }
 
+pub fn universe<'doc>(
+    level: impl Into<Doc<'doc, BoxDoc<'doc, Style>, Style>>,
+) -> Doc<'doc, BoxDoc<'doc, Style>, Style> {
+    Doc::text("Type^")
+        .append(level.into())
+        .annotate(Style::Universe)
+}
+
+/// Names that can't be used as binders, as they are keywords of the concrete
+/// syntax.
+const KEYWORDS: &[&str] = &[
+    "case",
+    "else",
+    "end",
+    "fun",
+    "Fun",
+    "if",
+    "in",
+    "let",
+    "namespace",
+    "open",
+    "pattern",
+    "primitive",
+    "record",
+    "Record",
+    "section",
+    "termination-by",
+    "then",
+    "Type",
+    "with",
+    "Σ",
+];
+
 /// An environment that can assist in pretty printing terms with pretty names.
 #[derive(Debug, Clone)]
 pub struct Env {
@@ -110,28 +219,92 @@ pub struct Env {
     names: var::Env<String>,
     /// A map of names to the number of times they have been used.
     names_to_counts: im::HashMap<String, usize>,
+    /// Whether implicit and instance applications and binders are displayed.
+    show_implicits: bool,
+    /// The size above which the bodies of functions are elided.
+    elide_size: Option<usize>,
+    /// The subterm to highlight, compared by address.
+    highlight: Option<Rc<syntax::Term>>,
+    /// The placeholders for subterms that were elided when reading back a
+    /// value, compared by address.
+    elided: Rc<[Rc<syntax::Term>]>,
 }
 
 impl Env {
     pub fn empty() -> Env {
         Env {
             names: var::Env::new(),
-            names_to_counts: im::HashMap::new(),
+            names_to_counts: reserved_names(),
+            show_implicits: false,
+            elide_size: None,
+            highlight: None,
+            elided: Rc::from(Vec::new()),
         }
     }
 
     pub fn new(names: var::Env<String>) -> Env {
         Env {
-            names_to_counts: names
-                .entries()
-                .iter()
+            names_to_counts: (names.entries().iter())
                 .map(|name| (name.clone(), 0))
+                .chain(reserved_names())
                 .collect(),
             names,
+            show_implicits: false,
+            elide_size: None,
+            highlight: None,
+            elided: Rc::from(Vec::new()),
         }
     }
 
-    fn lookup_name(&self, var_index: var::Index) -> Cow<'_, str> {
+    /// Whether implicit and instance applications and binders are displayed.
+    pub fn show_implicits(&self) -> bool {
+        self.show_implicits
+    }
+
+    /// Set whether implicit and instance applications and binders are
+    /// displayed. These are hidden by default.
+    pub fn set_show_implicits(&mut self, show_implicits: bool) {
+        self.show_implicits = show_implicits;
+    }
+
+    /// The size above which the bodies of functions are elided.
+    pub fn elide_size(&self) -> Option<usize> {
+        self.elide_size
+    }
+
+    /// Set the size, in nodes, above which the bodies of functions are
+    /// displayed as `...`. This is useful for displaying terms in weak head
+    /// normal form, where these bodies would be left unevaluated. Nothing is
+    /// elided by default.
+    pub fn set_elide_size(&mut self, elide_size: Option<usize>) {
+        self.elide_size = elide_size;
+    }
+
+    /// Set a subterm to annotate with `Style::Redex` when it is printed. The
+    /// subterm is found by its address, rather than by comparing terms, so it
+    /// should point into the term that is being printed.
+    pub fn set_highlight(&mut self, highlight: Option<Rc<syntax::Term>>) {
+        self.highlight = highlight;
+    }
+
+    /// Set the placeholders to display as `...`, for example those returned
+    /// by `nbe::read_back_whnf`. As with the highlighted subterm, these are
+    /// found by their addresses.
+    pub fn set_elided(&mut self, elided: Rc<[Rc<syntax::Term>]>) {
+        self.elided = elided;
+    }
+
+    /// Whether the term is a placeholder for an elided subterm.
+    fn is_elided(&self, term: &syntax::Term) -> bool {
+        (self.elided.iter()).any(|elided| std::ptr::eq(elided.as_ref(), term))
+    }
+
+    /// Whether the term is the subterm that is being highlighted.
+    fn is_highlight(&self, term: &syntax::Term) -> bool {
+        (self.highlight.as_ref()).is_some_and(|highlight| std::ptr::eq(highlight.as_ref(), term))
+    }
+
+    pub(crate) fn lookup_name(&self, var_index: var::Index) -> Cow<'_, str> {
         match self.names.lookup_entry(var_index) {
             Some(name) => Cow::from(name),
             None => Cow::from(format!("free{}", var_index)), // FIXME: Add to globals?
@@ -140,18 +313,29 @@ impl Env {
 
     /// Generate a fresh name based on the names that have already been
     /// used in the environment. We try to get close to the `name_hint`,
-    /// adding a number if necessary.
-    fn fresh_name(&mut self, name_hint: Option<&str>) -> String {
+    /// adding a number if necessary, so that the name doesn't shadow the
+    /// names in scope or clash with a keyword.
+    pub fn fresh_name(&mut self, name_hint: Option<&str>) -> String {
         // Use `x` as our default name, for lack of anything better...
         const DEFAULT_NAME: &str = "x";
 
         match name_hint {
-            None => self.fresh_name(Some(DEFAULT_NAME)),
+            // Placeholders can't be referred to, so they need a proper name
+            None | Some("_") => self.fresh_name(Some(DEFAULT_NAME)),
             Some(name_hint) => {
                 // Check to see if the hinted name was already used.
-                let name = match self.names_to_counts.get_mut(name_hint) {
-                    Some(count) => {
-                        *count += 1; // Bump the count if it's been used!
+                let name = match self.names_to_counts.get(name_hint) {
+                    Some(&count) => {
+                        // Bump the count if it's been used, skipping over
+                        // any names that would capture an existing name!
+                        let mut count = count + 1;
+                        while self
+                            .names_to_counts
+                            .contains_key(&format!("{}{}", name_hint, count))
+                        {
+                            count += 1;
+                        }
+                        self.names_to_counts.insert(name_hint.to_owned(), count);
                         format!("{}{}", name_hint, count)
                     },
                     None => name_hint.to_owned(),
@@ -167,8 +351,13 @@ impl Env {
     }
 }
 
+/// The usage counts of the keywords, so that they are never used as names.
+fn reserved_names() -> im::HashMap<String, usize> {
+    KEYWORDS.iter().map(|name| (name.to_string(), 0)).collect()
+}
+
 impl syntax::Module {
-    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, ()>> {
+    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, Style>, Style> {
         Doc::concat(self.items.iter().map(|item| {
             item.to_debug_doc()
                 .append(Doc::newline())
@@ -176,14 +365,14 @@ impl syntax::Module {
         }))
     }
 
-    pub fn to_display_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, ()>> {
+    pub fn to_display_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, Style>, Style> {
         let mut env = env.clone();
         items_to_display_doc(&self.items, &mut env)
     }
 }
 
 impl syntax::Item {
-    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, ()>> {
+    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, Style>, Style> {
         match self {
             syntax::Item::Declaration(_, label, term_ty) => {
                 declaration(Doc::as_string(label), term_ty.to_debug_doc())
@@ -198,60 +387,239 @@ impl syntax::Item {
 pub fn items_to_display_doc(
     items: &[syntax::Item],
     env: &mut Env,
-) -> Doc<'static, BoxDoc<'static, ()>> {
+) -> Doc<'static, BoxDoc<'static, Style>, Style> {
     Doc::concat(items.iter().map(|item| {
         match item {
             syntax::Item::Declaration(_, label, term_ty) => {
-                declaration(Doc::as_string(label), term_ty.to_display_doc(env))
+                // Use the name that the definition will be given
+                let name = env.clone().fresh_name(Some(&label.0));
+                declaration(binder(name), term_ty.to_display_doc(env))
                     .append(Doc::newline())
                     .append(Doc::newline())
             },
             syntax::Item::Definition(_, label, term) => {
-                let doc = definition(Doc::as_string(label), term.to_display_doc(env))
+                let term_doc = term.to_display_doc(env);
+                let name = env.fresh_name(Some(&label.0));
+                definition(binder(name), term_doc)
+                    .append(Doc::newline())
                     .append(Doc::newline())
-                    .append(Doc::newline());
-                env.fresh_name(Some(&label.0));
-                doc
             },
         }
     }))
 }
 
+/// The name hint to use for a parameter, falling back to the label of
+/// implicit and instance parameters.
+fn param_name_hint<'a>(app_mode: &'a AppMode, name_hint: &'a Option<String>) -> Option<&'a str> {
+    match (name_hint, app_mode) {
+        (Some(name_hint), _) => Some(name_hint.as_str()),
+        (None, AppMode::Explicit) => None,
+        (None, AppMode::Implicit(label)) | (None, AppMode::Instance(label)) => Some(&label.0),
+    }
+}
+
+/// The clauses of a chain of literal eliminations on the same scrutinee,
+/// along with the default body at the end of the chain.
+type LiteralElimClauses<'a> = (
+    Vec<&'a (LiteralPattern, Rc<syntax::Term>)>,
+    &'a Rc<syntax::Term>,
+);
+
+/// Merge chains of eliminations on the same scrutinee into a single case
+/// expression.
+fn merge_literal_elims<'a>(
+    scrutinee: &syntax::Term,
+    clauses: &'a [(LiteralPattern, Rc<syntax::Term>)],
+    default_body: &'a Rc<syntax::Term>,
+) -> LiteralElimClauses<'a> {
+    let mut clauses = clauses.iter().collect::<Vec<_>>();
+    let mut default_body = default_body;
+    while let syntax::Term::LiteralElim(next_scrutinee, next_clauses, next_default_body) =
+        default_body.as_ref()
+    {
+        if !next_scrutinee.alpha_eq(scrutinee) {
+            break;
+        }
+        for next_clause in next_clauses.iter() {
+            // Skip clauses that are shadowed by earlier clauses
+            if clauses
+                .iter()
+                .all(|(literal_pattern, _)| *literal_pattern != next_clause.0)
+            {
+                clauses.push(next_clause);
+            }
+        }
+        default_body = next_default_body;
+    }
+    (clauses, default_body)
+}
+
+/// Print a `case` or `with` expression, using the given pattern for the
+/// default clause.
+fn case_doc(
+    case_keyword: &'static str,
+    scrutinee: Doc<'static, BoxDoc<'static, Style>, Style>,
+    (clauses, default_body): LiteralElimClauses<'_>,
+    default_pattern: Doc<'static, BoxDoc<'static, Style>, Style>,
+    env: &Env,
+) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+    let clauses = if clauses.is_empty() {
+        Doc::nil()
+    } else {
+        Doc::concat(clauses.iter().map(|(literal_pattern, body)| {
+            clause(literal(literal_pattern), body.to_display_doc(env))
+                .group()
+                .append(Doc::space())
+        }))
+    };
+
+    Doc::nil()
+        .append(keyword(case_keyword))
+        .append(Doc::space())
+        .append(scrutinee)
+        .append(Doc::space())
+        .append("{")
+        .group()
+        .append(
+            Doc::nil()
+                .append(Doc::space())
+                .append(clauses)
+                .append(clause(default_pattern, default_body.to_display_doc(env)).group())
+                .group()
+                .nest(4),
+        )
+        .append(Doc::space())
+        .append("}")
+}
+
+/// The parameter name hint, clauses, and default body of the function that a
+/// `with` expression is elaborated to, if the function can be printed as
+/// one. The function matches on its parameter, and is annotated with the
+/// motive of the expression.
+#[allow(clippy::type_complexity)]
+fn with_elim(
+    fun: &syntax::Term,
+) -> Option<(
+    &Option<String>,
+    &[(LiteralPattern, Rc<syntax::Term>)],
+    &Rc<syntax::Term>,
+)> {
+    let fun = match fun {
+        syntax::Term::Ann(fun, _) => fun,
+        _ => return None,
+    };
+    let (name_hint, body) = match fun.as_ref() {
+        syntax::Term::FunIntro(AppMode::Explicit, name_hint, body) => (name_hint, body),
+        _ => return None,
+    };
+    match body.as_ref() {
+        syntax::Term::LiteralElim(scrutinee, clauses, default_body)
+            if **scrutinee == syntax::Term::var(0) =>
+        {
+            Some((name_hint, clauses, default_body))
+        },
+        _ => None,
+    }
+}
+
+/// The name hint and the types of the components of a record type, if it can
+/// be printed as a `Σ (x : A) -> B` type. The record needs to have exactly the
+/// `fst` and `snd` fields, with a name hint for the `fst` field.
+#[allow(clippy::type_complexity)]
+fn sigma_ty(
+    ty_fields: &[(DocString, Label, Option<String>, Rc<syntax::Term>)],
+) -> Option<(&str, &Rc<syntax::Term>, &Rc<syntax::Term>)> {
+    match ty_fields {
+        [(_, fst_label, Some(name_hint), fst_ty), (_, snd_label, None, snd_ty)]
+            if fst_label.0 == "fst" && snd_label.0 == "snd" =>
+        {
+            Some((name_hint, fst_ty, snd_ty))
+        },
+        _ => None,
+    }
+}
+
+/// The components of a record introduction, if it can be printed as a
+/// `(a, b)` pair. The record needs to have exactly the `fst` and `snd` fields,
+/// and the `snd` field can't refer to the `fst` field.
+fn pair_intro(
+    intro_fields: &[(Label, Rc<syntax::Term>)],
+) -> Option<(&Rc<syntax::Term>, &Rc<syntax::Term>)> {
+    match intro_fields {
+        [(fst_label, fst), (snd_label, snd)]
+            if fst_label.0 == "fst" && snd_label.0 == "snd" && !snd.has_free_var(var::Index(0)) =>
+        {
+            Some((fst, snd))
+        },
+        _ => None,
+    }
+}
+
+/// The consequent and alternative of a boolean elimination, if it can be
+/// printed as an `if` expression.
+fn if_branches<'a>(
+    clauses: &'a [(LiteralPattern, Rc<syntax::Term>)],
+    default_body: &'a Rc<syntax::Term>,
+) -> Option<(&'a Rc<syntax::Term>, &'a Rc<syntax::Term>)> {
+    match clauses {
+        [(LiteralPattern::Intro(LiteralIntro::Bool(true)), consequent)] => {
+            Some((consequent, default_body))
+        },
+        [(LiteralPattern::Intro(LiteralIntro::Bool(false)), alternative)] => {
+            Some((default_body, alternative))
+        },
+        [(LiteralPattern::Intro(LiteralIntro::Bool(false)), alternative), (LiteralPattern::Intro(LiteralIntro::Bool(true)), consequent)]
+        | [(LiteralPattern::Intro(LiteralIntro::Bool(true)), consequent), (LiteralPattern::Intro(LiteralIntro::Bool(false)), alternative)] => {
+            Some((consequent, alternative))
+        },
+        _ => None,
+    }
+}
+
 impl syntax::Term {
-    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, ()>> {
-        // FIXME: use proper precedences to mirror the Pratt parser?
-        match self {
+    pub fn to_debug_doc(&self) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        self.to_debug_prec_doc(Prec::Term)
+    }
+
+    pub fn to_debug_arg_doc(&self) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        self.to_debug_prec_doc(Prec::Arg)
+    }
+
+    pub fn to_debug_prec_doc(&self, prec: Prec) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        let doc = match self {
             syntax::Term::Var(var_index) => Doc::as_string(var_index),
             syntax::Term::Meta(meta_index) => Doc::as_string(meta_index),
             syntax::Term::Prim(prim_name) => prim(Doc::as_string(prim_name)),
 
-            syntax::Term::Ann(term, term_ty) => ann(term.to_debug_doc(), term_ty.to_debug_doc()),
+            syntax::Term::Ann(term, term_ty) => {
+                ann(term.to_debug_prec_doc(Prec::Arrow), term_ty.to_debug_doc())
+            },
             syntax::Term::Let(items, body) => Doc::nil()
-                .append("let")
+                .append(keyword("let"))
                 .append(Doc::space())
                 .append(Doc::concat(items.iter().map(|item| {
                     item.to_debug_doc()
                         .append(Doc::newline())
                         .append(Doc::newline())
                 })))
-                .append("in")
+                .append(keyword("in"))
                 .append(Doc::space().append(body.to_debug_doc()).group().nest(4)),
 
             syntax::Term::LiteralType(literal_ty) => Doc::as_string(literal_ty),
-            syntax::Term::LiteralIntro(literal_intro) => Doc::as_string(literal_intro),
+            syntax::Term::LiteralIntro(literal_intro) => literal(literal_intro),
             syntax::Term::LiteralElim(scrutinee, clauses, default_body) => {
                 let clauses = if clauses.is_empty() {
                     Doc::nil()
                 } else {
-                    Doc::concat(clauses.iter().map(|(literal_intro, body)| {
-                        clause(Doc::as_string(literal_intro), body.to_debug_doc())
+                    Doc::concat(clauses.iter().map(|(literal_pattern, body)| {
+                        clause(literal(literal_pattern), body.to_debug_doc())
                             .group()
                             .append(Doc::space())
                     }))
                 };
 
                 Doc::nil()
-                    .append("case")
+                    .append(keyword("case"))
                     .append(Doc::space())
                     .append(scrutinee.to_debug_arg_doc())
                     .append(Doc::space())
@@ -269,7 +637,7 @@ impl syntax::Term {
                     .append("}")
             },
 
-            syntax::Term::FunType(app_mode, _, param_ty, body_ty) => {
+            syntax::Term::FunType(_, app_mode, _, param_ty, body_ty) => {
                 let param = match app_mode {
                     AppMode::Explicit => Doc::nil()
                         .append("(")
@@ -298,7 +666,7 @@ impl syntax::Term {
                 };
 
                 Doc::nil()
-                    .append(Doc::text("Fun"))
+                    .append(keyword("Fun"))
                     .append(Doc::space())
                     .append(param.group())
                     .append(Doc::space())
@@ -306,7 +674,7 @@ impl syntax::Term {
                     .group()
                     .append(
                         Doc::space()
-                            .append(body_ty.to_debug_arg_doc())
+                            .append(body_ty.to_debug_prec_doc(Prec::Arrow))
                             .group()
                             .nest(4),
                     )
@@ -333,7 +701,7 @@ impl syntax::Term {
                 };
 
                 Doc::nil()
-                    .append("fun")
+                    .append(keyword("fun"))
                     .append(Doc::space())
                     .append(param.group())
                     .append(Doc::space())
@@ -362,15 +730,22 @@ impl syntax::Term {
                         .append("}}"),
                 };
 
+                let fun = match fun.as_ref() {
+                    syntax::Term::FunElim(_, _, _) => fun.to_debug_prec_doc(Prec::App),
+                    _ => fun.to_debug_arg_doc(),
+                };
+
                 Doc::nil()
-                    .append(fun.to_debug_arg_doc())
+                    .append(fun)
                     .append(Doc::space())
                     .append(arg.group())
             },
 
-            syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => Doc::text("Record {}"),
+            syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => {
+                keyword("Record").append(" {}")
+            },
             syntax::Term::RecordType(ty_fields) => Doc::nil()
-                .append("Record")
+                .append(keyword("Record"))
                 .append(Doc::space())
                 .append("{")
                 .group()
@@ -399,10 +774,10 @@ impl syntax::Term {
                 .append(Doc::space())
                 .append("}"),
             syntax::Term::RecordIntro(intro_fields) if intro_fields.is_empty() => {
-                Doc::text("record {}")
+                keyword("record").append(" {}")
             },
             syntax::Term::RecordIntro(intro_fields) => Doc::nil()
-                .append("record")
+                .append(keyword("record"))
                 .append(Doc::space())
                 .append("{")
                 .group()
@@ -432,45 +807,69 @@ impl syntax::Term {
                 .append(Doc::newline())
                 .append("}"),
             syntax::Term::RecordElim(record, label) => {
-                record_elim(record.to_debug_doc(), Doc::as_string(label))
+                record_elim(record.to_debug_arg_doc(), Doc::as_string(label))
+            },
+
+            syntax::Term::ArrayType(elem_ty) => array_ty(elem_ty.to_debug_arg_doc()),
+            syntax::Term::ArrayIntro(elems) => {
+                array_intro(elems.iter().map(|elem| elem.to_debug_doc()))
             },
 
             syntax::Term::Universe(level) => universe(Doc::as_string(level)),
-        }
+        };
+
+        with_prec(self.prec(), prec, doc)
     }
 
-    pub fn to_debug_arg_doc(&self) -> Doc<'static, BoxDoc<'static, ()>> {
-        match self {
-            syntax::Term::Var(_)
-            | syntax::Term::Meta(_)
-            | syntax::Term::LiteralIntro(_)
-            | syntax::Term::LiteralType(_)
-            | syntax::Term::RecordElim(_, _)
-            | syntax::Term::Universe(_) => self.to_debug_doc(),
-            _ => parens(self.to_debug_doc()),
-        }
+    pub fn to_display_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        self.to_display_prec_doc(env, Prec::Term)
     }
 
-    pub fn to_display_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, ()>> {
-        // FIXME: use proper precedences to mirror the Pratt parser?
-        match self {
+    pub fn to_display_arg_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        self.to_display_prec_doc(env, Prec::Arg)
+    }
+
+    pub fn to_display_prec_doc(
+        &self,
+        env: &Env,
+        prec: Prec,
+    ) -> Doc<'static, BoxDoc<'static, Style>, Style> {
+        if env.is_highlight(self) {
+            let mut env = env.clone();
+            env.set_highlight(None);
+            return self.to_display_prec_doc(&env, prec).annotate(Style::Redex);
+        }
+        if env.is_elided(self) {
+            return Doc::text("...");
+        }
+
+        let doc = match self {
             syntax::Term::Var(var_index) => Doc::as_string(env.lookup_name(*var_index)),
             syntax::Term::Meta(meta_index) => Doc::as_string(meta_index),
             syntax::Term::Prim(prim_name) => prim(Doc::as_string(prim_name)),
 
-            syntax::Term::Ann(term, term_ty) => {
-                ann(term.to_display_doc(env), term_ty.to_display_doc(env))
-            },
+            syntax::Term::Ann(term, term_ty) => ann(
+                term.to_display_prec_doc(env, Prec::Arrow),
+                term_ty.to_display_doc(env),
+            ),
             syntax::Term::Let(items, body) => {
                 let mut env = env.clone();
-                let items_doc = items_to_display_doc(items, &mut env);
+                let mut items_doc = items_to_display_doc(items, &mut env);
+                let mut body = body;
+                // Flatten nested lets into a single block
+                while let syntax::Term::Let(next_items, next_body) = body.as_ref() {
+                    if env.is_highlight(body) {
+                        break;
+                    }
+                    items_doc = items_doc.append(items_to_display_doc(next_items, &mut env));
+                    body = next_body;
+                }
 
-                // TODO: flatten definitions
                 Doc::nil()
-                    .append("let")
+                    .append(keyword("let"))
                     .append(Doc::space())
                     .append(items_doc)
-                    .append("in")
+                    .append(keyword("in"))
                     .append(
                         Doc::space()
                             .append(body.to_display_doc(&env))
@@ -480,60 +879,87 @@ impl syntax::Term {
             },
 
             syntax::Term::LiteralType(literal_ty) => Doc::as_string(literal_ty),
-            syntax::Term::LiteralIntro(literal_intro) => Doc::as_string(literal_intro),
+            syntax::Term::LiteralIntro(literal_intro) => literal(literal_intro),
             syntax::Term::LiteralElim(scrutinee, clauses, default_body) => {
+                if let Some((consequent, alternative)) = if_branches(clauses, default_body) {
+                    let doc = Doc::nil()
+                        .append(keyword("if"))
+                        .append(Doc::space())
+                        .append(scrutinee.to_display_doc(env))
+                        .append(Doc::space())
+                        .append(keyword("then"))
+                        .group()
+                        .append(Doc::space().append(consequent.to_display_doc(env)).nest(4))
+                        .append(Doc::space())
+                        .append(keyword("else"))
+                        .append(Doc::space().append(alternative.to_display_doc(env)).nest(4))
+                        .group();
+
+                    return with_prec(Prec::Term, prec, doc);
+                }
+
+                let clauses = merge_literal_elims(scrutinee, clauses, default_body);
                 let scrutinee = scrutinee.to_display_arg_doc(env);
-                let clauses = if clauses.is_empty() {
-                    Doc::nil()
-                } else {
-                    Doc::concat(clauses.iter().map(|(literal_intro, body)| {
-                        clause(Doc::as_string(literal_intro), body.to_display_doc(env))
-                            .group()
-                            .append(Doc::space())
-                    }))
-                };
+                case_doc("case", scrutinee, clauses, Doc::text("_"), env)
+            },
+
+            syntax::Term::FunType(_, AppMode::Explicit, name_hint, param_ty, body_ty)
+                if !body_ty.has_free_var(var::Index(0)) =>
+            {
+                let param_ty_doc = param_ty.to_display_prec_doc(env, Prec::App);
+                let mut env = env.clone();
+                env.fresh_name(name_hint.as_ref().map(String::as_str));
 
                 Doc::nil()
-                    .append("case")
-                    .append(Doc::space())
-                    .append(scrutinee)
+                    .append(param_ty_doc)
                     .append(Doc::space())
-                    .append("{")
+                    .append("->")
                     .group()
                     .append(
-                        Doc::nil()
-                            .append(Doc::space())
-                            .append(clauses)
-                            .append(clause("_", default_body.to_display_doc(env)).group())
+                        Doc::space()
+                            .append(body_ty.to_display_prec_doc(&env, Prec::Arrow))
                             .group()
                             .nest(4),
                     )
-                    .append(Doc::space())
-                    .append("}")
             },
-
-            syntax::Term::FunType(app_mode, name_hint, param_ty, body_ty) => {
+            syntax::Term::FunType(_, app_mode, name_hint, _, body_ty)
+                if !env.show_implicits && *app_mode != AppMode::Explicit =>
+            {
+                let mut env = env.clone();
+                env.fresh_name(param_name_hint(app_mode, name_hint));
+                return body_ty.to_display_prec_doc(&env, prec);
+            },
+            syntax::Term::FunType(_, app_mode, name_hint, param_ty, body_ty) => {
                 let mut env = env.clone();
                 let mut body_ty = body_ty;
                 let mut params = vec![(app_mode, name_hint, param_ty)];
-                while let syntax::Term::FunType(app_mode, name_hint, param_ty, next_body_ty) =
+                while let syntax::Term::FunType(_, app_mode, name_hint, param_ty, next_body_ty) =
                     body_ty.as_ref()
                 {
+                    // Leave non-dependent parameters to be printed as arrows
+                    if *app_mode == AppMode::Explicit && !next_body_ty.has_free_var(var::Index(0)) {
+                        break;
+                    }
                     params.push((app_mode, name_hint, param_ty));
                     body_ty = next_body_ty;
                 }
 
                 let params_doc = Doc::intersperse(
-                    params.iter().map(|(app_mode, name_hint, param_ty)| {
+                    params.iter().filter_map(|(app_mode, name_hint, param_ty)| {
+                        if !env.show_implicits && **app_mode != AppMode::Explicit {
+                            env.fresh_name(param_name_hint(app_mode, name_hint));
+                            return None;
+                        }
+
                         let param_ty_doc = param_ty.to_display_doc(&env);
-                        match app_mode {
+                        Some(match app_mode {
                             AppMode::Explicit => {
                                 let name_hint = name_hint.as_ref().map(String::as_str);
                                 let param_name = env.fresh_name(name_hint);
 
                                 Doc::nil()
                                     .append("(")
-                                    .append(param_name)
+                                    .append(binder(param_name))
                                     .append(Doc::space())
                                     .append(":")
                                     .group()
@@ -549,7 +975,7 @@ impl syntax::Term {
 
                                 Doc::nil()
                                     .append(if label.0 == param_name {
-                                        Doc::text("{").append(Doc::as_string(label)).group()
+                                        Doc::text("{").append(binder(label)).group()
                                     } else {
                                         Doc::nil()
                                             .append("{")
@@ -557,7 +983,7 @@ impl syntax::Term {
                                             .append(Doc::space())
                                             .append("=")
                                             .group()
-                                            .append(Doc::space().append(param_name).nest(4))
+                                            .append(Doc::space().append(binder(param_name)).nest(4))
                                     })
                                     .append(Doc::space())
                                     .append(":")
@@ -574,7 +1000,7 @@ impl syntax::Term {
 
                                 Doc::nil()
                                     .append(if label.0 == param_name {
-                                        Doc::text("{{").append(Doc::as_string(label)).group()
+                                        Doc::text("{{").append(binder(label)).group()
                                     } else {
                                         Doc::nil()
                                             .append("{{")
@@ -582,7 +1008,7 @@ impl syntax::Term {
                                             .append(Doc::space())
                                             .append("=")
                                             .group()
-                                            .append(Doc::space().append(param_name).nest(4))
+                                            .append(Doc::space().append(binder(param_name)).nest(4))
                                     })
                                     .append(Doc::space())
                                     .append(":")
@@ -591,26 +1017,32 @@ impl syntax::Term {
                                     .append("}}")
                                     .group()
                             },
-                        }
+                        })
                     }),
                     Doc::space(),
                 );
 
-                // TODO: use non-dependent function if possible
                 // TODO: flatten params
                 Doc::nil()
-                    .append(Doc::text("Fun"))
+                    .append(keyword("Fun"))
                     .append(Doc::space().append(params_doc).nest(4))
                     .append(Doc::space())
                     .append("->")
                     .group()
                     .append(
                         Doc::space()
-                            .append(body_ty.to_display_arg_doc(&env))
+                            .append(body_ty.to_display_prec_doc(&env, Prec::Arrow))
                             .group()
                             .nest(4),
                     )
             },
+            syntax::Term::FunIntro(app_mode, name_hint, body)
+                if !env.show_implicits && *app_mode != AppMode::Explicit =>
+            {
+                let mut env = env.clone();
+                env.fresh_name(param_name_hint(app_mode, name_hint));
+                return body.to_display_prec_doc(&env, prec);
+            },
             syntax::Term::FunIntro(app_mode, name_hint, body) => {
                 let mut env = env.clone();
                 let mut body = body;
@@ -621,12 +1053,16 @@ impl syntax::Term {
                 }
 
                 let params_doc = Doc::intersperse(
-                    app_modes
-                        .iter()
-                        .map(|(app_mode, name_hint)| match app_mode {
+                    app_modes.iter().filter_map(|(app_mode, name_hint)| {
+                        if !env.show_implicits && **app_mode != AppMode::Explicit {
+                            env.fresh_name(param_name_hint(app_mode, name_hint));
+                            return None;
+                        }
+
+                        Some(match app_mode {
                             AppMode::Explicit => {
                                 let name_hint = name_hint.as_ref().map(String::as_str);
-                                Doc::as_string(env.fresh_name(name_hint)).group()
+                                binder(env.fresh_name(name_hint)).group()
                             },
                             AppMode::Implicit(label) => {
                                 let param_name = match name_hint {
@@ -636,7 +1072,7 @@ impl syntax::Term {
 
                                 Doc::nil()
                                     .append(if label.0 == param_name {
-                                        Doc::text("{").append(Doc::as_string(label)).group()
+                                        Doc::text("{").append(binder(label)).group()
                                     } else {
                                         Doc::nil()
                                             .append("{")
@@ -644,7 +1080,7 @@ impl syntax::Term {
                                             .append(Doc::space())
                                             .append("=")
                                             .group()
-                                            .append(Doc::space().append(param_name).nest(4))
+                                            .append(Doc::space().append(binder(param_name)).nest(4))
                                     })
                                     .append("}")
                                     .group()
@@ -657,7 +1093,7 @@ impl syntax::Term {
 
                                 Doc::nil()
                                     .append(if label.0 == param_name {
-                                        Doc::text("{{").append(Doc::as_string(label)).group()
+                                        Doc::text("{{").append(binder(label)).group()
                                     } else {
                                         Doc::nil()
                                             .append("{{")
@@ -665,17 +1101,18 @@ impl syntax::Term {
                                             .append(Doc::space())
                                             .append("=")
                                             .group()
-                                            .append(Doc::space().append(param_name).nest(4))
+                                            .append(Doc::space().append(binder(param_name)).nest(4))
                                     })
                                     .append("}}")
                                     .group()
                             },
-                        }),
+                        })
+                    }),
                     Doc::space(),
                 );
 
                 Doc::nil()
-                    .append("fun")
+                    .append(keyword("fun"))
                     .append(Doc::space())
                     .append(params_doc)
                     .append(Doc::space())
@@ -683,16 +1120,38 @@ impl syntax::Term {
                     .group()
                     .append(
                         Doc::space()
-                            .append(body.to_display_doc(&env))
+                            .append(match env.elide_size {
+                                Some(size) if body.metrics().size > size => Doc::text("..."),
+                                Some(_) | None => body.to_display_doc(&env),
+                            })
                             .group()
                             .nest(4),
                     )
             },
+            syntax::Term::FunElim(fun, app_mode, _)
+                if !env.show_implicits && *app_mode != AppMode::Explicit =>
+            {
+                return fun.to_display_prec_doc(env, prec);
+            },
+            syntax::Term::FunElim(fun, AppMode::Explicit, arg) if with_elim(fun).is_some() => {
+                let (name_hint, clauses, default_body) = with_elim(fun).unwrap();
+                let scrutinee = arg.to_display_arg_doc(env);
+                let mut env = env.clone();
+                let name = env.fresh_name(name_hint.as_ref().map(String::as_str));
+                let clauses = merge_literal_elims(&syntax::Term::var(0), clauses, default_body);
+                case_doc("with", scrutinee, clauses, binder(name), &env)
+            },
             syntax::Term::FunElim(fun, app_mode, arg) => {
                 let mut fun = fun;
                 let mut args = vec![(app_mode, arg)];
                 while let syntax::Term::FunElim(next_fun, app_mode, arg) = fun.as_ref() {
-                    args.push((app_mode, arg));
+                    // Keep the highlighted application together
+                    if env.is_highlight(fun) {
+                        break;
+                    }
+                    if env.show_implicits || *app_mode == AppMode::Explicit {
+                        args.push((app_mode, arg));
+                    }
                     fun = next_fun;
                 }
 
@@ -726,7 +1185,41 @@ impl syntax::Term {
                     .append(Doc::space().append(args_doc).nest(4))
             },
 
-            syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => Doc::text("Record {}"),
+            syntax::Term::RecordType(ty_fields) if ty_fields.is_empty() => {
+                keyword("Record").append(" {}")
+            },
+            syntax::Term::RecordType(ty_fields) if sigma_ty(ty_fields).is_some() => {
+                let (name_hint, fst_ty, snd_ty) = sigma_ty(ty_fields).unwrap();
+                let fst_ty_doc = fst_ty.to_display_doc(env);
+                let mut env = env.clone();
+                let name = env.fresh_name(Some(name_hint));
+
+                let doc = Doc::nil()
+                    .append(keyword("Σ"))
+                    .append(Doc::space())
+                    .append(
+                        Doc::nil()
+                            .append("(")
+                            .append(binder(name))
+                            .append(Doc::space())
+                            .append(":")
+                            .group()
+                            .append(Doc::space().append(fst_ty_doc).nest(4))
+                            .append(")")
+                            .group(),
+                    )
+                    .append(Doc::space())
+                    .append("->")
+                    .group()
+                    .append(
+                        Doc::space()
+                            .append(snd_ty.to_display_prec_doc(&env, Prec::Arrow))
+                            .group()
+                            .nest(4),
+                    );
+
+                return with_prec(Prec::Arrow, prec, doc);
+            },
             syntax::Term::RecordType(ty_fields) => {
                 let mut env = env.clone();
 
@@ -741,14 +1234,14 @@ impl syntax::Term {
 
                             Doc::nil()
                                 .append(if label.0 == field_name {
-                                    Doc::as_string(label)
+                                    binder(label)
                                 } else {
                                     Doc::nil()
                                         .append(Doc::as_string(label))
                                         .append(Doc::space())
                                         .append("=")
                                         .group()
-                                        .append(Doc::space().append(field_name))
+                                        .append(Doc::space().append(binder(field_name)))
                                         .group()
                                 })
                                 .append(Doc::space())
@@ -762,7 +1255,7 @@ impl syntax::Term {
                 };
 
                 Doc::nil()
-                    .append("Record")
+                    .append(keyword("Record"))
                     .append(Doc::space())
                     .append("{")
                     .group()
@@ -771,57 +1264,92 @@ impl syntax::Term {
                     .append("}")
             },
             syntax::Term::RecordIntro(intro_fields) if intro_fields.is_empty() => {
-                Doc::text("record {}")
+                keyword("record").append(" {}")
+            },
+            syntax::Term::RecordIntro(intro_fields) if pair_intro(intro_fields).is_some() => {
+                let (fst, snd) = pair_intro(intro_fields).unwrap();
+                let fst_doc = fst.to_display_doc(env);
+                // The second component is in the scope of the first
+                let mut env = env.clone();
+                env.fresh_name(Some("fst"));
+
+                let doc = Doc::nil()
+                    .append("(")
+                    .append(fst_doc)
+                    .append(",")
+                    .append(Doc::space())
+                    .append(snd.to_display_doc(&env))
+                    .append(")")
+                    .group();
+
+                return with_prec(Prec::Arg, prec, doc);
+            },
+            syntax::Term::RecordIntro(intro_fields) => {
+                let mut env = env.clone();
+
+                let fields_doc = Doc::intersperse(
+                    intro_fields.iter().map(|(label, term)| {
+                        // TODO: parameter sugar
+                        let term_doc = term.to_display_doc(&env);
+                        // Later fields are in the scope of the earlier fields
+                        env.fresh_name(Some(&label.0));
+
+                        Doc::nil()
+                            .append(Doc::as_string(label))
+                            .append(Doc::space())
+                            .append("=")
+                            .group()
+                            .append(Doc::space().append(term_doc).append(";").group().nest(4))
+                            .group()
+                    }),
+                    Doc::space(),
+                );
+
+                Doc::nil()
+                    .append(keyword("record"))
+                    .append(Doc::space())
+                    .append("{")
+                    .group()
+                    .append(Doc::space().append(fields_doc).nest(4))
+                    .append(Doc::space())
+                    .append("}")
             },
-            syntax::Term::RecordIntro(intro_fields) => Doc::nil()
-                .append("record")
-                .append(Doc::space())
-                .append("{")
-                .group()
-                .append(
-                    Doc::nil()
-                        .append(Doc::space())
-                        .append(Doc::intersperse(
-                            intro_fields.iter().map(|(label, term)| {
-                                // TODO: parameter sugar
-                                Doc::nil()
-                                    .append(Doc::as_string(label))
-                                    .append(Doc::space())
-                                    .append("=")
-                                    .group()
-                                    .append(
-                                        Doc::space()
-                                            .append(term.to_display_doc(env))
-                                            .append(";")
-                                            .group()
-                                            .nest(4),
-                                    )
-                                    .group()
-                            }),
-                            Doc::space(),
-                        ))
-                        .nest(4),
-                )
-                .append(Doc::space())
-                .append("}"),
             syntax::Term::RecordElim(record, label) => {
-                record_elim(record.to_display_doc(env), Doc::as_string(label))
+                record_elim(record.to_display_arg_doc(env), Doc::as_string(label))
+            },
+
+            syntax::Term::ArrayType(elem_ty) => array_ty(elem_ty.to_display_arg_doc(env)),
+            syntax::Term::ArrayIntro(elems) => {
+                array_intro(elems.iter().map(|elem| elem.to_display_doc(env)))
             },
 
             syntax::Term::Universe(UniverseLevel(0)) => universe0(),
             syntax::Term::Universe(level) => universe(Doc::as_string(level)),
-        }
+        };
+
+        with_prec(self.prec(), prec, doc)
     }
 
-    pub fn to_display_arg_doc(&self, env: &Env) -> Doc<'static, BoxDoc<'static, ()>> {
+    /// The precedence of the term when it is printed.
+    pub fn prec(&self) -> Prec {
         match self {
             syntax::Term::Var(_)
             | syntax::Term::Meta(_)
-            | syntax::Term::LiteralIntro(_)
             | syntax::Term::LiteralType(_)
+            | syntax::Term::LiteralIntro(_)
             | syntax::Term::RecordElim(_, _)
-            | syntax::Term::Universe(_) => self.to_display_doc(env),
-            _ => parens(self.to_display_doc(env)),
+            | syntax::Term::ArrayIntro(_)
+            | syntax::Term::Universe(_) => Prec::Arg,
+            syntax::Term::Prim(_)
+            | syntax::Term::LiteralElim(_, _, _)
+            | syntax::Term::FunElim(_, _, _)
+            | syntax::Term::RecordType(_)
+            | syntax::Term::RecordIntro(_)
+            | syntax::Term::ArrayType(_) => Prec::App,
+            syntax::Term::FunType(_, _, _, _, _) => Prec::Arrow,
+            syntax::Term::Ann(_, _) | syntax::Term::Let(_, _) | syntax::Term::FunIntro(_, _, _) => {
+                Prec::Term
+            },
         }
     }
 }
@@ -829,6 +1357,7 @@ impl syntax::Term {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{DocString, Label};
 
     #[test]
     fn env_fresh_name() {
@@ -856,6 +1385,23 @@ mod tests {
         assert_eq!(env.fresh_name(Some("x2")), "x21");
     }
 
+    #[test]
+    fn env_fresh_name_placeholder() {
+        let mut env = Env::empty();
+
+        assert_eq!(env.fresh_name(Some("_")), "x");
+        assert_eq!(env.fresh_name(Some("_")), "x1");
+    }
+
+    #[test]
+    fn env_fresh_name_avoid_capture() {
+        let mut env = Env::empty();
+
+        assert_eq!(env.fresh_name(Some("x1")), "x1");
+        assert_eq!(env.fresh_name(Some("x")), "x");
+        assert_eq!(env.fresh_name(Some("x")), "x2");
+    }
+
     #[test]
     fn env_fresh_name_default_rev() {
         let mut env = Env::empty();
@@ -863,4 +1409,320 @@ mod tests {
         assert_eq!(env.fresh_name(Some("x")), "x");
         assert_eq!(env.fresh_name(None), "x1");
     }
+
+    fn fun_elim(fun: syntax::Term, arg: syntax::Term) -> syntax::Term {
+        syntax::Term::FunElim(Rc::from(fun), AppMode::Explicit, Rc::from(arg))
+    }
+
+    fn display(term: &syntax::Term) -> String {
+        display_with(term, false)
+    }
+
+    fn display_with(term: &syntax::Term, show_implicits: bool) -> String {
+        let mut names = var::Env::new();
+        names.add_entry("a".to_owned());
+        names.add_entry("g".to_owned());
+        names.add_entry("f".to_owned());
+        let mut env = Env::new(names);
+        env.set_show_implicits(show_implicits);
+        term.to_display_doc(&env)
+            .group()
+            .pretty(1_000_000_000)
+            .to_string()
+    }
+
+    #[test]
+    fn display_fun_type_non_dependent() {
+        let body_ty = syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("y".to_owned()),
+            Rc::from(syntax::Term::var(1)),
+            Rc::from(syntax::Term::var(2)),
+        );
+        let term = syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("A".to_owned()),
+            Rc::from(syntax::Term::universe(0)),
+            Rc::from(syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("x".to_owned()),
+                Rc::from(syntax::Term::var(0)),
+                Rc::from(body_ty),
+            )),
+        );
+
+        assert_eq!(display(&term), "Fun (A : Type) -> A -> A -> A");
+    }
+
+    #[test]
+    fn display_fun_elim_nested_fun() {
+        let term = fun_elim(
+            fun_elim(syntax::Term::var(0), syntax::Term::var(1)),
+            syntax::Term::var(2),
+        );
+
+        assert_eq!(display(&term), "f g a");
+        assert_eq!(format!("{:?}", term), "@0 @1 @2");
+    }
+
+    #[test]
+    fn display_fun_elim_nested_arg() {
+        let term = fun_elim(
+            syntax::Term::var(0),
+            fun_elim(syntax::Term::var(1), syntax::Term::var(2)),
+        );
+
+        assert_eq!(display(&term), "f (g a)");
+        assert_eq!(format!("{:?}", term), "@0 (@1 @2)");
+    }
+
+    #[test]
+    fn display_fun_elim_implicits() {
+        let label = Label("A".to_owned());
+        let fun = syntax::Term::FunElim(
+            Rc::from(syntax::Term::var(0)),
+            AppMode::Implicit(label),
+            Rc::from(syntax::Term::var(1)),
+        );
+        let term = fun_elim(fun.clone(), syntax::Term::var(2));
+
+        assert_eq!(display(&fun), "f");
+        assert_eq!(display(&term), "f a");
+        assert_eq!(display_with(&fun, true), "f {A = g}");
+        assert_eq!(display_with(&term, true), "f {A = g} a");
+    }
+
+    #[test]
+    fn display_fun_type_implicits() {
+        let term = syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Implicit(Label("A".to_owned())),
+            None,
+            Rc::from(syntax::Term::universe(0)),
+            Rc::from(syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("x".to_owned()),
+                Rc::from(syntax::Term::var(0)),
+                Rc::from(syntax::Term::var(1)),
+            )),
+        );
+
+        assert_eq!(display(&term), "A -> A");
+        assert_eq!(display_with(&term, true), "Fun {A : Type} -> A -> A");
+    }
+
+    #[test]
+    fn display_fun_intro_implicits() {
+        let term = syntax::Term::FunIntro(
+            AppMode::Implicit(Label("A".to_owned())),
+            None,
+            Rc::from(syntax::Term::FunIntro(
+                AppMode::Explicit,
+                Some("x".to_owned()),
+                Rc::from(syntax::Term::var(0)),
+            )),
+        );
+
+        assert_eq!(display(&term), "fun x => x");
+        assert_eq!(display_with(&term, true), "fun {A} x => x");
+    }
+
+    fn literal_elim(
+        scrutinee: syntax::Term,
+        clauses: Vec<(LiteralIntro, syntax::Term)>,
+        default_body: syntax::Term,
+    ) -> syntax::Term {
+        let clauses = clauses
+            .into_iter()
+            .map(|(literal_intro, body)| (LiteralPattern::from(literal_intro), Rc::from(body)))
+            .collect::<Vec<_>>();
+
+        syntax::Term::LiteralElim(
+            Rc::from(scrutinee),
+            Rc::from(clauses),
+            Rc::from(default_body),
+        )
+    }
+
+    #[test]
+    fn display_literal_elim_if() {
+        let term = literal_elim(
+            syntax::Term::var(0),
+            vec![(LiteralIntro::Bool(true), syntax::Term::var(1))],
+            syntax::Term::var(2),
+        );
+
+        assert_eq!(display(&term), "if f then g else a");
+    }
+
+    #[test]
+    fn display_literal_elim_if_false() {
+        let term = literal_elim(
+            syntax::Term::var(0),
+            vec![(LiteralIntro::Bool(false), syntax::Term::var(1))],
+            syntax::Term::var(2),
+        );
+
+        assert_eq!(display(&term), "if f then a else g");
+    }
+
+    #[test]
+    fn display_literal_elim_chain() {
+        let term = literal_elim(
+            syntax::Term::var(0),
+            vec![(LiteralIntro::U32(1), syntax::Term::var(1))],
+            literal_elim(
+                syntax::Term::var(0),
+                vec![
+                    (LiteralIntro::U32(1), syntax::Term::var(2)),
+                    (LiteralIntro::U32(2), syntax::Term::var(2)),
+                ],
+                syntax::Term::var(1),
+            ),
+        );
+
+        assert_eq!(display(&term), "case f { 1 => g; 2 => a; _ => g; }");
+    }
+
+    #[test]
+    fn display_let_nested() {
+        let doc = DocString::from("");
+        let term = syntax::Term::Let(
+            vec![syntax::Item::Definition(
+                doc.clone(),
+                Label("x".to_owned()),
+                Rc::from(syntax::Term::var(0)),
+            )],
+            Rc::from(syntax::Term::Let(
+                vec![syntax::Item::Definition(
+                    doc,
+                    Label("y".to_owned()),
+                    Rc::from(syntax::Term::var(0)),
+                )],
+                Rc::from(syntax::Term::var(0)),
+            )),
+        );
+
+        assert_eq!(display(&term), "let x = f;\n\ny = x;\n\nin y");
+    }
+
+    #[test]
+    fn display_let_shadowed() {
+        let term = syntax::Term::Let(
+            vec![
+                syntax::Item::Declaration(
+                    DocString::from(""),
+                    Label("a".to_owned()),
+                    Rc::from(syntax::Term::var(1)),
+                ),
+                syntax::Item::Definition(
+                    DocString::from(""),
+                    Label("a".to_owned()),
+                    Rc::from(syntax::Term::var(2)),
+                ),
+            ],
+            Rc::from(fun_elim(syntax::Term::var(0), syntax::Term::var(3))),
+        );
+
+        assert_eq!(display(&term), "let a1 : g;\n\na1 = a;\n\nin a1 a");
+    }
+
+    #[test]
+    fn display_fun_intro_keyword() {
+        let term = syntax::Term::FunIntro(
+            AppMode::Explicit,
+            Some("fun".to_owned()),
+            Rc::from(syntax::Term::var(0)),
+        );
+
+        assert_eq!(display(&term), "fun fun1 => fun1");
+    }
+
+    #[test]
+    fn display_with_expr() {
+        let clauses = vec![(
+            LiteralPattern::Intro(LiteralIntro::Bool(true)),
+            Rc::from(syntax::Term::var(1)),
+        )];
+        let fun = syntax::Term::FunIntro(
+            AppMode::Explicit,
+            Some("b".to_owned()),
+            Rc::from(syntax::Term::LiteralElim(
+                Rc::from(syntax::Term::var(0)),
+                Rc::from(clauses),
+                Rc::from(syntax::Term::var(0)),
+            )),
+        );
+        let fun_ty = syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("b".to_owned()),
+            Rc::from(syntax::Term::var(1)),
+            Rc::from(syntax::Term::var(2)),
+        );
+        let term = fun_elim(syntax::Term::ann(fun, fun_ty), syntax::Term::var(2));
+
+        assert_eq!(display(&term), "with a { true => f; b => b; }");
+    }
+
+    #[test]
+    fn display_sigma_ty() {
+        let term = syntax::Term::RecordType(vec![
+            (
+                DocString::from(""),
+                Label("fst".to_owned()),
+                Some("x".to_owned()),
+                Rc::from(syntax::Term::var(2)),
+            ),
+            (
+                DocString::from(""),
+                Label("snd".to_owned()),
+                None,
+                Rc::from(fun_elim(syntax::Term::var(2), syntax::Term::var(0))),
+            ),
+        ]);
+
+        assert_eq!(display(&term), "Σ (x : a) -> g x");
+    }
+
+    #[test]
+    fn display_pair_intro() {
+        let term = syntax::Term::RecordIntro(vec![
+            (Label("fst".to_owned()), Rc::from(syntax::Term::var(0))),
+            (Label("snd".to_owned()), Rc::from(syntax::Term::var(3))),
+        ]);
+
+        assert_eq!(display(&term), "(f, a)");
+    }
+
+    #[test]
+    fn display_pair_intro_dependent() {
+        let term = syntax::Term::RecordIntro(vec![
+            (Label("fst".to_owned()), Rc::from(syntax::Term::var(0))),
+            (Label("snd".to_owned()), Rc::from(syntax::Term::var(0))),
+        ]);
+
+        assert!(display(&term).starts_with("record {"));
+    }
+
+    #[test]
+    fn display_record_elim_fun_elim() {
+        let fun_elim = fun_elim(syntax::Term::var(0), syntax::Term::var(1));
+        let term = syntax::Term::RecordElim(Rc::from(fun_elim), Label("l".to_owned()));
+
+        assert_eq!(display(&term), "(f g).l");
+    }
+
+    #[test]
+    fn display_ann_fun_intro() {
+        let fun_intro =
+            syntax::Term::FunIntro(AppMode::Explicit, None, Rc::from(syntax::Term::var(0)));
+        let term = syntax::Term::ann(fun_intro, syntax::Term::universe(0));
+
+        assert_eq!(display(&term), "(fun x => x) : Type");
+    }
 }
@@ -1,8 +1,13 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::rc::Rc;
+use std::sync::Mutex;
 
-use super::literal::LiteralIntro;
-use crate::domain::{Elim, Value};
+use super::literal::{LiteralIntro, LiteralType};
+use crate::builder::{TermBuilder, Var};
+use crate::domain::{Elim, Head, Thunk, Value};
+use crate::syntax::Term;
+use crate::{var, AppMode, Rc};
 
 /// The name of a primitive.
 #[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
@@ -26,6 +31,31 @@ impl std::fmt::Display for Name {
     }
 }
 
+/// The effects that a primitive may have when it is interpreted, from the
+/// least to the most dangerous.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// The primitive always returns the same result for the same arguments.
+    Pure,
+    /// The primitive interacts with the world outside of the program.
+    Io,
+    /// The primitive can break the guarantees of the type system.
+    Unsafe,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Capability::Pure => write!(f, "pure"),
+            Capability::Io => write!(f, "io"),
+            Capability::Unsafe => write!(f, "unsafe"),
+        }
+    }
+}
+
+/// The interpretation of a primitive, given its arguments.
+pub type Interpretation = fn(Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>>;
+
 /// An entry in the primitive environment.
 #[derive(Clone)]
 pub struct Entry {
@@ -39,7 +69,12 @@ pub struct Entry {
     /// - `Some(Ok(_))`: if the primitive returned a value
     /// - `Some(Err(_))`: if the primitive resulted in an evaluation error
     /// - `None`: if the primitive is stuck on an argument
-    pub interpretation: fn(Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>>,
+    pub interpretation: Interpretation,
+    /// The type of the primitive, if it has a fixed signature. Primitives
+    /// without a signature can be used at any type that is provided for them.
+    pub ty: Option<Rc<Term>>,
+    /// The effects that the primitive may have when it is interpreted.
+    pub capability: Capability,
 }
 
 impl Entry {
@@ -49,6 +84,7 @@ impl Entry {
     /// Also known as [δ-reduction (delta-reduction)][δ-reduction].
     ///
     /// [δ-reduction]: http://barrywatson.se/lsi/lsi_delta_reduction.html
+    #[allow(clippy::type_complexity)]
     pub fn interpret<'spine>(
         &self,
         spine: &'spine [Elim],
@@ -79,14 +115,43 @@ impl fmt::Debug for Entry {
         f.debug_struct("Entry")
             .field("arity", &self.arity)
             .field("interpretation", &"|args| { .. }")
+            .field("ty", &self.ty)
+            .field("capability", &self.capability)
             .finish()
     }
 }
 
+/// A record of the primitives that were interpreted to a value, for
+/// reporting on how much constant folding happened during normalization.
+#[derive(Debug, Default)]
+pub struct FoldLog {
+    folds: Mutex<BTreeMap<Name, usize>>,
+}
+
+impl FoldLog {
+    /// Create a new, empty log.
+    pub fn new() -> FoldLog {
+        FoldLog::default()
+    }
+
+    /// The number of times that each primitive was interpreted to a value.
+    pub fn folds(&self) -> BTreeMap<Name, usize> {
+        self.folds.lock().unwrap().clone()
+    }
+
+    fn record(&self, name: &Name) {
+        *self.folds.lock().unwrap().entry(name.clone()).or_insert(0) += 1;
+    }
+}
+
 /// An environment of primitives to use during normalization.
 #[derive(Debug, Clone)]
 pub struct Env {
     entries: im::HashMap<Name, Entry>,
+    /// The most effectful primitives that can be interpreted.
+    allowed: Capability,
+    /// Where the primitives that are interpreted are recorded, if anywhere.
+    fold_log: Option<Rc<FoldLog>>,
 }
 
 impl Env {
@@ -94,9 +159,50 @@ impl Env {
     pub fn new() -> Env {
         Env {
             entries: im::HashMap::new(),
+            allowed: Capability::Pure,
+            fold_log: None,
         }
     }
 
+    /// The most effectful primitives that can be interpreted.
+    pub fn allowed(&self) -> Capability {
+        self.allowed
+    }
+
+    /// Set the most effectful primitives that can be interpreted. Primitives
+    /// that need more capabilities than this are left unevaluated, so that
+    /// normalization stays deterministic. Only pure primitives are
+    /// interpreted by default.
+    pub fn set_allowed(&mut self, allowed: Capability) {
+        self.allowed = allowed;
+    }
+
+    /// Record the primitives that are interpreted to a value in the given
+    /// log, or stop recording them if it is `None`.
+    pub fn set_fold_log(&mut self, fold_log: Option<Rc<FoldLog>>) {
+        self.fold_log = fold_log;
+    }
+
+    /// Interpret the primitive with the given name and entry, if the
+    /// environment allows its effects. `None` is returned if evaluation is
+    /// stuck.
+    #[allow(clippy::type_complexity)]
+    pub fn interpret<'spine>(
+        &self,
+        name: &Name,
+        entry: &Entry,
+        spine: &'spine [Elim],
+    ) -> Option<Result<(Rc<Value>, &'spine [Elim]), String>> {
+        if entry.capability > self.allowed {
+            return None;
+        }
+        let result = entry.interpret(spine);
+        if let (Some(fold_log), Some(Ok(_))) = (&self.fold_log, &result) {
+            fold_log.record(name);
+        }
+        result
+    }
+
     /// Lookup an entry in the environment.
     pub fn lookup_entry(&self, name: &Name) -> Option<&Entry> {
         self.entries.get(name)
@@ -106,22 +212,53 @@ impl Env {
     pub fn add_entry(&mut self, name: Name, entry: Entry) {
         self.entries.insert(name, entry);
     }
+
+    /// The entries in the environment, in no particular order.
+    pub fn entries(&self) -> impl Iterator<Item = (&Name, &Entry)> {
+        self.entries.iter()
+    }
 }
 
 trait TryFromValue {
-    fn try_from_value(src: &Value) -> Option<&Self>;
+    fn try_from_value(src: &Rc<Value>) -> Option<&Self>;
+}
+
+impl TryFromValue for Rc<Value> {
+    fn try_from_value(src: &Rc<Value>) -> Option<&Rc<Value>> {
+        Some(src)
+    }
+}
+
+impl TryFromValue for im::Vector<Rc<Value>> {
+    fn try_from_value(src: &Rc<Value>) -> Option<&im::Vector<Rc<Value>>> {
+        match src.as_ref() {
+            Value::ArrayIntro(elems) => Some(elems),
+            _ => None,
+        }
+    }
+}
+
+/// Rust types that correspond to a type in the core syntax.
+trait PrimType {
+    fn prim_ty() -> Rc<Term>;
 }
 
 macro_rules! impl_try_from_value_literal {
     ($T:ty, $Literal:ident) => {
         impl TryFromValue for $T {
-            fn try_from_value(src: &Value) -> Option<&$T> {
-                match src {
+            fn try_from_value(src: &Rc<Value>) -> Option<&$T> {
+                match src.as_ref() {
                     Value::LiteralIntro(LiteralIntro::$Literal(value)) => Some(value),
                     _ => None,
                 }
             }
         }
+
+        impl PrimType for $T {
+            fn prim_ty() -> Rc<Term> {
+                Rc::from(Term::literal_ty(LiteralType::$Literal))
+            }
+        }
     };
 }
 
@@ -139,6 +276,21 @@ impl_try_from_value_literal!(i64, S64);
 impl_try_from_value_literal!(f32, F32);
 impl_try_from_value_literal!(f64, F64);
 
+/// The result of a checked arithmetic primitive, reporting an evaluation error
+/// if the operation overflowed, divided by zero, shifted out of range, or
+/// converted to a value that is out of range.
+fn checked(prim_name: &str, result: Option<impl Into<LiteralIntro>>) -> Result<Rc<Value>, String> {
+    match result {
+        Some(value) => Ok(Rc::from(Value::literal_intro(value))),
+        None => Err(format!("arithmetic error in `{}`", prim_name)),
+    }
+}
+
+/// The longest array that `array-make` will create. This primitive also runs
+/// during elaboration and normalization, so it must not be allowed to exhaust
+/// the available memory.
+const ARRAY_MAX_LEN: u64 = 1 << 20;
+
 impl Default for Env {
     fn default() -> Env {
         macro_rules! count {
@@ -147,7 +299,7 @@ impl Default for Env {
         }
 
         macro_rules! prim {
-            (|| $body:expr) => {
+            (|| -> $RType:ty $body:block) => {
                 Entry {
                     arity: 0,
                     interpretation: {
@@ -158,7 +310,15 @@ impl Default for Env {
                             }
                         }
                         interpretation
-                    }
+                    },
+                    ty: Some(<$RType>::prim_ty()),
+                    capability: Capability::Pure,
+                }
+            };
+            (|$($param_name:ident : $PType:ty),*| -> $RType:ty $body:block) => {
+                Entry {
+                    ty: Some(fun_ty(vec![$(<$PType>::prim_ty()),*], <$RType>::prim_ty())),
+                    ..prim!(|$($param_name : $PType),*| $body)
                 }
             };
             (|$($param_name:ident : $PType:ty),*| $body:expr) => {
@@ -175,183 +335,892 @@ impl Default for Env {
                             }
                         }
                         interpretation
-                    }
+                    },
+                    ty: None,
+                    capability: Capability::Pure,
                 }
             };
         }
 
-        Env {
+        let mut env = Env {
+            allowed: Capability::Pure,
+            fold_log: None,
             entries: im::hashmap! {
                 Name::from("abort") => prim!(|message: Rc<str>| Err(message.to_string())),
+                Name::from("debug-print") => Entry {
+                    capability: Capability::Io,
+                    ..prim!(|message: Rc<str>| -> Rc<str> {
+                        eprintln!("{}", message);
+                        Ok(Rc::from(Value::literal_intro(message.clone())))
+                    })
+                },
+
+                Name::from("string-eq") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("char-eq") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("u8-eq") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("u16-eq") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("u32-eq") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("u64-eq") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("s8-eq") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("s16-eq") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("s32-eq") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("s64-eq") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("f32-eq") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+                Name::from("f64-eq") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs == rhs))) }),
+
+                Name::from("string-ne") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("char-ne") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("u8-ne") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("u16-ne") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("u32-ne") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("u64-ne") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("s8-ne") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("s16-ne") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("s32-ne") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("s64-ne") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("f32-ne") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+                Name::from("f64-ne") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs != rhs))) }),
+
+                Name::from("string-lt") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("char-lt") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("u8-lt") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("u16-lt") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("u32-lt") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("u64-lt") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("s8-lt") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("s16-lt") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("s32-lt") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("s64-lt") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("f32-lt") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+                Name::from("f64-lt") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs < rhs))) }),
+
+                Name::from("string-le") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("char-le") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("u8-le") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("u16-le") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("u32-le") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("u64-le") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("s8-le") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("s16-le") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("s32-le") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("s64-le") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("f32-le") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+                Name::from("f64-le") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs <= rhs))) }),
+
+                Name::from("string-ge") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("char-ge") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("u8-ge") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("u16-ge") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("u32-ge") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("u64-ge") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("s8-ge") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("s16-ge") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("s32-ge") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("s64-ge") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("f32-ge") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+                Name::from("f64-ge") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs >= rhs))) }),
+
+                Name::from("string-gt") => prim!(|lhs: Rc<str>, rhs: Rc<str>| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("char-gt") => prim!(|lhs: char, rhs: char| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("u8-gt") => prim!(|lhs: u8, rhs: u8| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("u16-gt") => prim!(|lhs: u16, rhs: u16| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("u32-gt") => prim!(|lhs: u32, rhs: u32| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("u64-gt") => prim!(|lhs: u64, rhs: u64| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("s8-gt") => prim!(|lhs: i8, rhs: i8| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("s16-gt") => prim!(|lhs: i16, rhs: i16| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("s32-gt") => prim!(|lhs: i32, rhs: i32| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("s64-gt") => prim!(|lhs: i64, rhs: i64| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("f32-gt") => prim!(|lhs: f32, rhs: f32| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+                Name::from("f64-gt") => prim!(|lhs: f64, rhs: f64| -> bool { Ok(Rc::from(Value::literal_intro(lhs > rhs))) }),
+
+                // Integer arithmetic wraps on overflow, modulo the size of the
+                // integer type. Division by zero is reported as an evaluation error.
+                Name::from("u8-add") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u16-add") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u32-add") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u64-add") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s8-add") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s16-add") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s32-add") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s64-add") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("f32-add") => prim!(|lhs: f32, rhs: f32| -> f32 { Ok(Rc::from(Value::literal_intro(lhs + rhs))) }),
+                Name::from("f64-add") => prim!(|lhs: f64, rhs: f64| -> f64 { Ok(Rc::from(Value::literal_intro(lhs + rhs))) }),
+
+                Name::from("u8-sub") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u16-sub") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u32-sub") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u64-sub") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s8-sub") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s16-sub") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s32-sub") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s64-sub") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("f32-sub") => prim!(|lhs: f32, rhs: f32| -> f32 { Ok(Rc::from(Value::literal_intro(lhs - rhs))) }),
+                Name::from("f64-sub") => prim!(|lhs: f64, rhs: f64| -> f64 { Ok(Rc::from(Value::literal_intro(lhs - rhs))) }),
+
+                Name::from("s8-neg") => prim!(|rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s16-neg") => prim!(|rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s32-neg") => prim!(|rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s64-neg") => prim!(|rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("f32-neg") => prim!(|rhs: f32| -> f32 { Ok(Rc::from(Value::literal_intro(-rhs))) }),
+                Name::from("f64-neg") => prim!(|rhs: f64| -> f64 { Ok(Rc::from(Value::literal_intro(-rhs))) }),
+
+                Name::from("u8-mul") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u16-mul") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u32-mul") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u64-mul") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s8-mul") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s16-mul") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s32-mul") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s64-mul") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("f32-mul") => prim!(|lhs: f32, rhs: f32| -> f32 { Ok(Rc::from(Value::literal_intro(lhs * rhs))) }),
+                Name::from("f64-mul") => prim!(|lhs: f64, rhs: f64| -> f64 { Ok(Rc::from(Value::literal_intro(lhs * rhs))) }),
+
+                Name::from("u8-div") => prim!(|lhs: u8, rhs: u8| -> u8 { checked("u8-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("u16-div") => prim!(|lhs: u16, rhs: u16| -> u16 { checked("u16-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("u32-div") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("u64-div") => prim!(|lhs: u64, rhs: u64| -> u64 { checked("u64-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("s8-div") => prim!(|lhs: i8, rhs: i8| -> i8 { checked("s8-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("s16-div") => prim!(|lhs: i16, rhs: i16| -> i16 { checked("s16-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("s32-div") => prim!(|lhs: i32, rhs: i32| -> i32 { checked("s32-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("s64-div") => prim!(|lhs: i64, rhs: i64| -> i64 { checked("s64-div", Some(*rhs).filter(|rhs| *rhs != 0).map(|rhs| lhs.wrapping_div(rhs))) }),
+                Name::from("f32-div") => prim!(|lhs: f32, rhs: f32| -> f32 { Ok(Rc::from(Value::literal_intro(lhs / rhs))) }),
+                Name::from("f64-div") => prim!(|lhs: f64, rhs: f64| -> f64 { Ok(Rc::from(Value::literal_intro(lhs / rhs))) }),
+
+                // Wrapping arithmetic, modulo the size of the integer type
+                Name::from("u8-add-wrap") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u16-add-wrap") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u32-add-wrap") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("u64-add-wrap") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s8-add-wrap") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s16-add-wrap") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s32-add-wrap") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+                Name::from("s64-add-wrap") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_add(*rhs)))) }),
+
+                Name::from("u8-sub-wrap") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u16-sub-wrap") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u32-sub-wrap") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("u64-sub-wrap") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s8-sub-wrap") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s16-sub-wrap") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s32-sub-wrap") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+                Name::from("s64-sub-wrap") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_sub(*rhs)))) }),
+
+                Name::from("u8-mul-wrap") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u16-mul-wrap") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u32-mul-wrap") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("u64-mul-wrap") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s8-mul-wrap") => prim!(|lhs: i8, rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s16-mul-wrap") => prim!(|lhs: i16, rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s32-mul-wrap") => prim!(|lhs: i32, rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+                Name::from("s64-mul-wrap") => prim!(|lhs: i64, rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(lhs.wrapping_mul(*rhs)))) }),
+
+                Name::from("s8-neg-wrap") => prim!(|rhs: i8| -> i8 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s16-neg-wrap") => prim!(|rhs: i16| -> i16 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s32-neg-wrap") => prim!(|rhs: i32| -> i32 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+                Name::from("s64-neg-wrap") => prim!(|rhs: i64| -> i64 { Ok(Rc::from(Value::literal_intro(rhs.wrapping_neg()))) }),
+
+                // Checked arithmetic, where overflow and division by zero are reported
+                // as evaluation errors. These should return a `Maybe` once we have sums.
+                Name::from("u8-add-checked") => prim!(|lhs: u8, rhs: u8| -> u8 { checked("u8-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("u16-add-checked") => prim!(|lhs: u16, rhs: u16| -> u16 { checked("u16-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("u32-add-checked") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("u64-add-checked") => prim!(|lhs: u64, rhs: u64| -> u64 { checked("u64-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("s8-add-checked") => prim!(|lhs: i8, rhs: i8| -> i8 { checked("s8-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("s16-add-checked") => prim!(|lhs: i16, rhs: i16| -> i16 { checked("s16-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("s32-add-checked") => prim!(|lhs: i32, rhs: i32| -> i32 { checked("s32-add-checked", lhs.checked_add(*rhs)) }),
+                Name::from("s64-add-checked") => prim!(|lhs: i64, rhs: i64| -> i64 { checked("s64-add-checked", lhs.checked_add(*rhs)) }),
+
+                Name::from("u8-sub-checked") => prim!(|lhs: u8, rhs: u8| -> u8 { checked("u8-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("u16-sub-checked") => prim!(|lhs: u16, rhs: u16| -> u16 { checked("u16-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("u32-sub-checked") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("u64-sub-checked") => prim!(|lhs: u64, rhs: u64| -> u64 { checked("u64-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("s8-sub-checked") => prim!(|lhs: i8, rhs: i8| -> i8 { checked("s8-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("s16-sub-checked") => prim!(|lhs: i16, rhs: i16| -> i16 { checked("s16-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("s32-sub-checked") => prim!(|lhs: i32, rhs: i32| -> i32 { checked("s32-sub-checked", lhs.checked_sub(*rhs)) }),
+                Name::from("s64-sub-checked") => prim!(|lhs: i64, rhs: i64| -> i64 { checked("s64-sub-checked", lhs.checked_sub(*rhs)) }),
+
+                Name::from("u8-mul-checked") => prim!(|lhs: u8, rhs: u8| -> u8 { checked("u8-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("u16-mul-checked") => prim!(|lhs: u16, rhs: u16| -> u16 { checked("u16-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("u32-mul-checked") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("u64-mul-checked") => prim!(|lhs: u64, rhs: u64| -> u64 { checked("u64-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("s8-mul-checked") => prim!(|lhs: i8, rhs: i8| -> i8 { checked("s8-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("s16-mul-checked") => prim!(|lhs: i16, rhs: i16| -> i16 { checked("s16-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("s32-mul-checked") => prim!(|lhs: i32, rhs: i32| -> i32 { checked("s32-mul-checked", lhs.checked_mul(*rhs)) }),
+                Name::from("s64-mul-checked") => prim!(|lhs: i64, rhs: i64| -> i64 { checked("s64-mul-checked", lhs.checked_mul(*rhs)) }),
+
+                Name::from("u8-div-checked") => prim!(|lhs: u8, rhs: u8| -> u8 { checked("u8-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("u16-div-checked") => prim!(|lhs: u16, rhs: u16| -> u16 { checked("u16-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("u32-div-checked") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("u64-div-checked") => prim!(|lhs: u64, rhs: u64| -> u64 { checked("u64-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("s8-div-checked") => prim!(|lhs: i8, rhs: i8| -> i8 { checked("s8-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("s16-div-checked") => prim!(|lhs: i16, rhs: i16| -> i16 { checked("s16-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("s32-div-checked") => prim!(|lhs: i32, rhs: i32| -> i32 { checked("s32-div-checked", lhs.checked_div(*rhs)) }),
+                Name::from("s64-div-checked") => prim!(|lhs: i64, rhs: i64| -> i64 { checked("s64-div-checked", lhs.checked_div(*rhs)) }),
+
+                Name::from("s8-neg-checked") => prim!(|rhs: i8| -> i8 { checked("s8-neg-checked", rhs.checked_neg()) }),
+                Name::from("s16-neg-checked") => prim!(|rhs: i16| -> i16 { checked("s16-neg-checked", rhs.checked_neg()) }),
+                Name::from("s32-neg-checked") => prim!(|rhs: i32| -> i32 { checked("s32-neg-checked", rhs.checked_neg()) }),
+                Name::from("s64-neg-checked") => prim!(|rhs: i64| -> i64 { checked("s64-neg-checked", rhs.checked_neg()) }),
+
+                Name::from("u8-and") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs & rhs))) }),
+                Name::from("u16-and") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs & rhs))) }),
+                Name::from("u32-and") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs & rhs))) }),
+                Name::from("u64-and") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs & rhs))) }),
+
+                Name::from("u8-or") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs | rhs))) }),
+                Name::from("u16-or") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs | rhs))) }),
+                Name::from("u32-or") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs | rhs))) }),
+                Name::from("u64-or") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs | rhs))) }),
 
-                Name::from("string-eq") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("char-eq") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("u8-eq") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("u16-eq") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("u32-eq") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("u64-eq") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("s8-eq") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("s16-eq") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("s32-eq") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("s64-eq") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("f32-eq") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-                Name::from("f64-eq") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs == rhs)))),
-
-                Name::from("string-ne") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("char-ne") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("u8-ne") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("u16-ne") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("u32-ne") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("u64-ne") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("s8-ne") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("s16-ne") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("s32-ne") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("s64-ne") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("f32-ne") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-                Name::from("f64-ne") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs != rhs)))),
-
-                Name::from("string-lt") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("char-lt") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("u8-lt") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("u16-lt") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("u32-lt") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("u64-lt") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("s8-lt") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("s16-lt") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("s32-lt") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("s64-lt") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("f32-lt") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-                Name::from("f64-lt") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs < rhs)))),
-
-                Name::from("string-le") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("char-le") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("u8-le") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("u16-le") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("u32-le") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("u64-le") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("s8-le") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("s16-le") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("s32-le") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("s64-le") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("f32-le") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-                Name::from("f64-le") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs <= rhs)))),
-
-                Name::from("string-ge") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("char-ge") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("u8-ge") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("u16-ge") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("u32-ge") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("u64-ge") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("s8-ge") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("s16-ge") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("s32-ge") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("s64-ge") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("f32-ge") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-                Name::from("f64-ge") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs >= rhs)))),
-
-                Name::from("string-gt") => prim!(|lhs: Rc<str>, rhs: Rc<str>| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("char-gt") => prim!(|lhs: char, rhs: char| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("u8-gt") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("u16-gt") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("u32-gt") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("u64-gt") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("s8-gt") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("s16-gt") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("s32-gt") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("s64-gt") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("f32-gt") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-                Name::from("f64-gt") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs > rhs)))),
-
-                Name::from("u8-add") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("u16-add") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("u32-add") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("u64-add") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("s8-add") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("s16-add") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("s32-add") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("s64-add") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("f32-add") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-                Name::from("f64-add") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs + rhs)))),
-
-                Name::from("u8-sub") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("u16-sub") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("u32-sub") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("u64-sub") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("s8-sub") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("s16-sub") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("s32-sub") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("s64-sub") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("f32-sub") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-                Name::from("f64-sub") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs - rhs)))),
-
-                Name::from("s8-neg") => prim!(|rhs: i8| Ok(Rc::from(Value::literal_intro(-rhs)))),
-                Name::from("s16-neg") => prim!(|rhs: i16| Ok(Rc::from(Value::literal_intro(-rhs)))),
-                Name::from("s32-neg") => prim!(|rhs: i32| Ok(Rc::from(Value::literal_intro(-rhs)))),
-                Name::from("s64-neg") => prim!(|rhs: i64| Ok(Rc::from(Value::literal_intro(-rhs)))),
-                Name::from("f32-neg") => prim!(|rhs: f32| Ok(Rc::from(Value::literal_intro(-rhs)))),
-                Name::from("f64-neg") => prim!(|rhs: f64| Ok(Rc::from(Value::literal_intro(-rhs)))),
-
-                Name::from("u8-mul") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("u16-mul") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("u32-mul") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("u64-mul") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("s8-mul") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("s16-mul") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("s32-mul") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("s64-mul") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("f32-mul") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-                Name::from("f64-mul") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs * rhs)))),
-
-                Name::from("u8-div") => prim!(|lhs: u8, rhs: u8| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("u16-div") => prim!(|lhs: u16, rhs: u16| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("u32-div") => prim!(|lhs: u32, rhs: u32| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("u64-div") => prim!(|lhs: u64, rhs: u64| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("s8-div") => prim!(|lhs: i8, rhs: i8| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("s16-div") => prim!(|lhs: i16, rhs: i16| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("s32-div") => prim!(|lhs: i32, rhs: i32| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("s64-div") => prim!(|lhs: i64, rhs: i64| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("f32-div") => prim!(|lhs: f32, rhs: f32| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-                Name::from("f64-div") => prim!(|lhs: f64, rhs: f64| Ok(Rc::from(Value::literal_intro(lhs / rhs)))),
-
-                Name::from("char-to-string") => prim!(|value: char| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("u8-to-string") => prim!(|value: u8| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("u16-to-string") => prim!(|value: u16| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("u32-to-string") => prim!(|value: u32| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("u64-to-string") => prim!(|value: u64| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("s8-to-string") => prim!(|value: i8| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("s16-to-string") => prim!(|value: i16| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("s32-to-string") => prim!(|value: i32| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("s64-to-string") => prim!(|value: i64| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("f32-to-string") => prim!(|value: f32| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-                Name::from("f64-to-string") => prim!(|value: f64| Ok(Rc::from(Value::literal_intro(value.to_string())))),
-
-                Name::from("u8-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u8::MIN)))),
-                Name::from("u16-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u16::MIN)))),
-                Name::from("u32-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u32::MIN)))),
-                Name::from("u64-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u64::MIN)))),
-                Name::from("s8-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i8::MIN)))),
-                Name::from("s16-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i16::MIN)))),
-                Name::from("s32-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i32::MIN)))),
-                Name::from("s64-min") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i64::MIN)))),
-
-                Name::from("u8-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u8::MAX)))),
-                Name::from("u16-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u16::MAX)))),
-                Name::from("u32-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u32::MAX)))),
-                Name::from("u64-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::u64::MAX)))),
-                Name::from("s8-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i8::MAX)))),
-                Name::from("s16-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i16::MAX)))),
-                Name::from("s32-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i32::MAX)))),
-                Name::from("s64-max") => prim!(|| Ok(Rc::from(Value::literal_intro(std::i64::MAX)))),
-
-                Name::from("f32-nan") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f32::NAN)))),
-                Name::from("f64-nan") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f64::NAN)))),
-
-                Name::from("f32-infinity") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f32::INFINITY)))),
-                Name::from("f64-infinity") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f64::INFINITY)))),
-
-                Name::from("f32-neg-infinity") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f32::NEG_INFINITY)))),
-                Name::from("f64-neg-infinity") => prim!(|| Ok(Rc::from(Value::literal_intro(std::f64::NEG_INFINITY)))),
+                Name::from("u8-xor") => prim!(|lhs: u8, rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(lhs ^ rhs))) }),
+                Name::from("u16-xor") => prim!(|lhs: u16, rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(lhs ^ rhs))) }),
+                Name::from("u32-xor") => prim!(|lhs: u32, rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(lhs ^ rhs))) }),
+                Name::from("u64-xor") => prim!(|lhs: u64, rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(lhs ^ rhs))) }),
+
+                Name::from("u8-not") => prim!(|rhs: u8| -> u8 { Ok(Rc::from(Value::literal_intro(!rhs))) }),
+                Name::from("u16-not") => prim!(|rhs: u16| -> u16 { Ok(Rc::from(Value::literal_intro(!rhs))) }),
+                Name::from("u32-not") => prim!(|rhs: u32| -> u32 { Ok(Rc::from(Value::literal_intro(!rhs))) }),
+                Name::from("u64-not") => prim!(|rhs: u64| -> u64 { Ok(Rc::from(Value::literal_intro(!rhs))) }),
+
+                // Shifting by at least the number of bits in the type is reported as an
+                // evaluation error
+                Name::from("u8-shl") => prim!(|lhs: u8, rhs: u32| -> u8 { checked("u8-shl", lhs.checked_shl(*rhs)) }),
+                Name::from("u16-shl") => prim!(|lhs: u16, rhs: u32| -> u16 { checked("u16-shl", lhs.checked_shl(*rhs)) }),
+                Name::from("u32-shl") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-shl", lhs.checked_shl(*rhs)) }),
+                Name::from("u64-shl") => prim!(|lhs: u64, rhs: u32| -> u64 { checked("u64-shl", lhs.checked_shl(*rhs)) }),
+
+                Name::from("u8-shr") => prim!(|lhs: u8, rhs: u32| -> u8 { checked("u8-shr", lhs.checked_shr(*rhs)) }),
+                Name::from("u16-shr") => prim!(|lhs: u16, rhs: u32| -> u16 { checked("u16-shr", lhs.checked_shr(*rhs)) }),
+                Name::from("u32-shr") => prim!(|lhs: u32, rhs: u32| -> u32 { checked("u32-shr", lhs.checked_shr(*rhs)) }),
+                Name::from("u64-shr") => prim!(|lhs: u64, rhs: u32| -> u64 { checked("u64-shr", lhs.checked_shr(*rhs)) }),
+
+                // Conversions that preserve every value
+                Name::from("u8-to-u16") => prim!(|value: u8| -> u16 { Ok(Rc::from(Value::literal_intro(u16::from(*value)))) }),
+                Name::from("u8-to-u32") => prim!(|value: u8| -> u32 { Ok(Rc::from(Value::literal_intro(u32::from(*value)))) }),
+                Name::from("u8-to-u64") => prim!(|value: u8| -> u64 { Ok(Rc::from(Value::literal_intro(u64::from(*value)))) }),
+                Name::from("u8-to-s16") => prim!(|value: u8| -> i16 { Ok(Rc::from(Value::literal_intro(i16::from(*value)))) }),
+                Name::from("u8-to-s32") => prim!(|value: u8| -> i32 { Ok(Rc::from(Value::literal_intro(i32::from(*value)))) }),
+                Name::from("u8-to-s64") => prim!(|value: u8| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("u16-to-u32") => prim!(|value: u16| -> u32 { Ok(Rc::from(Value::literal_intro(u32::from(*value)))) }),
+                Name::from("u16-to-u64") => prim!(|value: u16| -> u64 { Ok(Rc::from(Value::literal_intro(u64::from(*value)))) }),
+                Name::from("u16-to-s32") => prim!(|value: u16| -> i32 { Ok(Rc::from(Value::literal_intro(i32::from(*value)))) }),
+                Name::from("u16-to-s64") => prim!(|value: u16| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("u32-to-u64") => prim!(|value: u32| -> u64 { Ok(Rc::from(Value::literal_intro(u64::from(*value)))) }),
+                Name::from("u32-to-s64") => prim!(|value: u32| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("s8-to-s16") => prim!(|value: i8| -> i16 { Ok(Rc::from(Value::literal_intro(i16::from(*value)))) }),
+                Name::from("s8-to-s32") => prim!(|value: i8| -> i32 { Ok(Rc::from(Value::literal_intro(i32::from(*value)))) }),
+                Name::from("s8-to-s64") => prim!(|value: i8| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("s16-to-s32") => prim!(|value: i16| -> i32 { Ok(Rc::from(Value::literal_intro(i32::from(*value)))) }),
+                Name::from("s16-to-s64") => prim!(|value: i16| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("s32-to-s64") => prim!(|value: i32| -> i64 { Ok(Rc::from(Value::literal_intro(i64::from(*value)))) }),
+                Name::from("u8-to-char") => prim!(|value: u8| -> char { Ok(Rc::from(Value::literal_intro(char::from(*value)))) }),
+                Name::from("char-to-u32") => prim!(|value: char| -> u32 { Ok(Rc::from(Value::literal_intro(u32::from(*value)))) }),
+                Name::from("f32-to-f64") => prim!(|value: f32| -> f64 { Ok(Rc::from(Value::literal_intro(f64::from(*value)))) }),
+
+                // Checked conversions, where values that are out of range in the target
+                // type are reported as evaluation errors. Floats are truncated towards
+                // zero before being checked.
+                Name::from("u8-to-s8-checked") => prim!(|value: u8| -> i8 { checked("u8-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("u16-to-u8-checked") => prim!(|value: u16| -> u8 { checked("u16-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("u16-to-s8-checked") => prim!(|value: u16| -> i8 { checked("u16-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("u16-to-s16-checked") => prim!(|value: u16| -> i16 { checked("u16-to-s16-checked", i16::try_from(*value).ok()) }),
+                Name::from("u32-to-u8-checked") => prim!(|value: u32| -> u8 { checked("u32-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("u32-to-u16-checked") => prim!(|value: u32| -> u16 { checked("u32-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("u32-to-s8-checked") => prim!(|value: u32| -> i8 { checked("u32-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("u32-to-s16-checked") => prim!(|value: u32| -> i16 { checked("u32-to-s16-checked", i16::try_from(*value).ok()) }),
+                Name::from("u32-to-s32-checked") => prim!(|value: u32| -> i32 { checked("u32-to-s32-checked", i32::try_from(*value).ok()) }),
+                Name::from("u64-to-u8-checked") => prim!(|value: u64| -> u8 { checked("u64-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("u64-to-u16-checked") => prim!(|value: u64| -> u16 { checked("u64-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("u64-to-u32-checked") => prim!(|value: u64| -> u32 { checked("u64-to-u32-checked", u32::try_from(*value).ok()) }),
+                Name::from("u64-to-s8-checked") => prim!(|value: u64| -> i8 { checked("u64-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("u64-to-s16-checked") => prim!(|value: u64| -> i16 { checked("u64-to-s16-checked", i16::try_from(*value).ok()) }),
+                Name::from("u64-to-s32-checked") => prim!(|value: u64| -> i32 { checked("u64-to-s32-checked", i32::try_from(*value).ok()) }),
+                Name::from("u64-to-s64-checked") => prim!(|value: u64| -> i64 { checked("u64-to-s64-checked", i64::try_from(*value).ok()) }),
+                Name::from("s8-to-u8-checked") => prim!(|value: i8| -> u8 { checked("s8-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("s8-to-u16-checked") => prim!(|value: i8| -> u16 { checked("s8-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("s8-to-u32-checked") => prim!(|value: i8| -> u32 { checked("s8-to-u32-checked", u32::try_from(*value).ok()) }),
+                Name::from("s8-to-u64-checked") => prim!(|value: i8| -> u64 { checked("s8-to-u64-checked", u64::try_from(*value).ok()) }),
+                Name::from("s16-to-u8-checked") => prim!(|value: i16| -> u8 { checked("s16-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("s16-to-u16-checked") => prim!(|value: i16| -> u16 { checked("s16-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("s16-to-u32-checked") => prim!(|value: i16| -> u32 { checked("s16-to-u32-checked", u32::try_from(*value).ok()) }),
+                Name::from("s16-to-u64-checked") => prim!(|value: i16| -> u64 { checked("s16-to-u64-checked", u64::try_from(*value).ok()) }),
+                Name::from("s16-to-s8-checked") => prim!(|value: i16| -> i8 { checked("s16-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("s32-to-u8-checked") => prim!(|value: i32| -> u8 { checked("s32-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("s32-to-u16-checked") => prim!(|value: i32| -> u16 { checked("s32-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("s32-to-u32-checked") => prim!(|value: i32| -> u32 { checked("s32-to-u32-checked", u32::try_from(*value).ok()) }),
+                Name::from("s32-to-u64-checked") => prim!(|value: i32| -> u64 { checked("s32-to-u64-checked", u64::try_from(*value).ok()) }),
+                Name::from("s32-to-s8-checked") => prim!(|value: i32| -> i8 { checked("s32-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("s32-to-s16-checked") => prim!(|value: i32| -> i16 { checked("s32-to-s16-checked", i16::try_from(*value).ok()) }),
+                Name::from("s64-to-u8-checked") => prim!(|value: i64| -> u8 { checked("s64-to-u8-checked", u8::try_from(*value).ok()) }),
+                Name::from("s64-to-u16-checked") => prim!(|value: i64| -> u16 { checked("s64-to-u16-checked", u16::try_from(*value).ok()) }),
+                Name::from("s64-to-u32-checked") => prim!(|value: i64| -> u32 { checked("s64-to-u32-checked", u32::try_from(*value).ok()) }),
+                Name::from("s64-to-u64-checked") => prim!(|value: i64| -> u64 { checked("s64-to-u64-checked", u64::try_from(*value).ok()) }),
+                Name::from("s64-to-s8-checked") => prim!(|value: i64| -> i8 { checked("s64-to-s8-checked", i8::try_from(*value).ok()) }),
+                Name::from("s64-to-s16-checked") => prim!(|value: i64| -> i16 { checked("s64-to-s16-checked", i16::try_from(*value).ok()) }),
+                Name::from("s64-to-s32-checked") => prim!(|value: i64| -> i32 { checked("s64-to-s32-checked", i32::try_from(*value).ok()) }),
+                Name::from("f32-to-u8-checked") => prim!(|value: f32| -> u8 { checked("f32-to-u8-checked", Some(value.trunc()).filter(|value| *value >= u8::MIN as f32 && *value < u8::MAX as f32 + 1.0).map(|value| value as u8)) }),
+                Name::from("f32-to-u16-checked") => prim!(|value: f32| -> u16 { checked("f32-to-u16-checked", Some(value.trunc()).filter(|value| *value >= u16::MIN as f32 && *value < u16::MAX as f32 + 1.0).map(|value| value as u16)) }),
+                Name::from("f32-to-u32-checked") => prim!(|value: f32| -> u32 { checked("f32-to-u32-checked", Some(value.trunc()).filter(|value| *value >= u32::MIN as f32 && *value < u32::MAX as f32 + 1.0).map(|value| value as u32)) }),
+                Name::from("f32-to-u64-checked") => prim!(|value: f32| -> u64 { checked("f32-to-u64-checked", Some(value.trunc()).filter(|value| *value >= u64::MIN as f32 && *value < u64::MAX as f32 + 1.0).map(|value| value as u64)) }),
+                Name::from("f32-to-s8-checked") => prim!(|value: f32| -> i8 { checked("f32-to-s8-checked", Some(value.trunc()).filter(|value| *value >= i8::MIN as f32 && *value < i8::MAX as f32 + 1.0).map(|value| value as i8)) }),
+                Name::from("f32-to-s16-checked") => prim!(|value: f32| -> i16 { checked("f32-to-s16-checked", Some(value.trunc()).filter(|value| *value >= i16::MIN as f32 && *value < i16::MAX as f32 + 1.0).map(|value| value as i16)) }),
+                Name::from("f32-to-s32-checked") => prim!(|value: f32| -> i32 { checked("f32-to-s32-checked", Some(value.trunc()).filter(|value| *value >= i32::MIN as f32 && *value < i32::MAX as f32 + 1.0).map(|value| value as i32)) }),
+                Name::from("f32-to-s64-checked") => prim!(|value: f32| -> i64 { checked("f32-to-s64-checked", Some(value.trunc()).filter(|value| *value >= i64::MIN as f32 && *value < i64::MAX as f32 + 1.0).map(|value| value as i64)) }),
+                Name::from("f64-to-u8-checked") => prim!(|value: f64| -> u8 { checked("f64-to-u8-checked", Some(value.trunc()).filter(|value| *value >= u8::MIN as f64 && *value < u8::MAX as f64 + 1.0).map(|value| value as u8)) }),
+                Name::from("f64-to-u16-checked") => prim!(|value: f64| -> u16 { checked("f64-to-u16-checked", Some(value.trunc()).filter(|value| *value >= u16::MIN as f64 && *value < u16::MAX as f64 + 1.0).map(|value| value as u16)) }),
+                Name::from("f64-to-u32-checked") => prim!(|value: f64| -> u32 { checked("f64-to-u32-checked", Some(value.trunc()).filter(|value| *value >= u32::MIN as f64 && *value < u32::MAX as f64 + 1.0).map(|value| value as u32)) }),
+                Name::from("f64-to-u64-checked") => prim!(|value: f64| -> u64 { checked("f64-to-u64-checked", Some(value.trunc()).filter(|value| *value >= u64::MIN as f64 && *value < u64::MAX as f64 + 1.0).map(|value| value as u64)) }),
+                Name::from("f64-to-s8-checked") => prim!(|value: f64| -> i8 { checked("f64-to-s8-checked", Some(value.trunc()).filter(|value| *value >= i8::MIN as f64 && *value < i8::MAX as f64 + 1.0).map(|value| value as i8)) }),
+                Name::from("f64-to-s16-checked") => prim!(|value: f64| -> i16 { checked("f64-to-s16-checked", Some(value.trunc()).filter(|value| *value >= i16::MIN as f64 && *value < i16::MAX as f64 + 1.0).map(|value| value as i16)) }),
+                Name::from("f64-to-s32-checked") => prim!(|value: f64| -> i32 { checked("f64-to-s32-checked", Some(value.trunc()).filter(|value| *value >= i32::MIN as f64 && *value < i32::MAX as f64 + 1.0).map(|value| value as i32)) }),
+                Name::from("f64-to-s64-checked") => prim!(|value: f64| -> i64 { checked("f64-to-s64-checked", Some(value.trunc()).filter(|value| *value >= i64::MIN as f64 && *value < i64::MAX as f64 + 1.0).map(|value| value as i64)) }),
+                Name::from("u32-to-char-checked") => prim!(|value: u32| -> char { checked("u32-to-char-checked", std::char::from_u32(*value)) }),
+
+                // Wrapping conversions, truncating or reinterpreting the bits of integers
+                Name::from("u8-to-s8-wrap") => prim!(|value: u8| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("u16-to-u8-wrap") => prim!(|value: u16| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("u16-to-s8-wrap") => prim!(|value: u16| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("u16-to-s16-wrap") => prim!(|value: u16| -> i16 { Ok(Rc::from(Value::literal_intro(*value as i16))) }),
+                Name::from("u32-to-u8-wrap") => prim!(|value: u32| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("u32-to-u16-wrap") => prim!(|value: u32| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("u32-to-s8-wrap") => prim!(|value: u32| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("u32-to-s16-wrap") => prim!(|value: u32| -> i16 { Ok(Rc::from(Value::literal_intro(*value as i16))) }),
+                Name::from("u32-to-s32-wrap") => prim!(|value: u32| -> i32 { Ok(Rc::from(Value::literal_intro(*value as i32))) }),
+                Name::from("u64-to-u8-wrap") => prim!(|value: u64| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("u64-to-u16-wrap") => prim!(|value: u64| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("u64-to-u32-wrap") => prim!(|value: u64| -> u32 { Ok(Rc::from(Value::literal_intro(*value as u32))) }),
+                Name::from("u64-to-s8-wrap") => prim!(|value: u64| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("u64-to-s16-wrap") => prim!(|value: u64| -> i16 { Ok(Rc::from(Value::literal_intro(*value as i16))) }),
+                Name::from("u64-to-s32-wrap") => prim!(|value: u64| -> i32 { Ok(Rc::from(Value::literal_intro(*value as i32))) }),
+                Name::from("u64-to-s64-wrap") => prim!(|value: u64| -> i64 { Ok(Rc::from(Value::literal_intro(*value as i64))) }),
+                Name::from("s8-to-u8-wrap") => prim!(|value: i8| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("s8-to-u16-wrap") => prim!(|value: i8| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("s8-to-u32-wrap") => prim!(|value: i8| -> u32 { Ok(Rc::from(Value::literal_intro(*value as u32))) }),
+                Name::from("s8-to-u64-wrap") => prim!(|value: i8| -> u64 { Ok(Rc::from(Value::literal_intro(*value as u64))) }),
+                Name::from("s16-to-u8-wrap") => prim!(|value: i16| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("s16-to-u16-wrap") => prim!(|value: i16| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("s16-to-u32-wrap") => prim!(|value: i16| -> u32 { Ok(Rc::from(Value::literal_intro(*value as u32))) }),
+                Name::from("s16-to-u64-wrap") => prim!(|value: i16| -> u64 { Ok(Rc::from(Value::literal_intro(*value as u64))) }),
+                Name::from("s16-to-s8-wrap") => prim!(|value: i16| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("s32-to-u8-wrap") => prim!(|value: i32| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("s32-to-u16-wrap") => prim!(|value: i32| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("s32-to-u32-wrap") => prim!(|value: i32| -> u32 { Ok(Rc::from(Value::literal_intro(*value as u32))) }),
+                Name::from("s32-to-u64-wrap") => prim!(|value: i32| -> u64 { Ok(Rc::from(Value::literal_intro(*value as u64))) }),
+                Name::from("s32-to-s8-wrap") => prim!(|value: i32| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("s32-to-s16-wrap") => prim!(|value: i32| -> i16 { Ok(Rc::from(Value::literal_intro(*value as i16))) }),
+                Name::from("s64-to-u8-wrap") => prim!(|value: i64| -> u8 { Ok(Rc::from(Value::literal_intro(*value as u8))) }),
+                Name::from("s64-to-u16-wrap") => prim!(|value: i64| -> u16 { Ok(Rc::from(Value::literal_intro(*value as u16))) }),
+                Name::from("s64-to-u32-wrap") => prim!(|value: i64| -> u32 { Ok(Rc::from(Value::literal_intro(*value as u32))) }),
+                Name::from("s64-to-u64-wrap") => prim!(|value: i64| -> u64 { Ok(Rc::from(Value::literal_intro(*value as u64))) }),
+                Name::from("s64-to-s8-wrap") => prim!(|value: i64| -> i8 { Ok(Rc::from(Value::literal_intro(*value as i8))) }),
+                Name::from("s64-to-s16-wrap") => prim!(|value: i64| -> i16 { Ok(Rc::from(Value::literal_intro(*value as i16))) }),
+                Name::from("s64-to-s32-wrap") => prim!(|value: i64| -> i32 { Ok(Rc::from(Value::literal_intro(*value as i32))) }),
+
+                // Conversions to floats, rounding to the nearest representable value
+                Name::from("u8-to-f32") => prim!(|value: u8| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("u8-to-f64") => prim!(|value: u8| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("u16-to-f32") => prim!(|value: u16| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("u16-to-f64") => prim!(|value: u16| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("u32-to-f32") => prim!(|value: u32| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("u32-to-f64") => prim!(|value: u32| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("u64-to-f32") => prim!(|value: u64| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("u64-to-f64") => prim!(|value: u64| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("s8-to-f32") => prim!(|value: i8| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("s8-to-f64") => prim!(|value: i8| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("s16-to-f32") => prim!(|value: i16| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("s16-to-f64") => prim!(|value: i16| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("s32-to-f32") => prim!(|value: i32| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("s32-to-f64") => prim!(|value: i32| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("s64-to-f32") => prim!(|value: i64| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+                Name::from("s64-to-f64") => prim!(|value: i64| -> f64 { Ok(Rc::from(Value::literal_intro(*value as f64))) }),
+                Name::from("f64-to-f32") => prim!(|value: f64| -> f32 { Ok(Rc::from(Value::literal_intro(*value as f32))) }),
+
+                // Arrays, where the first argument is the type of the elements
+                Name::from("array-make") => prim!(|_elem_ty: Rc<Value>, len: u64, elem: Rc<Value>| {
+                    if *len <= ARRAY_MAX_LEN {
+                        let elems = std::iter::repeat_n(elem.clone(), *len as usize).collect();
+                        Ok(Rc::from(Value::ArrayIntro(elems)))
+                    } else {
+                        Err(format!("array length too large: {} > {}", len, ARRAY_MAX_LEN))
+                    }
+                }),
+                Name::from("array-length") => prim!(|_elem_ty: Rc<Value>, array: im::Vector<Rc<Value>>| {
+                    Ok(Rc::from(Value::literal_intro(array.len() as u64)))
+                }),
+                Name::from("array-get") => prim!(|_elem_ty: Rc<Value>, array: im::Vector<Rc<Value>>, index: u64| {
+                    match array.get(*index as usize) {
+                        Some(elem) => Ok(elem.clone()),
+                        None => Err(format!("array index out of bounds: {} >= {}", index, array.len())),
+                    }
+                }),
+                Name::from("array-set") => prim!(|_elem_ty: Rc<Value>, array: im::Vector<Rc<Value>>, index: u64, elem: Rc<Value>| {
+                    if (*index as usize) < array.len() {
+                        Ok(Rc::from(Value::ArrayIntro(array.update(*index as usize, elem.clone()))))
+                    } else {
+                        Err(format!("array index out of bounds: {} >= {}", index, array.len()))
+                    }
+                }),
+
+                Name::from("char-to-string") => prim!(|value: char| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("u8-to-string") => prim!(|value: u8| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("u16-to-string") => prim!(|value: u16| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("u32-to-string") => prim!(|value: u32| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("u64-to-string") => prim!(|value: u64| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("s8-to-string") => prim!(|value: i8| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("s16-to-string") => prim!(|value: i16| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("s32-to-string") => prim!(|value: i32| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("s64-to-string") => prim!(|value: i64| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("f32-to-string") => prim!(|value: f32| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+                Name::from("f64-to-string") => prim!(|value: f64| -> Rc<str> { Ok(Rc::from(Value::literal_intro(value.to_string()))) }),
+
+                Name::from("u8-min") => prim!(|| -> u8 { Ok(Rc::from(Value::literal_intro(u8::MIN))) }),
+                Name::from("u16-min") => prim!(|| -> u16 { Ok(Rc::from(Value::literal_intro(u16::MIN))) }),
+                Name::from("u32-min") => prim!(|| -> u32 { Ok(Rc::from(Value::literal_intro(u32::MIN))) }),
+                Name::from("u64-min") => prim!(|| -> u64 { Ok(Rc::from(Value::literal_intro(u64::MIN))) }),
+                Name::from("s8-min") => prim!(|| -> i8 { Ok(Rc::from(Value::literal_intro(i8::MIN))) }),
+                Name::from("s16-min") => prim!(|| -> i16 { Ok(Rc::from(Value::literal_intro(i16::MIN))) }),
+                Name::from("s32-min") => prim!(|| -> i32 { Ok(Rc::from(Value::literal_intro(i32::MIN))) }),
+                Name::from("s64-min") => prim!(|| -> i64 { Ok(Rc::from(Value::literal_intro(i64::MIN))) }),
+
+                Name::from("u8-max") => prim!(|| -> u8 { Ok(Rc::from(Value::literal_intro(u8::MAX))) }),
+                Name::from("u16-max") => prim!(|| -> u16 { Ok(Rc::from(Value::literal_intro(u16::MAX))) }),
+                Name::from("u32-max") => prim!(|| -> u32 { Ok(Rc::from(Value::literal_intro(u32::MAX))) }),
+                Name::from("u64-max") => prim!(|| -> u64 { Ok(Rc::from(Value::literal_intro(u64::MAX))) }),
+                Name::from("s8-max") => prim!(|| -> i8 { Ok(Rc::from(Value::literal_intro(i8::MAX))) }),
+                Name::from("s16-max") => prim!(|| -> i16 { Ok(Rc::from(Value::literal_intro(i16::MAX))) }),
+                Name::from("s32-max") => prim!(|| -> i32 { Ok(Rc::from(Value::literal_intro(i32::MAX))) }),
+                Name::from("s64-max") => prim!(|| -> i64 { Ok(Rc::from(Value::literal_intro(i64::MAX))) }),
+
+                Name::from("f32-nan") => prim!(|| -> f32 { Ok(Rc::from(Value::literal_intro(f32::NAN))) }),
+                Name::from("f64-nan") => prim!(|| -> f64 { Ok(Rc::from(Value::literal_intro(f64::NAN))) }),
+
+                Name::from("f32-infinity") => prim!(|| -> f32 { Ok(Rc::from(Value::literal_intro(f32::INFINITY))) }),
+                Name::from("f64-infinity") => prim!(|| -> f64 { Ok(Rc::from(Value::literal_intro(f64::INFINITY))) }),
+
+                Name::from("f32-neg-infinity") => prim!(|| -> f32 { Ok(Rc::from(Value::literal_intro(f32::NEG_INFINITY))) }),
+                Name::from("f64-neg-infinity") => prim!(|| -> f64 { Ok(Rc::from(Value::literal_intro(f64::NEG_INFINITY))) }),
+            },
+        };
+
+        for (name, ty) in array_prim_tys() {
+            if let Some(entry) = env.entries.get_mut(&Name::from(name)) {
+                entry.ty = Some(ty);
+            }
+        }
+        for (name, entry) in acc_prims() {
+            env.entries.insert(Name::from(name), entry);
+        }
+
+        env
+    }
+}
+
+/// The type of a non-dependent function with the given parameter types.
+fn fun_ty(param_tys: Vec<Rc<Term>>, body_ty: Rc<Term>) -> Rc<Term> {
+    param_tys
+        .into_iter()
+        .rev()
+        .fold(body_ty, |body_ty, param_ty| {
+            Rc::from(Term::FunType(
+                crate::DocString::from(""),
+                AppMode::Explicit,
+                None,
+                param_ty,
+                body_ty,
+            ))
+        })
+}
+
+/// The types of the array primitives, which take the type of the elements as
+/// their first argument.
+fn array_prim_tys() -> Vec<(&'static str, Rc<Term>)> {
+    let builder = TermBuilder::new();
+    let universe = || Rc::from(Term::universe(0));
+    let u64_ty = || u64::prim_ty();
+    let array_ty = |elem_ty| Rc::from(Term::ArrayType(elem_ty));
+    let explicit = || AppMode::Explicit;
+
+    vec![
+        // Fun (A : Type) -> U64 -> A -> Array A
+        (
+            "array-make",
+            builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                b.fun_type(explicit(), None, u64_ty(), |b, _| {
+                    b.fun_type(explicit(), None, b.var(a), |b, _| array_ty(b.var(a)))
+                })
+            }),
+        ),
+        // Fun (A : Type) -> Array A -> U64
+        (
+            "array-length",
+            builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                b.fun_type(explicit(), None, array_ty(b.var(a)), |_, _| u64_ty())
+            }),
+        ),
+        // Fun (A : Type) -> Array A -> U64 -> A
+        (
+            "array-get",
+            builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                b.fun_type(explicit(), None, array_ty(b.var(a)), |b, _| {
+                    b.fun_type(explicit(), None, u64_ty(), |b, _| b.var(a))
+                })
+            }),
+        ),
+        // Fun (A : Type) -> Array A -> U64 -> A -> Array A
+        (
+            "array-set",
+            builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                b.fun_type(explicit(), None, array_ty(b.var(a)), |b, _| {
+                    b.fun_type(explicit(), None, u64_ty(), |b, _| {
+                        b.fun_type(explicit(), None, b.var(a), |b, _| array_ty(b.var(a)))
+                    })
+                })
+            }),
+        ),
+    ]
+}
+
+/// Apply a term to some explicit arguments.
+fn fun_elims(fun: Rc<Term>, args: Vec<Rc<Term>>) -> Rc<Term> {
+    (args.into_iter()).fold(fun, |fun, arg| {
+        Rc::from(Term::FunElim(fun, AppMode::Explicit, arg))
+    })
+}
+
+/// The type of a relation between the elements of `A`: `A -> A -> Type`.
+fn relation_ty<'scope>(builder: &TermBuilder<'scope, '_>, a: Var<'scope>) -> Rc<Term> {
+    builder.fun_type(AppMode::Explicit, None, builder.var(a), |b, _| {
+        b.fun_type(AppMode::Explicit, None, b.var(a), |_, _| {
+            Rc::from(Term::universe(0))
+        })
+    })
+}
+
+/// The interpretation of primitives that never reduce, like type formers and
+/// introduction forms.
+fn stuck(_: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>> {
+    None
+}
+
+/// The primitives for well-founded recursion, using the accessibility
+/// predicate. An element is accessible with respect to a relation if every
+/// element that is related to it is also accessible, so recursion on a proof
+/// of accessibility always terminates.
+///
+/// Like the array primitives, these take the type of the elements as their
+/// first argument, followed by the relation.
+fn acc_prims() -> Vec<(&'static str, Entry)> {
+    let builder = TermBuilder::new();
+    let universe = || Rc::from(Term::universe(0));
+    let explicit = || AppMode::Explicit;
+    let acc_ty = |a, r, x| fun_elims(Rc::from(Term::prim("Acc")), vec![a, r, x]);
+
+    vec![
+        // Fun (A : Type) -> (A -> A -> Type) -> A -> Type
+        (
+            "Acc",
+            Entry {
+                arity: 3,
+                interpretation: stuck,
+                ty: Some(builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                    b.fun_type(explicit(), Some("R"), relation_ty(b, a), |b, _| {
+                        b.fun_type(explicit(), None, b.var(a), |_, _| universe())
+                    })
+                })),
+                capability: Capability::Pure,
+            },
+        ),
+        // Fun (A : Type) (R : A -> A -> Type) (x : A)
+        //     -> (Fun (y : A) -> R y x -> Acc A R y)
+        //     -> Acc A R x
+        (
+            "acc",
+            Entry {
+                arity: 4,
+                interpretation: stuck,
+                ty: Some(builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                    b.fun_type(explicit(), Some("R"), relation_ty(b, a), |b, r| {
+                        b.fun_type(explicit(), Some("x"), b.var(a), |b, x| {
+                            let below_ty = b.fun_type(explicit(), Some("y"), b.var(a), |b, y| {
+                                let related_ty = fun_elims(b.var(r), vec![b.var(y), b.var(x)]);
+                                b.fun_type(explicit(), None, related_ty, |b, _| {
+                                    acc_ty(b.var(a), b.var(r), b.var(y))
+                                })
+                            });
+                            b.fun_type(explicit(), None, below_ty, |b, _| {
+                                acc_ty(b.var(a), b.var(r), b.var(x))
+                            })
+                        })
+                    })
+                })),
+                capability: Capability::Pure,
             },
+        ),
+        // Fun (A : Type) (R : A -> A -> Type) (P : A -> Type)
+        //     -> (Fun (x : A)
+        //         -> (Fun (y : A) -> R y x -> Acc A R y)
+        //         -> (Fun (y : A) -> R y x -> P y)
+        //         -> P x)
+        //     -> Fun (x : A) -> Acc A R x -> P x
+        (
+            "acc-elim",
+            Entry {
+                arity: 6,
+                interpretation: interpret_acc_elim,
+                ty: Some(builder.fun_type(explicit(), Some("A"), universe(), |b, a| {
+                    b.fun_type(explicit(), Some("R"), relation_ty(b, a), |b, r| {
+                        let motive_ty = b.fun_type(explicit(), None, b.var(a), |_, _| universe());
+                        b.fun_type(explicit(), Some("P"), motive_ty, |b, p| {
+                            let step_ty = b.fun_type(explicit(), Some("x"), b.var(a), |b, x| {
+                                let below_ty =
+                                    b.fun_type(explicit(), Some("y"), b.var(a), |b, y| {
+                                        let related_ty =
+                                            fun_elims(b.var(r), vec![b.var(y), b.var(x)]);
+                                        b.fun_type(explicit(), None, related_ty, |b, _| {
+                                            acc_ty(b.var(a), b.var(r), b.var(y))
+                                        })
+                                    });
+                                b.fun_type(explicit(), None, below_ty, |b, _| {
+                                    let rec_ty =
+                                        b.fun_type(explicit(), Some("y"), b.var(a), |b, y| {
+                                            let related_ty =
+                                                fun_elims(b.var(r), vec![b.var(y), b.var(x)]);
+                                            b.fun_type(explicit(), None, related_ty, |b, _| {
+                                                fun_elims(b.var(p), vec![b.var(y)])
+                                            })
+                                        });
+                                    b.fun_type(explicit(), None, rec_ty, |b, _| {
+                                        fun_elims(b.var(p), vec![b.var(x)])
+                                    })
+                                })
+                            });
+                            b.fun_type(explicit(), None, step_ty, |b, _| {
+                                b.fun_type(explicit(), Some("x"), b.var(a), |b, x| {
+                                    let acc_ty = acc_ty(b.var(a), b.var(r), b.var(x));
+                                    b.fun_type(explicit(), None, acc_ty, |b, _| {
+                                        fun_elims(b.var(p), vec![b.var(x)])
+                                    })
+                                })
+                            })
+                        })
+                    })
+                })),
+                capability: Capability::Pure,
+            },
+        ),
+    ]
+}
+
+/// Eliminate a proof of accessibility, once it has been introduced with `acc`:
+///
+/// ```text
+/// acc-elim A R P f x (acc A R x h) ~> f x h (fun y r => acc-elim A R P f y (h y r))
+/// ```
+///
+/// Primitives can't evaluate terms themselves, so the result is suspended
+/// until it is forced.
+fn interpret_acc_elim(args: Vec<Rc<Value>>) -> Option<Result<Rc<Value>, String>> {
+    let below = match args.get(5)?.as_ref() {
+        Value::Neutral(Head::Prim(name), spine) if name.0 == "acc" && spine.len() == 4 => {
+            match &spine[3] {
+                Elim::Fun(_, below) => below.clone(),
+                Elim::Literal(_) | Elim::Record(_) => return None,
+            }
+        },
+        _ => return None,
+    };
+
+    let builder = TermBuilder::new();
+    let explicit = || AppMode::Explicit;
+    let reduct = builder.fun_intro(explicit(), Some("A"), |b, a| {
+        b.fun_intro(explicit(), Some("R"), |b, r| {
+            b.fun_intro(explicit(), Some("P"), |b, p| {
+                b.fun_intro(explicit(), Some("f"), |b, f| {
+                    b.fun_intro(explicit(), Some("x"), |b, x| {
+                        b.fun_intro(explicit(), Some("h"), |b, h| {
+                            let rec = b.fun_intro(explicit(), Some("y"), |b, y| {
+                                b.fun_intro(explicit(), Some("r"), |b, r1| {
+                                    let below = fun_elims(b.var(h), vec![b.var(y), b.var(r1)]);
+                                    let args = vec![b.var(a), b.var(r), b.var(p), b.var(f)];
+                                    let elim = fun_elims(Rc::from(Term::prim("acc-elim")), args);
+                                    fun_elims(elim, vec![b.var(y), below])
+                                })
+                            });
+                            fun_elims(b.var(f), vec![b.var(x), b.var(h), rec])
+                        })
+                    })
+                })
+            })
+        })
+    });
+
+    let mut values = var::Env::new();
+    for arg in &args[..5] {
+        values.add_entry(arg.clone());
+    }
+    values.add_entry(below);
+    let size = values.size().0;
+    let vars = (0..size)
+        .rev()
+        .map(|index| Rc::from(Term::var(index)))
+        .collect();
+    let term = fun_elims(reduct, vars);
+
+    Some(Ok(Rc::from(Value::thunk(Thunk::new(term, values)))))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn interpret(name: &str, args: Vec<Value>) -> Option<Result<Rc<Value>, String>> {
+        let prims = Env::default();
+        let entry = prims.lookup_entry(&Name::from(name)).unwrap();
+        (entry.interpretation)(args.into_iter().map(Rc::from).collect())
+    }
+
+    #[test]
+    fn refuse_effectful_prims() {
+        let mut prims = Env::default();
+        let name = Name::from("debug-print");
+        let entry = prims.lookup_entry(&name).unwrap().clone();
+        let message = Rc::from(Value::literal_intro("hello"));
+        let spine = [Elim::Fun(AppMode::Explicit, message.clone())];
+
+        assert_eq!(entry.capability, Capability::Io);
+        assert!(prims.interpret(&name, &entry, &spine).is_none());
+        prims.set_allowed(Capability::Io);
+        let (value, _) = prims.interpret(&name, &entry, &spine).unwrap().unwrap();
+        assert_eq!(value, message);
+    }
+
+    #[test]
+    fn acc_elim_computes() {
+        let prims = Env::default();
+        let metas = crate::meta::Env::new();
+        let arg = |level: u32| Elim::Fun(AppMode::Explicit, Rc::from(Value::var(level)));
+        let proof_spine = vec![arg(0), arg(1), arg(4), arg(5)];
+        let proof = Value::Neutral(Head::Prim(Name::from("acc")), proof_spine);
+        let mut args = (0..5).map(Value::var).collect::<Vec<_>>();
+        args.push(proof);
+
+        let value = interpret("acc-elim", args).unwrap().unwrap();
+        let value = crate::nbe::force_value(&prims, &metas, &value).unwrap();
+
+        match value.as_ref() {
+            Value::Neutral(Head::Var(level), spine) => {
+                assert_eq!(*level, var::Level(3));
+                assert_eq!(spine.len(), 3);
+                assert_eq!(spine[..2], [arg(4), arg(5)]);
+            },
+            value => panic!("unexpected value: {:?}", value),
+        }
+
+        let stuck = interpret("acc-elim", (0..6).map(Value::var).collect());
+        assert!(stuck.is_none());
+    }
+
+    #[test]
+    fn record_folds() {
+        let mut prims = Env::default();
+        let fold_log = Rc::new(FoldLog::new());
+        prims.set_fold_log(Some(fold_log.clone()));
+
+        let name = Name::from("u8-not");
+        let entry = prims.lookup_entry(&name).unwrap().clone();
+        let arg = |value: Value| [Elim::Fun(AppMode::Explicit, Rc::from(value))];
+        prims.interpret(&name, &entry, &arg(Value::literal_intro(0_u8)));
+        prims.interpret(&name, &entry, &arg(Value::literal_intro(1_u8)));
+        prims.interpret(&name, &entry, &arg(Value::var(0)));
+
+        assert_eq!(
+            fold_log.folds().into_iter().collect::<Vec<_>>(),
+            vec![(name, 2)]
+        );
+    }
+
+    #[test]
+    fn signatures() {
+        let prims = Env::default();
+        let ty = |name: &str| prims.lookup_entry(&Name::from(name)).unwrap().ty.clone();
+
+        assert_eq!(ty("abort"), None);
+        assert_eq!(ty("u8-max"), Some(u8::prim_ty()));
+        assert_eq!(
+            ty("u8-to-string"),
+            Some(fun_ty(vec![u8::prim_ty()], <Rc<str>>::prim_ty())),
+        );
+        assert_eq!(
+            ty("u64-shl"),
+            Some(fun_ty(vec![u64::prim_ty(), u32::prim_ty()], u64::prim_ty())),
+        );
+        assert!(ty("array-get").is_some());
+    }
+
+    #[test]
+    fn float_to_int_checked_bounds() {
+        let result = interpret("f64-to-s8-checked", vec![Value::literal_intro(-128.9_f64)]);
+        assert_eq!(result, Some(Ok(Rc::from(Value::literal_intro(-128_i8)))));
+        let result = interpret("f32-to-u8-checked", vec![Value::literal_intro(255.5_f32)]);
+        assert_eq!(result, Some(Ok(Rc::from(Value::literal_intro(255_u8)))));
+
+        for value in &[128.0, f64::NAN, f64::INFINITY] {
+            let result = interpret("f64-to-s8-checked", vec![Value::literal_intro(*value)]);
+            assert!(result.unwrap().is_err());
+        }
+        let result = interpret(
+            "f64-to-u64-checked",
+            vec![Value::literal_intro(u64::MAX as f64)],
+        );
+        assert!(result.unwrap().is_err());
+    }
+
+    #[test]
+    fn integer_arithmetic_overflow() {
+        let int = |name: &str, args: Vec<Value>| interpret(name, args).unwrap();
+        let u8s = |lhs: u8, rhs: u8| vec![Value::literal_intro(lhs), Value::literal_intro(rhs)];
+        let i8s = |lhs: i8, rhs: i8| vec![Value::literal_intro(lhs), Value::literal_intro(rhs)];
+
+        assert_eq!(
+            int("u8-add", u8s(255, 1)),
+            Ok(Rc::from(Value::literal_intro(0_u8)))
+        );
+        assert_eq!(
+            int("u8-sub", u8s(0, 1)),
+            Ok(Rc::from(Value::literal_intro(255_u8)))
+        );
+        assert_eq!(
+            int("u8-mul", u8s(16, 16)),
+            Ok(Rc::from(Value::literal_intro(0_u8)))
+        );
+        assert_eq!(
+            int("s8-neg", vec![Value::literal_intro(i8::MIN)]),
+            Ok(Rc::from(Value::literal_intro(i8::MIN))),
+        );
+        assert_eq!(
+            int("s8-div", i8s(i8::MIN, -1)),
+            Ok(Rc::from(Value::literal_intro(i8::MIN))),
+        );
+        assert!(int("u8-div", u8s(1, 0)).is_err());
+        assert!(int("s8-div", i8s(1, 0)).is_err());
+    }
+
+    #[test]
+    fn array_make_bounds() {
+        let make = |len: u64| {
+            let args = vec![
+                Value::universe(0),
+                Value::literal_intro(len),
+                Value::literal_intro(0_u8),
+            ];
+            interpret("array-make", args).unwrap()
+        };
+
+        match make(3).unwrap().as_ref() {
+            Value::ArrayIntro(elems) => assert_eq!(elems.len(), 3),
+            value => panic!("expected an array, found {:?}", value),
         }
+        assert!(make(ARRAY_MAX_LEN + 1).is_err());
+        assert!(make(u64::MAX).is_err());
     }
 }
@@ -0,0 +1,145 @@
+//! Signatures of checked modules.
+//!
+//! A signature summarises the items of a module once it has been checked,
+//! recording the type of each item, and its value if it has been defined. This
+//! gives imports, interface files, and documentation a single description of
+//! the module to work from, without having to check the module again.
+
+use crate::domain::{Type, Value};
+use crate::{DocString, Label, Rc};
+
+/// The signature of an item in a module.
+#[derive(Debug, Clone)]
+pub struct ItemSignature {
+    /// The documentation of the item.
+    pub doc: DocString,
+    /// The label of the item.
+    pub label: Label,
+    /// The type of the item.
+    pub ty: Rc<Type>,
+    /// The value of the item, or `None` if it was declared without being
+    /// defined.
+    pub value: Option<Rc<Value>>,
+}
+
+/// The signature of a module.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSignature {
+    /// The items of the module, in the order that they were first introduced.
+    items: Vec<ItemSignature>,
+    /// The position of each item in `items`, by label.
+    positions: im::HashMap<Label, usize>,
+}
+
+impl ModuleSignature {
+    /// Create a new, empty signature.
+    pub fn new() -> ModuleSignature {
+        ModuleSignature::default()
+    }
+
+    /// The items of the module, in the order that they were first introduced.
+    pub fn items(&self) -> &[ItemSignature] {
+        &self.items
+    }
+
+    /// Lookup the signature of an item.
+    pub fn lookup_item(&self, label: &Label) -> Option<&ItemSignature> {
+        self.positions
+            .get(label)
+            .map(|position| &self.items[*position])
+    }
+
+    /// Record the declaration of an item that is yet to be defined.
+    pub fn add_declaration(&mut self, doc: DocString, label: Label, ty: Rc<Type>) {
+        self.add_item(ItemSignature {
+            doc,
+            label,
+            ty,
+            value: None,
+        });
+    }
+
+    /// Record the definition of an item, filling in its value if it was
+    /// previously declared.
+    ///
+    /// The documentation of the declaration is kept, unless it was empty.
+    pub fn add_definition(&mut self, doc: DocString, label: Label, ty: Rc<Type>, value: Rc<Value>) {
+        match self.positions.get(&label) {
+            Some(position) => {
+                let item = &mut self.items[*position];
+                if item.doc.is_empty() {
+                    item.doc = doc;
+                }
+                item.ty = ty;
+                item.value = Some(value);
+            },
+            None => self.add_item(ItemSignature {
+                doc,
+                label,
+                ty,
+                value: Some(value),
+            }),
+        }
+    }
+
+    /// Add the items of the signature of a namespace, qualifying their labels
+    /// with the name of the namespace.
+    pub fn add_namespace(&mut self, name: &str, signature: ModuleSignature) {
+        for item in signature.items {
+            self.add_item(ItemSignature {
+                label: Label(format!("{}.{}", name, item.label)),
+                ..item
+            });
+        }
+    }
+
+    fn add_item(&mut self, item: ItemSignature) {
+        self.positions.insert(item.label.clone(), self.items.len());
+        self.items.push(item);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn label(name: &str) -> Label {
+        Label(name.to_owned())
+    }
+
+    #[test]
+    fn define_declared_item() {
+        let mut signature = ModuleSignature::new();
+        let ty = Rc::from(Value::universe(0));
+        let value = Rc::from(Value::RecordTypeEmpty);
+
+        signature.add_declaration(DocString::from("Unit type.\n"), label("Unit"), ty.clone());
+        signature.add_declaration(DocString::from(""), label("unit"), value.clone());
+        signature.add_definition(DocString::from(""), label("Unit"), ty, value.clone());
+
+        let labels = (signature.items().iter())
+            .map(|item| item.label.0.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Unit", "unit"]);
+
+        let item = signature.lookup_item(&label("Unit")).unwrap();
+        assert_eq!(item.doc.as_ref(), "Unit type.\n");
+        assert_eq!(item.value, Some(value));
+        assert_eq!(signature.lookup_item(&label("unit")).unwrap().value, None);
+    }
+
+    #[test]
+    fn qualify_namespace_items() {
+        let ty = Rc::from(Value::universe(0));
+        let value = Rc::from(Value::RecordTypeEmpty);
+
+        let mut namespace_signature = ModuleSignature::new();
+        namespace_signature.add_definition(DocString::from(""), label("Unit"), ty, value);
+
+        let mut signature = ModuleSignature::new();
+        signature.add_namespace("Prelude", namespace_signature);
+
+        assert!(signature.lookup_item(&label("Unit")).is_none());
+        assert!(signature.lookup_item(&label("Prelude.Unit")).is_some());
+    }
+}
@@ -0,0 +1,675 @@
+//! Step-by-step reduction of terms.
+//!
+//! Normalization by evaluation computes normal forms in one go, without
+//! showing how they arise. The functions in this module instead reduce a term
+//! one redex at a time, in normal order (leftmost, outermost first), recording
+//! each of the intermediate terms along the way.
+//!
+//! Beta reductions, let bindings, projections, and case splits are contracted
+//! by substitution on the syntax. Definitions from the environment, solved
+//! metavariables, and primitive applications are instead unfolded by reducing
+//! them to weak head normal form and reading the result back. Definitions that
+//! unfold to atomic terms, like `Bool` or `true`, are unfolded up front, as
+//! they would otherwise look the same before and after taking a step.
+
+use crate::diff::{self, Path};
+use crate::domain::Value;
+use crate::syntax::{Item, Term};
+use crate::{meta, nbe, prim, var, Rc};
+
+/// A redex in a term, ie. a subterm that can be contracted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redex {
+    /// The path from the root of the term to the redex.
+    pub path: Path,
+    /// The redex itself. This points to the subterm in the reduction step, so
+    /// that it can be highlighted when the term is printed.
+    pub term: Rc<Term>,
+}
+
+/// A term in a sequence of reduction steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReductionStep {
+    /// The term at this step.
+    pub term: Rc<Term>,
+    /// The redex that is contracted to get to the next step, or `None` if the
+    /// term is in normal form.
+    pub redex: Option<Redex>,
+}
+
+/// Reduce a term one redex at a time, returning each of the intermediate
+/// terms, starting with the term itself.
+///
+/// At most `fuel` redexes are contracted. If the fuel runs out before a
+/// normal form is reached, the redex of the last step is left uncontracted.
+pub fn reduction_steps(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    values: &var::Env<Rc<Value>>,
+    term: &Rc<Term>,
+    fuel: usize,
+) -> Result<Vec<ReductionStep>, String> {
+    let reducer = Reducer {
+        prims,
+        metas,
+        values,
+    };
+    let mut steps = Vec::new();
+    let mut term = reducer.unfold_aliases(term)?;
+
+    loop {
+        let mut path = Vec::new();
+        if !reducer.find_redex(&term, 0, &mut path)? {
+            steps.push(ReductionStep { term, redex: None });
+            return Ok(steps);
+        }
+
+        // Copy the redex, so that it is not shared with any other parts of
+        // the term that would otherwise be highlighted along with it
+        let mut redex = None;
+        let step_term = replace_at(&term, 0, &path, &mut |term, _| {
+            let term = Rc::from(Term::clone(term));
+            redex = Some(term.clone());
+            Ok(term)
+        })?;
+        let redex = redex.ok_or_else(|| "steps: redex not found".to_owned())?;
+
+        let next_term = if steps.len() < fuel {
+            Some(replace_at(&step_term, 0, &path, &mut |term, depth| {
+                reducer.contract(term, depth)
+            })?)
+        } else {
+            None
+        };
+
+        steps.push(ReductionStep {
+            term: step_term,
+            redex: Some(Redex {
+                path: Path(path),
+                term: redex,
+            }),
+        });
+
+        match next_term {
+            Some(next_term) => term = next_term,
+            None => return Ok(steps),
+        }
+    }
+}
+
+struct Reducer<'a> {
+    prims: &'a prim::Env,
+    metas: &'a meta::Env,
+    /// The values of the variables that are free in the root of the term.
+    values: &'a var::Env<Rc<Value>>,
+}
+
+impl<'a> Reducer<'a> {
+    /// Unfold the variables of the environment that are defined as atomic
+    /// terms, like literals, literal types, universes, and primitives.
+    fn unfold_aliases(&self, term: &Rc<Term>) -> Result<Rc<Term>, String> {
+        let size = self.values.size();
+        let aliases = (0..size.0)
+            .map(|index| {
+                let term = self.delta(&Rc::from(Term::var(index)), 0)?;
+                Ok(match term.as_ref() {
+                    Term::Prim(_)
+                    | Term::LiteralType(_)
+                    | Term::LiteralIntro(_)
+                    | Term::Universe(_) => Some(term),
+                    _ => None,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(map_vars(
+            term,
+            0,
+            &|term, index, depth| match index.0.checked_sub(depth) {
+                Some(index) => match &aliases.get(index as usize) {
+                    Some(Some(alias)) => alias.clone(),
+                    Some(None) | None => term.clone(),
+                },
+                None => term.clone(),
+            },
+        ))
+    }
+
+    /// Find the leftmost, outermost redex in a term that is under `depth`
+    /// binders, pushing the steps that lead to it onto `path`.
+    fn find_redex(
+        &self,
+        term: &Rc<Term>,
+        depth: u32,
+        path: &mut Vec<diff::Step>,
+    ) -> Result<bool, String> {
+        match term.as_ref() {
+            Term::Var(_) | Term::Meta(_) => return self.is_delta_redex(term, depth),
+            Term::Ann(_, _) | Term::Let(_, _) => return Ok(true),
+            Term::LiteralElim(scrutinee, _, _) => {
+                if let Term::LiteralIntro(_) = scrutinee.as_ref() {
+                    return Ok(true);
+                }
+            },
+            Term::FunElim(fun, app_mode, _) => {
+                if let Term::FunIntro(intro_app_mode, _, _) = fun.as_ref() {
+                    if intro_app_mode == app_mode {
+                        return Ok(true);
+                    }
+                }
+            },
+            Term::RecordElim(record, label) => {
+                if let Term::RecordIntro(intro_fields) = record.as_ref() {
+                    if intro_fields.iter().any(|(l, _)| l == label) {
+                        return Ok(true);
+                    }
+                }
+            },
+            _ => {},
+        }
+
+        for (step, child, child_depth) in children(term, depth) {
+            path.push(step);
+            if self.find_redex(child, child_depth, path)? {
+                return Ok(true);
+            }
+            path.pop();
+        }
+
+        // Primitives are only applied once their arguments are in normal form
+        if is_prim_app(term) {
+            return self.is_delta_redex(term, depth);
+        }
+
+        Ok(false)
+    }
+
+    /// Contract a redex that is under `depth` binders.
+    fn contract(&self, term: &Rc<Term>, depth: u32) -> Result<Rc<Term>, String> {
+        match term.as_ref() {
+            Term::Ann(term, _) => Ok(term.clone()),
+            Term::Let(items, body) => {
+                // Declarations are dropped along with the first definition
+                let position = items.iter().position(|item| match item {
+                    Item::Declaration(_, _, _) => false,
+                    Item::Definition(_, _, _) => true,
+                });
+                match position.map(|position| (&items[position], &items[position + 1..])) {
+                    Some((Item::Definition(_, _, term), [])) => Ok(instantiate(body, term)),
+                    Some((Item::Definition(_, _, term), items)) => {
+                        let body = Rc::from(Term::Let(items.to_vec(), body.clone()));
+                        Ok(instantiate(&body, term))
+                    },
+                    Some((Item::Declaration(_, _, _), _)) | None => Ok(body.clone()),
+                }
+            },
+            Term::LiteralElim(scrutinee, clauses, default_body) => match scrutinee.as_ref() {
+                Term::LiteralIntro(literal_intro) => {
+                    let body = nbe::find_literal_clause(clauses, literal_intro);
+                    Ok(body.unwrap_or(default_body).clone())
+                },
+                _ => self.delta(term, depth),
+            },
+            Term::FunElim(fun, _, arg) => match fun.as_ref() {
+                Term::FunIntro(_, _, body) => Ok(instantiate(body, arg)),
+                _ => self.delta(term, depth),
+            },
+            Term::RecordElim(record, label) => match record.as_ref() {
+                Term::RecordIntro(intro_fields) => {
+                    let position = (intro_fields.iter().position(|(l, _)| l == label))
+                        .ok_or_else(|| format!("steps: field not found: {}", label))?;
+                    // Each field is in the scope of the fields before it
+                    let (earlier_fields, _) = intro_fields.split_at(position);
+                    let term = earlier_fields
+                        .iter()
+                        .rev()
+                        .fold(intro_fields[position].1.clone(), |term, (_, field)| {
+                            instantiate(&term, field)
+                        });
+                    Ok(term)
+                },
+                _ => self.delta(term, depth),
+            },
+            _ => self.delta(term, depth),
+        }
+    }
+
+    /// Unfold a term that is under `depth` binders, by reducing it to weak
+    /// head normal form and reading it back.
+    fn delta(&self, term: &Rc<Term>, depth: u32) -> Result<Rc<Term>, String> {
+        let mut values = self.values.clone();
+        for _ in 0..depth {
+            let var_level = values.size().next_level();
+            values.add_entry(Rc::from(Value::var(var_level)));
+        }
+
+        let value = nbe::whnf(self.prims, self.metas, &values, term)?;
+        nbe::read_back_value(self.prims, self.metas, values.size(), &value)
+    }
+
+    /// Check if unfolding a term makes any progress.
+    fn is_delta_redex(&self, term: &Rc<Term>, depth: u32) -> Result<bool, String> {
+        Ok(self.delta(term, depth)? != *term)
+    }
+}
+
+/// Check if a term is a primitive, applied to zero or more arguments.
+fn is_prim_app(term: &Term) -> bool {
+    match term {
+        Term::Prim(_) => true,
+        Term::FunElim(fun, _, _) => is_prim_app(fun),
+        _ => false,
+    }
+}
+
+/// The immediate subterms of a term that is under `depth` binders, along with
+/// the number of binders that each of the subterms are under.
+///
+/// Annotations and let bindings are always redexes, so we never need to look
+/// inside them.
+fn children(term: &Rc<Term>, depth: u32) -> Vec<(diff::Step, &Rc<Term>, u32)> {
+    match term.as_ref() {
+        Term::Var(_) | Term::Meta(_) | Term::Prim(_) => Vec::new(),
+        Term::Ann(_, _) | Term::Let(_, _) => Vec::new(),
+
+        Term::LiteralType(_) | Term::LiteralIntro(_) => Vec::new(),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            std::iter::once((diff::Step::Scrutinee, scrutinee, depth))
+                .chain(
+                    (clauses.iter().enumerate())
+                        .map(|(index, (_, body))| (diff::Step::Clause(index), body, depth)),
+                )
+                .chain(std::iter::once((
+                    diff::Step::DefaultClause,
+                    default_body,
+                    depth,
+                )))
+                .collect()
+        },
+
+        Term::FunType(_, _, _, param_ty, body_ty) => vec![
+            (diff::Step::ParamType, param_ty, depth),
+            (diff::Step::BodyType, body_ty, depth + 1),
+        ],
+        Term::FunIntro(_, _, body) => vec![(diff::Step::FunBody, body, depth + 1)],
+        Term::FunElim(fun, _, arg) => {
+            vec![(diff::Step::Fun, fun, depth), (diff::Step::Arg, arg, depth)]
+        },
+
+        Term::RecordType(ty_fields) => (ty_fields.iter().enumerate())
+            .map(|(i, (_, label, _, ty))| {
+                (diff::Step::FieldType(label.clone()), ty, depth + i as u32)
+            })
+            .collect(),
+        Term::RecordIntro(intro_fields) => (intro_fields.iter().enumerate())
+            .map(|(i, (label, term))| (diff::Step::Field(label.clone()), term, depth + i as u32))
+            .collect(),
+        Term::RecordElim(record, _) => vec![(diff::Step::Record, record, depth)],
+
+        Term::ArrayType(elem_ty) => vec![(diff::Step::ElemType, elem_ty, depth)],
+        Term::ArrayIntro(elems) => (elems.iter().enumerate())
+            .map(|(index, elem)| (diff::Step::Elem(index), elem, depth))
+            .collect(),
+
+        Term::Universe(_) => Vec::new(),
+    }
+}
+
+/// Replace the subterm at the end of a path, using a function that is given
+/// the subterm along with the number of binders that it is under.
+fn replace_at(
+    term: &Rc<Term>,
+    depth: u32,
+    path: &[diff::Step],
+    replace: &mut impl FnMut(&Rc<Term>, u32) -> Result<Rc<Term>, String>,
+) -> Result<Rc<Term>, String> {
+    let (step, path) = match path.split_first() {
+        None => return replace(term, depth),
+        Some((step, path)) => (step, path),
+    };
+
+    let term = match (term.as_ref(), step) {
+        (Term::LiteralElim(scrutinee, clauses, default_body), diff::Step::Scrutinee) => {
+            let scrutinee = replace_at(scrutinee, depth, path, replace)?;
+            Term::LiteralElim(scrutinee, clauses.clone(), default_body.clone())
+        },
+        (Term::LiteralElim(scrutinee, clauses, default_body), diff::Step::Clause(index))
+            if *index < clauses.len() =>
+        {
+            let mut clauses = clauses.to_vec();
+            clauses[*index].1 = replace_at(&clauses[*index].1, depth, path, replace)?;
+            Term::LiteralElim(scrutinee.clone(), Rc::from(clauses), default_body.clone())
+        },
+        (Term::LiteralElim(scrutinee, clauses, default_body), diff::Step::DefaultClause) => {
+            let default_body = replace_at(default_body, depth, path, replace)?;
+            Term::LiteralElim(scrutinee.clone(), clauses.clone(), default_body)
+        },
+
+        (Term::FunType(doc, app_mode, name_hint, param_ty, body_ty), diff::Step::ParamType) => {
+            let param_ty = replace_at(param_ty, depth, path, replace)?;
+            Term::FunType(
+                doc.clone(),
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty,
+                body_ty.clone(),
+            )
+        },
+        (Term::FunType(doc, app_mode, name_hint, param_ty, body_ty), diff::Step::BodyType) => {
+            let body_ty = replace_at(body_ty, depth + 1, path, replace)?;
+            Term::FunType(
+                doc.clone(),
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty.clone(),
+                body_ty,
+            )
+        },
+        (Term::FunIntro(app_mode, name_hint, body), diff::Step::FunBody) => {
+            let body = replace_at(body, depth + 1, path, replace)?;
+            Term::FunIntro(app_mode.clone(), name_hint.clone(), body)
+        },
+        (Term::FunElim(fun, app_mode, arg), diff::Step::Fun) => {
+            let fun = replace_at(fun, depth, path, replace)?;
+            Term::FunElim(fun, app_mode.clone(), arg.clone())
+        },
+        (Term::FunElim(fun, app_mode, arg), diff::Step::Arg) => {
+            let arg = replace_at(arg, depth, path, replace)?;
+            Term::FunElim(fun.clone(), app_mode.clone(), arg)
+        },
+
+        (Term::RecordType(ty_fields), diff::Step::FieldType(label)) => {
+            let mut ty_fields = ty_fields.clone();
+            let (i, field) = (ty_fields.iter_mut().enumerate())
+                .find(|(_, (_, l, _, _))| l == label)
+                .ok_or_else(|| format!("steps: field not found: {}", label))?;
+            field.3 = replace_at(&field.3, depth + i as u32, path, replace)?;
+            Term::RecordType(ty_fields)
+        },
+        (Term::RecordIntro(intro_fields), diff::Step::Field(label)) => {
+            let mut intro_fields = intro_fields.clone();
+            let (i, field) = (intro_fields.iter_mut().enumerate())
+                .find(|(_, (l, _))| l == label)
+                .ok_or_else(|| format!("steps: field not found: {}", label))?;
+            field.1 = replace_at(&field.1, depth + i as u32, path, replace)?;
+            Term::RecordIntro(intro_fields)
+        },
+        (Term::RecordElim(record, label), diff::Step::Record) => {
+            Term::RecordElim(replace_at(record, depth, path, replace)?, label.clone())
+        },
+
+        (Term::ArrayType(elem_ty), diff::Step::ElemType) => {
+            Term::ArrayType(replace_at(elem_ty, depth, path, replace)?)
+        },
+        (Term::ArrayIntro(elems), diff::Step::Elem(index)) if *index < elems.len() => {
+            let mut elems = elems.clone();
+            elems[*index] = replace_at(&elems[*index], depth, path, replace)?;
+            Term::ArrayIntro(elems)
+        },
+
+        (_, step) => return Err(format!("steps: invalid path step: {}", step)),
+    };
+
+    Ok(Rc::from(term))
+}
+
+/// Substitute a term for the variable bound by the innermost binder of `body`,
+/// shifting the other free variables of `body` down to account for the
+/// removal of the binder.
+pub fn instantiate(body: &Rc<Term>, arg: &Rc<Term>) -> Rc<Term> {
+    map_vars(body, 0, &|term, index, depth| {
+        if index.0 < depth {
+            term.clone()
+        } else if index.0 == depth {
+            shift(arg, depth)
+        } else {
+            Rc::from(Term::var(index.0 - 1))
+        }
+    })
+}
+
+/// Shift the free variables of a term up by the given amount.
+pub fn shift(term: &Rc<Term>, amount: u32) -> Rc<Term> {
+    if amount == 0 {
+        return term.clone();
+    }
+
+    map_vars(term, 0, &|term, index, depth| {
+        if index.0 < depth {
+            term.clone()
+        } else {
+            Rc::from(Term::var(index.0 + amount))
+        }
+    })
+}
+
+/// Replace the variables in a term that is under `depth` binders, using a
+/// function that is given each variable along with the number of binders
+/// that it is under.
+fn map_vars(
+    term: &Rc<Term>,
+    depth: u32,
+    on_var: &impl Fn(&Rc<Term>, var::Index, u32) -> Rc<Term>,
+) -> Rc<Term> {
+    match term.as_ref() {
+        Term::Var(index) => on_var(term, *index, depth),
+        Term::Meta(_) | Term::Prim(_) => term.clone(),
+
+        Term::Ann(term, term_ty) => Rc::from(Term::Ann(
+            map_vars(term, depth, on_var),
+            map_vars(term_ty, depth, on_var),
+        )),
+        Term::Let(items, body) => {
+            let mut depth = depth;
+            let items = (items.iter())
+                .map(|item| match item {
+                    Item::Declaration(doc, label, term_ty) => {
+                        let term_ty = map_vars(term_ty, depth, on_var);
+                        Item::Declaration(doc.clone(), label.clone(), term_ty)
+                    },
+                    Item::Definition(doc, label, term) => {
+                        let term = map_vars(term, depth, on_var);
+                        depth += 1;
+                        Item::Definition(doc.clone(), label.clone(), term)
+                    },
+                })
+                .collect();
+            Rc::from(Term::Let(items, map_vars(body, depth, on_var)))
+        },
+
+        Term::LiteralType(_) | Term::LiteralIntro(_) => term.clone(),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let clauses = (clauses.iter())
+                .map(|(pattern, body)| (pattern.clone(), map_vars(body, depth, on_var)))
+                .collect::<Vec<_>>();
+            Rc::from(Term::LiteralElim(
+                map_vars(scrutinee, depth, on_var),
+                Rc::from(clauses),
+                map_vars(default_body, depth, on_var),
+            ))
+        },
+
+        Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => Rc::from(Term::FunType(
+            doc.clone(),
+            app_mode.clone(),
+            name_hint.clone(),
+            map_vars(param_ty, depth, on_var),
+            map_vars(body_ty, depth + 1, on_var),
+        )),
+        Term::FunIntro(app_mode, name_hint, body) => Rc::from(Term::FunIntro(
+            app_mode.clone(),
+            name_hint.clone(),
+            map_vars(body, depth + 1, on_var),
+        )),
+        Term::FunElim(fun, app_mode, arg) => Rc::from(Term::FunElim(
+            map_vars(fun, depth, on_var),
+            app_mode.clone(),
+            map_vars(arg, depth, on_var),
+        )),
+
+        Term::RecordType(ty_fields) => {
+            let ty_fields = (ty_fields.iter().enumerate())
+                .map(|(i, (doc, label, name_hint, ty))| {
+                    let ty = map_vars(ty, depth + i as u32, on_var);
+                    (doc.clone(), label.clone(), name_hint.clone(), ty)
+                })
+                .collect();
+            Rc::from(Term::RecordType(ty_fields))
+        },
+        Term::RecordIntro(intro_fields) => {
+            let intro_fields = (intro_fields.iter().enumerate())
+                .map(|(i, (label, term))| (label.clone(), map_vars(term, depth + i as u32, on_var)))
+                .collect();
+            Rc::from(Term::RecordIntro(intro_fields))
+        },
+        Term::RecordElim(record, label) => Rc::from(Term::RecordElim(
+            map_vars(record, depth, on_var),
+            label.clone(),
+        )),
+
+        Term::ArrayType(elem_ty) => Rc::from(Term::ArrayType(map_vars(elem_ty, depth, on_var))),
+        Term::ArrayIntro(elems) => {
+            let elems = elems
+                .iter()
+                .map(|elem| map_vars(elem, depth, on_var))
+                .collect();
+            Rc::from(Term::ArrayIntro(elems))
+        },
+
+        Term::Universe(_) => term.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::literal::LiteralIntro;
+    use crate::{AppMode, Label};
+
+    fn fun_intro(name: &str, body: Term) -> Term {
+        Term::FunIntro(AppMode::Explicit, Some(name.to_owned()), Rc::from(body))
+    }
+
+    fn fun_elim(fun: Term, arg: Term) -> Term {
+        Term::FunElim(Rc::from(fun), AppMode::Explicit, Rc::from(arg))
+    }
+
+    fn bool_intro(value: bool) -> Term {
+        Term::literal_intro(LiteralIntro::Bool(value))
+    }
+
+    fn steps(values: &var::Env<Rc<Value>>, term: Term, fuel: usize) -> Vec<ReductionStep> {
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+
+        reduction_steps(&prims, &metas, values, &Rc::from(term), fuel).unwrap()
+    }
+
+    fn terms(steps: &[ReductionStep]) -> Vec<Rc<Term>> {
+        steps.iter().map(|step| step.term.clone()).collect()
+    }
+
+    #[test]
+    fn steps_normal_form() {
+        let steps = steps(&var::Env::new(), bool_intro(true), 10);
+
+        assert_eq!(terms(&steps), [Rc::from(bool_intro(true))]);
+        assert_eq!(steps[0].redex, None);
+    }
+
+    #[test]
+    fn steps_beta() {
+        let term = fun_elim(fun_intro("x", Term::var(0)), bool_intro(true));
+        let steps = steps(&var::Env::new(), term.clone(), 10);
+
+        assert_eq!(
+            terms(&steps),
+            [Rc::from(term.clone()), Rc::from(bool_intro(true))]
+        );
+        let redex = steps[0].redex.as_ref().unwrap();
+        assert_eq!(redex.path, Path::default());
+        assert_eq!(redex.term, Rc::from(term));
+        assert!(Rc::ptr_eq(&redex.term, &steps[0].term));
+    }
+
+    #[test]
+    fn steps_under_binders() {
+        // fun z => (fun x y => x) z
+        let term = fun_intro(
+            "z",
+            fun_elim(fun_intro("x", fun_intro("y", Term::var(1))), Term::var(0)),
+        );
+        let steps = steps(&var::Env::new(), term, 10);
+
+        assert_eq!(steps.len(), 2);
+        let redex = steps[0].redex.as_ref().unwrap();
+        assert_eq!(redex.path.to_string(), "/fun-intro.body");
+        // The argument is shifted under the binder of `y`
+        assert_eq!(
+            steps[1].term,
+            Rc::from(fun_intro("z", fun_intro("y", Term::var(1)))),
+        );
+    }
+
+    #[test]
+    fn steps_let_and_record_elim() {
+        // let a = true in record { b = a; c = b }.c
+        let record = Term::RecordIntro(vec![
+            (Label("b".to_owned()), Rc::from(Term::var(0))),
+            (Label("c".to_owned()), Rc::from(Term::var(0))),
+        ]);
+        let term = Term::Let(
+            vec![Item::Definition(
+                Rc::from(""),
+                Label("a".to_owned()),
+                Rc::from(bool_intro(true)),
+            )],
+            Rc::from(Term::RecordElim(Rc::from(record), Label("c".to_owned()))),
+        );
+        let steps = steps(&var::Env::new(), term, 10);
+
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[2].term, Rc::from(bool_intro(true)));
+        assert_eq!(steps[2].redex, None);
+    }
+
+    #[test]
+    fn steps_unfold_definition() {
+        // r.a, where r = record { a = false }
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::RecordIntro(vec![(
+            Label("a".to_owned()),
+            Rc::from(Value::literal_intro(LiteralIntro::Bool(false))),
+        )])));
+        let term = Term::RecordElim(Rc::from(Term::var(0)), Label("a".to_owned()));
+        let steps = steps(&values, term, 10);
+
+        assert_eq!(steps.len(), 3);
+        assert_eq!(
+            steps[0].redex.as_ref().map(|redex| redex.path.to_string()),
+            Some("/record-elim.record".to_owned()),
+        );
+        assert_eq!(steps[2].term, Rc::from(bool_intro(false)));
+    }
+
+    #[test]
+    fn steps_unfold_aliases() {
+        let mut values = var::Env::new();
+        values.add_entry(Rc::from(Value::literal_intro(LiteralIntro::Bool(false))));
+        let steps = steps(&values, Term::var(0), 10);
+
+        assert_eq!(terms(&steps), [Rc::from(bool_intro(false))]);
+    }
+
+    #[test]
+    fn steps_out_of_fuel() {
+        // (fun x => x x) (fun x => x x)
+        let omega = || fun_intro("x", fun_elim(Term::var(0), Term::var(0)));
+        let term = fun_elim(omega(), omega());
+        let steps = steps(&var::Env::new(), term.clone(), 3);
+
+        assert_eq!(steps.len(), 4);
+        assert!(steps.iter().all(|step| step.term == Rc::from(term.clone())));
+        assert!(steps[3].redex.is_some());
+    }
+}
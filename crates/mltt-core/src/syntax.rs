@@ -1,10 +1,9 @@
 //! The core syntax of the language.
 
 use std::fmt;
-use std::rc::Rc;
 
-use super::literal::{LiteralIntro, LiteralType};
-use crate::{meta, prim, var, AppMode, DocString, Label, UniverseLevel};
+use super::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use crate::{meta, prim, var, AppMode, DocString, Label, Rc, UniverseLevel};
 
 /// Top-level module.
 #[derive(Clone, PartialEq)]
@@ -60,10 +59,10 @@ pub enum Term {
     /// We include a scrutinee, a list of clauses, and a default term. The
     /// clauses are sorted in ascending order by the literal to allow for
     /// efficient binary searching during evaluation.
-    LiteralElim(Rc<Term>, Rc<[(LiteralIntro, Rc<Term>)]>, Rc<Term>),
+    LiteralElim(Rc<Term>, Rc<[(LiteralPattern, Rc<Term>)]>, Rc<Term>),
 
     /// Dependent function types
-    FunType(AppMode, Option<String>, Rc<Term>, Rc<Term>),
+    FunType(DocString, AppMode, Option<String>, Rc<Term>, Rc<Term>),
     /// Introduce a function
     FunIntro(AppMode, Option<String>, Rc<Term>),
     /// Eliminate a function (application)
@@ -76,6 +75,11 @@ pub enum Term {
     /// Eliminate a record (projection)
     RecordElim(Rc<Term>, Label),
 
+    /// Array types
+    ArrayType(Rc<Term>),
+    /// Introduce an array
+    ArrayIntro(Vec<Rc<Term>>),
+
     /// Universe of types
     Universe(UniverseLevel),
 }
@@ -175,8 +179,8 @@ impl Term {
             },
 
             (
-                Term::FunType(app_mode1, _, param_ty1, body_ty1),
-                Term::FunType(app_mode2, _, param_ty2, body_ty2),
+                Term::FunType(_, app_mode1, _, param_ty1, body_ty1),
+                Term::FunType(_, app_mode2, _, param_ty2, body_ty2),
             ) => {
                 Term::alpha_eq(param_ty1, param_ty2)
                     && app_mode1 == app_mode2
@@ -203,11 +207,84 @@ impl Term {
                 Term::alpha_eq(record1, record2) && label1 == label2
             },
 
+            (Term::ArrayType(elem_ty1), Term::ArrayType(elem_ty2)) => {
+                Term::alpha_eq(elem_ty1, elem_ty2)
+            },
+            (Term::ArrayIntro(elems1), Term::ArrayIntro(elems2)) => {
+                elems1.len() == elems2.len()
+                    && Iterator::zip(elems1.iter(), elems2.iter())
+                        .all(|(elem1, elem2)| Term::alpha_eq(elem1, elem2))
+            },
+
             (Term::Universe(level1), Term::Universe(level2)) => level1 == level2,
 
             (_, _) => false,
         }
     }
+
+    /// Checks if the variable at the given index occurs free in the term.
+    ///
+    /// This is useful for determining if a function type is dependent, and
+    /// can therefore be printed using the arrow sugar, `A -> B`.
+    pub fn has_free_var(&self, var_index: var::Index) -> bool {
+        match self {
+            Term::Var(index) => *index == var_index,
+            Term::Meta(_) | Term::Prim(_) => false,
+
+            Term::Ann(term, term_ty) => {
+                term.has_free_var(var_index) || term_ty.has_free_var(var_index)
+            },
+            Term::Let(items, body) => {
+                let mut var_index = var_index;
+                for item in items {
+                    match item {
+                        Item::Declaration(_, _, term_ty) => {
+                            if term_ty.has_free_var(var_index) {
+                                return true;
+                            }
+                        },
+                        Item::Definition(_, _, term) => {
+                            if term.has_free_var(var_index) {
+                                return true;
+                            }
+                            var_index += 1;
+                        },
+                    }
+                }
+                body.has_free_var(var_index)
+            },
+
+            Term::LiteralType(_) | Term::LiteralIntro(_) => false,
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                scrutinee.has_free_var(var_index)
+                    || clauses.iter().any(|(_, body)| body.has_free_var(var_index))
+                    || default_body.has_free_var(var_index)
+            },
+
+            Term::FunType(_, _, _, param_ty, body_ty) => {
+                param_ty.has_free_var(var_index) || body_ty.has_free_var(var_index + 1)
+            },
+            Term::FunIntro(_, _, body) => body.has_free_var(var_index + 1),
+            Term::FunElim(fun, _, arg) => {
+                fun.has_free_var(var_index) || arg.has_free_var(var_index)
+            },
+
+            Term::RecordType(ty_fields) => ty_fields
+                .iter()
+                .enumerate()
+                .any(|(i, (_, _, _, ty))| ty.has_free_var(var_index + i as u32)),
+            Term::RecordIntro(intro_fields) => intro_fields
+                .iter()
+                .enumerate()
+                .any(|(i, (_, term))| term.has_free_var(var_index + i as u32)),
+            Term::RecordElim(record, _) => record.has_free_var(var_index),
+
+            Term::ArrayType(elem_ty) => elem_ty.has_free_var(var_index),
+            Term::ArrayIntro(elems) => elems.iter().any(|elem| elem.has_free_var(var_index)),
+
+            Term::Universe(_) => false,
+        }
+    }
 }
 
 impl fmt::Debug for Term {
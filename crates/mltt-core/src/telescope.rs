@@ -0,0 +1,315 @@
+//! Telescopes of dependent bindings.
+//!
+//! The fields of record types and the parameters of function types both form
+//! _telescopes_, where the types of the later bindings may depend on the
+//! values given to the earlier bindings. In the semantic domain these are
+//! represented one binding at a time, with the remaining bindings captured in
+//! a closure. This module provides a view over these values, so that we don't
+//! have to instantiate the closures by hand each time we traverse them.
+
+use crate::domain::{AppClosure, Type, Value};
+use crate::{meta, nbe, prim, var, AppMode, DocString, Label, Rc};
+
+/// The binder that introduced an entry in a telescope.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binder {
+    /// A field of a record type.
+    Field(Label),
+    /// A parameter of a function type.
+    Param(AppMode),
+}
+
+/// An entry in a telescope.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The documentation of the entry.
+    pub doc: DocString,
+    /// The binder that introduced the entry.
+    pub binder: Binder,
+    /// The name that was originally given to the binder.
+    pub name_hint: Option<String>,
+    /// The type of the entry.
+    pub ty: Rc<Type>,
+}
+
+impl Entry {
+    /// The label of the entry, if it is the field of a record type.
+    pub fn label(&self) -> Option<&Label> {
+        match &self.binder {
+            Binder::Field(label) => Some(label),
+            Binder::Param(_) => None,
+        }
+    }
+
+    /// The application mode of the entry, if it is the parameter of a
+    /// function type.
+    pub fn app_mode(&self) -> Option<&AppMode> {
+        match &self.binder {
+            Binder::Field(_) => None,
+            Binder::Param(app_mode) => Some(app_mode),
+        }
+    }
+}
+
+/// The kind of type that a telescope was taken from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    Record,
+    Fun,
+}
+
+/// A view of the bindings at the start of a record type or function type.
+#[derive(Debug, Clone)]
+pub struct Telescope {
+    kind: Kind,
+    /// The type, with the entries that have been instantiated so far removed.
+    ty: Rc<Type>,
+}
+
+impl Telescope {
+    /// The telescope of fields in a record type.
+    pub fn record(ty: Rc<Type>) -> Telescope {
+        Telescope {
+            kind: Kind::Record,
+            ty,
+        }
+    }
+
+    /// The telescope of parameters in a function type.
+    pub fn fun(ty: Rc<Type>) -> Telescope {
+        Telescope {
+            kind: Kind::Fun,
+            ty,
+        }
+    }
+
+    /// The type that remains after the entries that have been instantiated so
+    /// far. For records this will be the empty record type once every field
+    /// has been instantiated, and for functions this will be the body type once
+    /// every parameter has been instantiated.
+    pub fn rest(&self) -> &Rc<Type> {
+        &self.ty
+    }
+
+    /// Returns `true` if this is a record telescope, and all of the fields
+    /// have been instantiated.
+    pub fn is_record_empty(&self) -> bool {
+        self.kind == Kind::Record && matches!(self.ty.as_ref(), Value::RecordTypeEmpty)
+    }
+
+    /// Split the first entry off the telescope, along with the closure that
+    /// contains the remaining entries.
+    fn split_first(&self) -> Option<(Entry, &AppClosure)> {
+        match (self.kind, self.ty.as_ref()) {
+            (Kind::Record, Value::RecordTypeExtend(doc, label, name_hint, ty, rest)) => {
+                let entry = Entry {
+                    doc: doc.clone(),
+                    binder: Binder::Field(label.clone()),
+                    name_hint: name_hint.clone(),
+                    ty: ty.clone(),
+                };
+                Some((entry, rest))
+            },
+            (Kind::Fun, Value::FunType(doc, app_mode, name_hint, ty, rest)) => {
+                let entry = Entry {
+                    doc: doc.clone(),
+                    binder: Binder::Param(app_mode.clone()),
+                    name_hint: name_hint.clone(),
+                    ty: ty.clone(),
+                };
+                Some((entry, rest))
+            },
+            (_, _) => None,
+        }
+    }
+
+    /// The first entry of the telescope, if there is one.
+    pub fn first(&self) -> Option<Entry> {
+        self.split_first().map(|(entry, _)| entry)
+    }
+
+    /// Instantiate the first entry of the telescope with a value, moving on to
+    /// the next entry.
+    pub fn instantiate(
+        &mut self,
+        prims: &prim::Env,
+        metas: &meta::Env,
+        arg: Rc<Value>,
+    ) -> Result<(), String> {
+        let ty = match self.split_first() {
+            Some((_, rest)) => nbe::app_closure(prims, metas, rest, arg)?,
+            None => return Err("instantiate: empty telescope".to_owned()),
+        };
+        self.ty = ty;
+        Ok(())
+    }
+
+    /// Instantiate a prefix of the telescope with the given values.
+    pub fn instantiate_prefix(
+        &mut self,
+        prims: &prim::Env,
+        metas: &meta::Env,
+        args: impl IntoIterator<Item = Rc<Value>>,
+    ) -> Result<(), String> {
+        for arg in args {
+            self.instantiate(prims, metas, arg)?;
+        }
+        Ok(())
+    }
+
+    /// Split a record telescope at the field with the given label, returning
+    /// the entry for that field, or `None` if the field could not be found.
+    ///
+    /// The fields before it are instantiated with projections from `record`,
+    /// leaving the telescope at the field that was found.
+    pub fn split(
+        &mut self,
+        prims: &prim::Env,
+        metas: &meta::Env,
+        record: &Rc<Value>,
+        label: &Label,
+    ) -> Result<Option<Entry>, String> {
+        while let Some(entry) = self.first() {
+            match entry.label() {
+                Some(current_label) if current_label == label => return Ok(Some(entry)),
+                Some(current_label) => {
+                    let field = nbe::eval_record_elim(record.clone(), current_label)?;
+                    self.instantiate(prims, metas, field)?;
+                },
+                None => break,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Weaken the telescope into an environment of the given size, by
+    /// instantiating each of its entries with a fresh variable.
+    ///
+    /// Returns the entries, where the type of each entry is in an environment
+    /// extended by the entries before it, along with the type that remains.
+    pub fn weaken(
+        mut self,
+        prims: &prim::Env,
+        metas: &meta::Env,
+        size: var::Size,
+    ) -> Result<(Vec<Entry>, Rc<Type>), String> {
+        let mut entries = Vec::new();
+        let mut size = size;
+
+        while let Some(entry) = self.first() {
+            entries.push(entry);
+            self.instantiate(prims, metas, Rc::from(Value::var(size.next_level())))?;
+            size += 1;
+        }
+
+        Ok((entries, self.ty))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{Elim, Head};
+    use crate::literal::{LiteralIntro, LiteralType};
+    use crate::syntax::Term;
+
+    /// Evaluate `Record { A : Type; a : A }`.
+    fn sigma_ty() -> Rc<Type> {
+        let ty = Rc::from(Term::RecordType(vec![
+            (
+                DocString::from(""),
+                Label("A".to_owned()),
+                None,
+                Rc::from(Term::universe(0)),
+            ),
+            (
+                DocString::from(""),
+                Label("a".to_owned()),
+                None,
+                Rc::from(Term::var(0)),
+            ),
+        ]));
+        let values = var::Env::new();
+
+        nbe::eval_term(&prim::Env::new(), &meta::Env::new(), &values, &ty).unwrap()
+    }
+
+    #[test]
+    fn weaken_record() {
+        let telescope = Telescope::record(sigma_ty());
+        let (entries, rest) =
+            (telescope.weaken(&prim::Env::new(), &meta::Env::new(), 3.into())).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label(), Some(&Label("A".to_owned())));
+        assert_eq!(entries[0].ty, Rc::from(Value::universe(0)));
+        assert_eq!(entries[1].label(), Some(&Label("a".to_owned())));
+        assert_eq!(entries[1].ty, Rc::from(Value::var(3)));
+        assert_eq!(rest, Rc::from(Value::RecordTypeEmpty));
+    }
+
+    #[test]
+    fn split_record() {
+        let record = Rc::from(Value::var(0));
+        let label = Label("a".to_owned());
+        let mut telescope = Telescope::record(sigma_ty());
+        let entry = (telescope.split(&prim::Env::new(), &meta::Env::new(), &record, &label))
+            .unwrap()
+            .unwrap();
+
+        let field_ty = Value::Neutral(
+            Head::Var(var::Level(0)),
+            vec![Elim::Record(Label("A".to_owned()))],
+        );
+        assert_eq!(entry.ty, Rc::from(field_ty));
+        assert_eq!(telescope.first().unwrap().label(), Some(&label));
+    }
+
+    #[test]
+    fn split_record_missing_field() {
+        let record = Rc::from(Value::var(0));
+        let label = Label("b".to_owned());
+        let mut telescope = Telescope::record(sigma_ty());
+        let entry =
+            (telescope.split(&prim::Env::new(), &meta::Env::new(), &record, &label)).unwrap();
+
+        assert!(entry.is_none());
+        assert!(telescope.is_record_empty());
+    }
+
+    #[test]
+    fn instantiate_fun_prefix() {
+        // Fun (A : Type) (a : A) -> A
+        let ty = Rc::from(Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("A".to_owned()),
+            Rc::from(Term::universe(0)),
+            Rc::from(Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("a".to_owned()),
+                Rc::from(Term::var(0)),
+                Rc::from(Term::var(1)),
+            )),
+        ));
+        let prims = prim::Env::new();
+        let metas = meta::Env::new();
+        let ty = nbe::eval_term(&prims, &metas, &var::Env::new(), &ty).unwrap();
+
+        let mut telescope = Telescope::fun(ty);
+        let args = vec![
+            Rc::from(Value::literal_ty(LiteralType::S32)),
+            Rc::from(Value::literal_intro(LiteralIntro::S32(1))),
+        ];
+        telescope.instantiate_prefix(&prims, &metas, args).unwrap();
+
+        assert!(telescope.first().is_none());
+        assert!(!telescope.is_record_empty());
+        assert_eq!(
+            telescope.rest(),
+            &Rc::from(Value::literal_ty(LiteralType::S32))
+        );
+    }
+}
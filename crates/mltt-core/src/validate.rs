@@ -5,16 +5,24 @@
 //! this stage.
 
 use itertools::Itertools;
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_span::FileSpan;
 use std::error::Error;
 use std::fmt;
-use std::rc::Rc;
 
-use super::literal::{LiteralIntro, LiteralType};
-use crate::domain::{AppClosure, Type, Value};
+use super::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use crate::domain::{AppClosure, Head, Type, Value};
+use crate::signature::ModuleSignature;
 use crate::syntax::{Item, Module, Term};
-use crate::{meta, nbe, prim, var, AppMode, Label, UniverseLevel};
+use crate::telescope::{Entry, Telescope};
+use crate::{meta, nbe, prim, var, AppMode, Label, Rc, UniverseLevel};
 
 /// Local type checking context.
+///
+/// Rather than cloning the context each time we go under a binder, a single
+/// context is threaded through the checker by mutable reference. Entries are
+/// pushed when binders are entered, and popped again when we leave them using
+/// `Context::scope`.
 #[derive(Debug, Clone)]
 pub struct Context {
     /// Primitive entries.
@@ -23,6 +31,14 @@ pub struct Context {
     values: var::Env<Rc<Value>>,
     /// Types of the entries in the context.
     tys: var::Env<Rc<Type>>,
+    /// Whether universes are allowed to contain themselves.
+    type_in_type: bool,
+    /// The greatest universe level that can be represented.
+    max_universe: UniverseLevel,
+    /// The strategy to use when evaluating terms.
+    strategy: nbe::Strategy,
+    /// The greatest number of steps that a subtyping check can take.
+    conversion_budget: Option<u32>,
 }
 
 impl Context {
@@ -30,7 +46,15 @@ impl Context {
     ///
     /// We assume that the value and type environments are of the same length.
     pub fn new(prims: prim::Env, values: var::Env<Rc<Value>>, tys: var::Env<Rc<Type>>) -> Context {
-        Context { prims, values, tys }
+        Context {
+            prims,
+            values,
+            tys,
+            type_in_type: false,
+            max_universe: UniverseLevel::MAX,
+            strategy: nbe::Strategy::CallByValue,
+            conversion_budget: None,
+        }
     }
 
     /// Create a new, empty context.
@@ -48,6 +72,51 @@ impl Context {
         &self.values
     }
 
+    /// Set whether universes are allowed to contain themselves, ie.
+    /// `Type : Type`. This is disabled by default.
+    pub fn set_type_in_type(&mut self, type_in_type: bool) {
+        self.type_in_type = type_in_type;
+    }
+
+    /// Set the greatest universe level that can be represented. This is
+    /// `UniverseLevel::MAX` by default.
+    pub fn set_max_universe(&mut self, max_universe: UniverseLevel) {
+        self.max_universe = max_universe;
+    }
+
+    /// Set the strategy to use when evaluating terms. This is
+    /// `Strategy::CallByValue` by default.
+    pub fn set_strategy(&mut self, strategy: nbe::Strategy) {
+        self.strategy = strategy;
+    }
+
+    /// Set the greatest number of steps that a subtyping check can take before
+    /// giving up. This is unlimited by default.
+    pub fn set_conversion_budget(&mut self, conversion_budget: Option<u32>) {
+        self.conversion_budget = conversion_budget;
+    }
+
+    /// The number of entries in the context.
+    pub fn size(&self) -> var::Size {
+        self.values.size()
+    }
+
+    /// Remove any entries that were added after the context was at the given
+    /// size.
+    pub fn truncate(&mut self, size: var::Size) {
+        self.values.truncate(size);
+        self.tys.truncate(size);
+    }
+
+    /// Run `f` in a new scope, removing any entries that it added to the
+    /// context once it returns.
+    pub fn scope<T>(&mut self, f: impl FnOnce(&mut Context) -> T) -> T {
+        let size = self.size();
+        let result = f(self);
+        self.truncate(size);
+        result
+    }
+
     /// Lookup the type of a variable in the context.
     pub fn lookup_ty(&self, var_index: var::Index) -> Option<&Rc<Type>> {
         self.tys.lookup_entry(var_index)
@@ -80,9 +149,63 @@ impl Context {
         nbe::app_closure(self.prims(), metas, closure, arg).map_err(TypeError::Nbe)
     }
 
+    /// Instantiate the first entry of a telescope with a value.
+    pub fn instantiate_telescope(
+        &self,
+        metas: &meta::Env,
+        telescope: &mut Telescope,
+        arg: Rc<Value>,
+    ) -> Result<(), TypeError> {
+        (telescope.instantiate(self.prims(), metas, arg)).map_err(TypeError::Nbe)
+    }
+
+    /// Split a record telescope at the field with the given label.
+    pub fn split_telescope(
+        &self,
+        metas: &meta::Env,
+        telescope: &mut Telescope,
+        record: &Rc<Value>,
+        label: &Label,
+    ) -> Result<Option<Entry>, TypeError> {
+        (telescope.split(self.prims(), metas, record, label)).map_err(TypeError::Nbe)
+    }
+
     /// Evaluate a term using the evaluation environment.
     pub fn eval_term(&self, metas: &meta::Env, term: &Rc<Term>) -> Result<Rc<Value>, TypeError> {
-        nbe::eval_term(self.prims(), metas, self.values(), term).map_err(TypeError::Nbe)
+        nbe::eval_term_with_strategy(self.prims(), metas, self.strategy, self.values(), term)
+            .map_err(TypeError::Nbe)
+    }
+
+    /// Specialize the expected type of a literal elimination to one of its
+    /// clauses. If the scrutinee is a bound variable, its occurrences in the
+    /// type are replaced with the literal that the clause matches on.
+    pub fn refine_ty(
+        &self,
+        metas: &meta::Env,
+        scrutinee: &Term,
+        literal_intro: &LiteralIntro,
+        ty: &Rc<Type>,
+    ) -> Result<Rc<Type>, TypeError> {
+        let var_index = match scrutinee {
+            Term::Var(var_index) => *var_index,
+            _ => return Ok(ty.clone()),
+        };
+
+        match self.values.lookup_entry(var_index).map(Rc::as_ref) {
+            Some(Value::Neutral(Head::Var(var_level), spine))
+                if spine.is_empty() && self.size().index(*var_level) == var_index =>
+            {
+                let ty = nbe::read_back_value(self.prims(), metas, self.size(), ty)
+                    .map_err(TypeError::Nbe)?;
+                let mut values = self.values.clone();
+                let literal_value = Rc::from(Value::literal_intro(literal_intro.clone()));
+                values.set_entry(var_index, literal_value);
+
+                nbe::eval_term_with_strategy(self.prims(), metas, self.strategy, &values, &ty)
+                    .map_err(TypeError::Nbe)
+            },
+            _ => Ok(ty.clone()),
+        }
     }
 
     /// Expect that `ty1` is a subtype of `ty2` in the current context.
@@ -92,10 +215,14 @@ impl Context {
         ty1: &Rc<Type>,
         ty2: &Rc<Type>,
     ) -> Result<(), TypeError> {
-        if nbe::check_ty(self.prims(), metas, self.values().size(), true, ty1, ty2)
+        let mut budget = nbe::ConversionBudget::new(self.conversion_budget);
+        let size = self.values().size();
+        if nbe::check_ty_with_budget(self.prims(), metas, size, true, &mut budget, ty1, ty2)
             .map_err(TypeError::Nbe)?
         {
             Ok(())
+        } else if let (true, Some(limit)) = (budget.is_exceeded(), budget.limit()) {
+            Err(TypeError::ConversionBudgetExceeded(limit))
         } else {
             Err(TypeError::ExpectedSubtype(ty1.clone(), ty2.clone()))
         }
@@ -109,20 +236,22 @@ pub enum TypeError {
     AlreadyDefined(Label),
     ExpectedFunType { found: Rc<Type> },
     ExpectedPairType { found: Rc<Type> },
+    ExpectedArrayType { found: Rc<Type> },
     ExpectedUniverse { found: Rc<Type> },
     ExpectedSubtype(Rc<Type>, Rc<Type>),
+    ConversionBudgetExceeded(u32),
     AmbiguousTerm(Rc<Term>),
     UnboundVariable(var::Index),
     UnboundMeta(meta::Index),
     UnsolvedMeta(meta::Index),
     UnknownPrim(prim::Name),
-    BadLiteralPatterns(Vec<LiteralIntro>),
+    BadLiteralPatterns(Vec<LiteralPattern>),
     NoFieldInType(Label),
     UnexpectedField { found: Label, expected: Label },
     UnexpectedAppMode { found: AppMode, expected: AppMode },
     TooManyFieldsFound,
     NotEnoughFieldsProvided,
-    OverflowingUniverseLevel,
+    OverflowingUniverseLevel(UniverseLevel),
     Nbe(String),
 }
 
@@ -135,17 +264,21 @@ impl fmt::Display for TypeError {
             TypeError::AlreadyDefined(label) => write!(f, "already defined: {}", label),
             TypeError::ExpectedFunType { .. } => write!(f, "expected function type"),
             TypeError::ExpectedPairType { .. } => write!(f, "expected function type"),
+            TypeError::ExpectedArrayType { .. } => write!(f, "expected array type"),
             TypeError::ExpectedUniverse { .. } => write!(f, "expected universe"),
             TypeError::ExpectedSubtype(..) => write!(f, "not a subtype"),
+            TypeError::ConversionBudgetExceeded(limit) => {
+                write!(f, "conversion check exceeded budget of {} steps", limit)
+            },
             TypeError::AmbiguousTerm(..) => write!(f, "could not infer the type"),
             TypeError::UnboundVariable(index) => write!(f, "unbound variable: {}", index),
             TypeError::UnboundMeta(level) => write!(f, "unbound metavariable: `{}`", level),
             TypeError::UnsolvedMeta(level) => write!(f, "unsolved metavariable `{}`", level),
             TypeError::UnknownPrim(name) => write!(f, "unbound primitive: {}", name),
-            TypeError::BadLiteralPatterns(literal_intros) => write!(
+            TypeError::BadLiteralPatterns(literal_patterns) => write!(
                 f,
-                "literal patterns are not sorted properly: {}",
-                literal_intros.iter().format(", "),
+                "literal patterns are not sorted and disjoint: {}",
+                literal_patterns.iter().format(", "),
             ),
             TypeError::NoFieldInType(label) => write!(f, "no field in type `{}`", label),
             TypeError::UnexpectedField { found, expected } => write!(
@@ -160,37 +293,67 @@ impl fmt::Display for TypeError {
             ),
             TypeError::TooManyFieldsFound => write!(f, "too many fields found"),
             TypeError::NotEnoughFieldsProvided => write!(f, "not enough fields provided"),
-            TypeError::OverflowingUniverseLevel => write!(
+            TypeError::OverflowingUniverseLevel(max_universe) => write!(
                 f,
                 "cannot represent universes greater than `{}`",
-                UniverseLevel::MAX,
+                max_universe,
             ),
             TypeError::Nbe(err) => err.fmt(f),
         }
     }
 }
 
+impl TypeError {
+    /// Convert the error into a diagnostic, labelled with the span of the
+    /// term that was being validated, if it is known.
+    ///
+    /// Errors found during validation indicate a bug in the elaborator, so
+    /// they are reported as such.
+    pub fn to_diagnostic(&self, span: impl Into<Option<FileSpan>>) -> Diagnostic<FileSpan> {
+        match span.into() {
+            None => Diagnostic::new_bug(format!("failed to validate core term: {}", self)),
+            Some(span) => Diagnostic::new_bug("failed to validate core term")
+                .with_label(DiagnosticLabel::new_primary(span).with_message(self.to_string())),
+        }
+    }
+}
+
+impl From<TypeError> for Diagnostic<FileSpan> {
+    fn from(src: TypeError) -> Diagnostic<FileSpan> {
+        src.to_diagnostic(None)
+    }
+}
+
 /// Check that this is a valid module.
+///
+/// Returns the signature of the module.
 pub fn check_module(
     context: &Context,
     metas: &meta::Env,
     module: &Module,
-) -> Result<(), TypeError> {
+) -> Result<ModuleSignature, TypeError> {
     let mut context = context.clone();
-    check_items(&mut context, metas, &module.items)?;
-    Ok(())
+    check_items(&mut context, metas, &module.items)
 }
 
 /// Check the given items and add them to the context.
-fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Result<(), TypeError> {
+///
+/// Returns the signature of the items.
+fn check_items(
+    context: &mut Context,
+    metas: &meta::Env,
+    items: &[Item],
+) -> Result<ModuleSignature, TypeError> {
     // Declarations that may be waiting to be defined
     let mut forward_declarations = im::HashMap::new();
+    // The signature of the checked items
+    let mut signature = ModuleSignature::new();
 
     for item in items {
         use im::hashmap::Entry;
 
         match item {
-            Item::Declaration(_, label, term_ty) => {
+            Item::Declaration(doc, label, term_ty) => {
                 log::trace!("checking declaration:\t{}\t= {:?}", label, term_ty);
 
                 match forward_declarations.entry(&label.0) {
@@ -198,11 +361,12 @@ fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Resu
                     // go-ahead and type check, elaborate, and then add it to
                     // the context
                     Entry::Vacant(entry) => {
-                        synth_universe(&context, metas, &term_ty)?;
+                        synth_universe(context, metas, term_ty)?;
                         // Ensure that we evaluate the forward declaration in
                         // the current context - if we wait until later more
                         // definitions might have come in to scope!
-                        let term_ty = context.eval_term(metas, &term_ty)?;
+                        let term_ty = context.eval_term(metas, term_ty)?;
+                        signature.add_declaration(doc.clone(), label.clone(), term_ty.clone());
                         entry.insert(Some(term_ty));
                     },
                     // There's a declaration for this name already pending - we
@@ -212,14 +376,14 @@ fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Resu
 
                 log::trace!("validated declaration:\t{}", label);
             },
-            Item::Definition(_, label, term) => {
+            Item::Definition(doc, label, term) => {
                 log::trace!("checking definition:\t{}\t= {:?}", label, term);
 
                 let (term, ty) = match forward_declarations.entry(&label.0) {
                     // No prior declaration was found, so we'll try synthesizing
                     // its type instead
                     Entry::Vacant(entry) => {
-                        let term_ty = synth_term(&context, metas, term)?;
+                        let term_ty = synth_term(context, metas, term)?;
                         entry.insert(None);
                         (term, term_ty)
                     },
@@ -229,7 +393,7 @@ fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Resu
                         // We found a prior declaration, so we'll use it as a
                         // basis for checking the definition
                         Some(term_ty) => {
-                            check_term(&context, metas, term, &term_ty)?;
+                            check_term(context, metas, term, &term_ty)?;
                             (term, term_ty)
                         },
                         // This declaration was already given a definition, so
@@ -244,12 +408,39 @@ fn check_items(context: &mut Context, metas: &meta::Env, items: &[Item]) -> Resu
 
                 log::trace!("validated definition:\t{}", label);
 
-                let value = context.eval_term(metas, &term)?;
+                let value = context.eval_term(metas, term)?;
+                signature.add_definition(doc.clone(), label.clone(), ty.clone(), value.clone());
                 context.add_defn(value, ty);
             },
         }
     }
 
+    Ok(signature)
+}
+
+/// Check that the clauses of a literal elimination are sorted by their
+/// patterns, and that the patterns don't overlap.
+pub(crate) fn check_literal_patterns(
+    clauses: &[(LiteralPattern, Rc<Term>)],
+) -> Result<(), TypeError> {
+    let is_empty_range = |pattern: &LiteralPattern| match pattern {
+        LiteralPattern::Range(start, end) => start > end,
+        LiteralPattern::Intro(_) | LiteralPattern::Prefix(_) => false,
+    };
+
+    // TODO: use `Iterator::is_sorted_by` when it is stable
+    if clauses.iter().any(|(l, _)| is_empty_range(l))
+        || clauses
+            .iter()
+            .tuple_windows()
+            // FIXME: Floating point equality?
+            .any(|((l1, _), (l2, _))| l1.start() >= l2.start() || l1.overlaps(l2))
+    {
+        return Err(TypeError::BadLiteralPatterns(
+            clauses.iter().map(|(l, _)| l.clone()).collect(),
+        ));
+    }
+
     Ok(())
 }
 
@@ -263,6 +454,28 @@ pub fn check_literal(
     context.check_subtype(metas, &synth_literal(literal_intro), expected_ty)
 }
 
+/// Check that the literals matched by a pattern conform to a given type.
+pub fn check_literal_pattern(
+    context: &Context,
+    metas: &meta::Env,
+    literal_pattern: &LiteralPattern,
+    expected_ty: &Rc<Type>,
+) -> Result<(), TypeError> {
+    match literal_pattern {
+        LiteralPattern::Intro(literal_intro) => {
+            check_literal(context, metas, literal_intro, expected_ty)
+        },
+        LiteralPattern::Range(start, end) => {
+            check_literal(context, metas, start, expected_ty)?;
+            check_literal(context, metas, end, expected_ty)
+        },
+        LiteralPattern::Prefix(_) => {
+            let string_ty = Rc::from(Value::literal_ty(LiteralType::String));
+            context.check_subtype(metas, &string_ty, expected_ty)
+        },
+    }
+}
+
 /// Synthesize the type of the literal.
 pub fn synth_literal(literal_intro: &LiteralIntro) -> Rc<Type> {
     Rc::from(Value::literal_ty(match literal_intro {
@@ -284,7 +497,7 @@ pub fn synth_literal(literal_intro: &LiteralIntro) -> Rc<Type> {
 
 /// Ensures that the given term is a universe, returning the level of that universe.
 pub fn synth_universe(
-    context: &Context,
+    context: &mut Context,
     metas: &meta::Env,
     term: &Rc<Term>,
 ) -> Result<UniverseLevel, TypeError> {
@@ -297,7 +510,7 @@ pub fn synth_universe(
 
 /// Check that a term conforms to a given type.
 pub fn check_term(
-    context: &Context,
+    context: &mut Context,
     metas: &meta::Env,
     term: &Rc<Term>,
     expected_ty: &Rc<Type>,
@@ -307,47 +520,47 @@ pub fn check_term(
     match term.as_ref() {
         Term::Prim(prim_name) => match context.prims().lookup_entry(prim_name) {
             None => Err(TypeError::UnknownPrim(prim_name.clone())),
-            Some(_) => Ok(()),
-        },
-        Term::Let(items, body) => {
-            let mut context = context.clone();
-            check_items(&mut context, metas, items)?;
-            check_term(&context, metas, body, expected_ty)
+            Some(prim::Entry { ty: None, .. }) => Ok(()),
+            Some(prim::Entry { ty: Some(ty), .. }) => {
+                let ty = context.eval_term(metas, ty)?;
+                context.check_subtype(metas, &ty, expected_ty)
+            },
         },
+        Term::Let(items, body) => context.scope(|context| {
+            check_items(context, metas, items)?;
+            check_term(context, metas, body, expected_ty)
+        }),
 
         Term::LiteralElim(scrutinee, clauses, default_body) => {
             let scrutinee_ty = synth_term(context, metas, scrutinee)?;
+            check_literal_patterns(clauses)?;
 
-            // Check that the clauses are sorted by patterns and that patterns aren't duplicated
-            // TODO: use `Iterator::is_sorted_by` when it is stable
-            if clauses
-                .iter()
-                .tuple_windows()
-                // FIXME: Floating point equality?
-                .any(|((l1, _), (l2, _))| l1 >= l2)
-            {
-                return Err(TypeError::BadLiteralPatterns(
-                    clauses.iter().map(|(l, _)| l.clone()).collect(),
-                ));
-            }
-
-            for (literal_intro, body) in clauses.iter() {
-                check_literal(context, metas, literal_intro, &scrutinee_ty)?;
-                check_term(context, metas, body, &expected_ty)?;
+            for (literal_pattern, body) in clauses.iter() {
+                check_literal_pattern(context, metas, literal_pattern, &scrutinee_ty)?;
+                let body_ty = match literal_pattern {
+                    LiteralPattern::Intro(literal_intro) => {
+                        context.refine_ty(metas, scrutinee, literal_intro, expected_ty)?
+                    },
+                    LiteralPattern::Range(_, _) | LiteralPattern::Prefix(_) => expected_ty.clone(),
+                };
+                check_term(context, metas, body, &body_ty)?;
             }
 
             check_term(context, metas, default_body, expected_ty)
         },
 
         Term::FunIntro(intro_app_mode, _, body) => match expected_ty.as_ref() {
-            Value::FunType(ty_app_mode, _, param_ty, body_ty) if intro_app_mode == ty_app_mode => {
-                let mut body_context = context.clone();
-                let param = body_context.add_param(param_ty.clone());
-                let body_ty = context.app_closure(metas, body_ty, param)?;
+            Value::FunType(_, ty_app_mode, _, param_ty, body_ty)
+                if intro_app_mode == ty_app_mode =>
+            {
+                context.scope(|context| {
+                    let param = context.add_param(param_ty.clone());
+                    let body_ty = context.app_closure(metas, body_ty, param)?;
 
-                check_term(&body_context, metas, body, &body_ty)
+                    check_term(context, metas, body, &body_ty)
+                })
             },
-            Value::FunType(ty_app_mode, _, _, _) => Err(TypeError::UnexpectedAppMode {
+            Value::FunType(_, ty_app_mode, _, _, _) => Err(TypeError::UnexpectedAppMode {
                 found: intro_app_mode.clone(),
                 expected: ty_app_mode.clone(),
             }),
@@ -356,36 +569,46 @@ pub fn check_term(
             }),
         },
 
-        Term::RecordIntro(intro_fields) => {
-            let mut context = context.clone();
-            let mut expected_ty = expected_ty.clone();
+        Term::RecordIntro(intro_fields) => context.scope(|context| {
+            let mut telescope = Telescope::record(expected_ty.clone());
 
             for (label, term) in intro_fields {
-                if let Value::RecordTypeExtend(_, expected_label, _, expected_term_ty, rest) =
-                    expected_ty.as_ref()
-                {
-                    if label != expected_label {
+                let entry = telescope.first().ok_or(TypeError::TooManyFieldsFound)?;
+                match entry.label() {
+                    Some(expected_label) if label == expected_label => {},
+                    Some(expected_label) => {
                         return Err(TypeError::UnexpectedField {
                             found: label.clone(),
                             expected: expected_label.clone(),
                         });
-                    }
+                    },
+                    None => return Err(TypeError::TooManyFieldsFound),
+                }
 
-                    check_term(&context, metas, term, expected_term_ty)?;
-                    let term_value = context.eval_term(metas, term)?;
+                check_term(context, metas, term, &entry.ty)?;
+                let term_value = context.eval_term(metas, term)?;
 
-                    context.add_defn(term_value.clone(), expected_term_ty.clone());
-                    expected_ty = context.app_closure(metas, &rest, term_value)?;
-                } else {
-                    return Err(TypeError::TooManyFieldsFound);
-                }
+                context.add_defn(term_value.clone(), entry.ty);
+                context.instantiate_telescope(metas, &mut telescope, term_value)?;
             }
 
-            if let Value::RecordTypeEmpty = expected_ty.as_ref() {
+            if telescope.is_record_empty() {
                 Ok(())
             } else {
                 Err(TypeError::NotEnoughFieldsProvided)
             }
+        }),
+
+        Term::ArrayIntro(elems) => match expected_ty.as_ref() {
+            Value::ArrayType(elem_ty) => {
+                for elem in elems {
+                    check_term(context, metas, elem, elem_ty)?;
+                }
+                Ok(())
+            },
+            _ => Err(TypeError::ExpectedArrayType {
+                found: expected_ty.clone(),
+            }),
         },
 
         _ => {
@@ -395,9 +618,21 @@ pub fn check_term(
     }
 }
 
+/// Find the metavariable at the head of a spine of bound variables.
+fn meta_spine_head(term: &Term) -> Option<meta::Index> {
+    match term {
+        Term::Meta(meta_index) => Some(*meta_index),
+        Term::FunElim(fun, AppMode::Explicit, arg) => match arg.as_ref() {
+            Term::Var(_) => meta_spine_head(fun),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Synthesize the type of the term.
 pub fn synth_term(
-    context: &Context,
+    context: &mut Context,
     metas: &meta::Env,
     term: &Rc<Term>,
 ) -> Result<Rc<Type>, TypeError> {
@@ -417,47 +652,77 @@ pub fn synth_term(
         },
         Term::Prim(prim_name) => match context.prims().lookup_entry(prim_name) {
             None => Err(TypeError::UnknownPrim(prim_name.clone())),
-            Some(_) => Err(TypeError::AmbiguousTerm(term.clone())),
+            Some(prim::Entry { ty: None, .. }) => Err(TypeError::AmbiguousTerm(term.clone())),
+            Some(prim::Entry { ty: Some(ty), .. }) => context.eval_term(metas, ty),
         },
 
         Term::Ann(term, term_ty) => {
             synth_universe(context, metas, term_ty)?;
-            let term_ty = context.eval_term(metas, &term_ty)?;
+            let term_ty = context.eval_term(metas, term_ty)?;
             check_term(context, metas, term, &term_ty)?;
             Ok(term_ty)
         },
-        Term::Let(items, body) => {
-            let mut context = context.clone();
-            check_items(&mut context, metas, items)?;
-            synth_term(&context, metas, body)
-        },
+        Term::Let(items, body) => context.scope(|context| {
+            check_items(context, metas, items)?;
+            synth_term(context, metas, body)
+        }),
 
         Term::LiteralType(_) => Ok(Rc::from(Value::universe(0))),
         Term::LiteralIntro(literal_intro) => Ok(synth_literal(literal_intro)),
-        Term::LiteralElim(_, _, _) => Err(TypeError::AmbiguousTerm(term.clone())),
+        Term::LiteralElim(scrutinee, clauses, default_body) => {
+            let scrutinee_ty = synth_term(context, metas, scrutinee)?;
+            check_literal_patterns(clauses)?;
+
+            // The clauses should conform to the type of the default clause, or
+            // if they are all types, we use the largest of their universes
+            let mut ty = synth_term(context, metas, default_body)?;
+            for (literal_pattern, body) in clauses.iter() {
+                check_literal_pattern(context, metas, literal_pattern, &scrutinee_ty)?;
+                let body_ty = synth_term(context, metas, body)?;
+                ty = match (body_ty.as_ref(), ty.as_ref()) {
+                    (Value::Universe(level1), Value::Universe(level2)) => {
+                        Rc::from(Value::universe(cmp::max(*level1, *level2)))
+                    },
+                    (_, _) => {
+                        context.check_subtype(metas, &body_ty, &ty)?;
+                        ty
+                    },
+                };
+            }
 
-        Term::FunType(_app_mode, _, param_ty, body_ty) => {
+            Ok(ty)
+        },
+
+        Term::FunType(_, _app_mode, _, param_ty, body_ty) => {
             let param_level = synth_universe(context, metas, param_ty)?;
             let param_ty_value = context.eval_term(metas, param_ty)?;
 
-            let mut body_ty_context = context.clone();
-            body_ty_context.add_param(param_ty_value);
-
-            let body_level = synth_universe(&body_ty_context, metas, body_ty)?;
+            let body_level = context.scope(|context| {
+                context.add_param(param_ty_value);
+                synth_universe(context, metas, body_ty)
+            })?;
 
             Ok(Rc::from(Value::universe(cmp::max(param_level, body_level))))
         },
         Term::FunIntro(_, _, _) => Err(TypeError::AmbiguousTerm(term.clone())),
 
         Term::FunElim(fun, arg_app_mode, arg) => {
+            // Metavariables are applied to the variables that were bound when
+            // they were created, and are recorded with the type of the result
+            if let Some(meta_index) = meta_spine_head(term) {
+                return synth_term(context, metas, &Rc::from(Term::Meta(meta_index)));
+            }
+
             let fun_ty = synth_term(context, metas, fun)?;
             match fun_ty.as_ref() {
-                Value::FunType(ty_app_mode, _, arg_ty, body_ty) if arg_app_mode == ty_app_mode => {
+                Value::FunType(_, ty_app_mode, _, arg_ty, body_ty)
+                    if arg_app_mode == ty_app_mode =>
+                {
                     check_term(context, metas, arg, arg_ty)?;
                     let arg_value = context.eval_term(metas, arg)?;
                     Ok(context.app_closure(metas, body_ty, arg_value)?)
                 },
-                Value::FunType(ty_app_mode, _, _, _) => Err(TypeError::UnexpectedAppMode {
+                Value::FunType(_, ty_app_mode, _, _, _) => Err(TypeError::UnexpectedAppMode {
                     found: arg_app_mode.clone(),
                     expected: ty_app_mode.clone(),
                 }),
@@ -467,18 +732,18 @@ pub fn synth_term(
             }
         },
 
-        Term::RecordType(ty_fields) => {
-            let mut context = context.clone();
+        Term::RecordType(ty_fields) => context.scope(|context| {
             let mut max_level = UniverseLevel(0);
 
             for (_, _, _, ty) in ty_fields {
-                let ty_level = synth_universe(&context, metas, &ty)?;
-                context.add_param(context.eval_term(metas, &ty)?);
+                let ty_level = synth_universe(context, metas, ty)?;
+                let ty_value = context.eval_term(metas, ty)?;
+                context.add_param(ty_value);
                 max_level = cmp::max(max_level, ty_level);
             }
 
             Ok(Rc::from(Value::universe(max_level)))
-        },
+        }),
         Term::RecordIntro(intro_fields) => {
             if intro_fields.is_empty() {
                 Ok(Rc::from(Value::RecordTypeEmpty))
@@ -487,27 +752,37 @@ pub fn synth_term(
             }
         },
         Term::RecordElim(record, label) => {
-            let mut record_ty = synth_term(context, metas, record)?;
+            let mut telescope = Telescope::record(synth_term(context, metas, record)?);
+            let record = context.eval_term(metas, record)?;
 
-            while let Value::RecordTypeExtend(_, current_label, _, current_ty, rest) =
-                record_ty.as_ref()
-            {
-                if label == current_label {
-                    return Ok(current_ty.clone());
-                } else {
-                    let label = current_label.clone();
-                    let expr = Rc::from(Term::RecordElim(record.clone(), label));
-                    record_ty =
-                        context.app_closure(metas, rest, context.eval_term(metas, &expr)?)?;
-                }
+            match context.split_telescope(metas, &mut telescope, &record, label)? {
+                Some(entry) => Ok(entry.ty),
+                None => Err(TypeError::NoFieldInType(label.clone())),
             }
+        },
 
-            Err(TypeError::NoFieldInType(label.clone()))
+        Term::ArrayType(elem_ty) => {
+            let elem_level = synth_universe(context, metas, elem_ty)?;
+            Ok(Rc::from(Value::universe(elem_level)))
+        },
+        Term::ArrayIntro(elems) => match elems.split_first() {
+            // The other elements should conform to the type of the first
+            Some((first, rest)) => {
+                let elem_ty = synth_term(context, metas, first)?;
+                for elem in rest {
+                    check_term(context, metas, elem, &elem_ty)?;
+                }
+                Ok(Rc::from(Value::ArrayType(elem_ty)))
+            },
+            None => Err(TypeError::AmbiguousTerm(term.clone())),
         },
 
-        Term::Universe(level) => match level.shift(1) {
-            None => Err(TypeError::OverflowingUniverseLevel),
-            Some(level) => Ok(Rc::from(Value::universe(level))),
+        Term::Universe(level) => {
+            let shift = if context.type_in_type { 0 } else { 1 };
+            match level.shift_within(shift, context.max_universe) {
+                None => Err(TypeError::OverflowingUniverseLevel(context.max_universe)),
+                Some(level) => Ok(Rc::from(Value::universe(level))),
+            }
         },
     }
 }
@@ -536,4 +811,199 @@ mod test {
         assert_eq!(context.lookup_ty(var::Index(1)).unwrap(), &ty2);
         assert_eq!(context.lookup_ty(var::Index(0)).unwrap(), &ty3);
     }
+
+    #[test]
+    fn record_intro_dependent_fields() {
+        let mut context = Context::empty();
+        let metas = meta::Env::new();
+        let u8_ty = Rc::from(Term::literal_ty(LiteralType::U8));
+        let label = |name: &str| Label(name.to_owned());
+        let one = Rc::from(Term::literal_intro(LiteralIntro::U8(1)));
+
+        // record { a = 1; b = a } : Record { a : U8; b : U8 }
+        let term = Rc::from(Term::RecordIntro(vec![
+            (label("a"), one.clone()),
+            (label("b"), Rc::from(Term::var(0))),
+        ]));
+        let ty = Rc::from(Term::RecordType(vec![
+            (Rc::from(""), label("a"), None, u8_ty.clone()),
+            (Rc::from(""), label("b"), None, u8_ty),
+        ]));
+        let ty = context.eval_term(&metas, &ty).unwrap();
+
+        check_term(&mut context, &metas, &term, &ty).unwrap();
+
+        // Later fields are evaluated in the scope of the earlier fields
+        let value = context.eval_term(&metas, &term).unwrap();
+        let one_value = Rc::from(Value::literal_intro(LiteralIntro::U8(1)));
+        match value.as_ref() {
+            Value::RecordIntro(fields) => assert_eq!(fields[1], (label("b"), one_value)),
+            value => panic!("expected a record, found: {:?}", value),
+        }
+
+        let size = context.values().size();
+        let term = nbe::read_back_value(context.prims(), &metas, size, &value).unwrap();
+        let expected = Rc::from(Term::RecordIntro(vec![
+            (label("a"), one.clone()),
+            (label("b"), one),
+        ]));
+        assert_eq!(term, expected);
+    }
+
+    #[test]
+    fn check_subtype_budget() {
+        let metas = meta::Env::new();
+        let mut context = Context::empty();
+
+        // Fun (A : Type) -> A -> A
+        let ty = Rc::from(Value::FunType(
+            "".into(),
+            AppMode::Explicit,
+            Some("A".to_owned()),
+            Rc::from(Value::universe(0)),
+            AppClosure::new(
+                Rc::from(Term::FunType(
+                    "".into(),
+                    AppMode::Explicit,
+                    None,
+                    Rc::from(Term::var(0)),
+                    Rc::from(Term::var(1)),
+                )),
+                var::Env::new(),
+                nbe::Strategy::CallByValue,
+            ),
+        ));
+
+        assert_eq!(context.check_subtype(&metas, &ty, &ty), Ok(()));
+
+        context.set_conversion_budget(Some(2));
+        assert_eq!(
+            context.check_subtype(&metas, &ty, &ty),
+            Err(TypeError::ConversionBudgetExceeded(2)),
+        );
+
+        context.set_conversion_budget(Some(5));
+        assert_eq!(context.check_subtype(&metas, &ty, &ty), Ok(()));
+    }
+
+    #[test]
+    fn scope_removes_entries() {
+        let mut context = Context::empty();
+
+        let ty1 = Rc::from(Value::universe(0));
+        let ty2 = Rc::from(Value::universe(1));
+
+        context.add_param(ty1.clone());
+        let result: Result<(), TypeError> = context.scope(|context| {
+            context.add_param(ty2.clone());
+            context.add_param(ty2.clone());
+            assert_eq!(context.size(), var::Size(3));
+            Err(TypeError::TooManyFieldsFound)
+        });
+
+        assert!(result.is_err());
+        assert_eq!(context.size(), var::Size(1));
+        assert_eq!(context.lookup_ty(var::Index(0)).unwrap(), &ty1);
+        assert_eq!(context.lookup_ty(var::Index(1)), None);
+    }
+
+    #[test]
+    fn refine_ty_on_params() {
+        let mut context = Context::empty();
+        let metas = meta::Env::new();
+
+        context.add_param(Rc::from(Value::literal_ty(LiteralType::U32)));
+        let zero = LiteralIntro::U32(0);
+        // case n { 0 => String; _ => U32 }
+        let ty = context
+            .eval_term(
+                &metas,
+                &Rc::from(Term::LiteralElim(
+                    Rc::from(Term::var(0)),
+                    Rc::from(vec![(
+                        LiteralPattern::from(zero.clone()),
+                        Rc::from(Term::LiteralType(LiteralType::String)),
+                    )]),
+                    Rc::from(Term::LiteralType(LiteralType::U32)),
+                )),
+            )
+            .unwrap();
+
+        let refined_ty = context
+            .refine_ty(&metas, &Term::var(0), &zero, &ty)
+            .unwrap();
+        assert_eq!(refined_ty, Rc::from(Value::literal_ty(LiteralType::String)));
+
+        // Only variables can be refined
+        let scrutinee = Term::literal_intro(zero.clone());
+        let refined_ty = context.refine_ty(&metas, &scrutinee, &zero, &ty).unwrap();
+        assert_eq!(refined_ty, ty);
+    }
+
+    #[test]
+    fn type_error_to_diagnostic() {
+        use language_reporting::Severity;
+        use mltt_span::Files;
+
+        let error = TypeError::NoFieldInType(Label("x".to_owned()));
+
+        let diagnostic = Diagnostic::from(error.clone());
+        assert_eq!(diagnostic.severity, Severity::Bug);
+        assert_eq!(
+            diagnostic.message,
+            "failed to validate core term: no field in type `x`",
+        );
+        assert!(diagnostic.labels.is_empty());
+
+        let file_id = Files::new().add("test", "r.x");
+        let span = FileSpan::new(file_id, 0, 3);
+        let diagnostic = error.to_diagnostic(span);
+        assert_eq!(diagnostic.message, "failed to validate core term");
+        assert_eq!(diagnostic.labels.len(), 1);
+        assert_eq!(
+            diagnostic.labels[0].message.as_deref(),
+            Some("no field in type `x`"),
+        );
+    }
+
+    #[test]
+    fn max_universe() {
+        let metas = meta::Env::new();
+        let mut context = Context::empty();
+        context.set_max_universe(UniverseLevel(2));
+
+        let term = Rc::from(Term::universe(1));
+        assert_eq!(
+            synth_term(&mut context, &metas, &term),
+            Ok(Rc::from(Value::universe(2))),
+        );
+
+        let term = Rc::from(Term::universe(2));
+        assert_eq!(
+            synth_term(&mut context, &metas, &term),
+            Err(TypeError::OverflowingUniverseLevel(UniverseLevel(2))),
+        );
+
+        context.set_type_in_type(true);
+        assert_eq!(
+            synth_term(&mut context, &metas, &term),
+            Ok(Rc::from(Value::universe(2))),
+        );
+    }
+
+    #[test]
+    fn prim_signatures() {
+        let metas = meta::Env::new();
+        let mut context = Context::new(prim::Env::default(), var::Env::new(), var::Env::new());
+        let u8_ty = Rc::from(Value::literal_ty(LiteralType::U8));
+        let bool_ty = Rc::from(Value::literal_ty(LiteralType::Bool));
+        let u8_max = Rc::from(Term::prim("u8-max"));
+        let abort = Rc::from(Term::prim("abort"));
+
+        assert_eq!(synth_term(&mut context, &metas, &u8_max), Ok(u8_ty.clone()));
+        assert!(check_term(&mut context, &metas, &u8_max, &u8_ty).is_ok());
+        assert!(check_term(&mut context, &metas, &u8_max, &bool_ty).is_err());
+        // Primitives without a signature can be used at any type
+        assert!(check_term(&mut context, &metas, &abort, &bool_ty).is_ok());
+    }
 }
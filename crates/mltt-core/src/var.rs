@@ -11,6 +11,12 @@ pub struct Env<Entry: Clone> {
     entries: im::Vector<Entry>,
 }
 
+impl<Entry: Clone> Default for Env<Entry> {
+    fn default() -> Env<Entry> {
+        Env::new()
+    }
+}
+
 impl<Entry: Clone> Env<Entry> {
     /// Create a new, empty environment.
     pub fn new() -> Env<Entry> {
@@ -34,13 +40,27 @@ impl<Entry: Clone> Env<Entry> {
         self.entries.push_front(entry);
     }
 
+    /// Replace an entry in the environment.
+    pub fn set_entry(&mut self, index: Index, entry: Entry) {
+        self.entries.set(index.0 as usize, entry);
+    }
+
+    /// Remove the most recently added entries, until the environment is
+    /// back to the given size.
+    pub fn truncate(&mut self, size: Size) {
+        let excess = self.entries.len().saturating_sub(size.0 as usize);
+        for _ in 0..excess {
+            self.entries.pop_front();
+        }
+    }
+
     pub fn entries(&self) -> &im::Vector<Entry> {
         &self.entries
     }
 }
 
 /// The size of the environment.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Size(pub u32);
 
 impl Size {
@@ -53,6 +73,11 @@ impl Size {
     pub fn index(self, level: Level) -> Index {
         Index(self.0 - (level.0 + 1)) // FIXME: Check for over/underflow?
     }
+
+    /// Convert a variable index in the current environment to a variable level.
+    pub fn level(self, index: Index) -> Level {
+        Level(self.0 - (index.0 + 1)) // FIXME: Check for over/underflow?
+    }
 }
 
 impl From<u32> for Size {
@@ -0,0 +1,191 @@
+//! Checking of the attributes attached to items.
+//!
+//! This is where attributes are dispatched to the parts of the elaborator that
+//! understand them. Any attributes that are not recognised are reported as
+//! errors, so that typos don't silently change the meaning of a program.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::Attribute;
+use mltt_core::nbe::Strategy;
+use mltt_span::FileSpan;
+
+use crate::LanguageOptions;
+
+/// Attributes that can be attached to declarations and definitions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ItemAttribute {
+    /// `#[inline]`: specialize calls to the definition during elaboration,
+    /// leaving the residual terms in the elaborated output.
+    Inline,
+    /// `#[no_positivity_check]`: skip the strict positivity check for the
+    /// item. This is currently accepted for forward compatibility, as there are
+    /// no user-defined inductive types to check yet.
+    NoPositivityCheck,
+    /// `#[from_string]`: allow string literals to be elaborated to other types,
+    /// using the `from-string` field of the item, which should be a record.
+    FromString,
+}
+
+impl ItemAttribute {
+    /// Look up an item attribute by name.
+    fn from_name(name: &str) -> Option<ItemAttribute> {
+        match name {
+            "inline" => Some(ItemAttribute::Inline),
+            "no_positivity_check" => Some(ItemAttribute::NoPositivityCheck),
+            "from_string" => Some(ItemAttribute::FromString),
+            _ => None,
+        }
+    }
+}
+
+/// Check the attributes attached to an item, returning the ones that the
+/// elaborator understands.
+pub fn check_item_attributes(
+    attributes: &[Attribute<'_>],
+) -> Result<Vec<ItemAttribute>, Diagnostic<FileSpan>> {
+    let mut item_attributes = Vec::with_capacity(attributes.len());
+
+    for (index, attribute) in attributes.iter().enumerate() {
+        let name = attribute.name.slice;
+        let item_attribute = ItemAttribute::from_name(name).ok_or_else(|| {
+            Diagnostic::new_error(format!("unknown attribute `{}`", name)).with_label(
+                DiagnosticLabel::new_primary(attribute.name.span())
+                    .with_message("unknown attribute"),
+            )
+        })?;
+
+        if let Some(arg) = attribute.args.first() {
+            return Err(Diagnostic::new_error(format!(
+                "the `{}` attribute does not take any arguments",
+                name,
+            ))
+            .with_label(
+                DiagnosticLabel::new_primary(arg.span()).with_message("unexpected argument"),
+            ));
+        }
+
+        if let Some(prev) = attributes[..index]
+            .iter()
+            .find(|prev| prev.name.slice == name)
+        {
+            return Err(
+                Diagnostic::new_error(format!("duplicate attribute `{}`", name))
+                    .with_label(DiagnosticLabel::new_primary(attribute.span))
+                    .with_label(
+                        DiagnosticLabel::new_secondary(prev.span)
+                            .with_message("previously specified here"),
+                    ),
+            );
+        }
+
+        item_attributes.push(item_attribute);
+    }
+
+    Ok(item_attributes)
+}
+
+/// Check the attributes attached to a module, applying any language options to
+/// the given defaults.
+pub fn check_module_attributes(
+    mut options: LanguageOptions,
+    attributes: &[Attribute<'_>],
+) -> Result<LanguageOptions, Diagnostic<FileSpan>> {
+    for attribute in attributes {
+        match attribute.name.slice {
+            "options" => {
+                for arg in &attribute.args {
+                    match arg.slice {
+                        "type_in_type" => options.type_in_type = true,
+                        "no_prelude" => options.no_prelude = true,
+                        "call_by_need" => options.strategy = Strategy::CallByNeed,
+                        // The layout pass is run by the parser
+                        "layout" => {},
+                        "no_literal_defaulting" => {
+                            options.default_int_ty = None;
+                            options.default_float_ty = None;
+                        },
+                        name => {
+                            return Err(Diagnostic::new_error(format!(
+                                "unknown language option `{}`",
+                                name,
+                            ))
+                            .with_label(
+                                DiagnosticLabel::new_primary(arg.span())
+                                    .with_message("unknown language option"),
+                            ));
+                        },
+                    }
+                }
+            },
+            name => {
+                return Err(
+                    Diagnostic::new_error(format!("unknown module attribute `{}`", name))
+                        .with_label(
+                            DiagnosticLabel::new_primary(attribute.name.span())
+                                .with_message("unknown attribute"),
+                        ),
+                );
+            },
+        }
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod test {
+    use mltt_concrete::SpannedString;
+    use mltt_span::Files;
+
+    use super::*;
+
+    #[test]
+    fn module_options() {
+        let file_id = Files::new().add("test", "");
+        let attributes = [Attribute {
+            span: FileSpan::new(file_id, 0, 29),
+            name: SpannedString::new(file_id, 3, "options"),
+            args: vec![SpannedString::new(file_id, 11, "no_prelude")],
+        }];
+
+        let options = check_module_attributes(LanguageOptions::default(), &attributes).unwrap();
+
+        assert_eq!(
+            options,
+            LanguageOptions {
+                no_prelude: true,
+                ..LanguageOptions::default()
+            },
+        );
+    }
+
+    #[test]
+    fn module_options_no_literal_defaulting() {
+        let file_id = Files::new().add("test", "");
+        let attributes = [Attribute {
+            span: FileSpan::new(file_id, 0, 40),
+            name: SpannedString::new(file_id, 3, "options"),
+            args: vec![SpannedString::new(file_id, 11, "no_literal_defaulting")],
+        }];
+
+        let options = check_module_attributes(LanguageOptions::default(), &attributes).unwrap();
+
+        assert_eq!(options.default_int_ty, None);
+        assert_eq!(options.default_float_ty, None);
+    }
+
+    #[test]
+    fn module_options_unknown() {
+        let file_id = Files::new().add("test", "");
+        let attributes = [Attribute {
+            span: FileSpan::new(file_id, 0, 18),
+            name: SpannedString::new(file_id, 3, "options"),
+            args: vec![SpannedString::new(file_id, 11, "bogus")],
+        }];
+
+        let diagnostic =
+            check_module_attributes(LanguageOptions::default(), &attributes).unwrap_err();
+
+        assert_eq!(diagnostic.labels[0].span, FileSpan::new(file_id, 11, 16));
+    }
+}
@@ -1,13 +1,12 @@
 //! Elaboration of lists clauses to case trees.
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_concrete::{IntroParam, LiteralKind, Pattern, SpannedString, Term};
-use mltt_core::literal::LiteralIntro;
-use mltt_core::{domain, meta, syntax, AppMode, DocString, Label};
+use mltt_concrete::{Definition, IntroParam, Pattern, SpannedString, Term};
+use mltt_core::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use mltt_core::{domain, meta, prim, steps, syntax, var, AppMode, DocString, Label, Rc};
 use mltt_span::FileSpan;
-use std::rc::Rc;
 
-use super::{check_term, literal, synth_term, synth_universe, Context, MetaInsertion};
+use super::{check_term, literal, nbe, synth, synth_universe, Context, MetaInsertion};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Top-level Implementation
@@ -52,28 +51,19 @@ pub fn check_clause(
     let mut expected_ty = expected_ty.clone();
 
     while let (Some((app_mode, param_ty, next_body_ty)), Some((head_param, rest_params))) = (
-        next_expected_param(&expected_ty),
+        next_expected_param(&context, metas, &expected_ty)?,
         clause.params.split_first(),
     ) {
-        let (param_var, name_hint) = match check_param_app_mode(head_param, &app_mode)? {
-            CheckedPattern::Var(None) => (context.add_fresh_param(param_ty), None),
-            CheckedPattern::Var(Some(var_name)) => {
-                clause.params = rest_params;
-                (
-                    context.add_param(var_name, param_ty),
-                    Some(var_name.to_string()),
-                )
-            },
-            CheckedPattern::LiteralIntro(_, literal) => {
-                return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
-                    DiagnosticLabel::new_primary(literal.span())
-                        .with_message("use a case expression for matching on literals"),
-                ));
-            },
-        };
+        let pattern = check_param_app_mode(head_param, &app_mode)?;
+        // Implicit parameters that were skipped over leave the current
+        // parameter to be matched against the next expected parameter
+        if !matches!(pattern, CheckedPattern::Var(None)) {
+            clause.params = rest_params;
+        }
+        let (param_var, name_hint) = bind_pattern(&mut context, metas, pattern, param_ty)?;
 
         params.push((app_mode, name_hint));
-        expected_ty = context.app_closure(metas, next_body_ty, param_var)?;
+        expected_ty = context.app_closure(metas, &next_body_ty, param_var)?;
     }
 
     let body = check_clause_body(&context, metas, &clause, &expected_ty)?;
@@ -85,16 +75,36 @@ pub fn check_clause(
 pub struct CaseClause<'file> {
     /// The pattern for this case clause
     pattern: &'file Pattern<'file>,
+    /// The boolean guard for this case clause, if any
+    guard: Option<&'file Term<'file>>,
     /// The concrete body of this clause
     body: &'file Term<'file>,
 }
 
 impl<'file> CaseClause<'file> {
-    pub fn new(pattern: &'file Pattern<'file>, body: &'file Term<'file>) -> CaseClause<'file> {
-        CaseClause { pattern, body }
+    pub fn new(
+        pattern: &'file Pattern<'file>,
+        guard: Option<&'file Term<'file>>,
+        body: &'file Term<'file>,
+    ) -> CaseClause<'file> {
+        CaseClause {
+            pattern,
+            guard,
+            body,
+        }
     }
 }
 
+/// A case clause that has been elaborated, but not yet compiled into a case
+/// tree.
+struct CheckedCaseClause {
+    /// The literals that the clause matches on, or `None` if it matches
+    /// everything
+    literal_pattern: Option<LiteralPattern>,
+    guard: Option<Rc<syntax::Term>>,
+    body: Rc<syntax::Term>,
+}
+
 /// Check that the given case clauses conform to the expected type, and
 /// elaborate them into a case tree.
 pub fn check_case<'file>(
@@ -119,7 +129,7 @@ pub fn check_case<'file>(
             let (checked_scrutinee, (param_level, param_ty)) = {
                 let scrutinee_level = context.values().size().next_level();
                 let (scrutinee_term, scrutinee_ty) =
-                    synth_term(MetaInsertion::Yes, &context, metas, scrutinee)?;
+                    synth(MetaInsertion::Yes, &context, metas, scrutinee)?;
                 let scrutinee_value =
                     context.eval_term(metas, scrutinee.span(), &scrutinee_term)?;
                 context.add_fresh_defn(scrutinee_value, scrutinee_ty.clone());
@@ -127,41 +137,53 @@ pub fn check_case<'file>(
                 ((scrutinee_term, None), (scrutinee_level, scrutinee_ty))
             };
 
-            let mut literal_branches =
-                Vec::<(LiteralIntro, Rc<syntax::Term>)>::with_capacity(literal_clauses.len());
+            let bool_ty = Rc::from(domain::Value::literal_ty(LiteralType::Bool));
+            let mut checked_clauses = Vec::with_capacity(literal_clauses.len());
 
             for literal_clause in literal_clauses {
-                match literal_clause.pattern {
-                    Pattern::LiteralIntro(kind, literal) => {
-                        let literal_intro =
-                            literal::check(&context, metas, *kind, literal, &param_ty)?;
-                        let body = check_term(&context, metas, &literal_clause.body, expected_ty)?;
-
-                        match literal_branches
-                            .binary_search_by(|(l, _)| l.partial_cmp(&literal_intro).unwrap()) // NaN?
-                        {
-                            Ok(_) => {}, // TODO: Warn about duplicated patterns?
-                            Err(index) => literal_branches.insert(index, (literal_intro, body)),
-                        }
-                    },
-                    _ => {
-                        return Err(
-                            Diagnostic::new_error("variable literal pattern").with_label(
-                                DiagnosticLabel::new_primary(literal_clause.pattern.span())
-                                    .with_message("literal pattern expected here"),
-                            ),
-                        );
-                    },
-                }
+                let mut context = context.clone();
+                let pattern = literal_clause.pattern;
+                let literal_pattern =
+                    match check_literal_pattern(&context, metas, pattern, &param_ty)? {
+                        Some(literal_pattern) => Some(literal_pattern),
+                        // Variable patterns can only fall through to later clauses
+                        // if they are guarded
+                        None => match pattern {
+                            Pattern::Var(name) if literal_clause.guard.is_some() => {
+                                context.add_name(name, param_level);
+                                None
+                            },
+                            _ => return Err(expected_literal_pattern(pattern)),
+                        },
+                    };
+                let guard = match literal_clause.guard {
+                    None => None,
+                    Some(guard) => Some(check_term(&context, metas, guard, &bool_ty)?),
+                };
+                let body = check_term(&context, metas, literal_clause.body, expected_ty)?;
+
+                checked_clauses.push(CheckedCaseClause {
+                    literal_pattern,
+                    guard,
+                    body,
+                });
             }
 
-            let default_body = match default_clause.pattern {
-                Pattern::Var(name) => {
+            let default_body = match (default_clause.pattern, default_clause.guard) {
+                (_, Some(guard)) => {
+                    return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                        DiagnosticLabel::new_primary(guard.span())
+                            .with_message("the default case can't be guarded"),
+                    ));
+                },
+                (Pattern::Var(name), None)
+                    if context.lookup_pattern_synonym(name.slice).is_none() =>
+                {
                     let mut context = context.clone();
                     context.add_name(name, param_level);
-                    check_term(&context, metas, &default_clause.body, expected_ty)?
+                    check_term(&context, metas, default_clause.body, expected_ty)?
                 },
-                _ => {
+                (_, None) => {
                     return Err(
                         Diagnostic::new_error("expected variable pattern").with_label(
                             DiagnosticLabel::new_primary(default_clause.pattern.span())
@@ -171,19 +193,284 @@ pub fn check_case<'file>(
                 },
             };
 
-            let body = Rc::from(syntax::Term::LiteralElim(
-                Rc::from(syntax::Term::var(
-                    context.values().size().index(param_level),
-                )),
-                Rc::from(literal_branches),
-                default_body,
+            let scrutinee_var = Rc::from(syntax::Term::var(
+                context.values().size().index(param_level),
             ));
+            let body = compile_case(scrutinee_var, checked_clauses, default_body);
 
             Ok(done(vec![checked_scrutinee], Vec::new(), body))
         },
     }
 }
 
+/// Check that the clauses of a with expression conform to the expected type,
+/// and elaborate them into a case tree.
+///
+/// Before matching on the scrutinee, its occurrences in the expected type are
+/// abstracted over, so that each clause can be checked against the expected
+/// type specialized to its pattern. We elaborate this to a function that is
+/// annotated with the abstracted type, applied to the scrutinee:
+///
+/// ```text
+/// (fun x => case x { ... } : Fun (x : A) -> T[x/e]) e
+/// ```
+///
+/// Note that only the expected type is abstracted over for now - the types of
+/// the variables in the context are left as they are.
+pub fn check_with<'file>(
+    context: &Context,
+    metas: &mut meta::Env,
+    span: FileSpan,
+    scrutinee: &Term<'file>,
+    clauses: &'file [(Pattern<'file>, Option<Term<'file>>, Term<'file>)],
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let (default_clause, literal_clauses) = match clauses.split_last() {
+        None => {
+            return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                DiagnosticLabel::new_primary(span)
+                    .with_message("empty patterns are not yet supported"),
+            ));
+        },
+        Some(clauses) => clauses,
+    };
+
+    if let Some((_, Some(guard), _)) = clauses.iter().find(|(_, guard, _)| guard.is_some()) {
+        return Err(Diagnostic::new_error("unsupported guard").with_label(
+            DiagnosticLabel::new_primary(guard.span())
+                .with_message("guards are not yet supported in with expressions"),
+        ));
+    }
+
+    let (scrutinee_term, scrutinee_ty) = synth(MetaInsertion::Yes, context, metas, scrutinee)?;
+    let scrutinee_value = context.eval_term(metas, scrutinee.span(), &scrutinee_term)?;
+    let scrutinee_ty_term = context.read_back_value(metas, scrutinee.span(), &scrutinee_ty)?;
+
+    let mut body_context = context.clone();
+    let param_level = body_context.values().size().next_level();
+    body_context.add_fresh_param(scrutinee_ty.clone());
+
+    // Abstract the scrutinee out of the expected type
+    let motive = {
+        let expected_ty = body_context.read_back_value(metas, span, expected_ty)?;
+        let mut abstraction = Abstraction {
+            context: &body_context,
+            metas,
+            span,
+            value: &scrutinee_value,
+            read_back: Vec::new(),
+        };
+        abstraction.term(&expected_ty, 0)?
+    };
+
+    let mut literal_branches = Vec::with_capacity(literal_clauses.len());
+
+    for (pattern, _, body) in literal_clauses {
+        let literal_pattern = check_literal_pattern(context, metas, pattern, &scrutinee_ty)?
+            .ok_or_else(|| expected_literal_pattern(pattern))?;
+
+        // The expected type can only be specialized to patterns that match a
+        // single literal
+        let body_ty = match &literal_pattern {
+            LiteralPattern::Intro(literal_intro) => {
+                let mut context = context.clone();
+                let literal_value = Rc::from(domain::Value::literal_intro(literal_intro.clone()));
+                context.add_fresh_defn(literal_value, scrutinee_ty.clone());
+                context.eval_term(metas, pattern.span(), &motive)?
+            },
+            LiteralPattern::Range(_, _) | LiteralPattern::Prefix(_) => {
+                body_context.eval_term(metas, pattern.span(), &motive)?
+            },
+        };
+        let body = check_term(&body_context, metas, body, &body_ty)?;
+
+        literal_branches.push((literal_pattern, body));
+    }
+
+    let (default_pattern, _, default_body) = default_clause;
+    let (name_hint, default_body) = match default_pattern {
+        Pattern::Var(name) if context.lookup_pattern_synonym(name.slice).is_none() => {
+            let body_ty = body_context.eval_term(metas, name.span(), &motive)?;
+            let mut body_context = body_context.clone();
+            body_context.add_name(name, param_level);
+            let body = check_term(&body_context, metas, default_body, &body_ty)?;
+
+            (Some(name.to_string()), body)
+        },
+        _ => {
+            return Err(
+                Diagnostic::new_error("expected variable pattern").with_label(
+                    DiagnosticLabel::new_primary(default_pattern.span())
+                        .with_message("default case expected here"),
+                ),
+            );
+        },
+    };
+
+    let scrutinee_var = Rc::from(syntax::Term::var(0));
+    let mut branches = Vec::new();
+    let mut default_body = default_body;
+    for (literal_pattern, body) in literal_branches.into_iter().rev() {
+        let branch = (literal_pattern, body);
+        insert_branch(&scrutinee_var, &mut branches, &mut default_body, branch);
+    }
+
+    let fun = syntax::Term::FunIntro(
+        AppMode::Explicit,
+        name_hint.clone(),
+        literal_elim(&scrutinee_var, &branches, default_body),
+    );
+    let fun_ty = syntax::Term::FunType(
+        DocString::from(""),
+        AppMode::Explicit,
+        name_hint,
+        scrutinee_ty_term,
+        motive,
+    );
+
+    Ok(Rc::from(syntax::Term::FunElim(
+        Rc::from(syntax::Term::ann(fun, fun_ty)),
+        AppMode::Explicit,
+        scrutinee_term,
+    )))
+}
+
+/// A literal elimination on the given branches, or the default body if there
+/// are no branches to match on.
+fn literal_elim(
+    scrutinee: &Rc<syntax::Term>,
+    branches: &[(LiteralPattern, Rc<syntax::Term>)],
+    default_body: Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    match branches.is_empty() {
+        true => default_body,
+        false => Rc::from(syntax::Term::LiteralElim(
+            scrutinee.clone(),
+            Rc::from(branches),
+            default_body,
+        )),
+    }
+}
+
+/// Add a branch to the sorted, disjoint branches of a literal elimination,
+/// taking precedence over the branches that are already there.
+///
+/// If the pattern of the branch overlaps with the existing branches, they are
+/// moved to a literal elimination in the default body, which the new branches
+/// fall through to.
+fn insert_branch(
+    scrutinee: &Rc<syntax::Term>,
+    branches: &mut Vec<(LiteralPattern, Rc<syntax::Term>)>,
+    default_body: &mut Rc<syntax::Term>,
+    (literal_pattern, body): (LiteralPattern, Rc<syntax::Term>),
+) {
+    // The branches are sorted and disjoint, so the pattern can only overlap
+    // with the branches on either side of where it would be inserted
+    let start = literal_pattern.start();
+    let index = branches.partition_point(|(l, _)| l.start() < start);
+    let neighbours = index.saturating_sub(1)..usize::min(index + 1, branches.len());
+    let is_overlapping = (branches[neighbours].iter()).any(|(l, _)| l.overlaps(&literal_pattern));
+
+    if branches
+        .get(index)
+        .is_some_and(|(l, _)| *l == literal_pattern)
+    {
+        branches[index].1 = body; // TODO: Warn about duplicated patterns?
+    } else if is_overlapping {
+        *default_body = literal_elim(scrutinee, branches, default_body.clone());
+        *branches = vec![(literal_pattern, body)];
+    } else {
+        branches.insert(index, (literal_pattern, body));
+    }
+}
+
+/// Compile the checked clauses of a case expression into a case tree.
+///
+/// Clauses are compiled from last to first, so that each clause can fall
+/// through to the clauses that follow it. Unguarded literal clauses are
+/// collected into literal eliminations, where earlier clauses take
+/// precedence over later clauses that match the same literals. Guards are
+/// desugared to literal eliminations on `Bool`, falling through to the
+/// remaining clauses when the guard fails.
+fn compile_case(
+    scrutinee: Rc<syntax::Term>,
+    clauses: Vec<CheckedCaseClause>,
+    default_body: Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    let mut branches = Vec::<(LiteralPattern, Rc<syntax::Term>)>::new();
+    let mut default_body = default_body;
+
+    for clause in clauses.into_iter().rev() {
+        let body = match clause.guard {
+            None => clause.body,
+            Some(guard) => Rc::from(syntax::Term::LiteralElim(
+                guard,
+                Rc::from(vec![(
+                    LiteralPattern::from(LiteralIntro::Bool(true)),
+                    clause.body,
+                )]),
+                literal_elim(&scrutinee, &branches, default_body.clone()),
+            )),
+        };
+
+        match clause.literal_pattern {
+            Some(literal_pattern) => {
+                let branch = (literal_pattern, body);
+                insert_branch(&scrutinee, &mut branches, &mut default_body, branch);
+            },
+            None => {
+                branches.clear();
+                default_body = body;
+            },
+        }
+    }
+
+    literal_elim(&scrutinee, &branches, default_body)
+}
+
+/// Synthesize the type of the pattern that a pattern synonym stands for,
+/// elaborating it into a term.
+///
+/// Only literal patterns are supported for now, which may be annotated, or
+/// refer to earlier pattern synonyms.
+pub fn synth_synonym_pattern(
+    context: &Context,
+    metas: &mut meta::Env,
+    pattern: &Pattern<'_>,
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    match pattern {
+        Pattern::LiteralIntro(kind, literal) => literal::synth(context, metas, *kind, literal),
+        Pattern::Var(name) => match context.lookup_pattern_synonym(name.slice) {
+            Some((literal_intro, ty)) => {
+                let term = Rc::from(syntax::Term::literal_intro(literal_intro.clone()));
+                Ok((term, ty.clone()))
+            },
+            None => Err(
+                Diagnostic::new_error("expected a literal pattern").with_label(
+                    DiagnosticLabel::new_primary(name.span())
+                        .with_message("pattern synonyms can't bind variables"),
+                ),
+            ),
+        },
+        Pattern::Range(_, _) | Pattern::Prefix(_, _) => Err(Diagnostic::new_error(
+            "expected a literal pattern",
+        )
+        .with_label(
+            DiagnosticLabel::new_primary(pattern.span())
+                .with_message("pattern synonyms can only stand for single literals"),
+        )),
+        Pattern::Ann(_, pattern, concrete_ann) => {
+            let ann_span = concrete_ann.span();
+            let (ann, _) = synth_universe(context, metas, concrete_ann)?;
+            let ann_value = context.eval_term(metas, ann_span, &ann)?;
+            let (term, ty) = synth_synonym_pattern(context, metas, pattern)?;
+            context.unify_values(metas, pattern.span(), &ty, &ann_value)?;
+
+            Ok((term, ann_value))
+        },
+    }
+}
+
 /// Synthesize the type of the clauses, elaborating them into a case tree.
 ///
 /// Returns the elaborated term and its synthesized type.
@@ -192,51 +479,298 @@ pub fn synth_clause(
     metas: &mut meta::Env,
     clause: Clause<'_>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
-    if let Some(param) = clause.params.first() {
-        // TODO: We will be able to type this once we have annotated patterns!
+    let mut body_context = context.clone();
+    let mut params = Vec::new();
+    let mut param_tys = Vec::new();
+
+    for param in clause.params {
+        let app_mode = match param {
+            IntroParam::Explicit(_) => AppMode::Explicit,
+            IntroParam::Implicit(_, label, _) => AppMode::Implicit(Label(label.to_string())),
+            IntroParam::Instance(_, label, _) => AppMode::Instance(Label(label.to_string())),
+        };
+
+        // The parameter types can only be synthesized if they are annotated
+        let (pattern, param_ty) = match check_param_app_mode(param, &app_mode)? {
+            CheckedPattern::Ann(pattern, concrete_param_ty) => (*pattern, concrete_param_ty),
+            _ => {
+                return Err(
+                    Diagnostic::new_error("unable to infer the type of parameter").with_label(
+                        DiagnosticLabel::new_primary(param.span())
+                            .with_message("add a type annotation here"),
+                    ),
+                );
+            },
+        };
+
+        let param_ty_span = param_ty.span();
+        let (param_ty, _) = synth_universe(&body_context, metas, param_ty)?;
+        let param_ty_value = body_context.eval_term(metas, param_ty_span, &param_ty)?;
+        let (_, name_hint) = bind_pattern(&mut body_context, metas, pattern, param_ty_value)?;
+
+        params.push((app_mode.clone(), name_hint.clone()));
+        param_tys.push((app_mode, name_hint, param_ty));
+    }
+
+    let clause = Clause::new(&[], clause.body_ty, clause.body);
+    let (body, body_ty) = synth_clause_body(&body_context, metas, &clause)?;
+
+    if params.is_empty() {
+        return Ok((body, body_ty));
+    }
+
+    let body_ty = body_context.read_back_value(metas, clause.body.span(), &body_ty)?;
+    let fun_ty =
+        param_tys
+            .into_iter()
+            .rev()
+            .fold(body_ty, |acc, (app_mode, name_hint, param_ty)| {
+                let doc = DocString::from("");
+                Rc::from(syntax::Term::FunType(
+                    doc, app_mode, name_hint, param_ty, acc,
+                ))
+            });
+    let fun_ty_value = context.eval_term(metas, clause.body.span(), &fun_ty)?;
+    let fun = done(Vec::new(), params, body);
+
+    Ok((Rc::from(syntax::Term::ann(fun, fun_ty)), fun_ty_value))
+}
+
+/// Check a definition with a `termination-by` clause against its declared
+/// type, desugaring it to the eliminator of `Acc`:
+///
+/// ```text
+/// f : Fun (x : A) -> B x;
+/// f x = body termination-by proof;
+/// ```
+///
+/// is elaborated as:
+///
+/// ```text
+/// f = fun x => acc-elim A R (fun x => B x) (fun x _ f => body) x proof;
+/// ```
+///
+/// where `proof : Acc A R x`. In the body, `f` can only be called on the
+/// elements below `x`, given a proof that they are related by `R`.
+///
+/// Returns the elaborated term.
+pub fn check_recursive_clause(
+    context: &Context,
+    metas: &mut meta::Env,
+    definition: &Definition<'_>,
+    proof_src: &Term<'_>,
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let span = definition.label.span();
+    let param_name = match definition.params.as_slice() {
+        [IntroParam::Explicit(Pattern::Var(param_name))] => param_name.slice,
+        _ => {
+            return Err(
+                Diagnostic::new_error("unsupported `termination-by` parameters").with_label(
+                    DiagnosticLabel::new_primary(span)
+                        .with_message("expected a single explicit parameter"),
+                ),
+            );
+        },
+    };
+    let (param_ty, body_ty) = match next_expected_param(context, metas, expected_ty)? {
+        Some((AppMode::Explicit, param_ty, body_ty)) => (param_ty, body_ty),
+        _ => {
+            return Err(
+                Diagnostic::new_error("unsupported `termination-by` definition").with_label(
+                    DiagnosticLabel::new_primary(span)
+                        .with_message("expected the declared type to be an explicit function"),
+                ),
+            );
+        },
+    };
+
+    // The proof of accessibility is in the scope of the parameter, and
+    // determines the relation that the recursive calls descend through
+    let mut proof_context = context.clone();
+    proof_context.add_param(param_name, param_ty.clone());
+    let (proof, proof_ty) = synth(MetaInsertion::Yes, &proof_context, metas, proof_src)?;
+    let relation_value = match proof_context.force_value(metas, span, &proof_ty)?.as_ref() {
+        domain::Value::Neutral(domain::Head::Prim(name), spine)
+            if name.0 == "Acc" && spine.len() == 3 =>
+        {
+            match &spine[1] {
+                domain::Elim::Fun(AppMode::Explicit, relation) => relation.clone(),
+                _ => return Err(expected_acc_proof(proof_src)),
+            }
+        },
+        _ => return Err(expected_acc_proof(proof_src)),
+    };
+    let relation = proof_context.read_back_value(metas, span, &relation_value)?;
+    if relation.has_free_var(var::Index(0)) {
         return Err(
-            Diagnostic::new_error("unable to infer the type of parameter")
-                .with_label(DiagnosticLabel::new_primary(param.span())),
+            Diagnostic::new_error("relation depends on the parameter").with_label(
+                DiagnosticLabel::new_primary(proof_src.span()).with_message(format!(
+                    "the relation of this proof can't refer to `{}`",
+                    param_name,
+                )),
+            ),
         );
     }
+    let acc_ty = fun_elims(
+        Rc::from(syntax::Term::prim("Acc")),
+        vec![
+            proof_context.read_back_value(metas, span, &param_ty)?,
+            relation,
+            Rc::from(syntax::Term::var(0)),
+        ],
+    );
+    let acc_ty = proof_context.eval_term(metas, span, &acc_ty)?;
+    proof_context.unify_values(metas, proof_src.span(), &proof_ty, &acc_ty)?;
+
+    // Instantiate the type of `acc-elim` to find the type of the step
+    let acc_elim = prim::Name::from("acc-elim");
+    let acc_elim_ty = match context.prims().lookup_entry(&acc_elim) {
+        Some(prim::Entry { ty: Some(ty), .. }) => context.eval_term(metas, span, ty)?,
+        _ => return Err(Diagnostic::new_bug("missing type for primitive `acc-elim`")),
+    };
+    let motive_value = Rc::from(domain::Value::FunIntro(
+        AppMode::Explicit,
+        Some(param_name.to_owned()),
+        body_ty,
+    ));
+    let acc_elim_ty = instantiate_fun_ty(context, metas, span, &acc_elim_ty, param_ty.clone())?;
+    let acc_elim_ty =
+        instantiate_fun_ty(context, metas, span, &acc_elim_ty, relation_value.clone())?;
+    let acc_elim_ty = instantiate_fun_ty(context, metas, span, &acc_elim_ty, motive_value.clone())?;
 
-    synth_clause_body(&context, metas, &clause)
+    // The step of the recursion ignores the proofs of accessibility for the
+    // elements below the parameter, and binds the definition to the
+    // recursive calls
+    let step_ty = match next_expected_param(context, metas, &acc_elim_ty)? {
+        Some((_, step_ty, _)) => step_ty,
+        None => {
+            return Err(Diagnostic::new_bug(
+                "unexpected type for primitive `acc-elim`",
+            ))
+        },
+    };
+    let label = definition.label;
+    let step_params = [
+        definition.params[0].clone(),
+        IntroParam::Explicit(Pattern::Var(SpannedString::new(
+            label.source,
+            label.start,
+            "_",
+        ))),
+        IntroParam::Explicit(Pattern::Var(label)),
+    ];
+    let body_ty = definition.body_ty.as_ref();
+    let clause = Clause::new(&step_params, body_ty, &definition.body);
+    let step = check_clause(context, metas, clause, &step_ty)?;
+
+    let acc_elim = fun_elims(
+        Rc::from(syntax::Term::prim(acc_elim)),
+        vec![
+            context.read_back_value(metas, span, &param_ty)?,
+            context.read_back_value(metas, span, &relation_value)?,
+            context.read_back_value(metas, span, &motive_value)?,
+            step,
+        ],
+    );
+    let body = fun_elims(
+        steps::shift(&acc_elim, 1),
+        vec![Rc::from(syntax::Term::var(0)), proof],
+    );
+
+    Ok(Rc::from(syntax::Term::FunIntro(
+        AppMode::Explicit,
+        Some(param_name.to_owned()),
+        body,
+    )))
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 // Helper functions
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// The application mode and type of a parameter, along with the closure of the
+/// body type that follows it.
+type ExpectedParam = (AppMode, Rc<domain::Value>, domain::AppClosure);
+
 /// Get the next expected parameter
-fn next_expected_param<'ty>(
-    expected_ty: &'ty Rc<domain::Type>,
-) -> Option<(AppMode, Rc<domain::Value>, &'ty domain::AppClosure)> {
-    match expected_ty.as_ref() {
-        domain::Value::FunType(app_mode, _, param_ty, body_ty) => {
-            Some((app_mode.clone(), param_ty.clone(), body_ty))
+fn next_expected_param(
+    context: &Context,
+    metas: &meta::Env,
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Option<ExpectedParam>, Diagnostic<FileSpan>> {
+    match context.force_value(metas, None, expected_ty)?.as_ref() {
+        domain::Value::FunType(_, app_mode, _, param_ty, body_ty) => {
+            Ok(Some((app_mode.clone(), param_ty.clone(), body_ty.clone())))
         },
-        _ => None,
+        _ => Ok(None),
     }
 }
 
 enum CheckedPattern<'file> {
     Var(Option<SpannedString<'file>>),
-    LiteralIntro(LiteralKind, SpannedString<'file>),
+    Literal(FileSpan),
+    Ann(Box<CheckedPattern<'file>>, &'file Term<'file>),
 }
 
-impl<'file> From<&Pattern<'file>> for CheckedPattern<'file> {
-    fn from(src: &Pattern<'file>) -> CheckedPattern<'file> {
+impl<'file> From<&'file Pattern<'file>> for CheckedPattern<'file> {
+    fn from(src: &'file Pattern<'file>) -> CheckedPattern<'file> {
         match src {
-            Pattern::Var(name) => CheckedPattern::Var(Some(name.clone())),
-            Pattern::LiteralIntro(kind, src) => CheckedPattern::LiteralIntro(*kind, src.clone()),
+            Pattern::Var(name) => CheckedPattern::Var(Some(*name)),
+            Pattern::LiteralIntro(_, _) | Pattern::Range(_, _) | Pattern::Prefix(_, _) => {
+                CheckedPattern::Literal(src.span())
+            },
+            Pattern::Ann(_, pattern, ann) => {
+                CheckedPattern::Ann(Box::new(CheckedPattern::from(pattern.as_ref())), ann)
+            },
+        }
+    }
+}
+
+/// Bind a parameter pattern to a new variable in the context, checking any
+/// type annotations against the expected type of the parameter.
+fn bind_pattern<'file>(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    mut pattern: CheckedPattern<'file>,
+    param_ty: Rc<domain::Type>,
+) -> Result<(Rc<domain::Value>, Option<String>), Diagnostic<FileSpan>> {
+    loop {
+        match pattern {
+            CheckedPattern::Var(None) => return Ok((context.add_fresh_param(param_ty), None)),
+            CheckedPattern::Var(Some(var_name))
+                if context.lookup_pattern_synonym(var_name.slice).is_some() =>
+            {
+                return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                    DiagnosticLabel::new_primary(var_name.span())
+                        .with_message("use a case expression for matching on pattern synonyms"),
+                ));
+            },
+            CheckedPattern::Var(Some(var_name)) => {
+                let param_var = context.add_param(var_name, param_ty);
+                return Ok((param_var, Some(var_name.to_string())));
+            },
+            CheckedPattern::Literal(span) => {
+                return Err(Diagnostic::new_error("non-exhaustive patterns").with_label(
+                    DiagnosticLabel::new_primary(span)
+                        .with_message("use a case expression for matching on literals"),
+                ));
+            },
+            CheckedPattern::Ann(inner_pattern, concrete_ann) => {
+                let ann_span = concrete_ann.span();
+                let (ann, _) = synth_universe(context, metas, concrete_ann)?;
+                let ann_value = context.eval_term(metas, ann_span, &ann)?;
+                context.unify_values(metas, ann_span, &ann_value, &param_ty)?;
+                pattern = *inner_pattern;
+            },
         }
     }
 }
 
 /// Check that a given parameter matches the expected application mode, and
 /// return the pattern inside it.
-fn check_param_app_mode<'param, 'file>(
-    param: &'param IntroParam<'file>,
+fn check_param_app_mode<'file>(
+    param: &'file IntroParam<'file>,
     expected_app_mode: &AppMode,
 ) -> Result<CheckedPattern<'file>, Diagnostic<FileSpan>> {
     match (param, expected_app_mode) {
@@ -246,7 +780,7 @@ fn check_param_app_mode<'param, 'file>(
             if intro_label.slice == ty_label.0 =>
         {
             match pattern {
-                None => Ok(CheckedPattern::Var(Some(intro_label.clone()))),
+                None => Ok(CheckedPattern::Var(Some(*intro_label))),
                 Some(pattern) => Ok(CheckedPattern::from(pattern)),
             }
         },
@@ -270,14 +804,14 @@ fn check_clause_body(
     expected_body_ty: &Rc<domain::Type>,
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
     match clause.body_ty {
-        None => check_term(&context, metas, clause.body, &expected_body_ty),
+        None => check_term(context, metas, clause.body, expected_body_ty),
         Some(body_ty) => {
             let body_ty_span = body_ty.span();
-            let (body_ty, _) = synth_universe(&context, metas, body_ty)?;
+            let (body_ty, _) = synth_universe(context, metas, body_ty)?;
             let body_ty_value = context.eval_term(metas, body_ty_span, &body_ty)?;
-            let body = check_term(&context, metas, clause.body, &body_ty_value)?;
+            let body = check_term(context, metas, clause.body, &body_ty_value)?;
             // TODO: Ensure that this is respecting variance correctly!
-            context.unify_values(metas, clause.body.span(), &body_ty_value, &expected_body_ty)?;
+            context.unify_values(metas, clause.body.span(), &body_ty_value, expected_body_ty)?;
 
             Ok(Rc::from(syntax::Term::ann(body, body_ty)))
         },
@@ -291,7 +825,7 @@ fn synth_clause_body(
     clause: &Clause<'_>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
     match clause.body_ty {
-        None => synth_term(MetaInsertion::Yes, context, metas, clause.body),
+        None => synth(MetaInsertion::Yes, context, metas, clause.body),
         Some(body_ty) => {
             let body_ty_span = body_ty.span();
             let (body_ty, _) = synth_universe(context, metas, body_ty)?;
@@ -303,6 +837,233 @@ fn synth_clause_body(
     }
 }
 
+/// Check a case pattern that matches on literals, returning `None` if it is
+/// not a literal pattern.
+fn check_literal_pattern(
+    context: &Context,
+    metas: &mut meta::Env,
+    pattern: &Pattern<'_>,
+    param_ty: &Rc<domain::Type>,
+) -> Result<Option<LiteralPattern>, Diagnostic<FileSpan>> {
+    match pattern {
+        Pattern::LiteralIntro(kind, literal) => {
+            let literal_intro = literal::check(context, metas, *kind, literal, param_ty)?;
+            Ok(Some(LiteralPattern::Intro(comparable_literal(
+                pattern,
+                literal_intro,
+            )?)))
+        },
+        Pattern::Var(name) => match context.lookup_pattern_synonym(name.slice) {
+            Some((literal_intro, ty)) => {
+                context.unify_values(metas, name.span(), ty, param_ty)?;
+                let literal_intro = comparable_literal(pattern, literal_intro.clone())?;
+                Ok(Some(LiteralPattern::Intro(literal_intro)))
+            },
+            None => Ok(None),
+        },
+        Pattern::Range(start, end) => {
+            let start = check_literal_bound(context, metas, start, param_ty)?;
+            let end = check_literal_bound(context, metas, end, param_ty)?;
+
+            if start <= end {
+                Ok(Some(LiteralPattern::Range(start, end)))
+            } else {
+                Err(Diagnostic::new_error("empty range pattern").with_label(
+                    DiagnosticLabel::new_primary(pattern.span())
+                        .with_message(format!("`{}` is greater than `{}`", start, end,)),
+                ))
+            }
+        },
+        Pattern::Prefix(_, prefix) => {
+            let string_ty = Rc::from(domain::Value::literal_ty(LiteralType::String));
+            context.unify_values(metas, pattern.span(), &string_ty, param_ty)?;
+
+            match check_literal_bound(context, metas, prefix, param_ty)? {
+                LiteralIntro::String(prefix) => Ok(Some(LiteralPattern::Prefix(prefix))),
+                _ => Err(expected_literal_pattern(prefix)),
+            }
+        },
+        Pattern::Ann(_, _, _) => Ok(None),
+    }
+}
+
+/// Check a pattern that is used as the bound of a range or prefix pattern,
+/// which must match a single literal.
+fn check_literal_bound(
+    context: &Context,
+    metas: &mut meta::Env,
+    pattern: &Pattern<'_>,
+    param_ty: &Rc<domain::Type>,
+) -> Result<LiteralIntro, Diagnostic<FileSpan>> {
+    match check_literal_pattern(context, metas, pattern, param_ty)? {
+        Some(LiteralPattern::Intro(literal_intro)) => Ok(literal_intro),
+        Some(_) | None => Err(expected_literal_pattern(pattern)),
+    }
+}
+
+/// Reject `NaN` literals, which are not equal to themselves, and so can't be
+/// kept in the sorted branches of a literal elimination.
+fn comparable_literal(
+    pattern: &Pattern<'_>,
+    literal_intro: LiteralIntro,
+) -> Result<LiteralIntro, Diagnostic<FileSpan>> {
+    let is_nan = match literal_intro {
+        LiteralIntro::F32(value) => value.is_nan(),
+        LiteralIntro::F64(value) => value.is_nan(),
+        _ => false,
+    };
+
+    match is_nan {
+        true => Err(Diagnostic::new_error("NaN literal pattern").with_label(
+            DiagnosticLabel::new_primary(pattern.span())
+                .with_message("NaN is not equal to itself, so it can't be matched"),
+        )),
+        false => Ok(literal_intro),
+    }
+}
+
+fn expected_literal_pattern(pattern: &Pattern<'_>) -> Diagnostic<FileSpan> {
+    Diagnostic::new_error("variable literal pattern").with_label(
+        DiagnosticLabel::new_primary(pattern.span()).with_message("literal pattern expected here"),
+    )
+}
+
+/// Replaces the occurrences of a value in a term with the most recently bound
+/// variable in the context.
+struct Abstraction<'a> {
+    /// The context that the term is in
+    context: &'a Context,
+    metas: &'a meta::Env,
+    span: FileSpan,
+    /// The value to abstract over
+    value: &'a Rc<domain::Value>,
+    /// The value, read back under increasing numbers of binders
+    read_back: Vec<Rc<syntax::Term>>,
+}
+
+impl<'a> Abstraction<'a> {
+    /// Abstract over the value in a term, under the given number of binders.
+    fn term(
+        &mut self,
+        term: &Rc<syntax::Term>,
+        depth: u32,
+    ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+        use mltt_core::syntax::{Item, Term};
+
+        if term.alpha_eq(&*self.read_back(depth)?) {
+            return Ok(Rc::from(Term::var(depth)));
+        }
+
+        Ok(match term.as_ref() {
+            Term::Var(_)
+            | Term::Meta(_)
+            | Term::Prim(_)
+            | Term::LiteralType(_)
+            | Term::LiteralIntro(_)
+            | Term::Universe(_) => term.clone(),
+
+            Term::Ann(term, term_ty) => Rc::from(Term::Ann(
+                self.term(term, depth)?,
+                self.term(term_ty, depth)?,
+            )),
+            Term::Let(items, body) => {
+                let mut depth = depth;
+                let mut abstracted_items = Vec::with_capacity(items.len());
+                for item in items {
+                    abstracted_items.push(match item {
+                        Item::Declaration(doc, label, term_ty) => {
+                            let term_ty = self.term(term_ty, depth)?;
+                            Item::Declaration(doc.clone(), label.clone(), term_ty)
+                        },
+                        Item::Definition(doc, label, term) => {
+                            let term = self.term(term, depth)?;
+                            depth += 1;
+                            Item::Definition(doc.clone(), label.clone(), term)
+                        },
+                    });
+                }
+                Rc::from(Term::Let(abstracted_items, self.term(body, depth)?))
+            },
+
+            Term::LiteralElim(scrutinee, clauses, default_body) => {
+                let clauses = (clauses.iter())
+                    .map(|(literal_intro, body)| {
+                        Ok((literal_intro.clone(), self.term(body, depth)?))
+                    })
+                    .collect::<Result<Vec<_>, Diagnostic<FileSpan>>>()?;
+                Rc::from(Term::LiteralElim(
+                    self.term(scrutinee, depth)?,
+                    Rc::from(clauses),
+                    self.term(default_body, depth)?,
+                ))
+            },
+
+            Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => Rc::from(Term::FunType(
+                doc.clone(),
+                app_mode.clone(),
+                name_hint.clone(),
+                self.term(param_ty, depth)?,
+                self.term(body_ty, depth + 1)?,
+            )),
+            Term::FunIntro(app_mode, name_hint, body) => Rc::from(Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                self.term(body, depth + 1)?,
+            )),
+            Term::FunElim(fun, app_mode, arg) => Rc::from(Term::FunElim(
+                self.term(fun, depth)?,
+                app_mode.clone(),
+                self.term(arg, depth)?,
+            )),
+
+            Term::RecordType(ty_fields) => {
+                let ty_fields = (ty_fields.iter().enumerate())
+                    .map(|(i, (doc, label, name_hint, ty))| {
+                        let ty = self.term(ty, depth + i as u32)?;
+                        Ok((doc.clone(), label.clone(), name_hint.clone(), ty))
+                    })
+                    .collect::<Result<_, Diagnostic<FileSpan>>>()?;
+                Rc::from(Term::RecordType(ty_fields))
+            },
+            Term::RecordIntro(intro_fields) => {
+                let intro_fields = (intro_fields.iter().enumerate())
+                    .map(|(i, (label, term))| {
+                        Ok((label.clone(), self.term(term, depth + i as u32)?))
+                    })
+                    .collect::<Result<_, Diagnostic<FileSpan>>>()?;
+                Rc::from(Term::RecordIntro(intro_fields))
+            },
+            Term::RecordElim(record, label) => {
+                Rc::from(Term::RecordElim(self.term(record, depth)?, label.clone()))
+            },
+
+            Term::ArrayType(elem_ty) => Rc::from(Term::ArrayType(self.term(elem_ty, depth)?)),
+            Term::ArrayIntro(elems) => {
+                let elems = (elems.iter())
+                    .map(|elem| self.term(elem, depth))
+                    .collect::<Result<_, Diagnostic<FileSpan>>>()?;
+                Rc::from(Term::ArrayIntro(elems))
+            },
+        })
+    }
+
+    /// The value, read back under the given number of binders.
+    fn read_back(&mut self, depth: u32) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+        while self.read_back.len() <= depth as usize {
+            let size = self.context.values().size() + self.read_back.len() as u32;
+            let term = nbe::read_back_value(
+                self.context.prims(),
+                self.metas,
+                size,
+                self.span,
+                self.value,
+            )?;
+            self.read_back.push(term);
+        }
+        Ok(self.read_back[depth as usize].clone())
+    }
+}
+
 /// Finish elaborating the patterns into a case tree.
 fn done(
     scrutinees: Vec<(Rc<syntax::Term>, Option<Rc<syntax::Term>>)>,
@@ -335,3 +1096,34 @@ fn done(
         Rc::from(syntax::Term::Let(items, body))
     }
 }
+
+/// The error for a `termination-by` clause that is not a proof of
+/// accessibility.
+fn expected_acc_proof(proof_src: &Term<'_>) -> Diagnostic<FileSpan> {
+    Diagnostic::new_error("expected a proof of accessibility").with_label(
+        DiagnosticLabel::new_primary(proof_src.span())
+            .with_message("expected a term of type `Acc`"),
+    )
+}
+
+/// Apply the body of a function type to an argument.
+fn instantiate_fun_ty(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    fun_ty: &Rc<domain::Type>,
+    arg: Rc<domain::Value>,
+) -> Result<Rc<domain::Type>, Diagnostic<FileSpan>> {
+    match next_expected_param(context, metas, fun_ty)? {
+        Some((_, _, body_ty)) => context.app_closure(metas, &body_ty, arg),
+        None => Err(Diagnostic::new_bug("expected a function type")
+            .with_label(DiagnosticLabel::new_primary(span))),
+    }
+}
+
+/// Apply a function to a list of explicit arguments.
+fn fun_elims(fun: Rc<syntax::Term>, args: Vec<Rc<syntax::Term>>) -> Rc<syntax::Term> {
+    (args.into_iter()).fold(fun, |acc, arg| {
+        Rc::from(syntax::Term::FunElim(acc, AppMode::Explicit, arg))
+    })
+}
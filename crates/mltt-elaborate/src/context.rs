@@ -1,13 +1,67 @@
 //! The elaboration context.
 
 use language_reporting::Diagnostic;
-use mltt_core::{domain, meta, prim, syntax, validate, var, AppMode};
+use mltt_core::literal::{LiteralIntro, LiteralType};
+use mltt_core::nbe::{ConversionBudget, ElidedTerm, Strategy};
+use mltt_core::pretty::Style;
+use mltt_core::telescope::{Binder, Entry, Telescope};
+use mltt_core::{
+    domain, meta, prim, steps, syntax, validate, var, AppMode, DocString, Label, Rc, UniverseLevel,
+};
 use mltt_span::FileSpan;
 use pretty::{BoxDoc, Doc};
-use std::rc::Rc;
 
+use crate::index::{ElaboratedHole, ElaboratedImplicit, ElaboratedName, NameIndex};
+use crate::namespace::Namespaces;
 use crate::{nbe, unify};
 
+/// Language options that affect how a file is elaborated.
+///
+/// These are usually set on the command line, and can be overridden for a
+/// single file using an `#![options(...)]` attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LanguageOptions {
+    /// Allow `Type : Type`, at the expense of logical consistency.
+    pub type_in_type: bool,
+    /// The greatest universe level that can be represented. Universes whose
+    /// types would be greater than this are reported as errors.
+    pub max_universe: UniverseLevel,
+    /// Don't bring the builtin types and values, like `String` and `true`,
+    /// into scope.
+    pub no_prelude: bool,
+    /// The type to give to integer literals whose types could not be inferred
+    /// by the end of an item. If this is `None`, they are reported as
+    /// ambiguous instead.
+    pub default_int_ty: Option<LiteralType>,
+    /// The type to give to float literals whose types could not be inferred
+    /// by the end of an item. If this is `None`, they are reported as
+    /// ambiguous instead.
+    pub default_float_ty: Option<LiteralType>,
+    /// The strategy to use when evaluating terms.
+    pub strategy: Strategy,
+    /// Explain the steps that were taken when two values fail to unify.
+    pub explain_conversion: bool,
+    /// The greatest number of steps that a conversion check can take before
+    /// giving up. If this is `None`, conversion checks can take any number of
+    /// steps.
+    pub conversion_budget: Option<u32>,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> LanguageOptions {
+        LanguageOptions {
+            type_in_type: false,
+            max_universe: UniverseLevel::MAX,
+            no_prelude: false,
+            default_int_ty: Some(LiteralType::S32),
+            default_float_ty: Some(LiteralType::F64),
+            strategy: Strategy::CallByValue,
+            explain_conversion: false,
+            conversion_budget: None,
+        }
+    }
+}
+
 /// Local elaboration context.
 ///
 /// This stores the information that we need when elaborating terms from the
@@ -24,8 +78,12 @@ pub struct Context {
     values: var::Env<Rc<domain::Value>>,
     /// Types of the entries in the context.
     tys: var::Env<Rc<domain::Type>>,
-    /// Names of the entries in the context (used for pretty printing).
-    names: var::Env<String>,
+    /// Names of the entries in the context, keyed by the level in which they
+    /// were bound (used for pretty printing).
+    ///
+    /// Fresh entries won't have a name, so we have to key by level in order to
+    /// keep the names in sync with the values.
+    level_names: im::OrdMap<var::Level, String>,
     /// Substitutions from the user-defined names to the level in which they
     /// were bound.
     ///
@@ -34,10 +92,59 @@ pub struct Context {
     /// Not all entries in the context will have a corresponding name - for
     /// example we don't define a name for non-dependent function types.
     names_to_levels: im::HashMap<String, var::Level>,
+    /// The levels of the items that were brought into scope by opening
+    /// namespaces, keyed by their unqualified name.
+    ///
+    /// When more than one item shares a name, the name is overloaded, and is
+    /// disambiguated using the expected type.
+    opened_levels: im::HashMap<String, im::Vector<var::Level>>,
+    /// The overloaded names that have been resolved to a specific item, keyed
+    /// by the span of the name.
+    selected_overloads: im::HashMap<FileSpan, var::Level>,
+    /// The namespaces that are in scope, with their items mapped to the level
+    /// in which they were bound.
+    namespaces: Namespaces<var::Level>,
+    /// The levels of the items marked with `#[from_string]`, which allow
+    /// string literals to be elaborated to types other than `String`.
+    string_instances: im::Vector<var::Level>,
+    /// The levels of the items marked with `#[inline]`, whose calls are
+    /// specialized during elaboration.
+    inline_items: im::OrdSet<var::Level>,
+    /// Pattern synonyms, mapped to the literals that they stand for, along
+    /// with the types of those literals.
+    pattern_synonyms: im::HashMap<String, (LiteralIntro, Rc<domain::Type>)>,
     /// Local bound levels.
     ///
     /// This is used for making spines for fresh metas.
     bound_levels: im::Vector<var::Level>,
+    /// Whether implicit and instance applications and binders are displayed
+    /// when pretty printing.
+    show_implicits: bool,
+    /// The size above which the bodies of functions are elided when pretty
+    /// printing.
+    elide_size: Option<usize>,
+    /// Whether universes are allowed to contain themselves.
+    type_in_type: bool,
+    /// The greatest universe level that can be represented.
+    max_universe: UniverseLevel,
+    /// Whether a namespace of helpers is derived for each record type
+    /// definition.
+    derive_record_helpers: bool,
+    /// The type to give to integer literals whose types could not be inferred.
+    default_int_ty: Option<LiteralType>,
+    /// The type to give to float literals whose types could not be inferred.
+    default_float_ty: Option<LiteralType>,
+    /// The strategy to use when evaluating terms.
+    strategy: Strategy,
+    /// Whether the steps taken during unification are reported when it fails.
+    explain_conversion: bool,
+    /// The greatest number of steps that a conversion check can take.
+    conversion_budget: Option<u32>,
+    /// The most effectful primitives that can be interpreted when running
+    /// elaborated terms.
+    run_capability: prim::Capability,
+    /// Where the elaborated names are recorded, if anywhere.
+    name_index: Option<NameIndex>,
 }
 
 impl Context {
@@ -47,10 +154,98 @@ impl Context {
             prims: prim::Env::new(),
             values: var::Env::new(),
             tys: var::Env::new(),
-            names: var::Env::new(),
+            level_names: im::OrdMap::new(),
             names_to_levels: im::HashMap::new(),
+            opened_levels: im::HashMap::new(),
+            selected_overloads: im::HashMap::new(),
+            namespaces: Namespaces::new(),
+            string_instances: im::Vector::new(),
+            inline_items: im::OrdSet::new(),
+            pattern_synonyms: im::HashMap::new(),
             bound_levels: im::Vector::new(),
+            show_implicits: false,
+            elide_size: None,
+            type_in_type: false,
+            max_universe: UniverseLevel::MAX,
+            derive_record_helpers: true,
+            default_int_ty: None,
+            default_float_ty: None,
+            strategy: Strategy::CallByValue,
+            explain_conversion: false,
+            conversion_budget: None,
+            run_capability: prim::Capability::Unsafe,
+            name_index: None,
+        }
+    }
+
+    /// Create a new context, configured using the given language options.
+    pub fn new(options: LanguageOptions) -> Context {
+        use mltt_core::domain::{AppClosure, Value};
+        use mltt_core::literal::LiteralType as LitType;
+
+        let mut context = Context::empty();
+        context.type_in_type = options.type_in_type;
+        context.max_universe = options.max_universe;
+        context.default_int_ty = options.default_int_ty;
+        context.default_float_ty = options.default_float_ty;
+        context.strategy = options.strategy;
+        context.explain_conversion = options.explain_conversion;
+        context.conversion_budget = options.conversion_budget;
+
+        if !options.no_prelude {
+            let u0 = Rc::from(Value::universe(0));
+            let bool = Rc::from(Value::literal_ty(LitType::Bool));
+
+            context.add_defn(
+                "String",
+                Rc::from(Value::literal_ty(LitType::String)),
+                u0.clone(),
+            );
+            context.add_defn(
+                "Char",
+                Rc::from(Value::literal_ty(LitType::Char)),
+                u0.clone(),
+            );
+            context.add_defn("Bool", bool.clone(), u0.clone());
+            context.add_defn("true", Rc::from(Value::literal_intro(true)), bool.clone());
+            context.add_defn("false", Rc::from(Value::literal_intro(false)), bool.clone());
+            context.add_defn("U8", Rc::from(Value::literal_ty(LitType::U8)), u0.clone());
+            context.add_defn("U16", Rc::from(Value::literal_ty(LitType::U16)), u0.clone());
+            context.add_defn("U32", Rc::from(Value::literal_ty(LitType::U32)), u0.clone());
+            context.add_defn("U64", Rc::from(Value::literal_ty(LitType::U64)), u0.clone());
+            context.add_defn("S8", Rc::from(Value::literal_ty(LitType::S8)), u0.clone());
+            context.add_defn("S16", Rc::from(Value::literal_ty(LitType::S16)), u0.clone());
+            context.add_defn("S32", Rc::from(Value::literal_ty(LitType::S32)), u0.clone());
+            context.add_defn("S64", Rc::from(Value::literal_ty(LitType::S64)), u0.clone());
+            context.add_defn("F32", Rc::from(Value::literal_ty(LitType::F32)), u0.clone());
+            context.add_defn("F64", Rc::from(Value::literal_ty(LitType::F64)), u0.clone());
+
+            // Array : Type -> Type
+            let array_ty = Rc::from(syntax::Term::ArrayType(Rc::from(syntax::Term::var(0))));
+            context.add_defn(
+                "Array",
+                Rc::from(Value::FunIntro(
+                    AppMode::Explicit,
+                    Some("A".to_owned()),
+                    AppClosure::new(array_ty, var::Env::new(), context.strategy),
+                )),
+                Rc::from(Value::FunType(
+                    DocString::from(""),
+                    AppMode::Explicit,
+                    Some("A".to_owned()),
+                    u0.clone(),
+                    AppClosure::new(
+                        Rc::from(syntax::Term::universe(0)),
+                        var::Env::new(),
+                        context.strategy,
+                    ),
+                )),
+            );
         }
+
+        context.prims = prim::Env::default();
+
+        context
     }
 
     /// Primitive entries.
@@ -65,21 +260,354 @@ impl Context {
 
     /// Convert the context into a validation context.
     pub fn validation_context(&self) -> validate::Context {
-        validate::Context::new(self.prims.clone(), self.values.clone(), self.tys.clone())
+        let mut context =
+            validate::Context::new(self.prims.clone(), self.values.clone(), self.tys.clone());
+        context.set_type_in_type(self.type_in_type);
+        context.set_max_universe(self.max_universe);
+        context.set_strategy(self.strategy);
+        context.set_conversion_budget(self.conversion_budget);
+        context
     }
 
     /// Convert the context into a pretty printing environment.
+    ///
+    /// Shadowed and unnamed entries are given fresh names to ensure that
+    /// variables are not captured when they are printed.
     pub fn pretty_env(&self) -> mltt_core::pretty::Env {
-        mltt_core::pretty::Env::new(self.names.clone())
+        let mut env = mltt_core::pretty::Env::empty();
+        env.set_show_implicits(self.show_implicits);
+        env.set_elide_size(self.elide_size);
+        for var_level in (0..self.values.size().0).map(var::Level) {
+            env.fresh_name(self.level_names.get(&var_level).map(String::as_str));
+        }
+        env
+    }
+
+    /// Whether universes are allowed to contain themselves, ie. `Type : Type`.
+    pub fn type_in_type(&self) -> bool {
+        self.type_in_type
+    }
+
+    /// The greatest universe level that can be represented.
+    pub fn max_universe(&self) -> UniverseLevel {
+        self.max_universe
+    }
+
+    /// The strategy to use when evaluating terms.
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Whether a namespace of helpers is derived for each record type
+    /// definition.
+    pub fn derive_record_helpers(&self) -> bool {
+        self.derive_record_helpers
+    }
+
+    /// Set whether a namespace of helpers is derived for each record type
+    /// definition.
+    pub fn set_derive_record_helpers(&mut self, derive_record_helpers: bool) {
+        self.derive_record_helpers = derive_record_helpers;
+    }
+
+    /// The type to give to integer literals whose types could not be inferred.
+    pub fn default_int_ty(&self) -> Option<LiteralType> {
+        self.default_int_ty
+    }
+
+    /// The type to give to float literals whose types could not be inferred.
+    pub fn default_float_ty(&self) -> Option<LiteralType> {
+        self.default_float_ty
+    }
+
+    /// Set whether implicit and instance applications and binders are
+    /// displayed when pretty printing. These are hidden by default.
+    pub fn set_show_implicits(&mut self, show_implicits: bool) {
+        self.show_implicits = show_implicits;
+    }
+
+    /// The size above which the bodies of functions are elided.
+    pub fn elide_size(&self) -> Option<usize> {
+        self.elide_size
+    }
+
+    /// Set the size above which the bodies of functions are displayed as
+    /// `...` when pretty printing. Nothing is elided by default.
+    pub fn set_elide_size(&mut self, elide_size: Option<usize>) {
+        self.elide_size = elide_size;
+    }
+
+    /// Set whether the steps taken during unification are reported when it
+    /// fails. This is disabled by default.
+    pub fn set_explain_conversion(&mut self, explain_conversion: bool) {
+        self.explain_conversion = explain_conversion;
+    }
+
+    /// Set the greatest number of steps that a conversion check can take
+    /// before giving up. This is unlimited by default.
+    pub fn set_conversion_budget(&mut self, conversion_budget: Option<u32>) {
+        self.conversion_budget = conversion_budget;
+    }
+
+    /// Set the most effectful primitives that can be interpreted when running
+    /// elaborated terms. All primitives can be run by default.
+    ///
+    /// Only pure primitives are interpreted during elaboration, regardless of
+    /// this setting, so that type checking stays deterministic.
+    pub fn set_run_capability(&mut self, run_capability: prim::Capability) {
+        self.run_capability = run_capability;
+    }
+
+    /// A copy of the context that can be used to run elaborated terms,
+    /// interpreting the primitives allowed by the run capability.
+    pub fn run_context(&self) -> Context {
+        let mut context = self.clone();
+        context.prims.set_allowed(self.run_capability);
+        context
+    }
+
+    /// Record the primitives that are interpreted to a value when evaluating
+    /// terms in this context, or stop recording them if `fold_log` is `None`.
+    pub fn set_fold_log(&mut self, fold_log: Option<Rc<prim::FoldLog>>) {
+        self.prims.set_fold_log(fold_log);
+    }
+
+    /// Record the names that are elaborated in this context, and in the
+    /// contexts derived from it, in the given index.
+    pub fn set_name_index(&mut self, name_index: NameIndex) {
+        self.name_index = Some(name_index);
+    }
+
+    /// A copy of this context to store in the name index.
+    fn indexed_context(&self) -> Context {
+        // Avoid a reference cycle between the index and the context
+        let mut context = self.clone();
+        context.name_index = None;
+        context
+    }
+
+    /// Record that the name at the given span was elaborated to the entry at
+    /// the given index, if a name index was set.
+    pub(crate) fn record_name(&self, span: FileSpan, var_index: var::Index) {
+        self.record_name_with(span, var_index, false);
+    }
+
+    /// Record that the name at the given span was defined as the most recent
+    /// entry in the context, without a type annotation, if a name index was
+    /// set.
+    pub(crate) fn record_inferred_defn(&self, span: FileSpan) {
+        self.record_name_with(span, var::Index(0), true);
+    }
+
+    fn record_name_with(&self, span: FileSpan, var_index: var::Index, is_inferred: bool) {
+        if let Some(name_index) = &self.name_index {
+            if let Some(ty) = self.tys.lookup_entry(var_index) {
+                name_index.record(ElaboratedName {
+                    span,
+                    context: self.indexed_context(),
+                    var_index,
+                    ty: ty.clone(),
+                    is_inferred,
+                });
+            }
+        }
+    }
+
+    /// Record that a hole at the given span was elaborated to a term, if a
+    /// name index was set.
+    pub(crate) fn record_hole(
+        &self,
+        span: FileSpan,
+        meta: meta::Index,
+        term: &Rc<syntax::Term>,
+        expected_ty: &Rc<domain::Type>,
+    ) {
+        if let Some(name_index) = &self.name_index {
+            name_index.record_hole(ElaboratedHole {
+                span,
+                context: self.indexed_context(),
+                meta,
+                term: term.clone(),
+                expected_ty: expected_ty.clone(),
+            });
+        }
+    }
+
+    /// Record that an implicit argument was inserted at the given span, if a
+    /// name index was set.
+    pub(crate) fn record_implicit(
+        &self,
+        span: FileSpan,
+        label: &Label,
+        meta: meta::Index,
+        term: &Rc<syntax::Term>,
+        expected_ty: &Rc<domain::Type>,
+    ) {
+        if let Some(name_index) = &self.name_index {
+            name_index.record_implicit(ElaboratedImplicit {
+                label: label.clone(),
+                arg: ElaboratedHole {
+                    span,
+                    context: self.indexed_context(),
+                    meta,
+                    term: term.clone(),
+                    expected_ty: expected_ty.clone(),
+                },
+            });
+        }
     }
 
     /// Add a name-to-level substitution to the context.
     pub fn add_name(&mut self, name: impl Into<String>, var_level: var::Level) {
         let name = name.into();
-        self.names.add_entry(name.clone());
+        self.level_names.insert(var_level, name.clone());
+        self.opened_levels.remove(&name);
+        self.pattern_synonyms.remove(&name);
         self.names_to_levels.insert(name, var_level);
     }
 
+    /// Add a pattern synonym to the context, shadowing any bindings of the
+    /// same name.
+    pub fn add_pattern_synonym(
+        &mut self,
+        name: impl Into<String>,
+        literal_intro: LiteralIntro,
+        ty: Rc<domain::Type>,
+    ) {
+        let name = name.into();
+        log::trace!("add pattern synonym: {}", name);
+
+        self.opened_levels.remove(&name);
+        self.names_to_levels.remove(&name);
+        self.pattern_synonyms.insert(name, (literal_intro, ty));
+    }
+
+    /// Lookup the literal that a pattern synonym stands for, along with its
+    /// type.
+    pub fn lookup_pattern_synonym(&self, name: &str) -> Option<&(LiteralIntro, Rc<domain::Type>)> {
+        self.pattern_synonyms.get(name)
+    }
+
+    /// The context to use when checking the items of a namespace.
+    pub fn namespace_context(&self) -> Context {
+        Context {
+            namespaces: self.namespaces.enter(),
+            ..self.clone()
+        }
+    }
+
+    /// Add the items that were defined in the context of a namespace to this
+    /// context, under the given namespace name.
+    ///
+    /// The items will only be accessible using qualified names, until the
+    /// namespace is opened. Any items or namespaces that were re-exported
+    /// using `public open` are also added to the namespace.
+    pub fn add_namespace(&mut self, name: &str, namespace_context: Context) {
+        log::trace!("add namespace: {}", name);
+
+        let start_level = self.values.size().next_level();
+        let items = namespace_context
+            .names_to_levels
+            .iter()
+            .filter(|(_, var_level)| **var_level >= start_level)
+            .map(|(item_name, var_level)| (item_name.clone(), *var_level))
+            .collect::<im::HashMap<_, _>>();
+
+        for (item_name, var_level) in &items {
+            let qualified_name = format!("{}.{}", name, item_name);
+            self.level_names.insert(*var_level, qualified_name);
+        }
+        for (namespace_name, namespace_items) in namespace_context.namespaces.exported_namespaces()
+        {
+            let qualified_namespace_name = format!("{}.{}", name, namespace_name);
+            for (item_name, var_level) in namespace_items {
+                // Skip the items that were re-exported from elsewhere
+                if *var_level >= start_level {
+                    let qualified_name = format!("{}.{}", qualified_namespace_name, item_name);
+                    self.level_names.insert(*var_level, qualified_name);
+                }
+            }
+        }
+
+        self.values = namespace_context.values;
+        self.tys = namespace_context.tys;
+        self.string_instances = namespace_context.string_instances;
+        self.inline_items = namespace_context.inline_items;
+        (self.namespaces).add(name, items, namespace_context.namespaces);
+    }
+
+    /// Bring the items of a namespace that match the given predicate into
+    /// scope, returning `false` if the namespace could not be found.
+    ///
+    /// Namespaces nested inside the namespace are also brought into scope if
+    /// their names match the predicate. If the namespace is opened publicly,
+    /// the items are re-exported from the namespace that is currently being
+    /// checked.
+    pub fn open_namespace(
+        &mut self,
+        name: &str,
+        includes: impl Fn(&str) -> bool,
+        is_public: bool,
+    ) -> bool {
+        log::trace!("open namespace: {}", name);
+
+        match self.namespaces.open(name, includes, is_public) {
+            None => false,
+            Some(items) => {
+                for (item_name, var_level) in items {
+                    let opened_levels = self.opened_levels.entry(item_name.clone()).or_default();
+                    if !opened_levels.contains(&var_level) {
+                        opened_levels.push_back(var_level);
+                    }
+                    self.names_to_levels.insert(item_name, var_level);
+                }
+                true
+            },
+        }
+    }
+
+    /// Allow a namespace to be referred to using another name, returning
+    /// `false` if the namespace could not be found.
+    pub fn alias_namespace(&mut self, name: &str, alias: &str, is_public: bool) -> bool {
+        log::trace!("alias namespace: {} as {}", name, alias);
+
+        self.namespaces.alias(name, alias, is_public)
+    }
+
+    /// Returns `true` if a namespace with the given name was defined in the
+    /// scope that is currently being checked, and so can be reopened.
+    pub fn defines_namespace(&self, name: &str) -> bool {
+        self.namespaces.defines(name)
+    }
+
+    /// Returns `true` if a namespace with the given qualified name is in scope.
+    pub fn has_namespace(&self, name: &str) -> bool {
+        self.namespaces.contains(name)
+    }
+
+    /// Allow string literals to be elaborated using the `from-string` field of
+    /// the item at the given level.
+    pub fn add_string_instance(&mut self, var_level: var::Level) {
+        self.string_instances.push_back(var_level);
+    }
+
+    /// The levels of the items that can be used to elaborate string literals
+    /// to types other than `String`.
+    pub fn string_instances(&self) -> &im::Vector<var::Level> {
+        &self.string_instances
+    }
+
+    /// Specialize the calls to the item at the given level during elaboration.
+    pub fn add_inline_item(&mut self, var_level: var::Level) {
+        self.inline_items.insert(var_level);
+    }
+
+    /// Returns `true` if the item at the given level was marked with
+    /// `#[inline]`.
+    pub fn is_inline_item(&self, var_level: var::Level) -> bool {
+        self.inline_items.contains(&var_level)
+    }
+
     /// Add a fresh definition to the context.
     pub fn add_fresh_defn(&mut self, value: Rc<domain::Value>, ty: Rc<domain::Type>) {
         log::trace!("add fresh definition");
@@ -144,15 +672,40 @@ impl Context {
         span: FileSpan,
         ty: Rc<domain::Type>,
     ) -> Rc<syntax::Term> {
+        self.meta_spine(metas.add_unsolved(span, ty))
+    }
+
+    /// Create a fresh meta for a placeholder, which must be solved during
+    /// elaboration, and return the meta applied to all of the currently bound
+    /// vars.
+    pub fn new_placeholder(
+        &self,
+        metas: &mut meta::Env,
+        span: FileSpan,
+        ty: Rc<domain::Type>,
+    ) -> Rc<syntax::Term> {
+        self.meta_spine(metas.add_placeholder(span, ty))
+    }
+
+    /// Apply a meta to all of the currently bound vars.
+    fn meta_spine(&self, meta_index: meta::Index) -> Rc<syntax::Term> {
         let args = self.bound_levels.iter().map(|var_level| {
             let var_index = self.values().size().index(*var_level);
             Rc::from(syntax::Term::var(var_index))
         });
 
-        args.fold(
-            Rc::from(syntax::Term::Meta(metas.add_unsolved(span, ty))),
-            |acc, arg| Rc::from(syntax::Term::FunElim(acc, AppMode::Explicit, arg)),
-        )
+        args.fold(Rc::from(syntax::Term::Meta(meta_index)), |acc, arg| {
+            Rc::from(syntax::Term::FunElim(acc, AppMode::Explicit, arg))
+        })
+    }
+
+    /// The names and types of the parameters that are bound in the context,
+    /// from the outermost to the innermost.
+    pub fn params(&self) -> impl Iterator<Item = (Option<&str>, &Rc<domain::Type>)> + '_ {
+        self.bound_levels.iter().filter_map(move |var_level| {
+            let (_, ty) = self.lookup_level(*var_level)?;
+            Some((self.lookup_level_name(*var_level), ty))
+        })
     }
 
     /// Lookup the de-bruijn index and the type annotation of a binder in the
@@ -165,6 +718,82 @@ impl Context {
         Some((var_index, ty))
     }
 
+    /// Lookup the de-bruijn index and the type annotation of the binder at the
+    /// given level.
+    pub fn lookup_level(&self, var_level: var::Level) -> Option<(var::Index, &Rc<domain::Type>)> {
+        let var_index = self.values().size().index(var_level);
+        let ty = self.tys.lookup_entry(var_index)?;
+        Some((var_index, ty))
+    }
+
+    /// Lookup the name that will be used when pretty printing the binder at
+    /// the given level.
+    pub fn lookup_level_name(&self, var_level: var::Level) -> Option<&str> {
+        self.level_names.get(&var_level).map(String::as_str)
+    }
+
+    /// Lookup the levels of the items that a name could refer to, if it is
+    /// overloaded by opening several namespaces with items of the same name.
+    pub fn lookup_overloads(&self, name: &str) -> Option<&im::Vector<var::Level>> {
+        self.opened_levels
+            .get(name)
+            .filter(|var_levels| var_levels.len() > 1)
+    }
+
+    /// Lookup the item that an occurrence of an overloaded name was resolved
+    /// to, if any.
+    pub fn lookup_selected_overload(&self, span: FileSpan) -> Option<var::Level> {
+        self.selected_overloads.get(&span).cloned()
+    }
+
+    /// Resolve the occurrence of an overloaded name at the given span to the
+    /// item at the given level.
+    pub fn select_overload(&mut self, span: FileSpan, var_level: var::Level) {
+        self.selected_overloads.insert(span, var_level);
+    }
+
+    /// Lookup the de-bruijn index and the type annotation of an item in a
+    /// namespace.
+    pub fn lookup_qualified_binder(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Option<(var::Index, &Rc<domain::Type>)> {
+        let var_level = self.namespaces.lookup_item(namespace, name)?;
+        let var_index = self.values().size().index(*var_level);
+        let ty = self.tys.lookup_entry(var_index)?;
+        log::trace!(
+            "lookup qualified binder: {}.{} -> {}",
+            namespace,
+            name,
+            var_index
+        );
+        Some((var_index, ty))
+    }
+
+    /// The user-defined names in the context, along with the values that they
+    /// refer to.
+    pub(crate) fn named_values(&self) -> impl Iterator<Item = (&str, &Rc<domain::Value>)> + '_ {
+        self.names_to_levels
+            .iter()
+            .filter_map(move |(name, var_level)| {
+                let var_index = self.values().size().index(*var_level);
+                Some((name.as_str(), self.values.lookup_entry(var_index)?))
+            })
+    }
+
+    /// The names of the pattern synonyms in the context.
+    pub(crate) fn pattern_synonym_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.pattern_synonyms.keys().map(String::as_str)
+    }
+
+    /// The qualified names of the namespaces in the context, along with the
+    /// names of their items.
+    pub(crate) fn namespace_items(&self) -> impl Iterator<Item = (&str, Vec<&str>)> + '_ {
+        (self.namespaces.iter())
+            .map(|(name, items)| (name, items.keys().map(String::as_str).collect()))
+    }
+
     /// Apply a closure to an argument.
     pub fn app_closure(
         &self,
@@ -175,6 +804,38 @@ impl Context {
         nbe::app_closure(self.prims(), metas, closure, arg)
     }
 
+    /// Instantiate the first entry of a telescope with a value.
+    pub fn instantiate_telescope(
+        &self,
+        metas: &meta::Env,
+        telescope: &mut Telescope,
+        arg: Rc<domain::Value>,
+    ) -> Result<(), Diagnostic<FileSpan>> {
+        nbe::instantiate_telescope(self.prims(), metas, telescope, arg)
+    }
+
+    /// Split a record telescope at the field with the given label, projecting
+    /// the fields before it from `record`.
+    pub fn split_telescope(
+        &self,
+        metas: &meta::Env,
+        telescope: &mut Telescope,
+        record: &Rc<domain::Value>,
+        label: &Label,
+    ) -> Result<Option<Entry>, Diagnostic<FileSpan>> {
+        nbe::split_telescope(self.prims(), metas, telescope, record, label)
+    }
+
+    /// Weaken a telescope into the current context, instantiating each of its
+    /// entries with a fresh variable.
+    pub fn weaken_telescope(
+        &self,
+        metas: &meta::Env,
+        telescope: Telescope,
+    ) -> Result<(Vec<Entry>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+        nbe::weaken_telescope(self.prims(), metas, self.values().size(), telescope)
+    }
+
     /// Evaluate a term using the evaluation environment
     pub fn eval_term(
         &self,
@@ -182,7 +843,14 @@ impl Context {
         span: impl Into<Option<FileSpan>>,
         term: &Rc<syntax::Term>,
     ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-        nbe::eval_term(self.prims(), metas, self.values(), span, term)
+        nbe::eval_term(
+            self.prims(),
+            metas,
+            self.strategy,
+            self.values(),
+            span,
+            term,
+        )
     }
 
     /// Read a value back into the core syntax, normalizing as required.
@@ -195,6 +863,92 @@ impl Context {
         nbe::read_back_value(self.prims(), metas, self.values().size(), span, value)
     }
 
+    /// Read a value back into the core syntax, binding shared sub-values with
+    /// a `let` rather than duplicating them. The result is intended for
+    /// display.
+    pub fn read_back_value_shared(
+        &self,
+        metas: &meta::Env,
+        span: impl Into<Option<FileSpan>>,
+        value: &Rc<domain::Value>,
+    ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+        nbe::read_back_value_shared(self.prims(), metas, self.values().size(), span, value)
+    }
+
+    /// Read a value in weak head normal form back into the core syntax,
+    /// without evaluating the parts of it that are still unevaluated. These
+    /// are replaced with placeholders, which are returned along with the term.
+    pub fn read_back_whnf(
+        &self,
+        metas: &meta::Env,
+        span: impl Into<Option<FileSpan>>,
+        value: &Rc<domain::Value>,
+        elide_size: usize,
+    ) -> Result<ElidedTerm, Diagnostic<FileSpan>> {
+        let size = self.values().size();
+        nbe::read_back_whnf(self.prims(), metas, size, span, value, elide_size)
+    }
+
+    /// Reduce a term one redex at a time, contracting at most `fuel` redexes,
+    /// and returning each of the intermediate terms.
+    pub fn reduction_steps(
+        &self,
+        metas: &meta::Env,
+        span: impl Into<Option<FileSpan>>,
+        term: &Rc<syntax::Term>,
+        fuel: usize,
+    ) -> Result<Vec<steps::ReductionStep>, Diagnostic<FileSpan>> {
+        nbe::reduction_steps(self.prims(), metas, self.values(), span, term, fuel)
+    }
+
+    /// Expand a value of a record type into a record introduction, with its
+    /// fields recovered from the type, so that it is displayed using the
+    /// `record { l = v; ... }` syntax even if it is stuck on a variable. The
+    /// fields of the record are expanded in the same way.
+    ///
+    /// Values that need no expansion are returned as they are, so that any
+    /// sharing between them is preserved.
+    pub fn expand_record_value(
+        &self,
+        metas: &meta::Env,
+        span: impl Into<Option<FileSpan>>,
+        value: &Rc<domain::Value>,
+        ty: &Rc<domain::Type>,
+    ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
+        let span = span.into();
+        let ty = self.force_value(metas, span, ty)?;
+        match (value.as_ref(), ty.as_ref()) {
+            (domain::Value::Neutral(..), domain::Value::RecordTypeExtend(..))
+            | (domain::Value::RecordIntro(_), domain::Value::RecordTypeExtend(..)) => {
+                let mut telescope = Telescope::record(ty.clone());
+                let mut fields = Vec::new();
+                let mut is_expanded = matches!(value.as_ref(), domain::Value::Neutral(..));
+
+                while let Some(Entry {
+                    binder: Binder::Field(label),
+                    ty: field_ty,
+                    ..
+                }) = telescope.first()
+                {
+                    let field = nbe::eval_record_elim(value.clone(), &label)?;
+                    let expanded_field =
+                        self.expand_record_value(metas, span, &field, &field_ty)?;
+                    is_expanded |= !Rc::ptr_eq(&field, &expanded_field);
+                    let field = expanded_field;
+                    self.instantiate_telescope(metas, &mut telescope, field.clone())?;
+                    fields.push((label, field));
+                }
+
+                if is_expanded {
+                    Ok(Rc::from(domain::Value::RecordIntro(fields)))
+                } else {
+                    Ok(value.clone())
+                }
+            },
+            (_, _) => Ok(value.clone()),
+        }
+    }
+
     /// Fully normalize a term by first evaluating it, then reading it back.
     pub fn normalize_term(
         &self,
@@ -202,7 +956,21 @@ impl Context {
         span: impl Into<Option<FileSpan>>,
         term: &Rc<syntax::Term>,
     ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-        nbe::normalize_term(self.prims(), metas, self.values(), span, term)
+        let span = span.into();
+        let value = self.eval_term(metas, span, term)?;
+        self.read_back_value(metas, span, &value)
+    }
+
+    /// Reduce a term to weak head normal form, without reading it back.
+    pub fn whnf(
+        &self,
+        metas: &meta::Env,
+        span: impl Into<Option<FileSpan>>,
+        term: &Rc<syntax::Term>,
+    ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
+        let span = span.into();
+        let value = self.eval_term(metas, span, term)?;
+        self.force_value(metas, span, &value)
     }
 
     /// Evaluate a value further, if it's now possible due to updates made to the
@@ -217,6 +985,9 @@ impl Context {
     }
 
     /// Expect that `ty1` is a subtype of `ty2` in the current context
+    ///
+    /// If `explain_conversion` is enabled, the steps that were taken before
+    /// the values were found to be different are added to the error message.
     pub fn unify_values(
         &self,
         metas: &mut meta::Env,
@@ -224,11 +995,74 @@ impl Context {
         value1: &Rc<domain::Value>,
         value2: &Rc<domain::Value>,
     ) -> Result<(), Diagnostic<FileSpan>> {
-        unify::unify_values(self.prims(), metas, self.values(), span, value1, value2)
+        let budget = ConversionBudget::new(self.conversion_budget);
+        let mut trace = unify::Trace::new(budget, self.explain_conversion);
+        let values = self.values();
+        unify::unify_values_traced(
+            self.prims(),
+            metas,
+            values,
+            span,
+            &mut trace,
+            value1,
+            value2,
+        )
+        .map_err(|mut diagnostic| {
+            if self.explain_conversion {
+                diagnostic
+                    .message
+                    .push_str(&self.explain_steps(trace.steps()));
+            }
+            diagnostic
+        })
+    }
+
+    /// Describe the steps that were taken when unifying two values, one per
+    /// line, indented by how deeply they were nested.
+    fn explain_steps(&self, steps: &[unify::Step]) -> String {
+        use std::fmt::Write;
+        use unify::StepKind;
+
+        let mut explanation = String::from("\nconversion steps:");
+        for step in steps {
+            // Name the parameters that were introduced under binders
+            let mut env = self.pretty_env();
+            for _ in self.values().size().0..step.env_size.0 {
+                env.fresh_name(None);
+            }
+            let term1 = step
+                .term1
+                .to_display_doc(&env)
+                .group()
+                .pretty(1_000_000_000)
+                .to_string();
+            let term2 = step
+                .term2
+                .to_display_doc(&env)
+                .group()
+                .pretty(1_000_000_000)
+                .to_string();
+            let description = match step.kind {
+                StepKind::Compare => format!("compare `{}` with `{}`", term1, term2),
+                StepKind::EtaExpand => format!("eta-expand `{}` to match `{}`", term1, term2),
+                StepKind::SolveMeta(_) => format!("solve `{}` with `{}`", term1, term2),
+                StepKind::Mismatch => format!("mismatch between `{}` and `{}`", term1, term2),
+            };
+            let indent = 2 * (step.depth + 1);
+            write!(
+                explanation,
+                "\n{:indent$}{}",
+                "",
+                description,
+                indent = indent
+            )
+            .unwrap();
+        }
+        explanation
     }
 
     /// Convert a term to a pretty printable document.
-    pub fn term_to_doc(&self, term: &Rc<syntax::Term>) -> Doc<'_, BoxDoc<'_, ()>> {
+    pub fn term_to_doc(&self, term: &Rc<syntax::Term>) -> Doc<'_, BoxDoc<'_, Style>, Style> {
         term.to_display_doc(&self.pretty_env())
     }
 
@@ -237,7 +1071,7 @@ impl Context {
         &self,
         metas: &meta::Env,
         value: &Rc<domain::Value>,
-    ) -> Doc<'_, BoxDoc<'_, ()>> {
+    ) -> Doc<'_, BoxDoc<'_, Style>, Style> {
         match self.read_back_value(metas, None, value) {
             Ok(term) => term.to_display_doc(&self.pretty_env()),
             Err(_) => Doc::text("<error pretty printing>"),
@@ -247,40 +1081,7 @@ impl Context {
 
 impl Default for Context {
     fn default() -> Context {
-        use mltt_core::domain::Value;
-        use mltt_core::literal::LiteralType as LitType;
-
-        let mut context = Context::empty();
-        let u0 = Rc::from(Value::universe(0));
-        let bool = Rc::from(Value::literal_ty(LitType::Bool));
-
-        context.add_defn(
-            "String",
-            Rc::from(Value::literal_ty(LitType::String)),
-            u0.clone(),
-        );
-        context.add_defn(
-            "Char",
-            Rc::from(Value::literal_ty(LitType::Char)),
-            u0.clone(),
-        );
-        context.add_defn("Bool", bool.clone(), u0.clone());
-        context.add_defn("true", Rc::from(Value::literal_intro(true)), bool.clone());
-        context.add_defn("false", Rc::from(Value::literal_intro(false)), bool.clone());
-        context.add_defn("U8", Rc::from(Value::literal_ty(LitType::U8)), u0.clone());
-        context.add_defn("U16", Rc::from(Value::literal_ty(LitType::U16)), u0.clone());
-        context.add_defn("U32", Rc::from(Value::literal_ty(LitType::U32)), u0.clone());
-        context.add_defn("U64", Rc::from(Value::literal_ty(LitType::U64)), u0.clone());
-        context.add_defn("S8", Rc::from(Value::literal_ty(LitType::S8)), u0.clone());
-        context.add_defn("S16", Rc::from(Value::literal_ty(LitType::S16)), u0.clone());
-        context.add_defn("S32", Rc::from(Value::literal_ty(LitType::S32)), u0.clone());
-        context.add_defn("S64", Rc::from(Value::literal_ty(LitType::S64)), u0.clone());
-        context.add_defn("F32", Rc::from(Value::literal_ty(LitType::F32)), u0.clone());
-        context.add_defn("F64", Rc::from(Value::literal_ty(LitType::F64)), u0.clone());
-
-        context.prims = prim::Env::default();
-
-        context
+        Context::new(LanguageOptions::default())
     }
 }
 
@@ -352,4 +1153,153 @@ mod test {
 
         assert_eq!(context.lookup_binder("x").unwrap().1, &ty1);
     }
+
+    #[test]
+    fn pretty_env_names() {
+        use mltt_core::domain::Value;
+        use mltt_core::syntax::Term;
+
+        let mut context = Context::empty();
+
+        let ty = Rc::from(Value::universe(0));
+
+        context.add_param("x", ty.clone());
+        context.add_fresh_param(ty.clone());
+        context.add_param("x", ty.clone());
+
+        let env = context.pretty_env();
+        let display = |index| Term::var(index).to_display_doc(&env).pretty(80).to_string();
+
+        assert_eq!(display(0), "x2");
+        assert_eq!(display(1), "x1");
+        assert_eq!(display(2), "x");
+    }
+
+    #[test]
+    fn open_overloaded_names() {
+        use mltt_core::domain::Value;
+
+        let mut context = Context::empty();
+        let ty = Rc::from(Value::universe(0));
+
+        for namespace_name in &["Foo", "Bar"] {
+            let mut namespace_context = context.namespace_context();
+            namespace_context.add_param("x", ty.clone());
+            context.add_namespace(namespace_name, namespace_context);
+        }
+
+        context.open_namespace("Foo", |_| true, false);
+        assert_eq!(context.lookup_overloads("x"), None);
+        context.open_namespace("Bar", |_| true, false);
+        assert_eq!(
+            context.lookup_overloads("x"),
+            Some(&im::vector![var::Level(0), var::Level(1)]),
+        );
+        assert_eq!(context.lookup_level_name(var::Level(0)), Some("Foo.x"));
+
+        context.add_param("x", ty.clone());
+        assert_eq!(context.lookup_overloads("x"), None);
+    }
+
+    #[test]
+    fn max_universe() {
+        use mltt_parse::lexer::Lexer;
+        use mltt_parse::parser::parse_term;
+        use mltt_span::Files;
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "Type^3");
+        let term = parse_term(Lexer::new(&files[file_id])).unwrap();
+        let mut metas = meta::Env::new();
+
+        let context = Context::new(LanguageOptions {
+            max_universe: UniverseLevel(4),
+            ..LanguageOptions::default()
+        });
+        assert!(crate::synth_universe(&context, &mut metas, &term).is_ok());
+
+        let context = Context::new(LanguageOptions {
+            max_universe: UniverseLevel(3),
+            ..LanguageOptions::default()
+        });
+        let error = crate::synth_universe(&context, &mut metas, &term).unwrap_err();
+        assert_eq!(error.message, "maximum universe level reached");
+    }
+
+    #[test]
+    fn conversion_budget() {
+        use mltt_parse::lexer::Lexer;
+        use mltt_parse::parser::parse_term;
+        use mltt_span::Files;
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "fun (f : U8 -> U8 -> U8) => (f : U8 -> U8 -> U8)");
+        let term = parse_term(Lexer::new(&files[file_id])).unwrap();
+        let mut metas = meta::Env::new();
+
+        let context = Context::new(LanguageOptions::default());
+        assert!(crate::synth_term(crate::MetaInsertion::No, &context, &mut metas, &term).is_ok());
+
+        let context = Context::new(LanguageOptions {
+            conversion_budget: Some(2),
+            ..LanguageOptions::default()
+        });
+        let error =
+            crate::synth_term(crate::MetaInsertion::No, &context, &mut metas, &term).unwrap_err();
+        assert_eq!(error.message, "conversion check exceeded budget");
+    }
+
+    #[test]
+    fn explain_conversion() {
+        use mltt_parse::lexer::Lexer;
+        use mltt_parse::parser::parse_term;
+        use mltt_span::Files;
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "(fun (x : U8) => x) : U8 -> String");
+        let term = parse_term(Lexer::new(&files[file_id])).unwrap();
+        let mut metas = meta::Env::new();
+
+        let context = Context::new(LanguageOptions::default());
+        let error =
+            crate::synth_term(crate::MetaInsertion::No, &context, &mut metas, &term).unwrap_err();
+        assert_eq!(error.message, "can't unify");
+
+        let context = Context::new(LanguageOptions {
+            explain_conversion: true,
+            ..LanguageOptions::default()
+        });
+        let error =
+            crate::synth_term(crate::MetaInsertion::No, &context, &mut metas, &term).unwrap_err();
+        assert_eq!(
+            error.message,
+            "can't unify\n\
+             conversion steps:\n  \
+             compare `U8` with `String`\n    \
+             mismatch between `U8` and `String`",
+        );
+    }
+
+    #[test]
+    fn synth_term_result() {
+        use mltt_core::literal::LiteralType;
+        use mltt_parse::lexer::Lexer;
+        use mltt_parse::parser::parse_term;
+        use mltt_span::Files;
+
+        let mut files = Files::new();
+        let file_id = files.add("test", "(? : Bool)");
+        let term = parse_term(Lexer::new(&files[file_id])).unwrap();
+        let mut metas = meta::Env::new();
+
+        let context = Context::new(LanguageOptions::default());
+        let synthesized =
+            crate::synth_term(crate::MetaInsertion::Yes, &context, &mut metas, &term).unwrap();
+        assert_eq!(
+            *synthesized.ty_term,
+            syntax::Term::LiteralType(LiteralType::Bool),
+        );
+        assert_eq!(synthesized.unsolved_metas.len(), 1);
+        assert_eq!(synthesized.unsolved_metas[0].0, meta::Index(0));
+    }
 }
@@ -0,0 +1,161 @@
+//! An index of the names, holes, and implicit arguments that were elaborated,
+//! for use in editor tooling.
+//!
+//! Recording is enabled by setting a [`NameIndex`] on the elaboration context
+//! before checking a module. Each variable that is elaborated, each item that
+//! is defined, each hole that is found, and each implicit argument that is
+//! inserted, is then recorded along with the context that it was elaborated
+//! in, so that its type and definition can be displayed later on.
+
+use language_reporting::Diagnostic;
+use mltt_core::{domain, meta, syntax, var, Label, Rc};
+use mltt_span::{ByteIndex, FileId, FileSpan};
+use std::sync::{Arc, Mutex};
+
+use crate::Context;
+
+/// A name in the concrete syntax, along with the entry in the context that it
+/// was elaborated to.
+#[derive(Debug, Clone)]
+pub struct ElaboratedName {
+    /// The span of the name.
+    pub span: FileSpan,
+    /// The context that the name was elaborated in.
+    pub context: Context,
+    /// The entry in the context that the name refers to.
+    pub var_index: var::Index,
+    /// The type of the name.
+    pub ty: Rc<domain::Type>,
+    /// Whether this is the definition of an item without a type annotation,
+    /// whose type had to be inferred.
+    pub is_inferred: bool,
+}
+
+impl ElaboratedName {
+    /// The value that the name refers to. This will be a variable if the name
+    /// refers to a parameter.
+    pub fn value(&self) -> Option<&Rc<domain::Value>> {
+        self.context.values().lookup_entry(self.var_index)
+    }
+}
+
+/// A hole in the concrete syntax, for example `?`.
+#[derive(Debug, Clone)]
+pub struct ElaboratedHole {
+    /// The span of the hole.
+    pub span: FileSpan,
+    /// The context that the hole was elaborated in.
+    pub context: Context,
+    /// The metavariable that was created for the hole.
+    pub meta: meta::Index,
+    /// The term that the hole was elaborated to. This is the metavariable,
+    /// applied to the variables that were bound in the context.
+    pub term: Rc<syntax::Term>,
+    /// The type that was expected for the hole.
+    pub expected_ty: Rc<domain::Type>,
+}
+
+impl ElaboratedHole {
+    /// The normalized solution of the hole, if its metavariable was solved.
+    pub fn solution(
+        &self,
+        metas: &meta::Env,
+    ) -> Result<Option<Rc<syntax::Term>>, Diagnostic<FileSpan>> {
+        match metas.lookup_solution(self.meta) {
+            Some((_, meta::Solution::Solved(_), _)) => {
+                let term = self.context.normalize_term(metas, self.span, &self.term)?;
+                Ok(Some(term))
+            },
+            Some((_, meta::Solution::Unsolved, _)) | None => Ok(None),
+        }
+    }
+}
+
+/// An implicit argument that was inserted during elaboration.
+///
+/// The argument is a metavariable, so it is described in the same way as a
+/// hole, located at the end of the term that it was applied to.
+#[derive(Debug, Clone)]
+pub struct ElaboratedImplicit {
+    /// The label of the implicit parameter.
+    pub label: Label,
+    /// The argument that was inserted.
+    pub arg: ElaboratedHole,
+}
+
+/// The names, holes, and implicit arguments that were elaborated.
+///
+/// This is shared between the clones of the context that are made when
+/// entering into new scopes, so that the names elaborated in those scopes are
+/// recorded as well.
+#[derive(Debug, Clone, Default)]
+pub struct NameIndex {
+    names: Arc<Mutex<Vec<ElaboratedName>>>,
+    holes: Arc<Mutex<Vec<ElaboratedHole>>>,
+    implicits: Arc<Mutex<Vec<ElaboratedImplicit>>>,
+}
+
+impl NameIndex {
+    /// Create a new, empty index.
+    pub fn new() -> NameIndex {
+        NameIndex::default()
+    }
+
+    /// Record an elaborated name.
+    pub(crate) fn record(&self, name: ElaboratedName) {
+        self.names.lock().unwrap().push(name);
+    }
+
+    /// Record an elaborated hole.
+    pub(crate) fn record_hole(&self, hole: ElaboratedHole) {
+        self.holes.lock().unwrap().push(hole);
+    }
+
+    /// The holes that have been recorded, in the order that they were
+    /// elaborated.
+    pub fn holes(&self) -> Vec<ElaboratedHole> {
+        self.holes.lock().unwrap().clone()
+    }
+
+    /// Record an inserted implicit argument.
+    pub(crate) fn record_implicit(&self, implicit: ElaboratedImplicit) {
+        self.implicits.lock().unwrap().push(implicit);
+    }
+
+    /// The implicit arguments that have been recorded, in the order that they
+    /// were inserted.
+    pub fn implicits(&self) -> Vec<ElaboratedImplicit> {
+        self.implicits.lock().unwrap().clone()
+    }
+
+    /// The names that have been recorded, in the order that they were
+    /// elaborated.
+    pub fn names(&self) -> Vec<ElaboratedName> {
+        self.names.lock().unwrap().clone()
+    }
+
+    /// Lookup the name that was elaborated at the given span.
+    ///
+    /// Names may be elaborated more than once, for example when deciding
+    /// which item an overloaded name refers to, so the most recently recorded
+    /// name is returned.
+    pub fn lookup(&self, span: FileSpan) -> Option<ElaboratedName> {
+        let names = self.names.lock().unwrap();
+        names.iter().rev().find(|name| name.span == span).cloned()
+    }
+
+    /// Lookup the innermost name that covers the given byte index in a file.
+    pub fn lookup_index(
+        &self,
+        file_id: FileId,
+        byte: impl Into<ByteIndex>,
+    ) -> Option<ElaboratedName> {
+        let byte = byte.into();
+        let names = self.names.lock().unwrap();
+        (names.iter().rev())
+            .filter(|name| name.span.source() == file_id)
+            .filter(|name| name.span.start() <= byte && byte <= name.span.end())
+            .min_by_key(|name| name.span.len())
+            .cloned()
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Performs the following:
 //!
-//! - name resolution
+//! - name resolution (see the [`resolve`] module)
 //! - desugaring
 //! - pattern compilation (TODO)
 //! - bidirectional type checking
@@ -11,34 +11,78 @@
 #![warn(rust_2018_idioms)]
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_concrete::{Arg, Item, SpannedString, Term, TypeParam};
-use mltt_core::literal::{LiteralIntro, LiteralType};
-use mltt_core::{domain, meta, prim, syntax, AppMode, DocString, Label, UniverseLevel};
+use mltt_concrete::literal::decode_string;
+use mltt_concrete::{Arg, Imports, Item, LiteralKind, Module, SpannedString, Term, TypeParam};
+use mltt_core::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use mltt_core::signature::ModuleSignature;
+use mltt_core::telescope::{Binder, Entry, Telescope};
+use mltt_core::{
+    domain, meta, prim, steps, syntax, var, AppMode, DocString, Label, Rc, UniverseLevel,
+};
 use mltt_span::FileSpan;
-use std::rc::Rc;
 
+use crate::attribute::ItemAttribute;
 use crate::clause::{CaseClause, Clause};
-pub use crate::context::Context;
+pub use crate::context::{Context, LanguageOptions};
+pub use crate::index::{ElaboratedHole, ElaboratedImplicit, ElaboratedName, NameIndex};
+pub use crate::literal::{defaulting_warnings, solve_pending_literals};
+pub use crate::resolve::{resolve_items, resolve_term, NameKind, Resolution, ResolvedName};
 
+mod attribute;
 mod clause;
 mod context;
+mod index;
 mod literal;
+mod namespace;
 mod nbe;
+mod record;
+mod resolve;
 mod unify;
 
+/// Determine the language options to use when elaborating a module, by
+/// overriding the given defaults with the `#![options(...)]` attributes of the
+/// module.
+pub fn module_options(
+    defaults: LanguageOptions,
+    concrete_module: &Module<'_>,
+) -> Result<LanguageOptions, Diagnostic<FileSpan>> {
+    attribute::check_module_attributes(defaults, &concrete_module.attributes)
+}
+
 /// Check that this is a valid module.
 ///
-/// Returns the elaborated module.
+/// Returns the elaborated module, along with its signature.
 pub fn check_module(
     context: &Context,
     metas: &mut meta::Env,
     concrete_items: &[Item<'_>],
-) -> Result<syntax::Module, Diagnostic<FileSpan>> {
+) -> Result<(syntax::Module, ModuleSignature), Diagnostic<FileSpan>> {
     // The local elaboration context
     let mut context = context.clone();
-    let items = check_items(&mut context, metas, concrete_items)?;
+    load_module(&mut context, metas, concrete_items)
+}
+
+/// Check that this is a valid module, adding its items to the context so that
+/// later terms can refer to them.
+///
+/// Returns the elaborated module, along with its signature. If an error is
+/// found, the items before it will have already been added to the context.
+pub fn load_module(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    concrete_items: &[Item<'_>],
+) -> Result<(syntax::Module, ModuleSignature), Diagnostic<FileSpan>> {
+    // Report any unbound names before we begin type checking
+    let resolution = resolve_items(context, concrete_items);
+    if let Some(error) = resolution.errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let start_meta = metas.next_index();
+    let (items, signature) = check_items(context, metas, concrete_items)?;
+    check_placeholders(metas, start_meta)?;
 
-    Ok(syntax::Module { items })
+    Ok((syntax::Module { items }, signature))
 }
 
 /// Concatenate a bunch of lines of documentation into a single string, removing
@@ -51,31 +95,53 @@ fn concat_docs(doc_lines: &[SpannedString<'_>]) -> DocString {
         doc.push_str(match doc_line.slice {
             doc_line if doc_line.starts_with("||| ") => &doc_line["||| ".len()..],
             doc_line if doc_line.starts_with("|||") => &doc_line["|||".len()..],
-            doc_line => &doc_line[..],
+            doc_line => doc_line,
         });
     }
     DocString::from(doc)
 }
 
+/// Check that the placeholders created from the metavariable at `start`
+/// onwards have been solved.
+pub fn check_placeholders(
+    metas: &meta::Env,
+    start: meta::Index,
+) -> Result<(), Diagnostic<FileSpan>> {
+    match metas.unsolved_placeholders(start).next() {
+        None => Ok(()),
+        Some((_, span)) => Err(
+            Diagnostic::new_error("unable to infer placeholder").with_label(
+                DiagnosticLabel::new_primary(span).with_message("type annotations needed here"),
+            ),
+        ),
+    }
+}
+
 /// Check the given items and add them to the context.
 ///
-/// Returns the elaborated items.
+/// Returns the elaborated items, along with their signature.
 fn check_items(
     context: &mut Context,
     metas: &mut meta::Env,
     concrete_items: &[Item<'_>],
-) -> Result<Vec<syntax::Item>, Diagnostic<FileSpan>> {
+) -> Result<(Vec<syntax::Item>, ModuleSignature), Diagnostic<FileSpan>> {
     // Declarations that may be waiting to be defined
     let mut forward_declarations = im::HashMap::new();
+    // The attributes of the declarations, to be applied to their definitions
+    let mut declaration_attributes = im::HashMap::new();
     // The elaborated items
     let mut core_items = {
         let expected_defn_count = concrete_items.iter().filter(|i| i.is_definition()).count();
         Vec::with_capacity(expected_defn_count)
     };
+    // The signature of the elaborated items
+    let mut signature = ModuleSignature::new();
 
     for concrete_item in concrete_items {
         use im::hashmap::Entry;
 
+        let item_start_meta = metas.next_index();
+
         match concrete_item {
             Item::Declaration(declaration) => {
                 let label = declaration.label.slice;
@@ -83,6 +149,9 @@ fn check_items(
 
                 log::trace!("checking declaration:\t\t{}\t: {}", label, concrete_body_ty);
 
+                let attributes = attribute::check_item_attributes(&declaration.attributes)?;
+                log::trace!("declaration attributes:\t{}\t: {:?}", label, attributes);
+
                 match forward_declarations.entry(label) {
                     // No previous declaration for this name was seen, so we can
                     // go-ahead and type check, elaborate, and then add it to
@@ -90,7 +159,7 @@ fn check_items(
                     Entry::Vacant(entry) => {
                         let docs = concat_docs(&declaration.docs);
                         let label = Label(label.to_owned());
-                        let (body_ty, _) = synth_universe(&context, metas, &concrete_body_ty)?;
+                        let (body_ty, _) = synth_universe(context, metas, concrete_body_ty)?;
                         // Ensure that we evaluate the forward declaration in
                         // the current context - if we wait until later more
                         // definitions might have come in to scope!
@@ -99,8 +168,14 @@ fn check_items(
 
                         log::trace!("elaborated declaration:\t{}\t: {:?}", label, body_ty);
 
+                        signature.add_declaration(
+                            docs.clone(),
+                            label.clone(),
+                            body_ty_value.clone(),
+                        );
                         core_items.push(syntax::Item::Declaration(docs, label, body_ty));
                         entry.insert(Some(body_ty_value));
+                        declaration_attributes.insert(declaration.label.slice, attributes);
                     },
                     // There's a declaration for this name already pending - we
                     // can't add a new one!
@@ -118,16 +193,27 @@ fn check_items(
 
                 log::trace!("checking definition:\t\t{}\t= {}", label, body);
 
-                let (term, term_span, ty) = match forward_declarations.entry(label) {
+                let attributes = attribute::check_item_attributes(&definition.attributes)?;
+                log::trace!("definition attributes:\t{}\t: {:?}", label, attributes);
+
+                let is_inferred = body_ty.is_none();
+                let (term, term_span, ty, is_inferred) = match forward_declarations.entry(label) {
                     // No prior declaration was found, so we'll try synthesizing
                     // its type instead
+                    Entry::Vacant(_) if definition.termination_by.is_some() => {
+                        return Err(Diagnostic::new_error("missing type declaration").with_label(
+                            DiagnosticLabel::new_primary(definition.label.span()).with_message(
+                                "definitions with `termination-by` clauses need a declared type",
+                            ),
+                        ));
+                    },
                     Entry::Vacant(entry) => {
                         let clause = Clause::new(params, body_ty, body);
-                        let (term, ty) = clause::synth_clause(&context, metas, clause)?;
+                        let (term, ty) = clause::synth_clause(context, metas, clause)?;
 
                         entry.insert(None);
 
-                        (term, body.span(), ty)
+                        (term, body.span(), ty, is_inferred)
                     },
                     // Something has happened with this declaration, let's
                     // 'take' a look!
@@ -135,10 +221,17 @@ fn check_items(
                         // We found a prior declaration, so we'll use it as a
                         // basis for checking the definition
                         Some(ty) => {
-                            let clause = Clause::new(params, body_ty, body);
-                            let term = clause::check_clause(&context, metas, clause, &ty)?;
-
-                            (term, body.span(), ty)
+                            let term = match &definition.termination_by {
+                                None => {
+                                    let clause = Clause::new(params, body_ty, body);
+                                    clause::check_clause(context, metas, clause, &ty)?
+                                },
+                                Some(proof) => clause::check_recursive_clause(
+                                    context, metas, definition, proof, &ty,
+                                )?,
+                            };
+
+                            (term, body.span(), ty, false)
                         },
                         // This declaration was already given a definition, so
                         // this is an error!
@@ -160,13 +253,294 @@ fn check_items(
                 let docs = concat_docs(&definition.docs);
                 let value = context.eval_term(metas, term_span, &term)?;
 
-                context.add_defn(label.to_string(), value, ty);
+                let var_level = context.values().size().next_level();
+                context.add_defn(label.to_string(), value.clone(), ty.clone());
+                if is_inferred {
+                    context.record_inferred_defn(definition.label.span());
+                } else {
+                    context.record_name(definition.label.span(), var::Index(0));
+                }
+                signature.add_definition(docs.clone(), label.clone(), ty, value.clone());
                 core_items.push(syntax::Item::Definition(docs, label, term));
+
+                // Derive a namespace of helpers for record type definitions
+                if context.derive_record_helpers()
+                    && record::record_type_fields(definition).is_some()
+                {
+                    let name = definition.label.slice;
+                    let span = definition.label.span();
+                    let mut namespace_context = context.namespace_context();
+                    let (helper_items, helper_signature) = record::derive_helpers(
+                        &mut namespace_context,
+                        metas,
+                        span,
+                        name,
+                        var_level,
+                        &value,
+                    )?;
+                    context.add_namespace(name, namespace_context);
+                    core_items.extend(qualify_items(name, helper_items));
+                    signature.add_namespace(name, helper_signature);
+                }
+
+                let attributes = (attributes.iter())
+                    .chain(
+                        declaration_attributes
+                            .get(definition.label.slice)
+                            .into_iter()
+                            .flatten(),
+                    )
+                    .collect::<Vec<_>>();
+                if attributes.contains(&&ItemAttribute::Inline) {
+                    context.add_inline_item(var_level);
+                }
+                if attributes.contains(&&ItemAttribute::FromString) {
+                    let span = definition.label.span();
+                    literal::check_string_instance(context, metas, span, var_level)?;
+                    context.add_string_instance(var_level);
+                }
+            },
+            Item::Section(section) => {
+                log::trace!("checking section");
+
+                // Namespaced items can't be abstracted over the parameters of
+                // the section without losing their qualified names
+                if let Some(Item::Namespace(namespace)) =
+                    (section.items.iter()).find(|item| matches!(item, Item::Namespace(_)))
+                {
+                    let message = "namespaces are not supported inside sections";
+                    return Err(Diagnostic::new_error(message)
+                        .with_label(DiagnosticLabel::new_primary(namespace.name.span())));
+                }
+
+                let span = section.span;
+                let mut section_context = context.clone();
+                // The helpers derived for record types would also be namespaced
+                section_context.set_derive_record_helpers(false);
+                let (params, _) = check_type_params(&mut section_context, metas, &section.params)?;
+                // The items of the section are added to this context again as
+                // they are abstracted over the parameters
+                let mut item_context = section_context.clone();
+                let (section_items, _) = check_items(&mut section_context, metas, &section.items)?;
+                // The items that come before the current item in the section,
+                // which are bound again inside the abstracted item
+                let mut prefix_items = Vec::with_capacity(section_items.len());
+
+                for section_item in section_items {
+                    match &section_item {
+                        syntax::Item::Declaration(docs, label, body_ty) => {
+                            let body_ty = abstract_section_ty(&params, &prefix_items, body_ty);
+
+                            log::trace!("abstracted declaration:\t{}\t: {:?}", label, body_ty);
+
+                            let ty = context.eval_term(metas, span, &body_ty)?;
+                            signature.add_declaration(docs.clone(), label.clone(), ty);
+                            core_items.push(syntax::Item::Declaration(
+                                docs.clone(),
+                                label.clone(),
+                                body_ty,
+                            ));
+                        },
+                        syntax::Item::Definition(docs, label, term) => {
+                            let ty = match section_context.lookup_binder(&label.0) {
+                                Some((_, ty)) => ty.clone(),
+                                None => {
+                                    let message = format!("section item `{}` not in scope", label);
+                                    return Err(Diagnostic::new_bug(message)
+                                        .with_label(DiagnosticLabel::new_primary(span)));
+                                },
+                            };
+                            let body_ty = item_context.read_back_value(metas, span, &ty)?;
+                            let value = item_context.eval_term(metas, span, term)?;
+                            item_context.add_defn(label.to_string(), value, ty);
+
+                            let body_ty = abstract_section_ty(&params, &prefix_items, &body_ty);
+                            let term = abstract_section_term(&params, &prefix_items, term);
+
+                            log::trace!("abstracted definition:\t{}\t= {:?}", label, term);
+
+                            let ty = context.eval_term(metas, span, &body_ty)?;
+                            let value = context.eval_term(metas, span, &term)?;
+                            let term = Rc::from(syntax::Term::ann(term, body_ty));
+
+                            context.add_defn(label.to_string(), value.clone(), ty.clone());
+                            signature.add_definition(docs.clone(), label.clone(), ty, value);
+                            core_items.push(syntax::Item::Definition(
+                                docs.clone(),
+                                label.clone(),
+                                term,
+                            ));
+                        },
+                    }
+
+                    prefix_items.push(section_item);
+                }
+            },
+            Item::Namespace(namespace) => {
+                let name = namespace.name.slice;
+
+                log::trace!("checking namespace:\t{}", name);
+
+                let is_reopened = context.defines_namespace(name);
+                let mut namespace_context = context.namespace_context();
+                if is_reopened {
+                    // The items of a reopened namespace remain in scope
+                    namespace_context.open_namespace(name, |_| true, false);
+                    for label in namespace.items.iter().filter_map(item_label) {
+                        if context.lookup_qualified_binder(name, label.slice).is_some() {
+                            return Err(Diagnostic::new_error("already defined")
+                                .with_label(DiagnosticLabel::new_primary(label.span())));
+                        }
+                    }
+                }
+                let (namespace_items, namespace_signature) =
+                    check_items(&mut namespace_context, metas, &namespace.items)?;
+                context.add_namespace(name, namespace_context);
+                core_items.extend(qualify_items(name, namespace_items));
+                signature.add_namespace(name, namespace_signature);
+            },
+            Item::Open(open) => {
+                let name = open.name.slice;
+
+                log::trace!("opening namespace:\t{}", name);
+
+                if !context.has_namespace(name) {
+                    let message = format!("unknown namespace `{}`", name);
+                    return Err(Diagnostic::new_error(message).with_label(
+                        DiagnosticLabel::new_primary(open.name.span())
+                            .with_message("not found in this scope"),
+                    ));
+                }
+                for item_name in open.imports.item_names() {
+                    let nested_name = format!("{}.{}", name, item_name);
+                    if context
+                        .lookup_qualified_binder(name, item_name.slice)
+                        .is_none()
+                        && !context.has_namespace(&nested_name)
+                    {
+                        let message = format!("item not found in namespace `{}`", name);
+                        return Err(Diagnostic::new_error(message)
+                            .with_label(DiagnosticLabel::new_primary(item_name.span())));
+                    }
+                }
+
+                match &open.imports {
+                    Imports::Alias(alias) => {
+                        context.alias_namespace(name, alias.slice, open.is_public)
+                    },
+                    imports => {
+                        let includes = |item: &str| imports.includes(item);
+                        context.open_namespace(name, includes, open.is_public)
+                    },
+                };
+            },
+            Item::PatternSynonym(synonym) => {
+                let name = synonym.label.slice;
+
+                log::trace!("checking pattern synonym:\t{}", name);
+
+                let span = synonym.pattern.span();
+                let (term, ty) = clause::synth_synonym_pattern(context, metas, &synonym.pattern)?;
+                // The literal must be known before it can be matched against
+                literal::solve_pending_literals(context, metas, item_start_meta)?;
+                let value = context.eval_term(metas, span, &term)?;
+
+                match context.force_value(metas, span, &value)?.as_ref() {
+                    domain::Value::LiteralIntro(literal_intro) => {
+                        context.add_pattern_synonym(name, literal_intro.clone(), ty);
+                    },
+                    _ => {
+                        let message = "pattern synonym did not evaluate to a literal";
+                        return Err(Diagnostic::new_bug(message)
+                            .with_label(DiagnosticLabel::new_primary(span)));
+                    },
+                }
             },
         }
+
+        // Numeric literals whose types are still unknown are defaulted at the
+        // end of each item
+        literal::solve_pending_literals(context, metas, item_start_meta)?;
     }
 
-    Ok(core_items)
+    Ok((core_items, signature))
+}
+
+/// Qualify the labels of the items of a namespace, so that they don't clash
+/// with the items outside of the namespace.
+fn qualify_items(name: &str, items: Vec<syntax::Item>) -> impl Iterator<Item = syntax::Item> + '_ {
+    items.into_iter().map(move |item| match item {
+        syntax::Item::Declaration(docs, label, body_ty) => {
+            let label = Label(format!("{}.{}", name, label));
+            syntax::Item::Declaration(docs, label, body_ty)
+        },
+        syntax::Item::Definition(docs, label, term) => {
+            let label = Label(format!("{}.{}", name, label));
+            syntax::Item::Definition(docs, label, term)
+        },
+    })
+}
+
+/// The label of an item that is declared or defined directly in a namespace.
+fn item_label<'a, 'file>(item: &'a Item<'file>) -> Option<&'a SpannedString<'file>> {
+    match item {
+        Item::Declaration(declaration) => Some(&declaration.label),
+        Item::Definition(definition) => Some(&definition.label),
+        Item::Section(_) | Item::Namespace(_) | Item::Open(_) | Item::PatternSynonym(_) => None,
+    }
+}
+
+/// Abstract the type of a section item over the parameters of the section,
+/// binding the items that came before it.
+fn abstract_section_ty(
+    params: &[CheckedTypeParam],
+    prefix_items: &[syntax::Item],
+    ty: &Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    let ty = bind_section_items(prefix_items, ty);
+
+    params
+        .iter()
+        .rev()
+        .fold(ty, |acc, (doc, app_mode, name_hint, param_ty)| {
+            Rc::from(syntax::Term::FunType(
+                doc.clone(),
+                app_mode.clone(),
+                name_hint.clone(),
+                param_ty.clone(),
+                acc,
+            ))
+        })
+}
+
+/// Abstract the term of a section item over the parameters of the section,
+/// binding the items that came before it.
+fn abstract_section_term(
+    params: &[CheckedTypeParam],
+    prefix_items: &[syntax::Item],
+    term: &Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    let term = bind_section_items(prefix_items, term);
+
+    params
+        .iter()
+        .rev()
+        .fold(term, |acc, (_, app_mode, name_hint, _)| {
+            Rc::from(syntax::Term::FunIntro(
+                app_mode.clone(),
+                name_hint.clone(),
+                acc,
+            ))
+        })
+}
+
+/// Bind the items that came before an item in a section.
+fn bind_section_items(prefix_items: &[syntax::Item], term: &Rc<syntax::Term>) -> Rc<syntax::Term> {
+    if prefix_items.is_empty() {
+        term.clone()
+    } else {
+        Rc::from(syntax::Term::Let(prefix_items.to_vec(), term.clone()))
+    }
 }
 
 /// Ensures that the given term is a universe, returning the level of that
@@ -176,18 +550,152 @@ pub fn synth_universe(
     metas: &mut meta::Env,
     concrete_term: &Term<'_>,
 ) -> Result<(Rc<syntax::Term>, UniverseLevel), Diagnostic<FileSpan>> {
-    let (term, ty) = synth_term(MetaInsertion::Yes, context, metas, concrete_term)?;
+    if let Term::Parens(_, concrete_term) = concrete_term {
+        return synth_universe(context, metas, concrete_term);
+    }
+    // Placeholders in type positions can only stand for types
+    if let Term::Placeholder(span) = concrete_term {
+        let level = UniverseLevel(0);
+        let ty = Rc::from(domain::Value::universe(level));
+        return Ok((context.new_placeholder(metas, *span, ty), level));
+    }
+    // The types of eliminations can't be synthesized, so we assume that they
+    // stand for types in the lowest universe, as is done for placeholders
+    if let Term::If(..) | Term::Case(..) | Term::With(..) = concrete_term {
+        let level = UniverseLevel(0);
+        let ty = Rc::from(domain::Value::universe(level));
+        return Ok((check_term(context, metas, concrete_term, &ty)?, level));
+    }
+
+    let (term, ty) = synth(MetaInsertion::Yes, context, metas, concrete_term)?;
+    let ty = context.force_value(metas, concrete_term.span(), &ty)?;
     match ty.as_ref() {
         domain::Value::Universe(level) => Ok((term, *level)),
+        // The type of the term is still waiting to be solved (for example if
+        // it was bound with a placeholder type), so we assume the lowest level
+        domain::Value::Neutral(domain::Head::Meta(_), _) => {
+            let level = UniverseLevel(0);
+            let universe = Rc::from(domain::Value::universe(level));
+            context.unify_values(metas, concrete_term.span(), &universe, &ty)?;
+            Ok((term, level))
+        },
         _ => Err(Diagnostic::new_error("type expected").with_label(
             DiagnosticLabel::new_primary(concrete_term.span()).with_message(format!(
                 "found `{}`",
-                context.value_to_doc(metas, &ty).pretty(1000_000_000),
+                context.value_to_doc(metas, &ty).pretty(1_000_000_000),
             )),
         )),
     }
 }
 
+/// An elaborated function type parameter.
+type CheckedTypeParam = (DocString, AppMode, Option<String>, Rc<syntax::Term>);
+
+/// Check the parameters of a function type, adding them to the context.
+///
+/// Returns the elaborated parameters, along with the maximum universe level of
+/// their types.
+fn check_type_params(
+    context: &mut Context,
+    metas: &mut meta::Env,
+    concrete_params: &[TypeParam<'_>],
+) -> Result<(Vec<CheckedTypeParam>, UniverseLevel), Diagnostic<FileSpan>> {
+    use std::cmp;
+
+    let mut param_tys = Vec::new();
+    let mut max_level = UniverseLevel(0);
+
+    for param in concrete_params {
+        match param {
+            TypeParam::Explicit(_, docs, param_names, concrete_param_ty) => {
+                let doc = concat_docs(docs);
+                let param_ty_span = concrete_param_ty.span();
+                let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+                max_level = cmp::max(max_level, level);
+
+                for (index, param_name) in param_names.iter().enumerate() {
+                    let app_mode = AppMode::Explicit;
+                    let param_ty = match index {
+                        0 => param_ty.clone(),
+                        // Grouped parameters share the same type, but the
+                        // earlier ones are now bound in the context, so we
+                        // need to read it back at the current depth
+                        _ => context.read_back_value(metas, param_ty_span, &param_ty_value)?,
+                    };
+
+                    context.add_param(param_name, param_ty_value.clone());
+                    param_tys.push((
+                        doc.clone(),
+                        app_mode,
+                        Some(param_name.to_string()),
+                        param_ty,
+                    ));
+                }
+            },
+            TypeParam::Implicit(_, docs, param_labels, concrete_param_ty) => {
+                let doc = concat_docs(docs);
+                let shared_param_ty = match concrete_param_ty {
+                    None => None,
+                    Some(concrete_param_ty) => {
+                        let param_ty_span = concrete_param_ty.span();
+                        let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                        let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+                        max_level = cmp::max(max_level, level);
+
+                        Some((param_ty_span, param_ty, param_ty_value))
+                    },
+                };
+
+                for (index, param_label) in param_labels.iter().enumerate() {
+                    let app_mode = AppMode::Implicit(Label(param_label.to_string()));
+                    let (param_ty, param_ty_value) = match &shared_param_ty {
+                        Some((_, param_ty, param_ty_value)) if index == 0 => {
+                            (param_ty.clone(), param_ty_value.clone())
+                        },
+                        Some((param_ty_span, _, param_ty_value)) => {
+                            let param_ty =
+                                context.read_back_value(metas, *param_ty_span, param_ty_value)?;
+                            (param_ty, param_ty_value.clone())
+                        },
+                        // Parameters without annotations are given their own
+                        // placeholders, to be solved by unification
+                        None => {
+                            let span = param_label.span();
+                            let universe = domain::Value::universe(UniverseLevel(0));
+                            max_level = cmp::max(max_level, UniverseLevel(1));
+                            let param_ty = context.new_placeholder(metas, span, Rc::from(universe));
+                            let param_ty_value = context.eval_term(metas, span, &param_ty)?;
+                            (param_ty, param_ty_value)
+                        },
+                    };
+
+                    context.add_param(param_label, param_ty_value);
+                    param_tys.push((
+                        doc.clone(),
+                        app_mode,
+                        Some(param_label.to_string()),
+                        param_ty,
+                    ));
+                }
+            },
+            TypeParam::Instance(_, docs, param_label, concrete_param_ty) => {
+                let doc = concat_docs(docs);
+                let app_mode = AppMode::Instance(Label(param_label.to_string()));
+                let param_ty_span = concrete_param_ty.span();
+                let (param_ty, level) = synth_universe(context, metas, concrete_param_ty)?;
+                let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
+
+                context.add_param(param_label, param_ty_value);
+                param_tys.push((doc, app_mode, Some(param_label.to_string()), param_ty));
+                max_level = cmp::max(max_level, level);
+            },
+        }
+    }
+
+    Ok((param_tys, max_level))
+}
+
 /// Check that a given term conforms to an expected type.
 ///
 /// Returns the elaborated term.
@@ -201,18 +709,30 @@ pub fn check_term(
 
     match concrete_term {
         Term::Prim(_, name) => {
-            let prim_name = prim::Name(literal::parse_string(name)?);
+            let prim_name = prim::Name(decode_string(name)?.value);
             match context.prims().lookup_entry(&prim_name) {
                 None => Err(Diagnostic::new_error("unknown primitive")
                     .with_label(DiagnosticLabel::new_primary(name.span()))),
-                Some(_) => Ok(Rc::from(syntax::Term::prim(prim_name))),
+                Some(prim::Entry { ty: None, .. }) => Ok(Rc::from(syntax::Term::prim(prim_name))),
+                Some(prim::Entry { ty: Some(ty), .. }) => {
+                    let span = concrete_term.span();
+                    let ty = context.eval_term(metas, span, ty)?;
+                    context.unify_values(metas, span, &ty, expected_ty)?;
+                    Ok(Rc::from(syntax::Term::prim(prim_name)))
+                },
             }
         },
-        Term::Hole(span) => Ok(context.new_meta(metas, *span, expected_ty.clone())),
+        Term::Hole(span) => {
+            let meta = metas.next_index();
+            let term = context.new_meta(metas, *span, expected_ty.clone());
+            context.record_hole(*span, meta, &term, expected_ty);
+            Ok(term)
+        },
+        Term::Placeholder(span) => Ok(context.new_placeholder(metas, *span, expected_ty.clone())),
         Term::Parens(_, concrete_term) => check_term(context, metas, concrete_term, expected_ty),
         Term::Let(_, concrete_items, concrete_body) => {
             let mut context = context.clone();
-            let items = check_items(&mut context, metas, concrete_items)?;
+            let (items, _) = check_items(&mut context, metas, concrete_items)?;
             let body = check_term(&context, metas, concrete_body, expected_ty)?;
 
             Ok(Rc::from(syntax::Term::Let(items, body)))
@@ -225,22 +745,61 @@ pub fn check_term(
 
             Ok(Rc::from(syntax::Term::LiteralElim(
                 condition,
-                Rc::from(vec![(LiteralIntro::Bool(true), consequent)]),
+                Rc::from(vec![(
+                    LiteralPattern::from(LiteralIntro::Bool(true)),
+                    consequent,
+                )]),
                 alternative,
             )))
         },
         Term::Case(span, scrutinee, clauses) => {
             let clauses = clauses
                 .iter()
-                .map(|(pattern, body)| CaseClause::new(pattern, body))
+                .map(|(pattern, guard, body)| CaseClause::new(pattern, guard.as_ref(), body))
                 .collect();
 
             clause::check_case(context, metas, *span, scrutinee, clauses, expected_ty)
         },
+        Term::With(span, scrutinee, clauses) => {
+            clause::check_with(context, metas, *span, scrutinee, clauses, expected_ty)
+        },
 
         Term::LiteralIntro(kind, literal) => {
-            let literal_intro = literal::check(context, metas, *kind, literal, expected_ty)?;
-            Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+            let expected_ty = context.force_value(metas, literal.span(), expected_ty)?;
+            match expected_ty.as_ref() {
+                // The expected type is still waiting to be solved, so we try
+                // to infer it from the literal instead
+                domain::Value::Neutral(domain::Head::Meta(_), _) => match kind {
+                    LiteralKind::Int | LiteralKind::Float => {
+                        literal::postpone(context, metas, *kind, literal, expected_ty)
+                    },
+                    LiteralKind::String | LiteralKind::Char => {
+                        let (term, ty) = literal::synth(context, metas, *kind, literal)?;
+                        context.unify_values(metas, literal.span(), &ty, &expected_ty)?;
+                        Ok(term)
+                    },
+                },
+                domain::Value::LiteralType(_) => {
+                    let literal_intro =
+                        literal::check(context, metas, *kind, literal, &expected_ty)?;
+                    Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+                },
+                _ => {
+                    // String literals can be converted to other types using
+                    // the `#[from_string]` instances that are in scope
+                    if *kind == LiteralKind::String {
+                        if let Some(term) =
+                            literal::check_from_string(context, metas, literal, &expected_ty)?
+                        {
+                            return Ok(term);
+                        }
+                    }
+
+                    let literal_intro =
+                        literal::check(context, metas, *kind, literal, &expected_ty)?;
+                    Ok(Rc::from(syntax::Term::literal_intro(literal_intro)))
+                },
+            }
         },
 
         Term::FunIntro(_, concrete_params, concrete_body) => {
@@ -251,11 +810,16 @@ pub fn check_term(
         Term::RecordIntro(span, concrete_intro_fields) => {
             let mut context = context.clone();
             let mut fields = Vec::new();
-            let mut expected_ty = expected_ty.clone();
+            let expected_ty = context.force_value(metas, *span, expected_ty)?;
+            let mut telescope = Telescope::record(expected_ty);
 
             for concrete_intro_field in concrete_intro_fields {
-                let (expected_label, expected_term_ty, rest) = match expected_ty.as_ref() {
-                    domain::Value::RecordTypeExtend(_, label, _, ty, rest) => Ok((label, ty, rest)),
+                let (expected_label, expected_term_ty) = match telescope.first() {
+                    Some(Entry {
+                        binder: Binder::Field(label),
+                        ty,
+                        ..
+                    }) => Ok((label, ty)),
                     _ => Err(Diagnostic::new_error("too many fields found")
                         .with_label(DiagnosticLabel::new_primary(*span))),
                 }?;
@@ -264,14 +828,13 @@ pub fn check_term(
 
                 if found_label.slice == expected_label.0 {
                     let clause = Clause::new(params, body_ty, &body);
-                    let term = clause::check_clause(&context, metas, clause, expected_term_ty)?;
+                    let term = clause::check_clause(&context, metas, clause, &expected_term_ty)?;
 
                     let term_value = context.eval_term(metas, body.span(), &term)?;
-                    let term_ty = expected_term_ty.clone();
 
-                    fields.push((expected_label.clone(), term));
-                    context.add_defn(found_label, term_value.clone(), term_ty);
-                    expected_ty = context.app_closure(metas, &rest, term_value)?;
+                    fields.push((expected_label, term));
+                    context.add_defn(found_label, term_value.clone(), expected_term_ty);
+                    context.instantiate_telescope(metas, &mut telescope, term_value)?;
                 } else {
                     return Err(Diagnostic::new_error("field not found").with_label(
                         DiagnosticLabel::new_primary(found_label.span()).with_message(format!(
@@ -282,7 +845,7 @@ pub fn check_term(
                 }
             }
 
-            if let domain::Value::RecordTypeEmpty = expected_ty.as_ref() {
+            if telescope.is_record_empty() {
                 Ok(Rc::from(syntax::Term::RecordIntro(fields)))
             } else {
                 Err(Diagnostic::new_error("not enough fields provided")
@@ -290,14 +853,237 @@ pub fn check_term(
             }
         },
 
+        Term::PairIntro(span, concrete_fst, concrete_snd) => {
+            let expected_ty = context.force_value(metas, *span, expected_ty)?;
+            match expected_ty.as_ref() {
+                domain::Value::RecordTypeExtend(_, label, _, _, _) if label.0 == "fst" => {
+                    let mut context = context.clone();
+                    let mut fields = Vec::new();
+                    let mut telescope = Telescope::record(expected_ty.clone());
+
+                    for (expected_label, concrete_term) in
+                        &[("fst", concrete_fst), ("snd", concrete_snd)]
+                    {
+                        let term_ty = match telescope.first() {
+                            Some(Entry {
+                                binder: Binder::Field(label),
+                                ty,
+                                ..
+                            }) if label.0 == *expected_label => Ok(ty),
+                            _ => Err(Diagnostic::new_error("field not found").with_label(
+                                DiagnosticLabel::new_primary(*span).with_message(format!(
+                                    "expected a `{}` field in the pair type",
+                                    expected_label,
+                                )),
+                            )),
+                        }?;
+
+                        let term = check_term(&context, metas, concrete_term, &term_ty)?;
+                        let term_value = context.eval_term(metas, concrete_term.span(), &term)?;
+
+                        fields.push((Label(expected_label.to_string()), term));
+                        context.add_fresh_defn(term_value.clone(), term_ty);
+                        context.instantiate_telescope(metas, &mut telescope, term_value)?;
+                    }
+
+                    if telescope.is_record_empty() {
+                        Ok(Rc::from(syntax::Term::RecordIntro(fields)))
+                    } else {
+                        Err(Diagnostic::new_error("not enough fields provided")
+                            .with_label(DiagnosticLabel::new_primary(*span)))
+                    }
+                },
+                _ => {
+                    let (synth, synth_ty) =
+                        synth(MetaInsertion::Yes, context, metas, concrete_term)?;
+                    context.unify_values(metas, *span, &synth_ty, &expected_ty)?;
+                    Ok(synth)
+                },
+            }
+        },
+
+        Term::ArrayIntro(span, concrete_elems) => {
+            let expected_ty = context.force_value(metas, *span, expected_ty)?;
+            match expected_ty.as_ref() {
+                domain::Value::ArrayType(elem_ty) => {
+                    let elems = (concrete_elems.iter())
+                        .map(|concrete_elem| check_term(context, metas, concrete_elem, elem_ty))
+                        .collect::<Result<_, _>>()?;
+
+                    Ok(Rc::from(syntax::Term::ArrayIntro(elems)))
+                },
+                _ => {
+                    let (synth, synth_ty) =
+                        synth(MetaInsertion::Yes, context, metas, concrete_term)?;
+                    context.unify_values(metas, *span, &synth_ty, &expected_ty)?;
+                    Ok(synth)
+                },
+            }
+        },
+
         _ => {
-            let (synth, synth_ty) = synth_term(MetaInsertion::Yes, context, metas, concrete_term)?;
+            if let Some((name, var_levels)) = overloaded_head(context, concrete_term) {
+                return check_overloaded(
+                    context,
+                    metas,
+                    concrete_term,
+                    expected_ty,
+                    name,
+                    var_levels,
+                );
+            }
+
+            let (synth, synth_ty) = synth(MetaInsertion::Yes, context, metas, concrete_term)?;
             context.unify_values(metas, concrete_term.span(), &synth_ty, expected_ty)?;
             Ok(synth)
         },
     }
 }
 
+/// Specialize a call to an item marked with `#[inline]`, returning `None` if
+/// the head of the application refers to some other term.
+///
+/// Only the head is unfolded: it is normalized after being applied to fresh
+/// variables, which are then substituted with the arguments of the call. This
+/// leaves the arguments as they were elaborated, along with any of their
+/// unsolved metavariables.
+fn specialize_inline_call(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    term: &Rc<syntax::Term>,
+) -> Result<Option<Rc<syntax::Term>>, Diagnostic<FileSpan>> {
+    let mut head = term;
+    let mut args = Vec::new();
+    while let syntax::Term::FunElim(fun, app_mode, arg) = head.as_ref() {
+        args.push((app_mode.clone(), arg.clone()));
+        head = fun;
+    }
+    args.reverse();
+
+    let var_index = match head.as_ref() {
+        syntax::Term::Var(var_index) => *var_index,
+        _ => return Ok(None),
+    };
+    if !context.is_inline_item(context.values().size().level(var_index)) {
+        return Ok(None);
+    }
+
+    // Abstract the call over its arguments, so that they are not normalized
+    // along with the head
+    let arity = args.len() as u32;
+    let head = Rc::from(syntax::Term::var(var_index.0 + arity));
+    let call = (args.iter().enumerate()).fold(head, |fun, (i, (app_mode, _))| {
+        let arg = Rc::from(syntax::Term::var(arity - 1 - i as u32));
+        Rc::from(syntax::Term::FunElim(fun, app_mode.clone(), arg))
+    });
+    let call = (args.iter().rev()).fold(call, |body, (app_mode, _)| {
+        Rc::from(syntax::Term::FunIntro(app_mode.clone(), None, body))
+    });
+
+    let mut specialized = context.normalize_term(metas, span, &call)?;
+    for (_, arg) in &args {
+        specialized = match specialized.as_ref() {
+            syntax::Term::FunIntro(_, _, body) => steps::instantiate(body, arg),
+            _ => {
+                let message = "expected a function after specializing an inline call";
+                return Err(
+                    Diagnostic::new_bug(message).with_label(DiagnosticLabel::new_primary(span))
+                );
+            },
+        };
+    }
+
+    Ok(Some(specialized))
+}
+
+/// The name at the head of a term, along with the items that it could refer
+/// to, if it is overloaded.
+fn overloaded_head<'file>(
+    context: &Context,
+    concrete_term: &Term<'file>,
+) -> Option<(SpannedString<'file>, im::Vector<var::Level>)> {
+    match concrete_term {
+        Term::Var(name) => Some((*name, context.lookup_overloads(name.slice)?.clone())),
+        Term::Parens(_, concrete_term) | Term::FunElim(concrete_term, _) => {
+            overloaded_head(context, concrete_term)
+        },
+        _ => None,
+    }
+}
+
+/// Check a term with an overloaded name at its head, using the expected type
+/// to decide which of the items the name refers to.
+fn check_overloaded(
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_term: &Term<'_>,
+    expected_ty: &Rc<domain::Type>,
+    name: SpannedString<'_>,
+    var_levels: im::Vector<var::Level>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let mut matches = Vec::new();
+
+    for var_level in var_levels.iter().cloned() {
+        let mut context = context.clone();
+        context.select_overload(name.span(), var_level);
+
+        // Elaborate using a copy of the metavariables, so that the solutions
+        // from failed attempts are discarded
+        let mut attempt_metas = metas.clone();
+        let attempt = synth(
+            MetaInsertion::Yes,
+            &context,
+            &mut attempt_metas,
+            concrete_term,
+        )
+        .and_then(|(synth, synth_ty)| {
+            let span = concrete_term.span();
+            context.unify_values(&mut attempt_metas, span, &synth_ty, expected_ty)?;
+            Ok(synth)
+        });
+
+        if let Ok(term) = attempt {
+            matches.push((var_level, term, attempt_metas));
+        }
+    }
+
+    match matches.len() {
+        1 => {
+            let (_, term, attempt_metas) = matches.remove(0);
+            *metas = attempt_metas;
+            Ok(term)
+        },
+        0 => {
+            let message = format!("no item named `{}` matches the expected type", name);
+            Err(Diagnostic::new_error(message).with_label(
+                DiagnosticLabel::new_primary(name.span())
+                    .with_message(candidates_message(context, &var_levels)),
+            ))
+        },
+        _ => {
+            let var_levels = matches.iter().map(|(var_level, _, _)| *var_level).collect();
+            let message = format!("ambiguous name `{}`", name);
+            Err(Diagnostic::new_error(message).with_label(
+                DiagnosticLabel::new_primary(name.span())
+                    .with_message(candidates_message(context, &var_levels)),
+            ))
+        },
+    }
+}
+
+/// Describe the items that an overloaded name could refer to.
+fn candidates_message(context: &Context, var_levels: &im::Vector<var::Level>) -> String {
+    let candidates = (var_levels.iter())
+        .map(|var_level| match context.lookup_level_name(*var_level) {
+            Some(name) => format!("`{}`", name),
+            None => "<unnamed>".to_owned(),
+        })
+        .collect::<Vec<_>>();
+
+    format!("candidates: {}", candidates.join(", "))
+}
+
 /// Controls the insertion of metavariables when performing type synthesis.
 #[derive(Debug, Copy, Clone)]
 pub enum MetaInsertion<'file> {
@@ -320,12 +1106,15 @@ fn insert_metas(
     mut term: Rc<syntax::Term>,
     term_ty: &Rc<domain::Type>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
-    use mltt_core::domain::Value::FunType;
-
-    let mut term_ty = term_ty.clone();
-
-    while let FunType(app_mode, _, param_ty, body_ty) = term_ty.as_ref() {
-        match (meta_insertion, app_mode) {
+    let mut telescope = Telescope::fun(term_ty.clone());
+
+    while let Some(Entry {
+        binder: Binder::Param(app_mode),
+        ty: param_ty,
+        ..
+    }) = telescope.first()
+    {
+        match (meta_insertion, &app_mode) {
             // The user requested we stop inserting metavariables, or
             // we have seen an explicit argument, so we stop inserting
             // metavariables.
@@ -342,11 +1131,13 @@ fn insert_metas(
             // Based on the given type, we expected an implicit argument to be
             // applied. Instead, let's apply a metavariable argument in its
             // place, to be solved later (during unification).
-            (_, AppMode::Implicit(_)) => {
+            (_, AppMode::Implicit(label)) => {
+                let meta = metas.next_index();
                 let arg = context.new_meta(metas, span, param_ty.clone());
+                context.record_implicit(span, label, meta, &arg, &param_ty);
                 let arg_value = context.eval_term(metas, None, &arg)?;
-                term = Rc::from(syntax::Term::FunElim(term, app_mode.clone(), arg));
-                term_ty = context.app_closure(metas, body_ty, arg_value)?;
+                term = Rc::from(syntax::Term::FunElim(term, app_mode, arg));
+                context.instantiate_telescope(metas, &mut telescope, arg_value)?;
             },
 
             // TODO: Instance arguments
@@ -360,49 +1151,243 @@ fn insert_metas(
         }
     }
 
-    Ok((term, term_ty))
+    Ok((term, telescope.rest().clone()))
+}
+
+/// Resolve a term to the qualified name of a namespace, if it refers to one.
+///
+/// Bound variables take precedence over namespaces of the same name, unless
+/// they are types. Types don't have fields, so this allows the helpers derived
+/// for record types to be referred to using the names of the types.
+fn namespace_path(context: &Context, concrete_term: &Term<'_>) -> Option<String> {
+    let path = match concrete_term {
+        Term::Var(name) => match context.lookup_binder(name.slice) {
+            Some((_, ty)) if !matches!(ty.as_ref(), domain::Value::Universe(_)) => return None,
+            Some(_) | None => name.to_string(),
+        },
+        Term::RecordElim(concrete_term, label) => {
+            format!("{}.{}", namespace_path(context, concrete_term)?, label)
+        },
+        _ => return None,
+    };
+
+    if context.has_namespace(&path) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Find the namespaces that contain a method with the given name for values
+/// of the given type. These are the namespaces that share their name with the
+/// type, or with a definition of the type.
+fn method_namespaces(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    ty: &Rc<domain::Type>,
+    name: &str,
+) -> Result<Vec<String>, Diagnostic<FileSpan>> {
+    // We can't tell what the type is yet, so we give up rather than guessing
+    let ty = context.force_value(metas, span, ty)?;
+    if let domain::Value::Neutral(domain::Head::Meta(_), _) = ty.as_ref() {
+        return Ok(Vec::new());
+    }
+
+    let mut namespaces = Vec::new();
+    for (namespace, items) in context.namespace_items() {
+        if !items.contains(&name) {
+            continue;
+        }
+
+        let binder = match namespace.rfind('.') {
+            None => context.lookup_binder(namespace),
+            Some(i) => context.lookup_qualified_binder(&namespace[..i], &namespace[i + 1..]),
+        };
+        let namespace_ty = match binder {
+            Some((index, binder_ty)) => match binder_ty.as_ref() {
+                domain::Value::Universe(_) => match context.values().lookup_entry(index) {
+                    Some(namespace_ty) => namespace_ty.clone(),
+                    None => continue,
+                },
+                _ => continue,
+            },
+            None => continue,
+        };
+
+        // Compare using a copy of the metavariables, so that we don't commit
+        // to any solutions
+        let mut attempt_metas = metas.clone();
+        if (context.unify_values(&mut attempt_metas, span, &ty, &namespace_ty)).is_ok() {
+            namespaces.push(namespace.to_owned());
+        }
+    }
+    namespaces.sort();
+
+    Ok(namespaces)
+}
+
+/// Elaborate the method-call `record.label` to `namespace.label record`,
+/// returning the elaborated term and its type.
+fn synth_method_call(
+    context: &Context,
+    metas: &mut meta::Env,
+    namespace: &str,
+    label: &SpannedString<'_>,
+    record: Rc<syntax::Term>,
+    record_ty: &Rc<domain::Type>,
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    let span = label.span();
+    let (index, method_ty) = match context.lookup_qualified_binder(namespace, label.slice) {
+        Some((index, method_ty)) => (index, method_ty.clone()),
+        None => {
+            let message = format!("method `{}.{}` not in scope", namespace, label);
+            return Err(Diagnostic::new_bug(message).with_label(DiagnosticLabel::new_primary(span)));
+        },
+    };
+    let method = Rc::from(syntax::Term::var(index));
+    let (method, method_ty) =
+        insert_metas(MetaInsertion::Yes, context, metas, span, method, &method_ty)?;
+
+    match context.force_value(metas, span, &method_ty)?.as_ref() {
+        domain::Value::FunType(_, AppMode::Explicit, _, param_ty, body_ty) => {
+            context.unify_values(metas, span, record_ty, param_ty)?;
+            let record_value = context.eval_term(metas, span, &record)?;
+            let method = Rc::from(syntax::Term::FunElim(method, AppMode::Explicit, record));
+            let method_ty = context.app_closure(metas, body_ty, record_value)?;
+
+            Ok((method, method_ty))
+        },
+        _ => {
+            let message = format!("`{}.{}` can't be used as a method", namespace, label);
+            Err(Diagnostic::new_error(message).with_label(
+                DiagnosticLabel::new_primary(span).with_message(format!(
+                    "expected a function taking a `{}` as its first explicit argument",
+                    namespace,
+                )),
+            ))
+        },
+    }
+}
+
+/// The result of synthesizing the type of a term with [`synth_term`].
+#[derive(Debug, Clone)]
+pub struct Synthesized {
+    /// The elaborated term.
+    pub term: Rc<syntax::Term>,
+    /// The synthesized type.
+    pub ty: Rc<domain::Type>,
+    /// The synthesized type, read back into the core syntax.
+    pub ty_term: Rc<syntax::Term>,
+    /// The metavariables that were created while elaborating the term, but
+    /// were left unsolved, along with the spans where they were created.
+    pub unsolved_metas: Vec<(meta::Index, FileSpan)>,
 }
 
 /// Synthesize the type of the given term.
 ///
 /// Metavariables are inserted based on the given `meta_insertion`.
 ///
-/// Returns the elaborated term and its synthesized type.
+/// Returns the elaborated term along with its synthesized type, both as a
+/// value and read back into the core syntax, so that front-ends can choose
+/// how to display it. Metavariables left unsolved by the term are also
+/// returned, although some of them might still be solved by defaulting
+/// literals afterwards.
 pub fn synth_term(
     meta_insertion: MetaInsertion<'_>,
     context: &Context,
     metas: &mut meta::Env,
     concrete_term: &Term<'_>,
+) -> Result<Synthesized, Diagnostic<FileSpan>> {
+    let start_meta = metas.next_index();
+    let (term, ty) = synth(meta_insertion, context, metas, concrete_term)?;
+    let ty_term = context.read_back_value(metas, concrete_term.span(), &ty)?;
+    let unsolved_metas = (metas.iter())
+        .filter(|(index, _)| *index >= start_meta)
+        .filter_map(|(index, (span, solution, _))| match solution {
+            meta::Solution::Unsolved => Some((index, *span)),
+            meta::Solution::Solved(_) => None,
+        })
+        .collect();
+
+    Ok(Synthesized {
+        term,
+        ty,
+        ty_term,
+        unsolved_metas,
+    })
+}
+
+/// Synthesize the type of the given term, returning the elaborated term and
+/// its synthesized type.
+///
+/// Metavariables are inserted based on the given `meta_insertion`.
+pub(crate) fn synth(
+    meta_insertion: MetaInsertion<'_>,
+    context: &Context,
+    metas: &mut meta::Env,
+    concrete_term: &Term<'_>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
     use std::cmp;
 
     log::trace!("synthesizing term:\t\t{}", concrete_term);
 
     match concrete_term {
-        Term::Var(name) => match context.lookup_binder(name.slice) {
-            None => Err(Diagnostic::new_error("unbound variable")
-                .with_label(DiagnosticLabel::new_primary(name.span()))),
-            Some((index, var_ty)) => {
-                let span = concrete_term.span().end_span();
-                let var = Rc::from(syntax::Term::var(index));
-                insert_metas(meta_insertion, context, metas, span, var, var_ty)
-            },
+        Term::Var(name) => {
+            let binder = match context.lookup_overloads(name.slice) {
+                None => context.lookup_binder(name.slice),
+                Some(var_levels) => match context.lookup_selected_overload(name.span()) {
+                    Some(var_level) => context.lookup_level(var_level),
+                    None => {
+                        let message = format!("ambiguous name `{}`", name);
+                        return Err(Diagnostic::new_error(message).with_label(
+                            DiagnosticLabel::new_primary(name.span())
+                                .with_message(candidates_message(context, var_levels)),
+                        ));
+                    },
+                },
+            };
+
+            match binder {
+                None => match context.lookup_pattern_synonym(name.slice) {
+                    Some((literal_intro, ty)) => {
+                        let term = Rc::from(syntax::Term::literal_intro(literal_intro.clone()));
+                        Ok((term, ty.clone()))
+                    },
+                    None => Err(Diagnostic::new_error("unbound variable")
+                        .with_label(DiagnosticLabel::new_primary(name.span()))),
+                },
+                Some((index, var_ty)) => {
+                    context.record_name(name.span(), index);
+                    let span = concrete_term.span().end_span();
+                    let var = Rc::from(syntax::Term::var(index));
+                    insert_metas(meta_insertion, context, metas, span, var, var_ty)
+                },
+            }
+        },
+        Term::Prim(span, name) => {
+            let prim_name = prim::Name(decode_string(name)?.value);
+            match context.prims().lookup_entry(&prim_name) {
+                None => Err(Diagnostic::new_error("unknown primitive")
+                    .with_label(DiagnosticLabel::new_primary(name.span()))),
+                Some(prim::Entry { ty: None, .. }) => {
+                    Err(Diagnostic::new_error("ambiguous primitive").with_label(
+                        DiagnosticLabel::new_primary(*span)
+                            .with_message("type annotations needed here"),
+                    ))
+                },
+                Some(prim::Entry { ty: Some(ty), .. }) => {
+                    let ty = context.eval_term(metas, *span, ty)?;
+                    Ok((Rc::from(syntax::Term::prim(prim_name)), ty))
+                },
+            }
         },
-        Term::Prim(span, name) => match context
-            .prims()
-            .lookup_entry(&prim::Name(literal::parse_string(name)?))
-        {
-            None => Err(Diagnostic::new_error("unknown primitive")
-                .with_label(DiagnosticLabel::new_primary(name.span()))),
-            Some(_) => Err(Diagnostic::new_error("ambiguous primitive").with_label(
+        Term::Hole(span) | Term::Placeholder(span) => Err(Diagnostic::new_error("ambiguous term")
+            .with_label(
                 DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
             )),
-        },
-        Term::Hole(span) => Err(Diagnostic::new_error("ambiguous term").with_label(
-            DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
-        )),
 
-        Term::Parens(_, concrete_term) => synth_term(meta_insertion, context, metas, concrete_term),
+        Term::Parens(_, concrete_term) => synth(meta_insertion, context, metas, concrete_term),
         Term::Ann(concrete_term, concrete_term_ty) => {
             let (term_ty, _) = synth_universe(context, metas, concrete_term_ty)?;
             let term_ty_value = context.eval_term(metas, concrete_term_ty.span(), &term_ty)?;
@@ -412,90 +1397,38 @@ pub fn synth_term(
         },
         Term::Let(_, concrete_items, concrete_body) => {
             let mut context = context.clone();
-            let items = check_items(&mut context, metas, concrete_items)?;
-            let (body, body_ty) = synth_term(meta_insertion, &context, metas, concrete_body)?;
+            let (items, _) = check_items(&mut context, metas, concrete_items)?;
+            let (body, body_ty) = synth(meta_insertion, &context, metas, concrete_body)?;
 
             Ok((Rc::from(syntax::Term::Let(items, body)), body_ty))
         },
         Term::If(span, _, _, _) => Err(Diagnostic::new_error("ambiguous term").with_label(
             DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
         )),
-        Term::Case(span, _, _) => Err(Diagnostic::new_error("ambiguous term").with_label(
-            DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
-        )),
-
-        Term::LiteralIntro(kind, literal) => {
-            let (literal_intro, ty) = literal::synth(*kind, literal)?;
-            let term = Rc::from(syntax::Term::literal_intro(literal_intro));
-
-            Ok((term, ty))
+        Term::Case(span, _, _) | Term::With(span, _, _) => {
+            Err(Diagnostic::new_error("ambiguous term").with_label(
+                DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
+            ))
         },
 
+        Term::LiteralIntro(kind, literal) => literal::synth(context, metas, *kind, literal),
+
         Term::FunType(_, concrete_params, concrete_body_ty) => {
             let mut context = context.clone();
-            let mut param_tys = Vec::new();
-            let mut max_level = UniverseLevel(0);
-
-            for param in concrete_params {
-                match param {
-                    TypeParam::Explicit(_, param_names, concrete_param_ty) => {
-                        for param_name in param_names {
-                            let app_mode = AppMode::Explicit;
-                            let param_ty_span = concrete_param_ty.span();
-                            let (param_ty, level) =
-                                synth_universe(&context, metas, concrete_param_ty)?;
-                            let param_ty_value =
-                                context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                            context.add_param(param_name, param_ty_value);
-                            param_tys.push((app_mode, param_ty));
-                            max_level = cmp::max(max_level, level);
-                        }
-                    },
-                    TypeParam::Implicit(param_span, param_labels, concrete_param_ty) => {
-                        let concrete_param_ty = concrete_param_ty.as_ref().ok_or_else(|| {
-                            Diagnostic::new_error("implicit parameter is missing a type parameter")
-                                .with_label(DiagnosticLabel::new_primary(*param_span).with_message(
-                                    "inference of parameter annotations is not yet supported",
-                                ))
-                        })?;
-
-                        for param_label in param_labels {
-                            let app_mode = AppMode::Implicit(Label(param_label.to_string()));
-                            let param_ty_span = concrete_param_ty.span();
-                            let (param_ty, level) =
-                                synth_universe(&context, metas, concrete_param_ty)?;
-                            let param_ty_value =
-                                context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                            context.add_param(param_label, param_ty_value);
-                            param_tys.push((app_mode, param_ty));
-                            max_level = cmp::max(max_level, level);
-                        }
-                    },
-                    TypeParam::Instance(_, param_label, concrete_param_ty) => {
-                        let app_mode = AppMode::Instance(Label(param_label.to_string()));
-                        let param_ty_span = concrete_param_ty.span();
-                        let (param_ty, level) = synth_universe(&context, metas, concrete_param_ty)?;
-                        let param_ty_value = context.eval_term(metas, param_ty_span, &param_ty)?;
-
-                        context.add_param(param_label, param_ty_value);
-                        param_tys.push((app_mode, param_ty));
-                        max_level = cmp::max(max_level, level);
-                    },
-                }
-            }
-
+            let (param_tys, params_level) =
+                check_type_params(&mut context, metas, concrete_params)?;
             let (body_ty, body_level) = synth_universe(&context, metas, concrete_body_ty)?;
-            max_level = cmp::max(max_level, body_level);
+            let max_level = cmp::max(params_level, body_level);
 
             Ok((
-                param_tys
-                    .into_iter()
-                    .rev()
-                    .fold(body_ty, |acc, (app_mode, param_ty)| {
-                        Rc::from(syntax::Term::FunType(app_mode, None, param_ty, acc))
-                    }),
+                param_tys.into_iter().rev().fold(
+                    body_ty,
+                    |acc, (doc, app_mode, name_hint, param_ty)| {
+                        Rc::from(syntax::Term::FunType(
+                            doc, app_mode, name_hint, param_ty, acc,
+                        ))
+                    },
+                ),
                 Rc::from(domain::Value::universe(max_level)),
             ))
         },
@@ -508,7 +1441,13 @@ pub fn synth_term(
                 synth_universe(&context, metas, concrete_body_ty)?
             };
 
-            let fun_ty = syntax::Term::FunType(AppMode::Explicit, None, param_ty, body_ty);
+            let fun_ty = syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                None,
+                param_ty,
+                body_ty,
+            );
             let max_level = cmp::max(param_level, body_level);
 
             Ok((
@@ -522,7 +1461,7 @@ pub fn synth_term(
         },
         Term::FunElim(concrete_fun, concrete_args) => {
             let (concrete_arg, concrete_args) = match concrete_args.split_first() {
-                None => return synth_term(meta_insertion, context, metas, concrete_term),
+                None => return synth(meta_insertion, context, metas, concrete_term),
                 Some(concrete_args) => concrete_args,
             };
 
@@ -534,11 +1473,11 @@ pub fn synth_term(
                     Arg::Implicit(_, label, _) => MetaInsertion::UntilImplicit(label.slice),
                     Arg::Instance(_, label, _) => MetaInsertion::UntilInstance(label.slice),
                 };
-                synth_term(arg_meta_ins, context, metas, concrete_fun)?
+                synth(arg_meta_ins, context, metas, concrete_fun)?
             };
 
             match context.force_value(metas, None, &fun_ty)?.as_ref() {
-                domain::Value::FunType(app_mode, _, param_ty, body_ty) => {
+                domain::Value::FunType(_, app_mode, _, param_ty, body_ty) => {
                     let concrete_arg_term = concrete_arg.desugar_arg_term();
                     let app_mode = app_mode.clone(); // TODO: check app mode is compatible with insertion
                     let arg = check_term(context, metas, concrete_arg_term.as_ref(), param_ty)?;
@@ -551,7 +1490,7 @@ pub fn synth_term(
                     let fun_ty = context.value_to_doc(metas, &fun_ty);
                     return Err(Diagnostic::new_error("expected a function").with_label(
                         DiagnosticLabel::new_primary(concrete_fun.span())
-                            .with_message(format!("found: {}", fun_ty.pretty(1000_000_000))),
+                            .with_message(format!("found: {}", fun_ty.pretty(1_000_000_000))),
                     ));
                 },
             }
@@ -568,7 +1507,7 @@ pub fn synth_term(
                 };
 
                 match context.force_value(metas, None, &new_fun_ty)?.as_ref() {
-                    domain::Value::FunType(app_mode, _, param_ty, body_ty) => {
+                    domain::Value::FunType(_, app_mode, _, param_ty, body_ty) => {
                         let concrete_arg_term = concrete_arg.desugar_arg_term();
                         let app_mode = app_mode.clone(); // TODO: check app mode is compatible with insertion
                         let arg = check_term(context, metas, concrete_arg_term.as_ref(), param_ty)?;
@@ -581,14 +1520,27 @@ pub fn synth_term(
                         let fun_ty = context.value_to_doc(metas, &fun_ty);
                         return Err(Diagnostic::new_error("expected a function").with_label(
                             DiagnosticLabel::new_primary(concrete_fun.span())
-                                .with_message(format!("found: {}", fun_ty.pretty(1000_000_000))),
+                                .with_message(format!("found: {}", fun_ty.pretty(1_000_000_000))),
                         ));
                     },
                 }
             }
 
             let span = concrete_term.span().end_span();
-            insert_metas(meta_insertion, context, metas, span, fun, &fun_ty)
+            let (fun, fun_ty) = insert_metas(meta_insertion, context, metas, span, fun, &fun_ty)?;
+
+            // Calls to items marked with `#[inline]` are specialized now,
+            // leaving the residual term in their place
+            let span = concrete_term.span();
+            let fun = match specialize_inline_call(context, metas, span, &fun)? {
+                Some(specialized) => {
+                    log::trace!("specialized inline call:\t{}", concrete_term);
+                    specialized
+                },
+                None => fun,
+            };
+
+            Ok((fun, fun_ty))
         },
 
         Term::RecordType(_, concrete_ty_fields) => {
@@ -614,6 +1566,50 @@ pub fn synth_term(
                 Rc::from(domain::Value::universe(max_level)),
             ))
         },
+        Term::SigmaType(_, name, concrete_fst_ty, concrete_snd_ty) => {
+            let mut context = context.clone();
+            let (fst_ty, fst_level) = synth_universe(&context, metas, concrete_fst_ty)?;
+            let fst_ty_value = context.eval_term(metas, concrete_fst_ty.span(), &fst_ty)?;
+            context.add_param(*name, fst_ty_value);
+            let (snd_ty, snd_level) = synth_universe(&context, metas, concrete_snd_ty)?;
+
+            Ok((
+                Rc::from(syntax::Term::RecordType(vec![
+                    (
+                        DocString::from(""),
+                        Label("fst".to_owned()),
+                        Some(name.to_string()),
+                        fst_ty,
+                    ),
+                    (DocString::from(""), Label("snd".to_owned()), None, snd_ty),
+                ])),
+                Rc::from(domain::Value::universe(cmp::max(fst_level, snd_level))),
+            ))
+        },
+        Term::PairIntro(span, concrete_fst, concrete_snd) => {
+            let (fst, fst_ty) = synth(MetaInsertion::Yes, context, metas, concrete_fst)?;
+            let fst_value = context.eval_term(metas, concrete_fst.span(), &fst)?;
+            let mut snd_context = context.clone();
+            snd_context.add_fresh_defn(fst_value, fst_ty.clone());
+            let (snd, snd_ty) = synth(MetaInsertion::Yes, &snd_context, metas, concrete_snd)?;
+
+            // The synthesized type is non-dependent, so the type of the
+            // second component doesn't refer to the first
+            let fst_ty = context.read_back_value(metas, concrete_fst.span(), &fst_ty)?;
+            let snd_ty = snd_context.read_back_value(metas, concrete_snd.span(), &snd_ty)?;
+            let pair_ty = Rc::from(syntax::Term::RecordType(vec![
+                (DocString::from(""), Label("fst".to_owned()), None, fst_ty),
+                (DocString::from(""), Label("snd".to_owned()), None, snd_ty),
+            ]));
+
+            let pair_ty_value = context.eval_term(metas, *span, &pair_ty)?;
+            let pair = Rc::from(syntax::Term::RecordIntro(vec![
+                (Label("fst".to_owned()), fst),
+                (Label("snd".to_owned()), snd),
+            ]));
+
+            Ok((Rc::from(syntax::Term::ann(pair, pair_ty)), pair_ty_value))
+        },
         Term::RecordIntro(span, intro_fields) => {
             if intro_fields.is_empty() {
                 Ok((
@@ -628,38 +1624,93 @@ pub fn synth_term(
             }
         },
         Term::RecordElim(concrete_record, label) => {
-            let (record, mut record_ty) =
-                synth_term(MetaInsertion::Yes, context, metas, concrete_record)?;
+            if let Some(namespace) = namespace_path(context, concrete_record) {
+                return match context.lookup_qualified_binder(&namespace, label.slice) {
+                    None => {
+                        let message = format!("item not found in namespace `{}`", namespace);
+                        Err(Diagnostic::new_error(message)
+                            .with_label(DiagnosticLabel::new_primary(label.span())))
+                    },
+                    Some((index, var_ty)) => {
+                        let span = concrete_term.span().end_span();
+                        let var = Rc::from(syntax::Term::var(index));
+                        insert_metas(meta_insertion, context, metas, span, var, var_ty)
+                    },
+                };
+            }
+
+            let (record, record_ty) = synth(MetaInsertion::Yes, context, metas, concrete_record)?;
+            let record_ty = context.force_value(metas, concrete_record.span(), &record_ty)?;
+            let record_value = context.eval_term(metas, concrete_record.span(), &record)?;
+            let field_label = Label(label.to_string());
+            let mut telescope = Telescope::record(record_ty.clone());
 
-            while let domain::Value::RecordTypeExtend(_, current_label, _, current_ty, rest) =
-                record_ty.as_ref()
+            if let Some(entry) =
+                context.split_telescope(metas, &mut telescope, &record_value, &field_label)?
             {
-                let expr = Rc::from(syntax::Term::RecordElim(
-                    record.clone(),
-                    current_label.clone(),
-                ));
+                let expr = Rc::from(syntax::Term::RecordElim(record, field_label));
+                let span = concrete_term.span().end_span();
+                return insert_metas(meta_insertion, context, metas, span, expr, &entry.ty);
+            }
 
-                if current_label.0 == label.slice {
+            // No field was found, so try method-call sugar, elaborating
+            // `x.f args` as `T.f x args`, where `T` is the type of `x`
+            let span = concrete_record.span();
+            let namespaces = method_namespaces(context, metas, span, &record_ty, label.slice)?;
+            match namespaces.as_slice() {
+                [] => {
+                    let message = format!("field not found: `{}`", label);
+                    Err(Diagnostic::new_error(message)
+                        .with_label(DiagnosticLabel::new_primary(label.span())))
+                },
+                [namespace] => {
+                    let (method, method_ty) =
+                        synth_method_call(context, metas, namespace, label, record, &record_ty)?;
                     let span = concrete_term.span().end_span();
-                    return insert_metas(meta_insertion, context, metas, span, expr, &current_ty);
-                } else {
-                    let expr = context.eval_term(metas, None, &expr)?;
-                    record_ty = context.app_closure(metas, rest, expr)?;
-                }
+                    insert_metas(meta_insertion, context, metas, span, method, &method_ty)
+                },
+                namespaces => {
+                    let candidates = (namespaces.iter())
+                        .map(|namespace| format!("`{}.{}`", namespace, label))
+                        .collect::<Vec<_>>();
+                    let message = format!("ambiguous method `{}`", label);
+                    Err(Diagnostic::new_error(message).with_label(
+                        DiagnosticLabel::new_primary(label.span())
+                            .with_message(format!("candidates: {}", candidates.join(", "))),
+                    ))
+                },
             }
+        },
 
-            let message = format!("field not found: `{}`", label);
-            Err(Diagnostic::new_error(message)
-                .with_label(DiagnosticLabel::new_primary(label.span())))
+        Term::ArrayIntro(span, concrete_elems) => match concrete_elems.split_first() {
+            None => Err(Diagnostic::new_error("ambiguous term").with_label(
+                DiagnosticLabel::new_primary(*span).with_message("type annotations needed here"),
+            )),
+            Some((concrete_first, concrete_rest)) => {
+                // Use the first element to determine the type of the others
+                let (first, elem_ty) = synth(MetaInsertion::Yes, context, metas, concrete_first)?;
+                let mut elems = vec![first];
+                for concrete_elem in concrete_rest {
+                    elems.push(check_term(context, metas, concrete_elem, &elem_ty)?);
+                }
+
+                Ok((
+                    Rc::from(syntax::Term::ArrayIntro(elems)),
+                    Rc::from(domain::Value::ArrayType(elem_ty)),
+                ))
+            },
         },
 
         Term::Universe(span, level) => {
             let level = UniverseLevel(level.as_ref().map_or(Ok(0), literal::parse_int)?);
-            let ty_level = level.shift(1).ok_or_else(|| {
+            // With `Type : Type` every universe is its own type
+            let shift = if context.type_in_type() { 0 } else { 1 };
+            let max_universe = context.max_universe();
+            let ty_level = level.shift_within(shift, max_universe).ok_or_else(|| {
                 Diagnostic::new_error("maximum universe level reached").with_label(
                     DiagnosticLabel::new_primary(*span).with_message(format!(
                         "cannot represent universes greater than `{}`",
-                        UniverseLevel::MAX,
+                        max_universe,
                     )),
                 )
             })?;
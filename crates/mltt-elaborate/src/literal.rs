@@ -10,14 +10,15 @@
 //! into a data type of your choice, or return a custom error diagnostic.
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::literal::{decode_char, decode_string};
 use mltt_concrete::{LiteralKind, SpannedString};
 use mltt_core::literal::LiteralIntro;
-use mltt_core::{domain, meta};
+use mltt_core::telescope::Telescope;
+use mltt_core::{domain, meta, syntax, var, AppMode, Label, Rc};
 use mltt_span::FileSpan;
 use std::fmt;
-use std::rc::Rc;
 
-use super::Context;
+use super::{unify, Context};
 
 /// Check the type of a literal in a context.
 pub fn check(
@@ -31,11 +32,13 @@ pub fn check(
     use mltt_core::domain::Value::LiteralType;
     use mltt_core::literal::{LiteralIntro as LitIntro, LiteralType as LitType};
 
+    let expected_ty = context.force_value(metas, src.span(), expected_ty)?;
     match (kind, expected_ty.as_ref()) {
         (LitKind::String, LiteralType(LitType::String)) => {
-            parse_string(src).map(Rc::from).map(LitIntro::String)
+            let decoded = decode_string(src)?;
+            Ok(LitIntro::String(Rc::from(decoded.value)))
         },
-        (LitKind::Char, LiteralType(LitType::Char)) => parse_char(src).map(LitIntro::Char),
+        (LitKind::Char, LiteralType(LitType::Char)) => Ok(LitIntro::Char(decode_char(src)?.value)),
         (LitKind::Int, LiteralType(LitType::U8)) => parse_int::<u8>(src).map(LitIntro::U8),
         (LitKind::Int, LiteralType(LitType::U16)) => parse_int::<u16>(src).map(LitIntro::U16),
         (LitKind::Int, LiteralType(LitType::U32)) => parse_int::<u32>(src).map(LitIntro::U32),
@@ -51,138 +54,314 @@ pub fn check(
                 "expected: {}",
                 context
                     .value_to_doc(metas, &expected_ty)
-                    .pretty(1000_000_000),
+                    .pretty(1_000_000_000),
             )),
         )),
     }
 }
 
 /// Synthesize the type of a literal.
+///
+/// The types of numeric literals can't be determined from their syntax alone,
+/// so these are postponed until their types have been inferred.
 pub fn synth(
+    context: &Context,
+    metas: &mut meta::Env,
     kind: LiteralKind,
     src: &SpannedString<'_>,
-) -> Result<(LiteralIntro, Rc<domain::Type>), Diagnostic<FileSpan>> {
+) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
     use mltt_concrete::LiteralKind as LitKind;
     use mltt_core::literal::{LiteralIntro as LitIntro, LiteralType as LitType};
 
     match kind {
         LitKind::String => Ok((
-            LitIntro::String(Rc::from(parse_string(src)?)),
+            Rc::from(syntax::Term::literal_intro(LitIntro::String(Rc::from(
+                decode_string(src)?.value,
+            )))),
             Rc::from(domain::Value::literal_ty(LitType::String)),
         )),
         LitKind::Char => Ok((
-            LitIntro::Char(parse_char(src)?),
+            Rc::from(syntax::Term::literal_intro(LitIntro::Char(
+                decode_char(src)?.value,
+            ))),
             Rc::from(domain::Value::literal_ty(LitType::Char)),
         )),
-        LitKind::Int | LitKind::Float => Err(Diagnostic::new_error("ambiguous literal")
-            .with_label(DiagnosticLabel::new_primary(src.span()))),
+        LitKind::Int | LitKind::Float => {
+            let universe = Rc::from(domain::Value::universe(0));
+            let ty = context.new_meta(metas, src.span(), universe);
+            let ty = context.eval_term(metas, src.span(), &ty)?;
+            let term = postpone(context, metas, kind, src, ty.clone())?;
+
+            Ok((term, ty))
+        },
     }
 }
 
-fn literal_bug<T>(span: FileSpan, message: impl Into<String>) -> Result<T, Diagnostic<FileSpan>> {
-    // FIXME: improve precision of error span
-    Err(Diagnostic::new_bug(message).with_label(DiagnosticLabel::new_primary(span)))
+/// Postpone the elaboration of a numeric literal until its type is known,
+/// returning a metavariable that stands for the value of the literal.
+pub fn postpone(
+    context: &Context,
+    metas: &mut meta::Env,
+    kind: LiteralKind,
+    src: &SpannedString<'_>,
+    ty: Rc<domain::Type>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    use mltt_concrete::LiteralKind as LitKind;
+
+    let is_float = match kind {
+        LitKind::Int => false,
+        LitKind::Float => true,
+        LitKind::String | LitKind::Char => {
+            return literal_bug(src.span(), "only numeric literals can be postponed");
+        },
+    };
+
+    let index = metas.next_index();
+    let term = context.new_meta(metas, src.span(), ty.clone());
+    let value = context.eval_term(metas, src.span(), &term)?;
+
+    metas.add_pending_literal(meta::PendingLiteral {
+        index,
+        span: src.span(),
+        src: Rc::from(src.slice),
+        is_float,
+        value,
+        ty,
+        env: context.values().clone(),
+    });
+
+    Ok(term)
 }
 
-fn expect_char(
-    span: FileSpan,
-    chars: &mut impl Iterator<Item = char>,
-) -> Result<char, Diagnostic<FileSpan>> {
-    match chars.next() {
-        Some(ch) => Ok(ch),
-        None => literal_bug(span, "unexpected EOF"),
+/// Solve the literals that were postponed from the metavariable at `start`
+/// onwards.
+///
+/// Literals whose types are still unknown are given the default literal types
+/// of the context, if any were set.
+pub fn solve_pending_literals(
+    context: &Context,
+    metas: &mut meta::Env,
+    start: meta::Index,
+) -> Result<(), Diagnostic<FileSpan>> {
+    use mltt_concrete::LiteralKind as LitKind;
+
+    for literal in metas.take_pending_literals(start) {
+        let span = literal.span;
+        let ty = context.force_value(metas, span, &literal.ty)?;
+        let ty = match ty.as_ref() {
+            domain::Value::Neutral(domain::Head::Meta(_), _) => {
+                let default_ty = match literal.is_float {
+                    false => context.default_int_ty(),
+                    true => context.default_float_ty(),
+                };
+                let default_ty = default_ty.ok_or_else(|| {
+                    Diagnostic::new_error("ambiguous literal").with_label(
+                        DiagnosticLabel::new_primary(span)
+                            .with_message("type annotations needed here"),
+                    )
+                })?;
+                let default_ty_value = Rc::from(domain::Value::literal_ty(default_ty));
+
+                unify::unify_values(
+                    context.prims(),
+                    metas,
+                    &literal.env,
+                    span,
+                    &ty,
+                    &default_ty_value,
+                )?;
+                metas.add_defaulted_literal(literal.index, span, default_ty);
+
+                default_ty_value
+            },
+            _ => ty,
+        };
+
+        let kind = match literal.is_float {
+            false => LitKind::Int,
+            true => LitKind::Float,
+        };
+        let src = SpannedString::new(span.source(), span.start(), &literal.src);
+        let literal_intro = check(context, metas, kind, &src, &ty)?;
+        let value = Rc::from(domain::Value::literal_intro(literal_intro));
+
+        unify::unify_values(
+            context.prims(),
+            metas,
+            &literal.env,
+            span,
+            &literal.value,
+            &value,
+        )?;
     }
+
+    Ok(())
 }
 
-pub fn parse_string(src: &SpannedString<'_>) -> Result<String, Diagnostic<FileSpan>> {
-    let mut chars = src.slice.chars();
-    let mut string = String::new();
+/// Warn about the literals created from the metavariable at `start` onwards
+/// whose types were defaulted.
+pub fn defaulting_warnings(
+    metas: &meta::Env,
+    start: meta::Index,
+) -> impl Iterator<Item = Diagnostic<FileSpan>> + '_ {
+    metas.defaulted_literals(start).map(|(span, ty)| {
+        Diagnostic::new_warning(format!("defaulting the type of this literal to `{}`", ty))
+            .with_label(
+                DiagnosticLabel::new_primary(span)
+                    .with_message("consider adding a type annotation"),
+            )
+    })
+}
 
-    assert_eq!(chars.next(), Some('"'));
+/// The field of a `#[from_string]` instance that converts string literals.
+const FROM_STRING_FIELD: &str = "from-string";
 
-    loop {
-        match expect_char(src.span(), &mut chars)? {
-            '"' => break,
-            '\\' => string.push(parse_escape(src.span(), &mut chars)?),
-            ch => string.push(ch),
+/// Check that the item at the given level can be used as a `#[from_string]`
+/// instance, ie. that it is a record with a `from-string` field that takes a
+/// `String` as its argument.
+pub fn check_string_instance(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    var_level: var::Level,
+) -> Result<(), Diagnostic<FileSpan>> {
+    use mltt_core::domain::Value::{FunType, LiteralType};
+    use mltt_core::literal::LiteralType as LitType;
+
+    let (var_index, instance_ty) = match context.lookup_level(var_level) {
+        None => return literal_bug(span, "string instance not found in context"),
+        Some((var_index, instance_ty)) => (var_index, instance_ty.clone()),
+    };
+    let instance = Rc::from(syntax::Term::var(var_index));
+
+    if let Some((_, field_ty)) = from_string_field(context, metas, instance, &instance_ty)? {
+        if let FunType(_, AppMode::Explicit, _, param_ty, _) =
+            context.force_value(metas, span, &field_ty)?.as_ref()
+        {
+            if let LiteralType(LitType::String) =
+                context.force_value(metas, span, param_ty)?.as_ref()
+            {
+                return Ok(());
+            }
         }
     }
 
-    assert_eq!(chars.next(), None);
-
-    Ok(string)
+    Err(
+        Diagnostic::new_error("invalid `from_string` instance").with_label(
+            DiagnosticLabel::new_primary(span).with_message(format!(
+                "expected a record with a `{} : String -> ...` field",
+                FROM_STRING_FIELD,
+            )),
+        ),
+    )
 }
 
-pub fn parse_char(src: &SpannedString<'_>) -> Result<char, Diagnostic<FileSpan>> {
-    let mut chars = src.slice.chars();
+/// Check a string literal against a type other than `String`, using the
+/// `#[from_string]` instance whose `from-string` field returns the expected
+/// type.
+///
+/// Returns `None` if no instance returns the expected type.
+pub fn check_from_string(
+    context: &Context,
+    metas: &mut meta::Env,
+    src: &SpannedString<'_>,
+    expected_ty: &Rc<domain::Type>,
+) -> Result<Option<Rc<syntax::Term>>, Diagnostic<FileSpan>> {
+    use mltt_core::domain::Value::FunType;
+
+    let literal_intro = LiteralIntro::String(Rc::from(decode_string(src)?.value));
+    let literal = Rc::from(syntax::Term::literal_intro(literal_intro));
+    let literal_value = context.eval_term(metas, src.span(), &literal)?;
+    let mut matches = Vec::new();
+
+    for var_level in context.string_instances().iter().cloned() {
+        let (var_index, instance_ty) = match context.lookup_level(var_level) {
+            None => return literal_bug(src.span(), "string instance not found in context"),
+            Some((var_index, instance_ty)) => (var_index, instance_ty.clone()),
+        };
+        let instance = Rc::from(syntax::Term::var(var_index));
+        let (from_string, from_string_ty) =
+            match from_string_field(context, metas, instance, &instance_ty)? {
+                None => continue,
+                Some(field) => field,
+            };
 
-    assert_eq!(chars.next(), Some('\''));
+        // Use a copy of the metavariables, so that the solutions from
+        // instances that don't match are discarded
+        let mut attempt_metas = metas.clone();
+        let from_string_ty = context.force_value(&attempt_metas, src.span(), &from_string_ty)?;
+        if let FunType(_, AppMode::Explicit, _, _, body_ty) = from_string_ty.as_ref() {
+            let ty = context.app_closure(&attempt_metas, body_ty, literal_value.clone())?;
+            if context
+                .unify_values(&mut attempt_metas, src.span(), &ty, expected_ty)
+                .is_ok()
+            {
+                let term = syntax::Term::FunElim(from_string, AppMode::Explicit, literal.clone());
+                matches.push((var_level, Rc::from(term), attempt_metas));
+            }
+        }
+    }
 
-    let ch = match expect_char(src.span(), &mut chars)? {
-        '\'' => literal_bug(src.span(), "unexpected end of character"),
-        '\\' => parse_escape(src.span(), &mut chars),
-        ch => Ok(ch),
-    }?;
+    match matches.len() {
+        0 => Ok(None),
+        1 => {
+            let (_, term, attempt_metas) = matches.remove(0);
+            *metas = attempt_metas;
+            Ok(Some(term))
+        },
+        _ => {
+            let candidates = (matches.iter())
+                .filter_map(|(var_level, _, _)| context.lookup_level_name(*var_level))
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+
+            Err(
+                Diagnostic::new_error("ambiguous string literal").with_label(
+                    DiagnosticLabel::new_primary(src.span())
+                        .with_message(format!("candidates: {}", candidates.join(", "))),
+                ),
+            )
+        },
+    }
+}
 
-    assert_eq!(chars.next(), Some('\''));
-    assert_eq!(chars.next(), None);
+/// The elimination of the `from-string` field of an instance, along with the
+/// type of the field.
+type FromStringField = (Rc<syntax::Term>, Rc<domain::Type>);
 
-    Ok(ch)
+/// Lookup the `from-string` field of a `#[from_string]` instance.
+fn from_string_field(
+    context: &Context,
+    metas: &meta::Env,
+    instance: Rc<syntax::Term>,
+    instance_ty: &Rc<domain::Type>,
+) -> Result<Option<FromStringField>, Diagnostic<FileSpan>> {
+    let record_ty = context.force_value(metas, None, instance_ty)?;
+    let record = context.eval_term(metas, None, &instance)?;
+    let label = Label(FROM_STRING_FIELD.to_owned());
+    let mut telescope = Telescope::record(record_ty);
+
+    match context.split_telescope(metas, &mut telescope, &record, &label)? {
+        Some(entry) => {
+            let field = Rc::from(syntax::Term::RecordElim(instance, label));
+            Ok(Some((field, entry.ty)))
+        },
+        None => Ok(None),
+    }
 }
 
-fn parse_escape(
+fn literal_bug<T>(span: FileSpan, message: impl Into<String>) -> Result<T, Diagnostic<FileSpan>> {
+    // FIXME: improve precision of error span
+    Err(Diagnostic::new_bug(message).with_label(DiagnosticLabel::new_primary(span)))
+}
+
+fn expect_char(
     span: FileSpan,
     chars: &mut impl Iterator<Item = char>,
 ) -> Result<char, Diagnostic<FileSpan>> {
-    match expect_char(span, chars)? {
-        '\'' => Ok('\''),
-        '\"' => Ok('\"'),
-        '\\' => Ok('\\'),
-        'n' => Ok('\n'),
-        'r' => Ok('\r'),
-        't' => Ok('\t'),
-        '0' => Ok('\0'),
-        'x' => {
-            let mut code = 0;
-
-            match expect_char(span, chars)? {
-                ch @ '0'..='7' => code = code * 16 + (ch as u32 - '0' as u32),
-                _ => literal_bug(span, "invalid ascii escape")?,
-            };
-
-            match expect_char(span, chars)? {
-                ch @ '0'..='9' => code = code * 16 + (ch as u32 - '0' as u32),
-                ch @ 'a'..='f' => code = code * 16 + (ch as u32 - 'a' as u32 + 10),
-                ch @ 'A'..='F' => code = code * 16 + (ch as u32 - 'A' as u32 + 10),
-                _ => literal_bug(span, "invalid ascii escape")?,
-            };
-
-            match std::char::from_u32(code) {
-                Some(ch) => Ok(ch),
-                None => literal_bug(span, "invalid ascii escape"),
-            }
-        },
-        'u' => {
-            let mut code = 0;
-
-            assert_eq!(chars.next(), Some('{'));
-            loop {
-                match expect_char(span, chars)? {
-                    ch @ '0'..='9' => code = code * 16 + (ch as u32 - '0' as u32),
-                    ch @ 'a'..='f' => code = code * 16 + (ch as u32 - 'a' as u32 + 10),
-                    ch @ 'A'..='F' => code = code * 16 + (ch as u32 - 'A' as u32 + 10),
-                    '_' => continue,
-                    '}' => break,
-                    _ => literal_bug(span, "invalid unicode escape")?,
-                }
-            }
-
-            match std::char::from_u32(code) {
-                Some(ch) => Ok(ch),
-                None => literal_bug(span, "invalid unicode escape"),
-            }
-        },
-        _ => literal_bug(span, "unknown escape code"),
+    match chars.next() {
+        Some(ch) => Ok(ch),
+        None => literal_bug(span, "unexpected EOF"),
     }
 }
 
@@ -199,8 +378,8 @@ pub trait ParseIntLiteral: Sized + fmt::Display + fmt::Binary + fmt::Octal + fmt
 macro_rules! impl_parse_int_literal {
     ($T:ident) => {
         impl ParseIntLiteral for $T {
-            const MIN: $T = std::$T::MIN;
-            const MAX: $T = std::$T::MAX;
+            const MIN: $T = $T::MIN;
+            const MAX: $T = $T::MAX;
 
             fn from_u8(num: u8) -> $T {
                 num as $T
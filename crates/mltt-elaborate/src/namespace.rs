@@ -0,0 +1,191 @@
+//! Namespaces of items.
+//!
+//! This is shared between the elaboration context and the name resolver,
+//! which bind names to different kinds of entries.
+
+/// The items of a namespace, keyed by their unqualified names.
+pub type Items<Entry> = im::HashMap<String, Entry>;
+
+/// The namespaces that are in scope.
+///
+/// Persistent data structures are used internally, so it shouldn't be too
+/// costly to clone this when entering into new scopes.
+#[derive(Debug, Clone)]
+pub struct Namespaces<Entry: Clone> {
+    /// The items of the namespaces in scope, keyed by the qualified name of
+    /// the namespace. Nested namespaces are given qualified names, for example
+    /// `Foo.Bar`.
+    namespaces: im::HashMap<String, Items<Entry>>,
+    /// Items that were re-exported into the namespace that is currently being
+    /// defined, using `public open`.
+    exported_items: Items<Entry>,
+    /// Namespaces that were defined in, or re-exported into, the namespace that
+    /// is currently being defined.
+    exported_namespaces: im::HashMap<String, Items<Entry>>,
+}
+
+impl<Entry: Clone> Namespaces<Entry> {
+    /// Create an empty set of namespaces.
+    pub fn new() -> Namespaces<Entry> {
+        Namespaces {
+            namespaces: im::HashMap::new(),
+            exported_items: im::HashMap::new(),
+            exported_namespaces: im::HashMap::new(),
+        }
+    }
+
+    /// The namespaces in scope when entering into the definition of a new
+    /// namespace.
+    pub fn enter(&self) -> Namespaces<Entry> {
+        Namespaces {
+            namespaces: self.namespaces.clone(),
+            exported_items: im::HashMap::new(),
+            exported_namespaces: im::HashMap::new(),
+        }
+    }
+
+    /// The namespaces in scope, keyed by their qualified names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Items<Entry>)> {
+        (self.namespaces.iter()).map(|(name, items)| (name.as_str(), items))
+    }
+
+    /// The nested namespaces that will be added along with the namespace that
+    /// is currently being defined, keyed by their names relative to it.
+    pub fn exported_namespaces(&self) -> impl Iterator<Item = (&str, &Items<Entry>)> {
+        (self.exported_namespaces.iter()).map(|(name, items)| (name.as_str(), items))
+    }
+
+    /// Returns `true` if a namespace with the given qualified name is in scope.
+    pub fn contains(&self, name: &str) -> bool {
+        self.namespaces.contains_key(name)
+    }
+
+    /// Returns `true` if a namespace with the given name was defined in, or
+    /// re-exported into, the namespace that is currently being defined.
+    pub fn defines(&self, name: &str) -> bool {
+        self.exported_namespaces.contains_key(name)
+    }
+
+    /// Lookup the items of a namespace.
+    pub fn get(&self, name: &str) -> Option<&Items<Entry>> {
+        self.namespaces.get(name)
+    }
+
+    /// Lookup an item in a namespace.
+    pub fn lookup_item(&self, namespace: &str, name: &str) -> Option<&Entry> {
+        self.namespaces.get(namespace)?.get(name)
+    }
+
+    /// Bring a namespace into scope, without affecting the exports of the
+    /// namespace currently being defined.
+    pub fn insert(&mut self, name: impl Into<String>, items: Items<Entry>) {
+        self.namespaces.insert(name.into(), items);
+    }
+
+    /// Add a namespace, given the items that were defined in it, and the
+    /// namespaces that were in scope at the end of its definition.
+    ///
+    /// If a namespace of the same name was already defined in the namespace
+    /// currently being defined, it is reopened, and the new items are added
+    /// to it.
+    pub fn add(&mut self, name: &str, items: Items<Entry>, inner: Namespaces<Entry>) {
+        // Items defined in the namespace take precedence over re-exports
+        let mut all_items = inner.exported_items;
+        all_items.extend(items);
+
+        for (nested_name, nested_items) in inner.exported_namespaces {
+            let qualified_name = format!("{}.{}", name, nested_name);
+            self.add_items(qualified_name, nested_items);
+        }
+        self.add_items(name.to_owned(), all_items);
+    }
+
+    /// Add some items to a namespace that is exported from the namespace
+    /// currently being defined, keeping any items that it already has.
+    fn add_items(&mut self, name: String, items: Items<Entry>) {
+        let mut all_items = match self.exported_namespaces.get(&name) {
+            None => Items::new(),
+            Some(existing_items) => existing_items.clone(),
+        };
+        all_items.extend(items);
+
+        self.namespaces.insert(name.clone(), all_items.clone());
+        self.exported_namespaces.insert(name, all_items);
+    }
+
+    /// Open the namespace with the given qualified name, returning the items
+    /// that match the predicate, or `None` if the namespace could not be found.
+    ///
+    /// Nested namespaces that match the predicate can then be referred to
+    /// relative to the opened namespace. If the namespace is opened publicly,
+    /// the items and nested namespaces are re-exported from the namespace
+    /// currently being defined.
+    pub fn open(
+        &mut self,
+        name: &str,
+        includes: impl Fn(&str) -> bool,
+        is_public: bool,
+    ) -> Option<Vec<(String, Entry)>> {
+        let items = (self.namespaces.get(name)?.iter())
+            .filter(|(item_name, _)| includes(item_name))
+            .map(|(item_name, entry)| (item_name.clone(), entry.clone()))
+            .collect::<Vec<_>>();
+
+        let prefix = format!("{}.", name);
+        let nested_namespaces = (self.namespaces.iter())
+            .filter_map(|(namespace_name, namespace_items)| {
+                let nested_name = namespace_name.strip_prefix(&prefix)?;
+                let outermost_name = nested_name.split('.').next().unwrap_or(nested_name);
+                if includes(outermost_name) {
+                    Some((nested_name.to_owned(), namespace_items.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if is_public {
+            self.exported_items.extend(items.iter().cloned());
+            (self.exported_namespaces).extend(nested_namespaces.iter().cloned());
+        }
+        self.namespaces.extend(nested_namespaces);
+
+        Some(items)
+    }
+
+    /// Allow a namespace, and the namespaces nested inside it, to be referred
+    /// to using another name, returning `false` if the namespace could not be
+    /// found.
+    ///
+    /// If the alias is public, it is re-exported from the namespace currently
+    /// being defined.
+    pub fn alias(&mut self, name: &str, alias: &str, is_public: bool) -> bool {
+        let prefix = format!("{}.", name);
+        let aliased_namespaces = (self.namespaces.iter())
+            .filter_map(|(namespace_name, namespace_items)| {
+                let aliased_name = match namespace_name.strip_prefix(&prefix) {
+                    None if namespace_name == name => alias.to_owned(),
+                    None => return None,
+                    Some(nested_name) => format!("{}.{}", alias, nested_name),
+                };
+                Some((aliased_name, namespace_items.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        if aliased_namespaces.is_empty() {
+            return false;
+        }
+        if is_public {
+            (self.exported_namespaces).extend(aliased_namespaces.iter().cloned());
+        }
+        self.namespaces.extend(aliased_namespaces);
+
+        true
+    }
+}
+
+impl<Entry: Clone> Default for Namespaces<Entry> {
+    fn default() -> Namespaces<Entry> {
+        Namespaces::new()
+    }
+}
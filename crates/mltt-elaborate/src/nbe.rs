@@ -1,9 +1,9 @@
 //! Wrappers around the core NBE functions that return diagnostics on errors.
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_core::{domain, meta, nbe, prim, syntax, var, AppMode, Label};
+use mltt_core::telescope::{Entry, Telescope};
+use mltt_core::{domain, meta, nbe, prim, steps, syntax, var, AppMode, Label, Rc};
 use mltt_span::FileSpan;
-use std::rc::Rc;
 
 pub fn eval_fun_elim(
     prims: &prim::Env,
@@ -47,14 +47,17 @@ pub fn app_closure(
 pub fn eval_term(
     prims: &prim::Env,
     metas: &meta::Env,
+    strategy: nbe::Strategy,
     values: &var::Env<Rc<domain::Value>>,
     span: impl Into<Option<FileSpan>>,
     term: &Rc<syntax::Term>,
 ) -> Result<Rc<domain::Value>, Diagnostic<FileSpan>> {
-    nbe::eval_term(prims, metas, values, term).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to evaluate term: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to evaluate term")
-            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
+    nbe::eval_term_with_strategy(prims, metas, strategy, values, term).map_err(|error| {
+        match span.into() {
+            None => Diagnostic::new_bug(format!("failed to evaluate term: {}", error)),
+            Some(span) => Diagnostic::new_bug("failed to evaluate term")
+                .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
+        }
     })
 }
 
@@ -72,16 +75,48 @@ pub fn read_back_value(
     })
 }
 
-pub fn normalize_term(
+pub fn read_back_value_shared(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    env_size: var::Size,
+    span: impl Into<Option<FileSpan>>,
+    value: &Rc<domain::Value>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    nbe::read_back_value_shared(prims, metas, env_size, value).map_err(|error| match span.into() {
+        None => Diagnostic::new_bug(format!("failed to read-back value: {}", error)),
+        Some(span) => Diagnostic::new_bug("failed to read-back value")
+            .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
+    })
+}
+
+pub fn read_back_whnf(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    env_size: var::Size,
+    span: impl Into<Option<FileSpan>>,
+    value: &Rc<domain::Value>,
+    elide_size: usize,
+) -> Result<nbe::ElidedTerm, Diagnostic<FileSpan>> {
+    nbe::read_back_whnf(prims, metas, env_size, value, elide_size).map_err(|error| {
+        match span.into() {
+            None => Diagnostic::new_bug(format!("failed to read-back value: {}", error)),
+            Some(span) => Diagnostic::new_bug("failed to read-back value")
+                .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
+        }
+    })
+}
+
+pub fn reduction_steps(
     prims: &prim::Env,
     metas: &meta::Env,
     values: &var::Env<Rc<domain::Value>>,
     span: impl Into<Option<FileSpan>>,
     term: &Rc<syntax::Term>,
-) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-    nbe::normalize_term(prims, metas, values, term).map_err(|error| match span.into() {
-        None => Diagnostic::new_bug(format!("failed to normalize term: {}", error)),
-        Some(span) => Diagnostic::new_bug("failed to normalize term")
+    fuel: usize,
+) -> Result<Vec<steps::ReductionStep>, Diagnostic<FileSpan>> {
+    steps::reduction_steps(prims, metas, values, term, fuel).map_err(|error| match span.into() {
+        None => Diagnostic::new_bug(format!("failed to reduce term: {}", error)),
+        Some(span) => Diagnostic::new_bug("failed to reduce term")
             .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
     })
 }
@@ -98,3 +133,34 @@ pub fn force_value(
             .with_label(DiagnosticLabel::new_primary(span).with_message(error)),
     })
 }
+
+pub fn instantiate_telescope(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    telescope: &mut Telescope,
+    arg: Rc<domain::Value>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    (telescope.instantiate(prims, metas, arg))
+        .map_err(|error| Diagnostic::new_bug(format!("failed to instantiate telescope: {}", error)))
+}
+
+pub fn split_telescope(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    telescope: &mut Telescope,
+    record: &Rc<domain::Value>,
+    label: &Label,
+) -> Result<Option<Entry>, Diagnostic<FileSpan>> {
+    (telescope.split(prims, metas, record, label))
+        .map_err(|error| Diagnostic::new_bug(format!("failed to split telescope: {}", error)))
+}
+
+pub fn weaken_telescope(
+    prims: &prim::Env,
+    metas: &meta::Env,
+    env_size: var::Size,
+    telescope: Telescope,
+) -> Result<(Vec<Entry>, Rc<domain::Type>), Diagnostic<FileSpan>> {
+    (telescope.weaken(prims, metas, env_size))
+        .map_err(|error| Diagnostic::new_bug(format!("failed to weaken telescope: {}", error)))
+}
@@ -0,0 +1,340 @@
+//! Helpers derived for record type definitions.
+//!
+//! For every definition of a record type, like `Point = Record { x : S32; y :
+//! S32 };`, we generate a namespace of the same name containing:
+//!
+//! - `Point.mk`: a curried constructor, taking each of the fields in turn
+//! - `Point.elim`: passes the fields of a record to a function
+//! - `Point.x`, `Point.y`: projection functions for each of the fields
+//!
+//! This allows records to be passed to higher-order code without the user
+//! having to write this boilerplate themselves. The helpers are built directly
+//! in the core syntax, with their types being read back from the record type,
+//! so that we don't need to worry about shifting the field types under the
+//! binders that we introduce.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::{Definition, RecordTypeField, Term};
+use mltt_core::signature::ModuleSignature;
+use mltt_core::telescope::Telescope;
+use mltt_core::{domain, meta, syntax, var, AppMode, DocString, Label, Rc, UniverseLevel};
+use mltt_span::FileSpan;
+
+use crate::Context;
+
+/// The name of the derived constructor.
+pub const MK: &str = "mk";
+/// The name of the derived eliminator.
+pub const ELIM: &str = "elim";
+
+/// The fields of a definition, if it defines a record type.
+///
+/// Only record types without parameters have helpers derived for them.
+pub fn record_type_fields<'a, 'file>(
+    definition: &'a Definition<'file>,
+) -> Option<&'a [RecordTypeField<'file>]> {
+    if !definition.params.is_empty() {
+        return None;
+    }
+
+    let mut body = &definition.body;
+    loop {
+        match body {
+            Term::Parens(_, term) => body = term,
+            Term::RecordType(_, fields) => return Some(fields),
+            _ => return None,
+        }
+    }
+}
+
+/// The fields of a record type, read back in the context in which they were
+/// bound, along with the context with all of the fields bound.
+type Fields = (
+    Vec<(DocString, Label, Option<String>, Rc<syntax::Term>)>,
+    Context,
+);
+
+/// Derive the helpers for the record type defined at the given level, adding
+/// them to the context of its namespace.
+///
+/// Returns the core items of the helpers and their signature, which still need
+/// to be qualified with the name of the namespace.
+pub fn derive_helpers(
+    context: &mut Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    name: &str,
+    record_level: var::Level,
+    record_ty: &Rc<domain::Type>,
+) -> Result<(Vec<syntax::Item>, ModuleSignature), Diagnostic<FileSpan>> {
+    let mut helpers = Helpers::default();
+
+    let docs = DocString::from(format!("Construct a `{}` from its fields.\n", name));
+    let (ty, term) = derive_mk(context, metas, span, record_level, record_ty)?;
+    add_helper(context, metas, span, &mut helpers, docs, MK, ty, term)?;
+
+    let docs = DocString::from(format!(
+        "Eliminate a `{}` by passing its fields to a function.\n",
+        name,
+    ));
+    let (ty, term) = derive_elim(context, metas, span, record_level, record_ty)?;
+    add_helper(context, metas, span, &mut helpers, docs, ELIM, ty, term)?;
+
+    let (fields, _) = bind_fields(context, metas, span, record_ty)?;
+    for (_, label, _, _) in fields {
+        let (docs, ty, term) =
+            derive_projection(context, metas, span, record_level, record_ty, &label)?;
+        add_helper(context, metas, span, &mut helpers, docs, &label.0, ty, term)?;
+    }
+
+    Ok((helpers.items, helpers.signature))
+}
+
+/// The helpers that have been derived so far.
+#[derive(Default)]
+struct Helpers {
+    items: Vec<syntax::Item>,
+    signature: ModuleSignature,
+}
+
+/// Add a helper to the context, along with its core items and signature.
+#[allow(clippy::too_many_arguments)]
+fn add_helper(
+    context: &mut Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    helpers: &mut Helpers,
+    docs: DocString,
+    name: &str,
+    ty: Rc<syntax::Term>,
+    term: Rc<syntax::Term>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    log::trace!("derived record helper:\t{}\t: {:?}", name, ty);
+
+    let ty_value = context.eval_term(metas, span, &ty)?;
+    let value = context.eval_term(metas, span, &term)?;
+    context.add_defn(name, value.clone(), ty_value.clone());
+
+    let label = Label(name.to_owned());
+    (helpers.signature).add_definition(docs.clone(), label.clone(), ty_value, value);
+    (helpers.items).push(syntax::Item::Declaration(docs.clone(), label.clone(), ty));
+    (helpers.items).push(syntax::Item::Definition(docs, label, term));
+
+    Ok(())
+}
+
+/// A variable that refers to the given level in the context.
+fn var(context: &Context, var_level: var::Level) -> Rc<syntax::Term> {
+    Rc::from(syntax::Term::var(context.values().size().index(var_level)))
+}
+
+/// Bind each of the fields of a record type as fresh parameters, reading back
+/// their types as we go.
+fn bind_fields(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    record_ty: &Rc<domain::Type>,
+) -> Result<Fields, Diagnostic<FileSpan>> {
+    let mut context = context.clone();
+    let telescope = Telescope::record(record_ty.clone());
+    let (entries, rest_ty) = context.weaken_telescope(metas, telescope)?;
+
+    if let domain::Value::RecordTypeEmpty = rest_ty.as_ref() {
+        let mut fields = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let label = entry.label().cloned().ok_or_else(|| record_ty_bug(span))?;
+            let field_ty = context.read_back_value(metas, span, &entry.ty)?;
+            context.add_fresh_param(entry.ty);
+            fields.push((entry.doc, label, entry.name_hint, field_ty));
+        }
+
+        Ok((fields, context))
+    } else {
+        Err(record_ty_bug(span))
+    }
+}
+
+fn record_ty_bug(span: FileSpan) -> Diagnostic<FileSpan> {
+    Diagnostic::new_bug("expected a record type").with_label(DiagnosticLabel::new_primary(span))
+}
+
+/// Abstract a term over the fields of a record type, as a function type.
+fn fun_type(
+    fields: Vec<(DocString, Label, Option<String>, Rc<syntax::Term>)>,
+    body_ty: Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    fields
+        .into_iter()
+        .rev()
+        .fold(body_ty, |acc, (docs, label, name_hint, field_ty)| {
+            let name_hint = name_hint.or(Some(label.0));
+            Rc::from(syntax::Term::FunType(
+                docs,
+                AppMode::Explicit,
+                name_hint,
+                field_ty,
+                acc,
+            ))
+        })
+}
+
+/// Derive the type and term of the constructor:
+///
+/// ```text
+/// mk : Fun (x : S32) (y : S32) -> Point;
+/// mk x y = record { x = x; y = y };
+/// ```
+fn derive_mk(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    record_level: var::Level,
+    record_ty: &Rc<domain::Type>,
+) -> Result<(Rc<syntax::Term>, Rc<syntax::Term>), Diagnostic<FileSpan>> {
+    let (fields, fields_context) = bind_fields(context, metas, span, record_ty)?;
+    let field_count = fields.len();
+
+    // Each field is in the scope of the fields before it, so the parameters
+    // are always found at the same index
+    let intro_fields = (fields.iter())
+        .map(|(_, label, _, _)| {
+            let index = var::Index(field_count as u32 - 1);
+            (label.clone(), Rc::from(syntax::Term::var(index)))
+        })
+        .collect();
+    let term = (fields.iter().rev()).fold(
+        Rc::from(syntax::Term::RecordIntro(intro_fields)),
+        |acc, (_, label, name_hint, _)| {
+            let name_hint = name_hint.clone().or_else(|| Some(label.0.clone()));
+            Rc::from(syntax::Term::FunIntro(AppMode::Explicit, name_hint, acc))
+        },
+    );
+    let ty = fun_type(fields, var(&fields_context, record_level));
+
+    Ok((ty, term))
+}
+
+/// Derive the type and term of the eliminator:
+///
+/// ```text
+/// elim : Fun {C : Type} (f : Fun (x : S32) (y : S32) -> C) (r : Point) -> C;
+/// elim f r = f r.x r.y;
+/// ```
+fn derive_elim(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    record_level: var::Level,
+    record_ty: &Rc<domain::Type>,
+) -> Result<(Rc<syntax::Term>, Rc<syntax::Term>), Diagnostic<FileSpan>> {
+    let motive_label = Label("C".to_owned());
+    let motive_name = Some(motive_label.0.clone());
+    let motive_app_mode = AppMode::Implicit(motive_label);
+    let universe = Rc::from(syntax::Term::Universe(UniverseLevel(0)));
+
+    let mut elim_context = context.clone();
+    let motive_level = elim_context.values().size().next_level();
+    let universe_value = elim_context.eval_term(metas, span, &universe)?;
+    elim_context.add_fresh_param(universe_value);
+
+    let (fields, fields_context) = bind_fields(&elim_context, metas, span, record_ty)?;
+    let labels = (fields.iter())
+        .map(|(_, label, _, _)| label.clone())
+        .collect::<Vec<_>>();
+    let method_ty = fun_type(fields, var(&fields_context, motive_level));
+    let method_ty_value = elim_context.eval_term(metas, span, &method_ty)?;
+    elim_context.add_fresh_param(method_ty_value);
+
+    let record_term_ty = var(&elim_context, record_level);
+    elim_context.add_fresh_param(record_ty.clone());
+    let body_ty = var(&elim_context, motive_level);
+
+    let ty = Rc::from(syntax::Term::FunType(
+        DocString::from(""),
+        motive_app_mode.clone(),
+        motive_name.clone(),
+        universe,
+        Rc::from(syntax::Term::FunType(
+            DocString::from(""),
+            AppMode::Explicit,
+            Some("f".to_owned()),
+            method_ty,
+            Rc::from(syntax::Term::FunType(
+                DocString::from(""),
+                AppMode::Explicit,
+                Some("r".to_owned()),
+                record_term_ty,
+                body_ty,
+            )),
+        )),
+    ));
+
+    let method = Rc::from(syntax::Term::var(1));
+    let body = labels.into_iter().fold(method, |acc, label| {
+        let record = Rc::from(syntax::Term::var(0));
+        let field = Rc::from(syntax::Term::RecordElim(record, label));
+        Rc::from(syntax::Term::FunElim(acc, AppMode::Explicit, field))
+    });
+    let term = Rc::from(syntax::Term::FunIntro(
+        motive_app_mode,
+        motive_name,
+        Rc::from(syntax::Term::FunIntro(
+            AppMode::Explicit,
+            Some("f".to_owned()),
+            Rc::from(syntax::Term::FunIntro(
+                AppMode::Explicit,
+                Some("r".to_owned()),
+                body,
+            )),
+        )),
+    ));
+
+    Ok((ty, term))
+}
+
+/// Derive the docs, type, and term of the projection for the field with the
+/// given label:
+///
+/// ```text
+/// x : Fun (r : Point) -> S32;
+/// x r = r.x;
+/// ```
+///
+/// The types of the later fields may depend on the earlier fields, so these
+/// are instantiated with projections from the record parameter.
+fn derive_projection(
+    context: &Context,
+    metas: &meta::Env,
+    span: FileSpan,
+    record_level: var::Level,
+    record_ty: &Rc<domain::Type>,
+    label: &Label,
+) -> Result<(DocString, Rc<syntax::Term>, Rc<syntax::Term>), Diagnostic<FileSpan>> {
+    let mut record_context = context.clone();
+    let record_term_ty = var(&record_context, record_level);
+    let record = record_context.add_fresh_param(record_ty.clone());
+    let mut telescope = Telescope::record(record_ty.clone());
+
+    let entry = (record_context.split_telescope(metas, &mut telescope, &record, label)?)
+        .ok_or_else(|| record_ty_bug(span))?;
+    let field = Rc::from(syntax::Term::RecordElim(
+        Rc::from(syntax::Term::var(0)),
+        label.clone(),
+    ));
+
+    let ty = Rc::from(syntax::Term::FunType(
+        entry.doc.clone(),
+        AppMode::Explicit,
+        Some("r".to_owned()),
+        record_term_ty,
+        record_context.read_back_value(metas, span, &entry.ty)?,
+    ));
+    let term = Rc::from(syntax::Term::FunIntro(
+        AppMode::Explicit,
+        Some("r".to_owned()),
+        field,
+    ));
+
+    Ok((entry.doc, ty, term))
+}
@@ -0,0 +1,982 @@
+//! Name resolution.
+//!
+//! This classifies the identifiers in the concrete syntax before type checking
+//! begins, following the same scoping rules as the elaborator. Doing this in
+//! a separate pass means that unbound names can be reported up-front, and that
+//! unused or shadowed bindings can be found without knowing anything about the
+//! types of the terms involved.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::literal::decode_string;
+use mltt_concrete::{
+    Arg, Definition, Imports, IntroParam, Item, Open, Pattern, RecordIntroField, RecordTypeField,
+    SpannedString, Term, TypeParam,
+};
+use mltt_core::{domain, prim};
+use mltt_span::FileSpan;
+
+use crate::namespace::Namespaces;
+use crate::record;
+use crate::Context;
+
+/// The kind of entity that a name refers to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NameKind {
+    /// A parameter or pattern variable, bound at the given span.
+    Local(FileSpan),
+    /// An item, defined at the given span. Items that were defined outside of
+    /// the syntax being resolved, for example in the prelude, have no span.
+    Item(Option<FileSpan>),
+    /// A primitive, for example `primitive "string-eq"`.
+    Prim,
+    /// A data constructor, for example `true` or `false`.
+    Constructor,
+    /// A pattern synonym, defined at the given span. Pattern synonyms that
+    /// were defined outside of the syntax being resolved have no span.
+    PatternSynonym(Option<FileSpan>),
+    /// A name that could not be resolved.
+    Unknown,
+}
+
+/// A name, classified by the entity that it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedName<'file> {
+    pub name: SpannedString<'file>,
+    pub kind: NameKind,
+}
+
+/// The names found in some concrete syntax.
+#[derive(Debug)]
+pub struct Resolution<'file> {
+    /// The names that were found, in the order that they were resolved.
+    pub names: Vec<ResolvedName<'file>>,
+    /// The names that were bound or declared, in the order that they were
+    /// found.
+    pub binders: Vec<SpannedString<'file>>,
+    /// The labels of record fields, in the order that they were found.
+    pub labels: Vec<SpannedString<'file>>,
+    /// Names that could not be resolved.
+    pub errors: Vec<Diagnostic<FileSpan>>,
+    /// Bindings that were unused or shadowed.
+    pub warnings: Vec<Diagnostic<FileSpan>>,
+}
+
+impl<'file> Resolution<'file> {
+    /// Lookup the kind of the name found at the given span.
+    pub fn lookup(&self, span: FileSpan) -> Option<NameKind> {
+        (self.names.iter())
+            .find(|resolved| resolved.name.span() == span)
+            .map(|resolved| resolved.kind)
+    }
+}
+
+/// Resolve the names in some items, in the scope of the given context.
+pub fn resolve_items<'file>(context: &Context, items: &[Item<'file>]) -> Resolution<'file> {
+    let mut resolver = Resolver::new(context.prims());
+    let mut scope = resolver.initial_scope(context);
+    resolver.resolve_items(&mut scope, items);
+    resolver.finish()
+}
+
+/// Resolve the names in a term, in the scope of the given context.
+pub fn resolve_term<'file>(context: &Context, term: &Term<'file>) -> Resolution<'file> {
+    let mut resolver = Resolver::new(context.prims());
+    let scope = resolver.initial_scope(context);
+    resolver.resolve_term(&scope, term);
+    resolver.finish()
+}
+
+/// A binding in scope.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Binding {
+    /// The kind of entity that was bound.
+    kind: NameKind,
+    /// The order in which the binding was introduced.
+    level: usize,
+}
+
+/// The bindings in scope.
+///
+/// Persistent data structures are used so that this is cheap to clone when
+/// entering into new scopes, as is done for the elaboration context.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// Names that can be referred to without qualification.
+    bindings: im::HashMap<String, Binding>,
+    /// The namespaces in scope.
+    namespaces: Namespaces<Binding>,
+}
+
+impl Scope {
+    /// The scope to use when resolving the items of a namespace.
+    fn namespace_scope(&self) -> Scope {
+        Scope {
+            bindings: self.bindings.clone(),
+            namespaces: self.namespaces.enter(),
+        }
+    }
+
+    /// Add the items that were bound in the scope of a namespace, from the
+    /// given level onwards.
+    ///
+    /// Pattern synonyms are not exported from namespaces.
+    fn add_namespace(&mut self, name: &str, namespace_scope: Scope, start_level: usize) {
+        let items = (namespace_scope.bindings.into_iter())
+            .filter(|(_, binding)| binding.level >= start_level)
+            .filter(|(_, binding)| !matches!(binding.kind, NameKind::PatternSynonym(_)))
+            .collect();
+
+        (self.namespaces).add(name, items, namespace_scope.namespaces);
+    }
+
+    /// Bring the items of a namespace that match the given predicate into
+    /// scope, along with any matching nested namespaces.
+    fn open_namespace(&mut self, name: &str, includes: impl Fn(&str) -> bool, is_public: bool) {
+        if let Some(items) = self.namespaces.open(name, includes, is_public) {
+            self.bindings.extend(items);
+        }
+    }
+
+    /// Resolve a term to the qualified name of a namespace, if it refers to
+    /// one. Bound names take precedence over namespaces of the same name.
+    fn namespace_path(&self, term: &Term<'_>) -> Option<String> {
+        let path = match term {
+            Term::Var(name) if !self.bindings.contains_key(name.slice) => name.to_string(),
+            Term::RecordElim(term, label) => format!("{}.{}", self.namespace_path(term)?, label),
+            _ => return None,
+        };
+
+        if self.namespaces.contains(&path) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+}
+
+struct Resolver<'prims, 'file> {
+    prims: &'prims prim::Env,
+    names: Vec<ResolvedName<'file>>,
+    binders: Vec<SpannedString<'file>>,
+    labels: Vec<SpannedString<'file>>,
+    errors: Vec<Diagnostic<FileSpan>>,
+    warnings: Vec<Diagnostic<FileSpan>>,
+    /// Whether the binding at each level has been referred to.
+    used: Vec<bool>,
+    /// Binders that should be reported if they end up being unused.
+    unused_candidates: Vec<(usize, SpannedString<'file>)>,
+}
+
+impl<'prims, 'file> Resolver<'prims, 'file> {
+    fn new(prims: &'prims prim::Env) -> Resolver<'prims, 'file> {
+        Resolver {
+            prims,
+            names: Vec::new(),
+            binders: Vec::new(),
+            labels: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            used: Vec::new(),
+            unused_candidates: Vec::new(),
+        }
+    }
+
+    /// Create a scope containing the names defined in the context.
+    ///
+    /// Names that are bound to literal values, like `true` and `false`, are
+    /// treated as constructors.
+    fn initial_scope(&mut self, context: &Context) -> Scope {
+        let mut scope = Scope::default();
+
+        for (name, value) in context.named_values() {
+            let kind = match value.as_ref() {
+                domain::Value::LiteralIntro(_) => NameKind::Constructor,
+                _ => NameKind::Item(None),
+            };
+            let binding = self.fresh_binding(kind);
+            scope.bindings.insert(name.to_owned(), binding);
+        }
+        for name in context.pattern_synonym_names() {
+            let binding = self.fresh_binding(NameKind::PatternSynonym(None));
+            scope.bindings.insert(name.to_owned(), binding);
+        }
+        for (namespace_name, item_names) in context.namespace_items() {
+            let items = (item_names.into_iter())
+                .map(|name| (name.to_owned(), self.fresh_binding(NameKind::Item(None))))
+                .collect();
+            scope.namespaces.insert(namespace_name, items);
+        }
+
+        scope
+    }
+
+    /// Report the unused bindings, returning the result of the resolution.
+    fn finish(mut self) -> Resolution<'file> {
+        for (level, name) in &self.unused_candidates {
+            if !self.used[*level] && !name.slice.starts_with('_') {
+                self.warnings.push(
+                    Diagnostic::new_warning(format!("unused variable `{}`", name)).with_label(
+                        DiagnosticLabel::new_primary(name.span())
+                            .with_message(format!("consider renaming this to `_{}`", name)),
+                    ),
+                );
+            }
+        }
+
+        Resolution {
+            names: self.names,
+            binders: self.binders,
+            labels: self.labels,
+            errors: self.errors,
+            warnings: self.warnings,
+        }
+    }
+
+    fn fresh_binding(&mut self, kind: NameKind) -> Binding {
+        let level = self.used.len();
+        self.used.push(false);
+        Binding { kind, level }
+    }
+
+    /// Bind an item in the current scope.
+    fn bind_item(&mut self, scope: &mut Scope, label: SpannedString<'file>) {
+        let binding = self.fresh_binding(NameKind::Item(Some(label.span())));
+        scope.bindings.insert(label.to_string(), binding);
+        self.binders.push(label);
+    }
+
+    /// Bind a local variable in the current scope, warning if it shadows
+    /// another local variable.
+    fn bind_local(&mut self, scope: &mut Scope, name: SpannedString<'file>, warn_unused: bool) {
+        if let Some(Binding {
+            kind: NameKind::Local(prev_span),
+            ..
+        }) = scope.bindings.get(name.slice)
+        {
+            self.warnings.push(
+                Diagnostic::new_warning(format!("`{}` shadows an earlier binding", name))
+                    .with_label(DiagnosticLabel::new_primary(name.span()))
+                    .with_label(
+                        DiagnosticLabel::new_secondary(*prev_span)
+                            .with_message("previously bound here"),
+                    ),
+            );
+        }
+
+        let binding = self.fresh_binding(NameKind::Local(name.span()));
+        self.binders.push(name);
+        if warn_unused {
+            self.unused_candidates.push((binding.level, name));
+        }
+        scope.bindings.insert(name.to_string(), binding);
+    }
+
+    /// Record a reference to a binding.
+    fn use_binding(&mut self, name: SpannedString<'file>, binding: Binding) {
+        self.used[binding.level] = true;
+        self.names.push(ResolvedName {
+            name,
+            kind: binding.kind,
+        });
+    }
+
+    /// Record a name that could not be resolved.
+    fn unknown(&mut self, name: SpannedString<'file>, diagnostic: Diagnostic<FileSpan>) {
+        self.errors.push(diagnostic);
+        self.names.push(ResolvedName {
+            name,
+            kind: NameKind::Unknown,
+        });
+    }
+
+    fn resolve_items(&mut self, scope: &mut Scope, items: &[Item<'file>]) {
+        for item in items {
+            match item {
+                Item::Declaration(declaration) => {
+                    self.resolve_term(scope, &declaration.body_ty);
+                    self.binders.push(declaration.label);
+                },
+                Item::Definition(definition) => {
+                    let body_ty = definition.body_ty.as_ref();
+                    match &definition.termination_by {
+                        None => {
+                            let body = &definition.body;
+                            self.resolve_clause(scope, &definition.params, body_ty, body);
+                        },
+                        Some(proof) => self.resolve_recursive_clause(scope, definition, proof),
+                    }
+                    self.bind_item(scope, definition.label);
+
+                    if let Some(fields) = record::record_type_fields(definition) {
+                        self.bind_record_helpers(scope, definition.label, fields);
+                    }
+                },
+                Item::Section(section) => {
+                    let mut section_scope = scope.clone();
+                    self.bind_type_params(&mut section_scope, &section.params);
+                    self.resolve_items(&mut section_scope, &section.items);
+
+                    // The definitions remain in scope once the section has
+                    // been closed, abstracted over the section parameters
+                    for label in section_definitions(&section.items) {
+                        if let Some(binding) = section_scope.bindings.get(label.slice) {
+                            scope.bindings.insert(label.to_string(), *binding);
+                        }
+                    }
+                },
+                Item::Namespace(namespace) => {
+                    let start_level = self.used.len();
+                    let mut namespace_scope = scope.namespace_scope();
+                    // The items of a reopened namespace remain in scope
+                    if scope.namespaces.defines(namespace.name.slice) {
+                        namespace_scope.open_namespace(namespace.name.slice, |_| true, false);
+                    }
+                    self.resolve_items(&mut namespace_scope, &namespace.items);
+                    scope.add_namespace(namespace.name.slice, namespace_scope, start_level);
+                },
+                Item::Open(open) => self.resolve_open(scope, open),
+                Item::PatternSynonym(synonym) => {
+                    self.resolve_synonym_pattern(scope, &synonym.pattern);
+                    let span = synonym.label.span();
+                    let binding = self.fresh_binding(NameKind::PatternSynonym(Some(span)));
+                    scope.bindings.insert(synonym.label.to_string(), binding);
+                    self.binders.push(synonym.label);
+                },
+            }
+        }
+    }
+
+    /// Bind the namespace of helpers that is derived for a record type
+    /// definition.
+    fn bind_record_helpers(
+        &mut self,
+        scope: &mut Scope,
+        label: SpannedString<'file>,
+        fields: &[RecordTypeField<'file>],
+    ) {
+        let start_level = self.used.len();
+        let mut namespace_scope = scope.namespace_scope();
+        for name in &[record::MK, record::ELIM] {
+            let binding = self.fresh_binding(NameKind::Item(Some(label.span())));
+            namespace_scope.bindings.insert(name.to_string(), binding);
+        }
+        for field in fields {
+            self.bind_item(&mut namespace_scope, field.label);
+        }
+        scope.add_namespace(label.slice, namespace_scope, start_level);
+    }
+
+    fn resolve_open(&mut self, scope: &mut Scope, open: &Open<'file>) {
+        let name = open.name.slice;
+        let items = match scope.namespaces.get(name) {
+            Some(items) => items.clone(),
+            None => {
+                let message = format!("unknown namespace `{}`", name);
+                self.errors.push(
+                    Diagnostic::new_error(message).with_label(
+                        DiagnosticLabel::new_primary(open.name.span())
+                            .with_message("not found in this scope"),
+                    ),
+                );
+                return;
+            },
+        };
+
+        for item_name in open.imports.item_names() {
+            match items.get(item_name.slice) {
+                Some(binding) => self.use_binding(*item_name, *binding),
+                // Nested namespaces can also be listed
+                None if scope
+                    .namespaces
+                    .contains(&format!("{}.{}", name, item_name)) => {},
+                None => self.unknown(
+                    *item_name,
+                    Diagnostic::new_error(format!("item not found in namespace `{}`", name))
+                        .with_label(DiagnosticLabel::new_primary(item_name.span())),
+                ),
+            }
+        }
+
+        match &open.imports {
+            Imports::Alias(alias) => {
+                scope.namespaces.alias(name, alias.slice, open.is_public);
+            },
+            imports => {
+                let includes = |item: &str| imports.includes(item);
+                scope.open_namespace(name, includes, open.is_public);
+            },
+        }
+    }
+
+    /// Resolve the names in a clause, binding its parameters for the body.
+    fn resolve_clause(
+        &mut self,
+        scope: &Scope,
+        params: &[IntroParam<'file>],
+        body_ty: Option<&Term<'file>>,
+        body: &Term<'file>,
+    ) {
+        let mut scope = scope.clone();
+        self.bind_intro_params(&mut scope, params);
+
+        if let Some(body_ty) = body_ty {
+            self.resolve_term(&scope, body_ty);
+        }
+        self.resolve_term(&scope, body);
+    }
+
+    /// Resolve a definition with a `termination-by` clause. The proof of
+    /// accessibility is in the scope of the parameters, and the body can also
+    /// refer to the definition itself, to make recursive calls.
+    fn resolve_recursive_clause(
+        &mut self,
+        scope: &Scope,
+        definition: &Definition<'file>,
+        proof: &Term<'file>,
+    ) {
+        let mut scope = scope.clone();
+        self.bind_intro_params(&mut scope, &definition.params);
+
+        if let Some(body_ty) = &definition.body_ty {
+            self.resolve_term(&scope, body_ty);
+        }
+        self.resolve_term(&scope, proof);
+        self.bind_local(&mut scope, definition.label, true);
+        self.resolve_term(&scope, &definition.body);
+    }
+
+    fn bind_intro_params(&mut self, scope: &mut Scope, params: &[IntroParam<'file>]) {
+        for param in params {
+            match param {
+                IntroParam::Explicit(pattern)
+                | IntroParam::Implicit(_, _, Some(pattern))
+                | IntroParam::Instance(_, _, Some(pattern)) => {
+                    self.bind_pattern(scope, pattern);
+                },
+                // The label is needed to match against the parameter type,
+                // so we don't warn if it is unused
+                IntroParam::Implicit(_, label, None) | IntroParam::Instance(_, label, None) => {
+                    self.bind_local(scope, *label, false);
+                },
+            }
+        }
+    }
+
+    fn bind_pattern(&mut self, scope: &mut Scope, pattern: &Pattern<'file>) {
+        match pattern {
+            Pattern::Var(name) => match scope.bindings.get(name.slice) {
+                Some(binding) if matches!(binding.kind, NameKind::PatternSynonym(_)) => {
+                    self.use_binding(*name, *binding);
+                },
+                Some(_) | None => self.bind_local(scope, *name, true),
+            },
+            Pattern::LiteralIntro(_, _) => {},
+            // The bounds of ranges and prefixes can only refer to pattern synonyms
+            Pattern::Range(start, end) => {
+                self.resolve_synonym_pattern(scope, start);
+                self.resolve_synonym_pattern(scope, end);
+            },
+            Pattern::Prefix(_, prefix) => self.resolve_synonym_pattern(scope, prefix),
+            Pattern::Ann(_, pattern, ann) => {
+                self.resolve_term(scope, ann);
+                self.bind_pattern(scope, pattern);
+            },
+        }
+    }
+
+    /// Resolve the names in the pattern of a pattern synonym. Names refer to
+    /// earlier pattern synonyms, rather than binding variables.
+    fn resolve_synonym_pattern(&mut self, scope: &Scope, pattern: &Pattern<'file>) {
+        match pattern {
+            Pattern::Var(name) => self.resolve_var(scope, *name),
+            Pattern::LiteralIntro(_, _) => {},
+            Pattern::Range(start, end) => {
+                self.resolve_synonym_pattern(scope, start);
+                self.resolve_synonym_pattern(scope, end);
+            },
+            Pattern::Prefix(_, prefix) => self.resolve_synonym_pattern(scope, prefix),
+            Pattern::Ann(_, pattern, ann) => {
+                self.resolve_term(scope, ann);
+                self.resolve_synonym_pattern(scope, pattern);
+            },
+        }
+    }
+
+    fn bind_type_params(&mut self, scope: &mut Scope, params: &[TypeParam<'file>]) {
+        for param in params {
+            match param {
+                TypeParam::Explicit(_, _, names, ann) => {
+                    self.resolve_term(scope, ann);
+                    for name in names {
+                        self.bind_local(scope, *name, false);
+                    }
+                },
+                TypeParam::Implicit(_, _, labels, ann) => {
+                    if let Some(ann) = ann {
+                        self.resolve_term(scope, ann);
+                    }
+                    for label in labels {
+                        self.bind_local(scope, *label, false);
+                    }
+                },
+                TypeParam::Instance(_, _, label, ann) => {
+                    self.resolve_term(scope, ann);
+                    self.bind_local(scope, *label, false);
+                },
+            }
+        }
+    }
+
+    fn resolve_var(&mut self, scope: &Scope, name: SpannedString<'file>) {
+        match scope.bindings.get(name.slice) {
+            Some(binding) => self.use_binding(name, *binding),
+            None => self.unknown(
+                name,
+                Diagnostic::new_error("unbound variable")
+                    .with_label(DiagnosticLabel::new_primary(name.span())),
+            ),
+        }
+    }
+
+    fn resolve_qualified(&mut self, scope: &Scope, namespace: &str, name: SpannedString<'file>) {
+        match scope.namespaces.lookup_item(namespace, name.slice) {
+            Some(binding) => self.use_binding(name, *binding),
+            None => self.unknown(
+                name,
+                Diagnostic::new_error(format!("item not found in namespace `{}`", namespace))
+                    .with_label(DiagnosticLabel::new_primary(name.span())),
+            ),
+        }
+    }
+
+    fn resolve_prim(&mut self, name: SpannedString<'file>) {
+        // Invalid string literals are reported during elaboration
+        if let Ok(decoded) = decode_string(&name) {
+            match self.prims.lookup_entry(&prim::Name(decoded.value)) {
+                Some(_) => self.names.push(ResolvedName {
+                    name,
+                    kind: NameKind::Prim,
+                }),
+                None => self.unknown(
+                    name,
+                    Diagnostic::new_error("unknown primitive")
+                        .with_label(DiagnosticLabel::new_primary(name.span())),
+                ),
+            }
+        }
+    }
+
+    fn resolve_term(&mut self, scope: &Scope, term: &Term<'file>) {
+        match term {
+            Term::Var(name) => self.resolve_var(scope, *name),
+            Term::Prim(_, name) => self.resolve_prim(*name),
+            Term::Hole(_) | Term::Placeholder(_) => {},
+
+            Term::Parens(_, term) => self.resolve_term(scope, term),
+            Term::Ann(term, ann) => {
+                self.resolve_term(scope, term);
+                self.resolve_term(scope, ann);
+            },
+            Term::Let(_, items, body) => {
+                let mut scope = scope.clone();
+                self.resolve_items(&mut scope, items);
+                self.resolve_term(&scope, body);
+            },
+            Term::If(_, condition, consequent, alternative) => {
+                self.resolve_term(scope, condition);
+                self.resolve_term(scope, consequent);
+                self.resolve_term(scope, alternative);
+            },
+            Term::Case(_, scrutinee, clauses) | Term::With(_, scrutinee, clauses) => {
+                self.resolve_term(scope, scrutinee);
+                for (pattern, guard, body) in clauses {
+                    let mut scope = scope.clone();
+                    self.bind_pattern(&mut scope, pattern);
+                    if let Some(guard) = guard {
+                        self.resolve_term(&scope, guard);
+                    }
+                    self.resolve_term(&scope, body);
+                }
+            },
+
+            Term::LiteralIntro(_, _) => {},
+
+            Term::FunType(_, params, body_ty) => {
+                let mut scope = scope.clone();
+                self.bind_type_params(&mut scope, params);
+                self.resolve_term(&scope, body_ty);
+            },
+            Term::FunArrowType(param_ty, body_ty) => {
+                self.resolve_term(scope, param_ty);
+                self.resolve_term(scope, body_ty);
+            },
+            Term::FunIntro(_, params, body) => self.resolve_clause(scope, params, None, body),
+            Term::FunElim(fun, args) => {
+                self.resolve_term(scope, fun);
+                for arg in args {
+                    match arg {
+                        Arg::Explicit(term)
+                        | Arg::Implicit(_, _, Some(term))
+                        | Arg::Instance(_, _, Some(term)) => self.resolve_term(scope, term),
+                        // Punned arguments refer to a variable of the same name
+                        Arg::Implicit(_, label, None) | Arg::Instance(_, label, None) => {
+                            self.resolve_var(scope, *label);
+                        },
+                    }
+                }
+            },
+
+            Term::RecordType(_, fields) => {
+                let mut scope = scope.clone();
+                for field in fields {
+                    self.resolve_term(&scope, &field.ann);
+                    let binding = self.fresh_binding(NameKind::Local(field.label.span()));
+                    scope.bindings.insert(field.label.to_string(), binding);
+                    self.labels.push(field.label);
+                }
+            },
+            Term::RecordIntro(_, fields) => {
+                let mut scope = scope.clone();
+                for field in fields {
+                    match field {
+                        RecordIntroField::Punned { label } => self.resolve_var(&scope, *label),
+                        RecordIntroField::Explicit {
+                            label: _,
+                            params,
+                            body_ty,
+                            body,
+                        } => self.resolve_clause(&scope, params, body_ty.as_ref(), body),
+                    }
+                    // Later fields can refer to the earlier ones
+                    let label = match field {
+                        RecordIntroField::Punned { label } => label,
+                        RecordIntroField::Explicit { label, .. } => label,
+                    };
+                    self.bind_item(&mut scope, *label);
+                    self.labels.push(*label);
+                }
+            },
+            Term::RecordElim(record, label) => match scope.namespace_path(record) {
+                Some(namespace) => self.resolve_qualified(scope, &namespace, *label),
+                None => {
+                    self.resolve_term(scope, record);
+                    self.labels.push(*label);
+                },
+            },
+
+            Term::SigmaType(_, name, param_ty, body_ty) => {
+                self.resolve_term(scope, param_ty);
+                let mut scope = scope.clone();
+                let binding = self.fresh_binding(NameKind::Local(name.span()));
+                scope.bindings.insert(name.to_string(), binding);
+                self.resolve_term(&scope, body_ty);
+            },
+            Term::PairIntro(_, fst, snd) => {
+                self.resolve_term(scope, fst);
+                self.resolve_term(scope, snd);
+            },
+
+            Term::ArrayIntro(_, elems) => {
+                for elem in elems {
+                    self.resolve_term(scope, elem);
+                }
+            },
+
+            Term::Universe(_, _) => {},
+        }
+    }
+}
+
+/// The labels of the definitions in a section, including those in nested
+/// sections.
+fn section_definitions<'a, 'file>(items: &'a [Item<'file>]) -> Vec<&'a SpannedString<'file>> {
+    let mut labels = Vec::new();
+    for item in items {
+        match item {
+            Item::Definition(definition) => labels.push(&definition.label),
+            Item::Section(section) => labels.extend(section_definitions(&section.items)),
+            Item::Declaration(_) | Item::Namespace(_) | Item::Open(_) | Item::PatternSynonym(_) => {
+            },
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use mltt_parse::lexer::Lexer;
+    use mltt_parse::parser::parse_term;
+    use mltt_span::{FileId, Files};
+
+    use super::*;
+
+    fn resolve(src: &str, check: impl FnOnce(FileId, Resolution<'_>)) {
+        let mut files = Files::new();
+        let file_id = files.add("test", src);
+        let term = parse_term(Lexer::new(&files[file_id])).unwrap();
+
+        check(file_id, resolve_term(&Context::default(), &term));
+    }
+
+    #[test]
+    fn classify_names() {
+        resolve(
+            "let id (a : Bool) = a; in id true",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.names,
+                    vec![
+                        ResolvedName {
+                            name: SpannedString::new(file_id, 12, "Bool"),
+                            kind: NameKind::Item(None),
+                        },
+                        ResolvedName {
+                            name: SpannedString::new(file_id, 20, "a"),
+                            kind: NameKind::Local(FileSpan::new(file_id, 8, 9)),
+                        },
+                        ResolvedName {
+                            name: SpannedString::new(file_id, 26, "id"),
+                            kind: NameKind::Item(Some(FileSpan::new(file_id, 4, 6))),
+                        },
+                        ResolvedName {
+                            name: SpannedString::new(file_id, 29, "true"),
+                            kind: NameKind::Constructor,
+                        },
+                    ],
+                );
+                assert!(resolution.errors.is_empty());
+                assert!(resolution.warnings.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn binders_and_labels() {
+        resolve(
+            "let p = record { x = 1 }; in fun y => p.x",
+            |file_id, resolution| {
+                let binders = (resolution.binders.iter()).map(|name| name.slice);
+                assert_eq!(binders.collect::<Vec<_>>(), ["x", "p", "y"]);
+                assert_eq!(
+                    resolution.labels,
+                    [
+                        SpannedString::new(file_id, 17, "x"),
+                        SpannedString::new(file_id, 40, "x"),
+                    ],
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn classify_prim() {
+        resolve(r#"primitive "string-eq""#, |file_id, resolution| {
+            let span = FileSpan::new(file_id, 10, 21);
+            assert_eq!(resolution.lookup(span), Some(NameKind::Prim));
+        });
+    }
+
+    #[test]
+    fn unbound_variable() {
+        resolve("fun x => y", |file_id, resolution| {
+            let span = FileSpan::new(file_id, 9, 10);
+            assert_eq!(resolution.lookup(span), Some(NameKind::Unknown));
+            assert_eq!(resolution.errors.len(), 1);
+            assert_eq!(resolution.errors[0].labels[0].span, span);
+        });
+    }
+
+    #[test]
+    fn definition_not_in_scope_in_own_body() {
+        resolve("let x = x; in x", |file_id, resolution| {
+            let span = FileSpan::new(file_id, 8, 9);
+            assert_eq!(resolution.lookup(span), Some(NameKind::Unknown));
+        });
+    }
+
+    #[test]
+    fn unused_variable() {
+        resolve("fun x y _z => x", |file_id, resolution| {
+            assert_eq!(resolution.warnings.len(), 1);
+            assert_eq!(resolution.warnings[0].message, "unused variable `y`");
+            assert_eq!(
+                resolution.warnings[0].labels[0].span,
+                FileSpan::new(file_id, 6, 7),
+            );
+        });
+    }
+
+    #[test]
+    fn shadowed_variable() {
+        resolve("fun x => fun x => x", |file_id, resolution| {
+            let span = FileSpan::new(file_id, 18, 19);
+            assert_eq!(
+                resolution.lookup(span),
+                Some(NameKind::Local(FileSpan::new(file_id, 13, 14))),
+            );
+            assert!(resolution
+                .warnings
+                .iter()
+                .any(|warning| warning.message == "`x` shadows an earlier binding"));
+        });
+    }
+
+    #[test]
+    fn section_params() {
+        resolve(
+            "let section (A : Type) id (a : A) = a; end in id",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 31, 32)),
+                    Some(NameKind::Local(FileSpan::new(file_id, 13, 14))),
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 46, 48)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 23, 25)))),
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn qualified_names() {
+        resolve(
+            "let namespace Foo x = Bool; end in Foo.x",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 39, 40)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 18, 19)))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn reopened_namespaces() {
+        resolve(
+            "let Foo.x = Bool; y = Bool; Foo.z = x; in Foo.z",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 36, 37)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 8, 9)))),
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 46, 47)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 32, 33)))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn unqualified_namespace_item() {
+        resolve(
+            "let namespace Foo x = Bool; end in x",
+            |file_id, resolution| {
+                let span = FileSpan::new(file_id, 35, 36);
+                assert_eq!(resolution.lookup(span), Some(NameKind::Unknown));
+            },
+        );
+    }
+
+    #[test]
+    fn selective_imports() {
+        resolve(
+            "let namespace Foo x = Bool; y = Bool; end open Foo (x); in y",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 52, 53)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 18, 19)))),
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 59, 60)),
+                    Some(NameKind::Unknown),
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn aliased_imports() {
+        resolve(
+            "let namespace Foo x = Bool; end open Foo as F; in F.x",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 52, 53)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 18, 19)))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn public_open() {
+        resolve(
+            "let namespace Foo x = Bool; end namespace Bar public open Foo; end in Bar.x",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 74, 75)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 18, 19)))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn private_open() {
+        resolve(
+            "let namespace Foo x = Bool; end namespace Bar open Foo; end in Bar.x",
+            |file_id, resolution| {
+                let span = FileSpan::new(file_id, 67, 68);
+                assert_eq!(resolution.lookup(span), Some(NameKind::Unknown));
+            },
+        );
+    }
+
+    #[test]
+    fn pattern_synonyms() {
+        resolve(
+            r"let pattern nl = '\n'; in fun c => case c { nl => c; c => c; }",
+            |file_id, resolution| {
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 44, 46)),
+                    Some(NameKind::PatternSynonym(Some(FileSpan::new(
+                        file_id, 12, 14
+                    )))),
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 50, 51)),
+                    Some(NameKind::Local(FileSpan::new(file_id, 30, 31))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn open_record_helpers() {
+        resolve(
+            "let Point = Record { x : S32 }; open Point; in mk (x (elim (\\a => a)))",
+            |file_id, resolution| {
+                let helper_kind = Some(NameKind::Item(Some(FileSpan::new(file_id, 4, 9))));
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 47, 49)),
+                    helper_kind
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 54, 58)),
+                    helper_kind
+                );
+                assert_eq!(
+                    resolution.lookup(FileSpan::new(file_id, 51, 52)),
+                    Some(NameKind::Item(Some(FileSpan::new(file_id, 21, 22)))),
+                );
+                assert!(resolution.errors.is_empty());
+            },
+        );
+    }
+}
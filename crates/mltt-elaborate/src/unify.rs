@@ -1,13 +1,96 @@
 //! Unification of values.
 
 use language_reporting::{Diagnostic, Label as DiagnosticLabel};
-use mltt_core::literal::{LiteralIntro, LiteralType};
-use mltt_core::{domain, meta, prim, syntax, var, AppMode};
+use mltt_core::literal::{LiteralIntro, LiteralPattern, LiteralType};
+use mltt_core::nbe::{ConversionBudget, Strategy};
+use mltt_core::{domain, meta, prim, syntax, var, AppMode, Rc};
 use mltt_span::FileSpan;
-use std::rc::Rc;
 
 use crate::nbe;
 
+/// The kinds of steps that are taken when unifying two values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepKind {
+    /// Two values are compared structurally.
+    Compare,
+    /// A function or record is eta-expanded to compare it with a neutral value.
+    EtaExpand,
+    /// A metavariable is solved with a value.
+    SolveMeta(meta::Index),
+    /// The values were found to be different.
+    Mismatch,
+}
+
+/// A step taken when unifying two values, with the values read back into the
+/// core syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// The number of comparisons that this step is nested inside.
+    pub depth: usize,
+    /// The size of the environment that the terms are in.
+    pub env_size: var::Size,
+    /// The kind of step that was taken.
+    pub kind: StepKind,
+    pub term1: Rc<syntax::Term>,
+    pub term2: Rc<syntax::Term>,
+}
+
+/// The steps taken when unifying two values, used for explaining why they
+/// failed to unify, and for giving up once too many steps have been taken.
+#[derive(Debug, Clone)]
+pub struct Trace {
+    /// The recorded steps, or `None` if no steps should be recorded.
+    steps: Option<Vec<Step>>,
+    /// The number of comparisons that we are currently nested inside.
+    depth: usize,
+    /// The number of comparisons that can be made before giving up.
+    budget: ConversionBudget,
+}
+
+impl Trace {
+    /// A trace that records nothing, and never gives up.
+    pub fn disabled() -> Trace {
+        Trace::new(ConversionBudget::unlimited(), false)
+    }
+
+    /// Create a trace that gives up once the budget has been spent, and
+    /// records each of the steps taken if `record_steps` is `true`.
+    pub fn new(budget: ConversionBudget, record_steps: bool) -> Trace {
+        Trace {
+            steps: if record_steps { Some(Vec::new()) } else { None },
+            depth: 0,
+            budget,
+        }
+    }
+
+    /// The steps that have been recorded.
+    pub fn steps(&self) -> &[Step] {
+        self.steps.as_ref().map_or(&[], Vec::as_slice)
+    }
+
+    /// Record a step, if the trace is enabled.
+    fn record(
+        &mut self,
+        prims: &prim::Env,
+        metas: &meta::Env,
+        env_size: var::Size,
+        kind: StepKind,
+        value1: &Rc<domain::Value>,
+        value2: &Rc<domain::Value>,
+    ) -> Result<(), Diagnostic<FileSpan>> {
+        if let Some(steps) = &mut self.steps {
+            steps.push(Step {
+                depth: self.depth,
+                env_size,
+                kind,
+                term1: nbe::read_back_value(prims, metas, env_size, None, value1)?,
+                term2: nbe::read_back_value(prims, metas, env_size, None, value2)?,
+            });
+        }
+        Ok(())
+    }
+}
+
 /// Check that all entries in a spine are bound variables.
 fn check_spine(
     prims: &prim::Env,
@@ -90,7 +173,7 @@ fn check_solution(
             Ok(())
         },
 
-        syntax::Term::FunType(_, _, param_ty, body_ty) => {
+        syntax::Term::FunType(_, _, _, param_ty, body_ty) => {
             check_solution(env_size, span, head, bound_levels, param_ty)?;
             check_solution(env_size + 1, span, head, bound_levels, body_ty)?;
             Ok(())
@@ -112,8 +195,8 @@ fn check_solution(
             Ok(())
         },
         syntax::Term::RecordIntro(intro_fields) => {
-            for (_, term) in intro_fields.iter() {
-                check_solution(env_size, span, head, bound_levels, term)?;
+            for (i, (_, term)) in intro_fields.iter().enumerate() {
+                check_solution(env_size + i as u32, span, head, bound_levels, term)?;
             }
             Ok(())
         },
@@ -121,21 +204,40 @@ fn check_solution(
             check_solution(env_size, span, head, bound_levels, record)
         },
 
+        syntax::Term::ArrayType(elem_ty) => {
+            check_solution(env_size, span, head, bound_levels, elem_ty)
+        },
+        syntax::Term::ArrayIntro(elems) => {
+            for elem in elems {
+                check_solution(env_size, span, head, bound_levels, elem)?;
+            }
+            Ok(())
+        },
+
         syntax::Term::Universe(_) => Ok(()),
     }
 }
 
 /// Solve metavariables in the case where a metavariable has been found in a
 /// head position.
+#[allow(clippy::too_many_arguments)]
 fn solve_neutral(
     prims: &prim::Env,
     metas: &mut meta::Env,
     values: &var::Env<Rc<domain::Value>>,
     span: FileSpan,
+    trace: &mut Trace,
     head: meta::Index,
     spine: &domain::Spine,
     rhs: &Rc<domain::Value>,
 ) -> Result<(), Diagnostic<FileSpan>> {
+    let meta_value = Rc::from(domain::Value::Neutral(
+        domain::Head::Meta(head),
+        spine.clone(),
+    ));
+    let kind = StepKind::SolveMeta(head);
+    trace.record(prims, metas, values.size(), kind, &meta_value, rhs)?;
+
     let bound_levels = check_spine(prims, metas, span, spine)?;
     let rhs = nbe::read_back_value(prims, metas, values.size(), None, rhs)?;
 
@@ -145,7 +247,14 @@ fn solve_neutral(
         Rc::from(syntax::Term::FunIntro(AppMode::Explicit, None, acc))
     });
 
-    let rhs_value = nbe::eval_term(prims, metas, &var::Env::new(), None, &rhs)?;
+    let rhs_value = nbe::eval_term(
+        prims,
+        metas,
+        Strategy::CallByValue,
+        &var::Env::new(),
+        None,
+        &rhs,
+    )?;
 
     metas.add_solved(head, rhs_value);
 
@@ -162,6 +271,51 @@ pub fn unify_values(
     span: FileSpan,
     value1: &Rc<domain::Value>,
     value2: &Rc<domain::Value>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    let mut trace = Trace::disabled();
+    unify_values_traced(prims, metas, values, span, &mut trace, value1, value2)
+}
+
+/// Unify two values, recording the steps taken in the trace.
+pub fn unify_values_traced(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    values: &var::Env<Rc<domain::Value>>,
+    span: FileSpan,
+    trace: &mut Trace,
+    value1: &Rc<domain::Value>,
+    value2: &Rc<domain::Value>,
+) -> Result<(), Diagnostic<FileSpan>> {
+    if !trace.budget.spend() {
+        let limit = trace.budget.limit().unwrap_or(u32::MAX);
+        return Err(
+            Diagnostic::new_error("conversion check exceeded budget").with_label(
+                DiagnosticLabel::new_primary(span).with_message(format!(
+                    "gave up after {} steps - the budget can be raised with `--conversion-budget`",
+                    limit,
+                )),
+            ),
+        );
+    }
+
+    let kind = StepKind::Compare;
+    trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
+    trace.depth += 1;
+    let result = unify_step(prims, metas, values, span, trace, value1, value2);
+    trace.depth -= 1;
+    result
+}
+
+/// Unify two values, dispatching on their structure.
+fn unify_step(
+    prims: &prim::Env,
+    metas: &mut meta::Env,
+    values: &var::Env<Rc<domain::Value>>,
+    span: FileSpan,
+    trace: &mut Trace,
+    value1: &Rc<domain::Value>,
+    value2: &Rc<domain::Value>,
 ) -> Result<(), Diagnostic<FileSpan>> {
     log::trace!("unifying values");
 
@@ -175,10 +329,17 @@ pub fn unify_values(
     }
 
     fn unification_error(
+        prims: &prim::Env,
+        metas: &meta::Env,
+        values: &var::Env<Rc<domain::Value>>,
         span: FileSpan,
-        _value1: &Rc<domain::Value>,
-        _value2: &Rc<domain::Value>,
+        trace: &mut Trace,
+        value1: &Rc<domain::Value>,
+        value2: &Rc<domain::Value>,
     ) -> Result<(), Diagnostic<FileSpan>> {
+        let kind = StepKind::Mismatch;
+        trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
         // FIXME: Better error message
         Err(Diagnostic::new_error("can't unify").with_label(DiagnosticLabel::new_primary(span)))
     }
@@ -195,27 +356,82 @@ pub fn unify_values(
                     (domain::Elim::Fun(app_mode1, arg1), domain::Elim::Fun(app_mode2, arg2))
                         if app_mode1 == app_mode2 =>
                     {
-                        unify_values(prims, metas, values, span, arg1, arg2)?;
-                    }
+                        unify_values_traced(prims, metas, values, span, trace, arg1, arg2)?;
+                    },
                     (domain::Elim::Record(l1), domain::Elim::Record(l2)) if l1 == l2 => {},
                     (domain::Elim::Literal(lc1), domain::Elim::Literal(lc2)) => {
-                        // Hum, guessing here??
-                        let (sc, values) = instantiate_value(values);
-                        let val1 = nbe::eval_literal_elim(prims, metas, sc.clone(), lc1.clone())?;
-                        let val2 = nbe::eval_literal_elim(prims, metas, sc.clone(), lc2.clone())?;
-                        unify_values(prims, metas, &values, span, &val1, &val2)?;
+                        // Range and prefix patterns match too many literals to
+                        // compare one at a time, so closures that use them
+                        // must have the same patterns
+                        let is_intro = |(pattern, _): &(LiteralPattern, _)| {
+                            matches!(pattern, LiteralPattern::Intro(_))
+                        };
+                        let is_intro =
+                            lc1.clauses.iter().all(is_intro) && lc2.clauses.iter().all(is_intro);
+                        let is_same_patterns = lc1.clauses.len() == lc2.clauses.len()
+                            && Iterator::zip(lc1.clauses.iter(), lc2.clauses.iter())
+                                .all(|((p1, _), (p2, _))| p1.alpha_eq(p2));
+                        if !is_intro && !is_same_patterns {
+                            unification_error(prims, metas, values, span, trace, value1, value2)?;
+                        }
+
+                        // Compare the clauses for each of the literals that
+                        // either closure matches on, followed by the defaults
+                        let literal_patterns =
+                            Iterator::chain(lc1.clauses.iter(), lc2.clauses.iter());
+                        for (literal_pattern, _) in literal_patterns {
+                            let sc =
+                                Rc::from(domain::Value::literal_intro(literal_pattern.start()));
+                            let val1 =
+                                nbe::eval_literal_elim(prims, metas, sc.clone(), lc1.clone())?;
+                            let val2 = nbe::eval_literal_elim(prims, metas, sc, lc2.clone())?;
+                            unify_values_traced(prims, metas, values, span, trace, &val1, &val2)?;
+                        }
+                        let default1 = nbe::eval_term(
+                            prims,
+                            metas,
+                            lc1.strategy,
+                            &lc1.values,
+                            span,
+                            &lc1.default,
+                        )?;
+                        let default2 = nbe::eval_term(
+                            prims,
+                            metas,
+                            lc2.strategy,
+                            &lc2.values,
+                            span,
+                            &lc2.default,
+                        )?;
+                        unify_values_traced(
+                            prims, metas, values, span, trace, &default1, &default2,
+                        )?;
                     },
-                    (_, _) => unification_error(span, value1, value2)?,
+                    (_, _) => unification_error(prims, metas, values, span, trace, value1, value2)?,
                 }
             }
             Ok(())
-        }
-        (domain::Value::Neutral(domain::Head::Meta(meta_level), spine), _) => {
-            solve_neutral(prims, metas, values, span, *meta_level, spine, value2)
-        },
-        (_, domain::Value::Neutral(domain::Head::Meta(meta_level), spine)) => {
-            solve_neutral(prims, metas, values, span, *meta_level, spine, value1)
         },
+        (domain::Value::Neutral(domain::Head::Meta(meta_level), spine), _) => solve_neutral(
+            prims,
+            metas,
+            values,
+            span,
+            trace,
+            *meta_level,
+            spine,
+            value2,
+        ),
+        (_, domain::Value::Neutral(domain::Head::Meta(meta_level), spine)) => solve_neutral(
+            prims,
+            metas,
+            values,
+            span,
+            trace,
+            *meta_level,
+            spine,
+            value1,
+        ),
 
         (
             domain::Value::LiteralIntro(literal_intro1),
@@ -228,16 +444,16 @@ pub fn unify_values(
         },
 
         (
-            domain::Value::FunType(app_mode1, _, param_ty1, body_ty1),
-            domain::Value::FunType(app_mode2, _, param_ty2, body_ty2),
+            domain::Value::FunType(_, app_mode1, _, param_ty1, body_ty1),
+            domain::Value::FunType(_, app_mode2, _, param_ty2, body_ty2),
         ) if app_mode1 == app_mode2 => {
-            unify_values(prims, metas, values, span, param_ty1, param_ty2)?;
+            unify_values_traced(prims, metas, values, span, trace, param_ty1, param_ty2)?;
 
             let (param, values) = instantiate_value(values);
             let body_ty1 = nbe::app_closure(prims, metas, body_ty1, param.clone())?;
             let body_ty2 = nbe::app_closure(prims, metas, body_ty2, param.clone())?;
 
-            unify_values(prims, metas, &values, span, &body_ty1, &body_ty2)?;
+            unify_values_traced(prims, metas, &values, span, trace, &body_ty1, &body_ty2)?;
 
             Ok(())
         },
@@ -249,7 +465,7 @@ pub fn unify_values(
             let body1 = nbe::app_closure(prims, metas, body1, param.clone())?;
             let body2 = nbe::app_closure(prims, metas, body2, param.clone())?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values_traced(prims, metas, &values, span, trace, &body1, &body2)?;
 
             Ok(())
         },
@@ -265,20 +481,26 @@ pub fn unify_values(
         // - https://ncatlab.org/nlab/show/eta-conversion
         // - https://en.wikipedia.org/wiki/Lambda_calculus#%CE%B7-conversion
         (domain::Value::FunIntro(app_mode1, _, body1), _) => {
+            let kind = StepKind::EtaExpand;
+            trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
             let (param, values) = instantiate_value(values);
             let body1 = nbe::app_closure(prims, metas, body1, param.clone())?;
             let body2 = nbe::eval_fun_elim(prims, metas, value2.clone(), app_mode1, param)?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values_traced(prims, metas, &values, span, trace, &body1, &body2)?;
 
             Ok(())
         },
         (_, domain::Value::FunIntro(app_mode2, _, body2)) => {
+            let kind = StepKind::EtaExpand;
+            trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
             let (param, values) = instantiate_value(values);
             let body2 = nbe::app_closure(prims, metas, body2, param.clone())?;
             let body1 = nbe::eval_fun_elim(prims, metas, value1.clone(), app_mode2, param)?;
 
-            unify_values(prims, metas, &values, span, &body1, &body2)?;
+            unify_values_traced(prims, metas, &values, span, trace, &body1, &body2)?;
 
             Ok(())
         },
@@ -287,13 +509,13 @@ pub fn unify_values(
             domain::Value::RecordTypeExtend(_, label1, _, value_ty1, rest_ty1),
             domain::Value::RecordTypeExtend(_, label2, _, value_ty2, rest_ty2),
         ) if label1 == label2 => {
-            unify_values(prims, metas, values, span, value_ty1, value_ty2)?;
+            unify_values_traced(prims, metas, values, span, trace, value_ty1, value_ty2)?;
 
             let (value, values) = instantiate_value(values);
             let rest_ty1 = nbe::app_closure(prims, metas, rest_ty1, value.clone())?;
             let rest_ty2 = nbe::app_closure(prims, metas, rest_ty2, value.clone())?;
 
-            unify_values(prims, metas, &values, span, &rest_ty1, &rest_ty2)?;
+            unify_values_traced(prims, metas, &values, span, trace, &rest_ty1, &rest_ty2)?;
 
             Ok(())
         },
@@ -306,14 +528,14 @@ pub fn unify_values(
                 Iterator::zip(fields1.iter(), fields2.iter())
             {
                 if label1 == label2 {
-                    unify_values(prims, metas, &values, span, value1, value2)?;
+                    unify_values_traced(prims, metas, &values, span, trace, value1, value2)?;
                     values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
                 } else {
-                    unification_error(span, value1, value2)?;
+                    unification_error(prims, metas, &values, span, trace, value1, value2)?;
                 }
             }
             Ok(())
-        }
+        },
 
         // Eta conversion (η-conversion) for records:
         //
@@ -327,28 +549,46 @@ pub fn unify_values(
         // - https://en.wikipedia.org/wiki/Lambda_calculus#%CE%B7-conversion
         // - https://agda.readthedocs.io/en/latest/language/record-types.html#eta-expansion
         (domain::Value::RecordIntro(fields1), _) => {
+            let kind = StepKind::EtaExpand;
+            trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
             let mut values = values.clone();
             for (label1, value1) in fields1 {
                 let value2 = nbe::eval_record_elim(value2.clone(), label1)?;
-                unify_values(prims, metas, &values, span, value1, &value2)?;
+                unify_values_traced(prims, metas, &values, span, trace, value1, &value2)?;
                 values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
             }
             Ok(())
         },
         (_, domain::Value::RecordIntro(fields2)) => {
+            let kind = StepKind::EtaExpand;
+            trace.record(prims, metas, values.size(), kind, value1, value2)?;
+
             let mut values = values.clone();
             for (label2, value2) in fields2 {
                 let value1 = nbe::eval_record_elim(value1.clone(), label2)?;
-                unify_values(prims, metas, &values, span, &value1, value2)?;
+                unify_values_traced(prims, metas, &values, span, trace, &value1, value2)?;
                 values.add_entry(Rc::from(domain::Value::var(values.size().next_level())));
             }
             Ok(())
         },
 
+        (domain::Value::ArrayType(elem_ty1), domain::Value::ArrayType(elem_ty2)) => {
+            unify_values_traced(prims, metas, values, span, trace, elem_ty1, elem_ty2)
+        },
+        (domain::Value::ArrayIntro(elems1), domain::Value::ArrayIntro(elems2))
+            if elems1.len() == elems2.len() =>
+        {
+            for (elem1, elem2) in Iterator::zip(elems1.iter(), elems2.iter()) {
+                unify_values_traced(prims, metas, values, span, trace, elem1, elem2)?;
+            }
+            Ok(())
+        },
+
         (domain::Value::Universe(level1), domain::Value::Universe(level2)) if level1 <= level2 => {
             Ok(())
         },
 
-        (_, _) => unification_error(span, value1, value2),
+        (_, _) => unification_error(prims, metas, values, span, trace, value1, value2),
     }
 }
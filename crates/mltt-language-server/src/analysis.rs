@@ -0,0 +1,198 @@
+//! Analysis of the documents that are open in the editor.
+
+use language_reporting::{Diagnostic, Label as DiagnosticLabel};
+use mltt_concrete::Item;
+use mltt_core::meta;
+use mltt_core::signature::ModuleSignature;
+use mltt_elaborate::{Context, ElaboratedHole, LanguageOptions, NameIndex, NameKind};
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_span::{FileId, FileSpan, Files};
+
+/// The result of checking a document.
+///
+/// Documents are checked from scratch each time they are analysed, and
+/// elaboration stops at the first error, so names after that error will not
+/// be found in the index.
+pub struct Analysis {
+    /// The context that the items of the document were added to.
+    pub context: Context,
+    /// The signature of the document, or an empty signature if an error was
+    /// found during elaboration.
+    pub signature: ModuleSignature,
+    /// The locations of the items in the document.
+    pub items: Vec<ItemLocation>,
+    /// The metavariables that were created when elaborating the document.
+    pub metas: meta::Env,
+    /// The names that were elaborated, along with their types.
+    pub names: NameIndex,
+    /// The names found during name resolution, keyed by their spans.
+    pub resolved_names: Vec<(FileSpan, NameKind)>,
+    /// The spans of the names that were bound or declared.
+    pub binders: Vec<FileSpan>,
+    /// The spans of the labels of record fields.
+    pub labels: Vec<FileSpan>,
+    /// Errors and warnings that were found.
+    pub diagnostics: Vec<Diagnostic<FileSpan>>,
+}
+
+impl Analysis {
+    /// Check the document in the given file.
+    pub fn new(files: &Files, file_id: FileId) -> Analysis {
+        let mut analysis = Analysis {
+            context: Context::empty(),
+            signature: ModuleSignature::new(),
+            items: Vec::new(),
+            metas: meta::Env::new(),
+            names: NameIndex::new(),
+            resolved_names: Vec::new(),
+            binders: Vec::new(),
+            labels: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        let concrete_module = match parser::parse_module(Lexer::new(&files[file_id])) {
+            Ok(concrete_module) => concrete_module,
+            Err(diagnostic) => {
+                analysis.diagnostics.push(diagnostic);
+                return analysis;
+            },
+        };
+        let defaults = LanguageOptions::default();
+        let language_options = match mltt_elaborate::module_options(defaults, &concrete_module) {
+            Ok(language_options) => language_options,
+            Err(diagnostic) => {
+                analysis.diagnostics.push(diagnostic);
+                return analysis;
+            },
+        };
+
+        analysis.items = item_locations(&concrete_module.items);
+
+        let mut context = Context::new(language_options);
+        context.set_name_index(analysis.names.clone());
+
+        let resolution = mltt_elaborate::resolve_items(&context, &concrete_module.items);
+        analysis.resolved_names = (resolution.names.iter())
+            .map(|resolved| (resolved.name.span(), resolved.kind))
+            .collect();
+        analysis.binders = resolution.binders.iter().map(|name| name.span()).collect();
+        analysis.labels = resolution.labels.iter().map(|label| label.span()).collect();
+        analysis.diagnostics.extend(resolution.warnings);
+
+        let start_meta = analysis.metas.next_index();
+        let result =
+            mltt_elaborate::load_module(&mut context, &mut analysis.metas, &concrete_module.items);
+        let warnings = mltt_elaborate::defaulting_warnings(&analysis.metas, start_meta);
+        analysis.diagnostics.extend(warnings);
+        match result {
+            Ok((_, signature)) => analysis.signature = signature,
+            Err(diagnostic) => analysis.diagnostics.push(diagnostic),
+        }
+        analysis.context = context;
+        for hole in analysis.holes() {
+            let diagnostic = hole_diagnostic(&analysis.metas, &hole);
+            analysis.diagnostics.push(diagnostic);
+        }
+
+        analysis
+    }
+
+    /// The holes that were elaborated, in the order that they appear in the
+    /// document.
+    pub fn holes(&self) -> Vec<ElaboratedHole> {
+        let mut holes = Vec::<ElaboratedHole>::new();
+        // Holes may be elaborated more than once, for example when deciding
+        // which item an overloaded name refers to, so the last one is used
+        for hole in self.names.holes().into_iter().rev() {
+            if holes.iter().all(|other| other.span != hole.span) {
+                holes.push(hole);
+            }
+        }
+        holes.sort_by_key(|hole| hole.span.start());
+        holes
+    }
+
+    /// Lookup the location of the item with the given qualified label.
+    pub fn lookup_item(&self, label: &str) -> Option<&ItemLocation> {
+        self.items.iter().find(|item| item.label == label)
+    }
+
+    /// Lookup the kind of entity that the name at the given span refers to.
+    ///
+    /// Returns `None` if no name was resolved at the span, for example if the
+    /// span is where an item was defined.
+    pub fn lookup_kind(&self, span: FileSpan) -> Option<NameKind> {
+        (self.resolved_names.iter())
+            .find(|(resolved_span, _)| *resolved_span == span)
+            .map(|(_, kind)| *kind)
+    }
+}
+
+/// The location of an item in a document.
+#[derive(Debug, Clone)]
+pub struct ItemLocation {
+    /// The label of the item, qualified with the namespaces it is nested in.
+    pub label: String,
+    /// The span of the label where the item was first introduced.
+    pub label_span: FileSpan,
+    /// The span covering the declaration and the definition of the item.
+    pub span: FileSpan,
+}
+
+/// The locations of the declarations and definitions in a list of items.
+fn item_locations(items: &[Item<'_>]) -> Vec<ItemLocation> {
+    let mut locations = Vec::new();
+    add_item_locations(&mut locations, "", items);
+    locations
+}
+
+fn add_item_locations(locations: &mut Vec<ItemLocation>, prefix: &str, items: &[Item<'_>]) {
+    for item in items {
+        let (name, span) = match item {
+            Item::Declaration(declaration) => (&declaration.label, declaration.full_span()),
+            Item::Definition(definition) => (&definition.label, definition.full_span()),
+            Item::Section(section) => {
+                add_item_locations(locations, prefix, &section.items);
+                continue;
+            },
+            Item::Namespace(namespace) => {
+                let prefix = format!("{}{}.", prefix, namespace.name.slice);
+                add_item_locations(locations, &prefix, &namespace.items);
+                continue;
+            },
+            Item::Open(_) | Item::PatternSynonym(_) => continue,
+        };
+
+        let label = format!("{}{}", prefix, name.slice);
+        match locations
+            .iter_mut()
+            .find(|location| location.label == label)
+        {
+            Some(location) => location.span = FileSpan::merge(location.span, span),
+            None => locations.push(ItemLocation {
+                label,
+                label_span: name.span(),
+                span,
+            }),
+        }
+    }
+}
+
+/// A diagnostic describing the expected type of a hole, and its solution if
+/// one was found.
+fn hole_diagnostic(metas: &meta::Env, hole: &ElaboratedHole) -> Diagnostic<FileSpan> {
+    let context = &hole.context;
+    let ty = context.value_to_doc(metas, &hole.expected_ty);
+    let message = format!("found hole of type `{}`", ty.group().pretty(1_000_000_000));
+    let label = match hole.solution(metas) {
+        Ok(Some(term)) => {
+            let term = context.term_to_doc(&term);
+            let label_message = format!("solved as `{}`", term.group().pretty(1_000_000_000));
+            DiagnosticLabel::new_primary(hole.span).with_message(label_message)
+        },
+        Ok(None) | Err(_) => DiagnosticLabel::new_primary(hole.span),
+    };
+
+    Diagnostic::new_note(message).with_label(label)
+}
@@ -0,0 +1,174 @@
+//! Code actions for filling in holes.
+
+use lsp_types::{CodeAction, CodeActionKind, Range, TextEdit, Url, WorkspaceEdit};
+use mltt_core::domain::Value;
+use mltt_core::literal::LiteralType;
+use mltt_elaborate::ElaboratedHole;
+use mltt_span::{FileId, Files};
+use std::collections::HashMap;
+
+use crate::analysis::Analysis;
+
+/// The code actions for the holes that overlap the given range:
+///
+/// - fill the hole with the solution that was inferred for it, if any
+/// - case split on a boolean variable that is in scope
+/// - annotate the hole with its expected type
+pub fn code_actions(
+    files: &Files,
+    file_id: FileId,
+    uri: &Url,
+    analysis: &Analysis,
+    range: Range,
+) -> Vec<CodeAction> {
+    let span = match files.range_to_span(file_id, range) {
+        Some(span) => span,
+        None => return Vec::new(),
+    };
+    let mut actions = Vec::new();
+
+    for hole in analysis.holes() {
+        if hole.span.source() != file_id
+            || hole.span.end() < span.start()
+            || span.end() < hole.span.start()
+        {
+            continue;
+        }
+        let hole_range = match files.span_to_range(hole.span) {
+            Some(hole_range) => hole_range,
+            None => continue,
+        };
+        let replace_hole = |title: String, new_text: String| {
+            let edit = TextEdit::new(hole_range, new_text);
+            CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..WorkspaceEdit::default()
+                }),
+                ..CodeAction::default()
+            }
+        };
+
+        if let Ok(Some(term)) = hole.solution(&analysis.metas) {
+            let term = render(hole.context.term_to_doc(&term));
+            let title = format!("Fill hole with `{}`", term);
+            actions.push(replace_hole(title, parenthesize(term)));
+        }
+        for name in bool_params(&hole) {
+            let title = format!("Case split on `{}`", name);
+            let new_text = format!("case {} {{ true => ?; false => ?; }}", name);
+            actions.push(replace_hole(title, new_text));
+        }
+
+        let ty = hole
+            .context
+            .value_to_doc(&analysis.metas, &hole.expected_ty);
+        let ty = render(ty);
+        let title = format!("Insert type annotation `{}`", ty);
+        actions.push(replace_hole(title, format!("(? : {})", ty)));
+    }
+
+    actions
+}
+
+/// Render a document on a single line.
+fn render<A>(doc: pretty::Doc<'_, pretty::BoxDoc<'_, A>, A>) -> String {
+    doc.group().pretty(1_000_000_000).to_string()
+}
+
+/// Wrap some source code in parentheses, unless it is a single token.
+fn parenthesize(src: String) -> String {
+    if src.contains(char::is_whitespace) {
+        format!("({})", src)
+    } else {
+        src
+    }
+}
+
+/// The names of the boolean parameters that are in scope at a hole. Names
+/// that are shadowed by later parameters are skipped, because they can't be
+/// referred to.
+fn bool_params(hole: &ElaboratedHole) -> Vec<&str> {
+    let params = hole.context.params().collect::<Vec<_>>();
+    let mut names = Vec::new();
+
+    for (i, (name, ty)) in params.iter().enumerate() {
+        let name = match name {
+            Some(name) if !name.starts_with('_') => *name,
+            Some(_) | None => continue,
+        };
+        let is_shadowed = (params[i + 1..].iter()).any(|(other, _)| *other == Some(name));
+        if !is_shadowed && matches!(ty.as_ref(), Value::LiteralType(LiteralType::Bool)) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    fn action_edits(src: &str, position: Position) -> Vec<(String, String)> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let uri = Url::parse("file:///test.mltt").unwrap();
+        let analysis = Analysis::new(&files, file_id);
+        let range = Range::new(position, position);
+
+        (code_actions(&files, file_id, &uri, &analysis, range).into_iter())
+            .map(|action| {
+                let mut changes = action.edit.unwrap().changes.unwrap();
+                let edits = changes.remove(&uri).unwrap();
+                (action.title, edits[0].new_text.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unsolved_hole() {
+        let src = "not : Bool -> Bool;\nnot b = ?;\n";
+
+        assert_eq!(
+            action_edits(src, Position::new(1, 8)),
+            vec![
+                (
+                    "Case split on `b`".to_owned(),
+                    "case b { true => ?; false => ?; }".to_owned(),
+                ),
+                (
+                    "Insert type annotation `Bool`".to_owned(),
+                    "(? : Bool)".to_owned(),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn solved_hole() {
+        let src = "id : Fun (A : Type) -> A -> A;\nid A a = a;\nx : Bool;\nx = id (?) true;\n";
+
+        assert_eq!(
+            action_edits(src, Position::new(3, 8)),
+            vec![
+                ("Fill hole with `Bool`".to_owned(), "Bool".to_owned()),
+                (
+                    "Insert type annotation `Type`".to_owned(),
+                    "(? : Type)".to_owned(),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn no_hole() {
+        let src = "x : Bool;\nx = true;\n";
+
+        assert_eq!(action_edits(src, Position::new(1, 5)), vec![]);
+    }
+}
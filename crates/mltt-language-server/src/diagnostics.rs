@@ -0,0 +1,33 @@
+//! Conversion of diagnostics to the form used by the LSP.
+
+use language_reporting::{Diagnostic, LabelStyle, Severity};
+use lsp_types::DiagnosticSeverity;
+use mltt_span::{FileSpan, Files};
+
+/// Convert a diagnostic to an LSP diagnostic, located at its primary label.
+///
+/// Returns `None` if the diagnostic has no labels, or if its primary label is
+/// not in the files.
+pub fn to_lsp(files: &Files, diagnostic: &Diagnostic<FileSpan>) -> Option<lsp_types::Diagnostic> {
+    let label = (diagnostic.labels.iter())
+        .find(|label| matches!(label.style, LabelStyle::Primary))
+        .or_else(|| diagnostic.labels.first())?;
+    let severity = match diagnostic.severity {
+        Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Note => DiagnosticSeverity::INFORMATION,
+        Severity::Help => DiagnosticSeverity::HINT,
+    };
+    let message = match &label.message {
+        Some(label_message) => format!("{}\n{}", diagnostic.message, label_message),
+        None => diagnostic.message.clone(),
+    };
+
+    Some(lsp_types::Diagnostic {
+        range: files.span_to_range(label.span)?,
+        severity: Some(severity),
+        source: Some("mltt".to_owned()),
+        message,
+        ..lsp_types::Diagnostic::default()
+    })
+}
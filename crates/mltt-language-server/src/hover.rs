@@ -0,0 +1,95 @@
+//! Information about the name under the cursor.
+
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+use mltt_core::pretty;
+use mltt_elaborate::NameKind;
+use mltt_span::{FileId, Files};
+
+use crate::analysis::Analysis;
+
+/// The width to wrap the pretty printed types and definitions at.
+const WIDTH: usize = 80;
+
+/// Show the elaborated type of the name at the given position.
+///
+/// Names that refer to items are also shown with their normalized
+/// definitions.
+pub fn hover(
+    files: &Files,
+    file_id: FileId,
+    analysis: &Analysis,
+    position: Position,
+) -> Option<Hover> {
+    let byte = files.position_to_byte_index(file_id, position)?;
+    let name = analysis.names.lookup_index(file_id, byte)?;
+    let label = files.source(name.span)?;
+    let context = &name.context;
+    let metas = &analysis.metas;
+
+    let ty = pretty::declaration(label, context.value_to_doc(metas, &name.ty));
+    let mut lines = vec![ty.group().pretty(WIDTH).to_string()];
+
+    match analysis.lookup_kind(name.span) {
+        Some(NameKind::Local(_)) | Some(NameKind::Constructor) => {},
+        // Either a reference to an item, or the item's definition
+        Some(_) | None => {
+            if let Some(value) = name.value() {
+                let term = context.read_back_value(metas, name.span, value).ok()?;
+                let defn = pretty::definition(label, context.term_to_doc(&term));
+                lines.push(defn.group().pretty(WIDTH).to_string());
+            }
+        },
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```mltt\n{}\n```", lines.join("\n")),
+        }),
+        range: files.span_to_range(name.span),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hover_text(src: &str, line: u32, character: u32) -> Option<String> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let analysis = Analysis::new(&files, file_id);
+        let hover = hover(&files, file_id, &analysis, Position::new(line, character))?;
+
+        match hover.contents {
+            HoverContents::Markup(content) => Some(content.value),
+            contents => panic!("unexpected hover contents: {:?}", contents),
+        }
+    }
+
+    #[test]
+    fn hover_local() {
+        let src = "id : Fun (A : Type) -> A -> A;\nid A a = a;\n";
+
+        assert_eq!(
+            hover_text(src, 1, 9).as_deref(),
+            Some("```mltt\na : A;\n```"),
+        );
+    }
+
+    #[test]
+    fn hover_item() {
+        let src = "three : S32;\nthree = 3;\nalso-three = three;\n";
+
+        assert_eq!(
+            hover_text(src, 2, 14).as_deref(),
+            Some("```mltt\nthree : S32;\nthree = 3;\n```"),
+        );
+    }
+
+    #[test]
+    fn hover_nothing() {
+        let src = "three : S32;\nthree = 3;\n";
+
+        assert_eq!(hover_text(src, 0, 7), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Hints showing the information that was inferred during elaboration.
+//!
+//! This includes the implicit arguments that were inserted at call sites, and
+//! the types of definitions that were given without a type annotation.
+
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Range};
+use mltt_elaborate::ElaboratedImplicit;
+use mltt_span::{FileId, FileSpan, Files};
+
+use crate::analysis::Analysis;
+
+/// The inlay hints for the parts of a document that overlap the given range.
+pub fn inlay_hints(
+    files: &Files,
+    file_id: FileId,
+    analysis: &Analysis,
+    range: Range,
+) -> Vec<InlayHint> {
+    let span = match files.range_to_span(file_id, range) {
+        Some(span) => span,
+        None => return Vec::new(),
+    };
+    let in_range = |other: FileSpan| {
+        other.source() == file_id && span.start() <= other.end() && other.start() <= span.end()
+    };
+    let mut hints = Vec::new();
+
+    for name in analysis.names.names() {
+        if !name.is_inferred || !in_range(name.span) {
+            continue;
+        }
+        let ty = name.context.value_to_doc(&analysis.metas, &name.ty);
+        let label = format!(": {}", render(ty));
+        if let Some(hint) = hint(files, name.span, label, InlayHintKind::TYPE) {
+            hints.push(hint);
+        }
+    }
+
+    for implicit in implicits(analysis) {
+        let arg = &implicit.arg;
+        if !in_range(arg.span) {
+            continue;
+        }
+        let value = match arg.solution(&analysis.metas) {
+            Ok(Some(term)) => render(arg.context.term_to_doc(&term)),
+            Ok(None) | Err(_) => "?".to_owned(),
+        };
+        let label = format!("{{{} = {}}}", implicit.label, value);
+        if let Some(hint) = hint(files, arg.span, label, InlayHintKind::PARAMETER) {
+            hints.push(hint);
+        }
+    }
+
+    hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    hints
+}
+
+/// The implicit arguments that were inserted, without duplicates.
+///
+/// Terms may be elaborated more than once, for example when deciding which
+/// item an overloaded name refers to, so the last insertion is used.
+fn implicits(analysis: &Analysis) -> Vec<ElaboratedImplicit> {
+    let mut implicits = Vec::<ElaboratedImplicit>::new();
+    for implicit in analysis.names.implicits().into_iter().rev() {
+        if (implicits.iter())
+            .all(|other| other.arg.span != implicit.arg.span || other.label != implicit.label)
+        {
+            implicits.push(implicit);
+        }
+    }
+    implicits.reverse();
+    implicits
+}
+
+/// A hint that is shown after the given span.
+fn hint(files: &Files, span: FileSpan, label: String, kind: InlayHintKind) -> Option<InlayHint> {
+    Some(InlayHint {
+        position: files.span_to_range(span)?.end,
+        label: InlayHintLabel::String(label),
+        kind: Some(kind),
+        text_edits: None,
+        tooltip: None,
+        padding_left: Some(true),
+        padding_right: None,
+        data: None,
+    })
+}
+
+/// Render a document on a single line.
+fn render<A>(doc: pretty::Doc<'_, pretty::BoxDoc<'_, A>, A>) -> String {
+    doc.group().pretty(1_000_000_000).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    fn hint_labels(src: &str, range: Range) -> Vec<(Position, String)> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let analysis = Analysis::new(&files, file_id);
+
+        (inlay_hints(&files, file_id, &analysis, range).into_iter())
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => (hint.position, label),
+                InlayHintLabel::LabelParts(_) => panic!("unexpected label parts"),
+            })
+            .collect()
+    }
+
+    fn everything(src: &str) -> Range {
+        let end = Position::new(src.lines().count() as u32, 0);
+        Range::new(Position::new(0, 0), end)
+    }
+
+    #[test]
+    fn inferred_implicit() {
+        let src = "id : Fun {A : Type} -> A -> A;\nid a = a;\nx = id true;\n";
+
+        assert_eq!(
+            hint_labels(src, everything(src)),
+            vec![
+                (Position::new(2, 1), ": Bool".to_owned()),
+                (Position::new(2, 6), "{A = Bool}".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn inferred_definition() {
+        let src = "x = true;\ny : Bool;\ny = x;\n";
+
+        assert_eq!(
+            hint_labels(src, everything(src)),
+            vec![(Position::new(0, 1), ": Bool".to_owned())],
+        );
+    }
+
+    #[test]
+    fn outside_range() {
+        let src = "x = true;\ny = false;\n";
+        let range = Range::new(Position::new(1, 0), Position::new(1, 10));
+
+        assert_eq!(
+            hint_labels(src, range),
+            vec![(Position::new(1, 1), ": Bool".to_owned())],
+        );
+    }
+}
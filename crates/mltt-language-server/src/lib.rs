@@ -0,0 +1,416 @@
+//! A language server for the MLTT language.
+//!
+//! This communicates with editors using the [Language Server Protocol], with
+//! JSON-RPC messages sent over standard input and standard output.
+//!
+//! [Language Server Protocol]: https://microsoft.github.io/language-server-protocol/
+
+#![warn(rust_2018_idioms)]
+
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Notification,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, DocumentSymbolRequest, GotoTypeDefinition, HoverRequest, Initialize,
+    InlayHintRequest, Request, SemanticTokensFullRequest, Shutdown,
+};
+use lsp_types::{
+    CodeActionOrCommand, CodeActionProviderCapability, DocumentSymbolResponse,
+    GotoDefinitionResponse, HoverProviderCapability, InitializeResult, OneOf,
+    PublishDiagnosticsParams, SemanticTokensFullOptions, SemanticTokensOptions,
+    SemanticTokensResult, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TypeDefinitionProviderCapability, Url,
+};
+use mltt_span::{FileId, Files};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+mod analysis;
+mod code_actions;
+mod diagnostics;
+mod hover;
+mod inlay_hints;
+mod semantic_tokens;
+mod symbols;
+mod type_definition;
+
+pub use crate::analysis::Analysis;
+
+/// Run the language server, until the editor asks it to exit or closes
+/// standard input.
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut reader = stdin.lock();
+    let mut writer = stdout.lock();
+    let mut server = Server::new();
+
+    while let Some(message) = read_message(&mut reader)? {
+        for reply in server.handle(&message) {
+            write_message(&mut writer, &reply)?;
+        }
+        if server.is_exited() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a message, returning `None` if the end of the input was reached.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing `Content-Length` header",
+        )
+    })?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Write a message, prefixed with its header.
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = serde_json::to_string(message)?;
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+/// The state of the language server.
+pub struct Server {
+    files: Files,
+    /// The files that the open documents are loaded into.
+    documents: HashMap<Url, FileId>,
+    /// Whether a shutdown has been requested.
+    is_shutdown: bool,
+    /// Whether the server should exit.
+    is_exited: bool,
+}
+
+impl Server {
+    /// Create a new language server.
+    pub fn new() -> Server {
+        Server {
+            files: Files::new(),
+            documents: HashMap::new(),
+            is_shutdown: false,
+            is_exited: false,
+        }
+    }
+
+    /// Returns `true` if the editor has asked the server to exit.
+    pub fn is_exited(&self) -> bool {
+        self.is_exited
+    }
+
+    /// Handle a message from the editor, returning the messages to send back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = message["params"].clone();
+
+        match message.get("id") {
+            Some(id) => {
+                let response = match self.handle_request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                };
+                vec![response]
+            },
+            None => self.handle_notification(method, params),
+        }
+    }
+
+    /// Handle a request, returning the result or an error code and message.
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, (i64, String)> {
+        // The error codes defined by JSON-RPC and the LSP specification
+        const INVALID_PARAMS: i64 = -32602;
+        const METHOD_NOT_FOUND: i64 = -32601;
+        const INVALID_REQUEST: i64 = -32600;
+
+        if self.is_shutdown && method != Shutdown::METHOD {
+            return Err((INVALID_REQUEST, "the server has been shut down".to_owned()));
+        }
+
+        let invalid_params = |error: serde_json::Error| (INVALID_PARAMS, error.to_string());
+
+        match method {
+            Initialize::METHOD => Ok(json!(InitializeResult {
+                capabilities: ServerCapabilities {
+                    text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                        TextDocumentSyncKind::FULL,
+                    )),
+                    hover_provider: Some(HoverProviderCapability::Simple(true)),
+                    code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                    inlay_hint_provider: Some(OneOf::Left(true)),
+                    document_symbol_provider: Some(OneOf::Left(true)),
+                    type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true,)),
+                    semantic_tokens_provider: Some(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..SemanticTokensOptions::default()
+                        }
+                        .into(),
+                    ),
+                    ..ServerCapabilities::default()
+                },
+                server_info: Some(ServerInfo {
+                    name: "mltt".to_owned(),
+                    version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+                }),
+            })),
+            Shutdown::METHOD => {
+                self.is_shutdown = true;
+                Ok(Value::Null)
+            },
+            HoverRequest::METHOD => {
+                let params = serde_json::from_value::<<HoverRequest as Request>::Params>(params)
+                    .map_err(invalid_params)?;
+                let document = params.text_document_position_params;
+                let hover = self
+                    .documents
+                    .get(&document.text_document.uri)
+                    .and_then(|file_id| {
+                        let analysis = Analysis::new(&self.files, *file_id);
+                        hover::hover(&self.files, *file_id, &analysis, document.position)
+                    });
+                Ok(json!(hover))
+            },
+            SemanticTokensFullRequest::METHOD => {
+                type Params = <SemanticTokensFullRequest as Request>::Params;
+                let params = serde_json::from_value::<Params>(params).map_err(invalid_params)?;
+                let tokens = self
+                    .documents
+                    .get(&params.text_document.uri)
+                    .map(|file_id| {
+                        let analysis = Analysis::new(&self.files, *file_id);
+                        let tokens =
+                            semantic_tokens::semantic_tokens(&self.files, *file_id, &analysis);
+                        SemanticTokensResult::Tokens(tokens)
+                    });
+                Ok(json!(tokens))
+            },
+            CodeActionRequest::METHOD => {
+                type Params = <CodeActionRequest as Request>::Params;
+                let params = serde_json::from_value::<Params>(params).map_err(invalid_params)?;
+                let uri = &params.text_document.uri;
+                let actions = self.documents.get(uri).map(|file_id| {
+                    let analysis = Analysis::new(&self.files, *file_id);
+                    let actions = code_actions::code_actions(
+                        &self.files,
+                        *file_id,
+                        uri,
+                        &analysis,
+                        params.range,
+                    );
+                    (actions.into_iter())
+                        .map(CodeActionOrCommand::CodeAction)
+                        .collect::<Vec<_>>()
+                });
+                Ok(json!(actions))
+            },
+            InlayHintRequest::METHOD => {
+                type Params = <InlayHintRequest as Request>::Params;
+                let params = serde_json::from_value::<Params>(params).map_err(invalid_params)?;
+                let hints = self
+                    .documents
+                    .get(&params.text_document.uri)
+                    .map(|file_id| {
+                        let analysis = Analysis::new(&self.files, *file_id);
+                        inlay_hints::inlay_hints(&self.files, *file_id, &analysis, params.range)
+                    });
+                Ok(json!(hints))
+            },
+            DocumentSymbolRequest::METHOD => {
+                type Params = <DocumentSymbolRequest as Request>::Params;
+                let params = serde_json::from_value::<Params>(params).map_err(invalid_params)?;
+                let symbols = self
+                    .documents
+                    .get(&params.text_document.uri)
+                    .map(|file_id| {
+                        let analysis = Analysis::new(&self.files, *file_id);
+                        let symbols = symbols::document_symbols(&self.files, &analysis);
+                        DocumentSymbolResponse::Nested(symbols)
+                    });
+                Ok(json!(symbols))
+            },
+            GotoTypeDefinition::METHOD => {
+                type Params = <GotoTypeDefinition as Request>::Params;
+                let params = serde_json::from_value::<Params>(params).map_err(invalid_params)?;
+                let document = params.text_document_position_params;
+                let uri = &document.text_document.uri;
+                let location = self.documents.get(uri).and_then(|file_id| {
+                    let analysis = Analysis::new(&self.files, *file_id);
+                    type_definition::type_definition(
+                        &self.files,
+                        *file_id,
+                        uri,
+                        &analysis,
+                        document.position,
+                    )
+                });
+                Ok(json!(location.map(GotoDefinitionResponse::Scalar)))
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method `{}`", method))),
+        }
+    }
+
+    /// Handle a notification, returning the notifications to send back.
+    /// Malformed notifications are ignored, because there is no way to report
+    /// errors for them.
+    fn handle_notification(&mut self, method: &str, params: Value) -> Vec<Value> {
+        match method {
+            DidOpenTextDocument::METHOD => {
+                type Params = <DidOpenTextDocument as Notification>::Params;
+                if let Ok(params) = serde_json::from_value::<Params>(params) {
+                    let document = params.text_document;
+                    let file_id = self.files.add(document.uri.to_string(), document.text);
+                    self.documents.insert(document.uri.clone(), file_id);
+                    return vec![self.publish_diagnostics(document.uri, document.version)];
+                }
+            },
+            DidChangeTextDocument::METHOD => {
+                type Params = <DidChangeTextDocument as Notification>::Params;
+                if let Ok(params) = serde_json::from_value::<Params>(params) {
+                    let document = params.text_document;
+                    // We only support syncing the full document
+                    let change = params.content_changes.into_iter().last();
+                    if let (Some(file_id), Some(change)) =
+                        (self.documents.get(&document.uri), change)
+                    {
+                        self.files.update(*file_id, change.text);
+                        return vec![self.publish_diagnostics(document.uri, document.version)];
+                    }
+                }
+            },
+            DidCloseTextDocument::METHOD => {
+                type Params = <DidCloseTextDocument as Notification>::Params;
+                if let Ok(params) = serde_json::from_value::<Params>(params) {
+                    self.documents.remove(&params.text_document.uri);
+                }
+            },
+            Exit::METHOD => self.is_exited = true,
+            _ => {},
+        }
+
+        Vec::new()
+    }
+
+    /// Check a document, returning a notification that reports the problems
+    /// that were found.
+    fn publish_diagnostics(&self, uri: Url, version: i32) -> Value {
+        let diagnostics = match self.documents.get(&uri) {
+            Some(file_id) => {
+                let analysis = Analysis::new(&self.files, *file_id);
+                (analysis.diagnostics.iter())
+                    .filter_map(|diagnostic| diagnostics::to_lsp(&self.files, diagnostic))
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, Some(version));
+
+        json!({
+            "jsonrpc": "2.0",
+            "method": PublishDiagnostics::METHOD,
+            "params": params,
+        })
+    }
+}
+
+impl Default for Server {
+    fn default() -> Server {
+        Server::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hover_request() {
+        let mut server = Server::new();
+        let uri = "file:///test.mltt";
+
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "capabilities": {} },
+        }));
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "mltt",
+                    "version": 1,
+                    "text": "unit : Bool;\nunit = true;\n",
+                },
+            },
+        }));
+        assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+        assert_eq!(replies[0]["params"]["diagnostics"], json!([]));
+
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "textDocument/hover",
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": 1, "character": 1 },
+            },
+        }));
+        assert_eq!(
+            replies[0]["result"]["contents"]["value"],
+            "```mltt\nunit : Bool;\nunit = true;\n```",
+        );
+    }
+
+    #[test]
+    fn read_and_write_messages() {
+        let message = json!({ "jsonrpc": "2.0", "method": "exit" });
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+
+        let mut reader = io::Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Classification of the tokens in a document, for syntax highlighting.
+//!
+//! Unlike the regular expressions used by editor grammars, this can tell the
+//! difference between binders, the variables that refer to them, and the
+//! labels of record fields, because it is driven by name resolution.
+
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensLegend,
+};
+use mltt_elaborate::NameKind;
+use mltt_parse::lexer::Lexer;
+use mltt_parse::token::{Keyword, Token, TokenKind};
+use mltt_span::{FileId, FileSpan, Files};
+
+use crate::analysis::Analysis;
+
+/// The classification of a token.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TokenClass {
+    /// A name that is introduced by a parameter, a pattern, or an item.
+    Binder,
+    /// A name that refers to a binder.
+    Variable,
+    /// The name of a primitive, for example `"string-eq"`.
+    Primitive,
+    /// A universe, for example `Type` or `Type^1`.
+    Universe,
+    /// A string or character literal.
+    TextLiteral,
+    /// An integer or floating point literal.
+    NumericLiteral,
+    /// The label of a record field.
+    Label,
+}
+
+impl TokenClass {
+    /// The token classes, in the order that they appear in the legend.
+    const ALL: [TokenClass; 7] = [
+        TokenClass::Binder,
+        TokenClass::Variable,
+        TokenClass::Primitive,
+        TokenClass::Universe,
+        TokenClass::TextLiteral,
+        TokenClass::NumericLiteral,
+        TokenClass::Label,
+    ];
+
+    /// The standard token type that editors will use to highlight the class.
+    fn token_type(self) -> SemanticTokenType {
+        match self {
+            TokenClass::Binder => SemanticTokenType::PARAMETER,
+            TokenClass::Variable => SemanticTokenType::VARIABLE,
+            TokenClass::Primitive => SemanticTokenType::FUNCTION,
+            TokenClass::Universe => SemanticTokenType::TYPE,
+            TokenClass::TextLiteral => SemanticTokenType::STRING,
+            TokenClass::NumericLiteral => SemanticTokenType::NUMBER,
+            TokenClass::Label => SemanticTokenType::PROPERTY,
+        }
+    }
+}
+
+/// The modifier that is applied to binders.
+const DECLARATION_MODIFIER: u32 = 1 << 0;
+/// The modifier that is applied to primitives.
+const DEFAULT_LIBRARY_MODIFIER: u32 = 1 << 1;
+
+/// The legend describing the token types and modifiers that are used.
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TokenClass::ALL
+            .iter()
+            .map(|class| class.token_type())
+            .collect(),
+        token_modifiers: vec![
+            SemanticTokenModifier::DECLARATION,
+            SemanticTokenModifier::DEFAULT_LIBRARY,
+        ],
+    }
+}
+
+/// Classify the tokens in a document.
+pub fn classify(
+    files: &Files,
+    file_id: FileId,
+    analysis: &Analysis,
+) -> Vec<(FileSpan, TokenClass)> {
+    let tokens = Lexer::new(&files[file_id])
+        .filter(|token| !token.is_whitespace() && token.kind != TokenKind::LineDoc)
+        .collect::<Vec<_>>();
+    let mut classes = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let class = match token.kind {
+            TokenKind::Keyword(Keyword::Universe) => Some(TokenClass::Universe),
+            // The level of a universe, for example the `^1` in `Type^1`
+            TokenKind::Caret | TokenKind::IntLiteral if follows_universe(&tokens[..i]) => {
+                Some(TokenClass::Universe)
+            },
+            TokenKind::StringLiteral => match analysis.lookup_kind(token.span()) {
+                Some(NameKind::Prim) => Some(TokenClass::Primitive),
+                Some(_) | None => Some(TokenClass::TextLiteral),
+            },
+            TokenKind::CharLiteral => Some(TokenClass::TextLiteral),
+            TokenKind::IntLiteral | TokenKind::FloatLiteral => Some(TokenClass::NumericLiteral),
+            TokenKind::Identifier => classify_name(analysis, token.identifier_name().span()),
+            _ => None,
+        };
+
+        if let Some(class) = class {
+            classes.push((token.span(), class));
+        }
+    }
+
+    classes
+}
+
+/// Returns `true` if the tokens end with a universe, or with a universe
+/// followed by a caret.
+fn follows_universe(tokens: &[Token<'_>]) -> bool {
+    match tokens {
+        [.., last] if last.is_keyword(Keyword::Universe) => true,
+        [.., second_last, last] => {
+            second_last.is_keyword(Keyword::Universe) && last.kind == TokenKind::Caret
+        },
+        _ => false,
+    }
+}
+
+/// Classify a name using the results of name resolution.
+fn classify_name(analysis: &Analysis, span: FileSpan) -> Option<TokenClass> {
+    if analysis.lookup_kind(span).is_some() {
+        Some(TokenClass::Variable)
+    } else if analysis.labels.contains(&span) {
+        Some(TokenClass::Label)
+    } else if analysis.binders.contains(&span) {
+        Some(TokenClass::Binder)
+    } else {
+        None
+    }
+}
+
+/// Encode the classified tokens of a document, using positions that are
+/// relative to the previous token, as required by the LSP specification.
+///
+/// Tokens that span multiple lines are skipped, because not every editor
+/// supports them.
+pub fn semantic_tokens(files: &Files, file_id: FileId, analysis: &Analysis) -> SemanticTokens {
+    let mut data = Vec::new();
+    let (mut prev_line, mut prev_start) = (0, 0);
+
+    for (span, class) in classify(files, file_id, analysis) {
+        let range = match files.span_to_range(span) {
+            Some(range) if range.start.line == range.end.line => range,
+            Some(_) | None => continue,
+        };
+        let line = range.start.line;
+        let start = range.start.character;
+        let token_type = TokenClass::ALL
+            .iter()
+            .position(|c| *c == class)
+            .unwrap_or(0);
+        let token_modifiers_bitset = match class {
+            TokenClass::Binder => DECLARATION_MODIFIER,
+            TokenClass::Primitive => DEFAULT_LIBRARY_MODIFIER,
+            _ => 0,
+        };
+
+        data.push(SemanticToken {
+            delta_line: line - prev_line,
+            delta_start: if line == prev_line {
+                start - prev_start
+            } else {
+                start
+            },
+            length: range.end.character - start,
+            token_type: token_type as u32,
+            token_modifiers_bitset,
+        });
+        prev_line = line;
+        prev_start = start;
+    }
+
+    SemanticTokens {
+        result_id: None,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(src: &str) -> Vec<(&str, TokenClass)> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let analysis = Analysis::new(&files, file_id);
+
+        (classify(&files, file_id, &analysis).into_iter())
+            .map(|(span, class)| (&src[span.start().to_usize()..span.end().to_usize()], class))
+            .collect()
+    }
+
+    #[test]
+    fn classify_tokens() {
+        let src = r#"
+            Point : Type^1;
+            Point = Record { x : S32 };
+
+            origin : Point;
+            origin = record { x = 0 };
+
+            eq : String -> String -> Bool;
+            eq = primitive "string-eq";
+
+            origin-x = origin.x;
+        "#;
+
+        use TokenClass::*;
+
+        assert_eq!(
+            classes(src),
+            vec![
+                ("Point", Binder),
+                ("Type", Universe),
+                ("^", Universe),
+                ("1", Universe),
+                ("Point", Binder),
+                ("x", Label),
+                ("S32", Variable),
+                ("origin", Binder),
+                ("Point", Variable),
+                ("origin", Binder),
+                ("x", Label),
+                ("0", NumericLiteral),
+                ("eq", Binder),
+                ("String", Variable),
+                ("String", Variable),
+                ("Bool", Variable),
+                ("eq", Binder),
+                ("\"string-eq\"", Primitive),
+                ("origin-x", Binder),
+                ("origin", Variable),
+                ("x", Label),
+            ],
+        );
+    }
+
+    #[test]
+    fn encode_relative_positions() {
+        let src = "id : Fun (A : Type) -> A -> A;\nid A a = a;\n";
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let analysis = Analysis::new(&files, file_id);
+        let tokens = semantic_tokens(&files, file_id, &analysis);
+
+        let positions = (tokens.data.iter())
+            .map(|token| (token.delta_line, token.delta_start, token.length))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            positions,
+            vec![
+                (0, 0, 2),  // id
+                (0, 10, 1), // A
+                (0, 4, 4),  // Type
+                (0, 9, 1),  // A
+                (0, 5, 1),  // A
+                (1, 0, 2),  // id
+                (0, 3, 1),  // A
+                (0, 2, 1),  // a
+                (0, 4, 1),  // a
+            ],
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! An outline of the items in a document.
+
+use lsp_types::{DocumentSymbol, SymbolKind};
+use mltt_core::domain::Value;
+use mltt_core::signature::ItemSignature;
+use mltt_span::Files;
+
+use crate::analysis::Analysis;
+
+/// The symbols for the items in the signature of a document, along with their
+/// types.
+///
+/// Items that have no location in the document, for example the helpers that
+/// are derived for record types, are skipped.
+pub fn document_symbols(files: &Files, analysis: &Analysis) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+
+    for item in analysis.signature.items() {
+        let location = match analysis.lookup_item(&item.label.0) {
+            Some(location) => location,
+            None => continue,
+        };
+        let (range, selection_range) = match (
+            files.span_to_range(location.span),
+            files.span_to_range(location.label_span),
+        ) {
+            (Some(range), Some(selection_range)) => (range, selection_range),
+            (_, _) => continue,
+        };
+        let ty = analysis.context.value_to_doc(&analysis.metas, &item.ty);
+
+        #[allow(deprecated)]
+        symbols.push(DocumentSymbol {
+            name: item.label.to_string(),
+            detail: Some(ty.group().pretty(1_000_000_000).to_string()),
+            kind: symbol_kind(item),
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range,
+            children: None,
+        });
+    }
+
+    symbols
+}
+
+/// The kind of symbol to show for an item.
+fn symbol_kind(item: &ItemSignature) -> SymbolKind {
+    match (
+        item.ty.as_ref(),
+        item.value.as_ref().map(|value| value.as_ref()),
+    ) {
+        (Value::Universe(_), Some(Value::RecordTypeExtend(..)))
+        | (Value::Universe(_), Some(Value::RecordTypeEmpty)) => SymbolKind::STRUCT,
+        (Value::Universe(_), _) => SymbolKind::TYPE_PARAMETER,
+        (Value::FunType(..), _) => SymbolKind::FUNCTION,
+        (_, _) => SymbolKind::CONSTANT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(src: &str) -> Vec<(String, String, SymbolKind, u32)> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let analysis = Analysis::new(&files, file_id);
+
+        (document_symbols(&files, &analysis).into_iter())
+            .map(|symbol| {
+                let detail = symbol.detail.unwrap();
+                (symbol.name, detail, symbol.kind, symbol.range.end.line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outline() {
+        let src = r#"Point : Type;
+Point = Record { x : S32 };
+
+origin : Point;
+origin = record { x = 0 };
+
+Name = String;
+
+namespace ids
+    id : Bool -> Bool;
+    id b = b;
+end
+"#;
+
+        assert_eq!(
+            symbols(src),
+            vec![
+                ("Point".to_owned(), "Type".to_owned(), SymbolKind::STRUCT, 1),
+                (
+                    "origin".to_owned(),
+                    "Record { x : S32; }".to_owned(),
+                    SymbolKind::CONSTANT,
+                    4,
+                ),
+                (
+                    "Name".to_owned(),
+                    "Type".to_owned(),
+                    SymbolKind::TYPE_PARAMETER,
+                    6
+                ),
+                (
+                    "ids.id".to_owned(),
+                    "Bool -> Bool".to_owned(),
+                    SymbolKind::FUNCTION,
+                    10,
+                ),
+            ],
+        );
+    }
+}
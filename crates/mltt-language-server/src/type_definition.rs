@@ -0,0 +1,72 @@
+//! Navigation from a name to the definition of its type.
+
+use lsp_types::{Location, Position, Url};
+use mltt_core::domain::Value;
+use mltt_span::{FileId, Files};
+
+use crate::analysis::Analysis;
+
+/// The location of the record type definition of the type of the name at the
+/// given position.
+///
+/// Definitions are unfolded during elaboration, so the type is compared with
+/// the values of the record types in the signature of the document.
+pub fn type_definition(
+    files: &Files,
+    file_id: FileId,
+    uri: &Url,
+    analysis: &Analysis,
+    position: Position,
+) -> Option<Location> {
+    let byte = files.position_to_byte_index(file_id, position)?;
+    let name = analysis.names.lookup_index(file_id, byte)?;
+
+    let item = analysis.signature.items().iter().find(|item| {
+        (item.value.as_ref())
+            .is_some_and(|value| is_record_type(value) && value.shared_eq(&name.ty))
+    })?;
+    let location = analysis.lookup_item(&item.label.0)?;
+
+    Some(Location::new(
+        uri.clone(),
+        files.span_to_range(location.label_span)?,
+    ))
+}
+
+/// Returns `true` if the value is a record type.
+fn is_record_type(value: &Value) -> bool {
+    matches!(value, Value::RecordTypeExtend(..) | Value::RecordTypeEmpty)
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn type_definition_range(src: &str, position: Position) -> Option<Range> {
+        let mut files = Files::new();
+        let file_id = files.add("test.mltt", src);
+        let uri = Url::parse("file:///test.mltt").unwrap();
+        let analysis = Analysis::new(&files, file_id);
+
+        type_definition(&files, file_id, &uri, &analysis, position).map(|location| location.range)
+    }
+
+    #[test]
+    fn record_type() {
+        let src = "Point : Type;\nPoint = Record { x : S32 };\n\norigin : Point;\norigin = record { x = 0 };\n\nx = origin.x;\n";
+
+        assert_eq!(
+            type_definition_range(src, Position::new(6, 5)),
+            Some(Range::new(Position::new(0, 0), Position::new(0, 5))),
+        );
+    }
+
+    #[test]
+    fn not_a_record_type() {
+        let src = "x : Bool;\nx = true;\n\ny = x;\n";
+
+        assert_eq!(type_definition_range(src, Position::new(3, 4)), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! A buffered cursor over a stream of tokens.
+
+use mltt_span::FileSpan;
+
+use crate::token::{Token, TokenStream};
+
+/// A cursor over a stream of tokens, providing two tokens of lookahead.
+///
+/// Whitespace and line comments are skipped by the cursor, so the parser only
+/// ever sees significant tokens.
+pub struct TokenCursor<'file, Tokens> {
+    /// The underlying stream of tokens.
+    tokens: Tokens,
+    /// The current lookahead token.
+    peeked: Option<Token<'file>>,
+    /// The token after the current lookahead token.
+    peeked2: Option<Token<'file>>,
+    /// The span of the most recently consumed token.
+    prev_span: Option<FileSpan>,
+}
+
+impl<'file, Tokens> TokenCursor<'file, Tokens>
+where
+    Tokens: Iterator<Item = Token<'file>>,
+{
+    /// Create a new cursor from a stream of tokens.
+    pub fn new(mut tokens: Tokens) -> TokenCursor<'file, Tokens> {
+        let peeked = next_non_whitespace(&mut tokens);
+        let peeked2 = next_non_whitespace(&mut tokens);
+
+        TokenCursor {
+            tokens,
+            peeked,
+            peeked2,
+            prev_span: None,
+        }
+    }
+
+    /// Peek at the current lookahead token.
+    pub fn peek(&self) -> Option<&Token<'file>> {
+        self.peeked.as_ref()
+    }
+
+    /// Peek at the token after the current lookahead token.
+    pub fn peek2(&self) -> Option<&Token<'file>> {
+        self.peeked2.as_ref()
+    }
+
+    /// The span of the current lookahead token.
+    pub fn peek_span(&self) -> Option<FileSpan> {
+        self.peek().map(Token::span)
+    }
+
+    /// The span of the most recently consumed token.
+    pub fn prev_span(&self) -> Option<FileSpan> {
+        self.prev_span
+    }
+
+    /// An empty span pointing to the end of the most recently consumed token,
+    /// which is useful for reporting unexpected ends of files.
+    pub fn prev_end_span(&self) -> Option<FileSpan> {
+        let span = self.prev_span?;
+        Some(FileSpan::new(span.source(), span.end(), span.end()))
+    }
+
+    /// Consume the current token and load the next one. Return the old token.
+    pub fn advance(&mut self) -> Option<Token<'file>> {
+        let next_token2 = next_non_whitespace(&mut self.tokens);
+        let next_token1 = std::mem::replace(&mut self.peeked2, next_token2);
+        let token = std::mem::replace(&mut self.peeked, next_token1)?;
+        self.prev_span = Some(token.span());
+
+        Some(token)
+    }
+
+    /// Take back the underlying stream of tokens, discarding any lookahead.
+    pub fn into_inner(self) -> Tokens {
+        self.tokens
+    }
+}
+
+impl<'file, Tokens> Iterator for TokenCursor<'file, Tokens>
+where
+    Tokens: Iterator<Item = Token<'file>>,
+{
+    type Item = Token<'file>;
+
+    fn next(&mut self) -> Option<Token<'file>> {
+        self.advance()
+    }
+}
+
+/// A saved position in a token cursor.
+pub struct Checkpoint<'file, Tokens: TokenStream<'file>> {
+    tokens: Tokens::Checkpoint,
+    peeked: Option<Token<'file>>,
+    peeked2: Option<Token<'file>>,
+    prev_span: Option<FileSpan>,
+}
+
+// Derived `Clone` would require `Tokens: Clone`, which is not needed
+impl<'file, Tokens: TokenStream<'file>> Clone for Checkpoint<'file, Tokens> {
+    fn clone(&self) -> Checkpoint<'file, Tokens> {
+        Checkpoint {
+            tokens: self.tokens.clone(),
+            peeked: self.peeked.clone(),
+            peeked2: self.peeked2.clone(),
+            prev_span: self.prev_span,
+        }
+    }
+}
+
+impl<'file, Tokens> TokenStream<'file> for TokenCursor<'file, Tokens>
+where
+    Tokens: TokenStream<'file>,
+{
+    type Checkpoint = Checkpoint<'file, Tokens>;
+
+    fn checkpoint(&self) -> Checkpoint<'file, Tokens> {
+        Checkpoint {
+            tokens: self.tokens.checkpoint(),
+            peeked: self.peeked.clone(),
+            peeked2: self.peeked2.clone(),
+            prev_span: self.prev_span,
+        }
+    }
+
+    fn rewind(&mut self, checkpoint: Checkpoint<'file, Tokens>) {
+        self.tokens.rewind(checkpoint.tokens);
+        self.peeked = checkpoint.peeked;
+        self.peeked2 = checkpoint.peeked2;
+        self.prev_span = checkpoint.prev_span;
+    }
+}
+
+/// Skip whitespace or line comment tokens.
+fn next_non_whitespace<'file>(
+    tokens: &mut impl Iterator<Item = Token<'file>>,
+) -> Option<Token<'file>> {
+    tokens.find(|token| !token.is_whitespace())
+}
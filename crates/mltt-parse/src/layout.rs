@@ -0,0 +1,318 @@
+//! An optional layout pass, allowing blocks to be delimited by indentation.
+//!
+//! Much like [Haskell's layout rule], this inserts virtual semicolons and
+//! braces into the token stream based on the indentation of the source code,
+//! so that the following:
+//!
+//! ```text
+//! let
+//!     id : Fun (A : Type) -> A -> A
+//!     id A a = a
+//! in
+//!     case (id String "hi")
+//!         "hi" => "hello"
+//!         _ => "goodbye"
+//! ```
+//!
+//! is parsed as if it were written as:
+//!
+//! ```text
+//! let
+//!     id : Fun (A : Type) -> A -> A;
+//!     id A a = a;
+//! in
+//!     case (id String "hi") {
+//!         "hi" => "hello";
+//!         _ => "goodbye"
+//!     }
+//! ```
+//!
+//! The rules are as follows:
+//!
+//! - The first token after a `let` keyword opens a layout block for the items
+//!   of the `let`, at the column of that token.
+//! - If the scrutinee of a `case` or `with` expression is not followed by an
+//!   explicit `{`, the first token on a new line opens a layout block for the
+//!   clauses, at the column of that token, inserting a virtual `{`.
+//! - A token that starts a line at the same column as the innermost layout
+//!   block begins a new item or clause, inserting a virtual `;`.
+//! - A token that starts a line at a column less than that of the innermost
+//!   layout block closes the block, inserting a virtual `;` for `let` blocks,
+//!   or a virtual `}` for `case` blocks.
+//! - The `in` keyword closes the innermost `let` block, and any `case` blocks
+//!   nested inside it. Closing delimiters close any layout blocks that were
+//!   opened after the corresponding opening delimiter.
+//! - The end of the file closes all remaining layout blocks.
+//!
+//! Virtual semicolons are not inserted directly after explicit ones, so items
+//! and clauses may still be terminated explicitly.
+//!
+//! [Haskell's layout rule]: https://www.haskell.org/onlinereport/haskell2010/haskellch10.html#x17-17800010.3
+
+use mltt_concrete::SpannedString;
+use mltt_span::{ByteIndex, FileId};
+use std::collections::VecDeque;
+
+use crate::token::{DelimKind, Keyword, Token, TokenKind, TokenStream};
+
+/// A context that the layout pass is currently in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Context {
+    /// An explicit delimiter, written in the source code.
+    Delimiter,
+    /// A layout block for the items of a `let` expression, at the given column.
+    Let(usize),
+    /// A layout block for the clauses of a `case` expression, at the given
+    /// column.
+    Case(usize),
+}
+
+/// A layout block that is waiting to be opened.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Pending {
+    /// A `let` block, to be opened at the next token.
+    Let,
+    /// A `case` block, to be opened at the next token on a new line, unless an
+    /// explicit brace is found at the given context depth.
+    Case(usize),
+}
+
+/// The state of the layout pass.
+#[derive(Debug, Clone)]
+struct State<'file> {
+    /// The stack of contexts that we are in.
+    contexts: Vec<Context>,
+    /// A layout block that is waiting to be opened.
+    pending: Option<Pending>,
+    /// Tokens that are ready to be emitted.
+    queue: VecDeque<Token<'file>>,
+    /// The column of the next token.
+    column: usize,
+    /// Whether the next significant token is the first on its line.
+    is_line_start: bool,
+    /// The kind of the last significant token that was emitted.
+    last_kind: Option<TokenKind>,
+    /// The file and position of the end of the last token, where virtual
+    /// tokens are inserted.
+    last_end: Option<(FileId, ByteIndex)>,
+    /// Whether we have reached the end of the token stream.
+    is_eof: bool,
+}
+
+/// A token stream adapter that inserts virtual tokens based on indentation.
+///
+/// This expects a token stream that includes whitespace, like the one produced
+/// by the `Lexer`.
+pub struct Layout<'file, Tokens> {
+    tokens: Tokens,
+    state: State<'file>,
+}
+
+impl<'file, Tokens> Layout<'file, Tokens>
+where
+    Tokens: Iterator<Item = Token<'file>>,
+{
+    /// Create a new layout pass over a stream of tokens.
+    pub fn new(tokens: Tokens) -> Layout<'file, Tokens> {
+        Layout {
+            tokens,
+            state: State {
+                contexts: Vec::new(),
+                pending: None,
+                queue: VecDeque::new(),
+                column: 0,
+                is_line_start: true,
+                last_kind: None,
+                last_end: None,
+                is_eof: false,
+            },
+        }
+    }
+}
+
+impl<'file> State<'file> {
+    /// Queue a virtual token at the end of the last token.
+    fn push_virtual(&mut self, kind: TokenKind) {
+        if let Some((source, end)) = self.last_end {
+            self.queue.push_back(Token {
+                kind,
+                src: SpannedString::new(source, end, ""),
+            });
+            self.last_kind = Some(kind);
+        }
+    }
+
+    /// Queue a virtual semicolon, unless one was just emitted.
+    fn push_separator(&mut self) {
+        match self.last_kind {
+            Some(TokenKind::Semicolon) | Some(TokenKind::Open(DelimKind::Brace)) => {},
+            _ => self.push_virtual(TokenKind::Semicolon),
+        }
+    }
+
+    /// Close the innermost context, returning it.
+    fn close_block(&mut self) -> Option<Context> {
+        let context = self.contexts.pop();
+        match context {
+            Some(Context::Let(_)) => self.push_separator(),
+            Some(Context::Case(_)) => self.push_virtual(TokenKind::Close(DelimKind::Brace)),
+            Some(Context::Delimiter) | None => {},
+        }
+        context
+    }
+
+    /// The column of the innermost layout block, if we are directly inside
+    /// one.
+    fn block_column(&self) -> Option<usize> {
+        match self.contexts.last() {
+            Some(Context::Let(column)) | Some(Context::Case(column)) => Some(*column),
+            Some(Context::Delimiter) | None => None,
+        }
+    }
+
+    /// Update the current column, based on the source of a token.
+    fn advance_column(&mut self, slice: &str) {
+        match slice.rfind('\n') {
+            Some(index) => {
+                self.column = slice[index + 1..].chars().count();
+                self.is_line_start = true;
+            },
+            None => self.column += slice.chars().count(),
+        }
+    }
+
+    /// Process a significant token, queuing it along with any virtual tokens
+    /// that should come before it.
+    fn push_token(&mut self, token: Token<'file>) {
+        let column = self.column;
+        let is_line_start = std::mem::replace(&mut self.is_line_start, false)
+            // Items may be preceded by doc comments and attributes
+            && !matches!(
+                self.last_kind,
+                Some(TokenKind::LineDoc) | Some(TokenKind::Close(DelimKind::Bracket))
+            );
+
+        let is_in = token.kind == TokenKind::Keyword(Keyword::In);
+        // Whether the `let` block that an `in` keyword belongs to has already
+        // been closed by a decrease in indentation.
+        let mut is_let_closed = false;
+
+        match self.pending {
+            Some(Pending::Let) => {
+                self.pending = None;
+                self.contexts.push(Context::Let(column));
+            },
+            Some(Pending::Case(depth))
+                if token.kind == TokenKind::Open(DelimKind::Brace)
+                    && self.contexts.len() == depth =>
+            {
+                self.pending = None;
+            },
+            Some(Pending::Case(_)) if is_line_start => {
+                self.pending = None;
+                self.contexts.push(Context::Case(column));
+                self.last_end = Some((token.src.source, token.src.start));
+                self.push_virtual(TokenKind::Open(DelimKind::Brace));
+            },
+            None if is_line_start => {
+                while self.block_column().is_some_and(|block| column < block) {
+                    if matches!(self.close_block(), Some(Context::Let(_))) && is_in {
+                        is_let_closed = true;
+                        break;
+                    }
+                }
+                if self.block_column() == Some(column) {
+                    self.push_separator();
+                }
+            },
+            Some(Pending::Case(_)) | None => {},
+        }
+
+        match token.kind {
+            TokenKind::Keyword(Keyword::Let) => self.pending = Some(Pending::Let),
+            TokenKind::Keyword(Keyword::Case) | TokenKind::Keyword(Keyword::With) => {
+                self.pending = Some(Pending::Case(self.contexts.len()));
+            },
+            TokenKind::Keyword(Keyword::In) if !is_let_closed => {
+                while let Some(Context::Case(_)) = self.contexts.last() {
+                    self.close_block();
+                }
+                if let Some(Context::Let(_)) = self.contexts.last() {
+                    self.close_block();
+                }
+            },
+            TokenKind::Open(_) => self.contexts.push(Context::Delimiter),
+            TokenKind::Close(_) => {
+                while self.block_column().is_some() {
+                    self.close_block();
+                }
+                self.contexts.pop();
+            },
+            _ => {},
+        }
+
+        self.last_kind = Some(token.kind);
+        self.last_end = Some((token.src.source, token.span().end()));
+        self.queue.push_back(token);
+    }
+
+    /// Close any remaining layout blocks at the end of the file.
+    fn push_eof(&mut self) {
+        self.is_eof = true;
+        while !self.contexts.is_empty() {
+            self.close_block();
+        }
+    }
+}
+
+impl<'file, Tokens> Iterator for Layout<'file, Tokens>
+where
+    Tokens: Iterator<Item = Token<'file>>,
+{
+    type Item = Token<'file>;
+
+    fn next(&mut self) -> Option<Token<'file>> {
+        while self.state.queue.is_empty() && !self.state.is_eof {
+            match self.tokens.next() {
+                None => self.state.push_eof(),
+                Some(token) if token.is_whitespace() => {
+                    self.state.advance_column(token.src.slice);
+                    return Some(token);
+                },
+                Some(token) => {
+                    let slice = token.src.slice;
+                    self.state.push_token(token);
+                    self.state.advance_column(slice);
+                },
+            }
+        }
+
+        self.state.queue.pop_front()
+    }
+}
+
+/// A saved position in a layout pass.
+#[derive(Clone)]
+pub struct Checkpoint<'file, TokensCheckpoint> {
+    tokens: TokensCheckpoint,
+    state: State<'file>,
+}
+
+impl<'file, Tokens> TokenStream<'file> for Layout<'file, Tokens>
+where
+    Tokens: TokenStream<'file>,
+{
+    type Checkpoint = Checkpoint<'file, Tokens::Checkpoint>;
+
+    fn checkpoint(&self) -> Checkpoint<'file, Tokens::Checkpoint> {
+        Checkpoint {
+            tokens: self.tokens.checkpoint(),
+            state: self.state.clone(),
+        }
+    }
+
+    fn rewind(&mut self, checkpoint: Checkpoint<'file, Tokens::Checkpoint>) {
+        self.tokens.rewind(checkpoint.tokens);
+        self.state = checkpoint.state;
+    }
+}
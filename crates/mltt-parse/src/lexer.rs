@@ -2,28 +2,14 @@ use language_reporting::{Diagnostic, Label};
 use mltt_concrete::SpannedString;
 use mltt_span::{ByteIndex, ByteSize, File, FileSpan};
 use std::str::Chars;
+use unicode_xid::UnicodeXID;
 
-use crate::token::{DelimKind, Token, TokenKind};
-
-/// The keywords used in the language.
-pub const KEYWORDS: &[&str] = &[
-    "case",
-    "else",
-    "if",
-    "in",
-    "let",
-    "then",
-    "Type",
-    "Fun",
-    "fun",
-    "primitive",
-    "Record",
-    "record",
-];
+use crate::token::{DelimKind, Keyword, Token, TokenKind, TokenStream};
 
 fn is_whitespace(ch: char) -> bool {
-    match ch {
-        | '\u{0009}' // horizontal tab, '\t'
+    matches!(
+        ch,
+        '\u{0009}' // horizontal tab, '\t'
         | '\u{000A}' // line feed, '\n'
         | '\u{000B}' // vertical tab
         | '\u{000C}' // form feed
@@ -34,58 +20,45 @@ fn is_whitespace(ch: char) -> bool {
         | '\u{200F}' // right-to-left mark
         | '\u{2028}' // line separator
         | '\u{2029}' // paragraph separator
-        => true,
-        _ => false,
-    }
+    )
 }
 
 fn is_symbol(ch: char) -> bool {
-    match ch {
-        '&' | '!' | ':' | '.' | '=' | '\\' | '/' | '>' | '<' | '-' | '|' | '+' | '*' | '^' => true,
-        _ => false,
-    }
+    matches!(
+        ch,
+        '&' | '!' | ':' | '.' | '=' | '\\' | '/' | '>' | '<' | '-' | '|' | '+' | '*' | '^'
+    )
 }
 
+/// Identifiers start with a character in the Unicode `XID_Start` category, or
+/// an underscore.
 fn is_identifier_start(ch: char) -> bool {
-    match ch {
-        'a'..='z' | 'A'..='Z' | '_' => true,
-        _ => false,
-    }
+    ch == '_' || UnicodeXID::is_xid_start(ch)
 }
 
+/// Identifiers continue with characters in the Unicode `XID_Continue`
+/// category.
+///
+/// Dashes and primes are also permitted in identifiers, but are handled
+/// separately in `Lexer::consume_identifier`.
 fn is_identifier_continue(ch: char) -> bool {
-    match ch {
-        '0'..='9' | 'a'..='z' | 'A'..='Z' | '_' | '-' => true,
-        _ => false,
-    }
+    UnicodeXID::is_xid_continue(ch)
 }
 
 fn is_bin_digit(ch: char) -> bool {
-    match ch {
-        '0'..='1' => true,
-        _ => false,
-    }
+    matches!(ch, '0'..='1')
 }
 
 fn is_oct_digit(ch: char) -> bool {
-    match ch {
-        '0'..='7' => true,
-        _ => false,
-    }
+    matches!(ch, '0'..='7')
 }
 
 fn is_dec_digit(ch: char) -> bool {
-    match ch {
-        '0'..='9' => true,
-        _ => false,
-    }
+    ch.is_ascii_digit()
 }
 
 fn is_hex_digit(ch: char) -> bool {
-    match ch {
-        '0'..='9' | 'a'..='f' | 'A'..='F' => true,
-        _ => false,
-    }
+    ch.is_ascii_hexdigit()
 }
 
 /// An iterator over a source string that yields `Token`s for subsequent use by
@@ -128,7 +101,7 @@ impl<'file> Lexer<'file> {
 
     /// Take the diagnostics from the lexer.
     pub fn take_diagnostics(&mut self) -> Vec<Diagnostic<FileSpan>> {
-        std::mem::replace(&mut self.diagnostics, Vec::new())
+        std::mem::take(&mut self.diagnostics)
     }
 
     /// Record a diagnostic.
@@ -179,6 +152,11 @@ impl<'file> Lexer<'file> {
         self.peeked
     }
 
+    /// Peek at the character after the current lookahead character.
+    fn peek_next(&self) -> Option<char> {
+        self.chars.clone().next()
+    }
+
     /// Consume the current character and load the next one. Return the old token.
     fn advance(&mut self) -> Option<char> {
         let current = std::mem::replace(&mut self.peeked, self.chars.next());
@@ -197,7 +175,7 @@ impl<'file> Lexer<'file> {
 
     /// Skip characters while the predicate matches the lookahead character.
     fn skip_while(&mut self, mut keep_going: impl FnMut(char) -> bool) {
-        while self.peek().map_or(false, |ch| keep_going(ch)) {
+        while self.peek().is_some_and(&mut keep_going) {
             self.advance();
         }
     }
@@ -218,6 +196,7 @@ impl<'file> Lexer<'file> {
         self.advance().map(|ch| match ch {
             ',' => TokenKind::Comma,
             ';' => TokenKind::Semicolon,
+            '#' => TokenKind::Hash,
             '?' => TokenKind::Question,
             '(' => TokenKind::Open(DelimKind::Paren),
             ')' => TokenKind::Close(DelimKind::Paren),
@@ -271,7 +250,8 @@ impl<'file> Lexer<'file> {
             "=" => TokenKind::Equals,
             "->" => TokenKind::RArrow,
             "=>" => TokenKind::RFatArrow,
-            "-" => self.consume_neg_number(),
+            "|" => TokenKind::Pipe,
+            "-" if self.peek().is_some_and(is_dec_digit) => self.consume_neg_number(),
             slice if slice.starts_with("|||") => self.consume_line_doc(),
             slice if slice.starts_with("--") => self.consume_line_comment(),
             _ => TokenKind::Symbol,
@@ -279,13 +259,54 @@ impl<'file> Lexer<'file> {
     }
 
     /// Consume a identifier.
+    ///
+    /// Identifiers may be written in kebab-case, like `is-even` or `bin-11`,
+    /// but to avoid ambiguities with symbols, a dash is only included in an
+    /// identifier if it is immediately followed by an identifier continue
+    /// character. This means that `a->b` is lexed as `a`, `->`, `b`, and that
+    /// `x--y` is lexed as `x` followed by a line comment.
+    ///
+    /// Identifiers may also end with any number of primes, like `x'` or `x''`.
     fn consume_identifier(&mut self) -> TokenKind {
-        self.skip_while(is_identifier_continue);
-        if KEYWORDS.contains(&self.token_slice()) {
-            TokenKind::Keyword
+        self.skip_identifier_continue();
+        if self.token_slice() == "r" && self.skip_if(|ch| ch == '#') {
+            self.consume_raw_identifier()
         } else {
+            match Keyword::from_identifier(self.token_slice()) {
+                Some(keyword) => TokenKind::Keyword(keyword),
+                None => TokenKind::Identifier,
+            }
+        }
+    }
+
+    /// Consume the name of a raw identifier, like `r#let`, allowing keywords
+    /// to be used as identifiers.
+    fn consume_raw_identifier(&mut self) -> TokenKind {
+        if self.skip_if(is_identifier_start) {
+            self.skip_identifier_continue();
             TokenKind::Identifier
+        } else {
+            self.add_diagnostic(
+                Diagnostic::new_error("expected an identifier after `r#`")
+                    .with_label(Label::new_primary(self.token_span())),
+            );
+            TokenKind::Error
+        }
+    }
+
+    /// Skip the remaining characters of an identifier, including any dashes
+    /// and trailing primes.
+    fn skip_identifier_continue(&mut self) {
+        loop {
+            self.skip_while(is_identifier_continue);
+            match (self.peek(), self.peek_next()) {
+                (Some('-'), Some(ch)) if is_identifier_continue(ch) => {
+                    self.advance();
+                },
+                (_, _) => break,
+            }
         }
+        self.skip_while(|ch| ch == '\'');
     }
 
     /// Skip an ASCII character code.
@@ -414,22 +435,12 @@ impl<'file> Lexer<'file> {
         digits
     }
 
-    /// Consume a number starting with a negative sign.
+    /// Consume a number starting with a negative sign. The sign must be
+    /// immediately followed by a digit.
     fn consume_neg_number(&mut self) -> TokenKind {
-        match self.expect_advance() {
-            Ok('0') => self.consume_zero_number(),
-            Ok(ch) if is_dec_digit(ch) => self.consume_dec_literal(),
-            Ok(ch) => {
-                self.add_diagnostic(
-                    Diagnostic::new_error(format!("unexpected character `{}`", ch))
-                        .with_label(Label::new_primary(self.token_span())),
-                );
-                TokenKind::Error
-            },
-            Err(err) => {
-                self.add_diagnostic(err);
-                TokenKind::Error
-            },
+        match self.advance() {
+            Some('0') => self.consume_zero_number(),
+            _ => self.consume_dec_literal(),
         }
     }
 
@@ -485,7 +496,9 @@ impl<'file> Lexer<'file> {
         // the first token.
         self.skip_separated_digits(is_dec_digit);
 
-        if self.skip_if(|ch| ch == '.') {
+        // Two dots start a range pattern, like in `0..9`
+        let is_range = self.peek() == Some('.') && self.peek_next() == Some('.');
+        if !is_range && self.skip_if(|ch| ch == '.') {
             // We should see at least one decimal digit at the beginning of
             // the fractional part of the floating point number. This rules
             // out numbers like `0._1`.
@@ -529,6 +542,41 @@ impl<'file> Lexer<'file> {
     }
 }
 
+/// A saved position in the lexer.
+#[derive(Clone)]
+pub struct Checkpoint<'file> {
+    chars: Chars<'file>,
+    peeked: Option<char>,
+    token_start: ByteIndex,
+    token_end: ByteIndex,
+    diagnostics_len: usize,
+}
+
+impl<'file> TokenStream<'file> for Lexer<'file> {
+    type Checkpoint = Checkpoint<'file>;
+
+    fn checkpoint(&self) -> Checkpoint<'file> {
+        Checkpoint {
+            chars: self.chars.clone(),
+            peeked: self.peeked,
+            token_start: self.token_start,
+            token_end: self.token_end,
+            diagnostics_len: self.diagnostics.len(),
+        }
+    }
+
+    /// Rewind the lexer to a previously saved position. Diagnostics recorded
+    /// since the checkpoint was saved are discarded, as they will be recorded
+    /// again if the same tokens are lexed a second time.
+    fn rewind(&mut self, checkpoint: Checkpoint<'file>) {
+        self.chars = checkpoint.chars;
+        self.peeked = checkpoint.peeked;
+        self.token_start = checkpoint.token_start;
+        self.token_end = checkpoint.token_end;
+        self.diagnostics.truncate(checkpoint.diagnostics_len);
+    }
+}
+
 impl<'file> Iterator for Lexer<'file> {
     type Item = Token<'file>;
 
@@ -2,6 +2,8 @@
 
 #![warn(rust_2018_idioms)]
 
+pub mod cursor;
+pub mod layout;
 pub mod lexer;
 pub mod parser;
 pub mod token;
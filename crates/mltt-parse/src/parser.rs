@@ -5,24 +5,33 @@
 //! The language follows the following [BNF]-style grammar:
 //!
 //! ```text
-//! module  ::= item* EOF
+//! module  ::= inner-attribute* item* EOF
 //!
-//! item    ::= DOC_COMMENT* IDENTIFIER ":" term ";"
-//!           | DOC_COMMENT* IDENTIFIER intro-param* (":" term)? "=" term ";"
+//! item    ::= DOC_COMMENT* attribute* IDENTIFIER ("." IDENTIFIER)* ":" term ";"
+//!           | DOC_COMMENT* attribute* IDENTIFIER ("." IDENTIFIER)* intro-param* (":" term)? "=" term ("termination-by" term)? ";"
+//!           | "section" type-param* item* "end"
+//!           | "namespace" IDENTIFIER item* "end"
+//!           | "public"? "open" IDENTIFIER imports? ";"
+//!           | DOC_COMMENT* "pattern" IDENTIFIER "=" pattern ";"
 //!
 //! pattern ::= IDENTIFIER
 //!           | STRING_LITERAL
 //!           | CHAR_LITERAL
 //!           | INT_LITERAL
 //!           | FLOAT_LITERAL
+//!           | pattern ".." pattern
+//!           | pattern "++" "_"
+//!           | "(" pattern ":" term ")"
 //!
 //! term    ::= IDENTIFIER
+//!           | "_"
 //!           | "?"
 //!           | "(" term ")"
 //!           | term ":" term
 //!           | "let" item+ "in" term
 //!           | "if" term "then" term "else" term
 //!           | "case" term "{" (case-clause ";")* case-clause? "}"
+//!           | "with" term "{" (case-clause ";")* case-clause? "}"
 //!           | STRING_LITERAL
 //!           | CHAR_LITERAL
 //!           | INT_LITERAL
@@ -30,16 +39,19 @@
 //!           | "primitive" STRING_LITERAL
 //!           | "Fun" type-param+ "->" term
 //!           | term "->" term
-//!           | "fun" intro-param+ "=>" term
+//!           | ("fun" | "\\") intro-param+ "=>" term
 //!           | term arg
 //!           | "Record" "{" (record-type-field ";")* record-type-field? "}"
 //!           | "record" "{" (record-intro-field ";")* record-intro-field? "}"
 //!           | term "." IDENTIFIER
+//!           | "Σ" "(" IDENTIFIER ":" term ")" "->" term
+//!           | "(" term "," term ")"
+//!           | "[" (term ",")* term? "]"
 //!           | "Type" ("^" INT_LITERAL)?
 //!
-//! type-param  ::= "(" IDENTIFIER+ ":" term ")"
-//!               | "{" IDENTIFIER+ (":" term)? "}"
-//!               | "{{" IDENTIFIER ":" term "}}"
+//! type-param  ::= DOC_COMMENT* "(" IDENTIFIER+ ":" term ")"
+//!               | DOC_COMMENT* "{" IDENTIFIER+ (":" term)? "}"
+//!               | DOC_COMMENT* "{{" IDENTIFIER ":" term "}}"
 //! intro-param ::= pattern
 //!               | "{" IDENTIFIER ("=" pattern)? "}"
 //!               | "{{" IDENTIFIER ("=" pattern)? "}}"
@@ -47,7 +59,15 @@
 //!               | "{" IDENTIFIER ("=" term)? "}"
 //!               | "{{" IDENTIFIER ("=" term)? "}}"
 //!
-//! case-clause         ::= pattern "=>" term
+//! attribute       ::= "#" "[" IDENTIFIER attribute-args? "]"
+//! inner-attribute ::= "#" "!" "[" IDENTIFIER attribute-args? "]"
+//! attribute-args  ::= "(" (IDENTIFIER ",")* IDENTIFIER? ")"
+//!
+//! imports     ::= "(" (IDENTIFIER ",")* IDENTIFIER? ")"
+//!               | "hiding" "(" (IDENTIFIER ",")* IDENTIFIER? ")"
+//!               | "as" IDENTIFIER
+//!
+//! case-clause         ::= pattern ("|" term)? "=>" term
 //! record-type-field   ::= DOC_COMMENT* IDENTIFIER ":" term
 //! record-intro-field  ::= IDENTIFIER
 //!                       | IDENTIFIER intro-param* (":" term)? "=" term
@@ -62,16 +82,48 @@
 
 use language_reporting::{Diagnostic, Label};
 use mltt_concrete::{
-    Arg, Declaration, Definition, IntroParam, Item, LiteralKind, Pattern, RecordIntroField,
-    RecordTypeField, SpannedString, Term, TypeParam,
+    Arg, Attribute, Declaration, Definition, Imports, IntroParam, Item, LiteralKind, Module,
+    Namespace, Open, Pattern, PatternSynonym, RecordIntroField, RecordTypeField, Section,
+    SpannedString, Term, TypeParam,
 };
 use mltt_span::FileSpan;
 
-use crate::token::{DelimKind, Token, TokenKind};
+use crate::cursor::TokenCursor;
+use crate::layout::Layout;
+use crate::token::{DelimKind, Keyword, Token, TokenKind, TokenStream};
+
+/// Parse a module, running the layout pass over its tokens if it is enabled
+/// with the `layout` language option:
+///
+/// ```text
+/// #![options(layout)]
+/// ```
+///
+/// The layout pass needs to see whitespace, so the tokens should come straight
+/// from the lexer.
+pub fn parse_module<'file, Tokens>(tokens: Tokens) -> Result<Module<'file>, Diagnostic<FileSpan>>
+where
+    Tokens: TokenStream<'file> + 'file,
+{
+    let start = tokens.checkpoint();
+    let mut parser = Parser::new(tokens);
+    let attributes = parser.parse_inner_attributes()?;
+    let mut tokens = parser.tokens.into_inner();
+    tokens.rewind(start);
+
+    let is_layout = attributes.iter().any(|attribute| {
+        attribute.name.slice == "options" && attribute.args.iter().any(|arg| arg.slice == "layout")
+    });
+    if is_layout {
+        parse_module_tokens(Layout::new(tokens))
+    } else {
+        parse_module_tokens(tokens)
+    }
+}
 
-pub fn parse_module<'file>(
-    tokens: impl Iterator<Item = Token<'file>> + 'file,
-) -> Result<Vec<Item<'file>>, Diagnostic<FileSpan>> {
+fn parse_module_tokens<'file>(
+    tokens: impl TokenStream<'file> + 'file,
+) -> Result<Module<'file>, Diagnostic<FileSpan>> {
     let mut parser = Parser::new(tokens);
     let module = parser.parse_module()?;
     parser.expect_eof()?;
@@ -79,7 +131,7 @@ pub fn parse_module<'file>(
 }
 
 pub fn parse_item<'file>(
-    tokens: impl Iterator<Item = Token<'file>> + 'file,
+    tokens: impl TokenStream<'file> + 'file,
 ) -> Result<Item<'file>, Diagnostic<FileSpan>> {
     let mut parser = Parser::new(tokens);
     let item = parser.parse_item()?;
@@ -88,7 +140,7 @@ pub fn parse_item<'file>(
 }
 
 pub fn parse_term<'file>(
-    tokens: impl Iterator<Item = Token<'file>> + 'file,
+    tokens: impl TokenStream<'file> + 'file,
 ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
     let mut parser = Parser::new(tokens);
     let term = parser.parse_term(Prec(0))?;
@@ -96,8 +148,27 @@ pub fn parse_term<'file>(
     Ok(term)
 }
 
+/// Parse a term followed by a type, for example `(fun x => x) (Bool -> Bool)`.
+///
+/// The term is parsed as a function argument, so it needs to be wrapped in
+/// parentheses unless it is atomic.
+pub fn parse_term_and_type<'file>(
+    tokens: impl TokenStream<'file> + 'file,
+) -> Result<(Term<'file>, Term<'file>), Diagnostic<FileSpan>> {
+    let mut parser = Parser::new(tokens);
+    let term = parser.parse_arg_term(Prec(0))?;
+    let ty = parser.parse_term(Prec(0))?;
+    parser.expect_eof()?;
+    Ok((term, ty))
+}
+
 trait Matcher<Given> {
     fn is_match(&self, given: &Given) -> bool;
+
+    /// A description of what the matcher expects, for use in error messages.
+    fn expected(&self) -> Option<String> {
+        None
+    }
 }
 
 impl Matcher<Token<'_>> for TokenKind {
@@ -106,11 +177,27 @@ impl Matcher<Token<'_>> for TokenKind {
     }
 }
 
-struct Keyword<'a>(pub &'a str);
+impl Matcher<Token<'_>> for Keyword {
+    fn is_match(&self, given: &Token<'_>) -> bool {
+        given.is_keyword(*self)
+    }
+
+    fn expected(&self) -> Option<String> {
+        Some(format!("`{}`", self))
+    }
+}
+
+/// Identifiers that have a special meaning in some positions, but that can
+/// otherwise be used as names.
+struct ContextualKeyword(&'static str);
 
-impl Matcher<Token<'_>> for Keyword<'_> {
+impl Matcher<Token<'_>> for ContextualKeyword {
     fn is_match(&self, given: &Token<'_>) -> bool {
-        given.kind == TokenKind::Keyword && given.src.slice == self.0
+        given.kind == TokenKind::Identifier && given.src.slice == self.0
+    }
+
+    fn expected(&self) -> Option<String> {
+        Some(format!("`{}`", self.0))
     }
 }
 
@@ -118,10 +205,16 @@ struct ItemStart;
 
 impl Matcher<Token<'_>> for ItemStart {
     fn is_match(&self, given: &Token<'_>) -> bool {
-        match given.kind {
-            TokenKind::LineDoc | TokenKind::Identifier => true,
-            _ => false,
-        }
+        matches!(
+            given.kind,
+            TokenKind::LineDoc
+                | TokenKind::Hash
+                | TokenKind::Identifier
+                | TokenKind::Keyword(Keyword::Section)
+                | TokenKind::Keyword(Keyword::Namespace)
+                | TokenKind::Keyword(Keyword::Open)
+                | TokenKind::Keyword(Keyword::Pattern)
+        )
     }
 }
 
@@ -129,17 +222,18 @@ struct ArgParamStart;
 
 impl Matcher<Token<'_>> for ArgParamStart {
     fn is_match(&self, given: &Token<'_>) -> bool {
-        match given.kind {
+        matches!(
+            given.kind,
             TokenKind::Identifier
-            | TokenKind::StringLiteral
-            | TokenKind::CharLiteral
-            | TokenKind::IntLiteral
-            | TokenKind::FloatLiteral
-            | TokenKind::Open(DelimKind::Paren)
-            | TokenKind::Open(DelimKind::Brace) => true,
-            TokenKind::Keyword if given.src.slice == "Type" => true,
-            _ => false,
-        }
+                | TokenKind::StringLiteral
+                | TokenKind::CharLiteral
+                | TokenKind::IntLiteral
+                | TokenKind::FloatLiteral
+                | TokenKind::Open(DelimKind::Paren)
+                | TokenKind::Open(DelimKind::Brace)
+                | TokenKind::Open(DelimKind::Bracket)
+                | TokenKind::Keyword(Keyword::Universe)
+        )
     }
 }
 
@@ -181,39 +275,31 @@ impl PartialOrd<u32> for Prec {
     }
 }
 
-/// Skip whitespace or line comment tokens.
-fn next_non_whitespace<'file>(
-    tokens: &mut impl Iterator<Item = Token<'file>>,
-) -> Option<Token<'file>> {
-    tokens.skip_while(Token::is_whitespace).next()
-}
-
 /// A language parser.
-struct Parser<Tokens: Iterator> {
-    /// The underlying iterator of tokens.
-    tokens: Tokens,
-    /// For remembering the peeked token.
-    peeked: Option<Tokens::Item>,
+struct Parser<'file, Tokens> {
+    /// The underlying cursor over the tokens.
+    tokens: TokenCursor<'file, Tokens>,
 }
 
-impl<'file, Tokens> Parser<Tokens>
+impl<'file, Tokens> Parser<'file, Tokens>
 where
-    Tokens: Iterator<Item = Token<'file>> + 'file,
+    Tokens: TokenStream<'file> + 'file,
 {
     /// Create a new parser from an iterator of tokens.
-    fn new(mut tokens: Tokens) -> Parser<Tokens> {
-        let peeked = next_non_whitespace(&mut tokens);
-        Parser { tokens, peeked }
+    fn new(tokens: Tokens) -> Parser<'file, Tokens> {
+        Parser {
+            tokens: TokenCursor::new(tokens),
+        }
     }
 
     /// Peek at the current lookahead token.
     fn peek(&self) -> Option<&Token<'file>> {
-        self.peeked.as_ref()
+        self.tokens.peek()
     }
 
     /// Consume the current token and load the next one. Return the old token.
     fn advance(&mut self) -> Option<Token<'file>> {
-        let next_token = std::mem::replace(&mut self.peeked, next_non_whitespace(&mut self.tokens));
+        let next_token = self.tokens.advance();
 
         log::trace!(
             "shift: consumed = {:?}, lookahead = {:?}",
@@ -224,8 +310,25 @@ where
         next_token
     }
 
+    /// Consume the current token, or return an error if we have reached the
+    /// end of the file.
+    fn expect_advance(&mut self) -> Result<Token<'file>, Diagnostic<FileSpan>> {
+        self.advance()
+            .ok_or_else(|| self.unexpected_eof("unexpected EOF"))
+    }
+
+    /// An error for an unexpected end of file, pointing at the end of the most
+    /// recently consumed token.
+    fn unexpected_eof(&self, message: impl Into<String>) -> Diagnostic<FileSpan> {
+        let diagnostic = Diagnostic::new_error(message);
+        match self.tokens.prev_end_span() {
+            None => diagnostic,
+            Some(span) => diagnostic.with_label(Label::new_primary(span)),
+        }
+    }
+
     fn is_peek_match(&self, matcher: impl Matcher<Token<'file>>) -> bool {
-        self.peek().map_or(false, |token| matcher.is_match(token))
+        self.peek().is_some_and(|token| matcher.is_match(token))
     }
 
     fn try_match(&mut self, matcher: impl Matcher<Token<'file>>) -> Option<Token<'file>> {
@@ -240,11 +343,18 @@ where
         &mut self,
         matcher: impl Matcher<Token<'file>>,
     ) -> Result<Token<'file>, Diagnostic<FileSpan>> {
+        let expected = matcher.expected();
         self.try_match(matcher).ok_or_else(|| {
             log::debug!("unexpected: lookahead = {:?}", self.peek());
+            let message = match (self.peek(), &expected) {
+                (None, Some(expected)) => format!("expected {}, found EOF", expected),
+                (None, None) => "unexpected EOF".to_owned(),
+                (Some(_), Some(expected)) => format!("expected {}", expected),
+                (Some(_), None) => "unexpected token".to_owned(),
+            };
             match self.peek() {
-                None => Diagnostic::new_error("unexpected EOF"), // FIXME: Span
-                Some(token) => Diagnostic::new_error("unexpected token")
+                None => self.unexpected_eof(message),
+                Some(token) => Diagnostic::new_error(message)
                     .with_label(Label::new_primary(token.span()).with_message("token found here")),
             }
         })
@@ -252,12 +362,12 @@ where
 
     fn try_identifier(&mut self) -> Option<SpannedString<'file>> {
         let token = self.try_match(TokenKind::Identifier)?;
-        Some(token.src)
+        Some(token.identifier_name())
     }
 
     fn expect_identifier(&mut self) -> Result<SpannedString<'file>, Diagnostic<FileSpan>> {
         let token = self.expect_match(TokenKind::Identifier)?;
-        Ok(token.src)
+        Ok(token.identifier_name())
     }
 
     fn expect_eof(&mut self) -> Result<(), Diagnostic<FileSpan>> {
@@ -279,30 +389,153 @@ where
         docs
     }
 
+    /// Parse zero-or-more attributes.
+    ///
+    /// ```text
+    /// attribute ::= "#" attribute-body
+    /// ```
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute<'file>>, Diagnostic<FileSpan>> {
+        let mut attributes = Vec::new();
+        while let Some(start_token) = self.try_match(TokenKind::Hash) {
+            attributes.push(self.parse_attribute_body(start_token)?);
+        }
+        Ok(attributes)
+    }
+
+    /// Parse zero-or-more inner attributes, which apply to the enclosing
+    /// module.
+    ///
+    /// ```text
+    /// inner-attribute ::= "#" "!" attribute-body
+    /// ```
+    fn parse_inner_attributes(&mut self) -> Result<Vec<Attribute<'file>>, Diagnostic<FileSpan>> {
+        let mut attributes = Vec::new();
+        while self.is_peek_match(TokenKind::Hash)
+            && self
+                .tokens
+                .peek2()
+                .is_some_and(|token| token.kind == TokenKind::Symbol && token.src.slice == "!")
+        {
+            let start_token = self.expect_advance()?;
+            self.expect_advance()?;
+            attributes.push(self.parse_attribute_body(start_token)?);
+        }
+        Ok(attributes)
+    }
+
+    /// Parse the trailing part of an attribute.
+    ///
+    /// ```text
+    /// attribute-body  ::= "[" IDENTIFIER attribute-args? "]"
+    /// attribute-args  ::= "(" (IDENTIFIER ",")* IDENTIFIER? ")"
+    /// ```
+    fn parse_attribute_body(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Attribute<'file>, Diagnostic<FileSpan>> {
+        self.expect_match(TokenKind::Open(DelimKind::Bracket))?;
+        let name = self.expect_identifier()?;
+
+        let mut args = Vec::new();
+        if self.try_match(TokenKind::Open(DelimKind::Paren)).is_some() {
+            while let Some(arg) = self.try_identifier() {
+                args.push(arg);
+                if self.try_match(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+            self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+        }
+
+        let end_token = self.expect_match(TokenKind::Close(DelimKind::Bracket))?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Attribute { span, name, args })
+    }
+
     /// Parse a module.
     ///
     /// ```text
-    /// module ::= item*
+    /// module ::= inner-attribute* item*
     /// ```
-    fn parse_module(&mut self) -> Result<Vec<Item<'file>>, Diagnostic<FileSpan>> {
+    fn parse_module(&mut self) -> Result<Module<'file>, Diagnostic<FileSpan>> {
+        let attributes = self.parse_inner_attributes()?;
         let mut items = Vec::new();
         while self.peek().is_some() {
-            items.push(self.parse_item()?);
+            push_item(&mut items, self.parse_item()?);
         }
-        Ok(items)
+        Ok(Module { attributes, items })
     }
 
     /// Parse an item.
     ///
     /// ```text
-    /// item ::= DOC_COMMENT* IDENTIFIER ":" term(0) ";"
-    ///        | DOC_COMMENT* IDENTIFIER intro-param* (":" term(0))? "=" term(0) ";"
+    /// item ::= DOC_COMMENT* attribute* item-label ":" term(0) ";"
+    ///        | DOC_COMMENT* attribute* item-label intro-param* (":" term(0))? "=" term(0)
+    ///          ("termination-by" term(0))? ";"
+    ///        | "section" section
+    ///        | "namespace" namespace
+    ///        | "public"? "open" IDENTIFIER imports? ";"
+    ///        | DOC_COMMENT* "pattern" IDENTIFIER "=" pattern(0) ";"
+    ///
+    /// item-label ::= IDENTIFIER ("." IDENTIFIER)*
     /// ```
+    ///
+    /// Items with qualified labels, eg. `Data.List.map`, are placed in the
+    /// namespaces named by the qualifiers.
     fn parse_item(&mut self) -> Result<Item<'file>, Diagnostic<FileSpan>> {
         log::trace!("expecting item");
 
+        if let Some(start_token) = self.try_match(Keyword::Section) {
+            return Ok(Item::Section(self.parse_section(start_token)?));
+        }
+        if let Some(start_token) = self.try_match(Keyword::Namespace) {
+            return Ok(Item::Namespace(self.parse_namespace(start_token)?));
+        }
+        let public_token = if self.is_peek_match(ContextualKeyword("public"))
+            && (self.tokens.peek2()).is_some_and(|token| token.is_keyword(Keyword::Open))
+        {
+            Some(self.expect_advance()?)
+        } else {
+            None
+        };
+        if let Some(open_token) = self.try_match(Keyword::Open) {
+            let start_token = public_token.as_ref().unwrap_or(&open_token);
+            let name = self.expect_identifier()?;
+            let imports = self.parse_imports()?;
+            let end_token = self.expect_match(TokenKind::Semicolon)?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            return Ok(Item::Open(Open {
+                span,
+                is_public: public_token.is_some(),
+                name,
+                imports,
+            }));
+        }
+
         let docs = self.expect_doc_comments();
-        let label = self.expect_identifier()?;
+        if let Some(start_token) = self.try_match(Keyword::Pattern) {
+            let label = self.expect_identifier()?;
+            self.expect_match(TokenKind::Equals)?;
+            let pattern = self.parse_pattern(Prec(0))?;
+            let end_token = self.expect_match(TokenKind::Semicolon)?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            return Ok(Item::PatternSynonym(PatternSynonym {
+                span,
+                docs,
+                label,
+                pattern,
+            }));
+        }
+        let attributes = self.parse_attributes()?;
+        let mut qualifiers = Vec::new();
+        let mut label = self.expect_identifier()?;
+        while self.try_match(TokenKind::Dot).is_some() {
+            qualifiers.push(label);
+            label = self.expect_identifier()?;
+        }
 
         log::trace!("item label: {:?}", label);
 
@@ -314,11 +547,12 @@ where
             if params.is_empty() && self.try_match(TokenKind::Semicolon).is_some() {
                 let declaration = Declaration {
                     docs,
+                    attributes,
                     label,
                     body_ty,
                 };
 
-                return Ok(Item::Declaration(declaration));
+                return Ok(qualify_item(qualifiers, Item::Declaration(declaration)));
             } else {
                 Some(body_ty)
             }
@@ -328,17 +562,23 @@ where
 
         if self.try_match(TokenKind::Equals).is_some() {
             let body = self.parse_term(Prec(0))?;
+            let termination_by = match self.try_match(Keyword::TerminationBy) {
+                Some(_) => Some(self.parse_term(Prec(0))?),
+                None => None,
+            };
             self.expect_match(TokenKind::Semicolon)?;
 
             let definition = Definition {
                 docs,
+                attributes,
                 label,
                 params,
                 body_ty,
                 body,
+                termination_by,
             };
 
-            Ok(Item::Definition(definition))
+            Ok(qualify_item(qualifiers, Item::Definition(definition)))
         } else if params.is_empty() {
             // TODO: Span
             Err(Diagnostic::new_error("expected declaration or definition"))
@@ -350,6 +590,88 @@ where
         }
     }
 
+    /// Parse the trailing part of a section.
+    ///
+    /// ```text
+    /// section ::= type-param* item* "end"
+    /// ```
+    fn parse_section(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Section<'file>, Diagnostic<FileSpan>> {
+        let params = self.parse_type_params()?;
+        let mut items = Vec::new();
+        while self.is_peek_match(ItemStart) {
+            push_item(&mut items, self.parse_item()?);
+        }
+        let end_token = self.expect_match(Keyword::End)?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Section {
+            span,
+            params,
+            items,
+        })
+    }
+
+    /// Parse the trailing part of a namespace.
+    ///
+    /// ```text
+    /// namespace ::= IDENTIFIER item* "end"
+    /// ```
+    fn parse_namespace(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Namespace<'file>, Diagnostic<FileSpan>> {
+        let name = self.expect_identifier()?;
+        let mut items = Vec::new();
+        while self.is_peek_match(ItemStart) {
+            push_item(&mut items, self.parse_item()?);
+        }
+        let end_token = self.expect_match(Keyword::End)?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Namespace { span, name, items })
+    }
+
+    /// Parse the items to bring into scope when opening a namespace.
+    ///
+    /// ```text
+    /// imports ::= import-names
+    ///           | "hiding" import-names
+    ///           | "as" IDENTIFIER
+    /// ```
+    fn parse_imports(&mut self) -> Result<Imports<'file>, Diagnostic<FileSpan>> {
+        if self.try_match(ContextualKeyword("as")).is_some() {
+            Ok(Imports::Alias(self.expect_identifier()?))
+        } else if self.try_match(ContextualKeyword("hiding")).is_some() {
+            Ok(Imports::Hiding(self.parse_import_names()?))
+        } else if self.is_peek_match(TokenKind::Open(DelimKind::Paren)) {
+            Ok(Imports::Only(self.parse_import_names()?))
+        } else {
+            Ok(Imports::All)
+        }
+    }
+
+    /// Parse a list of names to import.
+    ///
+    /// ```text
+    /// import-names ::= "(" (IDENTIFIER ",")* IDENTIFIER? ")"
+    /// ```
+    fn parse_import_names(&mut self) -> Result<Vec<SpannedString<'file>>, Diagnostic<FileSpan>> {
+        self.expect_match(TokenKind::Open(DelimKind::Paren))?;
+        let mut names = Vec::new();
+        while let Some(name) = self.try_identifier() {
+            names.push(name);
+            if self.try_match(TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+
+        Ok(names)
+    }
+
     /// Parse zero-or-more function introduction parameters.
     ///
     /// ```text
@@ -439,24 +761,26 @@ where
     ///
     /// ```text
     /// pattern(prec) ::= operators(prec) {
+    ///     prefix  "("     ::= pattern-ann
     ///     nilfix  IDENTIFIER
     ///     nilfix  STRING_LITERAL
     ///     nilfix  CHAR_LITERAL
     ///     nilfix  INT_LITERAL
     ///     nilfix  FLOAT_LITERAL
+    ///
+    ///     infixl  ".."            50  ::= pattern-range
+    ///     postfix "++"            50  ::= pattern-prefix
     /// }
     /// ```
-    fn parse_pattern(&mut self, _right_prec: Prec) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+    fn parse_pattern(&mut self, right_prec: Prec) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
         // Use Top-Down Operator Precedence Parsing (a.k.a. Pratt Parsing) to
         // recognise the term syntax. This is not yet abstracted out into a more
         // general form.
 
-        let token = self.advance().ok_or_else(
-            || Diagnostic::new_error("unexpected EOF"), // FIXME: Spanned diagnostic
-        )?;
+        let token = self.expect_advance()?;
 
         // Prefix operators
-        let pattern = match (token.kind, token.src.slice) {
+        let mut pattern = match (token.kind, token.src.slice) {
             (TokenKind::Identifier, _) => Ok(Pattern::Var(self.parse_var(token)?)),
             (TokenKind::StringLiteral, _) => {
                 let (kind, literal) = self.parse_string_literal(token)?;
@@ -474,6 +798,7 @@ where
                 let (kind, literal) = self.parse_float_literal(token)?;
                 Ok(Pattern::LiteralIntro(kind, literal))
             },
+            (TokenKind::Open(DelimKind::Paren), _) => self.parse_pattern_ann(token),
             (_, _) => Err(Diagnostic::new_error("expected a pattern").with_label(
                 Label::new_primary(token.span()).with_message("pattern expected here"),
             )),
@@ -481,7 +806,15 @@ where
 
         // Infix operators
         while let Some(token) = self.peek() {
-            match token.kind {
+            match (token.kind, token.src.slice) {
+                (TokenKind::Symbol, "..") if right_prec < 50 => {
+                    self.advance().unwrap();
+                    pattern = self.parse_pattern_range(pattern)?;
+                },
+                (TokenKind::Symbol, "++") if right_prec < 50 => {
+                    self.advance().unwrap();
+                    pattern = self.parse_pattern_prefix(pattern)?;
+                },
                 _ => break,
             }
         }
@@ -489,6 +822,53 @@ where
         Ok(pattern)
     }
 
+    /// Parse the trailing part of a range pattern.
+    ///
+    /// ```text
+    /// pattern-range ::= pattern(50)
+    /// ```
+    fn parse_pattern_range(
+        &mut self,
+        start: Pattern<'file>,
+    ) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+        let end = self.parse_pattern(Prec(50))?;
+
+        Ok(Pattern::Range(Box::new(start), Box::new(end)))
+    }
+
+    /// Parse the trailing part of a string prefix pattern.
+    ///
+    /// ```text
+    /// pattern-prefix ::= "_"
+    /// ```
+    fn parse_pattern_prefix(
+        &mut self,
+        prefix: Pattern<'file>,
+    ) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+        let end_token = self.expect_match(ContextualKeyword("_"))?;
+        let span = FileSpan::merge(prefix.span(), end_token.span());
+
+        Ok(Pattern::Prefix(span, Box::new(prefix)))
+    }
+
+    /// Parse an annotated pattern.
+    ///
+    /// ```text
+    /// pattern-ann ::= "(" pattern(0) ":" term(0) ")"
+    /// ```
+    fn parse_pattern_ann(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Pattern<'file>, Diagnostic<FileSpan>> {
+        let pattern = self.parse_pattern(Prec(0))?;
+        self.expect_match(TokenKind::Colon)?;
+        let ann = self.parse_term(Prec(0))?;
+        let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Pattern::Ann(span, Box::new(pattern), Box::new(ann)))
+    }
+
     /// Parse a term.
     ///
     /// ```text
@@ -496,14 +876,19 @@ where
     ///     prefix  "let"               ::= let-expr
     ///     prefix  "if"                ::= if-expr
     ///     prefix  "case"              ::= case-expr
+    ///     prefix  "with"              ::= with-expr
     ///     prefix  "("                 ::= parens fun-elim
     ///     prefix  "Fun"               ::= fun-type
+    ///     prefix  "Σ"                 ::= sigma-type
     ///     prefix  "fun"               ::= fun-intro
+    ///     prefix  "\\"                ::= fun-intro
     ///     prefix  "Record"            ::= record-type
     ///     prefix  "record"            ::= record-intro
+    ///     prefix  "["                 ::= array-intro
     ///     prefix  "Type"              ::= universe
     ///     prefix  "primitive"         ::= primitive
     ///     prefix  IDENTIFIER          ::= fun-elim
+    ///     nilfix  "_"                 ::= placeholder
     ///     nilfix  "?"                 ::= hole fun-elim
     ///     nilfix  STRING_LITERAL
     ///     nilfix  CHAR_LITERAL
@@ -520,12 +905,11 @@ where
         // recognise the term syntax. This is not yet abstracted out into a more
         // general form.
 
-        let token = self.advance().ok_or_else(
-            || Diagnostic::new_error("unexpected EOF"), // FIXME: Spanned diagnostic
-        )?;
+        let token = self.expect_advance()?;
 
         // Prefix operators
         let mut term = match (token.kind, token.src.slice) {
+            (TokenKind::Identifier, "_") => self.parse_placeholder(token),
             (TokenKind::Identifier, _) => {
                 let term = Term::Var(self.parse_var(token)?);
                 self.parse_fun_elim(term)
@@ -554,15 +938,26 @@ where
                 let term = self.parse_parens(token)?;
                 self.parse_fun_elim(term)
             },
-            (TokenKind::Keyword, "Fun") => self.parse_fun_ty(token),
-            (TokenKind::Keyword, "fun") => self.parse_fun_intro(token),
-            (TokenKind::Keyword, "Record") => self.parse_record_ty(token),
-            (TokenKind::Keyword, "record") => self.parse_record_intro(token),
-            (TokenKind::Keyword, "let") => self.parse_let_expr(token),
-            (TokenKind::Keyword, "if") => self.parse_if_expr(token),
-            (TokenKind::Keyword, "case") => self.parse_case_expr(token),
-            (TokenKind::Keyword, "Type") => self.parse_universe(token),
-            (TokenKind::Keyword, "primitive") => self.parse_prim(token),
+            (TokenKind::Keyword(Keyword::FunType), _) => self.parse_fun_ty(token),
+            (TokenKind::Keyword(Keyword::SigmaType), _) => self.parse_sigma_ty(token),
+            (TokenKind::Keyword(Keyword::FunIntro), _) | (TokenKind::Symbol, "\\") => {
+                self.parse_fun_intro(token)
+            },
+            (TokenKind::Keyword(Keyword::RecordType), _) => self.parse_record_ty(token),
+            (TokenKind::Keyword(Keyword::RecordIntro), _) => self.parse_record_intro(token),
+            (TokenKind::Open(DelimKind::Bracket), _) => self.parse_array_intro(token),
+            (TokenKind::Keyword(Keyword::Let), _) => self.parse_let_expr(token),
+            (TokenKind::Keyword(Keyword::If), _) => self.parse_if_expr(token),
+            (TokenKind::Keyword(Keyword::Case), _) => self.parse_case_expr(token),
+            (TokenKind::Keyword(Keyword::With), _) => self.parse_with_expr(token),
+            (TokenKind::Keyword(Keyword::Universe), _) => self.parse_universe(token),
+            (TokenKind::Keyword(Keyword::Primitive), _) => self.parse_prim(token),
+            (TokenKind::Symbol, "-") => {
+                Err(Diagnostic::new_error("expected a term")
+                    .with_label(Label::new_primary(token.span()).with_message(
+                        "negative numbers must be written without a space, eg. `-1`",
+                    )))
+            },
             (_, _) => Err(Diagnostic::new_error("expected a term")
                 .with_label(Label::new_primary(token.span()).with_message("term expected here"))),
         }?;
@@ -595,8 +990,10 @@ where
     /// ```text
     /// arg-term(prec) ::= operators(prec) {
     ///     prefix  "("                 ::= parens
+    ///     prefix  "["                 ::= array-intro
     ///     prefix  "Type"              ::= universe
     ///     nilfix  IDENTIFIER
+    ///     nilfix  "_"
     ///     nilfix  "?"
     ///     nilfix  STRING_LITERAL
     ///     nilfix  CHAR_LITERAL
@@ -611,12 +1008,11 @@ where
         // recognise the term syntax. This is not yet abstracted out into a more
         // general form.
 
-        let token = self.advance().ok_or_else(
-            || Diagnostic::new_error("unexpected EOF"), // FIXME: Spanned diagnostic
-        )?;
+        let token = self.expect_advance()?;
 
         // Prefix operators
         let mut term = match (token.kind, token.src.slice) {
+            (TokenKind::Identifier, "_") => self.parse_placeholder(token),
             (TokenKind::Identifier, _) => Ok(Term::Var(self.parse_var(token)?)),
             (TokenKind::Question, _) => self.parse_hole(token),
             (TokenKind::StringLiteral, _) => {
@@ -636,7 +1032,8 @@ where
                 Ok(Term::LiteralIntro(kind, literal))
             },
             (TokenKind::Open(DelimKind::Paren), _) => self.parse_parens(token),
-            (TokenKind::Keyword, "Type") => self.parse_universe(token),
+            (TokenKind::Open(DelimKind::Bracket), _) => self.parse_array_intro(token),
+            (TokenKind::Keyword(Keyword::Universe), _) => self.parse_universe(token),
             (_, _) => Err(Diagnostic::new_error("expected a term")
                 .with_label(Label::new_primary(token.span()).with_message("term expected here"))),
         }?;
@@ -660,7 +1057,15 @@ where
         &mut self,
         token: Token<'file>,
     ) -> Result<SpannedString<'file>, Diagnostic<FileSpan>> {
-        Ok(token.src)
+        Ok(token.identifier_name())
+    }
+
+    /// Parse the trailing part of a placeholder.
+    fn parse_placeholder(
+        &mut self,
+        token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        Ok(Term::Placeholder(token.span()))
     }
 
     /// Parse the trailing part of a hole.
@@ -704,18 +1109,77 @@ where
     ///
     /// ```text
     /// fun-ty  ::= type-param+ "->" term(50 - 1)
-    ///
-    /// type-param  ::= "(" IDENTIFIER+ ":" term(0) ")"
-    ///               | "{" IDENTIFIER+ (":" term(0))? "}"
-    ///               | "{{" IDENTIFIER ":" term(0) "}}"
     /// ```
     fn parse_fun_ty(
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        let params = self.parse_type_params()?;
+        if let Some(doc_token) = self.try_match(TokenKind::LineDoc) {
+            return Err(
+                Diagnostic::new_error("expected a parameter after doc comment").with_label(
+                    Label::new_primary(doc_token.span())
+                        .with_message("this doc comment is not attached to a parameter"),
+                ),
+            );
+        }
+        if params.is_empty() {
+            return Err(
+                Diagnostic::new_error("expected at least one parameter").with_label(
+                    Label::new_primary(start_token.span())
+                        .with_message("at least one parameter was expected after this keyword"),
+                ),
+            );
+        }
+
+        self.expect_match(TokenKind::RArrow)?;
+        let body_ty = self.parse_term(Prec(50 - 1))?;
+        let span = FileSpan::merge(start_token.span(), body_ty.span());
+
+        Ok(Term::FunType(span, params, Box::new(body_ty)))
+    }
+
+    /// Parse the trailing part of a dependent pair type.
+    ///
+    /// ```text
+    /// sigma-type ::= "(" IDENTIFIER ":" term(0) ")" "->" term(50 - 1)
+    /// ```
+    fn parse_sigma_ty(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        self.expect_match(TokenKind::Open(DelimKind::Paren))?;
+        let param_name = self.expect_identifier()?;
+        self.expect_match(TokenKind::Colon)?;
+        let param_ty = self.parse_term(Prec(0))?;
+        self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+
+        self.expect_match(TokenKind::RArrow)?;
+        let body_ty = self.parse_term(Prec(50 - 1))?;
+        let span = FileSpan::merge(start_token.span(), body_ty.span());
+
+        Ok(Term::SigmaType(
+            span,
+            param_name,
+            Box::new(param_ty),
+            Box::new(body_ty),
+        ))
+    }
+
+    /// Parse a list of function type parameters.
+    ///
+    /// ```text
+    /// type-param  ::= DOC_COMMENT* "(" IDENTIFIER+ ":" term(0) ")"
+    ///               | DOC_COMMENT* "{" IDENTIFIER+ (":" term(0))? "}"
+    ///               | DOC_COMMENT* "{{" IDENTIFIER ":" term(0) "}}"
+    /// ```
+    fn parse_type_params(&mut self) -> Result<Vec<TypeParam<'file>>, Diagnostic<FileSpan>> {
         let mut params = Vec::new();
 
         loop {
+            let checkpoint = self.tokens.checkpoint();
+            let docs = self.expect_doc_comments();
+
             if let Some(start_param_token) = self.try_match(TokenKind::Open(DelimKind::Paren)) {
                 let mut param_names = Vec::new();
                 while let Some(param_name) = self.try_identifier() {
@@ -733,7 +1197,7 @@ where
                 let end_param_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
                 let param_span = FileSpan::merge(start_param_token.span(), end_param_token.span());
 
-                params.push(TypeParam::Explicit(param_span, param_names, param_ty));
+                params.push(TypeParam::Explicit(param_span, docs, param_names, param_ty));
             } else if let Some(start_param_token) =
                 self.try_match(TokenKind::Open(DelimKind::Brace))
             {
@@ -746,7 +1210,7 @@ where
                     let param_span =
                         FileSpan::merge(start_param_token.span(), end_param_token.span());
 
-                    params.push(TypeParam::Instance(param_span, param_name, param_ty));
+                    params.push(TypeParam::Instance(param_span, docs, param_name, param_ty));
                 } else {
                     let mut param_names = Vec::new();
                     while let Some(param_name) = self.try_identifier() {
@@ -770,27 +1234,16 @@ where
                     let param_span =
                         FileSpan::merge(start_param_token.span(), end_param_token.span());
 
-                    params.push(TypeParam::Implicit(param_span, param_names, param_ty));
+                    params.push(TypeParam::Implicit(param_span, docs, param_names, param_ty));
                 }
             } else {
+                // Leave any doc comments for whatever follows the parameters
+                self.tokens.rewind(checkpoint);
                 break;
             }
         }
 
-        if params.is_empty() {
-            return Err(
-                Diagnostic::new_error("expected at least one parameter").with_label(
-                    Label::new_primary(start_token.span())
-                        .with_message("at least one parameter was expected after this keyword"),
-                ),
-            );
-        }
-
-        self.expect_match(TokenKind::RArrow)?;
-        let body_ty = self.parse_term(Prec(50 - 1))?;
-        let span = FileSpan::merge(start_token.span(), body_ty.span());
-
-        Ok(Term::FunType(span, params, Box::new(body_ty)))
+        Ok(params)
     }
 
     /// Parse the trailing part of a function introduction.
@@ -822,18 +1275,53 @@ where
     ///
     /// ```text
     /// parens ::= term(0) ")"
+    ///          | term(0) "," term(0) ")"
     /// ```
     fn parse_parens(
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         let term = self.parse_term(Prec(0))?;
+        if self.try_match(TokenKind::Comma).is_some() {
+            let snd = self.parse_term(Prec(0))?;
+            let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
+            let span = FileSpan::merge(start_token.span(), end_token.span());
+
+            return Ok(Term::PairIntro(span, Box::new(term), Box::new(snd)));
+        }
         let end_token = self.expect_match(TokenKind::Close(DelimKind::Paren))?;
         let span = FileSpan::merge(start_token.span(), end_token.span());
 
         Ok(Term::Parens(span, Box::new(term)))
     }
 
+    /// Parse the trailing part of an array introduction.
+    ///
+    /// ```text
+    /// array-intro ::= (term(0) ",")* term(0)? "]"
+    /// ```
+    fn parse_array_intro(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        let mut elems = Vec::new();
+
+        let end_token = loop {
+            if let Some(end_token) = self.try_match(TokenKind::Close(DelimKind::Bracket)) {
+                break end_token;
+            }
+
+            elems.push(self.parse_term(Prec(0))?);
+
+            if self.try_match(TokenKind::Comma).is_none() {
+                break self.expect_match(TokenKind::Close(DelimKind::Bracket))?;
+            }
+        };
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Term::ArrayIntro(span, elems))
+    }
+
     /// Parse the trailing part of a record type.
     ///
     /// ```text
@@ -938,7 +1426,7 @@ where
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         let mut items = Vec::new();
         while self.is_peek_match(ItemStart) {
-            items.push(self.parse_item()?);
+            push_item(&mut items, self.parse_item()?);
         }
         if items.is_empty() {
             return Err(
@@ -949,7 +1437,7 @@ where
             );
         }
 
-        self.expect_match(Keyword("in"))?;
+        self.expect_match(Keyword::In)?;
         let body_term = self.parse_term(Prec(0))?;
 
         let span = FileSpan::merge(start_token.span(), body_term.span());
@@ -967,9 +1455,9 @@ where
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         let condition = self.parse_term(Prec(0))?;
-        self.expect_match(Keyword("then"))?;
+        self.expect_match(Keyword::Then)?;
         let consequent = self.parse_term(Prec(0))?;
-        self.expect_match(Keyword("else"))?;
+        self.expect_match(Keyword::Else)?;
         let alternative = self.parse_term(Prec(0))?;
 
         let span = FileSpan::merge(start_token.span(), alternative.span());
@@ -985,41 +1473,76 @@ where
     /// Parse the trailing part of a case expression.
     ///
     /// ```text
-    /// case-expr   ::= arg-term(0) "{" (case-clause ";")* case-clause? "}"
-    /// case-clause ::= pattern(0) "=>" term(0)
+    /// case-expr   ::= arg-term(0) case-clauses
     /// ```
     fn parse_case_expr(
         &mut self,
         start_token: Token<'file>,
     ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
         let scrutinee = self.parse_arg_term(Prec(0))?;
+        let (end_token, clauses) = self.parse_case_clauses()?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Term::Case(span, Box::new(scrutinee), clauses))
+    }
 
+    /// Parse the trailing part of a with expression.
+    ///
+    /// ```text
+    /// with-expr   ::= arg-term(0) case-clauses
+    /// ```
+    fn parse_with_expr(
+        &mut self,
+        start_token: Token<'file>,
+    ) -> Result<Term<'file>, Diagnostic<FileSpan>> {
+        let scrutinee = self.parse_arg_term(Prec(0))?;
+        let (end_token, clauses) = self.parse_case_clauses()?;
+        let span = FileSpan::merge(start_token.span(), end_token.span());
+
+        Ok(Term::With(span, Box::new(scrutinee), clauses))
+    }
+
+    /// Parse the clauses of a case or with expression, returning the closing
+    /// brace along with the clauses.
+    ///
+    /// ```text
+    /// case-clauses ::= "{" (case-clause ";")* case-clause? "}"
+    /// case-clause  ::= pattern(0) ("|" term(0))? "=>" term(0)
+    /// ```
+    #[allow(clippy::type_complexity)]
+    fn parse_case_clauses(
+        &mut self,
+    ) -> Result<
+        (
+            Token<'file>,
+            Vec<(Pattern<'file>, Option<Term<'file>>, Term<'file>)>,
+        ),
+        Diagnostic<FileSpan>,
+    > {
         self.expect_match(TokenKind::Open(DelimKind::Brace))?;
 
         let mut clauses = Vec::new();
         while !self.is_peek_match(TokenKind::Close(DelimKind::Brace)) {
             let pattern = self.parse_pattern(Prec(0))?;
+            let guard = match self.try_match(TokenKind::Pipe) {
+                Some(_) => Some(self.parse_term(Prec(0))?),
+                None => None,
+            };
 
             self.expect_match(TokenKind::RFatArrow)?;
 
             let body = self.parse_term(Prec(0))?;
 
-            clauses.push((pattern, body));
-
-            if self.try_match(TokenKind::Semicolon).is_some() {
-                continue;
-            } else {
-                let end_token = self.expect_match(TokenKind::Close(DelimKind::Brace))?;
-                let span = FileSpan::merge(start_token.span(), end_token.span());
+            clauses.push((pattern, guard, body));
 
-                return Ok(Term::Case(span, Box::new(scrutinee), clauses));
+            if self.try_match(TokenKind::Semicolon).is_none() {
+                break;
             }
         }
 
         let end_token = self.expect_match(TokenKind::Close(DelimKind::Brace))?;
-        let span = FileSpan::merge(start_token.span(), end_token.span());
 
-        Ok(Term::Case(span, Box::new(scrutinee), clauses))
+        Ok((end_token, clauses))
     }
 
     /// Parse the trailing part of a universe.
@@ -1118,3 +1641,39 @@ where
         }
     }
 }
+
+/// Place an item with a qualified label, eg. `Data.List.map`, in the
+/// namespaces named by its qualifiers.
+///
+/// The spans of these namespaces start at their names, rather than at a
+/// `namespace` keyword.
+fn qualify_item<'file>(qualifiers: Vec<SpannedString<'file>>, item: Item<'file>) -> Item<'file> {
+    qualifiers.into_iter().rev().fold(item, |item, name| {
+        let span = FileSpan::merge(name.span(), item.span());
+        let items = vec![item];
+        Item::Namespace(Namespace { span, name, items })
+    })
+}
+
+/// Returns `true` if the namespace was introduced by a qualified label,
+/// rather than a `namespace` block.
+fn is_qualifier(namespace: &Namespace<'_>) -> bool {
+    namespace.span.start() == namespace.name.start
+}
+
+/// Add an item to a list of items. Consecutive items with qualified labels
+/// that share a qualifier are grouped in the same namespace, so that
+/// declarations stay alongside their definitions.
+fn push_item<'file>(items: &mut Vec<Item<'file>>, item: Item<'file>) {
+    match (items.last_mut(), item) {
+        (Some(Item::Namespace(last)), Item::Namespace(next))
+            if is_qualifier(last) && is_qualifier(&next) && last.name.slice == next.name.slice =>
+        {
+            last.span = FileSpan::merge(last.span, next.span);
+            for item in next.items {
+                push_item(&mut last.items, item);
+            }
+        },
+        (_, item) => items.push(item),
+    }
+}
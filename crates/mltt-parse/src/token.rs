@@ -1,5 +1,5 @@
 use mltt_concrete::SpannedString;
-use mltt_span::FileSpan;
+use mltt_span::{ByteSize, FileSpan};
 use std::fmt;
 
 /// A kind of delimiter.
@@ -13,6 +13,136 @@ pub enum DelimKind {
     Bracket,
 }
 
+/// A keyword in the language.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Keyword {
+    /// `case`
+    Case,
+    /// `else`
+    Else,
+    /// `end`
+    End,
+    /// `fun`
+    FunIntro,
+    /// `Fun`
+    FunType,
+    /// `if`
+    If,
+    /// `in`
+    In,
+    /// `let`
+    Let,
+    /// `namespace`
+    Namespace,
+    /// `open`
+    Open,
+    /// `pattern`
+    Pattern,
+    /// `primitive`
+    Primitive,
+    /// `record`
+    RecordIntro,
+    /// `Record`
+    RecordType,
+    /// `section`
+    Section,
+    /// `termination-by`
+    TerminationBy,
+    /// `then`
+    Then,
+    /// `Type`
+    Universe,
+    /// `with`
+    With,
+    /// `Σ`
+    SigmaType,
+}
+
+impl Keyword {
+    /// All of the keywords used in the language.
+    pub const ALL: &'static [Keyword] = &[
+        Keyword::Case,
+        Keyword::Else,
+        Keyword::End,
+        Keyword::FunIntro,
+        Keyword::FunType,
+        Keyword::If,
+        Keyword::In,
+        Keyword::Let,
+        Keyword::Namespace,
+        Keyword::Open,
+        Keyword::Pattern,
+        Keyword::Primitive,
+        Keyword::RecordIntro,
+        Keyword::RecordType,
+        Keyword::Section,
+        Keyword::TerminationBy,
+        Keyword::Then,
+        Keyword::Universe,
+        Keyword::With,
+        Keyword::SigmaType,
+    ];
+
+    /// Look up the keyword that corresponds to an identifier, if any.
+    pub fn from_identifier(slice: &str) -> Option<Keyword> {
+        match slice {
+            "case" => Some(Keyword::Case),
+            "else" => Some(Keyword::Else),
+            "end" => Some(Keyword::End),
+            "fun" => Some(Keyword::FunIntro),
+            "Fun" => Some(Keyword::FunType),
+            "if" => Some(Keyword::If),
+            "in" => Some(Keyword::In),
+            "let" => Some(Keyword::Let),
+            "namespace" => Some(Keyword::Namespace),
+            "open" => Some(Keyword::Open),
+            "pattern" => Some(Keyword::Pattern),
+            "primitive" => Some(Keyword::Primitive),
+            "record" => Some(Keyword::RecordIntro),
+            "Record" => Some(Keyword::RecordType),
+            "section" => Some(Keyword::Section),
+            "termination-by" => Some(Keyword::TerminationBy),
+            "then" => Some(Keyword::Then),
+            "Type" => Some(Keyword::Universe),
+            "with" => Some(Keyword::With),
+            "Σ" => Some(Keyword::SigmaType),
+            _ => None,
+        }
+    }
+
+    /// The source code of the keyword.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Keyword::Case => "case",
+            Keyword::Else => "else",
+            Keyword::End => "end",
+            Keyword::FunIntro => "fun",
+            Keyword::FunType => "Fun",
+            Keyword::If => "if",
+            Keyword::In => "in",
+            Keyword::Let => "let",
+            Keyword::Namespace => "namespace",
+            Keyword::Open => "open",
+            Keyword::Pattern => "pattern",
+            Keyword::Primitive => "primitive",
+            Keyword::RecordIntro => "record",
+            Keyword::RecordType => "Record",
+            Keyword::Section => "section",
+            Keyword::TerminationBy => "termination-by",
+            Keyword::Then => "then",
+            Keyword::Universe => "Type",
+            Keyword::With => "with",
+            Keyword::SigmaType => "Σ",
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// A tag that makes it easier to remember what type of token this is.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TokenKind {
@@ -22,7 +152,7 @@ pub enum TokenKind {
     LineComment,
     LineDoc,
 
-    Keyword,
+    Keyword(Keyword),
     Symbol,
     Identifier,
     StringLiteral,
@@ -34,6 +164,8 @@ pub enum TokenKind {
     Colon,
     Dot,
     Equals,
+    Hash,
+    Pipe,
     Question,
     RArrow,
     RFatArrow,
@@ -54,7 +186,7 @@ pub struct Token<'file> {
     pub src: SpannedString<'file>,
 }
 
-impl Token<'_> {
+impl<'file> Token<'file> {
     pub fn span(&self) -> FileSpan {
         self.src.span()
     }
@@ -63,11 +195,35 @@ impl Token<'_> {
         self.kind == TokenKind::Whitespace || self.kind == TokenKind::LineComment
     }
 
-    pub fn is_keyword(&self, slice: &str) -> bool {
-        self.kind == TokenKind::Keyword && self.src.slice == slice
+    pub fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.kind == TokenKind::Keyword(keyword)
+    }
+
+    /// The name referred to by an identifier token. The `r#` prefix is
+    /// removed from raw identifiers, so `r#let` refers to the name `let`.
+    pub fn identifier_name(&self) -> SpannedString<'file> {
+        match self.src.slice.strip_prefix("r#") {
+            Some(name) => {
+                SpannedString::new(self.src.source, self.src.start + ByteSize::from(2), name)
+            },
+            None => self.src,
+        }
     }
 }
 
+/// A stream of tokens that can be rewound to a previously saved position,
+/// allowing the parser to backtrack when speculatively parsing.
+pub trait TokenStream<'file>: Iterator<Item = Token<'file>> {
+    /// A saved position in the token stream.
+    type Checkpoint: Clone;
+
+    /// Save the current position in the token stream.
+    fn checkpoint(&self) -> Self::Checkpoint;
+
+    /// Rewind the token stream to a previously saved position.
+    fn rewind(&mut self, checkpoint: Self::Checkpoint);
+}
+
 impl fmt::Debug for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{kind:?}@{src:?}", kind = self.kind, src = self.src)
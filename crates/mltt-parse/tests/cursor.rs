@@ -0,0 +1,50 @@
+use mltt_parse::cursor::TokenCursor;
+use mltt_parse::lexer::Lexer;
+use mltt_parse::token::{TokenKind, TokenStream};
+use mltt_span::{FileSpan, Files};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn peek2() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "foo -- comment\n : Type");
+    let mut cursor = TokenCursor::new(Lexer::new(&files[file_id]));
+
+    assert_eq!(
+        cursor.peek().map(|token| token.kind),
+        Some(TokenKind::Identifier)
+    );
+    assert_eq!(
+        cursor.peek2().map(|token| token.kind),
+        Some(TokenKind::Colon)
+    );
+    assert_eq!(cursor.prev_span(), None);
+
+    cursor.advance();
+    assert_eq!(cursor.peek_span(), Some(FileSpan::new(file_id, 16, 17)));
+    assert_eq!(cursor.prev_span(), Some(FileSpan::new(file_id, 0, 3)));
+    assert_eq!(cursor.prev_end_span(), Some(FileSpan::new(file_id, 3, 3)));
+
+    cursor.advance();
+    cursor.advance();
+    assert_eq!(cursor.peek(), None);
+    assert_eq!(cursor.peek2(), None);
+    assert_eq!(cursor.advance(), None);
+    assert_eq!(cursor.prev_span(), Some(FileSpan::new(file_id, 18, 22)));
+}
+
+#[test]
+fn checkpoint_rewind() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "a b c d");
+    let mut cursor = TokenCursor::new(Lexer::new(&files[file_id]));
+
+    cursor.advance();
+    let checkpoint = cursor.checkpoint();
+    let tokens = cursor.by_ref().take(2).collect::<Vec<_>>();
+
+    cursor.rewind(checkpoint);
+    assert_eq!(cursor.prev_span(), Some(FileSpan::new(file_id, 0, 1)));
+    assert_eq!(cursor.by_ref().take(2).collect::<Vec<_>>(), tokens);
+    assert_eq!(cursor.peek().map(|token| token.src.slice), Some("d"));
+}
@@ -0,0 +1,137 @@
+use language_reporting::termcolor::{ColorChoice, StandardStream};
+use mltt_parse::layout::Layout;
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser::{parse_module, parse_term};
+use mltt_parse::token::TokenKind;
+use mltt_span::Files;
+use pretty_assertions::assert_eq;
+
+/// Check that the layout pass inserts the same tokens as were written in the
+/// explicit version of the source code.
+macro_rules! test_layout {
+    ($layout_src:expr, $explicit_src:expr $(,)?) => {{
+        let _ = pretty_env_logger::try_init();
+
+        let mut files = Files::new();
+        let layout_file_id = files.add("layout", $layout_src);
+        let explicit_file_id = files.add("explicit", $explicit_src);
+
+        let layout_kinds = Layout::new(Lexer::new(&files[layout_file_id]))
+            .filter(|token| !token.is_whitespace())
+            .map(|token| token.kind)
+            .collect::<Vec<TokenKind>>();
+        let explicit_kinds = Lexer::new(&files[explicit_file_id])
+            .filter(|token| !token.is_whitespace())
+            .map(|token| token.kind)
+            .collect::<Vec<TokenKind>>();
+
+        assert_eq!(layout_kinds, explicit_kinds);
+
+        if let Err(diagnostic) = parse_term(Layout::new(Lexer::new(&files[layout_file_id]))) {
+            let writer = StandardStream::stdout(ColorChoice::Always);
+            language_reporting::emit(
+                &mut writer.lock(),
+                &files,
+                &diagnostic,
+                &language_reporting::DefaultConfig,
+            )
+            .unwrap();
+            panic!("error encountered");
+        }
+    }};
+}
+
+#[test]
+fn let_items() {
+    test_layout!(
+        "let\n    x = a\n    y : Type\n    y = x\nin\n    y",
+        "let x = a; y : Type; y = x; in y",
+    );
+}
+
+#[test]
+fn let_items_single_line() {
+    test_layout!("let x = a in x", "let x = a; in x");
+}
+
+#[test]
+fn let_items_explicit_semicolons() {
+    test_layout!("let x = a;\n    y = x;\nin y", "let x = a; y = x; in y",);
+}
+
+#[test]
+fn let_items_continued() {
+    test_layout!(
+        "let x =\n        f a\n            b\n    y = x\nin y",
+        "let x = f a b; y = x; in y",
+    );
+}
+
+#[test]
+fn let_items_doc_comments() {
+    test_layout!(
+        "let\n    ||| The x\n    x = a\n    ||| The y\n    y = x\nin y",
+        "let ||| The x\n x = a; ||| The y\n y = x; in y",
+    );
+}
+
+#[test]
+fn let_items_attributes() {
+    test_layout!(
+        "let\n    #[inline]\n    x = a\n    y = x\nin y",
+        "let #[inline] x = a; y = x; in y",
+    );
+}
+
+#[test]
+fn nested_lets() {
+    test_layout!(
+        "let\n    x =\n        let y = a\n        in y\n    z = x\nin z",
+        "let x = let y = a; in y; z = x; in z",
+    );
+}
+
+#[test]
+fn case_clauses() {
+    test_layout!(
+        "case x\n    \"a\" => b\n    _ => c",
+        "case x { \"a\" => b; _ => c }",
+    );
+}
+
+#[test]
+fn case_clauses_explicit() {
+    test_layout!(
+        "case x {\n    \"a\" => b;\n    _ => c;\n}",
+        "case x { \"a\" => b; _ => c; }",
+    );
+}
+
+#[test]
+fn case_in_let() {
+    test_layout!(
+        "let\n    f x = case x\n        1 => a\n        _ => b\n    g = f\nin g",
+        "let f x = case x { 1 => a; _ => b }; g = f; in g",
+    );
+}
+
+#[test]
+fn case_in_parens() {
+    test_layout!(
+        "f (case x\n    1 => a\n    _ => b) c",
+        "f (case x { 1 => a; _ => b }) c",
+    );
+}
+
+#[test]
+fn module_option() {
+    let mut files = Files::new();
+    let layout_file_id = files.add("layout", "#![options(layout)]\nx = let\n    y = a\nin y;\n");
+    let explicit_file_id = files.add("explicit", "x = let\n    y = a\nin y;\n");
+
+    let module = parse_module(Lexer::new(&files[layout_file_id])).unwrap();
+    assert_eq!(module.attributes.len(), 1);
+    assert_eq!(module.items.len(), 1);
+
+    assert!(parse_module(Lexer::new(&files[explicit_file_id])).is_err());
+}
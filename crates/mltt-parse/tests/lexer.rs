@@ -1,6 +1,6 @@
 use mltt_concrete::SpannedString;
 use mltt_parse::lexer::Lexer;
-use mltt_parse::token::{DelimKind, Token, TokenKind};
+use mltt_parse::token::{DelimKind, Keyword, Token, TokenKind, TokenStream};
 use mltt_span::{ByteIndex, Files};
 use pretty_assertions::assert_eq;
 
@@ -38,6 +38,100 @@ fn data() {
     };
 }
 
+#[test]
+fn unicode_identifiers() {
+    test! {
+        "σ naïve Σ-type",
+        "~~               " => TokenKind::Identifier,
+        "  ~              " => TokenKind::Whitespace,
+        "   ~~~~~~        " => TokenKind::Identifier,
+        "         ~       " => TokenKind::Whitespace,
+        "          ~~~~~~~" => TokenKind::Identifier,
+    };
+}
+
+#[test]
+fn mixed_script_identifiers() {
+    test! {
+        "x日本 _αβγ1 Γ-ctx",
+        "~~~~~~~                " => TokenKind::Identifier,
+        "       ~               " => TokenKind::Whitespace,
+        "        ~~~~~~~~       " => TokenKind::Identifier,
+        "                ~      " => TokenKind::Whitespace,
+        "                 ~~~~~~" => TokenKind::Identifier,
+    };
+}
+
+#[test]
+fn raw_identifiers() {
+    test! {
+        "r#let r#foo r r#1",
+        "~~~~~            " => TokenKind::Identifier,
+        "     ~           " => TokenKind::Whitespace,
+        "      ~~~~~      " => TokenKind::Identifier,
+        "           ~     " => TokenKind::Whitespace,
+        "            ~    " => TokenKind::Identifier,
+        "             ~   " => TokenKind::Whitespace,
+        "              ~~ " => TokenKind::Error,
+        "                ~" => TokenKind::IntLiteral,
+    };
+}
+
+#[test]
+fn kebab_case_identifiers() {
+    test! {
+        "is-even a->b x--y",
+        "~~~~~~~          " => TokenKind::Identifier,
+        "       ~         " => TokenKind::Whitespace,
+        "        ~        " => TokenKind::Identifier,
+        "         ~~      " => TokenKind::RArrow,
+        "           ~     " => TokenKind::Identifier,
+        "            ~    " => TokenKind::Whitespace,
+        "             ~   " => TokenKind::Identifier,
+        "              ~~~" => TokenKind::LineComment,
+    };
+}
+
+#[test]
+fn dash_before_number() {
+    test! {
+        "x-1 y -1",
+        "~~~     " => TokenKind::Identifier,
+        "   ~    " => TokenKind::Whitespace,
+        "    ~   " => TokenKind::Identifier,
+        "     ~  " => TokenKind::Whitespace,
+        "      ~~" => TokenKind::IntLiteral,
+    };
+}
+
+#[test]
+fn lone_minus() {
+    test! {
+        "- 1 -x",
+        "~     " => TokenKind::Symbol,
+        " ~    " => TokenKind::Whitespace,
+        "  ~   " => TokenKind::IntLiteral,
+        "   ~  " => TokenKind::Whitespace,
+        "    ~ " => TokenKind::Symbol,
+        "     ~" => TokenKind::Identifier,
+    };
+}
+
+#[test]
+fn primed_identifiers() {
+    test! {
+        "x' f'' g-h' x'y",
+        "~~             " => TokenKind::Identifier,
+        "  ~            " => TokenKind::Whitespace,
+        "   ~~~         " => TokenKind::Identifier,
+        "      ~        " => TokenKind::Whitespace,
+        "       ~~~~    " => TokenKind::Identifier,
+        "           ~   " => TokenKind::Whitespace,
+        "            ~~ " => TokenKind::Identifier,
+        "              ~" => TokenKind::Identifier,
+    };
+}
+
 #[test]
 fn comment() {
     test! {
@@ -125,6 +219,16 @@ fn dec_literal() {
     };
 }
 
+#[test]
+fn dec_literal_range() {
+    test! {
+        "0..12",
+        "~    " => TokenKind::IntLiteral,
+        " ~~  " => TokenKind::Symbol,
+        "   ~~" => TokenKind::IntLiteral,
+    };
+}
+
 #[test]
 fn hex_literal() {
     test! {
@@ -166,31 +270,79 @@ fn keywords() {
         "~~                                                             " => TokenKind::Whitespace,
         "  ~~                                                           " => TokenKind::Identifier,
         "    ~                                                          " => TokenKind::Whitespace,
-        "     ~~~~                                                      " => TokenKind::Keyword,
+        "     ~~~~                                                      " => TokenKind::Keyword(Keyword::Case),
         "         ~                                                     " => TokenKind::Whitespace,
-        "          ~~~~                                                 " => TokenKind::Keyword,
+        "          ~~~~                                                 " => TokenKind::Keyword(Keyword::Else),
         "              ~                                                " => TokenKind::Whitespace,
-        "               ~~                                              " => TokenKind::Keyword,
+        "               ~~                                              " => TokenKind::Keyword(Keyword::If),
         "                 ~                                             " => TokenKind::Whitespace,
         "                  ~~~~~~                                       " => TokenKind::Identifier,
         "                        ~                                      " => TokenKind::Whitespace,
-        "                         ~~                                    " => TokenKind::Keyword,
+        "                         ~~                                    " => TokenKind::Keyword(Keyword::In),
         "                           ~                                   " => TokenKind::Whitespace,
-        "                            ~~~                                " => TokenKind::Keyword,
+        "                            ~~~                                " => TokenKind::Keyword(Keyword::Let),
         "                               ~                               " => TokenKind::Whitespace,
-        "                                ~~~~~~                         " => TokenKind::Keyword,
+        "                                ~~~~~~                         " => TokenKind::Keyword(Keyword::RecordIntro),
         "                                      ~                        " => TokenKind::Whitespace,
-        "                                       ~~~~~~                  " => TokenKind::Keyword,
+        "                                       ~~~~~~                  " => TokenKind::Keyword(Keyword::RecordType),
         "                                             ~                 " => TokenKind::Whitespace,
-        "                                              ~~~~             " => TokenKind::Keyword,
+        "                                              ~~~~             " => TokenKind::Keyword(Keyword::Then),
         "                                                  ~            " => TokenKind::Whitespace,
-        "                                                   ~~~~        " => TokenKind::Keyword,
+        "                                                   ~~~~        " => TokenKind::Keyword(Keyword::Universe),
         "                                                       ~       " => TokenKind::Whitespace,
         "                                                        ~~~~~  " => TokenKind::Identifier,
         "                                                             ~~" => TokenKind::Whitespace,
     };
 }
 
+#[test]
+fn section_keywords() {
+    test! {
+        "section end",
+        "~~~~~~~    " => TokenKind::Keyword(Keyword::Section),
+        "       ~   " => TokenKind::Whitespace,
+        "        ~~~" => TokenKind::Keyword(Keyword::End),
+    };
+}
+
+#[test]
+fn namespace_keywords() {
+    test! {
+        "namespace open end",
+        "~~~~~~~~~         " => TokenKind::Keyword(Keyword::Namespace),
+        "         ~        " => TokenKind::Whitespace,
+        "          ~~~~    " => TokenKind::Keyword(Keyword::Open),
+        "              ~   " => TokenKind::Whitespace,
+        "               ~~~" => TokenKind::Keyword(Keyword::End),
+    };
+}
+
+#[test]
+fn with_keyword() {
+    test! {
+        "with",
+        "~~~~" => TokenKind::Keyword(Keyword::With),
+    };
+}
+
+#[test]
+fn pattern_keyword() {
+    test! {
+        "pattern",
+        "~~~~~~~" => TokenKind::Keyword(Keyword::Pattern),
+    };
+}
+
+#[test]
+fn termination_by_keyword() {
+    test! {
+        "termination-by termination",
+        "~~~~~~~~~~~~~~            " => TokenKind::Keyword(Keyword::TerminationBy),
+        "              ~           " => TokenKind::Whitespace,
+        "               ~~~~~~~~~~~" => TokenKind::Identifier,
+    };
+}
+
 #[test]
 fn symbols() {
     test! {
@@ -219,6 +371,17 @@ fn symbols() {
     }
 }
 
+#[test]
+fn attribute() {
+    test! {
+        "#[inline]",
+        "~        " => TokenKind::Hash,
+        " ~       " => TokenKind::Open(DelimKind::Bracket),
+        "  ~~~~~~ " => TokenKind::Identifier,
+        "        ~" => TokenKind::Close(DelimKind::Bracket),
+    }
+}
+
 #[test]
 fn delimiters() {
     test! {
@@ -238,3 +401,27 @@ fn delimiters() {
         "            ~" => TokenKind::Whitespace,
     }
 }
+
+#[test]
+fn keyword_names() {
+    for &keyword in Keyword::ALL {
+        assert_eq!(Keyword::from_identifier(keyword.as_str()), Some(keyword));
+    }
+}
+
+#[test]
+fn checkpoint_rewind() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "foo 'bar' baz");
+    let mut lexer = Lexer::new(&files[file_id]);
+
+    lexer.next();
+    let checkpoint = lexer.checkpoint();
+    let tokens = lexer.by_ref().take(3).collect::<Vec<_>>();
+    assert_eq!(lexer.diagnostics().len(), 1);
+
+    lexer.rewind(checkpoint);
+    assert!(lexer.diagnostics().is_empty());
+    assert_eq!(lexer.by_ref().take(3).collect::<Vec<_>>(), tokens);
+    assert_eq!(lexer.diagnostics().len(), 1);
+}
@@ -1,10 +1,11 @@
 use language_reporting::termcolor::{ColorChoice, StandardStream};
 use mltt_concrete::{
-    Arg, Definition, IntroParam, Item, LiteralKind, Pattern, RecordIntroField, RecordTypeField,
-    SpannedString, Term, TypeParam,
+    Arg, Attribute, Declaration, Definition, Imports, IntroParam, Item, LiteralKind, Namespace,
+    Open, Pattern, PatternSynonym, RecordIntroField, RecordTypeField, Section, SpannedString, Term,
+    TypeParam,
 };
 use mltt_parse::lexer::Lexer;
-use mltt_parse::parser::parse_term;
+use mltt_parse::parser::{parse_term, parse_term_and_type};
 use mltt_span::FileSpan;
 use mltt_span::Files;
 use pretty_assertions::assert_eq;
@@ -44,11 +45,25 @@ fn var() {
     )));
 }
 
+#[test]
+fn raw_var() {
+    test_term!("r#let", |file_id| Term::Var(SpannedString::new(
+        file_id, 2, "let",
+    )));
+}
+
 #[test]
 fn hole() {
     test_term!("?", |file_id| Term::Hole(FileSpan::new(file_id, 0, 1)));
 }
 
+#[test]
+fn placeholder() {
+    test_term!("_", |file_id| Term::Placeholder(FileSpan::new(
+        file_id, 0, 1
+    )));
+}
+
 #[test]
 fn string_literal() {
     test_term!("\"value\"", |file_id| Term::LiteralIntro(
@@ -73,6 +88,14 @@ fn int_literal() {
     ));
 }
 
+#[test]
+fn neg_int_literal() {
+    test_term!("-42", |file_id| Term::LiteralIntro(
+        LiteralKind::Int,
+        SpannedString::new(file_id, 0, "-42"),
+    ));
+}
+
 #[test]
 fn float_literal() {
     test_term!("0.3_46e_23", |file_id| Term::LiteralIntro(
@@ -87,15 +110,338 @@ fn let_expr() {
         FileSpan::new(file_id, 0, 22),
         vec![Item::Definition(Definition {
             docs: Vec::new(),
+            attributes: Vec::new(),
             label: SpannedString::new(file_id, 4, "var"),
             params: Vec::new(),
             body_ty: None,
             body: Term::Universe(FileSpan::new(file_id, 10, 14), None),
+            termination_by: None,
         })],
         Box::new(Term::Var(SpannedString::new(file_id, 19, "var"))),
     ),);
 }
 
+#[test]
+fn let_expr_termination_by() {
+    test_term!("let f x = x termination-by p; in f", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 34),
+        vec![Item::Definition(Definition {
+            docs: Vec::new(),
+            attributes: Vec::new(),
+            label: SpannedString::new(file_id, 4, "f"),
+            params: vec![IntroParam::Explicit(Pattern::Var(SpannedString::new(
+                file_id, 6, "x",
+            )))],
+            body_ty: None,
+            body: Term::Var(SpannedString::new(file_id, 10, "x")),
+            termination_by: Some(Term::Var(SpannedString::new(file_id, 27, "p"))),
+        })],
+        Box::new(Term::Var(SpannedString::new(file_id, 33, "f"))),
+    ),);
+}
+
+#[test]
+fn let_expr_attributes() {
+    test_term!("let #[inline] #[foo(a, b)] var = Type; in var", |file_id| {
+        Term::Let(
+            FileSpan::new(file_id, 0, 45),
+            vec![Item::Definition(Definition {
+                docs: Vec::new(),
+                attributes: vec![
+                    Attribute {
+                        span: FileSpan::new(file_id, 4, 13),
+                        name: SpannedString::new(file_id, 6, "inline"),
+                        args: Vec::new(),
+                    },
+                    Attribute {
+                        span: FileSpan::new(file_id, 14, 26),
+                        name: SpannedString::new(file_id, 16, "foo"),
+                        args: vec![
+                            SpannedString::new(file_id, 20, "a"),
+                            SpannedString::new(file_id, 23, "b"),
+                        ],
+                    },
+                ],
+                label: SpannedString::new(file_id, 27, "var"),
+                params: Vec::new(),
+                body_ty: None,
+                body: Term::Universe(FileSpan::new(file_id, 33, 37), None),
+                termination_by: None,
+            })],
+            Box::new(Term::Var(SpannedString::new(file_id, 42, "var"))),
+        )
+    });
+}
+
+#[test]
+fn let_expr_section() {
+    test_term!("let section (A : Type) id : A -> A; end in id", |file_id| {
+        Term::Let(
+            FileSpan::new(file_id, 0, 45),
+            vec![Item::Section(Section {
+                span: FileSpan::new(file_id, 4, 39),
+                params: vec![TypeParam::Explicit(
+                    FileSpan::new(file_id, 12, 22),
+                    Vec::new(),
+                    vec![SpannedString::new(file_id, 13, "A")],
+                    Term::Universe(FileSpan::new(file_id, 17, 21), None),
+                )],
+                items: vec![Item::Declaration(Declaration {
+                    docs: Vec::new(),
+                    attributes: Vec::new(),
+                    label: SpannedString::new(file_id, 23, "id"),
+                    body_ty: Term::FunArrowType(
+                        Box::new(Term::Var(SpannedString::new(file_id, 28, "A"))),
+                        Box::new(Term::Var(SpannedString::new(file_id, 33, "A"))),
+                    ),
+                })],
+            })],
+            Box::new(Term::Var(SpannedString::new(file_id, 43, "id"))),
+        )
+    });
+}
+
+#[test]
+fn let_expr_namespace() {
+    test_term!(
+        "let namespace Foo x = Type; end open Foo; in x",
+        |file_id| {
+            Term::Let(
+                FileSpan::new(file_id, 0, 46),
+                vec![
+                    Item::Namespace(Namespace {
+                        span: FileSpan::new(file_id, 4, 31),
+                        name: SpannedString::new(file_id, 14, "Foo"),
+                        items: vec![Item::Definition(Definition {
+                            docs: Vec::new(),
+                            attributes: Vec::new(),
+                            label: SpannedString::new(file_id, 18, "x"),
+                            params: Vec::new(),
+                            body_ty: None,
+                            body: Term::Universe(FileSpan::new(file_id, 22, 26), None),
+                            termination_by: None,
+                        })],
+                    }),
+                    Item::Open(Open {
+                        span: FileSpan::new(file_id, 32, 41),
+                        is_public: false,
+                        name: SpannedString::new(file_id, 37, "Foo"),
+                        imports: Imports::All,
+                    }),
+                ],
+                Box::new(Term::Var(SpannedString::new(file_id, 45, "x"))),
+            )
+        }
+    );
+}
+
+#[test]
+fn let_expr_qualified_labels() {
+    test_term!("let A.x : Type; A.x = Type; in A.x", |file_id| {
+        Term::Let(
+            FileSpan::new(file_id, 0, 34),
+            vec![Item::Namespace(Namespace {
+                span: FileSpan::new(file_id, 4, 26),
+                name: SpannedString::new(file_id, 4, "A"),
+                items: vec![
+                    Item::Declaration(Declaration {
+                        docs: Vec::new(),
+                        attributes: Vec::new(),
+                        label: SpannedString::new(file_id, 6, "x"),
+                        body_ty: Term::Universe(FileSpan::new(file_id, 10, 14), None),
+                    }),
+                    Item::Definition(Definition {
+                        docs: Vec::new(),
+                        attributes: Vec::new(),
+                        label: SpannedString::new(file_id, 18, "x"),
+                        params: Vec::new(),
+                        body_ty: None,
+                        body: Term::Universe(FileSpan::new(file_id, 22, 26), None),
+                        termination_by: None,
+                    }),
+                ],
+            })],
+            Box::new(Term::RecordElim(
+                Box::new(Term::Var(SpannedString::new(file_id, 31, "A"))),
+                SpannedString::new(file_id, 33, "x"),
+            )),
+        )
+    });
+}
+
+#[test]
+fn let_expr_open_only() {
+    test_term!("let open Foo (a, b); in a", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 25),
+        vec![Item::Open(Open {
+            span: FileSpan::new(file_id, 4, 20),
+            is_public: false,
+            name: SpannedString::new(file_id, 9, "Foo"),
+            imports: Imports::Only(vec![
+                SpannedString::new(file_id, 14, "a"),
+                SpannedString::new(file_id, 17, "b"),
+            ]),
+        })],
+        Box::new(Term::Var(SpannedString::new(file_id, 24, "a"))),
+    ));
+}
+
+#[test]
+fn let_expr_open_hiding() {
+    test_term!("let open Foo hiding (a); in b", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 29),
+        vec![Item::Open(Open {
+            span: FileSpan::new(file_id, 4, 24),
+            is_public: false,
+            name: SpannedString::new(file_id, 9, "Foo"),
+            imports: Imports::Hiding(vec![SpannedString::new(file_id, 21, "a")]),
+        })],
+        Box::new(Term::Var(SpannedString::new(file_id, 28, "b"))),
+    ));
+}
+
+#[test]
+fn let_expr_open_alias() {
+    test_term!("let open Foo as F; in F.a", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 25),
+        vec![Item::Open(Open {
+            span: FileSpan::new(file_id, 4, 18),
+            is_public: false,
+            name: SpannedString::new(file_id, 9, "Foo"),
+            imports: Imports::Alias(SpannedString::new(file_id, 16, "F")),
+        })],
+        Box::new(Term::RecordElim(
+            Box::new(Term::Var(SpannedString::new(file_id, 22, "F"))),
+            SpannedString::new(file_id, 24, "a"),
+        )),
+    ));
+}
+
+#[test]
+fn let_expr_public_open() {
+    test_term!("let public open Foo; in x", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 25),
+        vec![Item::Open(Open {
+            span: FileSpan::new(file_id, 4, 20),
+            is_public: true,
+            name: SpannedString::new(file_id, 16, "Foo"),
+            imports: Imports::All,
+        })],
+        Box::new(Term::Var(SpannedString::new(file_id, 24, "x"))),
+    ));
+}
+
+#[test]
+fn let_expr_pattern_synonym() {
+    test_term!("let pattern nul = (0 : U8); in nul", |file_id| Term::Let(
+        FileSpan::new(file_id, 0, 34),
+        vec![Item::PatternSynonym(PatternSynonym {
+            span: FileSpan::new(file_id, 4, 27),
+            docs: Vec::new(),
+            label: SpannedString::new(file_id, 12, "nul"),
+            pattern: Pattern::Ann(
+                FileSpan::new(file_id, 18, 26),
+                Box::new(Pattern::LiteralIntro(
+                    LiteralKind::Int,
+                    SpannedString::new(file_id, 19, "0"),
+                )),
+                Box::new(Term::Var(SpannedString::new(file_id, 23, "U8"))),
+            ),
+        })],
+        Box::new(Term::Var(SpannedString::new(file_id, 31, "nul"))),
+    ));
+}
+
+#[test]
+fn case_expr_guard() {
+    test_term!("case x { y | f y => y; z => x }", |file_id| Term::Case(
+        FileSpan::new(file_id, 0, 31),
+        Box::new(Term::Var(SpannedString::new(file_id, 5, "x"))),
+        vec![
+            (
+                Pattern::Var(SpannedString::new(file_id, 9, "y")),
+                Some(Term::FunElim(
+                    Box::new(Term::Var(SpannedString::new(file_id, 13, "f"))),
+                    vec![Arg::Explicit(Term::Var(SpannedString::new(
+                        file_id, 15, "y",
+                    )))],
+                )),
+                Term::Var(SpannedString::new(file_id, 20, "y")),
+            ),
+            (
+                Pattern::Var(SpannedString::new(file_id, 23, "z")),
+                None,
+                Term::Var(SpannedString::new(file_id, 28, "x")),
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn case_expr_range_prefix() {
+    test_term!(
+        r#"case x { 'a'..'z' => x; "ab" ++ _ => x; y => y }"#,
+        |file_id| {
+            Term::Case(
+                FileSpan::new(file_id, 0, 48),
+                Box::new(Term::Var(SpannedString::new(file_id, 5, "x"))),
+                vec![
+                    (
+                        Pattern::Range(
+                            Box::new(Pattern::LiteralIntro(
+                                LiteralKind::Char,
+                                SpannedString::new(file_id, 9, "'a'"),
+                            )),
+                            Box::new(Pattern::LiteralIntro(
+                                LiteralKind::Char,
+                                SpannedString::new(file_id, 14, "'z'"),
+                            )),
+                        ),
+                        None,
+                        Term::Var(SpannedString::new(file_id, 21, "x")),
+                    ),
+                    (
+                        Pattern::Prefix(
+                            FileSpan::new(file_id, 24, 33),
+                            Box::new(Pattern::LiteralIntro(
+                                LiteralKind::String,
+                                SpannedString::new(file_id, 24, "\"ab\""),
+                            )),
+                        ),
+                        None,
+                        Term::Var(SpannedString::new(file_id, 37, "x")),
+                    ),
+                    (
+                        Pattern::Var(SpannedString::new(file_id, 40, "y")),
+                        None,
+                        Term::Var(SpannedString::new(file_id, 45, "y")),
+                    ),
+                ],
+            )
+        }
+    );
+}
+
+#[test]
+fn with_expr() {
+    test_term!("with x { 1 => y; z => z }", |file_id| Term::With(
+        FileSpan::new(file_id, 0, 25),
+        Box::new(Term::Var(SpannedString::new(file_id, 5, "x"))),
+        vec![
+            (
+                Pattern::LiteralIntro(LiteralKind::Int, SpannedString::new(file_id, 9, "1")),
+                None,
+                Term::Var(SpannedString::new(file_id, 14, "y")),
+            ),
+            (
+                Pattern::Var(SpannedString::new(file_id, 17, "z")),
+                None,
+                Term::Var(SpannedString::new(file_id, 22, "z")),
+            ),
+        ],
+    ));
+}
+
 #[test]
 fn if_expr() {
     test_term!("if foo then bar else baz", |file_id| Term::If(
@@ -123,6 +469,7 @@ fn fun_ty() {
             vec![
                 TypeParam::Explicit(
                     FileSpan::new(file_id, 4, 16),
+                    Vec::new(),
                     vec![
                         SpannedString::new(file_id, 5, "x"),
                         SpannedString::new(file_id, 7, "y"),
@@ -131,6 +478,7 @@ fn fun_ty() {
                 ),
                 TypeParam::Explicit(
                     FileSpan::new(file_id, 17, 27),
+                    Vec::new(),
                     vec![SpannedString::new(file_id, 18, "z")],
                     Term::Universe(FileSpan::new(file_id, 22, 26), None),
                 ),
@@ -147,6 +495,7 @@ fn fun_ty_implicit() {
         vec![
             TypeParam::Implicit(
                 FileSpan::new(file_id, 4, 16),
+                Vec::new(),
                 vec![
                     SpannedString::new(file_id, 5, "x"),
                     SpannedString::new(file_id, 7, "y"),
@@ -155,6 +504,7 @@ fn fun_ty_implicit() {
             ),
             TypeParam::Implicit(
                 FileSpan::new(file_id, 17, 20),
+                Vec::new(),
                 vec![SpannedString::new(file_id, 18, "z")],
                 None,
             ),
@@ -169,6 +519,7 @@ fn fun_ty_instance() {
         FileSpan::new(file_id, 0, 20),
         vec![TypeParam::Instance(
             FileSpan::new(file_id, 4, 15),
+            Vec::new(),
             SpannedString::new(file_id, 6, "A"),
             Term::Var(SpannedString::new(file_id, 10, "Foo")),
         )],
@@ -176,6 +527,23 @@ fn fun_ty_instance() {
     ));
 }
 
+#[test]
+fn fun_ty_docs() {
+    test_term!(
+        "Fun ||| The type\n(A : Type) -> A",
+        |file_id| Term::FunType(
+            FileSpan::new(file_id, 0, 32),
+            vec![TypeParam::Explicit(
+                FileSpan::new(file_id, 17, 27),
+                vec![SpannedString::new(file_id, 4, "||| The type")],
+                vec![SpannedString::new(file_id, 18, "A")],
+                Term::Universe(FileSpan::new(file_id, 22, 26), None),
+            )],
+            Box::new(Term::Var(SpannedString::new(file_id, 31, "A"))),
+        )
+    );
+}
+
 #[test]
 fn fun_arrow_type() {
     test_term!("Foo -> Bar", |file_id| Term::FunArrowType(
@@ -289,6 +657,26 @@ fn fun_intro_multi_params_instance() {
     ));
 }
 
+#[test]
+fn fun_intro_annotated() {
+    test_term!(r"\(x : A) (y : B) => x", |file_id| Term::FunIntro(
+        FileSpan::new(file_id, 0, 21),
+        vec![
+            IntroParam::Explicit(Pattern::Ann(
+                FileSpan::new(file_id, 1, 8),
+                Box::new(Pattern::Var(SpannedString::new(file_id, 2, "x"))),
+                Box::new(Term::Var(SpannedString::new(file_id, 6, "A"))),
+            )),
+            IntroParam::Explicit(Pattern::Ann(
+                FileSpan::new(file_id, 9, 16),
+                Box::new(Pattern::Var(SpannedString::new(file_id, 10, "y"))),
+                Box::new(Term::Var(SpannedString::new(file_id, 14, "B"))),
+            )),
+        ],
+        Box::new(Term::Var(SpannedString::new(file_id, 20, "x"))),
+    ));
+}
+
 #[test]
 fn fun_app_1() {
     test_term!(r"foo arg", |file_id| Term::FunElim(
@@ -388,6 +776,18 @@ fn record_type() {
     ));
 }
 
+#[test]
+fn record_type_raw_label() {
+    test_term!("Record { r#in : Type }", |file_id| Term::RecordType(
+        FileSpan::new(file_id, 0, 22),
+        vec![RecordTypeField {
+            docs: Vec::new(),
+            label: SpannedString::new(file_id, 11, "in"),
+            ann: Term::Universe(FileSpan::new(file_id, 16, 20), None),
+        }]
+    ));
+}
+
 #[test]
 fn record_type_trailing_semicolon() {
     test_term!(
@@ -483,6 +883,63 @@ fn record_intro_trailing_semicolon() {
     });
 }
 
+#[test]
+fn array_intro() {
+    test_term!("[x, y, ]", |file_id| Term::ArrayIntro(
+        FileSpan::new(file_id, 0, 8),
+        vec![
+            Term::Var(SpannedString::new(file_id, 1, "x")),
+            Term::Var(SpannedString::new(file_id, 4, "y")),
+        ],
+    ));
+}
+
+#[test]
+fn array_intro_empty() {
+    test_term!("[]", |file_id| Term::ArrayIntro(
+        FileSpan::new(file_id, 0, 2),
+        Vec::new(),
+    ));
+}
+
+#[test]
+fn sigma_ty() {
+    test_term!("Σ (x : Type) -> x", |file_id| Term::SigmaType(
+        FileSpan::new(file_id, 0, 18),
+        SpannedString::new(file_id, 4, "x"),
+        Box::new(Term::Universe(FileSpan::new(file_id, 8, 12), None)),
+        Box::new(Term::Var(SpannedString::new(file_id, 17, "x"))),
+    ));
+}
+
+#[test]
+fn sigma_ty_missing_param_ty() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "Σ (x)");
+
+    assert!(parse_term(Lexer::new(&files[file_id])).is_err());
+}
+
+#[test]
+fn sigma_raw_var() {
+    test_term!("r#Σ (x)", |file_id| Term::FunElim(
+        Box::new(Term::Var(SpannedString::new(file_id, 2, "Σ"))),
+        vec![Arg::Explicit(Term::Parens(
+            FileSpan::new(file_id, 5, 8),
+            Box::new(Term::Var(SpannedString::new(file_id, 6, "x"))),
+        ))],
+    ));
+}
+
+#[test]
+fn pair_intro() {
+    test_term!("(x, y)", |file_id| Term::PairIntro(
+        FileSpan::new(file_id, 0, 6),
+        Box::new(Term::Var(SpannedString::new(file_id, 1, "x"))),
+        Box::new(Term::Var(SpannedString::new(file_id, 4, "y"))),
+    ));
+}
+
 #[test]
 fn record_proj() {
     test_term!("foo.bar", |file_id| Term::RecordElim(
@@ -530,6 +987,22 @@ fn ann() {
     ));
 }
 
+#[test]
+fn term_and_type() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "foo Bar -> Baz");
+    let (term, ty) = parse_term_and_type(Lexer::new(&files[file_id])).unwrap();
+
+    assert_eq!(term, Term::Var(SpannedString::new(file_id, 0, "foo")));
+    assert_eq!(
+        ty,
+        Term::FunArrowType(
+            Box::new(Term::Var(SpannedString::new(file_id, 4, "Bar"))),
+            Box::new(Term::Var(SpannedString::new(file_id, 11, "Baz"))),
+        ),
+    );
+}
+
 #[test]
 fn universe() {
     test_term!("Type", |file_id| Term::Universe(
@@ -553,3 +1026,35 @@ fn universe_level_23() {
         Some(SpannedString::new(file_id, 5, "23")),
     ));
 }
+
+#[test]
+fn pretty_fun_elim_nested_arg() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "f g x");
+    let term = Term::FunElim(
+        Box::new(Term::Var(SpannedString::new(file_id, 0, "f"))),
+        vec![Arg::Explicit(Term::FunElim(
+            Box::new(Term::Var(SpannedString::new(file_id, 2, "g"))),
+            vec![Arg::Explicit(Term::Var(SpannedString::new(
+                file_id, 4, "x",
+            )))],
+        ))],
+    );
+
+    assert_eq!(term.to_string(), "f (g x)");
+}
+
+#[test]
+fn pretty_fun_arrow_type_nested_param() {
+    let mut files = Files::new();
+    let file_id = files.add("test", "A B C");
+    let term = Term::FunArrowType(
+        Box::new(Term::FunArrowType(
+            Box::new(Term::Var(SpannedString::new(file_id, 0, "A"))),
+            Box::new(Term::Var(SpannedString::new(file_id, 2, "B"))),
+        )),
+        Box::new(Term::Var(SpannedString::new(file_id, 4, "C"))),
+    );
+
+    assert_eq!(term.to_string(), "(A -> B) -> C");
+}
@@ -27,6 +27,10 @@ pub struct File {
     contents: String,
     /// The starting byte indices in the source code.
     line_starts: Vec<ByteIndex>,
+    /// Whether the file is virtual, ie. it has no corresponding file on disk.
+    is_virtual: bool,
+    /// The number of times the contents of the file have been updated.
+    version: usize,
 }
 
 impl File {
@@ -50,61 +54,134 @@ impl File {
         &self.line_starts
     }
 
+    /// Whether the file is virtual, ie. it has no corresponding file on disk.
+    /// This is the case for REPL input and unsaved documents.
+    pub fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+
+    /// The number of times the contents of the file have been updated.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
     /// Get the span of the source of this file.
     pub fn span(&self) -> FileSpan {
         Span::from_str(self.id(), self.contents())
     }
 }
 
+/// Compute the starting byte indices of the lines in the source code.
+fn line_starts(contents: &str) -> Vec<ByteIndex> {
+    std::iter::once(0)
+        .chain(contents.match_indices('\n').map(|(i, _)| i + 1))
+        .chain(std::iter::once(contents.len()))
+        .map(ByteIndex::from)
+        .collect()
+}
+
 /// A database of source files.
 #[derive(Debug, Clone)]
 pub struct Files {
     files: Vec<File>,
+    /// The number of columns between tab stops.
+    tab_width: usize,
+}
+
+impl Default for Files {
+    fn default() -> Files {
+        Files::new()
+    }
 }
 
 impl Files {
+    /// The default number of columns between tab stops. Tabs are treated as
+    /// a single column, like any other character.
+    pub const DEFAULT_TAB_WIDTH: usize = 1;
+
     /// Create a new, empty database.
     pub fn new() -> Files {
-        Files { files: Vec::new() }
+        Files {
+            files: Vec::new(),
+            tab_width: Files::DEFAULT_TAB_WIDTH,
+        }
+    }
+
+    /// The number of columns between tab stops, used when converting between
+    /// byte indices and columns.
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Set the number of columns between tab stops, used when converting
+    /// between byte indices and columns.
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width;
     }
 
     /// Add a file to the database, returning the handle that can be used to refer to it again.
     pub fn add(&mut self, name: impl Into<String>, contents: impl Into<String>) -> FileId {
-        let file_id = FileId(self.files.len());
-        let contents = contents.into();
+        self.add_file(name.into(), contents.into(), false)
+    }
 
-        // Pre-compute the line starting positions
-        let line_starts = std::iter::once(0)
-            .chain(contents.match_indices('\n').map(|(i, _)| i + 1))
-            .chain(std::iter::once(contents.len()))
-            .map(ByteIndex::from)
-            .collect();
+    /// Add a virtual file to the database, returning the handle that can be
+    /// used to refer to it again. Virtual files have no corresponding file on
+    /// disk, for example REPL input or unsaved documents in an editor.
+    pub fn add_virtual(&mut self, name: impl Into<String>, contents: impl Into<String>) -> FileId {
+        self.add_file(name.into(), contents.into(), true)
+    }
+
+    fn add_file(&mut self, name: String, contents: String, is_virtual: bool) -> FileId {
+        let file_id = FileId(self.files.len());
 
-        // Add the file to the database
         self.files.push(File {
             id: file_id,
-            name: name.into(),
+            name,
+            line_starts: line_starts(&contents),
             contents,
-            line_starts,
+            is_virtual,
+            version: 0,
         });
 
         file_id
     }
 
+    /// Replace the contents of a file in the database, returning the previous
+    /// contents.
+    ///
+    /// This invalidates any spans that point into the previous contents of
+    /// the file - they must not be used to look up the new contents, as they
+    /// may now refer to different source code, or be out of bounds. The
+    /// version of the file is incremented, which can be used to detect
+    /// diagnostics and other data that were derived from stale contents.
+    pub fn update(&mut self, file_id: FileId, contents: impl Into<String>) -> String {
+        let file = &mut self.files[file_id.0];
+        let contents = contents.into();
+
+        file.line_starts = line_starts(&contents);
+        file.version += 1;
+        std::mem::replace(&mut file.contents, contents)
+    }
+
+    /// Get the byte index of a line and column in a file, using the
+    /// pre-computed line starts to avoid scanning the preceding lines.
+    ///
+    /// Columns past the end of the line are clamped to the end of the line,
+    /// before its line ending.
     pub fn byte_index(
         &self,
         file_id: FileId,
         line: impl Into<LineIndex>,
         column: impl Into<ColumnIndex>,
     ) -> Option<ByteIndex> {
-        let file = &self[file_id];
-        let line = line.into();
-        let column = column.into();
-        let line_start = *file.line_starts().get(line.to_usize())?;
+        let line_span = self.line_span(file_id, line)?;
+        let line_src = self.source(line_span)?;
+        let line_src = line_src.trim_end_matches('\n').trim_end_matches('\r');
 
-        Some(column.to_byte_index(file.contents(), line_start))
+        Some(line_span.start() + column.into().to_byte_size(line_src, self.tab_width))
     }
 
+    /// Get the span of a line in a file, including its line ending.
     pub fn line_span(&self, file_id: FileId, line: impl Into<LineIndex>) -> Option<FileSpan> {
         let file = &self[file_id];
         let line = line.into();
@@ -114,10 +191,14 @@ impl Files {
         Some(Span::new(file_id, line_start, next_line_start))
     }
 
+    /// Get the line and column location of a byte index in a file, using a
+    /// binary search over the pre-computed line starts.
     pub fn location(&self, file_id: FileId, byte: impl Into<ByteIndex>) -> Option<Location> {
         let file = &self[file_id];
         let byte = byte.into();
-        let line_starts = file.line_starts();
+        // Skip the sentinel at the end of the line starts, so that the end of
+        // the file is located on the last line
+        let line_starts = &file.line_starts()[..file.line_starts().len() - 1];
         match line_starts.binary_search(&byte) {
             // Found the start of a line
             Ok(line) => Some(Location {
@@ -129,7 +210,8 @@ impl Files {
             Err(next_line) => {
                 let line = LineIndex::from(next_line - 1);
                 let line_start = line_starts[line.to_usize()];
-                let column = ColumnIndex::from_str(file.contents(), line_start, byte)?;
+                let column =
+                    ColumnIndex::from_str(file.contents(), line_start, byte, self.tab_width)?;
 
                 Some(Location { line, column, byte })
             },
@@ -154,7 +236,12 @@ impl language_reporting::ReportingFiles for Files {
     }
 
     fn file_name(&self, file_id: FileId) -> language_reporting::FileName {
-        language_reporting::FileName::Verbatim(self[file_id].name.clone())
+        let file = &self[file_id];
+        if file.is_virtual {
+            language_reporting::FileName::Virtual(file.name.clone().into())
+        } else {
+            language_reporting::FileName::Verbatim(file.name.clone())
+        }
     }
 
     fn byte_span(&self, file_id: FileId, from_index: usize, to_index: usize) -> Option<FileSpan> {
@@ -255,9 +342,124 @@ mod test {
             }),
         );
 
+        assert_eq!(
+            files.location(file_id, 13),
+            Some(Location {
+                line: LineIndex::from(3),
+                column: ColumnIndex::from(3),
+                byte: ByteIndex::from(13),
+            }),
+        );
+
         assert_eq!(files.location(file_id, 100), None);
     }
 
+    #[test]
+    fn byte_index() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "foo\nbar\r\n\nbaz");
+
+        let byte_indices = [(0, 0), (0, 3), (1, 0), (1, 2), (1, 100), (3, 1), (5, 0)]
+            .iter()
+            .map(|&(line, column)| files.byte_index(file_id, line, column))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            byte_indices,
+            [
+                Some(ByteIndex::from(0)),
+                Some(ByteIndex::from(3)),
+                Some(ByteIndex::from(4)),
+                Some(ByteIndex::from(6)),
+                Some(ByteIndex::from(7)),
+                Some(ByteIndex::from(11)),
+                None,
+            ],
+        );
+    }
+
+    #[test]
+    fn location_unicode() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "λ x → e\nfoo");
+
+        let columns = [0, 2, 3, 5, 6, 8, 9]
+            .iter()
+            .map(|&byte| {
+                files
+                    .location(file_id, byte)
+                    .map(|location| location.column)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            columns,
+            [
+                Some(ColumnIndex::from(0)),
+                Some(ColumnIndex::from(1)),
+                Some(ColumnIndex::from(2)),
+                Some(ColumnIndex::from(4)),
+                None,
+                Some(ColumnIndex::from(5)),
+                Some(ColumnIndex::from(6)),
+            ],
+        );
+
+        assert_eq!(files.byte_index(file_id, 0, 4), Some(ByteIndex::from(5)));
+        assert_eq!(files.byte_index(file_id, 0, 6), Some(ByteIndex::from(9)));
+    }
+
+    #[test]
+    fn location_tabs() {
+        let mut files = Files::new();
+        files.set_tab_width(4);
+        let file_id = files.add("test", "\tfoo\t\tbar");
+
+        let columns = [0, 1, 4, 5, 6]
+            .iter()
+            .map(|&byte| {
+                files
+                    .location(file_id, byte)
+                    .map(|location| location.column)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            columns,
+            [
+                Some(ColumnIndex::from(0)),
+                Some(ColumnIndex::from(4)),
+                Some(ColumnIndex::from(7)),
+                Some(ColumnIndex::from(8)),
+                Some(ColumnIndex::from(12)),
+            ],
+        );
+
+        assert_eq!(files.byte_index(file_id, 0, 2), Some(ByteIndex::from(0)));
+        assert_eq!(files.byte_index(file_id, 0, 4), Some(ByteIndex::from(1)));
+        assert_eq!(files.byte_index(file_id, 0, 12), Some(ByteIndex::from(6)));
+    }
+
+    #[test]
+    fn update() {
+        let mut files = Files::new();
+        let file_id = files.add_virtual("test", "foo\nbar");
+
+        assert!(files[file_id].is_virtual());
+        assert_eq!(files[file_id].version(), 0);
+
+        let old_contents = files.update(file_id, "baz");
+
+        assert_eq!(old_contents, "foo\nbar");
+        assert_eq!(files[file_id].contents(), "baz");
+        assert_eq!(files[file_id].version(), 1);
+        assert_eq!(
+            files[file_id].line_starts(),
+            [ByteIndex::from(0), ByteIndex::from(3)],
+        );
+        assert_eq!(files.location(file_id, 5), None);
+    }
+
     #[test]
     fn line_span_sources() {
         let mut files = Files::new();
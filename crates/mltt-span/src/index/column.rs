@@ -7,14 +7,27 @@ use crate::{ByteIndex, ByteSize};
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ColumnIndex(usize);
 
+/// Advance the column past a grapheme cluster, moving tabs to the next tab
+/// stop. A tab width of zero treats tabs like any other grapheme cluster.
+fn advance(column: usize, grapheme: &str, tab_width: usize) -> usize {
+    match grapheme {
+        "\t" if tab_width > 0 => (column / tab_width + 1) * tab_width,
+        _ => column + 1,
+    }
+}
+
 impl ColumnIndex {
     pub fn from_str(
         src: &str,
         line_start_byte: ByteIndex,
         column_byte: ByteIndex,
+        tab_width: usize,
     ) -> Option<ColumnIndex> {
         let line_src = &src.get(line_start_byte.to_usize()..column_byte.to_usize())?;
-        Some(ColumnIndex::from(line_src.graphemes(true).count()))
+        let column = (line_src.graphemes(true))
+            .fold(0, |column, grapheme| advance(column, grapheme, tab_width));
+
+        Some(ColumnIndex::from(column))
     }
 
     pub fn to_usize(self) -> usize {
@@ -22,16 +35,29 @@ impl ColumnIndex {
     }
 
     /// Convert to a byte size, based on a unicode string
-    pub fn to_byte_size(self, line_src: &str) -> ByteSize {
-        line_src
-            .graphemes(true)
-            .map(ByteSize::from_str_len_utf8)
-            .fold(ByteSize::from(0), |acc, size| acc + size)
+    ///
+    /// Columns that fall inside a tab are rounded down to the start of the tab.
+    pub fn to_byte_size(self, line_src: &str, tab_width: usize) -> ByteSize {
+        let mut column = 0;
+        let mut size = ByteSize::from(0);
+        for grapheme in line_src.graphemes(true) {
+            column = advance(column, grapheme, tab_width);
+            if column > self.to_usize() {
+                break;
+            }
+            size += ByteSize::from_str_len_utf8(grapheme);
+        }
+        size
     }
 
     /// Convert to a byte index, based on a unicode string and a starting index
-    pub fn to_byte_index(self, src: &str, line_start_byte: ByteIndex) -> ByteIndex {
-        line_start_byte + self.to_byte_size(&src[line_start_byte.to_usize()..])
+    pub fn to_byte_index(
+        self,
+        src: &str,
+        line_start_byte: ByteIndex,
+        tab_width: usize,
+    ) -> ByteIndex {
+        line_start_byte + self.to_byte_size(&src[line_start_byte.to_usize()..], tab_width)
     }
 }
 
@@ -5,9 +5,13 @@
 mod file;
 mod index;
 mod location;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod snippet;
 mod span;
 
 pub use crate::file::*;
 pub use crate::index::*;
 pub use crate::location::*;
+pub use crate::snippet::*;
 pub use crate::span::*;
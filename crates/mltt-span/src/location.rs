@@ -11,11 +11,11 @@ pub struct Location {
     pub byte: ByteIndex,
 }
 
-impl Into<language_reporting::Location> for Location {
-    fn into(self) -> language_reporting::Location {
+impl From<Location> for language_reporting::Location {
+    fn from(val: Location) -> Self {
         language_reporting::Location {
-            line: self.line.to_usize(),
-            column: self.column.to_usize(),
+            line: val.line.to_usize(),
+            column: val.column.to_usize(),
         }
     }
 }
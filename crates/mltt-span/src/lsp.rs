@@ -0,0 +1,162 @@
+//! Conversions between source positions and the ranges and positions used by
+//! the [Language Server Protocol](https://microsoft.github.io/language-server-protocol/).
+//!
+//! Note that LSP positions count columns in UTF-16 code units, whereas the
+//! rest of this crate uses byte indices and grapheme clusters.
+
+use lsp_types::{Position, Range};
+
+use crate::{ByteIndex, ByteSize, FileId, FileSpan, Files, LineIndex, Span};
+
+impl Files {
+    /// Convert a byte index in a file to an LSP position.
+    ///
+    /// Returns `None` if the byte index is out of bounds, or does not fall on
+    /// a character boundary.
+    pub fn byte_index_to_position(
+        &self,
+        file_id: FileId,
+        byte: impl Into<ByteIndex>,
+    ) -> Option<Position> {
+        let byte = byte.into();
+        let line = self.location(file_id, byte)?.line;
+        let line_start = self[file_id].line_starts()[line.to_usize()];
+        let line_src = self.source(Span::new(file_id, line_start, byte))?;
+        let character = line_src.encode_utf16().count();
+
+        Some(Position::new(line.to_usize() as u32, character as u32))
+    }
+
+    /// Convert an LSP position to a byte index in a file.
+    ///
+    /// Following the LSP specification, characters past the end of a line are
+    /// clamped to the end of the line, before any line terminator. Returns
+    /// `None` if the line is out of bounds, or if the character falls in the
+    /// middle of a surrogate pair.
+    pub fn position_to_byte_index(&self, file_id: FileId, position: Position) -> Option<ByteIndex> {
+        let line_span = self.line_span(file_id, LineIndex::from(position.line as usize))?;
+        let line_src = self.source(line_span)?;
+        let line_src = line_src.trim_end_matches(['\n', '\r']);
+
+        let mut character = 0;
+        let mut offset = ByteSize::from(0);
+        for ch in line_src.chars() {
+            if character >= position.character as usize {
+                break;
+            }
+            character += ch.len_utf16();
+            offset += ByteSize::from_char_len_utf8(ch);
+        }
+
+        if character > position.character as usize {
+            None // In the middle of a surrogate pair
+        } else {
+            Some(line_span.start() + offset)
+        }
+    }
+
+    /// Convert a span to an LSP range.
+    pub fn span_to_range(&self, span: FileSpan) -> Option<Range> {
+        Some(Range::new(
+            self.byte_index_to_position(span.source(), span.start())?,
+            self.byte_index_to_position(span.source(), span.end())?,
+        ))
+    }
+
+    /// Convert an LSP range to a span in a file.
+    pub fn range_to_span(&self, file_id: FileId, range: Range) -> Option<FileSpan> {
+        Some(Span::new(
+            file_id,
+            self.position_to_byte_index(file_id, range.start)?,
+            self.position_to_byte_index(file_id, range.end)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn byte_index_to_position() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "λ 𝔸\r\nfoo\n");
+
+        let positions = [0, 2, 3, 7, 8, 9, 12, 13, 14]
+            .iter()
+            .map(|&byte| files.byte_index_to_position(file_id, byte))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            positions,
+            [
+                Some(Position::new(0, 0)),
+                Some(Position::new(0, 1)),
+                Some(Position::new(0, 2)),
+                Some(Position::new(0, 4)),
+                Some(Position::new(0, 5)),
+                Some(Position::new(1, 0)),
+                Some(Position::new(1, 3)),
+                Some(Position::new(2, 0)),
+                None,
+            ],
+        );
+
+        // In the middle of `𝔸`
+        assert_eq!(files.byte_index_to_position(file_id, 4), None);
+    }
+
+    #[test]
+    fn position_to_byte_index() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "λ 𝔸\r\nfoo\n");
+
+        let byte_indices = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (0, 100),
+            (1, 1),
+            (2, 0),
+        ]
+        .iter()
+        .map(|&(line, character)| {
+            files.position_to_byte_index(file_id, Position::new(line, character))
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            byte_indices,
+            [
+                Some(ByteIndex::from(0)),
+                Some(ByteIndex::from(2)),
+                Some(ByteIndex::from(3)),
+                None,
+                Some(ByteIndex::from(7)),
+                Some(ByteIndex::from(7)),
+                Some(ByteIndex::from(10)),
+                Some(ByteIndex::from(13)),
+            ],
+        );
+
+        assert_eq!(
+            files.position_to_byte_index(file_id, Position::new(3, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn span_to_range_round_trip() {
+        let mut files = Files::new();
+        let file_id = files.add("test", "λ 𝔸\r\nfoo\n");
+        let span = Span::new(file_id, 3, 12);
+        let range = Range::new(Position::new(0, 2), Position::new(1, 3));
+
+        assert_eq!(files.span_to_range(span), Some(range));
+        assert_eq!(files.range_to_span(file_id, range), Some(span));
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::{FileSpan, Files, LineIndex};
+
+/// A snippet of source code, extracted from a file along with some
+/// surrounding lines for context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet<'files> {
+    /// The span that the snippet was extracted for.
+    pub span: FileSpan,
+    /// The lines in the snippet, including the context lines.
+    pub lines: Vec<SnippetLine<'files>>,
+}
+
+/// A line in a snippet of source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetLine<'files> {
+    /// The index of the line in the source file.
+    pub line: LineIndex,
+    /// The source code of the line, without the line ending.
+    pub source: &'files str,
+    /// Whether the line overlaps with the span of the snippet, as opposed to
+    /// being a context line.
+    pub is_marked: bool,
+}
+
+impl Files {
+    /// Extract the lines of source code covered by a span, along with up to
+    /// `context_lines` lines before and after it.
+    ///
+    /// Returns `None` if the span is out of bounds.
+    pub fn snippet(&self, span: FileSpan, context_lines: usize) -> Option<Snippet<'_>> {
+        let file_id = span.source();
+        let start_line = self.location(file_id, span.start())?.line.to_usize();
+        let end_line = {
+            let end = self.location(file_id, span.end())?;
+            // Don't mark the following line if the span ends with a newline
+            if end.line.to_usize() > start_line && end.column.to_usize() == 0 {
+                end.line.to_usize() - 1
+            } else {
+                end.line.to_usize()
+            }
+        };
+        // Skip the sentinel at the end of the line starts
+        let last_line = self[file_id].line_starts().len() - 2;
+
+        let first_line = start_line.saturating_sub(context_lines);
+        let final_line = usize::min(end_line + context_lines, last_line);
+
+        let lines = (first_line..=final_line)
+            .map(|line| {
+                let line = LineIndex::from(line);
+                let source = self.source(self.line_span(file_id, line)?)?;
+
+                Some(SnippetLine {
+                    line,
+                    source: source.trim_end_matches(['\n', '\r']),
+                    is_marked: start_line <= line.to_usize() && line.to_usize() <= end_line,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Snippet { span, lines })
+    }
+}
+
+impl fmt::Display for Snippet<'_> {
+    /// Display the snippet with 1-based line numbers, pointing out the marked
+    /// lines with a `>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number_width = match self.lines.last() {
+            Some(line) => (line.line.to_usize() + 1).to_string().len(),
+            None => 0,
+        };
+
+        for line in &self.lines {
+            let gutter = if line.is_marked { '>' } else { '|' };
+            let number = line.line.to_usize() + 1;
+            writeln!(
+                f,
+                "{:>width$} {} {}",
+                number,
+                gutter,
+                line.source,
+                width = number_width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Span;
+
+    const SOURCE: &str = "one\ntwo\nthree\nfour\r\nfive\nsix\nseven\neight\nnine\nten\n";
+
+    #[test]
+    fn snippet() {
+        let mut files = Files::new();
+        let file_id = files.add("test", SOURCE);
+        let span = Span::new(file_id, 14, 18); // "four"
+
+        let snippet = files.snippet(span, 1).unwrap();
+
+        assert_eq!(
+            snippet.lines,
+            [
+                SnippetLine {
+                    line: LineIndex::from(2),
+                    source: "three",
+                    is_marked: false,
+                },
+                SnippetLine {
+                    line: LineIndex::from(3),
+                    source: "four",
+                    is_marked: true,
+                },
+                SnippetLine {
+                    line: LineIndex::from(4),
+                    source: "five",
+                    is_marked: false,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn snippet_multiline() {
+        let mut files = Files::new();
+        let file_id = files.add("test", SOURCE);
+        let span = Span::new(file_id, 4, 14); // "two\nthree\n"
+
+        let snippet = files.snippet(span, 0).unwrap();
+
+        assert_eq!(snippet.to_string(), "2 > two\n3 > three\n");
+    }
+
+    #[test]
+    fn snippet_clamped() {
+        let mut files = Files::new();
+        let file_id = files.add("test", SOURCE);
+        let span = Span::new(file_id, 46, 49); // "ten"
+
+        let snippet = files.snippet(span, 2).unwrap();
+
+        assert_eq!(
+            snippet.to_string(),
+            " 8 | eight\n 9 | nine\n10 > ten\n11 | \n"
+        );
+    }
+
+    #[test]
+    fn snippet_out_of_bounds() {
+        let mut files = Files::new();
+        let file_id = files.add("test", SOURCE);
+        let span = Span::new(file_id, 100, 101);
+
+        assert_eq!(files.snippet(span, 2), None);
+    }
+}
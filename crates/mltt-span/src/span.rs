@@ -1,3 +1,4 @@
+use std::cmp;
 use std::fmt;
 
 use crate::{ByteIndex, ByteSize};
@@ -32,8 +33,50 @@ impl<Source: Copy> Span<Source> {
         Span::new(source, 0, s.len())
     }
 
-    pub fn merge(self, other: Span<Source>) -> Span<Source> {
-        Span::new(self.source(), self.start(), other.end())
+    /// Merge two spans into a span that covers both of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spans come from different sources. Use
+    /// `Span::checked_merge` if this is a possibility.
+    pub fn merge(self, other: Span<Source>) -> Span<Source>
+    where
+        Source: PartialEq + fmt::Debug,
+    {
+        match self.checked_merge(other) {
+            Some(span) => span,
+            None => panic!(
+                "attempted to merge spans from different sources: {:?} and {:?}",
+                self, other
+            ),
+        }
+    }
+
+    /// Merge two spans into a span that covers both of them, returning `None`
+    /// if the spans come from different sources.
+    pub fn checked_merge(self, other: Span<Source>) -> Option<Span<Source>>
+    where
+        Source: PartialEq,
+    {
+        if self.source() == other.source() {
+            let start = cmp::min(self.start(), other.start());
+            let end = cmp::max(self.end(), other.end());
+            Some(Span::new(self.source(), start, end))
+        } else {
+            None
+        }
+    }
+
+    /// Compute the smallest span that covers all of the given spans,
+    /// returning `None` if there are no spans, or if they come from different
+    /// sources.
+    pub fn cover(spans: impl IntoIterator<Item = Span<Source>>) -> Option<Span<Source>>
+    where
+        Source: PartialEq,
+    {
+        let mut spans = spans.into_iter();
+        let first = spans.next()?;
+        spans.try_fold(first, Span::checked_merge)
     }
 
     pub fn with_source<NewSource: Copy>(&self, source: NewSource) -> Span<NewSource> {
@@ -68,11 +111,12 @@ impl<Source: Copy> Span<Source> {
         self.end
     }
 
+    /// Returns `true` if the given span is contained within this span.
     pub fn contains(self, span: Span<Source>) -> bool
     where
         Source: PartialEq,
     {
-        self.source() == span.source() && self.start() >= span.start() && self.end() < span.end()
+        self.source() == span.source() && self.start() <= span.start() && span.end() <= self.end()
     }
 
     pub fn contains_index(self, index: impl Into<ByteIndex>) -> bool {
@@ -114,3 +158,59 @@ impl<Source: Copy + fmt::Debug> fmt::Debug for Span<Source> {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let span1 = Span::new(0, 4, 8);
+        let span2 = Span::new(0, 2, 6);
+
+        assert_eq!(span1.merge(span2), Span::new(0, 2, 8));
+        assert_eq!(span2.merge(span1), Span::new(0, 2, 8));
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_different_sources() {
+        Span::new(0, 0, 2).merge(Span::new(1, 4, 6));
+    }
+
+    #[test]
+    fn checked_merge() {
+        let span1 = Span::new(0, 0, 2);
+
+        assert_eq!(
+            span1.checked_merge(Span::new(0, 4, 6)),
+            Some(Span::new(0, 0, 6))
+        );
+        assert_eq!(span1.checked_merge(Span::new(1, 4, 6)), None);
+    }
+
+    #[test]
+    fn cover() {
+        let spans = vec![Span::new(0, 6, 8), Span::new(0, 1, 3), Span::new(0, 4, 5)];
+
+        assert_eq!(Span::cover(spans), Some(Span::new(0, 1, 8)));
+        assert_eq!(Span::cover(Vec::<Span<()>>::new()), None);
+        assert_eq!(
+            Span::cover(vec![Span::new(0, 1, 3), Span::new(1, 4, 5)]),
+            None
+        );
+    }
+
+    #[test]
+    fn contains() {
+        let span = Span::new(0, 2, 8);
+
+        assert!(span.contains(span));
+        assert!(span.contains(Span::new(0, 3, 5)));
+        assert!(!span.contains(Span::new(0, 1, 5)));
+        assert!(!span.contains(Span::new(0, 3, 9)));
+        assert!(!span.contains(Span::new(1, 3, 5)));
+    }
+}
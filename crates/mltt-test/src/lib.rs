@@ -14,13 +14,56 @@ mod samples {
         };
     }
 
+    test!(arrays, "arrays");
+    test!(call_by_need, "call-by-need");
     test!(categories, "categories");
     test!(combinators, "combinators");
     test!(connectives, "connectives");
     test!(cumulativity, "cumulativity");
     test!(empty, "empty");
+    test!(layout, "layout");
+    test!(literal_defaulting, "literal-defaulting");
+    test!(namespaces, "namespaces");
+    test!(no_prelude, "no-prelude");
+    test!(pattern_synonyms, "pattern-synonyms");
     test!(primitives, "primitives");
     test!(records, "records");
+    test!(sections, "sections");
+    test!(sigma, "sigma");
+    test!(type_in_type, "type-in-type");
+    test!(well_founded, "well-founded");
+    test!(with, "with");
+}
+
+mod round_trip {
+    macro_rules! test {
+        ($test_name:ident, $file_name:literal) => {
+            #[test]
+            fn $test_name() {
+                $crate::support::run_round_trip($file_name);
+            }
+        };
+    }
+
+    test!(arrays, "arrays");
+    test!(call_by_need, "call-by-need");
+    test!(categories, "categories");
+    test!(combinators, "combinators");
+    test!(connectives, "connectives");
+    test!(cumulativity, "cumulativity");
+    test!(empty, "empty");
+    test!(layout, "layout");
+    test!(literal_defaulting, "literal-defaulting");
+    test!(namespaces, "namespaces");
+    test!(no_prelude, "no-prelude");
+    test!(pattern_synonyms, "pattern-synonyms");
+    test!(primitives, "primitives");
+    test!(records, "records");
+    test!(sections, "sections");
+    test!(sigma, "sigma");
+    test!(type_in_type, "type-in-type");
+    test!(well_founded, "well-founded");
+    test!(with, "with");
 }
 
 mod elaborate {
@@ -34,6 +77,14 @@ mod elaborate {
             };
         }
 
+        mod case {
+            test!(guarded_default, "case/guarded-default");
+        }
+
+        mod fun_intro {
+            test!(annotation_mismatch, "fun-intro/annotation-mismatch");
+        }
+
         mod literal_intro {
             mod int {
                 mod u8 {
@@ -71,11 +122,15 @@ mod elaborate {
         mod case {
             test!(default_bind, "case/default-bind");
             test!(default, "case/default");
+            test!(guards, "case/guards");
+            test!(negative, "case/negative");
             test!(overlapping, "case/overlapping");
             test!(simple, "case/simple");
         }
 
         mod fun_intro {
+            test!(annotated, "fun-intro/annotated");
+            test!(docs, "fun-intro/docs");
             test!(explicit, "fun-intro/explicit");
             test!(implicit, "fun-intro/implicit");
             test!(instance, "fun-intro/instance");
@@ -201,17 +256,27 @@ mod elaborate {
 
         mod let_ {
             test!(already_defined, "let/already-defined");
+            test!(from_string_ambiguous, "let/from-string-ambiguous");
+            test!(from_string_invalid, "let/from-string-invalid");
+            test!(namespace_unqualified, "let/namespace-unqualified");
             test!(not_yet_declared, "let/not-yet-declared");
+            test!(open_hidden, "let/open-hidden");
+            test!(open_not_reexported, "let/open-not-reexported");
+            test!(open_unknown_item, "let/open-unknown-item");
+            test!(overloaded_ambiguous, "let/overloaded-ambiguous");
+            test!(overloaded_mismatch, "let/overloaded-mismatch");
+            test!(unknown_attribute, "let/unknown-attribute");
+            test!(unknown_namespace, "let/unknown-namespace");
         }
 
         mod literal_intro {
             mod float {
                 test!(float_ambiguous, "literal-intro/float/ambiguous");
             }
+        }
 
-            mod int {
-                test!(int_ambiguous, "literal-intro/int/ambiguous");
-            }
+        mod placeholder {
+            test!(ambiguous, "placeholder/ambiguous");
         }
 
         mod prim {
@@ -250,14 +315,22 @@ mod elaborate {
 
         mod fun_elim {
             test!(explicit, "fun-elim/explicit");
+            test!(explicit_placeholder, "fun-elim/explicit-placeholder");
             test!(implicit, "fun-elim/implicit");
             test!(implicit_insert_meta, "fun-elim/implicit-insert-meta");
             test!(instance, "fun-elim/instance");
         }
 
+        mod fun_intro {
+            test!(annotated, "fun-intro/annotated");
+        }
+
         mod fun_type {
+            test!(implicit_inferred, "fun-type/implicit-inferred");
             test!(param_group_1, "fun-type/param-group-1");
             test!(param_group_2, "fun-type/param-group-2");
+            test!(param_group_dependent, "fun-type/param-group-dependent");
+            test!(param_group_implicit, "fun-type/param-group-implicit");
         }
 
         mod fun_type_arrow {
@@ -270,10 +343,21 @@ mod elaborate {
         }
 
         mod let_ {
+            test!(attributes, "let/attributes");
             test!(complicated, "let/complicated");
             test!(definition, "let/definition");
+            test!(dependent_projection, "let/dependent-projection");
             test!(declaration_definition, "let/declaration-definition");
             test!(forward_declarations, "let/forward-declarations");
+            test!(from_string, "let/from-string");
+            test!(method_call, "let/method-call");
+            test!(namespace, "let/namespace");
+            test!(open_imports, "let/open-imports");
+            test!(overloaded_names, "let/overloaded-names");
+            test!(placeholder, "let/placeholder");
+            test!(public_open, "let/public-open");
+            test!(record_helpers, "let/record-helpers");
+            test!(record_helpers_dependent, "let/record-helpers-dependent");
         }
 
         #[rustfmt::skip]
@@ -293,6 +377,11 @@ mod elaborate {
                 test!(escape_unicode_upper_max, "literal-intro/char/escape-unicode-upper-max");
             }
 
+            mod int {
+                test!(default, "literal-intro/int/default");
+                test!(inferred, "literal-intro/int/inferred");
+            }
+
             mod string {
                 test!(ascii, "literal-intro/string/ascii");
                 test!(escape_ascii_lower_max, "literal-intro/string/escape-ascii-lower-max");
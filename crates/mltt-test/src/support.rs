@@ -1,14 +1,15 @@
 use language_reporting::termcolor::{ColorChoice, StandardStream};
 use language_reporting::Diagnostic;
-use mltt_core::{domain, nbe, syntax, validate};
-use mltt_elaborate::MetaInsertion;
+use mltt_compile::{closure, dead_code, erased, lifted};
+use mltt_concrete::Item;
+use mltt_core::{diff, domain, nbe, pretty, syntax, validate, var, Rc};
+use mltt_elaborate::{LanguageOptions, MetaInsertion};
 use mltt_parse::lexer::Lexer;
 use mltt_parse::parser;
 use mltt_span::{File, FileId, FileSpan, Files};
 use std::fs;
-use std::rc::Rc;
 
-const TESTS_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests");
+const TESTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests");
 const REPORTING_CONFIG: language_reporting::DefaultConfig = language_reporting::DefaultConfig;
 
 fn setup() -> (Files, mltt_core::meta::Env, mltt_elaborate::Context) {
@@ -34,17 +35,29 @@ fn emit_diagnostic<'a, T>(
     }
 }
 
+/// Describe the differences between two terms, one per line, for use in the
+/// messages of failed tests.
+fn describe_differences(env: &pretty::Env, differences: &[diff::Difference]) -> String {
+    (differences.iter())
+        .map(|difference| {
+            let doc = difference.to_display_doc(env).group();
+            format!("  {}", doc.pretty(1_000_000_000))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn synth_universe(
     context: &mltt_elaborate::Context,
     metas: &mut mltt_core::meta::Env,
     concrete_ty_file: &File,
 ) -> Result<Rc<domain::Type>, Diagnostic<FileSpan>> {
-    let lexer = Lexer::new(&concrete_ty_file);
+    let lexer = Lexer::new(concrete_ty_file);
     let concrete_ty = parser::parse_term(lexer)?;
     // FIXME: check lexer for errors
 
     let (ty, level1) = mltt_elaborate::synth_universe(context, metas, &concrete_ty)?;
-    let level2 = validate::synth_universe(&context.validation_context(), metas, &ty)
+    let level2 = validate::synth_universe(&mut context.validation_context(), metas, &ty)
         .unwrap_or_else(|error| panic!("validation error: {}", error));
 
     assert_eq!(level1, level2);
@@ -58,12 +71,14 @@ fn check_term(
     concrete_term_file: &File,
     expected_ty: &Rc<domain::Type>,
 ) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
-    let lexer = Lexer::new(&concrete_term_file);
+    let lexer = Lexer::new(concrete_term_file);
     let concrete_term = parser::parse_term(lexer)?;
     // FIXME: check lexer for errors
 
-    let term = mltt_elaborate::check_term(context, metas, &concrete_term, &expected_ty)?;
-    validate::check_term(&context.validation_context(), &metas, &term, &expected_ty)
+    let start_meta = metas.next_index();
+    let term = mltt_elaborate::check_term(context, metas, &concrete_term, expected_ty)?;
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    validate::check_term(&mut context.validation_context(), metas, &term, expected_ty)
         .unwrap_or_else(|error| panic!("{}", error));
 
     Ok(term)
@@ -75,28 +90,44 @@ fn synth_term(
     concrete_term_file: &File,
     expected_ty: &Rc<domain::Type>,
 ) -> Result<(Rc<syntax::Term>, Rc<domain::Type>), Diagnostic<FileSpan>> {
-    let lexer = Lexer::new(&concrete_term_file);
+    let lexer = Lexer::new(concrete_term_file);
     let concrete_term = parser::parse_term(lexer)?;
     // FIXME: check lexer for errors
 
-    let (term, term_ty) =
-        mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, &concrete_term)?;
-    validate::synth_term(&context.validation_context(), &metas, &term)
+    let start_meta = metas.next_index();
+    let mltt_elaborate::Synthesized {
+        term, ty: term_ty, ..
+    } = mltt_elaborate::synth_term(MetaInsertion::Yes, context, metas, &concrete_term)?;
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    validate::synth_term(&mut context.validation_context(), metas, &term)
         .unwrap_or_else(|error| panic!("{}", error));
 
     // Verify that we got the expected type (sans subtyping)
     let prims = context.prims();
     let size = context.values().size();
-    if !nbe::check_ty(&prims, &metas, size, false, &term_ty, &expected_ty)
+    if !nbe::check_ty(prims, metas, size, false, &term_ty, expected_ty)
         .unwrap_or_else(|error| panic!("{}", error))
     {
-        panic!("unequal types");
+        let differences = diff::diff_values(prims, metas, size, &term_ty, expected_ty)
+            .unwrap_or_else(|error| panic!("{}", error));
+        let env = context.pretty_env();
+        panic!(
+            "unequal types:\n{}",
+            describe_differences(&env, &differences)
+        );
     }
 
     // Ensure that the checking also works
+    let start_meta = metas.next_index();
     let term2 = mltt_elaborate::check_term(context, metas, &concrete_term, expected_ty)?;
-    validate::check_term(&context.validation_context(), &metas, &term2, &expected_ty)
-        .unwrap_or_else(|error| panic!("{}", error));
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+    validate::check_term(
+        &mut context.validation_context(),
+        metas,
+        &term2,
+        expected_ty,
+    )
+    .unwrap_or_else(|error| panic!("{}", error));
 
     Ok((term, term_ty))
 }
@@ -105,7 +136,7 @@ pub fn run_sample(name: &str) {
     let _ = pretty_env_logger::try_init();
     let writer = StandardStream::stdout(ColorChoice::Always);
 
-    let (mut files, mut metas, context) = setup();
+    let (mut files, mut metas, _) = setup();
 
     let module_path = format!("{}/samples/{}.mltt", TESTS_DIR, name);
     let module_file_id = load_file(&mut files, module_path);
@@ -115,10 +146,366 @@ pub fn run_sample(name: &str) {
         parser::parse_module(lexer).unwrap_or_else(emit_diagnostic(&writer, &files));
     // FIXME: check lexer for errors
 
-    let module = mltt_elaborate::check_module(&context, &mut metas, &concrete_module)
+    let options = mltt_elaborate::module_options(LanguageOptions::default(), &concrete_module)
         .unwrap_or_else(emit_diagnostic(&writer, &files));
-    validate::check_module(&context.validation_context(), &metas, &module)
-        .unwrap_or_else(|error| panic!("{}", error));
+    let context = mltt_elaborate::Context::new(options);
+    let (module, signature) =
+        mltt_elaborate::check_module(&context, &mut metas, &concrete_module.items)
+            .unwrap_or_else(emit_diagnostic(&writer, &files));
+    let validation_context = context.validation_context();
+    let validated_signature = validate::check_module(&validation_context, &metas, &module)
+        .map_err(Diagnostic::from)
+        .unwrap_or_else(emit_diagnostic(&writer, &files));
+
+    // The elaborator and the validator should agree on the signature
+    assert_eq!(signature.items().len(), validated_signature.items().len());
+    for (item, validated_item) in signature.items().iter().zip(validated_signature.items()) {
+        assert_eq!(item.label, validated_item.label);
+        assert_eq!(item.value.is_some(), validated_item.value.is_some());
+        let size = validation_context.values().size();
+        let prims = validation_context.prims();
+        let is_equal = nbe::check_ty(prims, &metas, size, false, &item.ty, &validated_item.ty)
+            .unwrap_or_else(|error| panic!("{}", error));
+        if !is_equal {
+            let differences = diff::diff_values(prims, &metas, size, &item.ty, &validated_item.ty)
+                .unwrap_or_else(|error| panic!("{}", error));
+            let env = context.pretty_env();
+            panic!(
+                "signatures differ for `{}`:\n{}",
+                item.label,
+                describe_differences(&env, &differences),
+            );
+        }
+    }
+
+    // Ensure that the module can be lowered by the compilation passes
+    let size = validation_context.values().size();
+    let prims = validation_context.prims();
+    let erased_module = erased::erase_module(prims, &metas, size, &module)
+        .unwrap_or_else(|error| panic!("erasure error: {}", error));
+    let roots = dead_code::default_roots(&erased_module);
+    let (erased_module, _) = dead_code::eliminate_dead_code(&erased_module, &roots);
+    let closure_module = closure::convert_module(size, &erased_module)
+        .unwrap_or_else(|error| panic!("closure conversion error: {}", error));
+    let program = lifted::lift_module(&closure_module);
+    assert_eq!(program.definitions.len(), erased_module.definitions.len());
+}
+
+/// Check that the items of a sample can be printed, then parsed and
+/// elaborated again to alpha equivalent core terms.
+///
+/// The items of namespaces are printed in the context of their namespace,
+/// and the items of sections are printed after they have been abstracted
+/// over the parameters of the section. Solved metavariables are replaced
+/// with their solutions before comparing the terms.
+pub fn run_round_trip(name: &str) {
+    let _ = pretty_env_logger::try_init();
+    let writer = StandardStream::stdout(ColorChoice::Always);
+
+    let (mut files, mut metas, _) = setup();
+
+    let module_path = format!("{}/samples/{}.mltt", TESTS_DIR, name);
+    let module_file_id = load_file(&mut files, module_path);
+
+    let lexer = Lexer::new(&files[module_file_id]);
+    let concrete_module =
+        parser::parse_module(lexer).unwrap_or_else(emit_diagnostic(&writer, &files));
+
+    let options = mltt_elaborate::module_options(LanguageOptions::default(), &concrete_module)
+        .unwrap_or_else(emit_diagnostic(&writer, &files));
+    let mut context = mltt_elaborate::Context::new(options);
+    context.set_show_implicits(true);
+
+    // The printed terms are added to their own files, as the concrete module
+    // borrows the sample's file
+    let mut printed_files = Files::new();
+    let items = &concrete_module.items;
+    round_trip_items(
+        &writer,
+        &files,
+        &mut printed_files,
+        &mut context,
+        &mut metas,
+        items,
+    );
+}
+
+/// Round trip a list of items, adding them to the context as they are loaded.
+fn round_trip_items(
+    writer: &StandardStream,
+    files: &Files,
+    printed_files: &mut Files,
+    context: &mut mltt_elaborate::Context,
+    metas: &mut mltt_core::meta::Env,
+    concrete_items: &[Item<'_>],
+) {
+    // Load the items one at a time, keeping declarations with the
+    // definitions that follow them
+    let mut concrete_items = concrete_items;
+    while !concrete_items.is_empty() {
+        let batch_len = match concrete_items {
+            [Item::Declaration(declaration), Item::Definition(definition), ..]
+                if declaration.label.slice == definition.label.slice =>
+            {
+                2
+            },
+            _ => 1,
+        };
+        let (batch, rest) = concrete_items.split_at(batch_len);
+        concrete_items = rest;
+
+        // The items of namespaces refer to the earlier items in the namespace
+        // by their unqualified names, so they are round tripped in the
+        // context of the namespace, before the namespace is loaded
+        if let [Item::Namespace(namespace)] = batch {
+            let name = namespace.name.slice;
+            let mut namespace_context = context.namespace_context();
+            if context.defines_namespace(name) {
+                namespace_context.open_namespace(name, |_| true, false);
+            }
+            let items = &namespace.items;
+            round_trip_items(
+                writer,
+                files,
+                printed_files,
+                &mut namespace_context,
+                metas,
+                items,
+            );
+            mltt_elaborate::load_module(context, metas, batch)
+                .unwrap_or_else(emit_diagnostic(writer, files));
+            continue;
+        }
+
+        let item_context = context.clone();
+        let (module, signature) = mltt_elaborate::load_module(context, metas, batch)
+            .unwrap_or_else(emit_diagnostic(writer, files));
+
+        // The helpers that are derived for record types are elaborated in the
+        // context of the record type's definition, and are skipped, as they
+        // weren't printed from anything in the sample
+        let helper_prefixes = (batch.iter())
+            .filter_map(|concrete_item| match concrete_item {
+                Item::Definition(definition) => Some(format!("{}.", definition.label.slice)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for item in &module.items {
+            let (label, term) = match item {
+                syntax::Item::Declaration(_, label, term) => (label, term),
+                syntax::Item::Definition(_, label, term) => (label, term),
+            };
+            if (helper_prefixes.iter()).any(|prefix| label.0.starts_with(prefix)) {
+                continue;
+            }
+            let expected_ty = match item {
+                syntax::Item::Declaration(..) => None,
+                syntax::Item::Definition(..) => match signature.lookup_item(label) {
+                    Some(item_signature) => Some(&item_signature.ty),
+                    None => continue,
+                },
+            };
+
+            let prims = item_context.prims();
+            let size = item_context.values().size();
+
+            // Metavariables can't be printed in a way that can be parsed
+            // again, so they are replaced with their solutions first
+            let term = nbe::zonk_term(prims, metas, size, term).unwrap();
+            let printed = term
+                .to_display_doc(&item_context.pretty_env())
+                .group()
+                .pretty(1_000_000_000)
+                .to_string();
+            let printed_file_id = printed_files.add(label.to_string(), printed.clone());
+            let printed_file = &printed_files[printed_file_id];
+            let round_tripped = elaborate_printed(&item_context, metas, printed_file, expected_ty)
+                .unwrap_or_else(emit_diagnostic(writer, printed_files));
+            let round_tripped = nbe::zonk_term(prims, metas, size, &round_tripped).unwrap();
+
+            let differences = diff::diff_terms(
+                &undo_desugarings(item_context.values(), 0, &term),
+                &undo_desugarings(item_context.values(), 0, &round_tripped),
+            );
+            assert!(
+                differences.is_empty(),
+                "`{}` was not round tripped: {}\n{}",
+                label,
+                printed,
+                describe_differences(&item_context.pretty_env(), &differences),
+            );
+        }
+    }
+}
+
+/// Undo the desugarings that the elaborator applies to printed terms, so that
+/// terms can be compared after being round tripped:
+///
+/// - the names of literal types elaborate to variables that refer to their
+///   definitions in the prelude
+/// - the scrutinees of case expressions are bound with a `let`, even if they
+///   were already bound to variables when the case expression was printed
+fn undo_desugarings(
+    values: &var::Env<Rc<domain::Value>>,
+    depth: u32,
+    term: &Rc<syntax::Term>,
+) -> Rc<syntax::Term> {
+    use mltt_core::syntax::{Item, Term};
+
+    map_term(term, depth, &|term, depth| match term.as_ref() {
+        Term::Var(index) if index.0 >= depth => {
+            match values.lookup_entry(var::Index(index.0 - depth)) {
+                Some(value) => match value.as_ref() {
+                    domain::Value::LiteralType(ty) => Some(Rc::from(Term::LiteralType(*ty))),
+                    _ => None,
+                },
+                None => None,
+            }
+        },
+        Term::Let(items, body) => match items.as_slice() {
+            [Item::Definition(_, _, scrutinee)] if **scrutinee == Term::var(0) => {
+                let body = undo_desugarings(values, depth + 1, body);
+                match body.as_ref() {
+                    Term::LiteralElim(body_scrutinee, _, _) if **body_scrutinee == Term::var(0) => {
+                        // Substitute the variable that was bound for itself
+                        Some(map_term(&body, 0, &|term, depth| match term.as_ref() {
+                            Term::Var(index) if index.0 > depth => {
+                                Some(Rc::from(Term::var(index.0 - 1)))
+                            },
+                            _ => None,
+                        }))
+                    },
+                    _ => Some(Rc::from(Term::Let(items.clone(), body))),
+                }
+            },
+            // The binding of a scrutinee may have been merged into the end of an
+            // enclosing let when it was printed, so split it back out
+            [rest @ .., last @ Item::Definition(_, _, scrutinee)]
+                if **scrutinee == Term::var(0) =>
+            {
+                let body = Rc::from(Term::Let(vec![last.clone()], body.clone()));
+                let term = Rc::from(Term::Let(rest.to_vec(), body));
+                Some(undo_desugarings(values, depth, &term))
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Rewrite the subterms of a term, from the outside in, using a function that
+/// is given each subterm along with the number of binders that it is under.
+/// Subterms where the function returns `None` are left unchanged, apart from
+/// their own subterms, which are rewritten in turn.
+fn map_term(
+    term: &Rc<syntax::Term>,
+    depth: u32,
+    rewrite: &impl Fn(&Rc<syntax::Term>, u32) -> Option<Rc<syntax::Term>>,
+) -> Rc<syntax::Term> {
+    use mltt_core::syntax::{Item, Term};
+
+    if let Some(term) = rewrite(term, depth) {
+        return term;
+    }
+
+    match term.as_ref() {
+        Term::Var(_) | Term::Meta(_) | Term::Prim(_) => term.clone(),
+        Term::Ann(term, term_ty) => Rc::from(Term::Ann(
+            map_term(term, depth, rewrite),
+            map_term(term_ty, depth, rewrite),
+        )),
+        Term::Let(items, body) => {
+            let mut depth = depth;
+            let items = (items.iter())
+                .map(|item| match item {
+                    Item::Declaration(doc, label, term_ty) => {
+                        let term_ty = map_term(term_ty, depth, rewrite);
+                        Item::Declaration(doc.clone(), label.clone(), term_ty)
+                    },
+                    Item::Definition(doc, label, term) => {
+                        let term = map_term(term, depth, rewrite);
+                        depth += 1;
+                        Item::Definition(doc.clone(), label.clone(), term)
+                    },
+                })
+                .collect();
+            Rc::from(Term::Let(items, map_term(body, depth, rewrite)))
+        },
+        Term::LiteralType(_) | Term::LiteralIntro(_) | Term::Universe(_) => term.clone(),
+        Term::LiteralElim(scrutinee, clauses, default_body) => Rc::from(Term::LiteralElim(
+            map_term(scrutinee, depth, rewrite),
+            (clauses.iter())
+                .map(|(pattern, body)| (pattern.clone(), map_term(body, depth, rewrite)))
+                .collect(),
+            map_term(default_body, depth, rewrite),
+        )),
+        Term::FunType(doc, app_mode, name_hint, param_ty, body_ty) => Rc::from(Term::FunType(
+            doc.clone(),
+            app_mode.clone(),
+            name_hint.clone(),
+            map_term(param_ty, depth, rewrite),
+            map_term(body_ty, depth + 1, rewrite),
+        )),
+        Term::FunIntro(app_mode, name_hint, body) => Rc::from(Term::FunIntro(
+            app_mode.clone(),
+            name_hint.clone(),
+            map_term(body, depth + 1, rewrite),
+        )),
+        Term::FunElim(fun, app_mode, arg) => Rc::from(Term::FunElim(
+            map_term(fun, depth, rewrite),
+            app_mode.clone(),
+            map_term(arg, depth, rewrite),
+        )),
+        Term::RecordType(ty_fields) => Rc::from(Term::RecordType(
+            (ty_fields.iter().enumerate())
+                .map(|(i, (doc, label, name_hint, ty))| {
+                    let ty = map_term(ty, depth + i as u32, rewrite);
+                    (doc.clone(), label.clone(), name_hint.clone(), ty)
+                })
+                .collect(),
+        )),
+        Term::RecordIntro(intro_fields) => Rc::from(Term::RecordIntro(
+            (intro_fields.iter().enumerate())
+                .map(|(i, (label, term))| {
+                    (label.clone(), map_term(term, depth + i as u32, rewrite))
+                })
+                .collect(),
+        )),
+        Term::RecordElim(record, label) => Rc::from(Term::RecordElim(
+            map_term(record, depth, rewrite),
+            label.clone(),
+        )),
+        Term::ArrayType(elem_ty) => Rc::from(Term::ArrayType(map_term(elem_ty, depth, rewrite))),
+        Term::ArrayIntro(elems) => Rc::from(Term::ArrayIntro(
+            (elems.iter())
+                .map(|elem| map_term(elem, depth, rewrite))
+                .collect(),
+        )),
+    }
+}
+
+/// Elaborate a printed term, checking it against the expected type if one is
+/// given, or otherwise as a type.
+fn elaborate_printed(
+    context: &mltt_elaborate::Context,
+    metas: &mut mltt_core::meta::Env,
+    printed_file: &File,
+    expected_ty: Option<&Rc<domain::Type>>,
+) -> Result<Rc<syntax::Term>, Diagnostic<FileSpan>> {
+    let lexer = Lexer::new(printed_file);
+    let concrete_term = parser::parse_term(lexer)?;
+
+    let start_meta = metas.next_index();
+    let term = match expected_ty {
+        Some(expected_ty) => {
+            mltt_elaborate::check_term(context, metas, &concrete_term, expected_ty)?
+        },
+        None => mltt_elaborate::synth_universe(context, metas, &concrete_term)?.0,
+    };
+    mltt_elaborate::solve_pending_literals(context, metas, start_meta)?;
+
+    Ok(term)
 }
 
 pub fn run_elaborate_check_pass(name: &str) {
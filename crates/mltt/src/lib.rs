@@ -0,0 +1,446 @@
+//! A convenient interface for embedding the MLTT language.
+//!
+//! This wires together the lexer, parser, elaborator, and validator, so that
+//! applications can check source code and normalize expressions without
+//! needing to know about each of the stages:
+//!
+//! ```rust
+//! let module = mltt::check_str("id : Fun (A : Type) -> A -> A; id A a = a;").unwrap();
+//! let (term, ty) = mltt::normalize(&module, "id String \"hello\"").unwrap();
+//!
+//! assert_eq!(module.display_term(&term), "\"hello\"");
+//! assert_eq!(module.display_term(&ty), "String");
+//! ```
+//!
+//! Terms can also be built from Rust code with the [`mltt_term!`] macro,
+//! which splices Rust values into the quoted syntax. See the [`quote`] module
+//! for more details.
+//!
+//! Enabling the `sync` feature allows checked modules and terms to be shared
+//! between threads.
+
+#![warn(rust_2018_idioms)]
+
+use language_reporting::Diagnostic;
+use mltt_core::signature::ModuleSignature;
+use mltt_core::{meta, syntax, validate, Label, Rc};
+use mltt_elaborate::{Context, LanguageOptions, MetaInsertion};
+use mltt_parse::lexer::Lexer;
+use mltt_parse::parser;
+use mltt_span::{FileId, FileSpan, Files};
+
+pub mod quote;
+
+/// The errors that were found when checking some source code.
+pub type Diagnostics = Vec<Diagnostic<FileSpan>>;
+
+/// A module that has been elaborated and validated.
+#[derive(Debug, Clone)]
+pub struct CheckedModule {
+    files: Files,
+    file_id: FileId,
+    context: Context,
+    metas: meta::Env,
+    module: syntax::Module,
+    signature: ModuleSignature,
+    warnings: Vec<Diagnostic<FileSpan>>,
+}
+
+impl CheckedModule {
+    /// The source files that diagnostics refer to.
+    pub fn files(&self) -> &Files {
+        &self.files
+    }
+
+    /// The file that the module was checked from.
+    pub fn file_id(&self) -> FileId {
+        self.file_id
+    }
+
+    /// The elaborated module.
+    pub fn module(&self) -> &syntax::Module {
+        &self.module
+    }
+
+    /// The signature of the module.
+    pub fn signature(&self) -> &ModuleSignature {
+        &self.signature
+    }
+
+    /// Warnings about unused or shadowed bindings, and about defaulted
+    /// literals.
+    pub fn warnings(&self) -> &[Diagnostic<FileSpan>] {
+        &self.warnings
+    }
+
+    /// The type of an item in the module, read back into the core syntax.
+    pub fn item_type(&self, label: &Label) -> Option<Rc<syntax::Term>> {
+        let item = self.signature.lookup_item(label)?;
+        self.context
+            .read_back_value(&self.metas, None, &item.ty)
+            .ok()
+    }
+
+    /// Display a term that is in the scope of the module's items.
+    pub fn display_term(&self, term: &syntax::Term) -> String {
+        let doc = term.to_display_doc(&self.context.pretty_env());
+        format!("{}", doc.group().pretty(1_000_000_000))
+    }
+}
+
+/// Parse, elaborate, and validate a module, using the default language
+/// options.
+pub fn check_str(source: &str) -> Result<CheckedModule, Diagnostics> {
+    check_str_with_options(LanguageOptions::default(), source)
+}
+
+/// Parse, elaborate, and validate a module. The language options can be
+/// overridden by the module itself.
+pub fn check_str_with_options(
+    language_options: LanguageOptions,
+    source: &str,
+) -> Result<CheckedModule, Diagnostics> {
+    let mut files = Files::new();
+    let file_id = files.add("<input>", source);
+    let mut metas = meta::Env::new();
+
+    let concrete_module = parser::parse_module(Lexer::new(&files[file_id])).map_err(|e| vec![e])?;
+    let language_options =
+        mltt_elaborate::module_options(language_options, &concrete_module).map_err(|e| vec![e])?;
+    let mut context = Context::new(language_options);
+
+    let resolution = mltt_elaborate::resolve_items(&context, &concrete_module.items);
+    let mut warnings = resolution.warnings;
+    if !resolution.errors.is_empty() {
+        return Err(resolution.errors);
+    }
+
+    let start_meta = metas.next_index();
+    let validation_context = context.validation_context();
+    let (module, signature) =
+        mltt_elaborate::load_module(&mut context, &mut metas, &concrete_module.items)
+            .map_err(|e| vec![e])?;
+    warnings.extend(mltt_elaborate::defaulting_warnings(&metas, start_meta));
+
+    // Double check the output of the elaborator
+    validate::check_module(&validation_context, &metas, &module).map_err(|e| vec![e.into()])?;
+
+    Ok(CheckedModule {
+        files,
+        file_id,
+        context,
+        metas,
+        module,
+        signature,
+        warnings,
+    })
+}
+
+/// Elaborate an expression in the scope of a module's items, returning its
+/// normal form along with its type.
+///
+/// The spans of the diagnostics refer to a copy of the expression that is not
+/// added to the files of the module, so their byte offsets are relative to
+/// the start of the expression.
+pub fn normalize(
+    module: &CheckedModule,
+    expr: &str,
+) -> Result<(Rc<syntax::Term>, Rc<syntax::Term>), Diagnostics> {
+    let mut files = Files::new();
+    let file_id = files.add("<expr>", expr);
+    let context = &module.context;
+    let mut metas = module.metas.clone();
+
+    let concrete_term = parser::parse_term(Lexer::new(&files[file_id])).map_err(|e| vec![e])?;
+    let term_span = concrete_term.span();
+
+    let start_meta = metas.next_index();
+    let synthesized =
+        mltt_elaborate::synth_term(MetaInsertion::Yes, context, &mut metas, &concrete_term)
+            .map_err(|e| vec![e])?;
+    mltt_elaborate::solve_pending_literals(context, &mut metas, start_meta).map_err(|e| vec![e])?;
+    mltt_elaborate::check_placeholders(&metas, start_meta).map_err(|e| vec![e])?;
+
+    let context = &context.run_context();
+    let value = (context.eval_term(&metas, term_span, &synthesized.term)).map_err(|e| vec![e])?;
+    let term = context
+        .read_back_value(&metas, term_span, &value)
+        .map_err(|e| vec![e])?;
+    let ty = context
+        .read_back_value(&metas, term_span, &synthesized.ty)
+        .map_err(|e| vec![e])?;
+
+    Ok((term, ty))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_str_errors() {
+        let diagnostics = check_str("id : Type; id = Type;").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn normalize_uses_items() {
+        let module = check_str("Greeting = String; greeting : Greeting; greeting = \"hi\";");
+        let module = module.unwrap();
+        let (term, ty) = normalize(&module, "greeting").unwrap();
+
+        assert_eq!(module.display_term(&term), "\"hi\"");
+        assert_eq!(module.display_term(&ty), "String");
+        let item_ty = module.item_type(&Label("greeting".to_owned())).unwrap();
+        assert_eq!(module.display_term(&item_ty), "String");
+    }
+
+    #[test]
+    fn normalize_case_guards() {
+        let module = check_str(
+            r#"
+                is-negative : S32 -> Bool;
+                is-negative n = case n { -3 => true; -2 => true; -1 => true; _ => false };
+
+                classify : S32 -> String;
+                classify n = case n {
+                    0 | false => "unreachable";
+                    0 => "zero";
+                    n | is-negative n => "negative";
+                    _ => "positive";
+                };
+
+                zero = classify 0;
+                negative = classify -3;
+                positive = classify 5;
+            "#,
+        );
+        let module = module.unwrap();
+
+        for name in &["zero", "negative", "positive"] {
+            let (term, _) = normalize(&module, name).unwrap();
+            assert_eq!(module.display_term(&term), format!("{:?}", name));
+        }
+    }
+
+    #[test]
+    fn normalize_range_and_prefix_patterns() {
+        let module = check_str(
+            r#"
+                classify : Char -> String;
+                classify c = case c {
+                    'x' => "ex";
+                    'a'..'z' => "lower";
+                    'A'..'Z' => "upper";
+                    _ => "other";
+                };
+
+                scheme : String -> String;
+                scheme url = case url {
+                    "https://" ++ _ => "secure";
+                    "http://" ++ _ => "insecure";
+                    "h" ++ _ => "unknown";
+                    "http" => "unreachable";
+                    _ => "none";
+                };
+
+                digit : U8 -> Bool;
+                digit n = case n { 0..9 => true; _ => false };
+            "#,
+        );
+        let module = module.unwrap();
+
+        let cases = [
+            ("classify 'x'", "\"ex\""),
+            ("classify 'q'", "\"lower\""),
+            ("classify 'Q'", "\"upper\""),
+            ("classify '!'", "\"other\""),
+            ("scheme \"https://example.com\"", "\"secure\""),
+            ("scheme \"http://example.com\"", "\"insecure\""),
+            ("scheme \"http\"", "\"unknown\""),
+            ("scheme \"ftp://example.com\"", "\"none\""),
+            ("digit 9", "true"),
+            ("digit 10", "false"),
+        ];
+        for (expr, expected) in &cases {
+            let (term, _) = normalize(&module, expr).unwrap();
+            assert_eq!(module.display_term(&term), *expected, "{}", expr);
+        }
+
+        assert!(check_str(
+            "empty : Char -> Bool; empty c = case c { 'z'..'a' => true; _ => false };"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn normalize_machine_arithmetic() {
+        let module = check_str(
+            r#"
+                u8-add-wrap = primitive "u8-add-wrap" : U8 -> U8 -> U8;
+                u8-add-checked = primitive "u8-add-checked" : U8 -> U8 -> U8;
+                s8-neg-wrap = primitive "s8-neg-wrap" : S8 -> S8;
+                s32-div-checked = primitive "s32-div-checked" : S32 -> S32 -> S32;
+            "#,
+        );
+        let module = module.unwrap();
+
+        let (term, _) = normalize(&module, "u8-add-wrap 255 2").unwrap();
+        assert_eq!(module.display_term(&term), "1");
+        let (term, _) = normalize(&module, "s8-neg-wrap -128").unwrap();
+        assert_eq!(module.display_term(&term), "-128");
+        let (term, _) = normalize(&module, "u8-add-checked 254 1").unwrap();
+        assert_eq!(module.display_term(&term), "255");
+
+        assert!(normalize(&module, "u8-add-checked 255 1").is_err());
+        assert!(normalize(&module, "s32-div-checked 1 0").is_err());
+    }
+
+    #[test]
+    fn normalize_bitwise_operations() {
+        let module = check_str(
+            r#"
+                u8-xor = primitive "u8-xor" : U8 -> U8 -> U8;
+                u8-not = primitive "u8-not" : U8 -> U8;
+                u16-shl = primitive "u16-shl" : U16 -> U32 -> U16;
+            "#,
+        );
+        let module = module.unwrap();
+
+        let (term, _) = normalize(&module, "u8-xor 12 10").unwrap();
+        assert_eq!(module.display_term(&term), "6");
+        let (term, _) = normalize(&module, "u8-not 0").unwrap();
+        assert_eq!(module.display_term(&term), "255");
+        let (term, _) = normalize(&module, "u16-shl 1 15").unwrap();
+        assert_eq!(module.display_term(&term), "32768");
+
+        assert!(normalize(&module, "u16-shl 1 16").is_err());
+    }
+
+    #[test]
+    fn normalize_conversions() {
+        let module = check_str(
+            r#"
+                u8-to-u32 = primitive "u8-to-u32" : U8 -> U32;
+                u32-to-u8-checked = primitive "u32-to-u8-checked" : U32 -> U8;
+                s16-to-u8-wrap = primitive "s16-to-u8-wrap" : S16 -> U8;
+                char-to-u32 = primitive "char-to-u32" : Char -> U32;
+            "#,
+        );
+        let module = module.unwrap();
+
+        let (term, _) = normalize(&module, "u8-to-u32 200").unwrap();
+        assert_eq!(module.display_term(&term), "200");
+        let (term, _) = normalize(&module, "u32-to-u8-checked 255").unwrap();
+        assert_eq!(module.display_term(&term), "255");
+        let (term, _) = normalize(&module, "s16-to-u8-wrap -1").unwrap();
+        assert_eq!(module.display_term(&term), "255");
+        let (term, _) = normalize(&module, "char-to-u32 'A'").unwrap();
+        assert_eq!(module.display_term(&term), "65");
+
+        assert!(normalize(&module, "u32-to-u8-checked 256").is_err());
+    }
+
+    #[test]
+    fn normalize_arrays() {
+        let module = check_str(
+            r#"
+                array-length = primitive "array-length" : Fun (A : Type) -> Array A -> U64;
+                array-get = primitive "array-get" : Fun (A : Type) -> Array A -> U64 -> A;
+                array-set =
+                    primitive "array-set" : Fun (A : Type) -> Array A -> U64 -> A -> Array A;
+
+                digits : Array U8;
+                digits = [1, 2, 3];
+            "#,
+        );
+        let module = module.unwrap();
+
+        let (term, ty) = normalize(&module, "digits").unwrap();
+        assert_eq!(module.display_term(&term), "[1, 2, 3]");
+        assert_eq!(module.display_term(&ty), "Array U8");
+        let (term, _) = normalize(&module, "array-get U8 digits 1").unwrap();
+        assert_eq!(module.display_term(&term), "2");
+        let (term, _) = normalize(&module, "array-length U8 digits").unwrap();
+        assert_eq!(module.display_term(&term), "3");
+        let (term, _) = normalize(&module, "array-set U8 digits 1 9").unwrap();
+        assert_eq!(module.display_term(&term), "[1, 9, 3]");
+
+        assert!(normalize(&module, "array-get U8 digits 3").is_err());
+    }
+
+    #[test]
+    fn inline_calls_are_specialized() {
+        let module = check_str(
+            r#"
+                #[inline]
+                const : Fun (A B : Type) -> A -> B -> A;
+                const A B a b = a;
+
+                greeting : String;
+                greeting = const String U8 "hello" 1;
+
+                id : String -> String;
+                id s = s;
+
+                bye : String;
+                bye = id "bye";
+
+                hello : String;
+                hello = const String U8 (id "hello") 1;
+            "#,
+        );
+        let module = module.unwrap();
+        let body = |label: &str| {
+            (module.module().items.iter())
+                .find_map(|item| match item {
+                    syntax::Item::Definition(_, l, term) if l.0 == label => Some(term.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(module.display_term(&body("greeting")), "\"hello\"");
+        assert!(matches!(*body("bye"), syntax::Term::FunElim(..)));
+        // Only the inline item is unfolded, leaving the arguments untouched
+        match body("hello").as_ref() {
+            syntax::Term::FunElim(_, _, arg) => assert_eq!(module.display_term(arg), "\"hello\""),
+            term => panic!("expected a call to `id`, found: {:?}", term),
+        }
+    }
+
+    #[test]
+    fn normalize_quoted_terms() {
+        let module = check_str(
+            "is-positive : S32 -> Bool; is-positive n = case n { 0 => false; _ => true };",
+        );
+        let module = module.unwrap();
+        let arg = crate::mltt_term!(fun (n : S32) => is-positive n);
+
+        for (n, expected) in &[(0, "false"), (-3, "true")] {
+            let (term, _) = normalize(&module, &crate::mltt_term!(#arg #n)).unwrap();
+            assert_eq!(module.display_term(&term), *expected);
+        }
+    }
+
+    #[test]
+    fn normalize_errors() {
+        let module = check_str("").unwrap();
+        let diagnostics = normalize(&module, "missing").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn checked_modules_are_thread_safe() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let module = check_str("greeting = \"hi\";").unwrap();
+        assert_send_sync(&module);
+
+        let (term, _) = std::thread::spawn(move || normalize(&module, "greeting").unwrap())
+            .join()
+            .unwrap();
+        assert_send_sync(&term);
+    }
+}
@@ -0,0 +1,320 @@
+//! Quasi-quoting of concrete syntax.
+//!
+//! The [`mltt_term!`] macro builds the source code of a term from Rust tokens,
+//! splicing in Rust values where a `#name` or `#(expr)` appears:
+//!
+//! ```rust
+//! use mltt::mltt_term;
+//! use mltt::quote::Name;
+//!
+//! let greeting = "hello \"world\"";
+//! let string = mltt_term!(String);
+//! let id = mltt_term!(fun a => a : #string -> #string);
+//! let var = Name::new("x").unwrap();
+//!
+//! assert_eq!(id.as_str(), "fun a => a : (String) -> (String)");
+//! assert_eq!(
+//!     mltt_term!((fun #var => #var) #(greeting)).as_str(),
+//!     r#"(fun x => x) "hello \"world\"""#,
+//! );
+//! ```
+//!
+//! Spliced terms are wrapped in parentheses, and strings and characters are
+//! escaped, so the result does not depend on the precedence or contents of
+//! the spliced values.
+//!
+//! Because the tokens are split up by the Rust tokenizer, some parts of the
+//! concrete syntax need care:
+//!
+//! - dashes are joined with the tokens on either side, so that names like
+//!   `is-negative` are kept together. Negative literals should be spliced, for
+//!   example `f #(-1)`.
+//! - primes can't be used in names, as they are read as Rust lifetimes. These
+//!   names can be spliced using [`Name`].
+//! - very long terms may need the `recursion_limit` of the crate to be raised.
+
+use mltt_parse::lexer::Lexer;
+use mltt_parse::token::TokenKind;
+use mltt_span::Files;
+use std::fmt;
+use std::ops::Deref;
+
+/// The source code of a quoted term.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Quoted {
+    source: String,
+}
+
+impl Quoted {
+    /// The source code of the term.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Deref for Quoted {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Quoted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// A name that can be spliced into binders as well as terms.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Name {
+    source: String,
+}
+
+impl Name {
+    /// Create a name, returning `None` if it is not a valid identifier.
+    /// Keywords are written as raw identifiers, eg. `r#fun`.
+    pub fn new(name: &str) -> Option<Name> {
+        let mut files = Files::new();
+        let file_id = files.add_virtual("name", name);
+        let mut tokens = Lexer::new(&files[file_id]);
+
+        let source = match tokens.next()? {
+            token if token.src.slice != name => return None,
+            token => match token.kind {
+                TokenKind::Identifier => name.to_owned(),
+                TokenKind::Keyword(_) => format!("r#{}", name),
+                _ => return None,
+            },
+        };
+        match tokens.next() {
+            None => Some(Name { source }),
+            Some(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Values that can be spliced into a quoted term.
+pub trait Quote {
+    /// Append the source code of the value to the given string.
+    fn quote(&self, source: &mut String);
+}
+
+impl<T: Quote + ?Sized> Quote for &'_ T {
+    fn quote(&self, source: &mut String) {
+        (**self).quote(source);
+    }
+}
+
+impl Quote for Quoted {
+    fn quote(&self, source: &mut String) {
+        source.push('(');
+        source.push_str(&self.source);
+        source.push(')');
+    }
+}
+
+impl Quote for Name {
+    fn quote(&self, source: &mut String) {
+        source.push_str(&self.source);
+    }
+}
+
+impl Quote for bool {
+    fn quote(&self, source: &mut String) {
+        source.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl Quote for str {
+    fn quote(&self, source: &mut String) {
+        source.push('"');
+        self.chars().for_each(|ch| push_escaped(source, ch));
+        source.push('"');
+    }
+}
+
+impl Quote for String {
+    fn quote(&self, source: &mut String) {
+        self.as_str().quote(source);
+    }
+}
+
+impl Quote for char {
+    fn quote(&self, source: &mut String) {
+        source.push('\'');
+        push_escaped(source, *self);
+        source.push('\'');
+    }
+}
+
+macro_rules! impl_quote_int {
+    ($($T:ty),*) => {
+        $(impl Quote for $T {
+            fn quote(&self, source: &mut String) {
+                source.push_str(&self.to_string());
+            }
+        })*
+    };
+}
+
+impl_quote_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Append a character to the contents of a string or character literal,
+/// escaping it if needed.
+fn push_escaped(source: &mut String, ch: char) {
+    match ch {
+        '\'' => source.push_str("\\'"),
+        '"' => source.push_str("\\\""),
+        '\\' => source.push_str("\\\\"),
+        '\n' => source.push_str("\\n"),
+        '\r' => source.push_str("\\r"),
+        '\t' => source.push_str("\\t"),
+        '\0' => source.push_str("\\0"),
+        ch => source.push(ch),
+    }
+}
+
+/// Joins the tokens of a quoted term together. This is used by the expansion
+/// of [`mltt_term!`].
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct Builder {
+    source: String,
+    /// Whether the next token should be joined to the last one.
+    join_next: bool,
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Add a token, separating it from the previous one with a space unless
+    /// they should be joined together.
+    pub fn token(&mut self, token: &str) {
+        let is_joined = matches!(token, "-" | "." | ".." | "+" | "^" | "#");
+        let is_close = matches!(token, ")" | "]" | "}" | "," | ";");
+        if !(self.join_next || is_joined || is_close) {
+            self.separate();
+        }
+        self.source.push_str(token);
+        self.join_next = is_joined || matches!(token, "(" | "[" | "{");
+    }
+
+    /// Add a spliced value.
+    pub fn splice(&mut self, value: &impl Quote) {
+        if !self.join_next {
+            self.separate();
+        }
+        value.quote(&mut self.source);
+        self.join_next = false;
+    }
+
+    pub fn finish(self) -> Quoted {
+        Quoted {
+            source: self.source,
+        }
+    }
+
+    fn separate(&mut self) {
+        if !self.source.is_empty() {
+            self.source.push(' ');
+        }
+    }
+}
+
+/// Quote the source code of a term, splicing in the values that follow a `#`.
+///
+/// See the [`quote`](crate::quote) module for more details.
+#[macro_export]
+macro_rules! mltt_term {
+    ($($tokens:tt)*) => {{
+        let mut builder = $crate::quote::Builder::new();
+        $crate::__mltt_quote_tokens!(builder; $($tokens)*);
+        builder.finish()
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __mltt_quote_tokens {
+    ($builder:ident;) => {};
+    ($builder:ident; # ( $splice:expr ) $($rest:tt)*) => {
+        $builder.splice(&$splice);
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+    ($builder:ident; # $splice:ident $($rest:tt)*) => {
+        $builder.splice(&$splice);
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+    ($builder:ident; ( $($inner:tt)* ) $($rest:tt)*) => {
+        $builder.token("(");
+        $crate::__mltt_quote_tokens!($builder; $($inner)*);
+        $builder.token(")");
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+    ($builder:ident; [ $($inner:tt)* ] $($rest:tt)*) => {
+        $builder.token("[");
+        $crate::__mltt_quote_tokens!($builder; $($inner)*);
+        $builder.token("]");
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+    ($builder:ident; { $($inner:tt)* } $($rest:tt)*) => {
+        $builder.token("{");
+        $crate::__mltt_quote_tokens!($builder; $($inner)*);
+        $builder.token("}");
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+    ($builder:ident; $token:tt $($rest:tt)*) => {
+        $builder.token(stringify!($token));
+        $crate::__mltt_quote_tokens!($builder; $($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joined_tokens() {
+        assert_eq!(
+            mltt_term!(is-negative x.y 'a'..'z' Type^1).as_str(),
+            "is-negative x.y 'a'..'z' Type^1",
+        );
+        assert_eq!(
+            mltt_term!(Fun {A : Type} -> [A, A]).as_str(),
+            "Fun {A : Type} -> [A, A]",
+        );
+        assert_eq!(
+            mltt_term!(record { x = 1; y = 2 }).as_str(),
+            "record {x = 1; y = 2}",
+        );
+    }
+
+    #[test]
+    fn splices() {
+        let arg = mltt_term!(f x);
+        let var = Name::new("is-zero").unwrap();
+
+        assert_eq!(mltt_term!(g #arg).as_str(), "g (f x)");
+        assert_eq!(mltt_term!(#var #(-3)).as_str(), "is-zero -3");
+        assert_eq!(mltt_term!(#('\n') #(true)).as_str(), "'\\n' true");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Name::new("x'").unwrap().to_string(), "x'");
+        assert_eq!(Name::new("fun").unwrap().to_string(), "r#fun");
+        assert_eq!(Name::new("x y"), None);
+        assert_eq!(Name::new("1"), None);
+        assert_eq!(Name::new(""), None);
+    }
+}